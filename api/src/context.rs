@@ -48,13 +48,14 @@ use aptos_vm::{
     move_vm_ext::MoveResolverExt,
 };
 use futures::{channel::oneshot, SinkExt};
-use move_core_types::language_storage::{ModuleId, StructTag};
+use move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
 use std::{
     collections::{BTreeMap, HashMap},
     ops::{Bound::Included, Deref},
     sync::{Arc, RwLock, RwLockWriteGuard},
-    time::Instant,
+    time::{Duration, Instant},
 };
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 // Context holds application scope context
 #[derive(Clone)]
@@ -66,6 +67,7 @@ pub struct Context {
     gas_schedule_cache: Arc<RwLock<GasScheduleCache>>,
     gas_estimation_cache: Arc<RwLock<GasEstimationCache>>,
     gas_limit_cache: Arc<RwLock<GasLimitCache>>,
+    event_stream_permits: Arc<Semaphore>,
 }
 
 impl std::fmt::Debug for Context {
@@ -81,6 +83,7 @@ impl Context {
         mp_sender: MempoolClientSender,
         node_config: NodeConfig,
     ) -> Self {
+        let event_stream_permits = Arc::new(Semaphore::new(node_config.api.max_event_streams));
         Self {
             chain_id,
             db,
@@ -100,6 +103,7 @@ impl Context {
                 last_updated_epoch: None,
                 block_gas_limit: None,
             })),
+            event_stream_permits,
         }
     }
 
@@ -169,6 +173,24 @@ impl Context {
         self.node_config.api.failpoints_enabled
     }
 
+    pub fn event_stream_enabled(&self) -> bool {
+        self.node_config.api.event_stream_enabled
+    }
+
+    pub fn event_stream_poll_interval(&self) -> Duration {
+        Duration::from_millis(self.node_config.api.event_stream_poll_interval_ms)
+    }
+
+    pub fn event_stream_catch_up_interval(&self) -> Duration {
+        Duration::from_millis(self.node_config.api.event_stream_catch_up_interval_ms)
+    }
+
+    /// Reserves one of the `max_event_streams` slots for an event stream, until the returned
+    /// permit is dropped. Returns None if all the slots are taken.
+    pub fn try_acquire_event_stream_permit(&self) -> Option<OwnedSemaphorePermit> {
+        self.event_stream_permits.clone().try_acquire_owned().ok()
+    }
+
     pub fn max_submit_transaction_batch_size(&self) -> usize {
         self.node_config.api.max_submit_transaction_batch_size
     }
@@ -811,6 +833,17 @@ impl Context {
         }
     }
 
+    pub fn get_events_by_type(
+        &self,
+        type_tag: &TypeTag,
+        start_version: u64,
+        limit: u16,
+        ledger_version: u64,
+    ) -> Result<Vec<EventWithVersion>> {
+        self.db
+            .get_events_by_type(type_tag, start_version, limit as u64, ledger_version)
+    }
+
     fn next_bucket(&self, gas_unit_price: u64) -> u64 {
         match self
             .node_config
@@ -0,0 +1,310 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streaming of committed module events over server-sent events (SSE), so that clients can
+//! subscribe to the events of a given type instead of polling the events endpoints.
+//!
+//! The stream tails the committed transaction outputs and pushes every event whose type matches
+//! the requested struct tag. Each pushed event carries a cursor (`<version>:<event_index>`) as
+//! its SSE id, which can be passed back (as the `cursor` query parameter, or the standard
+//! `Last-Event-ID` header sent by reconnecting `EventSource`s) to resume the stream right after
+//! that event. Payloads are encoded the same way as the GraphQL scalars are (see
+//! [`aptos_vm::decoded_event`]).
+//!
+//! The stream is disabled by default (see `event_stream_enabled`), and the number of streams
+//! open at the same time is capped by `max_event_streams`.
+
+use crate::context::Context;
+use anyhow::{bail, format_err, Context as AnyhowContext, Result};
+use aptos_api_types::AsConverter;
+use aptos_logger::warn;
+use aptos_types::transaction::Version;
+use aptos_vm::data_cache::AsMoveResolver;
+use futures::{stream, Stream, StreamExt};
+use move_core_types::language_storage::StructTag;
+use poem::{
+    handler,
+    http::StatusCode,
+    web::{
+        sse::{Event, SSE},
+        Data, Query,
+    },
+    Request,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::VecDeque, fmt, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::OwnedSemaphorePermit;
+
+/// Header sent by `EventSource`s when reconnecting, holding the id of the last received event.
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+/// Interval at which comments are sent to keep idle connections alive.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Deserialize)]
+pub struct EventStreamParams {
+    /// Struct tag of the events to stream, e.g. `0x1::coin::DepositEvent`.
+    event_type: String,
+    /// Cursor of the last received event; the stream resumes right after it.
+    cursor: Option<String>,
+    /// Version from which to stream the events, if no cursor is given. If neither is given,
+    /// only events committed after the subscription are streamed.
+    start_version: Option<Version>,
+}
+
+/// Position of an event in the ledger: the version of the transaction that emitted it and the
+/// index of the event among the events of that transaction.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct EventCursor {
+    pub version: Version,
+    pub event_index: u64,
+}
+
+impl fmt::Display for EventCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.version, self.event_index)
+    }
+}
+
+impl FromStr for EventCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (version, event_index) = s
+            .split_once(':')
+            .ok_or_else(|| format_err!("Cursor must be of the form <version>:<event_index>"))?;
+        Ok(Self {
+            version: version.parse().context("Invalid version in cursor")?,
+            event_index: event_index
+                .parse()
+                .context("Invalid event index in cursor")?,
+        })
+    }
+}
+
+#[handler]
+pub fn stream_events_poem(
+    context: Data<&Arc<Context>>,
+    Query(params): Query<EventStreamParams>,
+    request: &Request,
+) -> poem::Result<SSE> {
+    if !context.event_stream_enabled() {
+        return Err(poem::Error::from_string(
+            "The event stream API is disabled on this node",
+            StatusCode::FORBIDDEN,
+        ));
+    }
+    let event_type = StructTag::from_str(&params.event_type)
+        .map_err(|err| bad_request(format!("Invalid event_type: {}", err)))?;
+    let cursor = match params
+        .cursor
+        .as_deref()
+        .or_else(|| request.header(LAST_EVENT_ID_HEADER))
+    {
+        Some(cursor) => Some(
+            cursor
+                .parse::<EventCursor>()
+                .map_err(|err| bad_request(format!("Invalid cursor: {:#}", err)))?,
+        ),
+        None => None,
+    };
+    let next_version = match (cursor, params.start_version) {
+        (Some(cursor), _) => cursor.version,
+        (None, Some(start_version)) => start_version,
+        (None, None) => context.db.get_latest_version().map_err(poem::Error::from)? + 1,
+    };
+
+    let permit = context.try_acquire_event_stream_permit().ok_or_else(|| {
+        poem::Error::from_string(
+            "Too many event streams are open on this node",
+            StatusCode::SERVICE_UNAVAILABLE,
+        )
+    })?;
+
+    let state = EventStreamState::new(context.0.clone(), event_type, next_version, cursor, permit);
+    Ok(SSE::new(state.into_stream().map(Event::from)).keep_alive(KEEP_ALIVE_INTERVAL))
+}
+
+fn bad_request(message: String) -> poem::Error {
+    poem::Error::from_string(message, StatusCode::BAD_REQUEST)
+}
+
+/// An item pushed to the client.
+#[derive(Debug)]
+pub(crate) enum StreamItem {
+    Event {
+        cursor: EventCursor,
+        payload: serde_json::Value,
+    },
+    Error(String),
+}
+
+impl From<StreamItem> for Event {
+    fn from(item: StreamItem) -> Self {
+        match item {
+            StreamItem::Event { cursor, payload } => {
+                Event::message(payload.to_string()).id(cursor.to_string())
+            },
+            StreamItem::Error(message) => Event::message(message).event_type("error"),
+        }
+    }
+}
+
+pub(crate) struct EventStreamState {
+    context: Arc<Context>,
+    event_type: StructTag,
+    /// Version of the next transaction to look at.
+    next_version: Version,
+    /// Events up to (and including) this cursor have already been delivered to the client.
+    resume_after: Option<EventCursor>,
+    /// Items read from the DB but not yet pushed to the client.
+    pending: VecDeque<StreamItem>,
+    /// Delay before the next read of the DB: the poll interval once the stream caught up with
+    /// the committed transactions, the (shorter) catch-up interval otherwise.
+    next_read_delay: Option<Duration>,
+    /// Set once the stream failed, after the error is pushed the stream ends.
+    done: bool,
+    /// Slot of the stream among the `max_event_streams`, released when the stream is dropped.
+    _permit: OwnedSemaphorePermit,
+}
+
+impl EventStreamState {
+    pub(crate) fn new(
+        context: Arc<Context>,
+        event_type: StructTag,
+        next_version: Version,
+        resume_after: Option<EventCursor>,
+        permit: OwnedSemaphorePermit,
+    ) -> Self {
+        Self {
+            context,
+            event_type,
+            next_version,
+            resume_after,
+            pending: VecDeque::new(),
+            next_read_delay: None,
+            done: false,
+            _permit: permit,
+        }
+    }
+
+    pub(crate) fn into_stream(self) -> impl Stream<Item = StreamItem> + Send + 'static {
+        stream::unfold(self, |mut state| async move {
+            loop {
+                if let Some(item) = state.pending.pop_front() {
+                    return Some((item, state));
+                }
+                if state.done {
+                    return None;
+                }
+                if let Some(delay) = state.next_read_delay.take() {
+                    tokio::time::sleep(delay).await;
+                }
+                match state.poll_committed_events().await {
+                    Ok(true) => {
+                        state.next_read_delay = Some(state.context.event_stream_catch_up_interval())
+                    },
+                    Ok(false) => {
+                        state.next_read_delay = Some(state.context.event_stream_poll_interval())
+                    },
+                    Err(err) => {
+                        warn!("Event stream of {} failed: {:#}", state.event_type, err);
+                        state.done = true;
+                        state
+                            .pending
+                            .push_back(StreamItem::Error(format!("{:#}", err)));
+                    },
+                }
+            }
+        })
+    }
+
+    /// Reads the next batch of committed transactions and queues up their matching events.
+    /// Returns false if there is no new transaction yet.
+    async fn poll_committed_events(&mut self) -> Result<bool> {
+        let context = self.context.clone();
+        let event_type = self.event_type.clone();
+        let start_version = self.next_version;
+        let resume_after = self.resume_after;
+        let (next_version, events) = tokio::task::spawn_blocking(move || {
+            read_events(&context, &event_type, start_version, resume_after)
+        })
+        .await??;
+        if next_version == self.next_version {
+            return Ok(false);
+        }
+
+        self.next_version = next_version;
+        self.resume_after = None;
+        self.pending.extend(events);
+        Ok(true)
+    }
+}
+
+/// Reads the events of the given type from a batch of transactions starting at `start_version`,
+/// skipping the ones up to `resume_after`. Returns the version following the batch, together
+/// with the events to push.
+fn read_events(
+    context: &Context,
+    event_type: &StructTag,
+    start_version: Version,
+    resume_after: Option<EventCursor>,
+) -> Result<(Version, Vec<StreamItem>)> {
+    let ledger_version = context.get_latest_ledger_info_wrapped()?.version();
+    if start_version > ledger_version {
+        return Ok((start_version, vec![]));
+    }
+    let limit =
+        (ledger_version - start_version + 1).min(context.max_transactions_page_size() as u64);
+    let outputs = context
+        .db
+        .get_transaction_outputs(start_version, limit, ledger_version)
+        .context("Failed to read committed transaction outputs")?;
+    if outputs.first_transaction_output_version != Some(start_version) {
+        bail!("No transaction output found at version {}", start_version);
+    }
+
+    let state_view = context.latest_state_view()?;
+    let resolver = state_view.as_move_resolver();
+    let converter = resolver.as_converter(context.db.clone());
+    let mut events = vec![];
+    for (version, (_, output)) in (start_version..).zip(&outputs.transactions_and_outputs) {
+        for (event_index, event) in (0..).zip(output.events()) {
+            let cursor = EventCursor {
+                version,
+                event_index,
+            };
+            if !event.is_of_type(event_type) || resume_after.map_or(false, |c| cursor <= c) {
+                continue;
+            }
+            let payload = json!({
+                "version": version.to_string(),
+                "event_index": event_index,
+                "type": event.type_tag().to_string(),
+                "data": converter.try_into_event_json(event)?,
+            });
+            events.push(StreamItem::Event { cursor, payload });
+        }
+    }
+    Ok((
+        start_version + outputs.transactions_and_outputs.len() as u64,
+        events,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventCursor;
+
+    #[test]
+    fn test_event_cursor_round_trip() {
+        let cursor = EventCursor {
+            version: 42,
+            event_index: 3,
+        };
+        assert_eq!(cursor.to_string(), "42:3");
+        assert_eq!("42:3".parse::<EventCursor>().unwrap(), cursor);
+        assert!("42".parse::<EventCursor>().is_err());
+        assert!("42:x".parse::<EventCursor>().is_err());
+    }
+}
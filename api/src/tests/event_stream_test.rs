@@ -0,0 +1,98 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{new_test_context, new_test_context_with_config};
+use crate::event_stream::{EventCursor, EventStreamState, StreamItem};
+use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_config::config::NodeConfig;
+use futures::StreamExt;
+use move_core_types::language_storage::StructTag;
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+static EVENT_TYPE: &str = "0x1::coin::DepositEvent";
+
+fn new_event_stream_test_context(test_name: String, max_event_streams: usize) -> TestContext {
+    let mut node_config = NodeConfig::default();
+    node_config.api.event_stream_enabled = true;
+    node_config.api.max_event_streams = max_event_streams;
+    new_test_context_with_config(test_name, node_config)
+}
+
+async fn get_stream_status(context: &TestContext) -> u16 {
+    let path = context.prepend_path(&format!("/events/stream?event_type={}", EVENT_TYPE));
+    context
+        .reply(warp::test::request().method("GET").path(&path))
+        .await
+        .status()
+        .as_u16()
+}
+
+/// Streams the deposit events from the given position, and collects the first `num_events`.
+async fn collect_events(
+    context: &TestContext,
+    start_version: u64,
+    resume_after: Option<EventCursor>,
+    num_events: usize,
+) -> Vec<(EventCursor, serde_json::Value)> {
+    let permit = context.context.try_acquire_event_stream_permit().unwrap();
+    let stream = EventStreamState::new(
+        Arc::new(context.context.clone()),
+        StructTag::from_str(EVENT_TYPE).unwrap(),
+        start_version,
+        resume_after,
+        permit,
+    )
+    .into_stream()
+    .take(num_events)
+    .map(|item| match item {
+        StreamItem::Event { cursor, payload } => (cursor, payload),
+        StreamItem::Error(message) => panic!("Event stream failed: {}", message),
+    })
+    .collect();
+    tokio::time::timeout(Duration::from_secs(30), stream)
+        .await
+        .expect("Timed out waiting for the streamed events")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_event_stream_disabled_by_default() {
+    let context = new_test_context(current_function_name!());
+    assert_eq!(get_stream_status(&context).await, 403);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_event_stream_limit() {
+    let context = new_event_stream_test_context(current_function_name!(), 1);
+
+    let permit = context.context.try_acquire_event_stream_permit().unwrap();
+    assert!(context.context.try_acquire_event_stream_permit().is_none());
+    assert_eq!(get_stream_status(&context).await, 503);
+
+    // The slot is released once the stream holding it is gone.
+    drop(permit);
+    assert!(context.context.try_acquire_event_stream_permit().is_some());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_event_stream_catch_up_and_resume() {
+    let mut context = new_event_stream_test_context(current_function_name!(), 2);
+    let start_version = context.get_latest_ledger_info().version() + 1;
+    for _ in 0..2 {
+        let account = context.gen_account();
+        let txn = context.mint_user_account(&account).await;
+        context.commit_block(&[txn]).await;
+    }
+
+    let events = collect_events(&context, start_version, None, 2).await;
+    assert!(events[0].0 < events[1].0);
+    for (cursor, payload) in &events {
+        assert!(cursor.version >= start_version);
+        assert_eq!(payload["type"], EVENT_TYPE);
+        assert_eq!(payload["version"], cursor.version.to_string());
+        assert_eq!(payload["event_index"], cursor.event_index);
+    }
+
+    // Resuming after the first event starts the stream right after it.
+    let resumed = collect_events(&context, events[0].0.version, Some(events[0].0), 1).await;
+    assert_eq!(resumed[0], events[1]);
+}
@@ -34,7 +34,7 @@ use aptos_types::{
     vm_status::AbortLocation,
     write_set::WriteOp,
 };
-use aptos_vm::move_vm_ext::MoveResolverExt;
+use aptos_vm::{decoded_event::decode_event_as_json, move_vm_ext::MoveResolverExt};
 use move_binary_format::file_format::FunctionHandleIndex;
 use move_core_types::{
     account_address::AccountAddress,
@@ -495,6 +495,13 @@ impl<'a, R: MoveResolverExt + ?Sized> MoveConverter<'a, R> {
         Ok(ret)
     }
 
+    /// Converts the payload of the event into JSON, using the GraphQL scalar encodings (see
+    /// [`aptos_vm::decoded_event`]) rather than the encodings of the REST API.
+    pub fn try_into_event_json(&self, event: &ContractEvent) -> Result<Value> {
+        let layout = self.inner.get_type_layout_with_types(event.type_tag())?;
+        decode_event_as_json(event, &layout)
+    }
+
     pub fn try_into_signed_transaction(
         &self,
         txn: UserTransactionRequest,
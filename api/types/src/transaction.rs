@@ -512,6 +512,15 @@ pub struct BlockMetadataTransaction {
     pub timestamp: U64,
 }
 
+// Module events are not emitted to an event handle, so they are exposed with a zero GUID and
+// sequence number to keep the API format unchanged.
+fn module_event_guid() -> EventGuid {
+    EventGuid {
+        creation_number: U64(0),
+        account_address: AccountAddress::ZERO.into(),
+    }
+}
+
 /// An event from a transaction
 #[derive(Clone, Debug, Deserialize, Eq, Object, PartialEq, Serialize)]
 pub struct Event {
@@ -535,6 +544,12 @@ impl From<(&ContractEvent, serde_json::Value)> for Event {
                 typ: v0.type_tag().clone().into(),
                 data,
             },
+            ContractEvent::V2(v2) => Self {
+                guid: module_event_guid(),
+                sequence_number: U64(0),
+                typ: v2.type_tag().clone().into(),
+                data,
+            },
         }
     }
 }
@@ -564,6 +579,13 @@ impl From<(&EventWithVersion, serde_json::Value)> for VersionedEvent {
                 typ: v0.type_tag().clone().into(),
                 data,
             },
+            ContractEvent::V2(v2) => Self {
+                version: event.transaction_version.into(),
+                guid: module_event_guid(),
+                sequence_number: U64(0),
+                typ: v2.type_tag().clone().into(),
+                data,
+            },
         }
     }
 }
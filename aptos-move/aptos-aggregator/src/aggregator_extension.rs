@@ -278,6 +278,12 @@ impl Aggregator {
             )
     }
 
+    /// Returns the value and the state of the aggregator, without materializing it: if the
+    /// aggregator is in a delta state, the value is the delta to apply to the value in storage.
+    pub fn value_and_state(&self) -> (u128, AggregatorState) {
+        (self.value, self.state)
+    }
+
     /// Unpacks aggregator into its fields.
     pub fn into(self) -> (u128, AggregatorState, u128, Option<History>) {
         (self.value, self.state, self.limit, self.history)
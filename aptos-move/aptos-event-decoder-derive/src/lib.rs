@@ -0,0 +1,131 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Derive macro for `aptos_event_decoder::MoveEvent`, which binds a Rust struct to the Move
+//! struct of the events it decodes:
+//!
+//! ```ignore
+//! #[derive(Deserialize, MoveEvent)]
+//! #[move_event(address = "0x1", module = "coin", name = "DepositEvent")]
+//! struct Deposit {
+//!     amount: u64,
+//! }
+//! ```
+//!
+//! The name of the Move struct defaults to the name of the Rust struct.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Error, Lit, Meta, NestedMeta, Result};
+
+const ATTRIBUTE: &str = "move_event";
+
+#[proc_macro_derive(MoveEvent, attributes(move_event))]
+pub fn derive_move_event(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    derive(&ast)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn derive(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let mut address = None;
+    let mut module = None;
+    let mut name = None;
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(ATTRIBUTE))
+    {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected #[move_event(...)]")),
+        };
+        for nested in list.nested {
+            let name_value = match nested {
+                NestedMeta::Meta(Meta::NameValue(name_value)) => name_value,
+                nested => return Err(Error::new_spanned(nested, "expected `key = \"value\"`")),
+            };
+            let value = match &name_value.lit {
+                Lit::Str(value) => value.clone(),
+                lit => return Err(Error::new_spanned(lit, "expected a string literal")),
+            };
+            let slot = if name_value.path.is_ident("address") {
+                &mut address
+            } else if name_value.path.is_ident("module") {
+                &mut module
+            } else if name_value.path.is_ident("name") {
+                &mut name
+            } else {
+                return Err(Error::new_spanned(
+                    name_value.path,
+                    "expected `address`, `module` or `name`",
+                ));
+            };
+            *slot = Some(value);
+        }
+    }
+
+    let missing = |key| {
+        Error::new(
+            Span::call_site(),
+            format!("missing `{}` in #[{}(...)]", key, ATTRIBUTE),
+        )
+    };
+    let address = address.ok_or_else(|| missing("address"))?;
+    let address_bytes = parse_address(&address.value())
+        .ok_or_else(|| Error::new_spanned(&address, "invalid account address"))?;
+    let module = module.ok_or_else(|| missing("module"))?;
+    check_identifier(&module)?;
+    let name = match name {
+        Some(name) => {
+            check_identifier(&name)?;
+            name.value()
+        },
+        None => ast.ident.to_string(),
+    };
+
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::aptos_event_decoder::MoveEvent for #ident #ty_generics #where_clause {
+            const ADDRESS: ::aptos_event_decoder::AccountAddress =
+                ::aptos_event_decoder::AccountAddress::new([#(#address_bytes),*]);
+            const MODULE: &'static str = #module;
+            const NAME: &'static str = #name;
+        }
+    })
+}
+
+/// Parses a hex account address literal (e.g. `0x1`) into its 32 bytes.
+fn parse_address(literal: &str) -> Option<Vec<u8>> {
+    let hex = literal.strip_prefix("0x")?;
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = format!("{:0>64}", hex);
+    (0..64)
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Checks that the string is a valid Move identifier.
+fn check_identifier(lit: &syn::LitStr) -> Result<()> {
+    let value = lit.value();
+    let mut chars = value.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && value != "_"
+        },
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(lit, "invalid Move identifier"))
+    }
+}
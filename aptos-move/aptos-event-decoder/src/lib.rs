@@ -0,0 +1,187 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Decoding of the module events emitted by transactions, either into strongly typed Rust
+//! structs bound to their Move structs with [`MoveEvent`], or into JSON for events without a
+//! Rust counterpart, based on the layouts resolved from the modules on chain.
+//!
+//! ```ignore
+//! #[derive(Deserialize, MoveEvent)]
+//! #[move_event(address = "0x1", module = "coin", name = "DepositEvent")]
+//! struct Deposit {
+//!     amount: u64,
+//! }
+//!
+//! let deposits: Vec<Deposit> = decode_events(&events)?;
+//! ```
+
+// Makes the paths generated by the derive macro resolve in the tests of this crate.
+extern crate self as aptos_event_decoder;
+
+use anyhow::{anyhow, Result};
+pub use aptos_event_decoder_derive::MoveEvent;
+use aptos_types::{contract_event::ContractEvent, move_value_json::move_value_to_json};
+pub use move_core_types::account_address::AccountAddress;
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    resolver::MoveResolver,
+    value::MoveValue,
+};
+use move_resource_viewer::MoveValueAnnotator;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A Rust struct which the payloads of the events of a Move struct deserialize into. The fields
+/// of the Rust struct must match the fields of the Move struct, in declaration order.
+///
+/// The type arguments of the Move struct are not checked, so that a single Rust struct can
+/// decode the events of all instantiations of a struct whose type parameters are phantom.
+pub trait MoveEvent: DeserializeOwned {
+    /// The address of the module declaring the event struct.
+    const ADDRESS: AccountAddress;
+    /// The name of the module declaring the event struct.
+    const MODULE: &'static str;
+    /// The name of the event struct.
+    const NAME: &'static str;
+
+    /// Returns true if events of the given struct decode into this type.
+    fn matches(struct_tag: &StructTag) -> bool {
+        struct_tag.address == Self::ADDRESS
+            && struct_tag.module.as_str() == Self::MODULE
+            && struct_tag.name.as_str() == Self::NAME
+    }
+}
+
+/// Decodes the payload of an event of the given struct. Returns None if the events of the struct
+/// do not decode into `E`, and an error if the payload does not match the fields of `E`.
+pub fn decode<E: MoveEvent>(struct_tag: &StructTag, data: &[u8]) -> Result<Option<E>> {
+    if !E::matches(struct_tag) {
+        return Ok(None);
+    }
+    bcs::from_bytes(data)
+        .map(Some)
+        .map_err(|err| anyhow!("Failed to decode event {}: {}", struct_tag, err))
+}
+
+/// Decodes the event, see [`decode`].
+pub fn decode_event<E: MoveEvent>(event: &ContractEvent) -> Result<Option<E>> {
+    match event.type_tag() {
+        TypeTag::Struct(struct_tag) => decode(struct_tag, event.event_data()),
+        _ => Ok(None),
+    }
+}
+
+/// Decodes the events which decode into `E`, in order, skipping the other ones.
+pub fn decode_events<E: MoveEvent>(events: &[ContractEvent]) -> Result<Vec<E>> {
+    events
+        .iter()
+        .filter_map(|event| decode_event(event).transpose())
+        .collect()
+}
+
+/// Decodes events into JSON, resolving the layouts of their types from the modules on chain.
+pub struct EventDecoder<'a, T: ?Sized> {
+    annotator: MoveValueAnnotator<'a, T>,
+}
+
+impl<'a, T: MoveResolver + ?Sized> EventDecoder<'a, T> {
+    pub fn new(resolver: &'a T) -> Self {
+        Self {
+            annotator: MoveValueAnnotator::new(resolver),
+        }
+    }
+
+    /// Decodes the payload of an event of the given type into JSON. Values are encoded the same
+    /// way as GraphQL scalars are, see [`aptos_types::move_value_json`].
+    pub fn decode_as_json(&self, type_tag: &TypeTag, data: &[u8]) -> Result<Value> {
+        let layout = self.annotator.get_type_layout_with_types(type_tag)?;
+        let value = MoveValue::simple_deserialize(data, &layout)?;
+        Ok(move_value_to_json(&layout, value))
+    }
+
+    /// Decodes the event into JSON, see [`Self::decode_as_json`].
+    pub fn decode_event_as_json(&self, event: &ContractEvent) -> Result<Value> {
+        self.decode_as_json(event.type_tag(), event.event_data())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::identifier::Identifier;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Deserialize, MoveEvent, PartialEq, Serialize)]
+    #[move_event(address = "0x1", module = "coin", name = "DepositEvent")]
+    struct Deposit {
+        amount: u64,
+    }
+
+    #[derive(Debug, Deserialize, MoveEvent, PartialEq, Serialize)]
+    #[move_event(address = "0xcafe", module = "dex")]
+    struct Swap {
+        pool: AccountAddress,
+        amounts: Vec<u64>,
+    }
+
+    fn event<E: MoveEvent + Serialize>(type_params: Vec<TypeTag>, event: &E) -> ContractEvent {
+        let struct_tag = StructTag {
+            address: E::ADDRESS,
+            module: Identifier::new(E::MODULE).unwrap(),
+            name: Identifier::new(E::NAME).unwrap(),
+            type_params,
+        };
+        ContractEvent::new_v2(
+            TypeTag::Struct(Box::new(struct_tag)),
+            bcs::to_bytes(event).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(Deposit::ADDRESS, AccountAddress::ONE);
+        assert_eq!(Deposit::MODULE, "coin");
+        assert_eq!(Deposit::NAME, "DepositEvent");
+        assert_eq!(
+            Swap::ADDRESS,
+            AccountAddress::from_hex_literal("0xcafe").unwrap()
+        );
+        assert_eq!(Swap::NAME, "Swap");
+    }
+
+    #[test]
+    fn test_decode_events() {
+        let deposit = Deposit { amount: 100 };
+        let swap = Swap {
+            pool: AccountAddress::TWO,
+            amounts: vec![1, 2],
+        };
+        let events = vec![
+            event(vec![TypeTag::Address], &deposit),
+            event(vec![], &swap),
+            event(vec![TypeTag::U8], &deposit),
+        ];
+
+        assert_eq!(
+            decode_events::<Deposit>(&events).unwrap(),
+            vec![Deposit { amount: 100 }, Deposit { amount: 100 }]
+        );
+        assert_eq!(decode_events::<Swap>(&events).unwrap(), vec![swap]);
+        assert_eq!(
+            decode_event::<Swap>(&ContractEvent::new_v2(TypeTag::U64, vec![0; 8])).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decode_mismatched_payload() {
+        let event = event(vec![], &Deposit { amount: 100 });
+        let struct_tag = match event.type_tag() {
+            TypeTag::Struct(struct_tag) => struct_tag,
+            _ => unreachable!(),
+        };
+        assert!(decode::<Deposit>(struct_tag, &[1, 2]).is_err());
+    }
+}
@@ -8,9 +8,19 @@
 //!
 //! These expressions can be evaluated or interpreted symbolically, opening up possibilities
 //! for building advanced analysis tools.
+//!
+//! It also provides views of Rust types as Move values, so that natives can measure the abstract
+//! size of the Rust values they produce like they measure Move values.
 
 mod abstract_algebra;
 mod algebra;
+mod native_value;
+
+#[doc(hidden)]
+pub mod reexports {
+    pub use move_vm_types::views::ValueVisitor;
+}
 
 pub use abstract_algebra::*;
 pub use algebra::*;
+pub use native_value::*;
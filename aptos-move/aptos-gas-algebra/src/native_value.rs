@@ -0,0 +1,126 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Views of Rust types as the Move values they mirror.
+//!
+//! Natives sometimes need to charge gas based on the abstract size of values that only exist on
+//! the Rust side (e.g. the results they are about to return). Instead of hand-rolling the size
+//! computation, such types implement [`NativeValueView`], usually via
+//! [`derive_native_value_view!`], and their size is computed by the same visitor as the one used
+//! for Move values, through the [`NativeValue`] adapter.
+
+use move_core_types::{account_address::AccountAddress, u256::U256};
+use move_vm_types::views::{ValueView, ValueVisitor};
+
+/// A Rust type whose values can be traversed as the Move values they mirror.
+pub trait NativeValueView {
+    /// Visits the value, as a Move value at the given depth.
+    fn visit_native(&self, depth: usize, visitor: &mut impl ValueVisitor);
+
+    /// Visits a vector of values, as a Move vector at the given depth. Primitive types override
+    /// this to visit packed vectors, like Move does.
+    fn visit_native_vec(vals: &[Self], depth: usize, visitor: &mut impl ValueVisitor)
+    where
+        Self: Sized,
+    {
+        if visitor.visit_vec(depth, vals.len()) {
+            for val in vals {
+                val.visit_native(depth + 1, visitor);
+            }
+        }
+    }
+}
+
+macro_rules! impl_native_value_view_for_primitive {
+    ($($ty:ty => $visit:ident, $visit_vec:ident);+ $(;)?) => {
+        $(
+            impl NativeValueView for $ty {
+                fn visit_native(&self, depth: usize, visitor: &mut impl ValueVisitor) {
+                    visitor.$visit(depth, *self);
+                }
+
+                fn visit_native_vec(vals: &[Self], depth: usize, visitor: &mut impl ValueVisitor) {
+                    visitor.$visit_vec(depth, vals);
+                }
+            }
+        )+
+    };
+}
+
+impl_native_value_view_for_primitive!(
+    u8 => visit_u8, visit_vec_u8;
+    u16 => visit_u16, visit_vec_u16;
+    u32 => visit_u32, visit_vec_u32;
+    u64 => visit_u64, visit_vec_u64;
+    u128 => visit_u128, visit_vec_u128;
+    U256 => visit_u256, visit_vec_u256;
+    bool => visit_bool, visit_vec_bool;
+    AccountAddress => visit_address, visit_vec_address;
+);
+
+impl<T: NativeValueView> NativeValueView for Vec<T> {
+    fn visit_native(&self, depth: usize, visitor: &mut impl ValueVisitor) {
+        T::visit_native_vec(self, depth, visitor);
+    }
+}
+
+/// Mirrors `0x1::string::String`, i.e. `struct String { bytes: vector<u8> }`.
+impl NativeValueView for String {
+    fn visit_native(&self, depth: usize, visitor: &mut impl ValueVisitor) {
+        if visitor.visit_struct(depth, 1) {
+            visitor.visit_vec_u8(depth + 1, self.as_bytes());
+        }
+    }
+}
+
+/// Mirrors `0x1::option::Option<T>`, i.e. `struct Option<T> { vec: vector<T> }`.
+impl<T: NativeValueView> NativeValueView for Option<T> {
+    fn visit_native(&self, depth: usize, visitor: &mut impl ValueVisitor) {
+        if visitor.visit_struct(depth, 1) {
+            let vals = match self {
+                Some(val) => std::slice::from_ref(val),
+                None => &[],
+            };
+            T::visit_native_vec(vals, depth + 1, visitor);
+        }
+    }
+}
+
+/// Adapter exposing a [`NativeValueView`] as a [`ValueView`], so that it can be passed to
+/// anything that measures Move values, e.g. the abstract value size gas parameters.
+pub struct NativeValue<'a, T>(pub &'a T);
+
+impl<'a, T: NativeValueView> ValueView for NativeValue<'a, T> {
+    fn visit(&self, visitor: &mut impl ValueVisitor) {
+        self.0.visit_native(0, visitor);
+    }
+}
+
+/// Implements [`NativeValueView`] for a struct with named fields, as a Move struct with the same
+/// fields in the same order. All fields must implement [`NativeValueView`].
+///
+/// ```ignore
+/// struct Proof {
+///     commitment: Vec<u8>,
+///     index: u64,
+/// }
+///
+/// derive_native_value_view!(Proof { commitment, index });
+/// ```
+#[macro_export]
+macro_rules! derive_native_value_view {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::NativeValueView for $ty {
+            fn visit_native(
+                &self,
+                depth: usize,
+                visitor: &mut impl $crate::reexports::ValueVisitor,
+            ) {
+                let num_fields = <[&str]>::len(&[$(stringify!($field)),*]);
+                if visitor.visit_struct(depth, num_fields) {
+                    $($crate::NativeValueView::visit_native(&self.$field, depth + 1, visitor);)*
+                }
+            }
+        }
+    };
+}
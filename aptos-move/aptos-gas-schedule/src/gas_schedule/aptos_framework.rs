@@ -192,6 +192,15 @@ crate::gas_schedule::macros::define_gas_parameters!(
         [event_write_to_event_store_base: InternalGas, "event.write_to_event_store.base", 300_000],
         // TODO(Gas): the on-chain name is wrong...
         [event_write_to_event_store_per_abstract_value_unit: InternalGasPerAbstractValueUnit, "event.write_to_event_store.per_abstract_memory_unit", 5_000],
+        [event_write_to_module_event_store_base: InternalGas, { 12.. => "event.write_to_module_event_store.base" }, 300_000],
+        [event_write_to_module_event_store_per_abstract_value_unit: InternalGasPerAbstractValueUnit, { 12.. => "event.write_to_module_event_store.per_abstract_value_unit" }, 5_000],
+        [event_legacy_copy_base: InternalGas, { 12.. => "event.legacy_copy.base" }, 300_000],
+        [event_legacy_copy_per_byte: InternalGasPerByte, { 12.. => "event.legacy_copy.per_byte" }, 100],
+        [event_read_pending_events_base: InternalGas, { 12.. => "event.read_pending_events.base" }, 3000],
+        [event_read_pending_events_per_event: InternalGasPerArg, { 12.. => "event.read_pending_events.per_event" }, 200],
+        [event_read_pending_events_per_byte: InternalGasPerByte, { 12.. => "event.read_pending_events.per_byte" }, 20],
+        [event_type_layout_per_node: InternalGasPerArg, { 12.. => "event.type_layout.per_node" }, 300],
+        [event_metadata_lookup_base: InternalGas, { 12.. => "event.metadata_lookup.base" }, 8000],
 
         [state_storage_get_usage_base_cost: InternalGas, "state_storage.get_usage.base", 10000],
 
@@ -201,6 +210,14 @@ crate::gas_schedule::macros::define_gas_parameters!(
         [aggregator_destroy_base: InternalGas, "aggregator.destroy.base", 10000],
         [aggregator_factory_new_aggregator_base: InternalGas, "aggregator_factory.new_aggregator.base", 10000],
 
+        [counter_create_base: InternalGas, { 12.. => "counter.create.base" }, 10000],
+        [counter_increment_base: InternalGas, { 12.. => "counter.increment.base" }, 6000],
+        [counter_read_snapshot_base: InternalGas, { 12.. => "counter.read_snapshot.base" }, 6000],
+        [counter_snapshot_value_base: InternalGas, { 12.. => "counter.snapshot_value.base" }, 6000],
+        [counter_snapshot_value_read: InternalGas, { 12.. => "counter.snapshot_value.read" }, 300000],
+
+        [block_seed_fetch_block_seed_base: InternalGas, { 12.. => "block_seed.fetch_block_seed.base" }, 3000],
+
         [object_exists_at_base: InternalGas, { 7.. => "object.exists_at.base" }, 5000],
         // These are dummy value, they copied from storage gas in aptos-core/aptos-vm/src/aptos_vm_impl.rs
         [object_exists_at_per_byte_loaded: InternalGasPerByte, { 7.. => "object.exists_at.per_byte_loaded" }, 1000],
@@ -616,3 +616,53 @@ impl InitialGasSchedule for MiscGasParameters {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LATEST_GAS_FEATURE_VERSION;
+    use aptos_gas_algebra::{derive_native_value_view, NativeValue};
+    use move_vm_types::values::{Struct, Value};
+
+    struct Item {
+        id: u64,
+        owner: AccountAddress,
+        name: String,
+        tags: Vec<Vec<u8>>,
+        parent: Option<u64>,
+    }
+
+    derive_native_value_view!(Item {
+        id,
+        owner,
+        name,
+        tags,
+        parent
+    });
+
+    #[test]
+    fn test_native_value_size_matches_move_value() {
+        let params = AbstractValueSizeGasParameters::initial();
+        let item = Item {
+            id: 1,
+            owner: AccountAddress::ONE,
+            name: "item".to_string(),
+            tags: vec![vec![1, 2], vec![3]],
+            parent: Some(0),
+        };
+        let value = Value::struct_(Struct::pack(vec![
+            Value::u64(item.id),
+            Value::address(item.owner),
+            Value::struct_(Struct::pack(vec![Value::vector_u8(item.name.bytes())])),
+            Value::vector_for_testing_only(item.tags.iter().cloned().map(Value::vector_u8)),
+            Value::struct_(Struct::pack(vec![Value::vector_u64(item.parent)])),
+        ]));
+
+        for feature_version in 0..=LATEST_GAS_FEATURE_VERSION {
+            assert_eq!(
+                params.abstract_value_size(NativeValue(&item), feature_version),
+                params.abstract_value_size(&value, feature_version),
+            );
+        }
+    }
+}
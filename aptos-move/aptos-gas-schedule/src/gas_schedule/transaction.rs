@@ -13,7 +13,8 @@ use aptos_types::{
     contract_event::ContractEvent, state_store::state_key::StateKey, write_set::WriteOp,
 };
 use move_core_types::gas_algebra::{
-    InternalGas, InternalGasPerArg, InternalGasPerByte, InternalGasUnit, NumBytes, ToUnitWithParams,
+    InternalGas, InternalGasPerArg, InternalGasPerByte, InternalGasUnit, NumArgs, NumBytes,
+    ToUnitWithParams,
 };
 
 const GAS_SCALING_FACTOR: u64 = 1_000_000;
@@ -139,6 +140,21 @@ crate::gas_schedule::macros::define_gas_parameters!(
             { 5.. => "max_bytes_all_events_per_transaction"},
             10 << 20, // all events from a single transaction are 10MB max
         ],
+        [
+            max_num_events_per_transaction: NumArgs,
+            { 12.. => "max_num_events_per_transaction" },
+            10_000,
+        ],
+        [
+            max_event_type_layout_depth: NumArgs,
+            { 12.. => "max_event_type_layout_depth" },
+            32,
+        ],
+        [
+            max_event_type_layout_nodes: NumArgs,
+            { 12.. => "max_event_type_layout_nodes" },
+            128,
+        ],
         [
             storage_fee_per_state_slot_create: FeePerSlot,
             { 7.. => "storage_fee_per_state_slot_create" },
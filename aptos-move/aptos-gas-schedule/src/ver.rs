@@ -8,6 +8,15 @@
 ///   - Changing how gas is calculated in any way
 ///
 /// Change log:
+/// - V12
+///   - Limits on the number and the size of the module events emitted by a transaction
+///   - Limits on the depth and the number of nodes of the layouts of module event types, and gas
+///     for them
+///   - Added read_pending_event_bytes and pending_event_count native functions
+///   - Separate gas parameters for module events, and for their legacy copies
+///   - Added counter native functions, and gas for the storage reads of counter snapshots
+///   - Added fetch_block_seed native function
+///   - Gas for the lookups of the metadata of the modules declaring events
 /// - V11
 //    - Ristretto255 natives (point cloning & double-scalar multiplication) and Bulletproofs natives
 /// - V10
@@ -41,4 +50,4 @@
 ///       global operations.
 /// - V1
 ///   - TBA
-pub const LATEST_GAS_FEATURE_VERSION: u64 = 11;
+pub const LATEST_GAS_FEATURE_VERSION: u64 = 12;
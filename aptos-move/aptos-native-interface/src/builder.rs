@@ -4,6 +4,7 @@
 use crate::{
     context::SafeNativeContext,
     errors::{SafeNativeError, SafeNativeResult},
+    metrics::NativeMetrics,
 };
 use aptos_gas_schedule::{MiscGasParameters, NativeGasParameters};
 use aptos_types::on_chain_config::{Features, TimedFeatures};
@@ -12,7 +13,7 @@ use move_vm_types::{
     loaded_data::runtime_types::Type, natives::function::NativeResult, values::Value,
 };
 use smallvec::SmallVec;
-use std::{collections::VecDeque, sync::Arc};
+use std::{collections::VecDeque, sync::Arc, time::Instant};
 
 /// Data shared by all native functions, mostly on-chain configurations.
 #[derive(Debug)]
@@ -31,6 +32,7 @@ struct SharedData {
 pub struct SafeNativeBuilder {
     data: Arc<SharedData>,
     enable_incremental_gas_charging: bool,
+    module_name: Option<String>,
 }
 
 impl SafeNativeBuilder {
@@ -53,6 +55,18 @@ impl SafeNativeBuilder {
                 features,
             }),
             enable_incremental_gas_charging: true,
+            module_name: None,
+        }
+    }
+
+    /// Returns a builder for the natives of the given module, which shares the configurations of
+    /// this one. The natives it names are recorded under `module::function`, as function names
+    /// alone collide across modules.
+    pub fn for_module(&self, module_name: &str) -> Self {
+        Self {
+            data: Arc::clone(&self.data),
+            enable_incremental_gas_charging: self.enable_incremental_gas_charging,
+            module_name: Some(module_name.to_string()),
         }
     }
 
@@ -86,6 +100,41 @@ impl SafeNativeBuilder {
     /// The closure will have access to the common Aptos configurations (features, gas params etc.),
     /// allowing the client to use [`SafeNativeContext`] instead of Move VM's [`NativeContext`].
     pub fn make_native<F>(&self, native: F) -> NativeFunction
+    where
+        F: Fn(
+                &mut SafeNativeContext,
+                Vec<Type>,
+                VecDeque<Value>,
+            ) -> SafeNativeResult<SmallVec<[Value; 1]>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.make_native_impl(None, native)
+    }
+
+    /// Same as [`Self::make_native`], but also records the execution time and the gas charged by
+    /// every call to the native, under the given name (prefixed by the module name of the builder,
+    /// see [`Self::for_module`]).
+    pub fn make_named_native<F>(&self, name: &str, native: F) -> NativeFunction
+    where
+        F: Fn(
+                &mut SafeNativeContext,
+                Vec<Type>,
+                VecDeque<Value>,
+            ) -> SafeNativeResult<SmallVec<[Value; 1]>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let metrics = match &self.module_name {
+            Some(module_name) => NativeMetrics::new(&format!("{}::{}", module_name, name)),
+            None => NativeMetrics::new(name),
+        };
+        self.make_native_impl(Some(metrics), native)
+    }
+
+    fn make_native_impl<F>(&self, metrics: Option<NativeMetrics>, native: F) -> NativeFunction
     where
         F: Fn(
                 &mut SafeNativeContext,
@@ -120,8 +169,12 @@ impl SafeNativeBuilder {
                 enable_incremental_gas_charging,
             };
 
+            let start = metrics.as_ref().map(|_| Instant::now());
             let res: Result<SmallVec<[Value; 1]>, SafeNativeError> =
                 native(&mut context, ty_args, args);
+            if let (Some(metrics), Some(start)) = (&metrics, start) {
+                metrics.observe(start.elapsed().as_secs_f64(), context.gas_used.into());
+            }
 
             match res {
                 Ok(ret_vals) => Ok(NativeResult::ok(context.gas_used, ret_vals)),
@@ -154,8 +207,10 @@ impl SafeNativeBuilder {
         S: Into<String>,
         I: IntoIterator<Item = (S, F)> + 'b,
     {
-        natives
-            .into_iter()
-            .map(|(func_name, func)| (func_name.into(), self.make_native(func)))
+        natives.into_iter().map(|(func_name, func)| {
+            let func_name = func_name.into();
+            let native = self.make_named_native(&func_name, func);
+            (func_name, native)
+        })
     }
 }
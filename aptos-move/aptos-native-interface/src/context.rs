@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::errors::{SafeNativeError, SafeNativeResult};
-use aptos_gas_algebra::{AbstractValueSize, GasExpression, GasQuantity, InternalGasUnit};
+use aptos_gas_algebra::{
+    AbstractValueSize, GasExpression, GasQuantity, InternalGasUnit, NativeValue, NativeValueView,
+};
 use aptos_gas_schedule::{MiscGasParameters, NativeGasParameters};
 use aptos_types::on_chain_config::{Features, TimedFeatureFlag, TimedFeatures};
 use move_core_types::gas_algebra::InternalGas;
@@ -85,6 +87,13 @@ impl<'a, 'b, 'c, 'd> SafeNativeContext<'a, 'b, 'c, 'd> {
             .abstract_value_size(val, self.gas_feature_version)
     }
 
+    /// Computes the abstract size of the Move value mirrored by the input Rust value.
+    pub fn native_abs_val_size(&self, val: &impl NativeValueView) -> AbstractValueSize {
+        self.misc_gas_params
+            .abs_val
+            .abstract_value_size(NativeValue(val), self.gas_feature_version)
+    }
+
     /// Returns the current gas feature version.
     pub fn gas_feature_version(&self) -> u64 {
         self.gas_feature_version
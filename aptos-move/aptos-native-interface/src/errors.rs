@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use move_binary_format::errors::PartialVMError;
+use move_core_types::vm_status::StatusCode;
+use std::fmt::Debug;
 
 /// Saner representation of a native function error.
 #[allow(unused)]
@@ -25,6 +27,79 @@ pub enum SafeNativeError {
     InvariantViolation(PartialVMError),
 }
 
+impl SafeNativeError {
+    /// Aborts with the canonical abort code made of the given category and reason, the same way
+    /// as a Move function aborting with `std::error::canonical(category, reason)`.
+    pub fn abort(category: ErrorCategory, reason: u64) -> Self {
+        SafeNativeError::Abort {
+            abort_code: category.canonical(reason),
+        }
+    }
+}
+
+/// The categories of abort codes, as defined by the `std::error` Move module.
+///
+/// A canonical abort code is made of a category, shared by all modules, and of a reason, specific
+/// to the module raising it: e.g. `0x01_0002` is the reason `2` in the `InvalidArgument` category.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Caller specified an invalid argument (http: 400).
+    InvalidArgument = 0x1,
+    /// An input or result of a computation is out of range (http: 400).
+    OutOfRange = 0x2,
+    /// The system is not in a state where the operation can be performed (http: 400).
+    InvalidState = 0x3,
+    /// Request not authenticated due to missing, invalid, or expired auth token (http: 401).
+    Unauthenticated = 0x4,
+    /// Client does not have sufficient permission (http: 403).
+    PermissionDenied = 0x5,
+    /// A specified resource is not found (http: 404).
+    NotFound = 0x6,
+    /// Concurrency conflict, such as read-modify-write conflict (http: 409).
+    Aborted = 0x7,
+    /// The resource that a client tried to create already exists (http: 409).
+    AlreadyExists = 0x8,
+    /// Out of gas or other forms of quota (http: 429).
+    ResourceExhausted = 0x9,
+    /// Request cancelled by the client (http: 499).
+    Cancelled = 0xA,
+    /// Internal error (http: 500).
+    Internal = 0xB,
+    /// Feature not implemented (http: 501).
+    NotImplemented = 0xC,
+    /// The service is currently unavailable. Indicates that a retry could solve the issue (http: 503).
+    Unavailable = 0xD,
+}
+
+impl ErrorCategory {
+    /// Returns the canonical abort code for the given reason in this category.
+    pub const fn canonical(self, reason: u64) -> u64 {
+        ((self as u64) << 16) + reason
+    }
+
+    /// Returns the category of the given canonical abort code, if any.
+    pub fn of_abort_code(abort_code: u64) -> Option<Self> {
+        use ErrorCategory::*;
+
+        Some(match (abort_code >> 16) & 0xFF {
+            0x1 => InvalidArgument,
+            0x2 => OutOfRange,
+            0x3 => InvalidState,
+            0x4 => Unauthenticated,
+            0x5 => PermissionDenied,
+            0x6 => NotFound,
+            0x7 => Aborted,
+            0x8 => AlreadyExists,
+            0x9 => ResourceExhausted,
+            0xA => Cancelled,
+            0xB => Internal,
+            0xC => NotImplemented,
+            0xD => Unavailable,
+            _ => return None,
+        })
+    }
+}
+
 // Allows us to keep using the `?` operator on function calls that return `PartialVMResult` inside safe natives.
 //
 // TODO(Gas): This automatic conversion is VERY PROBLEMATIC as it makes it extremely easy to
@@ -40,3 +115,30 @@ impl From<PartialVMError> for SafeNativeError {
 }
 
 pub type SafeNativeResult<T> = Result<T, SafeNativeError>;
+
+/// Conversion of the failure of a fallible expression into an invariant violation that records
+/// where it happened. Used by `aptos_try_native!`.
+#[doc(hidden)]
+pub trait IntoInvariantViolation<T> {
+    fn into_invariant_violation(self, status: StatusCode, location: &str) -> SafeNativeResult<T>;
+}
+
+impl<T, E: Debug> IntoInvariantViolation<T> for Result<T, E> {
+    fn into_invariant_violation(self, status: StatusCode, location: &str) -> SafeNativeResult<T> {
+        self.map_err(|err| {
+            SafeNativeError::InvariantViolation(
+                PartialVMError::new(status).with_message(format!("[{}] {:?}", location, err)),
+            )
+        })
+    }
+}
+
+impl<T> IntoInvariantViolation<T> for Option<T> {
+    fn into_invariant_violation(self, status: StatusCode, location: &str) -> SafeNativeResult<T> {
+        self.ok_or_else(|| {
+            SafeNativeError::InvariantViolation(
+                PartialVMError::new(status).with_message(format!("[{}] Unexpected None", location)),
+            )
+        })
+    }
+}
@@ -90,3 +90,29 @@ macro_rules! safely_pop_vec_arg {
         vec_vec
     }};
 }
+
+/// Like `aptos_try!` but for safe natives that return `SafeNativeResult<T>`. Evaluates the
+/// expression, in which `?` can be used, and early-returns a
+/// `SafeNativeError::InvariantViolation` if it results in an error (or `None`). The status code
+/// defaults to `UNKNOWN_INVARIANT_VIOLATION_ERROR`, and the message of the error records the
+/// file and line of the macro invocation along with the original error.
+///
+/// ```ignore
+/// let key: EventKey = aptos_try_native!(bcs::from_bytes(&guid), StatusCode::EVENT_KEY_MISMATCH);
+/// let blob = aptos_try_native!(msg.simple_serialize(&layout));
+/// ```
+#[macro_export]
+macro_rules! aptos_try_native {
+    ($e:expr) => {
+        $crate::aptos_try_native!(
+            $e,
+            $crate::reexports::move_vm_types::natives::function::StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR
+        )
+    };
+    ($e:expr, $status:expr) => {{
+        use $crate::IntoInvariantViolation as _;
+        #[allow(clippy::redundant_closure_call)]
+        let result = (|| $e)();
+        result.into_invariant_violation($status, concat!(file!(), ":", line!()))?
+    }};
+}
@@ -12,6 +12,7 @@
 mod builder;
 mod context;
 mod errors;
+mod metrics;
 mod native;
 
 #[macro_use]
@@ -22,5 +23,7 @@ pub mod reexports;
 
 pub use builder::SafeNativeBuilder;
 pub use context::SafeNativeContext;
-pub use errors::{SafeNativeError, SafeNativeResult};
+#[doc(hidden)]
+pub use errors::IntoInvariantViolation;
+pub use errors::{ErrorCategory, SafeNativeError, SafeNativeResult};
 pub use native::RawSafeNative;
@@ -0,0 +1,52 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics_core::{exponential_buckets, register_histogram_vec, Histogram, HistogramVec};
+use once_cell::sync::Lazy;
+
+/// The time spent executing each native function, labeled by `module::function`.
+static NATIVE_EXECUTION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "aptos_vm_native_execution_seconds",
+        // metric description
+        "The time spent in seconds executing a native function",
+        &["native"],
+        exponential_buckets(/*start=*/ 1e-7, /*factor=*/ 2.0, /*count=*/ 24).unwrap(),
+    )
+    .unwrap()
+});
+
+/// The gas charged by each native function, labeled by `module::function`.
+static NATIVE_GAS_CHARGED: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "aptos_vm_native_gas_charged",
+        // metric description
+        "The internal gas charged by a native function",
+        &["native"],
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 4.0, /*count=*/ 16).unwrap(),
+    )
+    .unwrap()
+});
+
+/// The metrics of a single native function, resolved once when the native is created so that
+/// recording them does not need to look up its label on every call.
+pub(crate) struct NativeMetrics {
+    execution_seconds: Histogram,
+    gas_charged: Histogram,
+}
+
+impl NativeMetrics {
+    pub(crate) fn new(native_name: &str) -> Self {
+        Self {
+            execution_seconds: NATIVE_EXECUTION_SECONDS.with_label_values(&[native_name]),
+            gas_charged: NATIVE_GAS_CHARGED.with_label_values(&[native_name]),
+        }
+    }
+
+    pub(crate) fn observe(&self, execution_seconds: f64, gas_charged: u64) {
+        self.execution_seconds.observe(execution_seconds);
+        self.gas_charged.observe(gas_charged as f64);
+    }
+}
@@ -43,6 +43,11 @@ pub enum FeatureFlag {
     GasPayerEnabled,
     AptosUniqueIdentifiers,
     BulletproofsNatives,
+    ModuleEventVersions,
+    ModuleEvent,
+    BlockSeed,
+    ConcurrentCounters,
+    ModuleEventDualEmission,
 }
 
 fn generate_features_blob(writer: &CodeWriter, data: &[u64]) {
@@ -166,6 +171,11 @@ impl From<FeatureFlag> for AptosFeatureFlag {
             FeatureFlag::GasPayerEnabled => AptosFeatureFlag::GAS_PAYER_ENABLED,
             FeatureFlag::AptosUniqueIdentifiers => AptosFeatureFlag::APTOS_UNIQUE_IDENTIFIERS,
             FeatureFlag::BulletproofsNatives => AptosFeatureFlag::BULLETPROOFS_NATIVES,
+            FeatureFlag::ModuleEventVersions => AptosFeatureFlag::MODULE_EVENT_VERSIONS,
+            FeatureFlag::ModuleEvent => AptosFeatureFlag::MODULE_EVENT,
+            FeatureFlag::BlockSeed => AptosFeatureFlag::BLOCK_SEED,
+            FeatureFlag::ConcurrentCounters => AptosFeatureFlag::CONCURRENT_COUNTERS,
+            FeatureFlag::ModuleEventDualEmission => AptosFeatureFlag::MODULE_EVENT_DUAL_EMISSION,
         }
     }
 }
@@ -212,6 +222,11 @@ impl From<AptosFeatureFlag> for FeatureFlag {
             AptosFeatureFlag::GAS_PAYER_ENABLED => FeatureFlag::GasPayerEnabled,
             AptosFeatureFlag::APTOS_UNIQUE_IDENTIFIERS => FeatureFlag::AptosUniqueIdentifiers,
             AptosFeatureFlag::BULLETPROOFS_NATIVES => FeatureFlag::BulletproofsNatives,
+            AptosFeatureFlag::MODULE_EVENT_VERSIONS => FeatureFlag::ModuleEventVersions,
+            AptosFeatureFlag::MODULE_EVENT => FeatureFlag::ModuleEvent,
+            AptosFeatureFlag::BLOCK_SEED => FeatureFlag::BlockSeed,
+            AptosFeatureFlag::CONCURRENT_COUNTERS => FeatureFlag::ConcurrentCounters,
+            AptosFeatureFlag::MODULE_EVENT_DUAL_EMISSION => FeatureFlag::ModuleEventDualEmission,
         }
     }
 }
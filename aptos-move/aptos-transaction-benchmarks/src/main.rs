@@ -84,6 +84,16 @@ struct ExecuteOpt {
 
     #[clap(long)]
     pub maybe_block_gas_limit: Option<u64>,
+
+    /// Applies the writes of each block to the state the next block is executed on, instead of
+    /// executing each block on a fresh state.
+    #[clap(long)]
+    pub apply_writes: bool,
+
+    /// Cross-checks the outputs of the parallel execution against a sequential execution of the
+    /// same block (not supported with sharding).
+    #[clap(long, conflicts_with = "remote_executor_addresses")]
+    pub validate_outputs: bool,
 }
 
 fn param_sweep(opt: ParamSweepOpt) {
@@ -121,6 +131,8 @@ fn param_sweep(opt: ParamSweepOpt) {
                 None,
                 false,
                 maybe_block_gas_limit,
+                false,
+                false,
             );
             par_tps.sort();
             seq_tps.sort();
@@ -183,6 +195,8 @@ fn execute(opt: ExecuteOpt) {
         opt.remote_executor_addresses,
         opt.no_conflict_txns,
         opt.maybe_block_gas_limit,
+        opt.apply_writes,
+        opt.validate_outputs,
     );
 
     let sum: usize = par_tps.iter().sum();
@@ -12,7 +12,9 @@ use aptos_language_e2e_tests::{
     executor::FakeExecutor,
     gas_costs::TXN_RESERVED,
 };
+use aptos_runtimes::thread_pools::{get_thread_pool, ThreadPoolKind};
 use aptos_types::{
+    block_executor::hot_state_keys::HOT_STATE_KEYS,
     block_metadata::BlockMetadata,
     on_chain_config::{OnChainConfig, ValidatorSet},
     transaction::{
@@ -30,7 +32,6 @@ use aptos_vm::{
     },
 };
 use criterion::{measurement::Measurement, BatchSize, Bencher};
-use once_cell::sync::Lazy;
 use proptest::{
     collection::vec,
     strategy::{Strategy, ValueTree},
@@ -38,16 +39,6 @@ use proptest::{
 };
 use std::{net::SocketAddr, sync::Arc, time::Instant};
 
-pub static RAYON_EXEC_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
-    Arc::new(
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_cpus::get())
-            .thread_name(|index| format!("par_exec_{}", index))
-            .build()
-            .unwrap(),
-    )
-});
-
 /// Benchmarking support for transactions.
 #[derive(Clone)]
 pub struct TransactionBencher<S> {
@@ -126,6 +117,12 @@ where
     }
 
     /// Runs the bencher.
+    ///
+    /// With `apply_writes`, the blocks are executed on top of each other, i.e. the writes of
+    /// each block are applied to the state the next one is executed on, instead of executing
+    /// each block on a fresh state. With `validate_outputs`, the outputs of the parallel
+    /// execution are cross-checked against the ones of a sequential execution of the same block.
+    /// Together, they turn the benchmark into a correctness soak test of BlockSTM.
     pub fn blockstm_benchmark(
         &self,
         num_accounts: usize,
@@ -139,6 +136,8 @@ where
         remote_executor_addresses: Option<Vec<SocketAddr>>,
         no_conflict_txn: bool,
         maybe_block_gas_limit: Option<u64>,
+        apply_writes: bool,
+        validate_outputs: bool,
     ) -> (Vec<usize>, Vec<usize>) {
         let mut par_tps = Vec::new();
         let mut seq_tps = Vec::new();
@@ -156,15 +155,37 @@ where
         } else {
             AccountPickStyle::Unlimited
         };
-        for i in 0..total_runs {
-            let mut state = TransactionBenchState::with_size(
+        assert!(
+            !validate_outputs || num_executor_shards == 1,
+            "Outputs can only be validated without sharding, as the sharded executor returns them \
+             in the partitioned order"
+        );
+        let new_state = || {
+            TransactionBenchState::with_size(
                 &self.strategy,
                 num_accounts,
                 num_txn,
                 num_executor_shards,
                 remote_executor_addresses.clone(),
                 account_pick_style.clone(),
-            );
+            )
+        };
+        // The account universe tracks the sequence numbers and balances the generated
+        // transactions expect, so it can only be kept across blocks if their writes are applied.
+        let mut shared_state = if apply_writes {
+            Some(new_state())
+        } else {
+            None
+        };
+        for i in 0..total_runs {
+            let mut fresh_state;
+            let state = match shared_state.as_mut() {
+                Some(state) => state,
+                None => {
+                    fresh_state = new_state();
+                    &mut fresh_state
+                },
+            };
             if i < num_warmups {
                 println!("WARMUP - ignore results");
                 state.execute_blockstm_benchmark(
@@ -173,6 +194,8 @@ where
                     no_conflict_txn,
                     concurrency_level_per_shard,
                     maybe_block_gas_limit,
+                    apply_writes,
+                    validate_outputs,
                 );
             } else {
                 let tps = state.execute_blockstm_benchmark(
@@ -181,6 +204,8 @@ where
                     no_conflict_txn,
                     concurrency_level_per_shard,
                     maybe_block_gas_limit,
+                    apply_writes,
+                    validate_outputs,
                 );
                 par_tps.push(tps.0);
                 seq_tps.push(tps.1);
@@ -247,7 +272,7 @@ where
         // characteristics.
         let universe = universe_gen.setup_gas_cost_stability(&mut executor);
 
-        let state_view = Arc::new(executor.get_state_view().clone());
+        let state_view = executor.get_state_view().snapshot();
         let (parallel_block_executor, block_partitioner) = if num_executor_shards == 1 {
             (None, None)
         } else {
@@ -256,7 +281,10 @@ where
             let parallel_block_executor = Arc::new(ShardedBlockExecutor::new(client));
             (
                 Some(parallel_block_executor),
-                Some(ShardedBlockPartitioner::new(num_executor_shards)),
+                Some(
+                    ShardedBlockPartitioner::new(num_executor_shards)
+                        .with_hot_key_registry(HOT_STATE_KEYS.clone()),
+                ),
             )
         };
 
@@ -343,7 +371,7 @@ where
             _,
             NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
         >(
-            Arc::clone(&RAYON_EXEC_POOL),
+            get_thread_pool(ThreadPoolKind::Execution),
             transactions,
             self.state_view.as_ref(),
             1,
@@ -388,7 +416,7 @@ where
                 _,
                 NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
             >(
-                Arc::clone(&RAYON_EXEC_POOL),
+                get_thread_pool(ThreadPoolKind::Execution),
                 transactions,
                 self.state_view.as_ref(),
                 concurrency_level_per_shard,
@@ -409,9 +437,11 @@ where
         no_conflict_txns: bool,
         conurrency_level_per_shard: usize,
         maybe_block_gas_limit: Option<u64>,
+        apply_writes: bool,
+        validate_outputs: bool,
     ) -> (usize, usize) {
         let transactions = self.gen_transaction(no_conflict_txns);
-        let (output, par_tps) = if run_par {
+        let (par_output, par_tps) = if run_par {
             println!("Parallel execution starts...");
             let (output, tps) = self.execute_benchmark_parallel(
                 transactions.clone(),
@@ -423,29 +453,75 @@ where
         } else {
             (vec![], 0)
         };
-        output.iter().for_each(|txn_output| {
+        par_output.iter().for_each(|txn_output| {
             assert_eq!(
                 txn_output.status(),
                 &TransactionStatus::Keep(ExecutionStatus::Success)
             );
         });
-        let (output, seq_tps) = if run_seq {
+        let (seq_output, seq_tps) = if run_seq {
             println!("Sequential execution starts...");
             let (output, tps) =
                 self.execute_benchmark_sequential(transactions, maybe_block_gas_limit);
             println!("Sequential execution finishes, TPS = {}", tps);
             (output, tps)
+        } else if run_par && validate_outputs {
+            // Not measured, only needed as the reference for the parallel outputs.
+            let (output, _) =
+                self.execute_benchmark_sequential(transactions, maybe_block_gas_limit);
+            (output, 0)
         } else {
             (vec![], 0)
         };
-        output.iter().for_each(|txn_output| {
+        seq_output.iter().for_each(|txn_output| {
             assert_eq!(
                 txn_output.status(),
                 &TransactionStatus::Keep(ExecutionStatus::Success)
             );
         });
+        if run_par && validate_outputs {
+            Self::validate_outputs(&par_output, &seq_output);
+        }
+        if apply_writes {
+            self.apply_writes(if run_par { &par_output } else { &seq_output });
+        }
         (par_tps, seq_tps)
     }
+
+    /// Checks that the parallel execution of a block produced exactly the outputs of its
+    /// sequential execution.
+    fn validate_outputs(par_output: &[TransactionOutput], seq_output: &[TransactionOutput]) {
+        assert_eq!(
+            par_output.len(),
+            seq_output.len(),
+            "Parallel and sequential executions produced different numbers of outputs"
+        );
+        for (idx, (par_txn_output, seq_txn_output)) in
+            par_output.iter().zip(seq_output.iter()).enumerate()
+        {
+            assert_eq!(
+                par_txn_output, seq_txn_output,
+                "Output of transaction {} differs between parallel and sequential executions",
+                idx
+            );
+        }
+        println!(
+            "Parallel outputs match the sequential ones for {} transactions",
+            par_output.len()
+        );
+    }
+
+    /// Applies the writes of the kept transactions of a block to the state the next block is
+    /// executed on.
+    fn apply_writes(&mut self, output: &[TransactionOutput]) {
+        let mut state_view = self.state_view.fork();
+        for txn_output in output {
+            if let TransactionStatus::Keep(_) = txn_output.status() {
+                state_view.add_write_set(txn_output.write_set());
+            }
+        }
+        self.state_view = Arc::new(state_view);
+    }
 }
 
 /// Returns a strategy for the account universe customized for benchmarks, i.e. having
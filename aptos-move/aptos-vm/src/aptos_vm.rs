@@ -7,33 +7,49 @@ use crate::{
         discard_error_output, discard_error_vm_status, PreprocessedTransaction, VMAdapter,
     },
     aptos_vm_impl::{get_transaction_output, AptosVMImpl, AptosVMInternals},
-    block_executor::{AptosTransactionOutput, BlockAptosVM},
+    block_executor::{
+        streaming_commit_hook::{CommittedOutputMsg, StreamingTransactionCommitHook},
+        AptosTransactionOutput, BlockAptosVM,
+    },
     counters::*,
     data_cache::StorageAdapter,
+    decoded_event::{decode_events, DecodedEvent},
     errors::expect_only_successful_execution,
     move_vm_ext::{MoveResolverExt, RespawnedSession, SessionExt, SessionId},
-    sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor},
+    sharded_block_executor::{
+        cross_shard_client::{CrossShardBatchConfig, DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT},
+        executor_client::ExecutorClient,
+        simulated_network::SimulatedNetworkConfig,
+        ShardedBlockExecutor,
+    },
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
     verifier, VMExecutor, VMValidator,
 };
 use anyhow::{anyhow, Result};
-use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_block_executor::{
+    scheduler_config::SchedulerConfig,
+    txn_commit_hook::{NoOpTransactionCommitHook, TransactionCommitHook},
+};
 use aptos_crypto::HashValue;
-use aptos_framework::natives::code::PublishRequest;
+use aptos_framework::natives::{code::PublishRequest, event::EventFilter};
 use aptos_gas_algebra::Gas;
 use aptos_gas_meter::{AptosGasMeter, StandardGasAlgebra, StandardGasMeter};
 use aptos_gas_schedule::VMGasParameters;
 use aptos_logger::{enabled, prelude::*, Level};
 use aptos_memory_usage_tracker::MemoryTrackedGasMeter;
+use aptos_runtimes::thread_pools::{get_thread_pool, ThreadPoolKind};
 use aptos_state_view::StateView;
 use aptos_types::{
+    access_path::AccessPath,
     account_config,
-    account_config::new_block_event_key,
+    account_config::{new_block_event_key, AccountResource, CoinStoreResource},
     block_executor::partitioner::SubBlocksForShard,
     block_metadata::BlockMetadata,
     fee_statement::FeeStatement,
-    on_chain_config::{new_epoch_event_key, FeatureFlag, TimedFeatureOverride},
+    on_chain_config::{
+        new_epoch_event_key, FeatureFlag, GasScheduleV2, OnChainConfig, TimedFeatureOverride,
+    },
     state_store::state_key::StateKey,
     transaction::{
         analyzed_transaction::AnalyzedTransaction, EntryFunction, ExecutionError, ExecutionStatus,
@@ -51,6 +67,7 @@ use aptos_vm_types::{
     output::VMOutput,
     storage::{ChangeSetConfigs, StorageGasParameters},
 };
+use crossbeam_channel::Sender;
 use fail::fail_point;
 use move_binary_format::{
     access::ModuleAccess,
@@ -63,6 +80,7 @@ use move_core_types::{
     ident_str,
     identifier::Identifier,
     language_storage::{ModuleId, TypeTag},
+    move_resource::MoveStructType,
     transaction_argument::convert_txn_args,
     value::{serialize_values, MoveValue},
     vm_status::StatusType,
@@ -70,7 +88,7 @@ use move_core_types::{
 use move_vm_runtime::session::SerializedReturnValues;
 use move_vm_types::gas::UnmeteredGasMeter;
 use num_cpus;
-use once_cell::sync::{Lazy, OnceCell};
+use once_cell::sync::OnceCell;
 use std::{
     cmp::{max, min},
     collections::{BTreeMap, BTreeSet},
@@ -80,25 +98,23 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
+static DELAYED_DELTA_MATERIALIZATION: OnceCell<bool> = OnceCell::new();
+static GAS_PRICE_PRIORITY_HINTS: OnceCell<bool> = OnceCell::new();
+static SHARED_MODULE_CACHE: OnceCell<bool> = OnceCell::new();
+static SCHEDULER_CONFIG: OnceCell<SchedulerConfig> = OnceCell::new();
 static NUM_EXECUTION_SHARD: OnceCell<usize> = OnceCell::new();
-static NUM_PROOF_READING_THREADS: OnceCell<usize> = OnceCell::new();
+static SIMULATED_SHARD_NETWORK: OnceCell<SimulatedNetworkConfig> = OnceCell::new();
+static CROSS_SHARD_BATCH_CONFIG: OnceCell<CrossShardBatchConfig> = OnceCell::new();
+static CROSS_SHARD_RECEIVE_TIMEOUT: OnceCell<Duration> = OnceCell::new();
+static SPECULATIVE_CROSS_SHARD_READS: OnceCell<bool> = OnceCell::new();
 static PARANOID_TYPE_CHECKS: OnceCell<bool> = OnceCell::new();
 static PROCESSED_TRANSACTIONS_DETAILED_COUNTERS: OnceCell<bool> = OnceCell::new();
 static TIMED_FEATURE_OVERRIDE: OnceCell<TimedFeatureOverride> = OnceCell::new();
 
-pub static RAYON_EXEC_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
-    Arc::new(
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_cpus::get())
-            .thread_name(|index| format!("par_exec_{}", index))
-            .build()
-            .unwrap(),
-    )
-});
-
 /// Remove this once the bundle is removed from the code.
 static MODULE_BUNDLE_DISALLOWED: AtomicBool = AtomicBool::new(true);
 pub fn allow_module_bundle_for_test() {
@@ -107,6 +123,19 @@ pub fn allow_module_bundle_for_test() {
 
 pub struct AptosVM(pub(crate) AptosVMImpl);
 
+/// Returns the keys read by the prologue of the transaction: the account and coin store of the
+/// sender, and the gas schedule.
+fn prologue_state_keys(txn: &SignatureCheckedTransaction) -> Vec<StateKey> {
+    [
+        AccessPath::resource_access_path(txn.sender(), AccountResource::struct_tag()),
+        AccessPath::resource_access_path(txn.sender(), CoinStoreResource::struct_tag()),
+        GasScheduleV2::access_path(),
+    ]
+    .into_iter()
+    .filter_map(|access_path| access_path.ok().map(StateKey::access_path))
+    .collect()
+}
+
 struct AptosSimulationVM(AptosVM);
 
 macro_rules! unwrap_or_discard {
@@ -131,6 +160,13 @@ impl AptosVM {
         Self::new(state)
     }
 
+    /// Creates a VM used by several threads at once, e.g. by all the workers of the parallel
+    /// execution of a block. Its loader cache is never flushed, see
+    /// `MoveVmExt::without_loader_cache_flush`.
+    pub(crate) fn new_shared(state: &impl StateView) -> Self {
+        Self(AptosVMImpl::new(state).without_loader_cache_flush())
+    }
+
     /// Sets execution concurrency level when invoked the first time.
     pub fn set_concurrency_level_once(mut concurrency_level: usize) {
         concurrency_level = min(concurrency_level, num_cpus::get());
@@ -162,6 +198,124 @@ impl AptosVM {
         }
     }
 
+    /// Sets the latency and jitter of the simulated network between the local executor shards,
+    /// when invoked the first time.
+    pub fn set_simulated_shard_network_once(network: SimulatedNetworkConfig) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        SIMULATED_SHARD_NETWORK.set(network).ok();
+    }
+
+    /// Get the simulated network between the local executor shards if already set, otherwise
+    /// return the default one without any delay.
+    pub fn get_simulated_shard_network() -> SimulatedNetworkConfig {
+        SIMULATED_SHARD_NETWORK.get().copied().unwrap_or_default()
+    }
+
+    /// Sets how the writes sent between the executor shards are batched and compressed, when
+    /// invoked the first time.
+    pub fn set_cross_shard_batch_config_once(batch_config: CrossShardBatchConfig) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        CROSS_SHARD_BATCH_CONFIG.set(batch_config).ok();
+    }
+
+    /// Get how the writes sent between the executor shards are batched if already set, otherwise
+    /// return the default, sending the writes of each transaction as soon as it is committed.
+    pub fn get_cross_shard_batch_config() -> CrossShardBatchConfig {
+        CROSS_SHARD_BATCH_CONFIG.get().copied().unwrap_or_default()
+    }
+
+    /// Sets how long the executor shards wait for the cross shard values they depend on without
+    /// receiving any message, before aborting the block, when invoked the first time.
+    pub fn set_cross_shard_receive_timeout_once(timeout: Duration) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        CROSS_SHARD_RECEIVE_TIMEOUT.set(timeout).ok();
+    }
+
+    /// Get how long the executor shards wait for the cross shard values if already set, otherwise
+    /// return the default timeout.
+    pub fn get_cross_shard_receive_timeout() -> Duration {
+        CROSS_SHARD_RECEIVE_TIMEOUT
+            .get()
+            .copied()
+            .unwrap_or(DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT)
+    }
+
+    /// Sets whether the executor shards read the cross shard values that did not arrive yet from
+    /// the state before the block, re-executing the sub block if the remote writes differ, when
+    /// invoked the first time.
+    pub fn set_speculative_cross_shard_reads_once(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        SPECULATIVE_CROSS_SHARD_READS.set(enable).ok();
+    }
+
+    /// Get whether the cross shard values are read speculatively if already set, otherwise
+    /// return false.
+    pub fn get_speculative_cross_shard_reads() -> bool {
+        match SPECULATIVE_CROSS_SHARD_READS.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets whether aggregator deltas are materialized only when the outputs of a block are
+    /// assembled (instead of when each transaction is committed) in parallel execution.
+    pub fn set_delayed_delta_materialization_once(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        DELAYED_DELTA_MATERIALIZATION.set(enable).ok();
+    }
+
+    /// Get whether delta materialization is delayed if already set, otherwise return default
+    /// false.
+    pub fn get_delayed_delta_materialization() -> bool {
+        match DELAYED_DELTA_MATERIALIZATION.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets whether the transactions of a block are prioritized by their gas unit price in
+    /// parallel execution.
+    pub fn set_gas_price_priority_hints_once(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        GAS_PRICE_PRIORITY_HINTS.set(enable).ok();
+    }
+
+    /// Get whether the transactions are prioritized by their gas unit price if already set,
+    /// otherwise return default false.
+    pub fn get_gas_price_priority_hints() -> bool {
+        match GAS_PRICE_PRIORITY_HINTS.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets whether the workers of the parallel execution of a block share a VM, and so the
+    /// modules loaded in its cache, when invoked the first time.
+    pub fn set_shared_module_cache_once(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        SHARED_MODULE_CACHE.set(enable).ok();
+    }
+
+    /// Get whether the module cache is shared by the workers if already set, otherwise return
+    /// default false.
+    pub fn get_shared_module_cache() -> bool {
+        match SHARED_MODULE_CACHE.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets the tuning knobs of the parallel execution scheduler, when invoked the first time.
+    pub fn set_scheduler_config_once(scheduler_config: SchedulerConfig) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        SCHEDULER_CONFIG.set(scheduler_config).ok();
+    }
+
+    /// Get the scheduler config if already set, otherwise return the default config.
+    pub fn get_scheduler_config() -> SchedulerConfig {
+        SCHEDULER_CONFIG.get().copied().unwrap_or_default()
+    }
+
     /// Sets runtime config when invoked the first time.
     pub fn set_paranoid_type_checks(enable: bool) {
         // Only the first call succeeds, due to OnceCell semantics.
@@ -185,23 +339,6 @@ impl AptosVM {
         TIMED_FEATURE_OVERRIDE.get().cloned()
     }
 
-    /// Sets the # of async proof reading threads.
-    pub fn set_num_proof_reading_threads_once(mut num_threads: usize) {
-        // TODO(grao): Do more analysis to tune this magic number.
-        num_threads = min(num_threads, 256);
-        // Only the first call succeeds, due to OnceCell semantics.
-        NUM_PROOF_READING_THREADS.set(num_threads).ok();
-    }
-
-    /// Returns the # of async proof reading threads if already set, otherwise return default value
-    /// (32).
-    pub fn get_num_proof_reading_threads() -> usize {
-        match NUM_PROOF_READING_THREADS.get() {
-            Some(num_threads) => *num_threads,
-            None => 32,
-        }
-    }
-
     /// Sets addigional details in counters when invoked the first time.
     pub fn set_processed_transactions_detailed_counters() {
         // Only the first call succeeds, due to OnceCell semantics.
@@ -855,7 +992,13 @@ impl AptosVM {
 
         gas_meter.charge_intrinsic_gas_for_transaction(txn_data.transaction_size())?;
 
+        self.0
+            .check_publishing_allowed()
+            .map_err(|err| err.finish(Location::Undefined).into_vm_status())?;
         Self::verify_module_bundle(&mut session, modules)?;
+        let compiled_modules = self.deserialize_module_bundle(modules)?;
+        let module_ids: Vec<_> = compiled_modules.iter().map(|m| m.self_id()).collect();
+        session.invalidate_module_metadata(&module_ids);
         session.publish_module_bundle_with_compat_config(
             modules.clone().into_inner(),
             txn_data.sender(),
@@ -874,7 +1017,7 @@ impl AptosVM {
         self.execute_module_initialization(
             &mut session,
             gas_meter,
-            &self.deserialize_module_bundle(modules)?,
+            &compiled_modules,
             BTreeSet::new(),
             &[txn_data.sender()],
             new_published_modules_loaded,
@@ -912,6 +1055,10 @@ impl AptosVM {
             check_compat: _,
         }) = session.extract_publish_request()
         {
+            self.0
+                .check_publishing_allowed()
+                .map_err(|err| err.finish(Location::Undefined))?;
+
             // TODO: unfortunately we need to deserialize the entire bundle here to handle
             // `init_module` and verify some deployment conditions, while the VM need to do
             // the deserialization again. Consider adding an API to MoveVM which allows to
@@ -933,6 +1080,8 @@ impl AptosVM {
             // Publish the bundle and execute initializers
             // publish_module_bundle doesn't actually load the published module into
             // the loader cache. It only puts the module data in the data cache.
+            let module_ids: Vec<_> = modules.iter().map(|m| m.self_id()).collect();
+            session.invalidate_module_metadata(&module_ids);
             return_on_failure!(session.publish_module_bundle_with_compat_config(
                 bundle.into_inner(),
                 destination,
@@ -1254,11 +1403,11 @@ impl AptosVM {
         let has_new_block_event = change_set
             .events()
             .iter()
-            .any(|e| *e.key() == new_block_event_key());
+            .any(|e| e.event_key() == Some(&new_block_event_key()));
         let has_new_epoch_event = change_set
             .events()
             .iter()
-            .any(|e| *e.key() == new_epoch_event_key());
+            .any(|e| e.event_key() == Some(&new_epoch_event_key()));
         if has_new_block_event && has_new_epoch_event {
             Ok(())
         } else {
@@ -1354,7 +1503,26 @@ impl AptosVM {
         txn: &SignedTransaction,
         state_view: &impl StateView,
     ) -> (VMStatus, TransactionOutput) {
-        let vm = AptosVM::new(state_view);
+        Self::simulate_signed_transaction_with_vm(AptosVM::new(state_view), txn, state_view)
+    }
+
+    /// Same as `simulate_signed_transaction`, but the events of the output are the ones returned
+    /// by the given filter, e.g. to redact large payloads. The gas charged for the events is
+    /// computed from the filtered events.
+    pub fn simulate_signed_transaction_with_event_filter(
+        txn: &SignedTransaction,
+        state_view: &impl StateView,
+        event_filter: Arc<dyn EventFilter>,
+    ) -> (VMStatus, TransactionOutput) {
+        let vm = Self(AptosVMImpl::new(state_view).with_event_filter(event_filter));
+        Self::simulate_signed_transaction_with_vm(vm, txn, state_view)
+    }
+
+    fn simulate_signed_transaction_with_vm(
+        vm: AptosVM,
+        txn: &SignedTransaction,
+        state_view: &impl StateView,
+    ) -> (VMStatus, TransactionOutput) {
         let simulation_vm = AptosSimulationVM(vm);
         let log_context = AdapterLogSchema::new(state_view.id(), 0);
 
@@ -1371,6 +1539,22 @@ impl AptosVM {
         )
     }
 
+    /// Executes a SignedTransaction without performing signature verification, and decodes
+    /// the module events emitted by the transaction. The decoded events are returned in the
+    /// same order as the events of the output, with None for events that could not be decoded.
+    pub fn simulate_signed_transaction_with_decoded_events(
+        txn: &SignedTransaction,
+        state_view: &impl StateView,
+    ) -> (VMStatus, TransactionOutput, Vec<Option<DecodedEvent>>) {
+        let (vm_status, output) = Self::simulate_signed_transaction(txn, state_view);
+
+        let vm = AptosVM::new(state_view);
+        let resolver = vm.as_move_resolver(state_view);
+        let session = vm.new_session(&resolver, SessionId::Void);
+        let decoded_events = decode_events(&session, output.events());
+        (vm_status, output, decoded_events)
+    }
+
     pub fn execute_view_function(
         state_view: &impl StateView,
         module_id: ModuleId,
@@ -1465,6 +1649,28 @@ impl AptosVM {
             },
         }
     }
+
+    fn execute_block_with_commit_hook<L: TransactionCommitHook<Output = AptosTransactionOutput>>(
+        transactions: Vec<Transaction>,
+        state_view: &(impl StateView + Sync),
+        maybe_block_gas_limit: Option<u64>,
+        transaction_commit_hook: Option<L>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let count = transactions.len();
+        let ret = BlockAptosVM::execute_block(
+            get_thread_pool(ThreadPoolKind::Execution),
+            transactions,
+            state_view,
+            Self::get_concurrency_level(),
+            maybe_block_gas_limit,
+            transaction_commit_hook,
+        );
+        if ret.is_ok() {
+            // Record the histogram count for transactions per block.
+            BLOCK_TRANSACTION_COUNT.observe(count as f64);
+        }
+        ret
+    }
 }
 
 // Executor external API
@@ -1492,23 +1698,36 @@ impl VMExecutor for AptosVM {
             transactions.len()
         );
 
-        let count = transactions.len();
-        let ret = BlockAptosVM::execute_block::<
-            _,
+        Self::execute_block_with_commit_hook::<
             NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
-        >(
-            Arc::clone(&RAYON_EXEC_POOL),
+        >(transactions, state_view, maybe_block_gas_limit, None)
+    }
+
+    fn execute_block_streaming(
+        transactions: Vec<Transaction>,
+        state_view: &(impl StateView + Sync),
+        maybe_block_gas_limit: Option<u64>,
+        output_tx: Sender<CommittedOutputMsg>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        fail_point!("move_adapter::execute_block", |_| {
+            Err(VMStatus::error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+                None,
+            ))
+        });
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+        info!(
+            log_context,
+            "Executing block with streamed outputs, transaction count: {}",
+            transactions.len()
+        );
+
+        Self::execute_block_with_commit_hook(
             transactions,
             state_view,
-            Self::get_concurrency_level(),
             maybe_block_gas_limit,
-            None,
-        );
-        if ret.is_ok() {
-            // Record the histogram count for transactions per block.
-            BLOCK_TRANSACTION_COUNT.observe(count as f64);
-        }
-        ret
+            Some(StreamingTransactionCommitHook::new(output_tx)),
+        )
     }
 
     fn execute_block_sharded<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>>(
@@ -1566,6 +1785,16 @@ impl VMValidator for AptosVM {
             },
         };
 
+        // Fetch the values read by the prologue in one batch. Caching state views (e.g. the
+        // `CachedDbStateView` of the mempool validator) keep them, so the prologue reads below
+        // are served from the cache.
+        if let Err(err) = state_view.get_state_values(&prologue_state_keys(&txn)) {
+            warn!(
+                log_context,
+                "[aptos_vm] Failed to prefetch prologue state values: {:?}", err
+            );
+        }
+
         let resolver = self.as_move_resolver(state_view);
         let mut session = self.0.new_session(&resolver, SessionId::prologue(&txn));
         let validation_result = self.validate_signature_checked_transaction(
@@ -1644,7 +1873,7 @@ impl VMAdapter for AptosVM {
             .change_set()
             .events()
             .iter()
-            .any(|event| *event.key() == new_epoch_event_key)
+            .any(|event| event.event_key() == Some(&new_epoch_event_key))
     }
 
     fn execute_single_transaction(
@@ -11,7 +11,10 @@ use crate::{
     transaction_metadata::TransactionMetadata,
     transaction_validation::APTOS_TRANSACTION_VALIDATION,
 };
-use aptos_framework::RuntimeModuleMetadataV1;
+use aptos_framework::{
+    natives::event::{EventFilter, NativeEventLimits},
+    RuntimeModuleMetadataV1,
+};
 use aptos_gas_algebra::{Gas, GasExpression};
 use aptos_gas_schedule::{
     AptosGasParameters, FromOnChainGasSchedule, MiscGasParameters, NativeGasParameters,
@@ -35,7 +38,10 @@ use aptos_vm_types::{
     storage::{ChangeSetConfigs, StorageGasParameters, StoragePricing},
 };
 use fail::fail_point;
-use move_binary_format::{errors::VMResult, CompiledModule};
+use move_binary_format::{
+    errors::{PartialVMError, PartialVMResult, VMResult},
+    CompiledModule,
+};
 use move_core_types::{
     gas_algebra::NumArgs,
     language_storage::ModuleId,
@@ -43,7 +49,10 @@ use move_core_types::{
 };
 use move_vm_runtime::logging::expect_no_verification_errors;
 use move_vm_types::gas::UnmeteredGasMeter;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 pub const MAXIMUM_APPROVED_TRANSACTION_SIZE: u64 = 1024 * 1024;
 
@@ -55,6 +64,10 @@ pub struct AptosVMImpl {
     storage_gas_params: Result<StorageGasParameters, String>,
     version: Option<Version>,
     features: Features,
+    // Whether the VM is used by several threads at once, in which case it must not publish
+    // modules, and whether a transaction tried to.
+    shared: bool,
+    publish_attempted: AtomicBool,
 }
 
 pub fn gas_config(storage: &impl MoveResolverExt) -> (Result<AptosGasParameters, String>, u64) {
@@ -137,6 +150,20 @@ impl AptosVMImpl {
             timed_features = timed_features.with_override_profile(profile)
         }
 
+        let event_limits = match &gas_params {
+            Ok(gas_params) if gas_feature_version >= 12 => NativeEventLimits {
+                max_num_events: gas_params.vm.txn.max_num_events_per_transaction.into(),
+                max_total_bytes: gas_params
+                    .vm
+                    .txn
+                    .max_bytes_all_events_per_transaction
+                    .into(),
+                max_type_layout_depth: gas_params.vm.txn.max_event_type_layout_depth.into(),
+                max_type_layout_nodes: gas_params.vm.txn.max_event_type_layout_nodes.into(),
+            },
+            _ => NativeEventLimits::unlimited(),
+        };
+
         let move_vm = MoveVmExt::new(
             native_gas_params,
             misc_gas_params,
@@ -145,7 +172,8 @@ impl AptosVMImpl {
             features.clone(),
             timed_features,
         )
-        .expect("should be able to create Move VM; check if there are duplicated natives");
+        .expect("should be able to create Move VM; check if there are duplicated natives")
+        .with_event_limits(event_limits);
 
         let version = Version::fetch_config(&storage);
 
@@ -156,6 +184,8 @@ impl AptosVMImpl {
             storage_gas_params,
             version,
             features,
+            shared: false,
+            publish_attempted: AtomicBool::new(false),
         }
     }
 
@@ -163,6 +193,42 @@ impl AptosVMImpl {
         self.move_vm.mark_loader_cache_as_invalid();
     }
 
+    pub(crate) fn is_loader_cache_invalidated(&self) -> bool {
+        self.move_vm.is_loader_cache_invalidated()
+    }
+
+    /// See `MoveVmExt::with_event_filter`.
+    pub(crate) fn with_event_filter(mut self, event_filter: Arc<dyn EventFilter>) -> Self {
+        self.move_vm = self.move_vm.with_event_filter(event_filter);
+        self
+    }
+
+    /// See `MoveVmExt::without_loader_cache_flush`. Such a VM refuses to publish modules, as
+    /// verifying them and running their initializers would load them into the loader cache while
+    /// other threads use it, see `check_publishing_allowed`.
+    pub(crate) fn without_loader_cache_flush(mut self) -> Self {
+        self.move_vm = self.move_vm.without_loader_cache_flush();
+        self.shared = true;
+        self
+    }
+
+    /// Fails if the VM is shared by several threads, before any of the modules to publish is
+    /// verified or loaded. The owner of the VM must then stop using it, and execute the
+    /// transaction again with a VM of its own (see `publish_attempted`).
+    pub(crate) fn check_publishing_allowed(&self) -> PartialVMResult<()> {
+        if self.shared {
+            self.publish_attempted.store(true, Ordering::Release);
+            return Err(PartialVMError::new(StatusCode::FEATURE_UNDER_GATING)
+                .with_message("Modules can not be published with a shared VM".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether a transaction tried to publish modules with this (shared) VM.
+    pub(crate) fn publish_attempted(&self) -> bool {
+        self.publish_attempted.load(Ordering::Acquire)
+    }
+
     /// Provides access to some internal APIs of the VM.
     pub fn internals(&self) -> AptosVMInternals {
         AptosVMInternals(self)
@@ -2,35 +2,44 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod streaming_commit_hook;
 pub(crate) mod vm_wrapper;
 
 use crate::{
     adapter_common::{preprocess_transaction, PreprocessedTransaction},
-    block_executor::vm_wrapper::AptosExecutorTask,
+    block_executor::vm_wrapper::{AptosExecutorTask, SharedBlockVM},
     counters::{
         BLOCK_EXECUTOR_CONCURRENCY, BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS,
         BLOCK_EXECUTOR_SIGNATURE_VERIFICATION_SECONDS,
     },
+    data_cache::AsMoveResolver,
     AptosVM,
 };
 use aptos_aggregator::delta_change_set::DeltaOp;
 use aptos_block_executor::{
+    block_analysis::BlockAnalysis,
     errors::Error,
+    execution_summary::BlockExecutionSummary,
     executor::BlockExecutor,
+    scheduler_config::SchedulerConfig,
     task::{
         Transaction as BlockExecutorTransaction,
         TransactionOutput as BlockExecutorTransactionOutput,
     },
-    txn_commit_hook::TransactionCommitHook,
+    txn_commit_hook::{NoOpTransactionCommitHook, TransactionCommitHook},
 };
 use aptos_infallible::Mutex;
+use aptos_logger::{debug, info};
 use aptos_state_view::{StateView, StateViewId};
 use aptos_types::{
+    block_executor::hot_state_keys::HOT_STATE_KEYS,
+    contract_event::ContractEvent,
     executable::ExecutableTestType,
     fee_statement::FeeStatement,
+    on_chain_config::{OnChainConfig, OnChainExecutionConfig},
     state_store::state_key::StateKey,
     transaction::{Transaction, TransactionOutput, TransactionStatus},
-    write_set::WriteOp,
+    write_set::{WriteOp, WriteSet},
 };
 use aptos_vm_logging::{flush_speculative_logs, init_speculative_logs};
 use aptos_vm_types::output::VMOutput;
@@ -40,6 +49,7 @@ use rayon::{prelude::*, ThreadPool};
 use std::sync::Arc;
 
 impl BlockExecutorTransaction for PreprocessedTransaction {
+    type Event = ContractEvent;
     type Key = StateKey;
     type Value = WriteOp;
 }
@@ -49,6 +59,8 @@ impl BlockExecutorTransaction for PreprocessedTransaction {
 pub struct AptosTransactionOutput {
     vm_output: Mutex<Option<VMOutput>>,
     committed_output: OnceCell<TransactionOutput>,
+    // The aggregator deltas materialized into the committed output.
+    committed_deltas: OnceCell<Vec<(StateKey, DeltaOp)>>,
 }
 
 impl AptosTransactionOutput {
@@ -56,6 +68,7 @@ impl AptosTransactionOutput {
         Self {
             vm_output: Mutex::new(Some(output)),
             committed_output: OnceCell::new(),
+            committed_deltas: OnceCell::new(),
         }
     }
 
@@ -63,6 +76,16 @@ impl AptosTransactionOutput {
         self.committed_output.get().unwrap()
     }
 
+    /// Returns the aggregator delta of the committed transaction to the given key, if its write
+    /// to the key was materialized from a delta.
+    pub(crate) fn committed_delta(&self, state_key: &StateKey) -> Option<DeltaOp> {
+        self.committed_deltas
+            .get()?
+            .iter()
+            .find(|(key, _)| key == state_key)
+            .map(|(_, delta_op)| *delta_op)
+    }
+
     fn take_output(mut self) -> TransactionOutput {
         match self.committed_output.take() {
             Some(output) => output,
@@ -111,18 +134,45 @@ impl BlockExecutorTransactionOutput for AptosTransactionOutput {
             .collect()
     }
 
+    /// Can be called both before and after incorporate_delta_writes, as events
+    /// are not affected by delta materialization.
+    fn get_events(&self) -> Vec<ContractEvent> {
+        match self.committed_output.get() {
+            Some(output) => output.events().to_vec(),
+            None => self
+                .vm_output
+                .lock()
+                .as_ref()
+                .expect("Output to be set to get events")
+                .change_set()
+                .events()
+                .to_vec(),
+        }
+    }
+
     /// Can be called (at most) once after transaction is committed to internally
     /// include the delta outputs with the transaction outputs.
     fn incorporate_delta_writes(&self, delta_writes: Vec<(StateKey, WriteOp)>) {
-        assert!(
-            self.committed_output
+        let vm_output = self
+            .vm_output
+            .lock()
+            .take()
+            .expect("Output must be set to combine with deltas");
+        if !delta_writes.is_empty() {
+            self.committed_deltas
                 .set(
-                    self.vm_output
-                        .lock()
-                        .take()
-                        .expect("Output must be set to combine with deltas")
-                        .into_transaction_output_with_materialized_deltas(delta_writes),
+                    vm_output
+                        .change_set()
+                        .aggregator_delta_set()
+                        .iter()
+                        .map(|(key, op)| (key.clone(), *op))
+                        .collect(),
                 )
+                .expect("Deltas must only be committed once");
+        }
+        assert!(
+            self.committed_output
+                .set(vm_output.into_transaction_output_with_materialized_deltas(delta_writes))
                 .is_ok(),
             "Could not combine VMOutput with deltas"
         );
@@ -138,6 +188,27 @@ impl BlockExecutorTransactionOutput for AptosTransactionOutput {
             .fee_statement()
             .clone()
     }
+
+    /// Should never be called after vm_output is consumed.
+    fn output_size(&self) -> u64 {
+        let vm_output = self.vm_output.lock();
+        let change_set = vm_output
+            .as_ref()
+            .expect("Output to be set to get output size")
+            .change_set();
+        let writes_size: usize = change_set
+            .write_set_iter()
+            .map(|(key, op)| key.size() + op.bytes().map_or(0, |bytes| bytes.len()))
+            .sum();
+        // Deltas are materialized to u128 values.
+        let deltas_size: usize = change_set
+            .aggregator_delta_set()
+            .keys()
+            .map(|key| key.size() + std::mem::size_of::<u128>())
+            .sum();
+        let events_size: usize = change_set.events().iter().map(|event| event.size()).sum();
+        (writes_size + deltas_size + events_size) as u64
+    }
 }
 
 pub struct BlockAptosVM();
@@ -151,6 +222,50 @@ impl BlockAptosVM {
             .collect()
     }
 
+    /// Returns the limit on the output size of the transactions committed in a block, from the
+    /// on-chain execution config. The limit cuts the block short like the block gas limit, so it
+    /// only applies if a block gas limit is set (as a state checkpoint is then appended after the
+    /// last committed transaction).
+    pub fn block_output_limit(
+        state_view: &impl StateView,
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Option<u64> {
+        maybe_block_gas_limit?;
+        OnChainExecutionConfig::fetch_config(&state_view.as_move_resolver())
+            .and_then(|config| config.block_output_limit())
+    }
+
+    /// Cuts the block once the accumulated output size of its transactions reaches the limit, the
+    /// same way the block executor does, i.e. the transactions after the one reaching the limit
+    /// are retried. This is used when the block is executed in parts (e.g. by shards), which can
+    /// not apply the limit to the whole block themselves.
+    pub fn apply_block_output_limit(outputs: &mut [TransactionOutput], block_output_limit: u64) {
+        let mut accumulated_output_size = 0;
+        let num_committed = outputs
+            .iter()
+            .position(|output| {
+                let writes_size: usize = output
+                    .write_set()
+                    .iter()
+                    .map(|(key, op)| key.size() + op.bytes().map_or(0, |bytes| bytes.len()))
+                    .sum();
+                let events_size: usize = output.events().iter().map(|event| event.size()).sum();
+                accumulated_output_size += (writes_size + events_size) as u64;
+                accumulated_output_size >= block_output_limit
+            })
+            .map_or(outputs.len(), |idx| idx + 1);
+        for output in &mut outputs[num_committed..] {
+            if !output.status().is_retry() {
+                *output = TransactionOutput::new(
+                    WriteSet::default(),
+                    vec![],
+                    0,
+                    TransactionStatus::Retry,
+                );
+            }
+        }
+    }
+
     pub fn execute_block<
         S: StateView + Sync,
         L: TransactionCommitHook<Output = AptosTransactionOutput>,
@@ -162,6 +277,91 @@ impl BlockAptosVM {
         maybe_block_gas_limit: Option<u64>,
         transaction_commit_listener: Option<L>,
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block_with_summary(
+            executor_thread_pool,
+            transactions,
+            state_view,
+            concurrency_level,
+            maybe_block_gas_limit,
+            transaction_commit_listener,
+        )
+        .map(|(outputs, _)| outputs)
+    }
+
+    /// Same as execute_block, but also returns the summary of how many times each transaction
+    /// was (speculatively) executed and the time spent, e.g. to find the conflict heavy
+    /// transactions of the block.
+    pub fn execute_block_with_summary<
+        S: StateView + Sync,
+        L: TransactionCommitHook<Output = AptosTransactionOutput>,
+    >(
+        executor_thread_pool: Arc<ThreadPool>,
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+        maybe_block_gas_limit: Option<u64>,
+        transaction_commit_listener: Option<L>,
+    ) -> Result<(Vec<TransactionOutput>, Option<BlockExecutionSummary>), VMStatus> {
+        let maybe_priority_hints = Self::priority_hints(&transactions);
+        Self::execute_block_with_scheduler_config(
+            executor_thread_pool,
+            transactions,
+            state_view,
+            concurrency_level,
+            maybe_block_gas_limit,
+            Self::block_output_limit(state_view, maybe_block_gas_limit),
+            transaction_commit_listener,
+            AptosVM::get_scheduler_config(),
+            maybe_priority_hints,
+        )
+    }
+
+    /// Returns the priority hints of the transactions, if they are to be prioritized by their
+    /// gas unit price (see `AptosVM::set_gas_price_priority_hints_once`).
+    pub fn priority_hints(transactions: &[Transaction]) -> Option<Vec<u64>> {
+        if AptosVM::get_gas_price_priority_hints() {
+            Self::priority_hints_by_gas_price(transactions)
+        } else {
+            None
+        }
+    }
+
+    /// The priority of a user transaction is the gas unit price it pays above the cheapest user
+    /// transaction of the block, so that the transactions paying the minimum, and the other
+    /// transactions (e.g. the block metadata) are not prioritized. Returns None if there are no
+    /// user transactions.
+    pub fn priority_hints_by_gas_price(transactions: &[Transaction]) -> Option<Vec<u64>> {
+        let gas_unit_price = |txn: &Transaction| match txn {
+            Transaction::UserTransaction(txn) => Some(txn.gas_unit_price()),
+            _ => None,
+        };
+        let min_gas_unit_price = transactions.iter().filter_map(gas_unit_price).min()?;
+        Some(
+            transactions
+                .iter()
+                .map(|txn| gas_unit_price(txn).map_or(0, |price| price - min_gas_unit_price))
+                .collect(),
+        )
+    }
+
+    /// Same as execute_block, but with the given block output limit, scheduler config (e.g.
+    /// conflict resolution policy) and priority hints for this block, instead of the ones that
+    /// apply to all the blocks. The execution summary of the block is returned alongside its
+    /// outputs.
+    pub fn execute_block_with_scheduler_config<
+        S: StateView + Sync,
+        L: TransactionCommitHook<Output = AptosTransactionOutput>,
+    >(
+        executor_thread_pool: Arc<ThreadPool>,
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+        maybe_block_gas_limit: Option<u64>,
+        maybe_block_output_limit: Option<u64>,
+        transaction_commit_listener: Option<L>,
+        scheduler_config: SchedulerConfig,
+        maybe_priority_hints: Option<Vec<u64>>,
+    ) -> Result<(Vec<TransactionOutput>, Option<BlockExecutionSummary>), VMStatus> {
         let _timer = BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS.start_timer();
         // Verify the signatures of all the transactions in parallel.
         // This is time consuming so don't wait and do the checking
@@ -181,7 +381,7 @@ impl BlockAptosVM {
         }
 
         BLOCK_EXECUTOR_CONCURRENCY.set(concurrency_level as i64);
-        let executor = BlockExecutor::<
+        let mut executor = BlockExecutor::<
             PreprocessedTransaction,
             AptosExecutorTask<S>,
             S,
@@ -192,9 +392,38 @@ impl BlockAptosVM {
             executor_thread_pool,
             maybe_block_gas_limit,
             transaction_commit_listener,
-        );
+        )
+        .with_delayed_delta_materialization(AptosVM::get_delayed_delta_materialization())
+        .with_block_output_limit(maybe_block_output_limit)
+        .with_scheduler_config(scheduler_config)
+        .with_hot_key_registry(HOT_STATE_KEYS.clone());
+        if let Some(priority_hints) = maybe_priority_hints {
+            executor = executor.with_priority_hints(priority_hints);
+        }
 
-        let ret = executor.execute_block(state_view, signature_verified_block, state_view);
+        // The modules are loaded once for all the workers, unless the block executes sequentially.
+        let shared_vm = (AptosVM::get_shared_module_cache() && concurrency_level > 1)
+            .then(|| SharedBlockVM::new(state_view));
+        let ret = executor.execute_block(
+            (state_view, shared_vm.as_ref()),
+            signature_verified_block,
+            state_view,
+        );
+        if let Some(report) = executor.take_scheduler_report() {
+            info!("[BlockSTM]: Scheduler report: {:?}", report);
+        }
+        let maybe_summary = executor.take_execution_summary();
+        if let Some(summary) = &maybe_summary {
+            let most_reexecuted = summary.most_reexecuted(10);
+            if !most_reexecuted.is_empty() {
+                debug!(
+                    "[BlockSTM]: {} executions of {} txns, most re-executed txns: {:?}",
+                    summary.total_executions(),
+                    summary.txn_stats.len(),
+                    most_reexecuted
+                );
+            }
+        }
         match ret {
             Ok(outputs) => {
                 let output_vec: Vec<TransactionOutput> = outputs
@@ -211,12 +440,104 @@ impl BlockAptosVM {
                     flush_speculative_logs(pos);
                 }
 
-                Ok(output_vec)
+                Ok((output_vec, maybe_summary))
             },
-            Err(Error::ModulePathReadWrite) => {
+            Err(Error::ModulePathReadWrite) | Err(Error::MaxRetriesExceeded) => {
                 unreachable!("[Execution]: Must be handled by sequential fallback")
             },
             Err(Error::UserError(err)) => Err(err),
         }
     }
+
+    /// Executes the block in parallel only to collect the read and write sets of its transactions
+    /// and their conflict graph, e.g. for the partitioner or for tooling predicting how well the
+    /// block parallelizes. No outputs are produced and nothing is committed. Errors that would
+    /// make execute_block fall back to sequential execution (e.g. a module both read and
+    /// published in the block) are returned, as the read and write sets are then incomplete.
+    /// Analysis requires parallel execution (which tracks the reads of the transactions), so
+    /// concurrency_level must be greater than 1.
+    pub fn analyze_block<S: StateView + Sync>(
+        executor_thread_pool: Arc<ThreadPool>,
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Result<BlockAnalysis<StateKey>, Error<VMStatus>> {
+        let signature_verified_block =
+            executor_thread_pool.install(|| Self::verify_transactions(transactions));
+
+        let num_txns = signature_verified_block.len();
+        if state_view.id() != StateViewId::Miscellaneous {
+            // Speculation is disabled in Miscellaneous context, which is used by testing and
+            // can even lead to concurrent execute_block invocations, leading to errors on flush.
+            init_speculative_logs(num_txns);
+        }
+
+        let executor = BlockExecutor::<
+            PreprocessedTransaction,
+            AptosExecutorTask<S>,
+            S,
+            NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
+            ExecutableTestType,
+        >::new(
+            concurrency_level,
+            executor_thread_pool,
+            maybe_block_gas_limit,
+            None,
+        )
+        .with_block_output_limit(Self::block_output_limit(state_view, maybe_block_gas_limit))
+        .with_scheduler_config(AptosVM::get_scheduler_config());
+
+        let ret = executor.analyze_block((state_view, None), signature_verified_block, state_view);
+        if state_view.id() != StateViewId::Miscellaneous {
+            // The logs of the dry run are discarded.
+            flush_speculative_logs(0);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
+    use aptos_types::{
+        account_address::AccountAddress,
+        test_helpers::transaction_test_helpers::get_test_signed_transaction,
+    };
+
+    fn user_txn(gas_unit_price: u64) -> Transaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        Transaction::UserTransaction(get_test_signed_transaction(
+            AccountAddress::random(),
+            0,
+            &private_key,
+            private_key.public_key(),
+            None,
+            u64::MAX,
+            gas_unit_price,
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_priority_hints_by_gas_price() {
+        assert_eq!(
+            BlockAptosVM::priority_hints_by_gas_price(&[Transaction::StateCheckpoint(
+                HashValue::zero()
+            )]),
+            None
+        );
+
+        let transactions = vec![
+            Transaction::StateCheckpoint(HashValue::zero()),
+            user_txn(150),
+            user_txn(100),
+            user_txn(300),
+        ];
+        assert_eq!(
+            BlockAptosVM::priority_hints_by_gas_price(&transactions),
+            Some(vec![0, 50, 0, 200])
+        );
+    }
 }
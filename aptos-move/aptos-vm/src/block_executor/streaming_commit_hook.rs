@@ -0,0 +1,190 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::block_executor::AptosTransactionOutput;
+use aptos_block_executor::txn_commit_hook::TransactionCommitHook;
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::TxnIndex;
+use aptos_types::transaction::TransactionOutput;
+use crossbeam_channel::Sender;
+use std::collections::BTreeMap;
+
+/// A message streamed by the `StreamingTransactionCommitHook`.
+///
+/// A consumer must not act irrevocably on the streamed outputs before the execution of the block
+/// returns: a `Restart` voids all the outputs streamed before it, which are streamed again, and
+/// may differ (e.g. when BlockSTM falls back to the sequential execution).
+#[derive(Debug)]
+pub enum CommittedOutputMsg {
+    /// The output of a committed transaction. The outputs are streamed in the order of the
+    /// transactions in the block.
+    Output(TxnIndex, TransactionOutput),
+    /// The outputs streamed so far are void, as the block is executed again from the start, and
+    /// the outputs are streamed again from the first transaction of the block.
+    Restart,
+}
+
+struct OrderedOutputs {
+    next_txn_idx: TxnIndex,
+    // The outputs committed ahead of the outputs of the transactions before them.
+    pending: BTreeMap<TxnIndex, TransactionOutput>,
+}
+
+/// Streams the outputs of the transactions to `output_tx` as BlockSTM commits them, so that the
+/// commit pipeline can start on the outputs before the whole block is executed. BlockSTM may
+/// notify the commits of different transactions concurrently, so the outputs committed ahead of
+/// the outputs of the transactions before them are held back, for the outputs to be streamed in
+/// the order of the block.
+///
+/// Only the outputs of the committed transactions are streamed, i.e. not the outputs of the
+/// transactions after the block gas limit is reached, or after a transaction aborting the block.
+/// When the execution restarts, a `CommittedOutputMsg::Restart` is streamed, after which the
+/// outputs are streamed again from the first transaction.
+pub struct StreamingTransactionCommitHook {
+    output_tx: Sender<CommittedOutputMsg>,
+    outputs: Mutex<OrderedOutputs>,
+}
+
+impl StreamingTransactionCommitHook {
+    pub fn new(output_tx: Sender<CommittedOutputMsg>) -> Self {
+        Self {
+            output_tx,
+            outputs: Mutex::new(OrderedOutputs {
+                next_txn_idx: 0,
+                pending: BTreeMap::new(),
+            }),
+        }
+    }
+
+    fn send(&self, msg: CommittedOutputMsg) {
+        // The receiver may stop listening, e.g. once it is not interested in the block anymore,
+        // which doesn't affect the execution.
+        let _ = self.output_tx.send(msg);
+    }
+}
+
+impl TransactionCommitHook for StreamingTransactionCommitHook {
+    type Output = AptosTransactionOutput;
+
+    fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &Self::Output) {
+        let mut outputs = self.outputs.lock();
+        if txn_idx < outputs.next_txn_idx {
+            return;
+        }
+        outputs
+            .pending
+            .insert(txn_idx, output.committed_output().clone());
+        // Sending while holding the lock keeps the outputs in order.
+        loop {
+            let next_txn_idx = outputs.next_txn_idx;
+            match outputs.pending.remove(&next_txn_idx) {
+                Some(output) => {
+                    self.send(CommittedOutputMsg::Output(next_txn_idx, output));
+                    outputs.next_txn_idx += 1;
+                },
+                None => break,
+            }
+        }
+    }
+
+    fn on_execution_aborted(&self, _txn_idx: TxnIndex) {
+        // The block fails, so there is no output to stream anymore.
+    }
+
+    fn on_execution_restarted(&self) {
+        let mut outputs = self.outputs.lock();
+        outputs.next_txn_idx = 0;
+        outputs.pending.clear();
+        self.send(CommittedOutputMsg::Restart);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosVM, VMExecutor};
+    use aptos_block_executor::task::TransactionOutput as BlockExecutorTransactionOutput;
+    use aptos_language_e2e_tests::{common_transactions::peer_to_peer_txn, executor::FakeExecutor};
+    use aptos_types::transaction::{ExecutionStatus, Transaction, TransactionStatus};
+    use aptos_vm_types::output::VMOutput;
+
+    fn committed_output() -> AptosTransactionOutput {
+        let output = AptosTransactionOutput::new(VMOutput::empty_with_status(
+            TransactionStatus::Keep(ExecutionStatus::Success),
+        ));
+        output.incorporate_delta_writes(vec![]);
+        output
+    }
+
+    fn streamed_txn_indices(
+        output_rx: &crossbeam_channel::Receiver<CommittedOutputMsg>,
+    ) -> Vec<TxnIndex> {
+        output_rx
+            .try_iter()
+            .map(|msg| match msg {
+                CommittedOutputMsg::Output(txn_idx, _) => txn_idx,
+                CommittedOutputMsg::Restart => panic!("Unexpected restart"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_outputs_are_streamed_in_order() {
+        let (output_tx, output_rx) = crossbeam_channel::unbounded();
+        let hook = StreamingTransactionCommitHook::new(output_tx);
+
+        hook.on_transaction_committed(2, &committed_output());
+        hook.on_transaction_committed(1, &committed_output());
+        assert!(streamed_txn_indices(&output_rx).is_empty());
+        hook.on_transaction_committed(0, &committed_output());
+        assert_eq!(streamed_txn_indices(&output_rx), vec![0, 1, 2]);
+        hook.on_transaction_committed(4, &committed_output());
+        hook.on_transaction_committed(3, &committed_output());
+        assert_eq!(streamed_txn_indices(&output_rx), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_outputs_are_streamed_again_after_restart() {
+        let (output_tx, output_rx) = crossbeam_channel::unbounded();
+        let hook = StreamingTransactionCommitHook::new(output_tx);
+
+        hook.on_transaction_committed(0, &committed_output());
+        hook.on_transaction_committed(2, &committed_output());
+        assert_eq!(streamed_txn_indices(&output_rx), vec![0]);
+
+        hook.on_execution_restarted();
+        assert!(matches!(
+            output_rx.try_recv(),
+            Ok(CommittedOutputMsg::Restart)
+        ));
+        // The output held back before the restart is void.
+        hook.on_transaction_committed(0, &committed_output());
+        hook.on_transaction_committed(1, &committed_output());
+        assert_eq!(streamed_txn_indices(&output_rx), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_execute_block_streaming() {
+        let mut executor = FakeExecutor::from_head_genesis();
+        let accounts = executor.create_accounts(10, 1_000_000, 0);
+        let transactions: Vec<_> = (0..accounts.len())
+            .map(|i| {
+                let receiver = &accounts[(i + 1) % accounts.len()];
+                Transaction::UserTransaction(peer_to_peer_txn(&accounts[i], receiver, 0, 1_000, 0))
+            })
+            .collect();
+
+        let (output_tx, output_rx) = crossbeam_channel::unbounded();
+        let returned_outputs =
+            AptosVM::execute_block_streaming(transactions, executor.data_store(), None, output_tx)
+                .unwrap();
+        let mut streamed_outputs = vec![];
+        for msg in output_rx.try_iter() {
+            match msg {
+                CommittedOutputMsg::Output(_, output) => streamed_outputs.push(output),
+                CommittedOutputMsg::Restart => streamed_outputs.clear(),
+            }
+        }
+        assert_eq!(streamed_outputs, returned_outputs);
+    }
+}
@@ -17,38 +17,97 @@ use move_core_types::{
     language_storage::{ModuleId, CORE_CODE_ADDRESS},
     vm_status::VMStatus,
 };
+use once_cell::unsync::OnceCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-pub(crate) struct AptosExecutorTask<'a, S> {
+/// Loading `0x1::account` and its transitive dependency into the code cache.
+///
+/// This should give us a warm VM to avoid the overhead of VM cold start.
+/// Result of this load could be omitted as this is a best effort approach and won't hurt if that fails.
+///
+/// Loading up `0x1::account` should be sufficient as this is the most common module
+/// used for prologue, epilogue and transfer functionality.
+fn warm_up(vm: &AptosVM, state_view: &impl StateView) {
+    let _ = vm.load_module(
+        &ModuleId::new(CORE_CODE_ADDRESS, ident_str!("account").to_owned()),
+        &vm.as_move_resolver(state_view),
+    );
+}
+
+/// A VM shared by all the workers of the parallel execution of a block, so that the modules used
+/// by the block are loaded (i.e. deserialized and verified) once, instead of once per worker.
+///
+/// The loader cache of the VM cannot be flushed while other workers execute transactions with it,
+/// and the modules published by a transaction must not be loaded into it before the transaction is
+/// committed. Hence, the VM refuses to publish modules, and is retired as soon as a transaction of
+/// the block tries to, before the modules are verified. The transaction is then executed again,
+/// and the workers execute the rest of the block, with VMs of their own.
+pub(crate) struct SharedBlockVM {
     vm: AptosVM,
+    retired: AtomicBool,
+}
+
+impl SharedBlockVM {
+    pub(crate) fn new(state_view: &impl StateView) -> Self {
+        let vm = AptosVM::new_shared(state_view);
+        warm_up(&vm, state_view);
+        Self {
+            vm,
+            retired: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Acquire)
+            || self.vm.0.publish_attempted()
+            || self.vm.0.is_loader_cache_invalidated()
+    }
+
+    /// Retires the VM once a transaction tried to publish modules with it, or its loader cache
+    /// got invalidated. Returns whether the VM is retired, in which case the transaction it just
+    /// executed may have been refused to publish modules, and must be executed again.
+    fn retire_if_modules_changed(&self) -> bool {
+        if self.vm.0.publish_attempted() || self.vm.0.is_loader_cache_invalidated() {
+            self.retired.store(true, Ordering::Release);
+        }
+        self.retired.load(Ordering::Acquire)
+    }
+}
+
+pub(crate) struct AptosExecutorTask<'a, S> {
+    shared_vm: Option<&'a SharedBlockVM>,
+    // When the VM is shared, created only once the shared VM is retired.
+    vm: OnceCell<AptosVM>,
     base_view: &'a S,
 }
 
+impl<'a, S: 'a + StateView + Sync> AptosExecutorTask<'a, S> {
+    fn own_vm(&self) -> &AptosVM {
+        self.vm.get_or_init(|| {
+            let vm = AptosVM::new(self.base_view);
+            warm_up(&vm, self.base_view);
+            vm
+        })
+    }
+}
+
 impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
-    type Argument = &'a S;
+    /// The base view, and the VM shared by the workers, if any.
+    type Argument = (&'a S, Option<&'a SharedBlockVM>);
     type Error = VMStatus;
     type Output = AptosTransactionOutput;
     type Txn = PreprocessedTransaction;
 
-    fn init(argument: &'a S) -> Self {
-        let vm = AptosVM::new(argument);
-
-        // Loading `0x1::account` and its transitive dependency into the code cache.
-        //
-        // This should give us a warm VM to avoid the overhead of VM cold start.
-        // Result of this load could be omitted as this is a best effort approach and won't hurt if that fails.
-        //
-        // Loading up `0x1::account` should be sufficient as this is the most common module
-        // used for prologue, epilogue and transfer functionality.
-
-        let _ = vm.load_module(
-            &ModuleId::new(CORE_CODE_ADDRESS, ident_str!("account").to_owned()),
-            &vm.as_move_resolver(argument),
-        );
-
-        Self {
-            vm,
-            base_view: argument,
+    fn init((base_view, shared_vm): Self::Argument) -> Self {
+        let task = Self {
+            shared_vm,
+            vm: OnceCell::new(),
+            base_view,
+        };
+        if shared_vm.is_none() {
+            task.own_vm();
         }
+        task
     }
 
     // This function is called by the BlockExecutor for each transaction is intends
@@ -62,11 +121,23 @@ impl<'a, S: 'a + StateView + Sync> ExecutorTask for AptosExecutorTask<'a, S> {
         materialize_deltas: bool,
     ) -> ExecutionStatus<AptosTransactionOutput, VMStatus> {
         let log_context = AdapterLogSchema::new(self.base_view.id(), txn_idx as usize);
-
-        match self
-            .vm
-            .execute_single_transaction(txn, &self.vm.as_move_resolver(view), &log_context)
-        {
+        let execute = |vm: &AptosVM| {
+            vm.execute_single_transaction(txn, &vm.as_move_resolver(view), &log_context)
+        };
+        let result = match self.shared_vm.filter(|shared_vm| !shared_vm.is_retired()) {
+            Some(shared_vm) => {
+                let result = execute(&shared_vm.vm);
+                // The VM may have been retired by the transaction of another worker, in which
+                // case executing the transaction again is merely redundant.
+                if shared_vm.retire_if_modules_changed() {
+                    execute(self.own_vm())
+                } else {
+                    result
+                }
+            },
+            None => execute(self.own_vm()),
+        };
+        match result {
             Ok((vm_status, mut vm_output, sender)) => {
                 if materialize_deltas {
                     // TODO: Integrate delta application failure.
@@ -4,7 +4,8 @@
 
 use aptos_metrics_core::{
     exponential_buckets, register_histogram, register_int_counter, register_int_counter_vec,
-    register_int_gauge, Histogram, IntCounter, IntCounterVec, IntGauge,
+    register_int_gauge, register_int_gauge_vec, Histogram, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -137,3 +138,36 @@ pub static TXN_GAS_USAGE: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Count the number of events emitted by committed transactions, with a "kind" label to
+/// distinguish module events from events emitted to an event handle.
+pub static EVENTS_EMITTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_vm_events_emitted",
+        "Number of events emitted by committed transactions",
+        &["kind"]
+    )
+    .unwrap()
+});
+
+/// Number of events emitted during the last completed window by each of the modules that
+/// emitted the most events. Only the top modules are exported to bound the cardinality.
+pub static TOP_EVENT_EMITTING_MODULES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_vm_top_event_emitting_modules",
+        "Number of events emitted in the last window by the modules emitting the most events",
+        &["module"]
+    )
+    .unwrap()
+});
+
+/// Number of events emitted during the last completed window for each of the event types
+/// that were emitted the most. Only the top types are exported to bound the cardinality.
+pub static TOP_EMITTED_EVENT_TYPES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_vm_top_emitted_event_types",
+        "Number of events emitted in the last window for the most emitted event types",
+        &["type"]
+    )
+    .unwrap()
+});
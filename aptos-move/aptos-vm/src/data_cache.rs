@@ -5,11 +5,13 @@
 
 use crate::{
     aptos_vm_impl::gas_config,
-    move_vm_ext::{get_max_binary_format_version, MoveResolverExt},
+    move_vm_ext::{
+        get_max_binary_format_version, module_metadata_cache::ModuleMetadataCache, MoveResolverExt,
+    },
 };
 #[allow(unused_imports)]
 use anyhow::Error;
-use aptos_framework::natives::state_storage::StateStorageUsageResolver;
+use aptos_framework::{natives::state_storage::StateStorageUsageResolver, RuntimeModuleMetadataV1};
 use aptos_state_view::StateView;
 use aptos_table_natives::{TableHandle, TableResolver};
 use aptos_types::{
@@ -25,7 +27,7 @@ use move_core_types::{
     resolver::{resource_size, ModuleResolver, ResourceResolver},
     vm_status::StatusCode,
 };
-use std::{cell::RefCell, collections::BTreeMap, ops::Deref};
+use std::{cell::RefCell, collections::BTreeMap, ops::Deref, sync::Arc};
 
 pub(crate) fn get_resource_group_from_metadata(
     struct_tag: &StructTag,
@@ -46,6 +48,7 @@ pub struct StorageAdapter<'a, S> {
     max_binary_format_version: u32,
     resource_group_cache:
         RefCell<BTreeMap<AccountAddress, BTreeMap<StructTag, BTreeMap<StructTag, Vec<u8>>>>>,
+    module_metadata_cache: ModuleMetadataCache,
 }
 
 impl<'a, S: StateView> StorageAdapter<'a, S> {
@@ -59,6 +62,7 @@ impl<'a, S: StateView> StorageAdapter<'a, S> {
             accurate_byte_count: false,
             max_binary_format_version: 0,
             resource_group_cache: RefCell::new(BTreeMap::new()),
+            module_metadata_cache: ModuleMetadataCache::default(),
         };
         if gas_feature_version >= 9 {
             s.accurate_byte_count = true;
@@ -73,6 +77,7 @@ impl<'a, S: StateView> StorageAdapter<'a, S> {
             accurate_byte_count: false,
             max_binary_format_version: 0,
             resource_group_cache: RefCell::new(BTreeMap::new()),
+            module_metadata_cache: ModuleMetadataCache::default(),
         };
         let (_, gas_feature_version) = gas_config(&s);
         let features = Features::fetch_config(&s).unwrap_or_default();
@@ -89,6 +94,21 @@ impl<'a, S: StateView> StorageAdapter<'a, S> {
             .map_err(|_| PartialVMError::new(StatusCode::STORAGE_ERROR))
     }
 
+    fn load_module_metadata(&self, module_id: &ModuleId) -> Vec<Metadata> {
+        let module_bytes = match self.get_module(module_id) {
+            Ok(Some(bytes)) => bytes,
+            _ => return vec![],
+        };
+        let module = match CompiledModule::deserialize_with_max_version(
+            &module_bytes,
+            self.max_binary_format_version,
+        ) {
+            Ok(module) => module,
+            _ => return vec![],
+        };
+        module.metadata
+    }
+
     fn get_any_resource(
         &self,
         address: &AccountAddress,
@@ -160,6 +180,19 @@ impl<'a, S: StateView> MoveResolverExt for StorageAdapter<'a, S> {
     ) -> BTreeMap<AccountAddress, BTreeMap<StructTag, BTreeMap<StructTag, Vec<u8>>>> {
         self.resource_group_cache.take()
     }
+
+    fn get_runtime_module_metadata(
+        &self,
+        module_id: &ModuleId,
+    ) -> Option<Arc<RuntimeModuleMetadataV1>> {
+        self.module_metadata_cache
+            .get_or_load(module_id, || self.load_module_metadata(module_id))
+            .runtime
+    }
+
+    fn invalidate_module_metadata(&self, module_ids: &[ModuleId]) {
+        self.module_metadata_cache.invalidate(module_ids);
+    }
 }
 
 impl<'a, S: StateView> ResourceResolver for StorageAdapter<'a, S> {
@@ -175,18 +208,10 @@ impl<'a, S: StateView> ResourceResolver for StorageAdapter<'a, S> {
 
 impl<'a, S: StateView> ModuleResolver for StorageAdapter<'a, S> {
     fn get_module_metadata(&self, module_id: &ModuleId) -> Vec<Metadata> {
-        let module_bytes = match self.get_module(module_id) {
-            Ok(Some(bytes)) => bytes,
-            _ => return vec![],
-        };
-        let module = match CompiledModule::deserialize_with_max_version(
-            &module_bytes,
-            self.max_binary_format_version,
-        ) {
-            Ok(module) => module,
-            _ => return vec![],
-        };
-        module.metadata
+        let metadata = self
+            .module_metadata_cache
+            .get_or_load(module_id, || self.load_module_metadata(module_id));
+        metadata.raw.as_ref().clone()
     }
 
     fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>, Error> {
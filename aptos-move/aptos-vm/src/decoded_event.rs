@@ -0,0 +1,74 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decoding of module events emitted by (simulated) transactions into JSON, so that clients
+//! can show what a transaction will emit before it is signed.
+//!
+//! Values are encoded the same way as GraphQL scalars are, see [`aptos_types::move_value_json`].
+
+use crate::move_vm_ext::SessionExt;
+use anyhow::{anyhow, Result};
+use aptos_types::contract_event::ContractEvent;
+pub use aptos_types::move_value_json::move_value_to_json;
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    value::{MoveTypeLayout, MoveValue},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// A module event emitted by a transaction, together with its type layout and payload
+/// decoded into JSON.
+#[derive(Clone, Debug, Serialize)]
+pub struct DecodedEvent {
+    /// The type of the event.
+    pub type_tag: TypeTag,
+    /// The fully annotated layout of the event type (with field names and struct tags).
+    pub layout: MoveTypeLayout,
+    /// The payload of the event, decoded into JSON.
+    pub data: Value,
+}
+
+impl DecodedEvent {
+    /// Returns the struct tag of the event, if the event is a struct (which is always the
+    /// case for events emitted by Move code).
+    pub fn struct_tag(&self) -> Option<&StructTag> {
+        match &self.type_tag {
+            TypeTag::Struct(struct_tag) => Some(struct_tag),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the given events using the type layouts resolved by the session. Returns the
+/// decoded events in the same order as the events, with None for events that can not be
+/// decoded (e.g. because their type is defined by a module published by the transaction).
+pub(crate) fn decode_events(
+    session: &SessionExt,
+    events: &[ContractEvent],
+) -> Vec<Option<DecodedEvent>> {
+    events
+        .iter()
+        .map(|event| decode_event(session, event).ok())
+        .collect()
+}
+
+fn decode_event(session: &SessionExt, event: &ContractEvent) -> Result<DecodedEvent> {
+    let type_tag = event.type_tag().clone();
+    let layout = session
+        .get_fully_annotated_type_layout(&type_tag)
+        .map_err(|err| anyhow!("Failed to resolve layout of {}: {:?}", type_tag, err))?;
+    let data = decode_event_as_json(event, &layout)?;
+    Ok(DecodedEvent {
+        type_tag,
+        layout,
+        data,
+    })
+}
+
+/// Decodes the payload of the event into JSON, given the (fully annotated) layout of its type,
+/// e.g. as resolved by an indexer from the modules on chain.
+pub fn decode_event_as_json(event: &ContractEvent, layout: &MoveTypeLayout) -> Result<Value> {
+    let value = MoveValue::simple_deserialize(event.event_data(), layout)?;
+    Ok(move_value_to_json(layout, value))
+}
@@ -0,0 +1,210 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Telemetry of the events emitted by committed transactions, so that operators can tell which
+//! modules are responsible for spikes in event volume (which put pressure on indexers).
+//!
+//! Events are counted per module and per type over fixed windows. At the end of each window,
+//! only the top emitters are exported as gauges, as exporting every module or type ever seen
+//! would blow up the cardinality of the metrics.
+
+use crate::counters::{EVENTS_EMITTED, TOP_EMITTED_EVENT_TYPES, TOP_EVENT_EMITTING_MODULES};
+use aptos_infallible::Mutex;
+use aptos_metrics_core::{IntCounter, IntGaugeVec};
+use aptos_types::contract_event::ContractEvent;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Length of the windows over which the events are counted.
+const WINDOW: Duration = Duration::from_secs(60);
+/// Number of modules and of types exported at the end of each window.
+const TOP_K: usize = 20;
+/// Maximum number of distinct types tracked within a window, events of any other type are
+/// counted under `OTHER`.
+const MAX_TRACKED_KEYS: usize = 10_000;
+/// Label of events whose type is not tracked (or is not a struct).
+const OTHER: &str = "other";
+
+static MODULE_EVENTS_EMITTED: Lazy<IntCounter> =
+    Lazy::new(|| EVENTS_EMITTED.with_label_values(&["module"]));
+static HANDLE_EVENTS_EMITTED: Lazy<IntCounter> =
+    Lazy::new(|| EVENTS_EMITTED.with_label_values(&["handle"]));
+
+static EVENT_RATE_TRACKER: Lazy<Mutex<EventRateTracker>> =
+    Lazy::new(|| Mutex::new(EventRateTracker::new(Instant::now())));
+
+/// Records the events of committed transactions. The events are first counted per type locally,
+/// so that the tracker is only locked once per call, and the labels are only built for the top
+/// emitters when they are exported.
+pub fn record_committed_events<'a>(events: impl IntoIterator<Item = &'a ContractEvent>) {
+    let mut counts_by_type: HashMap<Option<&StructTag>, u64> = HashMap::new();
+    let (mut num_module_events, mut num_handle_events) = (0, 0);
+    for event in events {
+        if event.is_v2() {
+            num_module_events += 1;
+        } else {
+            num_handle_events += 1;
+        }
+        let struct_tag = match event.type_tag() {
+            TypeTag::Struct(struct_tag) => Some(struct_tag.as_ref()),
+            _ => None,
+        };
+        *counts_by_type.entry(struct_tag).or_default() += 1;
+    }
+    if counts_by_type.is_empty() {
+        return;
+    }
+    MODULE_EVENTS_EMITTED.inc_by(num_module_events);
+    HANDLE_EVENTS_EMITTED.inc_by(num_handle_events);
+
+    let mut tracker = EVENT_RATE_TRACKER.lock();
+    for (struct_tag, count) in counts_by_type {
+        tracker.record(struct_tag, count);
+    }
+    tracker.maybe_publish(Instant::now());
+}
+
+struct EventRateTracker {
+    window_start: Instant,
+    by_type: HashMap<StructTag, u64>,
+    /// Number of events whose type is not tracked.
+    other: u64,
+}
+
+impl EventRateTracker {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            by_type: HashMap::new(),
+            other: 0,
+        }
+    }
+
+    fn record(&mut self, struct_tag: Option<&StructTag>, count: u64) {
+        match struct_tag {
+            Some(struct_tag) => match self.by_type.get_mut(struct_tag) {
+                Some(total) => *total += count,
+                None if self.by_type.len() < MAX_TRACKED_KEYS => {
+                    self.by_type.insert(struct_tag.clone(), count);
+                },
+                None => self.other += count,
+            },
+            None => self.other += count,
+        }
+    }
+
+    /// Exports the top emitters of the current window if it is over, and starts a new one.
+    fn maybe_publish(&mut self, now: Instant) {
+        if now.duration_since(self.window_start) < WINDOW {
+            return;
+        }
+        let (by_module, by_type) = self.counts_by_label();
+        publish(&TOP_EVENT_EMITTING_MODULES, top_k(&by_module));
+        publish(&TOP_EMITTED_EVENT_TYPES, top_k(&by_type));
+        *self = Self::new(now);
+    }
+
+    /// Returns the counts by module and by type (regardless of its type arguments) of the
+    /// window, keyed by their labels.
+    fn counts_by_label(&self) -> (HashMap<String, u64>, HashMap<String, u64>) {
+        let mut by_module: HashMap<_, u64> = HashMap::new();
+        let mut by_type: HashMap<_, u64> = HashMap::new();
+        for (struct_tag, count) in &self.by_type {
+            *by_module
+                .entry((&struct_tag.address, &struct_tag.module))
+                .or_default() += count;
+            *by_type
+                .entry((&struct_tag.address, &struct_tag.module, &struct_tag.name))
+                .or_default() += count;
+        }
+        let mut by_module: HashMap<_, _> = by_module
+            .into_iter()
+            .map(|((address, module), count)| {
+                let label = format!("{}::{}", address.short_str_lossless(), module);
+                (label, count)
+            })
+            .collect();
+        let mut by_type: HashMap<_, _> = by_type
+            .into_iter()
+            .map(|((address, module, name), count)| {
+                let label = format!("{}::{}::{}", address.short_str_lossless(), module, name);
+                (label, count)
+            })
+            .collect();
+        if self.other > 0 {
+            by_module.insert(OTHER.to_string(), self.other);
+            by_type.insert(OTHER.to_string(), self.other);
+        }
+        (by_module, by_type)
+    }
+}
+
+/// Returns the (at most) `TOP_K` keys with the highest counts, highest first.
+fn top_k(counts: &HashMap<String, u64>) -> Vec<(&str, u64)> {
+    let mut entries: Vec<_> = counts
+        .iter()
+        .map(|(key, count)| (key.as_str(), *count))
+        .collect();
+    entries.sort_unstable_by(|(key1, count1), (key2, count2)| {
+        count2.cmp(count1).then_with(|| key1.cmp(key2))
+    });
+    entries.truncate(TOP_K);
+    entries
+}
+
+fn publish(gauges: &IntGaugeVec, top: Vec<(&str, u64)>) {
+    // Drop the emitters of the previous window, which may not be at the top anymore.
+    gauges.reset();
+    for (key, count) in top {
+        gauges.with_label_values(&[key]).set(count as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+
+    #[test]
+    fn test_top_k() {
+        let mut counts = HashMap::new();
+        for i in 0..(TOP_K as u64 + 5) {
+            counts.insert(format!("0x1::m{}", i), i);
+        }
+        let top = top_k(&counts);
+        assert_eq!(top.len(), TOP_K);
+        assert_eq!(top[0], ("0x1::m24", 24));
+        assert_eq!(top[TOP_K - 1], ("0x1::m5", 5));
+    }
+
+    #[test]
+    fn test_untracked_types_are_counted_as_other() {
+        let struct_tag = |module: &str, name: &str| StructTag {
+            address: AccountAddress::ONE,
+            module: Identifier::new(module).unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params: vec![],
+        };
+        let mut tracker = EventRateTracker::new(Instant::now());
+        for i in 0..MAX_TRACKED_KEYS {
+            let struct_tag = struct_tag(&format!("m{}", i % 2), &format!("E{}", i));
+            tracker.record(Some(&struct_tag), 1);
+        }
+        tracker.record(Some(&struct_tag("m0", "E0")), 2);
+        tracker.record(Some(&struct_tag("m0", "New")), 1);
+        tracker.record(None, 1);
+        assert_eq!(tracker.by_type.len(), MAX_TRACKED_KEYS);
+        assert_eq!(tracker.other, 2);
+
+        let (by_module, by_type) = tracker.counts_by_label();
+        assert_eq!(by_module["0x1::m0"], MAX_TRACKED_KEYS as u64 / 2 + 2);
+        assert_eq!(by_module[OTHER], 2);
+        assert_eq!(by_type["0x1::m0::E0"], 3);
+        assert_eq!(by_type[OTHER], 2);
+        assert!(!by_type.contains_key("0x1::m0::New"));
+    }
+}
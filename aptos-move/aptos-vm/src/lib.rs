@@ -105,6 +105,7 @@ mod access_path_cache;
 #[macro_use]
 pub mod counters;
 pub mod data_cache;
+pub mod decoded_event;
 
 #[cfg(feature = "mirai-contracts")]
 pub mod foreign_contracts;
@@ -114,6 +115,7 @@ pub mod aptos_vm;
 mod aptos_vm_impl;
 pub mod block_executor;
 mod errors;
+pub mod event_metrics;
 pub mod move_vm_ext;
 pub mod natives;
 pub mod sharded_block_executor;
@@ -123,7 +125,11 @@ mod transaction_validation;
 mod verifier;
 
 pub use crate::aptos_vm::AptosVM;
-use crate::sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor};
+use crate::{
+    block_executor::streaming_commit_hook::CommittedOutputMsg,
+    sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor},
+};
+use aptos_mvhashmap::types::TxnIndex;
 use aptos_state_view::StateView;
 use aptos_types::{
     block_executor::partitioner::SubBlocksForShard,
@@ -133,6 +139,7 @@ use aptos_types::{
     },
     vm_status::VMStatus,
 };
+use crossbeam_channel::Sender;
 use std::{marker::Sync, sync::Arc};
 pub use verifier::view_function::determine_is_view;
 
@@ -160,6 +167,34 @@ pub trait VMExecutor: Send + Sync {
         maybe_block_gas_limit: Option<u64>,
     ) -> Result<Vec<TransactionOutput>, VMStatus>;
 
+    /// Same as `execute_block`, but also streams the outputs of the committed transactions to
+    /// `output_tx`, in the order of the block. By default, the outputs are streamed once the whole
+    /// block is executed, while VMs committing the transactions incrementally stream each output
+    /// as soon as the transaction is committed.
+    ///
+    /// If the execution restarts, `CommittedOutputMsg::Restart` voids the outputs streamed so far,
+    /// which are streamed again. The receiver must thus be able to drop what it did with them, and
+    /// only rely on the streamed outputs once this returns. The executor hashes the streamed
+    /// outputs ahead of the ledger update this way, see `ChunkOutput`.
+    fn execute_block_streaming(
+        transactions: Vec<Transaction>,
+        state_view: &(impl StateView + Sync),
+        maybe_block_gas_limit: Option<u64>,
+        output_tx: Sender<CommittedOutputMsg>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let outputs = Self::execute_block(transactions, state_view, maybe_block_gas_limit)?;
+        for (txn_idx, output) in outputs
+            .iter()
+            .take_while(|output| !output.status().is_retry())
+            .enumerate()
+        {
+            let msg = CommittedOutputMsg::Output(txn_idx as TxnIndex, output.clone());
+            // The receiver may stop listening, which doesn't affect the execution.
+            let _ = output_tx.send(msg);
+        }
+        Ok(outputs)
+    }
+
     /// Executes a block of transactions using a sharded block executor and returns the results.
     fn execute_block_sharded<S: StateView + Sync + Send + 'static, E: ExecutorClient<S>>(
         sharded_block_executor: &ShardedBlockExecutor<S, E>,
@@ -3,6 +3,7 @@
 
 //! MoveVM and Session wrapped, to make sure Aptos natives and extensions are always installed and
 //! taken care of after session finish.
+pub(crate) mod module_metadata_cache;
 mod resolver;
 mod respawned_session;
 mod session;
@@ -0,0 +1,53 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_framework::RuntimeModuleMetadataV1;
+use move_core_types::{language_storage::ModuleId, metadata::Metadata};
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+/// The metadata of a module, as stored and as parsed.
+#[derive(Clone)]
+pub(crate) struct CachedModuleMetadata {
+    pub(crate) raw: Arc<Vec<Metadata>>,
+    pub(crate) runtime: Option<Arc<RuntimeModuleMetadataV1>>,
+}
+
+/// Caches the metadata of the modules read through a resolver, so that the different consumers
+/// of the metadata (resource group resolution, natives, ...) don't each deserialize the same
+/// module and parse the same metadata blob.
+///
+/// Entries must be invalidated when the corresponding modules are published.
+#[derive(Default)]
+pub(crate) struct ModuleMetadataCache {
+    entries: RefCell<HashMap<ModuleId, CachedModuleMetadata>>,
+}
+
+impl ModuleMetadataCache {
+    /// Returns the cached metadata of the module, loading it with `load` on a miss.
+    pub(crate) fn get_or_load(
+        &self,
+        module_id: &ModuleId,
+        load: impl FnOnce() -> Vec<Metadata>,
+    ) -> CachedModuleMetadata {
+        if let Some(entry) = self.entries.borrow().get(module_id) {
+            return entry.clone();
+        }
+
+        let raw = load();
+        let entry = CachedModuleMetadata {
+            runtime: aptos_framework::get_metadata(&raw).map(Arc::new),
+            raw: Arc::new(raw),
+        };
+        self.entries
+            .borrow_mut()
+            .insert(module_id.clone(), entry.clone());
+        entry
+    }
+
+    pub(crate) fn invalidate(&self, module_ids: &[ModuleId]) {
+        let mut entries = self.entries.borrow_mut();
+        for module_id in module_ids {
+            entries.remove(module_id);
+        }
+    }
+}
@@ -1,16 +1,18 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_framework::natives::state_storage::StateStorageUsageResolver;
+use aptos_framework::{natives::state_storage::StateStorageUsageResolver, RuntimeModuleMetadataV1};
 use aptos_state_view::StateView;
 use aptos_table_natives::TableResolver;
 use aptos_types::on_chain_config::ConfigStorage;
 use aptos_utils::aptos_try;
 use move_binary_format::errors::VMResult;
 use move_core_types::{
-    account_address::AccountAddress, language_storage::StructTag, resolver::MoveResolver,
+    account_address::AccountAddress,
+    language_storage::{ModuleId, StructTag},
+    resolver::MoveResolver,
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 
 pub trait MoveResolverExt:
     MoveResolver + TableResolver + StateStorageUsageResolver + ConfigStorage + StateView
@@ -31,11 +33,22 @@ pub trait MoveResolverExt:
         &self,
     ) -> BTreeMap<AccountAddress, BTreeMap<StructTag, BTreeMap<StructTag, Vec<u8>>>>;
 
+    /// Returns the Aptos metadata of a module, parsed. Resolvers that cache the metadata of the
+    /// modules override this, so that it is only parsed once.
+    fn get_runtime_module_metadata(
+        &self,
+        module_id: &ModuleId,
+    ) -> Option<Arc<RuntimeModuleMetadataV1>> {
+        aptos_framework::get_metadata(&self.get_module_metadata(module_id)).map(Arc::new)
+    }
+
+    /// Drops the cached metadata of the given modules, e.g. because they are being republished.
+    fn invalidate_module_metadata(&self, _module_ids: &[ModuleId]) {}
+
     // Move to API does not belong here
     fn is_resource_group(&self, struct_tag: &StructTag) -> bool {
         aptos_try!({
-            let md =
-                aptos_framework::get_metadata(&self.get_module_metadata(&struct_tag.module_id()))?;
+            let md = self.get_runtime_module_metadata(&struct_tag.module_id())?;
             md.struct_attributes
                 .get(struct_tag.name.as_ident_str().as_str())?
                 .iter()
@@ -11,6 +11,7 @@ use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use aptos_framework::natives::{
     aggregator_natives::{AggregatorChange, AggregatorChangeSet, NativeAggregatorContext},
     code::{NativeCodeContext, PublishRequest},
+    event::NativeEventContext,
 };
 use aptos_table_natives::{NativeTableContext, TableChangeSet};
 use aptos_types::{
@@ -25,9 +26,7 @@ use aptos_vm_types::{change_set::VMChangeSet, storage::ChangeSetConfigs};
 use move_binary_format::errors::{Location, PartialVMError, VMResult};
 use move_core_types::{
     account_address::AccountAddress,
-    effects::{
-        AccountChangeSet, ChangeSet as MoveChangeSet, Event as MoveEvent, Op as MoveStorageOp,
-    },
+    effects::{AccountChangeSet, ChangeSet as MoveChangeSet, Op as MoveStorageOp},
     language_storage::{ModuleId, StructTag},
     vm_status::{err_msg, StatusCode, VMStatus},
 };
@@ -162,7 +161,17 @@ impl<'r, 'l> SessionExt<'r, 'l> {
         configs: &ChangeSetConfigs,
     ) -> VMResult<VMChangeSet> {
         let move_vm = self.inner.get_move_vm();
-        let (change_set, events, mut extensions) = self.inner.finish_with_extensions()?;
+        // Events are collected by the native event context rather than by the Move data cache,
+        // so that events emitted to a handle and module events keep their relative order.
+        let (change_set, data_cache_events, mut extensions) =
+            self.inner.finish_with_extensions()?;
+        if !data_cache_events.is_empty() {
+            return Err(
+                PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+                    .with_message("events must be emitted through the native event context".into())
+                    .finish(Location::Undefined),
+            );
+        }
 
         let (change_set, resource_group_change_set) =
             Self::split_and_merge_resource_groups(move_vm, self.remote, change_set)?;
@@ -176,6 +185,9 @@ impl<'r, 'l> SessionExt<'r, 'l> {
         let aggregator_context: NativeAggregatorContext = extensions.remove();
         let aggregator_change_set = aggregator_context.into_change_set();
 
+        let event_context: NativeEventContext = extensions.remove();
+        let events = event_context.into_events();
+
         let change_set = Self::convert_change_set(
             self.remote,
             self.new_slot_payer,
@@ -194,6 +206,12 @@ impl<'r, 'l> SessionExt<'r, 'l> {
         Ok(change_set)
     }
 
+    /// Drops the metadata of the given modules cached by the resolver, so that the metadata of the
+    /// new versions is read once they are published.
+    pub fn invalidate_module_metadata(&self, module_ids: &[ModuleId]) {
+        self.remote.invalidate_module_metadata(module_ids);
+    }
+
     pub fn extract_publish_request(&mut self) -> Option<PublishRequest> {
         let ctx = self.get_native_extensions().get_mut::<NativeCodeContext>();
         ctx.requested_module_bundle.take()
@@ -311,7 +329,7 @@ impl<'r, 'l> SessionExt<'r, 'l> {
         current_time: Option<&CurrentTimeMicroseconds>,
         change_set: MoveChangeSet,
         resource_group_change_set: MoveChangeSet,
-        events: Vec<MoveEvent>,
+        events: Vec<ContractEvent>,
         table_change_set: TableChangeSet,
         aggregator_change_set: AggregatorChangeSet,
         ap_cache: &mut C,
@@ -394,14 +412,6 @@ impl<'r, 'l> SessionExt<'r, 'l> {
             }
         }
 
-        let events = events
-            .into_iter()
-            .map(|(guid, seq_num, ty_tag, blob)| {
-                let key = bcs::from_bytes(guid.as_slice())
-                    .map_err(|_| VMStatus::error(StatusCode::EVENT_KEY_MISMATCH, None))?;
-                Ok(ContractEvent::new(key, seq_num, ty_tag, blob))
-            })
-            .collect::<Result<Vec<_>, VMStatus>>()?;
         VMChangeSet::new(
             resource_write_set,
             module_write_set,
@@ -7,8 +7,11 @@ use crate::{
 };
 use aptos_framework::natives::{
     aggregator_natives::NativeAggregatorContext,
+    block_seed::NativeBlockSeedContext,
     code::NativeCodeContext,
+    counter::NativeCounterContext,
     cryptography::{algebra::AlgebraContext, ristretto255_point::NativeRistrettoPointContext},
+    event::{EventFilter, NativeEventContext, NativeEventLimits},
     state_storage::NativeStateStorageContext,
     transaction_context::NativeTransactionContext,
 };
@@ -26,6 +29,9 @@ pub struct MoveVmExt {
     inner: MoveVM,
     chain_id: u8,
     features: Arc<Features>,
+    event_limits: NativeEventLimits,
+    event_filter: Option<Arc<dyn EventFilter>>,
+    flush_invalidated_loader_cache: bool,
 }
 
 pub fn get_max_binary_format_version(features: &Features, gas_feature_version: u64) -> u32 {
@@ -90,9 +96,34 @@ impl MoveVmExt {
             )?,
             chain_id,
             features: Arc::new(features),
+            event_limits: NativeEventLimits::unlimited(),
+            event_filter: None,
+            flush_invalidated_loader_cache: true,
         })
     }
 
+    /// Sets the limits on the events emitted during the sessions of the VM.
+    pub fn with_event_limits(mut self, event_limits: NativeEventLimits) -> Self {
+        self.event_limits = event_limits;
+        self
+    }
+
+    /// Sets the filter applied to the events emitted during the sessions of the VM, before they
+    /// are returned in their change sets.
+    pub fn with_event_filter(mut self, event_filter: Arc<dyn EventFilter>) -> Self {
+        self.event_filter = Some(event_filter);
+        self
+    }
+
+    /// Keeps the loader cache when it is invalidated, instead of flushing it when the next session
+    /// is created. Flushing is only sound if no other session of the VM is alive, which does not
+    /// hold for a VM used by several threads at once: the owner of such a VM must stop using it
+    /// once its loader cache is invalidated instead.
+    pub fn without_loader_cache_flush(mut self) -> Self {
+        self.flush_invalidated_loader_cache = false;
+        self
+    }
+
     pub fn new_session<'r, S: MoveResolverExt>(
         &self,
         remote: &'r S,
@@ -109,6 +140,14 @@ impl MoveVmExt {
         extensions.add(NativeRistrettoPointContext::new());
         extensions.add(AlgebraContext::new());
         extensions.add(NativeAggregatorContext::new(txn_hash, remote));
+        extensions.add(NativeCounterContext::new(txn_hash));
+
+        // The seed of a block is derived from its id, which is only known to the session executing
+        // its block metadata transaction.
+        extensions.add(match &session_id {
+            SessionId::BlockMeta { id } => NativeBlockSeedContext::for_block(*id),
+            _ => NativeBlockSeedContext::new(),
+        });
 
         let sender_opt = session_id.sender();
         let script_hash = match session_id {
@@ -137,10 +176,17 @@ impl MoveVmExt {
         ));
         extensions.add(NativeCodeContext::default());
         extensions.add(NativeStateStorageContext::new(remote));
+        let event_context = NativeEventContext::new(self.event_limits);
+        extensions.add(match &self.event_filter {
+            Some(event_filter) => event_context.with_filter(event_filter.clone()),
+            None => event_context,
+        });
 
         // The VM code loader has bugs around module upgrade. After a module upgrade, the internal
         // cache needs to be flushed to work around those bugs.
-        self.inner.flush_loader_cache_if_invalidated();
+        if self.flush_invalidated_loader_cache {
+            self.inner.flush_loader_cache_if_invalidated();
+        }
 
         SessionExt::new(
             self.inner.new_session_with_extensions(remote, extensions),
@@ -15,9 +15,11 @@ use aptos_types::{
 use move_vm_runtime::native_functions::NativeFunctionTable;
 #[cfg(feature = "testing")]
 use {
+    aptos_crypto::HashValue,
     aptos_framework::natives::{
-        aggregator_natives::NativeAggregatorContext, code::NativeCodeContext,
-        cryptography::ristretto255_point::NativeRistrettoPointContext,
+        aggregator_natives::NativeAggregatorContext, block_seed::NativeBlockSeedContext,
+        code::NativeCodeContext, counter::NativeCounterContext,
+        cryptography::ristretto255_point::NativeRistrettoPointContext, event::NativeEventContext,
         transaction_context::NativeTransactionContext,
     },
     move_vm_runtime::native_extensions::NativeContextExtensions,
@@ -83,7 +85,10 @@ pub fn assert_no_test_natives(err_msg: &str) {
                 || module_name.as_str() == "bls12381"
                     && func_name.as_str() == "generate_proof_of_possession_internal"
                 || module_name.as_str() == "event"
-                    && func_name.as_str() == "emitted_events_internal")
+                    && func_name.as_str() == "emitted_events_internal"
+                || module_name.as_str() == "event"
+                    && func_name.as_str() == "emitted_module_events_internal"
+                || module_name.as_str() == "debug" && func_name.as_str() == "native_print_value")
         }),
         "{}",
         err_msg
@@ -107,6 +112,9 @@ fn unit_test_extensions_hook(exts: &mut NativeContextExtensions) {
         ChainId::test().id(),
     )); // We use the testing environment chain ID here
     exts.add(NativeAggregatorContext::new([0; 32], &*DUMMY_RESOLVER));
+    exts.add(NativeCounterContext::new([0; 32]));
     exts.add(NativeRistrettoPointContext::new());
     exts.add(AlgebraContext::new());
+    exts.add(NativeEventContext::for_unit_tests());
+    exts.add(NativeBlockSeedContext::for_block(HashValue::zero()));
 }
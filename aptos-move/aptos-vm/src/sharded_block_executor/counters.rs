@@ -2,7 +2,10 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_metrics_core::{register_histogram_vec, register_int_gauge, HistogramVec, IntGauge};
+use aptos_metrics_core::{
+    exponential_buckets, register_histogram_vec, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+};
 use once_cell::sync::Lazy;
 
 pub static NUM_EXECUTOR_SHARDS: Lazy<IntGauge> = Lazy::new(|| {
@@ -21,3 +24,94 @@ pub static SHARDED_BLOCK_EXECUTION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static SHARD_BLOCK_EXECUTION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "sharded_executor_shard_block_execution_seconds",
+        "Time for a shard to execute all its sub blocks of a block in seconds",
+        &["shard_id"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "sharded_executor_cross_shard_messages",
+        "Number of cross shard messages with the writes of committed transactions, sent or received by a shard",
+        &["shard_id", "direction"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_SEND_RETRIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "sharded_executor_cross_shard_send_retries",
+        "Number of failed sends of cross shard messages by a shard, either retried or given up on",
+        &["shard_id", "result"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_DUPLICATE_WRITES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "sharded_executor_cross_shard_duplicate_writes",
+        "Number of cross shard writes received by a shard more than once, and dropped",
+        &["shard_id"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_QUEUE_DEPTH: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "sharded_executor_cross_shard_queue_depth",
+        "Number of cross shard messages queued for a shard and round, as of the latest send or receive",
+        &["shard_id", "round_id"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_BATCH_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "sharded_executor_cross_shard_batch_size",
+        "Number of writes batched into a cross shard message sent by a shard",
+        &["shard_id"],
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 14).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_EDGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "sharded_executor_cross_shard_edges",
+        "Number of writes of committed transactions sent by a shard to another one, i.e. the cross shard edge matrix",
+        &["source_shard_id", "dependent_shard_id"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_HOT_KEYS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "sharded_executor_cross_shard_hot_keys",
+        "Number of writes sent to the dependent shards for the hottest keys of the latest sub block of a shard and round, by rank",
+        &["shard_id", "round_id", "rank", "state_key"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_PREFETCHED_READS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "sharded_executor_cross_shard_prefetched_reads",
+        "Number of reads of the base state by a shard, by whether the value was prefetched",
+        &["shard_id", "result"]
+    )
+    .unwrap()
+});
+
+pub static CROSS_SHARD_SPECULATIVE_READS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "sharded_executor_cross_shard_speculative_reads",
+        "Number of cross shard keys read speculatively from the base state by a shard, by whether the remote write matched",
+        &["shard_id", "result"]
+    )
+    .unwrap()
+});
@@ -3,51 +3,304 @@
 use crate::{
     block_executor::AptosTransactionOutput,
     sharded_block_executor::{
+        counters::{
+            CROSS_SHARD_BATCH_SIZE, CROSS_SHARD_DUPLICATE_WRITES, CROSS_SHARD_MESSAGES,
+            CROSS_SHARD_SEND_RETRIES,
+        },
         cross_shard_state_view::CrossShardStateView,
-        messages::{CrossShardMsg, CrossShardMsg::RemoteTxnWriteMsg, RemoteTxnWrite},
+        cross_shard_stats,
+        messages::{
+            CrossShardMsg,
+            CrossShardMsg::{CompressedRemoteTxnWriteMsg, RemoteEventMsg, RemoteTxnWriteMsg},
+            CrossShardMsgPriority, RemoteTxnEvents, RemoteTxnWrite, RemoteWriteOp,
+        },
     },
 };
+use anyhow::Result;
 use aptos_block_executor::txn_commit_hook::TransactionCommitHook;
-use aptos_logger::trace;
+use aptos_infallible::Mutex;
+use aptos_logger::{error, trace, warn};
 use aptos_mvhashmap::types::TxnIndex;
-use aptos_state_view::StateView;
+use aptos_state_view::{StateView, TStateView};
 use aptos_types::{
     block_executor::partitioner::{RoundId, ShardId, SubBlock},
-    state_store::state_key::StateKey,
-    transaction::analyzed_transaction::AnalyzedTransaction,
+    state_store::{
+        state_key::{StateKey, StateKeyInner},
+        state_value::StateValue,
+    },
+    transaction::analyzed_transaction::{AnalyzedTransaction, StorageLocation},
     write_set::TransactionWrite,
 };
+use crossbeam_channel::RecvTimeoutError;
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
     sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 
+/// How long a cross shard commit receiver waits for a message while some of its cross shard values
+/// are still pending, before giving up on their remote shards.
+pub const DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of attempts at sending a cross shard message before giving up on it, in which case the
+/// dependent shard times out on the values it waits on.
+pub const MAX_CROSS_SHARD_SEND_ATTEMPTS: usize = 5;
+
+/// Delay before the first retry of a failed send, doubled for each further retry.
+const CROSS_SHARD_SEND_RETRY_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Sends a cross shard message with at-least-once semantics: the send is retried (with exponential
+/// backoff) until it succeeds or `MAX_CROSS_SHARD_SEND_ATTEMPTS` are made. As a send may fail after
+/// the message is delivered, the message may be delivered more than once, which the receivers
+/// tolerate by dropping the writes they already applied.
+pub fn send_cross_shard_msg_with_retry(
+    cross_shard_client: &dyn CrossShardClient,
+    shard_id: ShardId,
+    (dependent_shard_id, round_id): (ShardId, RoundId),
+    msg: CrossShardMsg,
+) -> Result<()> {
+    let mut backoff = CROSS_SHARD_SEND_RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match cross_shard_client.send_cross_shard_msg(dependent_shard_id, round_id, msg.clone()) {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt < MAX_CROSS_SHARD_SEND_ATTEMPTS => {
+                warn!(
+                    "Failed to send a cross shard message to shard {} for round {} (attempt {}): {:?}",
+                    dependent_shard_id, round_id, attempt, err
+                );
+                CROSS_SHARD_SEND_RETRIES
+                    .with_label_values(&[&shard_id.to_string(), "retried"])
+                    .inc();
+                thread::sleep(backoff);
+                backoff *= 2;
+            },
+            Err(err) => {
+                CROSS_SHARD_SEND_RETRIES
+                    .with_label_values(&[&shard_id.to_string(), "given_up"])
+                    .inc();
+                return Err(err);
+            },
+        }
+    }
+}
+
+/// Whether the key holds the code of a module.
+fn is_module(state_key: &StateKey) -> bool {
+    matches!(state_key.inner(), StateKeyInner::AccessPath(access_path) if access_path.is_code())
+}
+
+/// A received message, ordered by priority, then by arrival.
+struct PrioritizedMsg {
+    priority: CrossShardMsgPriority,
+    arrival: Reverse<usize>,
+    msg: CrossShardMsg,
+}
+
+impl PartialEq for PrioritizedMsg {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PrioritizedMsg {}
+
+impl PartialOrd for PrioritizedMsg {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PrioritizedMsg {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.arrival).cmp(&(other.priority, other.arrival))
+    }
+}
+
 pub struct CrossShardCommitReceiver {}
 
 impl CrossShardCommitReceiver {
+    /// Applies the writes received for the round to the cross shard state view, until the stop
+    /// message of the round. If no message is received for `receive_timeout` while values are
+    /// still pending, the remote shards are gone, or the coordinator aborts the block, the pending
+    /// values are marked as failed, so that their readers fail (and the block is aborted) rather
+    /// than hang. Writes delivered more than once (as per their sequence numbers) are only applied
+    /// once. Of the messages already received, the ones with the highest priority are processed
+    /// first.
     pub fn start<S: StateView + Sync + Send>(
+        shard_id: ShardId,
         cross_shard_state_view: Arc<CrossShardStateView<S>>,
         cross_shard_client: Arc<dyn CrossShardClient>,
         round: RoundId,
+        receive_timeout: Duration,
     ) {
+        let received_messages =
+            CROSS_SHARD_MESSAGES.with_label_values(&[&shard_id.to_string(), "received"]);
+        let duplicate_writes =
+            CROSS_SHARD_DUPLICATE_WRITES.with_label_values(&[&shard_id.to_string()]);
+        // The writes applied so far, by sequence number and key.
+        let mut applied_writes = HashSet::new();
+        let mut received_msgs = BinaryHeap::new();
+        let mut num_received = 0;
+        let mut receive = |msg: CrossShardMsg, received_msgs: &mut BinaryHeap<PrioritizedMsg>| {
+            received_msgs.push(PrioritizedMsg {
+                priority: msg.priority(),
+                arrival: Reverse(num_received),
+                msg,
+            });
+            num_received += 1;
+        };
         loop {
-            let msg = cross_shard_client.receive_cross_shard_msg(round);
+            if received_msgs.is_empty() {
+                match cross_shard_client.receive_cross_shard_msg(round, receive_timeout) {
+                    Ok(msg) => receive(msg, &mut received_msgs),
+                    Err(RecvTimeoutError::Timeout) => {
+                        let num_failed = cross_shard_state_view.fail_waiting_values();
+                        if num_failed > 0 {
+                            error!(
+                                "No cross shard message received for {:?}, giving up on {} values",
+                                receive_timeout, num_failed
+                            );
+                        }
+                        // Keep draining the messages until the stop message, for the remote
+                        // shards not to block on sending them.
+                        continue;
+                    },
+                    Err(RecvTimeoutError::Disconnected) => {
+                        let num_failed = cross_shard_state_view.fail_waiting_values();
+                        warn!(
+                            "Cross shard senders disconnected, giving up on {} values",
+                            num_failed
+                        );
+                        break;
+                    },
+                }
+            }
+            // The messages already queued are received as well, for the one with the highest
+            // priority to be processed first.
+            while let Ok(msg) = cross_shard_client.receive_cross_shard_msg(round, Duration::ZERO) {
+                receive(msg, &mut received_msgs);
+            }
+            let msg = received_msgs.pop().expect("A message must be received").msg;
             match msg {
-                RemoteTxnWriteMsg(txn_commit_msg) => {
-                    let (state_key, write_op) = txn_commit_msg.take();
-                    cross_shard_state_view
-                        .set_value(&state_key, write_op.and_then(|w| w.as_state_value()));
+                RemoteTxnWriteMsg(txn_writes, _) => {
+                    received_messages.inc();
+                    let num_duplicates = Self::apply_txn_writes(
+                        &cross_shard_state_view,
+                        txn_writes,
+                        &mut applied_writes,
+                    );
+                    duplicate_writes.inc_by(num_duplicates);
+                },
+                CompressedRemoteTxnWriteMsg(compressed_txn_writes, _) => {
+                    received_messages.inc();
+                    let num_duplicates = Self::apply_txn_writes(
+                        &cross_shard_state_view,
+                        CrossShardMsg::decompress_txn_writes(&compressed_txn_writes),
+                        &mut applied_writes,
+                    );
+                    duplicate_writes.inc_by(num_duplicates);
                 },
                 CrossShardMsg::StopMsg => {
-                    trace!("Cross shard commit receiver stopped for round {}", round);
+                    trace!("Cross shard commit receiver stopped");
                     break;
                 },
+                CrossShardMsg::AbortMsg => {
+                    let num_failed = cross_shard_state_view.fail_waiting_values();
+                    warn!(
+                        "Block aborted, giving up on {} cross shard values",
+                        num_failed
+                    );
+                    // Keep draining the messages until the stop message, as above.
+                },
+                RemoteEventMsg(_) => {
+                    unreachable!("Events are only sent to the coordinator")
+                },
+            }
+        }
+    }
+
+    /// Applies the writes not applied yet, returning the number of duplicates dropped.
+    fn apply_txn_writes<S: StateView + Sync + Send>(
+        cross_shard_state_view: &CrossShardStateView<S>,
+        txn_writes: Vec<RemoteTxnWrite>,
+        applied_writes: &mut HashSet<(TxnIndex, StateKey)>,
+    ) -> u64 {
+        let mut num_duplicates = 0;
+        for txn_write in txn_writes {
+            let seq_num = txn_write.seq_num();
+            let (state_key, write_op) = txn_write.take();
+            if !applied_writes.insert((seq_num, state_key.clone())) {
+                num_duplicates += 1;
+                continue;
+            }
+            if is_module(&state_key) {
+                if let Some(RemoteWriteOp::Write(write_op)) = write_op {
+                    cross_shard_state_view
+                        .set_published_module(state_key, write_op.as_state_value());
+                }
+                continue;
+            }
+            match write_op {
+                Some(RemoteWriteOp::Write(write_op)) => {
+                    cross_shard_state_view.set_value(&state_key, write_op.as_state_value())
+                },
+                Some(RemoteWriteOp::Delta(delta_op)) => cross_shard_state_view
+                    .set_delta(&state_key, delta_op)
+                    .expect("Failed to apply the delta of a cross shard aggregator"),
+                Some(RemoteWriteOp::Value(state_value)) => {
+                    cross_shard_state_view.set_value(&state_key, state_value)
+                },
+                // The remote shard could not determine the value (and aborts the block), so the
+                // value before the block is used for the shard not to wait on it.
+                None => cross_shard_state_view
+                    .set_base_value(&state_key)
+                    .expect("Failed to read the base value of a cross shard key"),
             }
         }
+        num_duplicates
+    }
+}
+
+/// How the writes sent to the dependent shards are batched.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CrossShardBatchConfig {
+    /// How long the writes to a dependent shard and round are held back, for the writes of later
+    /// transactions to be sent along in the same message. Without any window, the writes of a
+    /// transaction are sent as soon as it is committed.
+    pub window: Duration,
+    /// Number of writes beyond which a batch is sent before the end of its window.
+    pub max_batch_size: usize,
+    /// Whether the batches are compressed with LZ4, trading CPU for the bandwidth to the remote
+    /// shards.
+    pub compress: bool,
+    /// Whether the aggregator deltas are sent rather than their materialized values, when they
+    /// apply to the value before the block (as per the write hints of the sub block).
+    pub send_deltas: bool,
+}
+
+impl Default for CrossShardBatchConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::ZERO,
+            max_batch_size: 1024,
+            compress: false,
+            send_deltas: false,
+        }
     }
 }
 
-pub struct CrossShardCommitSender {
+struct PendingBatch {
+    txn_writes: Vec<RemoteTxnWrite>,
+    // The highest priority of the writes of the batch.
+    priority: CrossShardMsgPriority,
+    start_time: Instant,
+}
+
+pub struct CrossShardCommitSender<'a> {
     shard_id: ShardId,
     cross_shard_client: Arc<dyn CrossShardClient>,
     // The hashmap of source txn index to hashmap of conflicting storage location to the
@@ -55,19 +308,58 @@ pub struct CrossShardCommitSender {
     // global indices, so we need to convert the local index received from the parallel execution to
     // the global index.
     dependent_edges: HashMap<TxnIndex, HashMap<StateKey, HashSet<(ShardId, RoundId)>>>,
+    // The priority of the writes of each transaction with dependent edges, i.e. the length of the
+    // longest chain of cross shard dependencies starting at it.
+    priorities: HashMap<TxnIndex, CrossShardMsgPriority>,
+    // The storage locations of the dependent edges whose value before the transaction is the one
+    // before the block, so that the deltas of the transaction to them can be sent as is.
+    base_value_edges: HashMap<TxnIndex, HashSet<StateKey>>,
+    // The view of the shard before the sub block (i.e. storage, and the values received from the
+    // other shards), and the latest values written to the storage locations of the dependent edges
+    // by the transactions of the sub block committed so far. The value of a storage location the
+    // transaction did not write is the latest one before it, as it would be in an unsharded block.
+    state_view: &'a (dyn StateView + Sync),
+    edge_keys: HashSet<StateKey>,
+    sub_block_values: Mutex<HashMap<StateKey, Option<StateValue>>>,
     // The offset of the first transaction in the sub-block. This is used to convert the local index
     // in parallel execution to the global index.
     index_offset: TxnIndex,
+    // Whether the events of the committed transactions are streamed to the coordinator.
+    stream_events: bool,
+    batch_config: CrossShardBatchConfig,
+    // The writes held back per dependent shard and round, until their window elapses.
+    pending_batches: Mutex<HashMap<(ShardId, RoundId), PendingBatch>>,
+    // Whether all the writes and events are held back until the sender is dropped, for the sub
+    // block to be re-executed without them being sent (e.g. if it read cross shard values
+    // speculatively).
+    hold_back: bool,
+    held_back_events: Mutex<Vec<RemoteTxnEvents>>,
 }
 
-impl CrossShardCommitSender {
+impl<'a> CrossShardCommitSender<'a> {
     pub fn new(
         shard_id: ShardId,
+        round: RoundId,
         cross_shard_client: Arc<dyn CrossShardClient>,
         sub_block: &SubBlock<AnalyzedTransaction>,
+        state_view: &'a (dyn StateView + Sync),
+        batch_config: CrossShardBatchConfig,
     ) -> Self {
         let mut dependent_edges = HashMap::new();
+        let mut priorities = HashMap::new();
         let mut num_dependent_edges = 0;
+        // The keys whose value may differ from the one before the block by the time a transaction
+        // is executed, i.e. those received from other shards, and those written by the previous
+        // transactions of the sub block.
+        let mut overwritten_keys: HashSet<StateKey> = sub_block
+            .transactions
+            .iter()
+            .flat_map(|txn| txn.cross_shard_dependencies.required_edges_iter())
+            .flat_map(|(_, storage_locations)| storage_locations.iter())
+            .map(|storage_location| storage_location.clone().into_state_key())
+            .collect();
+        let mut overwritten_wildcard = false;
+        let mut base_value_edges = HashMap::new();
         for (txn_idx, txn_with_deps) in sub_block.txn_with_index_iter() {
             let mut storage_locations_to_target = HashMap::new();
             for (txn_id_with_shard, storage_locations) in txn_with_deps
@@ -83,72 +375,626 @@ impl CrossShardCommitSender {
                     num_dependent_edges += 1;
                 }
             }
+            if batch_config.send_deltas {
+                let keys: HashSet<_> = storage_locations_to_target
+                    .keys()
+                    .filter(|state_key| {
+                        !overwritten_wildcard && !overwritten_keys.contains(*state_key)
+                    })
+                    .cloned()
+                    .collect();
+                if !keys.is_empty() {
+                    base_value_edges.insert(txn_idx as TxnIndex, keys);
+                }
+                for write_hint in txn_with_deps.txn().write_hints() {
+                    match write_hint {
+                        StorageLocation::Specific(state_key) => {
+                            overwritten_keys.insert(state_key.clone());
+                        },
+                        _ => overwritten_wildcard = true,
+                    }
+                }
+            }
             if !storage_locations_to_target.is_empty() {
                 dependent_edges.insert(txn_idx as TxnIndex, storage_locations_to_target);
+                priorities.insert(
+                    txn_idx as TxnIndex,
+                    txn_with_deps
+                        .cross_shard_dependencies
+                        .dependent_chain_length(),
+                );
             }
         }
 
         trace!(
-            "CrossShardCommitSender::new: shard_id: {:?}, num_dependent_edges: {:?}",
-            shard_id,
+            "CrossShardCommitSender::new: num_dependent_edges: {:?}",
             num_dependent_edges
         );
+        // A write is sent for each dependent edge, whether the transaction is committed or not.
+        cross_shard_stats::record_sub_block_dependencies(
+            shard_id,
+            round,
+            dependent_edges.values().flatten(),
+        );
 
+        let edge_keys = dependent_edges
+            .values()
+            .flat_map(|edges| edges.keys().cloned())
+            .collect();
         Self {
             shard_id,
             cross_shard_client,
             dependent_edges,
+            priorities,
+            base_value_edges,
+            state_view,
+            edge_keys,
+            sub_block_values: Mutex::new(HashMap::new()),
             index_offset: sub_block.start_index as TxnIndex,
+            stream_events: false,
+            batch_config,
+            pending_batches: Mutex::new(HashMap::new()),
+            hold_back: false,
+            held_back_events: Mutex::new(vec![]),
         }
     }
 
-    fn send_remote_update_for_success(
-        &self,
-        txn_idx: TxnIndex,
-        txn_output: &AptosTransactionOutput,
-    ) {
+    /// Streams the events of the committed transactions to the coordinator.
+    pub fn with_event_streaming(mut self) -> Self {
+        self.stream_events = true;
+        self
+    }
+
+    /// Holds back all the writes and events until the sender is dropped.
+    pub fn with_hold_back(mut self) -> Self {
+        self.hold_back = true;
+        self
+    }
+
+    /// Discards the writes and events held back so far, e.g. as the sub block is re-executed.
+    pub fn discard_held_back(&self) {
+        self.pending_batches.lock().clear();
+        self.held_back_events.lock().clear();
+        self.sub_block_values.lock().clear();
+    }
+
+    /// Returns the value of the storage location before the transaction, i.e. the latest write
+    /// to it by the previous transactions of the sub block, or its value before the sub block.
+    /// None if the value can not be read, in which case the shard aborts the block.
+    fn value_before_txn(&self, state_key: &StateKey) -> Option<Option<StateValue>> {
+        if let Some(state_value) = self.sub_block_values.lock().get(state_key) {
+            return Some(state_value.clone());
+        }
+        match self.state_view.get_state_value(state_key) {
+            Ok(state_value) => Some(state_value),
+            Err(err) => {
+                error!(
+                    "Failed to read the value of {:?} for the dependent shards: {:?}",
+                    state_key, err
+                );
+                None
+            },
+        }
+    }
+
+    /// Records the writes of a committed transaction to the storage locations of the dependent
+    /// edges, for the later transactions that do not write them to send them instead.
+    fn record_sub_block_writes(&self, txn_output: &AptosTransactionOutput) {
+        let mut sub_block_values = self.sub_block_values.lock();
+        for (state_key, write_op) in txn_output.committed_output().write_set().iter() {
+            if self.edge_keys.contains(state_key) {
+                sub_block_values.insert(state_key.clone(), write_op.as_state_value());
+            }
+        }
+    }
+
+    /// Sends the value of each storage location the dependent shards wait on, i.e. the write of
+    /// the transaction to it, or its value before the transaction if the transaction did not
+    /// write it (e.g. as it was aborted or discarded, or its write hints over-approximate its
+    /// writes), for the dependent shards not to wait forever. The modules published by the
+    /// transaction are broadcast to all the dependent shards, ahead of its other writes.
+    fn send_remote_updates(&self, txn_idx: TxnIndex, txn_output: Option<&AptosTransactionOutput>) {
         let edges = self.dependent_edges.get(&txn_idx).unwrap();
-        let output = txn_output.committed_output();
-        let write_set = output.write_set();
-
-        for (state_key, write_op) in write_set.iter() {
-            if let Some(dependent_shard_ids) = edges.get(state_key) {
-                for (dependent_shard_id, round_id) in dependent_shard_ids.iter() {
-                    trace!("Sending remote update for success for shard id {:?} and txn_idx: {:?}, state_key: {:?}, dependent shard id: {:?}", self.shard_id, txn_idx, state_key, dependent_shard_id);
-                    let message = RemoteTxnWriteMsg(RemoteTxnWrite::new(
-                        state_key.clone(),
-                        Some(write_op.clone()),
-                    ));
-                    self.cross_shard_client.send_cross_shard_msg(
-                        *dependent_shard_id,
-                        *round_id,
-                        message,
-                    );
-                }
+        let priority = self.priorities[&txn_idx];
+        let write_set = txn_output.map(|txn_output| txn_output.committed_output().write_set());
+        let base_value_edges = self.base_value_edges.get(&txn_idx);
+        let module_writes: Vec<_> = write_set
+            .into_iter()
+            .flat_map(|write_set| write_set.iter())
+            .filter(|(state_key, _)| is_module(state_key))
+            .map(|(state_key, write_op)| {
+                RemoteTxnWrite::new(txn_idx, state_key.clone(), Some(write_op.clone()))
+            })
+            .collect();
+
+        // The writes are batched into a single message per dependent shard and round.
+        let mut txn_writes: HashMap<(ShardId, RoundId), Vec<RemoteTxnWrite>> = HashMap::new();
+        for (state_key, dependent_shard_ids) in edges.iter() {
+            let delta_op = txn_output
+                .filter(|_| base_value_edges.map_or(false, |keys| keys.contains(state_key)))
+                .and_then(|txn_output| txn_output.committed_delta(state_key));
+            let write_op = write_set.and_then(|write_set| write_set.get(state_key));
+            let txn_write = match (delta_op, write_op) {
+                (Some(delta_op), _) => {
+                    RemoteTxnWrite::new_delta(txn_idx, state_key.clone(), delta_op)
+                },
+                (None, Some(write_op)) => {
+                    RemoteTxnWrite::new(txn_idx, state_key.clone(), Some(write_op.clone()))
+                },
+                (None, None) => match self.value_before_txn(state_key) {
+                    Some(state_value) => {
+                        RemoteTxnWrite::new_value(txn_idx, state_key.clone(), state_value)
+                    },
+                    None => RemoteTxnWrite::new(txn_idx, state_key.clone(), None),
+                },
+            };
+            for dependent_shard_id_and_round in dependent_shard_ids.iter() {
+                txn_writes
+                    .entry(*dependent_shard_id_and_round)
+                    .or_insert_with(|| module_writes.clone())
+                    .push(txn_write.clone());
+            }
+        }
+        trace!(
+            "Sending remote updates for txn_idx: {:?} to {} dependent shards and rounds",
+            txn_idx,
+            txn_writes.len()
+        );
+        if self.batch_config.window.is_zero() && !self.hold_back {
+            for (dependent_shard_id_and_round, txn_writes) in txn_writes {
+                self.send_txn_writes(dependent_shard_id_and_round, txn_writes, priority);
             }
+            return;
+        }
+
+        // The batches are sent once the lock is released, as sending may block.
+        let ready_batches = {
+            let mut pending_batches = self.pending_batches.lock();
+            let now = Instant::now();
+            for (dependent_shard_id_and_round, txn_writes) in txn_writes {
+                let batch = pending_batches
+                    .entry(dependent_shard_id_and_round)
+                    .or_insert_with(|| PendingBatch {
+                        txn_writes: vec![],
+                        priority,
+                        start_time: now,
+                    });
+                batch.txn_writes.extend(txn_writes);
+                batch.priority = batch.priority.max(priority);
+            }
+            let ready: Vec<_> = pending_batches
+                .iter()
+                .filter(|(_, batch)| {
+                    !self.hold_back
+                        && (batch.txn_writes.len() >= self.batch_config.max_batch_size
+                            || now.duration_since(batch.start_time) >= self.batch_config.window)
+                })
+                .map(|(dependent_shard_id_and_round, _)| *dependent_shard_id_and_round)
+                .collect();
+            ready
+                .into_iter()
+                .map(|dependent_shard_id_and_round| {
+                    let batch = pending_batches
+                        .remove(&dependent_shard_id_and_round)
+                        .expect("Must exist");
+                    (dependent_shard_id_and_round, batch)
+                })
+                .collect::<Vec<_>>()
+        };
+        self.send_batches(ready_batches);
+    }
+
+    /// Sends the batches, the ones with the highest priority first.
+    fn send_batches(&self, mut batches: Vec<((ShardId, RoundId), PendingBatch)>) {
+        batches.sort_by_key(|(_, batch)| Reverse(batch.priority));
+        for (dependent_shard_id_and_round, batch) in batches {
+            self.send_txn_writes(
+                dependent_shard_id_and_round,
+                batch.txn_writes,
+                batch.priority,
+            );
+        }
+    }
+
+    fn send_txn_writes(
+        &self,
+        dependent_shard_id_and_round: (ShardId, RoundId),
+        txn_writes: Vec<RemoteTxnWrite>,
+        priority: CrossShardMsgPriority,
+    ) {
+        let shard_id = self.shard_id.to_string();
+        CROSS_SHARD_BATCH_SIZE
+            .with_label_values(&[&shard_id])
+            .observe(txn_writes.len() as f64);
+        let message = if self.batch_config.compress {
+            CrossShardMsg::compressed_txn_writes(&txn_writes, priority)
+        } else {
+            RemoteTxnWriteMsg(txn_writes, priority)
+        };
+        // The dependent shard times out on the writes if they can not be sent, rather than the
+        // shard failing.
+        if let Err(err) = send_cross_shard_msg_with_retry(
+            self.cross_shard_client.as_ref(),
+            self.shard_id,
+            dependent_shard_id_and_round,
+            message,
+        ) {
+            error!(
+                "Giving up on sending cross shard writes to shard {} for round {}: {:?}",
+                dependent_shard_id_and_round.0, dependent_shard_id_and_round.1, err
+            );
+            return;
+        }
+        CROSS_SHARD_MESSAGES
+            .with_label_values(&[&shard_id, "sent"])
+            .inc();
+    }
+}
+
+impl Drop for CrossShardCommitSender<'_> {
+    // Sends the writes and events still held back once the sub block is executed (and the sender
+    // dropped), as no later transaction will send them.
+    fn drop(&mut self) {
+        let pending_batches = std::mem::take(&mut *self.pending_batches.lock());
+        self.send_batches(pending_batches.into_iter().collect());
+        for events in std::mem::take(&mut *self.held_back_events.lock()) {
+            self.cross_shard_client
+                .send_coordinator_msg(RemoteEventMsg(events));
         }
     }
 }
 
-impl TransactionCommitHook for CrossShardCommitSender {
+impl TransactionCommitHook for CrossShardCommitSender<'_> {
     type Output = AptosTransactionOutput;
 
     fn on_transaction_committed(&self, txn_idx: TxnIndex, txn_output: &Self::Output) {
         let global_txn_idx = txn_idx + self.index_offset;
         if self.dependent_edges.contains_key(&global_txn_idx) {
-            self.send_remote_update_for_success(global_txn_idx, txn_output);
+            self.send_remote_updates(global_txn_idx, Some(txn_output));
+        }
+        self.record_sub_block_writes(txn_output);
+        if self.stream_events {
+            let events = RemoteTxnEvents::new(
+                global_txn_idx as usize,
+                txn_output.committed_output().events().to_vec(),
+            );
+            if self.hold_back {
+                self.held_back_events.lock().push(events);
+            } else {
+                self.cross_shard_client
+                    .send_coordinator_msg(RemoteEventMsg(events));
+            }
         }
     }
 
-    fn on_execution_aborted(&self, _txn_idx: TxnIndex) {
-        todo!("on_transaction_aborted not supported for sharded execution yet")
+    fn on_execution_aborted(&self, txn_idx: TxnIndex) {
+        let global_txn_idx = txn_idx + self.index_offset;
+        if self.dependent_edges.contains_key(&global_txn_idx) {
+            self.send_remote_updates(global_txn_idx, None);
+        }
     }
 }
 
 // CrossShardClient is a trait that defines the interface for sending and receiving messages across
 // shards.
 pub trait CrossShardClient: Send + Sync {
-    fn send_cross_shard_msg(&self, shard_id: ShardId, round: RoundId, msg: CrossShardMsg);
+    // Sends a message to the given shard for the given round. May block while the channel to the
+    // shard is full, so that a fast shard can not queue up messages into a slow one unboundedly.
+    // Fails if the message could not be sent, in which case it may or may not have been delivered.
+    fn send_cross_shard_msg(
+        &self,
+        shard_id: ShardId,
+        round: RoundId,
+        msg: CrossShardMsg,
+    ) -> Result<()>;
+
+    // Receives a message for the given round, failing if none is received within the timeout or
+    // the senders are gone.
+    fn receive_cross_shard_msg(
+        &self,
+        current_round: RoundId,
+        timeout: Duration,
+    ) -> Result<CrossShardMsg, RecvTimeoutError>;
+
+    // Whether messages can be streamed to the coordinator while the block is being executed.
+    fn streams_to_coordinator(&self) -> bool {
+        false
+    }
+
+    // Sends a message to the coordinator, i.e. the events of a committed transaction, or a stop
+    // message once the shard is done with the block. Only called if `streams_to_coordinator`.
+    fn send_coordinator_msg(&self, _msg: CrossShardMsg) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sharded_block_executor::{
+        local_executor_shard::LocalCrossShardClient,
+        mock_shards::{MockShards, ScriptedTxn},
+    };
+    use anyhow::anyhow;
+    use aptos_state_view::{in_memory_state_view::InMemoryStateView, TStateView};
+    use aptos_types::{
+        access_path::AccessPath, account_address::AccountAddress,
+        state_store::state_value::StateValue, write_set::WriteOp,
+    };
+    use crossbeam_channel::{unbounded, Receiver, Sender};
+    use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Delivers the messages to the shard itself. Unless all the sends fail, every other send
+    /// fails, alternately before the message is delivered (i.e. the message is dropped) and after
+    /// it is delivered (i.e. the message is duplicated once the send is retried).
+    struct FlakyCrossShardClient {
+        fail_all: bool,
+        num_sends: AtomicUsize,
+        message_tx: Sender<CrossShardMsg>,
+        message_rx: Receiver<CrossShardMsg>,
+    }
+
+    impl FlakyCrossShardClient {
+        fn new(fail_all: bool) -> Self {
+            let (message_tx, message_rx) = unbounded();
+            Self {
+                fail_all,
+                num_sends: AtomicUsize::new(0),
+                message_tx,
+                message_rx,
+            }
+        }
+    }
+
+    impl CrossShardClient for FlakyCrossShardClient {
+        fn send_cross_shard_msg(
+            &self,
+            _shard_id: ShardId,
+            _round: RoundId,
+            msg: CrossShardMsg,
+        ) -> Result<()> {
+            let num_sends = self.num_sends.fetch_add(1, Ordering::SeqCst);
+            if self.fail_all || num_sends % 4 == 1 {
+                return Err(anyhow!("Message dropped"));
+            }
+            self.message_tx.send(msg)?;
+            if num_sends % 4 == 3 {
+                return Err(anyhow!("Acknowledgement dropped"));
+            }
+            Ok(())
+        }
+
+        fn receive_cross_shard_msg(
+            &self,
+            _current_round: RoundId,
+            timeout: Duration,
+        ) -> Result<CrossShardMsg, RecvTimeoutError> {
+            self.message_rx.recv_timeout(timeout)
+        }
+    }
+
+    #[test]
+    fn test_dropped_and_duplicated_messages() {
+        let client = Arc::new(FlakyCrossShardClient::new(false));
+        let state_keys: Vec<_> = (0..10)
+            .map(|i| StateKey::raw(format!("key_{}", i).into_bytes()))
+            .collect();
+        let base_view = InMemoryStateView::new(HashMap::new());
+        let cross_shard_state_view = Arc::new(CrossShardStateView::new(
+            1,
+            state_keys.iter().cloned().collect(),
+            &base_view,
+        ));
+        let duplicate_writes = CROSS_SHARD_DUPLICATE_WRITES.with_label_values(&["1"]);
+        let num_duplicates_before = duplicate_writes.get();
+
+        let sender = CrossShardCommitSender::new(
+            0,
+            0,
+            client.clone(),
+            &SubBlock::empty(),
+            &base_view,
+            CrossShardBatchConfig::default(),
+        );
+        for (txn_idx, state_key) in state_keys.iter().enumerate() {
+            sender.send_txn_writes(
+                (1, 0),
+                vec![RemoteTxnWrite::new(
+                    txn_idx as TxnIndex,
+                    state_key.clone(),
+                    Some(WriteOp::Modification(vec![txn_idx as u8])),
+                )],
+                1,
+            );
+        }
+        send_cross_shard_msg_with_retry(client.as_ref(), 0, (1, 0), CrossShardMsg::StopMsg)
+            .unwrap();
+        CrossShardCommitReceiver::start(
+            1,
+            cross_shard_state_view.clone(),
+            client,
+            0,
+            Duration::from_secs(10),
+        );
+
+        // Every write is applied, the ones delivered twice only once.
+        assert_eq!(cross_shard_state_view.waiting_count(), 0);
+        for (txn_idx, state_key) in state_keys.iter().enumerate() {
+            assert_eq!(
+                cross_shard_state_view.get_state_value(state_key).unwrap(),
+                Some(StateValue::from(vec![txn_idx as u8]))
+            );
+        }
+        assert_eq!(duplicate_writes.get() - num_duplicates_before, 4);
+    }
+
+    #[test]
+    fn test_send_gives_up_after_max_attempts() {
+        let client = FlakyCrossShardClient::new(true);
+        assert!(
+            send_cross_shard_msg_with_retry(&client, 0, (1, 0), CrossShardMsg::StopMsg).is_err()
+        );
+        assert_eq!(
+            client.num_sends.load(Ordering::SeqCst),
+            MAX_CROSS_SHARD_SEND_ATTEMPTS
+        );
+    }
+
+    #[test]
+    fn test_writes_processed_before_stop_msg() {
+        let (message_tx, message_rx) = unbounded();
+        let (coordinator_tx, _coordinator_rx) = unbounded();
+        let client = Arc::new(LocalCrossShardClient::new(
+            0,
+            vec![vec![message_tx]],
+            vec![message_rx],
+            coordinator_tx,
+        ));
+        let state_keys: Vec<_> = (0..3)
+            .map(|i| StateKey::raw(format!("key_{}", i).into_bytes()))
+            .collect();
+        let base_view = InMemoryStateView::new(HashMap::new());
+        let cross_shard_state_view = Arc::new(CrossShardStateView::new(
+            0,
+            state_keys.iter().cloned().collect(),
+            &base_view,
+        ));
+
+        // The writes queued behind the stop message outrank it.
+        client
+            .send_cross_shard_msg(0, 0, CrossShardMsg::StopMsg)
+            .unwrap();
+        for (txn_idx, state_key) in state_keys.iter().enumerate() {
+            let txn_write = RemoteTxnWrite::new(
+                txn_idx as TxnIndex,
+                state_key.clone(),
+                Some(WriteOp::Modification(vec![txn_idx as u8])),
+            );
+            client
+                .send_cross_shard_msg(0, 0, RemoteTxnWriteMsg(vec![txn_write], txn_idx + 1))
+                .unwrap();
+        }
+        CrossShardCommitReceiver::start(
+            0,
+            cross_shard_state_view.clone(),
+            client,
+            0,
+            Duration::from_secs(10),
+        );
+        assert_eq!(cross_shard_state_view.waiting_count(), 0);
+    }
+
+    fn key(name: &str) -> StateKey {
+        StateKey::raw(name.as_bytes().to_owned())
+    }
+
+    fn value(value: &str) -> StateValue {
+        StateValue::from(value.as_bytes().to_owned())
+    }
 
-    fn receive_cross_shard_msg(&self, current_round: RoundId) -> CrossShardMsg;
+    #[test]
+    fn test_mock_shards_cross_shard_writes() {
+        let batch_configs = [
+            CrossShardBatchConfig::default(),
+            CrossShardBatchConfig {
+                window: Duration::from_secs(1),
+                max_batch_size: 2,
+                compress: true,
+                send_deltas: false,
+            },
+        ];
+        for batch_config in batch_configs {
+            let base_values = HashMap::from([(key("a"), value("a0")), (key("b"), value("b0"))]);
+            let mut mock_shards = MockShards::new(3, base_values).with_batch_config(batch_config);
+            let write_a = mock_shards.add_txn(
+                0,
+                0,
+                ScriptedTxn::committed(vec![(key("a"), WriteOp::Modification(b"a1".to_vec()))]),
+            );
+            mock_shards.add_txn(
+                1,
+                0,
+                ScriptedTxn::committed(vec![(key("b"), WriteOp::Modification(b"b1".to_vec()))]),
+            );
+            let aborted = mock_shards.add_txn(1, 0, ScriptedTxn::aborted());
+            let write_c = mock_shards.add_txn(
+                2,
+                0,
+                ScriptedTxn::committed(vec![(key("c"), WriteOp::Creation(b"c1".to_vec()))]),
+            );
+            let write_d = mock_shards.add_txn(
+                1,
+                1,
+                ScriptedTxn::committed(vec![(key("d"), WriteOp::Modification(b"d1".to_vec()))]),
+            );
+            let read_c = mock_shards.add_txn(0, 1, ScriptedTxn::committed(vec![]));
+            let read_d = mock_shards.add_txn(2, 1, ScriptedTxn::committed(vec![]));
+            mock_shards.add_edge(write_a, write_d, key("a"));
+            mock_shards.add_edge(aborted, write_d, key("b"));
+            mock_shards.add_edge(write_c, write_d, key("c"));
+            mock_shards.add_edge(write_c, read_c, key("c"));
+            // The key is not written by the source transaction.
+            mock_shards.add_edge(write_c, read_c, key("e"));
+            mock_shards.add_edge(write_d, read_d, key("d"));
+            // The key is not written by the source transaction, but received by its shard.
+            mock_shards.add_edge(write_d, read_d, key("a"));
+
+            let contents = mock_shards.run();
+            for contents_of_shard in &contents {
+                assert!(contents_of_shard[0].values.is_empty());
+                assert!(contents_of_shard
+                    .iter()
+                    .all(|contents| !contents.has_failed_reads));
+            }
+            // The keys not written by the source transaction (e.g. as it was aborted) resolve to
+            // their latest value before it, i.e. the write of the previous transaction of its sub
+            // block, the value received by its shard, or the value before the block.
+            assert_eq!(
+                contents[1][1].values,
+                HashMap::from([
+                    (key("a"), Some(value("a1"))),
+                    (key("b"), Some(value("b1"))),
+                    (key("c"), Some(value("c1"))),
+                ])
+            );
+            assert_eq!(
+                contents[0][1].values,
+                HashMap::from([(key("c"), Some(value("c1"))), (key("e"), None)])
+            );
+            assert_eq!(
+                contents[2][1].values,
+                HashMap::from([(key("a"), Some(value("a1"))), (key("d"), Some(value("d1")))])
+            );
+        }
+    }
+
+    #[test]
+    fn test_mock_shards_published_modules() {
+        let module_key = StateKey::access_path(AccessPath::code_access_path(ModuleId::new(
+            AccountAddress::ONE,
+            Identifier::new("module1").unwrap(),
+        )));
+        let mut mock_shards = MockShards::new(2, HashMap::new());
+        let publish = mock_shards.add_txn(
+            0,
+            0,
+            ScriptedTxn::committed(vec![
+                (module_key.clone(), WriteOp::Creation(b"module".to_vec())),
+                (key("a"), WriteOp::Creation(b"a1".to_vec())),
+            ]),
+        );
+        let read_a = mock_shards.add_txn(1, 1, ScriptedTxn::committed(vec![]));
+        mock_shards.add_edge(publish, read_a, key("a"));
+
+        let contents = mock_shards.run();
+        // The module is sent along with the write the dependent shard waits on.
+        assert_eq!(
+            contents[1][1].published_modules,
+            HashMap::from([(module_key, Some(value("module")))])
+        );
+        assert_eq!(
+            contents[1][1].values,
+            HashMap::from([(key("a"), Some(value("a1")))])
+        );
+        assert!(contents[1][0].published_modules.is_empty());
+    }
 }
@@ -1,7 +1,11 @@
 // Copyright © Aptos Foundation
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
-use anyhow::Result;
+use crate::sharded_block_executor::counters::{
+    CROSS_SHARD_PREFETCHED_READS, CROSS_SHARD_SPECULATIVE_READS,
+};
+use anyhow::{anyhow, Result};
+use aptos_aggregator::delta_change_set::DeltaOp;
 use aptos_logger::trace;
 use aptos_state_view::{StateView, TStateView};
 use aptos_types::{
@@ -12,7 +16,10 @@ use aptos_types::{
 };
 use std::{
     collections::{HashMap, HashSet},
-    sync::{Arc, Condvar, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex, RwLock,
+    },
 };
 
 #[derive(Clone)]
@@ -21,6 +28,8 @@ enum CrossShardValueStatus {
     Ready(Option<StateValue>),
     /// We are still waiting for remote shard to push the state value
     Waiting,
+    /// The remote shard will never push the state value, e.g. as it is gone
+    Failed,
 }
 
 #[derive(Clone)]
@@ -38,67 +47,254 @@ impl CrossShardStateValue {
     pub fn set_value(&self, value: Option<StateValue>) {
         let (lock, cvar) = &*self.value_condition;
         let mut status = lock.lock().unwrap();
-        // We only allow setting the value once and it must be in the waiting state
-        assert!(matches!(*status, CrossShardValueStatus::Waiting));
-        *status = CrossShardValueStatus::Ready(value);
+        match *status {
+            CrossShardValueStatus::Waiting => {
+                *status = CrossShardValueStatus::Ready(value);
+                cvar.notify_all();
+            },
+            // The value arrived after it was given up on, the readers already failed.
+            CrossShardValueStatus::Failed => (),
+            // We only allow setting the value once
+            CrossShardValueStatus::Ready(_) => panic!("Cross shard value is set twice"),
+        }
+    }
+
+    /// Marks the value as failed if it is still waiting, waking up its readers. Returns whether
+    /// it was waiting.
+    pub fn fail(&self) -> bool {
+        let (lock, cvar) = &*self.value_condition;
+        let mut status = lock.lock().unwrap();
+        if !matches!(*status, CrossShardValueStatus::Waiting) {
+            return false;
+        }
+        *status = CrossShardValueStatus::Failed;
         cvar.notify_all();
+        true
     }
 
-    pub fn get_value(&self) -> Option<StateValue> {
+    pub fn is_waiting(&self) -> bool {
+        matches!(
+            *self.value_condition.0.lock().unwrap(),
+            CrossShardValueStatus::Waiting
+        )
+    }
+
+    /// Waits for the value, returning None if it failed.
+    pub fn get_value(&self) -> Option<Option<StateValue>> {
         let (lock, cvar) = &*self.value_condition;
         let mut status = lock.lock().unwrap();
         while let CrossShardValueStatus::Waiting = *status {
             status = cvar.wait(status).unwrap();
         }
         match &*status {
-            CrossShardValueStatus::Ready(value) => value.clone(),
+            CrossShardValueStatus::Ready(value) => Some(value.clone()),
+            CrossShardValueStatus::Failed => None,
             CrossShardValueStatus::Waiting => unreachable!(),
         }
     }
+
+    /// Returns the value if it is ready, without waiting for it.
+    #[cfg(test)]
+    pub fn try_get_value(&self) -> Option<Option<StateValue>> {
+        match &*self.value_condition.0.lock().unwrap() {
+            CrossShardValueStatus::Ready(value) => Some(value.clone()),
+            CrossShardValueStatus::Waiting | CrossShardValueStatus::Failed => None,
+        }
+    }
 }
 
 /// A state view for reading cross shard state values. It is backed by a state view
 /// and a hashmap of cross shard state keys. When a cross shard state value is not
-/// available in the hashmap, it will be fetched from the underlying base view.
-#[derive(Clone)]
+/// available in the hashmap, it will be fetched from the underlying base view (unless
+/// it was prefetched).
+///
+/// The base values of the cross shard keys can be prefetched as well, while waiting for the remote
+/// writes, as they are read when a remote transaction does not write the key, when applying the
+/// aggregator deltas sent by other shards, and for speculative reads.
+///
+/// With speculative reads enabled, a cross shard value that did not arrive yet is read from the
+/// base view instead of waiting for it. The speculated values are recorded, for the execution to
+/// be validated against the remote writes once they arrive.
+///
+/// The modules published by the transactions of other shards are broadcast to the shard, and
+/// override the ones of the base view. As the VM (and thus its loader cache) is created afresh
+/// for each sub block, the modules are loaded from the view once the sub block is executed.
 pub struct CrossShardStateView<'a, S> {
-    _shard_id: ShardId,
+    shard_id: ShardId,
     cross_shard_data: HashMap<StateKey, CrossShardStateValue>,
+    prefetched_base_data: HashMap<StateKey, Option<StateValue>>,
     base_view: &'a S,
+    // Whether a cross shard value was read after it failed, in which case the results of the
+    // execution can not be trusted.
+    has_failed_reads: AtomicBool,
+    speculative_reads_enabled: AtomicBool,
+    // The base values returned for the cross shard values read before they arrived.
+    speculative_reads: Mutex<HashMap<StateKey, Option<StateValue>>>,
+    published_modules: RwLock<HashMap<StateKey, Option<StateValue>>>,
+    // The number of reads of the base view served by the prefetched values, and the other ones.
+    prefetch_hits: AtomicU64,
+    prefetch_misses: AtomicU64,
 }
 
 impl<'a, S: StateView + Sync + Send> CrossShardStateView<'a, S> {
     pub fn new(shard_id: ShardId, cross_shard_keys: HashSet<StateKey>, base_view: &'a S) -> Self {
         let mut cross_shard_data = HashMap::new();
         trace!(
-            "Iniitalizing cross shard state view with {} keys for shard id {}",
-            cross_shard_keys.len(),
-            shard_id
+            "Iniitalizing cross shard state view with {} keys",
+            cross_shard_keys.len()
         );
         for key in cross_shard_keys {
             cross_shard_data.insert(key, CrossShardStateValue::waiting());
         }
         Self {
-            // Added for debugging purpose
-            _shard_id: shard_id,
+            shard_id,
             cross_shard_data,
+            prefetched_base_data: HashMap::new(),
             base_view,
+            has_failed_reads: AtomicBool::new(false),
+            speculative_reads_enabled: AtomicBool::new(false),
+            speculative_reads: Mutex::new(HashMap::new()),
+            published_modules: RwLock::new(HashMap::new()),
+            prefetch_hits: AtomicU64::new(0),
+            prefetch_misses: AtomicU64::new(0),
         }
     }
 
-    #[cfg(test)]
-    fn waiting_count(&self) -> usize {
+    /// Reads the values of the given (non cross shard) keys from the base view in a single
+    /// batch, so that the subsequent reads of these keys do not hit the base view.
+    pub fn prefetch_base_values(&mut self, state_keys: Vec<StateKey>) -> Result<()> {
+        let state_keys: Vec<StateKey> = state_keys
+            .into_iter()
+            .filter(|state_key| !self.cross_shard_data.contains_key(state_key))
+            .collect();
+        let state_values = self.base_view.get_state_values(&state_keys)?;
+        self.prefetched_base_data
+            .extend(state_keys.into_iter().zip(state_values));
+        Ok(())
+    }
+
+    /// Reads the base values of all the cross shard keys in a single batch, ahead of the remote
+    /// writes.
+    pub fn prefetch_cross_shard_base_values(&mut self) -> Result<()> {
+        let state_keys: Vec<StateKey> = self
+            .cross_shard_data
+            .keys()
+            .filter(|state_key| !self.prefetched_base_data.contains_key(state_key))
+            .cloned()
+            .collect();
+        let state_values = self.base_view.get_state_values(&state_keys)?;
+        self.prefetched_base_data
+            .extend(state_keys.into_iter().zip(state_values));
+        Ok(())
+    }
+
+    fn get_base_value(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
+        if let Some(value) = self.prefetched_base_data.get(state_key) {
+            self.prefetch_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value.clone());
+        }
+        self.prefetch_misses.fetch_add(1, Ordering::Relaxed);
+        self.base_view.get_state_value(state_key)
+    }
+
+    /// Returns the number of cross shard values still waiting for their remote shard.
+    pub fn waiting_count(&self) -> usize {
         self.cross_shard_data
             .values()
-            .filter(|v| {
-                matches!(
-                    v.value_condition.0.lock().unwrap().clone(),
-                    CrossShardValueStatus::Waiting
-                )
-            })
+            .filter(|value| value.is_waiting())
             .count()
     }
 
+    /// Gives up on the cross shard values still waiting for their remote shard, e.g. once it is
+    /// gone, so that their readers fail instead of hanging. Returns the number of failed values.
+    pub fn fail_waiting_values(&self) -> usize {
+        self.cross_shard_data
+            .values()
+            .filter(|value| value.fail())
+            .count()
+    }
+
+    /// Returns the cross shard values resolved so far, without waiting for the other ones.
+    #[cfg(test)]
+    pub fn resolved_values(&self) -> HashMap<StateKey, Option<StateValue>> {
+        self.cross_shard_data
+            .iter()
+            .filter_map(|(state_key, value)| {
+                value
+                    .try_get_value()
+                    .map(|value| (state_key.clone(), value))
+            })
+            .collect()
+    }
+
+    /// Returns the modules published by the transactions of other shards so far.
+    #[cfg(test)]
+    pub fn published_modules(&self) -> HashMap<StateKey, Option<StateValue>> {
+        self.published_modules.read().unwrap().clone()
+    }
+
+    /// Whether a transaction read a failed cross shard value, so that the block must be aborted.
+    pub fn has_failed_reads(&self) -> bool {
+        self.has_failed_reads.load(Ordering::Acquire)
+    }
+
+    /// Sets whether the cross shard values that did not arrive yet are read speculatively from
+    /// the base view, rather than waited for.
+    pub fn set_speculative_reads(&self, enabled: bool) {
+        self.speculative_reads_enabled
+            .store(enabled, Ordering::Release);
+    }
+
+    pub fn speculative_reads_enabled(&self) -> bool {
+        self.speculative_reads_enabled.load(Ordering::Acquire)
+    }
+
+    /// Waits for the cross shard values read speculatively so far, and returns whether they all
+    /// match the values speculated, i.e. whether the execution having read them is valid. The
+    /// speculative reads are cleared, for the next execution to be validated on its own.
+    pub fn validate_speculative_reads(&self) -> bool {
+        let speculative_reads = std::mem::take(&mut *self.speculative_reads.lock().unwrap());
+        let shard_id = self.shard_id.to_string();
+        let mut valid = true;
+        for (state_key, speculated_value) in speculative_reads {
+            // A failed value never matches, the execution reading it being aborted.
+            let matches = self.cross_shard_data[&state_key].get_value() == Some(speculated_value);
+            CROSS_SHARD_SPECULATIVE_READS
+                .with_label_values(&[&shard_id, if matches { "hit" } else { "miss" }])
+                .inc();
+            valid &= matches;
+        }
+        valid
+    }
+
+    /// Waits for the given cross shard values, regardless of speculative reads, failing if one of
+    /// them is unavailable.
+    pub fn wait_for_values<'b>(
+        &self,
+        state_keys: impl IntoIterator<Item = &'b StateKey>,
+    ) -> Result<()> {
+        for state_key in state_keys {
+            if self.cross_shard_data[state_key].get_value().is_none() {
+                self.has_failed_reads.store(true, Ordering::Release);
+                return Err(anyhow!(
+                    "Cross shard value of {:?} is unavailable",
+                    state_key
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_speculatively(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
+        let mut speculative_reads = self.speculative_reads.lock().unwrap();
+        if let Some(speculated_value) = speculative_reads.get(state_key) {
+            return Ok(speculated_value.clone());
+        }
+        let base_value = self.get_base_value(state_key)?;
+        speculative_reads.insert(state_key.clone(), base_value.clone());
+        Ok(base_value)
+    }
+
     pub fn set_value(&self, state_key: &StateKey, state_value: Option<StateValue>) {
         self.cross_shard_data
             .get(state_key)
@@ -107,6 +303,30 @@ impl<'a, S: StateView + Sync + Send> CrossShardStateView<'a, S> {
         // uncomment the following line to debug waiting count
         // trace!("waiting count for shard id {} is {}", self.shard_id, self.waiting_count());
     }
+
+    /// Sets a module published by a transaction of another shard.
+    pub fn set_published_module(&self, state_key: StateKey, state_value: Option<StateValue>) {
+        self.published_modules
+            .write()
+            .unwrap()
+            .insert(state_key, state_value);
+    }
+
+    /// Resolves a cross shard key to its value in the base view, for when the remote transaction
+    /// it depends on did not write it (e.g. as it was aborted).
+    pub fn set_base_value(&self, state_key: &StateKey) -> Result<()> {
+        let state_value = self.get_base_value(state_key)?;
+        self.set_value(state_key, state_value);
+        Ok(())
+    }
+
+    /// Resolves a cross shard aggregator to its value in the base view, with the delta of the
+    /// remote transaction it depends on applied.
+    pub fn set_delta(&self, state_key: &StateKey, delta_op: DeltaOp) -> Result<()> {
+        let write_op = delta_op.try_into_write_op(&BaseStateView(self), state_key)?;
+        self.set_value(state_key, write_op.as_state_value());
+        Ok(())
+    }
 }
 
 impl<'a, S: StateView + Sync + Send> TStateView for CrossShardStateView<'a, S> {
@@ -114,9 +334,18 @@ impl<'a, S: StateView + Sync + Send> TStateView for CrossShardStateView<'a, S> {
 
     fn get_state_value(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
         if let Some(value) = self.cross_shard_data.get(state_key) {
-            return Ok(value.get_value());
+            if self.speculative_reads_enabled() && value.is_waiting() {
+                return self.read_speculatively(state_key);
+            }
+            return value.get_value().ok_or_else(|| {
+                self.has_failed_reads.store(true, Ordering::Release);
+                anyhow!("Cross shard value of {:?} is unavailable", state_key)
+            });
         }
-        self.base_view.get_state_value(state_key)
+        if let Some(value) = self.published_modules.read().unwrap().get(state_key) {
+            return Ok(value.clone());
+        }
+        self.get_base_value(state_key)
     }
 
     fn is_genesis(&self) -> bool {
@@ -128,15 +357,47 @@ impl<'a, S: StateView + Sync + Send> TStateView for CrossShardStateView<'a, S> {
     }
 }
 
+impl<'a, S> Drop for CrossShardStateView<'a, S> {
+    fn drop(&mut self) {
+        let shard_id = self.shard_id.to_string();
+        CROSS_SHARD_PREFETCHED_READS
+            .with_label_values(&[&shard_id, "hit"])
+            .inc_by(self.prefetch_hits.load(Ordering::Relaxed));
+        CROSS_SHARD_PREFETCHED_READS
+            .with_label_values(&[&shard_id, "miss"])
+            .inc_by(self.prefetch_misses.load(Ordering::Relaxed));
+    }
+}
+
+/// The base view of a cross shard state view, served from the prefetched values when possible.
+struct BaseStateView<'v, 'a, S>(&'v CrossShardStateView<'a, S>);
+
+impl<'v, 'a, S: StateView + Sync + Send> TStateView for BaseStateView<'v, 'a, S> {
+    type Key = StateKey;
+
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
+        self.0.get_base_value(state_key)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.0.base_view.is_genesis()
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage> {
+        self.0.base_view.get_usage()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::sharded_block_executor::cross_shard_state_view::CrossShardStateView;
+    use aptos_aggregator::delta_change_set::{delta_add, serialize};
     use aptos_state_view::{in_memory_state_view::InMemoryStateView, TStateView};
     use aptos_types::state_store::{state_key::StateKey, state_value::StateValue};
     use once_cell::sync::Lazy;
     use std::{
         collections::{HashMap, HashSet},
-        sync::Arc,
+        sync::{atomic::Ordering, Arc},
         thread,
         time::Duration,
     };
@@ -170,4 +431,207 @@ mod tests {
 
         wait_thread.join().unwrap();
     }
+
+    #[test]
+    fn test_cross_shard_state_view_prefetch_base_values() {
+        let base_key = StateKey::raw("key1".as_bytes().to_owned());
+        let base_value = StateValue::from("value1".as_bytes().to_owned());
+        let missing_key = StateKey::raw("key2".as_bytes().to_owned());
+        let cross_shard_key = StateKey::raw("key3".as_bytes().to_owned());
+        let base_view =
+            InMemoryStateView::new(HashMap::from([(base_key.clone(), base_value.clone())]));
+
+        let mut cross_shard_state_view =
+            CrossShardStateView::new(0, HashSet::from([cross_shard_key.clone()]), &base_view);
+        cross_shard_state_view
+            .prefetch_base_values(vec![
+                base_key.clone(),
+                missing_key.clone(),
+                cross_shard_key.clone(),
+            ])
+            .unwrap();
+        // Cross shard keys are never prefetched from the base view.
+        assert_eq!(cross_shard_state_view.prefetched_base_data.len(), 2);
+        assert_eq!(cross_shard_state_view.waiting_count(), 1);
+
+        assert_eq!(
+            cross_shard_state_view
+                .get_state_values(&[base_key, missing_key])
+                .unwrap(),
+            vec![Some(base_value), None]
+        );
+    }
+
+    #[test]
+    fn test_cross_shard_state_view_prefetch_cross_shard_base_values() {
+        let state_key = StateKey::raw("key1".as_bytes().to_owned());
+        let base_value = StateValue::from("value1".as_bytes().to_owned());
+        let other_key = StateKey::raw("key2".as_bytes().to_owned());
+        let base_view =
+            InMemoryStateView::new(HashMap::from([(state_key.clone(), base_value.clone())]));
+
+        let mut cross_shard_state_view =
+            CrossShardStateView::new(0, HashSet::from([state_key.clone()]), &base_view);
+        cross_shard_state_view
+            .prefetch_cross_shard_base_values()
+            .unwrap();
+        // The cross shard values are still waited for.
+        assert_eq!(cross_shard_state_view.waiting_count(), 1);
+
+        cross_shard_state_view.set_base_value(&state_key).unwrap();
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&state_key).unwrap(),
+            Some(base_value)
+        );
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&other_key).unwrap(),
+            None
+        );
+        assert_eq!(
+            cross_shard_state_view.prefetch_hits.load(Ordering::Relaxed),
+            1
+        );
+        assert_eq!(
+            cross_shard_state_view
+                .prefetch_misses
+                .load(Ordering::Relaxed),
+            1
+        );
+    }
+
+    #[test]
+    fn test_cross_shard_state_view_published_modules() {
+        let module_key = StateKey::raw("module1".as_bytes().to_owned());
+        let old_module = StateValue::from("old".as_bytes().to_owned());
+        let new_module = StateValue::from("new".as_bytes().to_owned());
+        let base_view =
+            InMemoryStateView::new(HashMap::from([(module_key.clone(), old_module.clone())]));
+        let cross_shard_state_view = CrossShardStateView::new(0, HashSet::new(), &base_view);
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&module_key).unwrap(),
+            Some(old_module)
+        );
+
+        // The modules published by other shards override the ones of the base view.
+        cross_shard_state_view.set_published_module(module_key.clone(), Some(new_module.clone()));
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&module_key).unwrap(),
+            Some(new_module)
+        );
+    }
+
+    #[test]
+    fn test_cross_shard_state_view_set_base_value() {
+        let state_key = StateKey::raw("key1".as_bytes().to_owned());
+        let base_value = StateValue::from("value1".as_bytes().to_owned());
+        let base_view =
+            InMemoryStateView::new(HashMap::from([(state_key.clone(), base_value.clone())]));
+        let cross_shard_state_view =
+            CrossShardStateView::new(0, HashSet::from([state_key.clone()]), &base_view);
+        assert_eq!(cross_shard_state_view.waiting_count(), 1);
+
+        cross_shard_state_view.set_base_value(&state_key).unwrap();
+        assert_eq!(cross_shard_state_view.waiting_count(), 0);
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&state_key).unwrap(),
+            Some(base_value)
+        );
+    }
+
+    #[test]
+    fn test_cross_shard_state_view_set_delta() {
+        let state_key = StateKey::raw("aggregator".as_bytes().to_owned());
+        let base_view = InMemoryStateView::new(HashMap::from([(
+            state_key.clone(),
+            StateValue::from(serialize(&100)),
+        )]));
+        let cross_shard_state_view =
+            CrossShardStateView::new(0, HashSet::from([state_key.clone()]), &base_view);
+
+        cross_shard_state_view
+            .set_delta(&state_key, delta_add(10, 1000))
+            .unwrap();
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&state_key).unwrap(),
+            Some(StateValue::from(serialize(&110)))
+        );
+    }
+
+    #[test]
+    fn test_cross_shard_state_view_fail_waiting_values() {
+        let ready_key = StateKey::raw("key1".as_bytes().to_owned());
+        let ready_value = StateValue::from("value1".as_bytes().to_owned());
+        let waiting_key = StateKey::raw("key2".as_bytes().to_owned());
+        let cross_shard_state_view = Arc::new(CrossShardStateView::new(
+            0,
+            HashSet::from([ready_key.clone(), waiting_key.clone()]),
+            &EMPTY_VIEW,
+        ));
+        cross_shard_state_view.set_value(&ready_key, Some(ready_value.clone()));
+
+        let cross_shard_state_view_clone = cross_shard_state_view.clone();
+        let waiting_key_clone = waiting_key.clone();
+        let wait_thread = thread::spawn(move || {
+            assert!(cross_shard_state_view_clone
+                .get_state_value(&waiting_key_clone)
+                .is_err());
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(cross_shard_state_view.fail_waiting_values(), 1);
+        wait_thread.join().unwrap();
+        assert!(cross_shard_state_view.has_failed_reads());
+        assert_eq!(cross_shard_state_view.waiting_count(), 0);
+        // A value arriving late is ignored.
+        cross_shard_state_view.set_value(&waiting_key, None);
+        assert_eq!(
+            cross_shard_state_view.get_state_value(&ready_key).unwrap(),
+            Some(ready_value)
+        );
+    }
+
+    #[test]
+    fn test_cross_shard_state_view_speculative_reads() {
+        let matching_key = StateKey::raw("key1".as_bytes().to_owned());
+        let mismatching_key = StateKey::raw("key2".as_bytes().to_owned());
+        let base_value = StateValue::from("value1".as_bytes().to_owned());
+        let base_view = InMemoryStateView::new(HashMap::from([
+            (matching_key.clone(), base_value.clone()),
+            (mismatching_key.clone(), base_value.clone()),
+        ]));
+        let cross_shard_state_view = CrossShardStateView::new(
+            0,
+            HashSet::from([matching_key.clone(), mismatching_key.clone()]),
+            &base_view,
+        );
+        cross_shard_state_view.set_speculative_reads(true);
+
+        // The values that did not arrive yet are read from the base view, without waiting.
+        assert_eq!(
+            cross_shard_state_view
+                .get_state_value(&matching_key)
+                .unwrap(),
+            Some(base_value.clone())
+        );
+        cross_shard_state_view.set_value(&matching_key, Some(base_value.clone()));
+        assert!(cross_shard_state_view.validate_speculative_reads());
+
+        assert_eq!(
+            cross_shard_state_view
+                .get_state_value(&mismatching_key)
+                .unwrap(),
+            Some(base_value)
+        );
+        cross_shard_state_view.set_value(&mismatching_key, None);
+        assert!(!cross_shard_state_view.validate_speculative_reads());
+
+        // Once arrived, the values are read as usual.
+        assert_eq!(
+            cross_shard_state_view
+                .get_state_value(&mismatching_key)
+                .unwrap(),
+            None
+        );
+        assert!(cross_shard_state_view.validate_speculative_reads());
+    }
 }
@@ -0,0 +1,118 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics of the cross shard dependencies, for the quality of the partitioning to be evaluated on
+//! real workloads: the number of writes each shard sends to each other one (i.e. the cross shard
+//! edge matrix), and the keys whose writes are sent to the most dependents.
+
+use crate::sharded_block_executor::counters::{CROSS_SHARD_EDGES, CROSS_SHARD_HOT_KEYS};
+use aptos_infallible::Mutex;
+use aptos_types::{
+    block_executor::partitioner::{RoundId, ShardId},
+    state_store::state_key::{StateKey, StateKeyInner},
+};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+
+/// Number of the hottest keys exported per shard and round.
+pub const NUM_HOT_KEYS: usize = 10;
+
+/// The label values of the hottest keys exported per shard and round, for their series to be
+/// removed once the keys are not among the hottest of the latest sub block anymore.
+static EXPORTED_HOT_KEYS: Lazy<Mutex<HashMap<(ShardId, RoundId), Vec<[String; 4]>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Exports the writes a shard sends for the sub block of a round, given the dependent shards and
+/// rounds of each key written by a transaction of the sub block.
+pub fn record_sub_block_dependencies<'a>(
+    shard_id: ShardId,
+    round: RoundId,
+    dependencies: impl IntoIterator<Item = (&'a StateKey, &'a HashSet<(ShardId, RoundId)>)>,
+) {
+    let mut num_writes_per_key: HashMap<&StateKey, u64> = HashMap::new();
+    let mut num_writes_per_dependent_shard: HashMap<ShardId, u64> = HashMap::new();
+    for (state_key, dependent_shard_ids) in dependencies {
+        *num_writes_per_key.entry(state_key).or_default() += dependent_shard_ids.len() as u64;
+        for (dependent_shard_id, _) in dependent_shard_ids {
+            *num_writes_per_dependent_shard
+                .entry(*dependent_shard_id)
+                .or_default() += 1;
+        }
+    }
+
+    let shard_id_label = shard_id.to_string();
+    for (dependent_shard_id, num_writes) in num_writes_per_dependent_shard {
+        CROSS_SHARD_EDGES
+            .with_label_values(&[&shard_id_label, &dependent_shard_id.to_string()])
+            .inc_by(num_writes);
+    }
+
+    let hot_keys: Vec<_> = hottest_keys(num_writes_per_key, NUM_HOT_KEYS)
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (state_key, num_writes))| {
+            let labels = [
+                shard_id_label.clone(),
+                round.to_string(),
+                rank.to_string(),
+                state_key_label(state_key),
+            ];
+            (labels, num_writes)
+        })
+        .collect();
+    let mut exported_hot_keys = EXPORTED_HOT_KEYS.lock();
+    let previous_hot_keys = exported_hot_keys
+        .insert(
+            (shard_id, round),
+            hot_keys.iter().map(|(labels, _)| labels.clone()).collect(),
+        )
+        .unwrap_or_default();
+    for labels in previous_hot_keys {
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        CROSS_SHARD_HOT_KEYS.remove_label_values(&labels).ok();
+    }
+    for (labels, num_writes) in hot_keys {
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        CROSS_SHARD_HOT_KEYS
+            .with_label_values(&labels)
+            .set(num_writes as i64);
+    }
+}
+
+/// Returns the `k` keys with the most writes, from the hottest one.
+fn hottest_keys<K: Ord>(num_writes_per_key: HashMap<K, u64>, k: usize) -> Vec<(K, u64)> {
+    let mut by_num_writes: Vec<_> = num_writes_per_key.into_iter().collect();
+    // Ties are broken by key, for the ranks to be deterministic.
+    by_num_writes.sort_unstable_by(|(key1, num_writes1), (key2, num_writes2)| {
+        num_writes2.cmp(num_writes1).then_with(|| key1.cmp(key2))
+    });
+    by_num_writes.truncate(k);
+    by_num_writes
+}
+
+fn state_key_label(state_key: &StateKey) -> String {
+    match state_key.inner() {
+        StateKeyInner::AccessPath(access_path) => access_path.to_string(),
+        StateKeyInner::TableItem { handle, key } => format!(
+            "TableItem {{ handle: {:x}, key: {} }}",
+            handle.0,
+            hex::encode(key)
+        ),
+        StateKeyInner::Raw(key) => hex::encode(key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hottest_keys() {
+        let num_writes_per_key = HashMap::from([(1, 3), (2, 5), (3, 1), (4, 5)]);
+        assert_eq!(
+            hottest_keys(num_writes_per_key.clone(), 3),
+            vec![(2, 5), (4, 5), (1, 3)]
+        );
+        assert_eq!(hottest_keys(num_writes_per_key, 10).len(), 4);
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_types::{contract_event::ContractEvent, transaction::TransactionOutput};
+use std::collections::BTreeMap;
+
+/// Merges the events streamed by the shards as they commit their transactions into the events of
+/// the block, in the order of the transactions in the block (i.e. the order in which unsharded
+/// execution would emit them). The events of a transaction are passed on as soon as the events of
+/// all the transactions before it have been received, so that they can be emitted before the
+/// whole block is executed.
+pub struct OrderedEventAssembler<F> {
+    next_txn_idx: usize,
+    // The events received ahead of the events of the transactions before them.
+    pending: BTreeMap<usize, Vec<ContractEvent>>,
+    on_events: F,
+}
+
+impl<F: FnMut(usize, &[ContractEvent])> OrderedEventAssembler<F> {
+    pub fn new(on_events: F) -> Self {
+        Self {
+            next_txn_idx: 0,
+            pending: BTreeMap::new(),
+            on_events,
+        }
+    }
+
+    pub fn add(&mut self, txn_idx: usize, events: Vec<ContractEvent>) {
+        if txn_idx < self.next_txn_idx {
+            return;
+        }
+        self.pending.insert(txn_idx, events);
+        while let Some(events) = self.pending.remove(&self.next_txn_idx) {
+            (self.on_events)(self.next_txn_idx, &events);
+            self.next_txn_idx += 1;
+        }
+    }
+
+    /// Passes on the events of the transactions that have not been passed on yet, from the
+    /// outputs of the block. Covers the transactions that were not committed (e.g. because the
+    /// block gas limit was reached), and executor clients that do not stream events.
+    pub fn finish(mut self, outputs: &[TransactionOutput]) {
+        for (txn_idx, output) in outputs.iter().enumerate().skip(self.next_txn_idx) {
+            (self.on_events)(txn_idx, output.events());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::{
+        transaction::{ExecutionStatus, TransactionStatus},
+        write_set::WriteSet,
+    };
+    use move_core_types::language_storage::TypeTag;
+
+    fn event(i: u8) -> ContractEvent {
+        ContractEvent::new_v2(TypeTag::U8, vec![i])
+    }
+
+    fn output(events: Vec<ContractEvent>) -> TransactionOutput {
+        TransactionOutput::new(
+            WriteSet::default(),
+            events,
+            0,
+            TransactionStatus::Keep(ExecutionStatus::Success),
+        )
+    }
+
+    #[test]
+    fn test_events_are_passed_on_in_order() {
+        let events: Vec<_> = (0..4).map(event).collect();
+        let mut received = vec![];
+        let mut assembler = OrderedEventAssembler::new(|txn_idx, events: &[ContractEvent]| {
+            received.push((txn_idx, events.to_vec()))
+        });
+
+        assembler.add(2, vec![events[2].clone()]);
+        assembler.add(1, vec![events[1].clone()]);
+        assembler.add(0, vec![events[0].clone()]);
+        // Transaction 3 was not committed, its events come from its output.
+        assembler.finish(&[
+            output(vec![]),
+            output(vec![]),
+            output(vec![]),
+            output(vec![events[3].clone()]),
+        ]);
+
+        assert_eq!(
+            received,
+            events
+                .into_iter()
+                .enumerate()
+                .map(|(txn_idx, event)| (txn_idx, vec![event]))
+                .collect::<Vec<_>>()
+        );
+    }
+}
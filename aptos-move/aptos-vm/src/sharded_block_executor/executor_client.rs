@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::sharded_block_executor::messages::CrossShardMsg;
 use aptos_state_view::StateView;
 use aptos_types::{
     block_executor::partitioner::SubBlocksForShard,
@@ -13,16 +14,40 @@ use std::sync::Arc;
 pub trait ExecutorClient<S: StateView + Sync + Send + 'static>: Send + Sync {
     fn num_shards(&self) -> usize;
 
-    // A non blocking call that sends the block to be executed by the executor shards.
+    // Replaces the executor shards, along with the channels between them, with `num_shards` new
+    // ones, for the number of shards to change between blocks.
+    fn set_num_shards(&mut self, num_shards: usize) {
+        assert_eq!(
+            num_shards,
+            self.num_shards(),
+            "The number of executor shards can not be changed"
+        );
+    }
+
+    // A non blocking call that sends the block to be executed by the executor shards. If
+    // `stream_events` is set, the shards stream the events of their committed transactions, to be
+    // received by `receive_coordinator_msg`.
     fn execute_block(
         &self,
         state_view: Arc<S>,
         block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
         concurrency_level_per_shard: usize,
         maybe_block_gas_limit: Option<u64>,
+        stream_events: bool,
     );
 
     // Blocking call that waits for the execution results from the executor shards. It returns the execution results
     // from each shard and in the sub-block order.
     fn get_execution_result(&self) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus>;
+
+    // Blocking call that waits for the next message streamed by the executor shards while they
+    // execute the block, i.e. the events of a committed transaction, or a stop message once a
+    // shard is done with the block. Returns None if the executor shards don't stream messages.
+    fn receive_coordinator_msg(&self) -> Option<CrossShardMsg> {
+        None
+    }
+
+    // Sets up the executor shards anew once a block failed, as the failed block may leave
+    // messages behind in the channels between them. It must be called before the next block.
+    fn reset_shards(&mut self) {}
 }
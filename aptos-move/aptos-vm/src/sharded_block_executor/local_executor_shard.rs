@@ -1,20 +1,33 @@
 // Copyright © Aptos Foundation
 
 use crate::sharded_block_executor::{
-    coordinator_client::CoordinatorClient, cross_shard_client::CrossShardClient,
-    executor_client::ExecutorClient, messages::CrossShardMsg,
-    sharded_executor_service::ShardedExecutorService, ExecutorShardCommand,
+    coordinator_client::CoordinatorClient,
+    counters::CROSS_SHARD_QUEUE_DEPTH,
+    cross_shard_client::CrossShardClient,
+    executor_client::ExecutorClient,
+    messages::CrossShardMsg,
+    sharded_executor_service::ShardedExecutorService,
+    simulated_network::{simulated_channel, SimulatedNetworkConfig},
+    ExecutorShardCommand,
 };
 use aptos_block_partitioner::sharded_block_partitioner::MAX_ALLOWED_PARTITIONING_ROUNDS;
-use aptos_logger::trace;
+use aptos_infallible::Mutex;
+use aptos_logger::{error, trace};
 use aptos_state_view::StateView;
 use aptos_types::{
     block_executor::partitioner::{RoundId, ShardId, SubBlocksForShard},
     transaction::{analyzed_transaction::AnalyzedTransaction, TransactionOutput},
 };
-use crossbeam_channel::{unbounded, Receiver, Sender};
-use move_core_types::vm_status::VMStatus;
-use std::{sync::Arc, thread};
+use crossbeam_channel::{Receiver, RecvError, RecvTimeoutError, Select, Sender};
+use move_core_types::vm_status::{StatusCode, VMStatus};
+use std::{sync::Arc, thread, time::Duration};
+
+/// Maximum number of cross shard messages queued for a shard and round, beyond which the sending
+/// shards are blocked.
+pub const CROSS_SHARD_CHANNEL_CAPACITY: usize = 1024;
+
+/// The outputs of the sub blocks of a shard, or the reason the shard failed.
+type ShardResult = Result<Vec<Vec<TransactionOutput>>, VMStatus>;
 
 /// Executor service that runs on local machine and waits for commands from the coordinator and executes
 /// them in parallel.
@@ -54,16 +67,34 @@ impl<S: StateView + Sync + Send + 'static> LocalExecutorService<S> {
         num_shards: usize,
         num_threads: Option<usize>,
     ) -> LocalExecutorClient<S> {
-        let num_threads = num_threads
+        Self::setup_local_executor_shards_with_network(
+            num_shards,
+            num_threads,
+            SimulatedNetworkConfig::default(),
+        )
+    }
+
+    /// Sets up the shards with all the messages between them and with the coordinator delayed as
+    /// if the shards were remote, per `network`.
+    pub fn setup_local_executor_shards_with_network(
+        num_shards: usize,
+        num_threads: Option<usize>,
+        network: SimulatedNetworkConfig,
+    ) -> LocalExecutorClient<S> {
+        let num_threads_per_shard = num_threads
             .unwrap_or_else(|| (num_cpus::get() as f64 / num_shards as f64).ceil() as usize);
         let (command_txs, command_rxs): (
             Vec<Sender<ExecutorShardCommand<S>>>,
             Vec<Receiver<ExecutorShardCommand<S>>>,
-        ) = (0..num_shards).map(|_| unbounded()).unzip();
+        ) = (0..num_shards)
+            .map(|shard_id| simulated_channel(network, &format!("command-{}", shard_id), None))
+            .unzip();
         let (result_txs, result_rxs): (
             Vec<Sender<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
             Vec<Receiver<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
-        ) = (0..num_shards).map(|_| unbounded()).unzip();
+        ) = (0..num_shards)
+            .map(|shard_id| simulated_channel(network, &format!("result-{}", shard_id), None))
+            .unzip();
         // We need to create channels for each shard and each round. This is needed because individual
         // shards might send cross shard messages to other shards that will be consumed in different rounds.
         // Having a single channel per shard will cause a shard to receiver messages that is not intended in the current round.
@@ -71,31 +102,47 @@ impl<S: StateView + Sync + Send + 'static> LocalExecutorService<S> {
             Vec<Vec<Sender<CrossShardMsg>>>,
             Vec<Vec<Receiver<CrossShardMsg>>>,
         ) = (0..num_shards)
-            .map(|_| {
+            .map(|shard_id| {
                 (0..MAX_ALLOWED_PARTITIONING_ROUNDS)
-                    .map(|_| unbounded())
+                    .map(|round| {
+                        simulated_channel(
+                            network,
+                            &format!("cross-shard-{}-{}", shard_id, round),
+                            Some(CROSS_SHARD_CHANNEL_CAPACITY),
+                        )
+                    })
                     .unzip()
             })
             .unzip();
+        // The channel for the shards to stream the events of their committed transactions to the
+        // coordinator.
+        let (coordinator_msg_tx, coordinator_msg_rx) =
+            simulated_channel(network, "coordinator", None);
         let executor_shards = command_rxs
             .into_iter()
             .zip(result_txs.into_iter())
             .zip(cross_shard_msg_rxs.into_iter())
             .enumerate()
             .map(|(shard_id, ((command_rx, result_tx), cross_shard_rxs))| {
-                let cross_shard_client =
-                    LocalCrossShardClient::new(cross_shard_msg_txs.clone(), cross_shard_rxs);
+                let cross_shard_client = LocalCrossShardClient::new(
+                    shard_id as ShardId,
+                    cross_shard_msg_txs.clone(),
+                    cross_shard_rxs,
+                    coordinator_msg_tx.clone(),
+                );
                 Self::new(
                     shard_id as ShardId,
                     num_shards,
-                    num_threads,
+                    num_threads_per_shard,
                     command_rx,
                     result_tx,
                     cross_shard_client,
                 )
             })
             .collect();
-        LocalExecutorClient::new(command_txs, result_rxs, executor_shards)
+        LocalExecutorClient::new(command_txs, result_rxs, coordinator_msg_rx, executor_shards)
+            .with_setup(num_threads, network)
+            .with_cross_shard_msg_txs(cross_shard_msg_txs)
     }
 }
 
@@ -104,22 +151,117 @@ pub struct LocalExecutorClient<S: StateView + Sync + Send + 'static> {
     command_txs: Vec<Sender<ExecutorShardCommand<S>>>,
     // Channels to receive execution results from the executor shards.
     result_rxs: Vec<Receiver<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
+    // Channel to receive the messages streamed by the executor shards.
+    coordinator_msg_rx: Receiver<CrossShardMsg>,
 
     executor_services: Vec<LocalExecutorService<S>>,
+    // Channels to send cross shard messages to the executor shards per round, to abort the block
+    // once a shard failed.
+    cross_shard_msg_txs: Vec<Vec<Sender<CrossShardMsg>>>,
+    // The results of the shards received so far for the block being executed.
+    results: Mutex<Vec<Option<ShardResult>>>,
+    // How the shards were set up, for them to be set up the same way when resized.
+    num_threads: Option<usize>,
+    network: SimulatedNetworkConfig,
 }
 
 impl<S: StateView + Sync + Send + 'static> LocalExecutorClient<S> {
     pub fn new(
         command_tx: Vec<Sender<ExecutorShardCommand<S>>>,
         result_rx: Vec<Receiver<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
+        coordinator_msg_rx: Receiver<CrossShardMsg>,
         executor_shards: Vec<LocalExecutorService<S>>,
     ) -> Self {
+        let num_shards = command_tx.len();
         Self {
             command_txs: command_tx,
             result_rxs: result_rx,
+            coordinator_msg_rx,
             executor_services: executor_shards,
+            cross_shard_msg_txs: vec![],
+            results: Mutex::new((0..num_shards).map(|_| None).collect()),
+            num_threads: None,
+            network: SimulatedNetworkConfig::default(),
+        }
+    }
+
+    fn with_setup(mut self, num_threads: Option<usize>, network: SimulatedNetworkConfig) -> Self {
+        self.num_threads = num_threads;
+        self.network = network;
+        self
+    }
+
+    fn with_cross_shard_msg_txs(
+        mut self,
+        cross_shard_msg_txs: Vec<Vec<Sender<CrossShardMsg>>>,
+    ) -> Self {
+        self.cross_shard_msg_txs = cross_shard_msg_txs;
+        self
+    }
+
+    /// Records the result of a shard. At the first failure of a shard, the other shards are told
+    /// to abort the block, so that they don't wait on the values of the failed shard.
+    fn set_result(
+        &self,
+        results: &mut [Option<ShardResult>],
+        shard_id: ShardId,
+        result: ShardResult,
+    ) {
+        if let Err(err) = &result {
+            if !results.iter().any(|result| matches!(result, Some(Err(_)))) {
+                error!("Shard {} failed, aborting the block: {:?}", shard_id, err);
+                self.abort_shards();
+            }
         }
+        results[shard_id] = Some(result);
     }
+
+    fn abort_shards(&self) {
+        for cross_shard_msg_txs in &self.cross_shard_msg_txs {
+            for cross_shard_msg_tx in cross_shard_msg_txs {
+                // The receivers of the rounds with a full channel are already busy applying
+                // writes, and drain it before waiting on any value.
+                let _ = cross_shard_msg_tx.try_send(CrossShardMsg::AbortMsg);
+            }
+        }
+    }
+
+    /// Waits for the next result among the shards without one yet, or for the next coordinator
+    /// message if `coordinator_msg_rx` is given, which is then returned.
+    fn select(
+        &self,
+        results: &mut [Option<ShardResult>],
+        coordinator_msg_rx: Option<&Receiver<CrossShardMsg>>,
+    ) -> Option<Result<CrossShardMsg, RecvError>> {
+        let pending_shards: Vec<_> = (0..results.len())
+            .filter(|shard_id| results[*shard_id].is_none())
+            .collect();
+        let mut select = Select::new();
+        for shard_id in &pending_shards {
+            select.recv(&self.result_rxs[*shard_id]);
+        }
+        if let Some(coordinator_msg_rx) = coordinator_msg_rx {
+            select.recv(coordinator_msg_rx);
+        }
+        let oper = select.select();
+        match pending_shards.get(oper.index()) {
+            Some(shard_id) => {
+                let result = oper
+                    .recv(&self.result_rxs[*shard_id])
+                    .unwrap_or_else(|_| Err(shard_gone(*shard_id)));
+                self.set_result(results, *shard_id, result);
+                None
+            },
+            None => Some(oper.recv(coordinator_msg_rx.expect("Must be selected"))),
+        }
+    }
+}
+
+fn shard_gone(shard_id: ShardId) -> VMStatus {
+    VMStatus::error(
+        StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+        Some(format!("Executor shard {} is gone", shard_id)),
+    )
 }
 
 impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for LocalExecutorClient<S> {
@@ -127,36 +269,75 @@ impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for LocalExecutorCl
         self.command_txs.len()
     }
 
+    fn set_num_shards(&mut self, num_shards: usize) {
+        if num_shards == self.num_shards() {
+            return;
+        }
+        // The previous shards are stopped once replaced. Without a fixed number of threads, the
+        // cores are split between the new shards.
+        *self = LocalExecutorService::setup_local_executor_shards_with_network(
+            num_shards,
+            self.num_threads,
+            self.network,
+        );
+    }
+
     fn execute_block(
         &self,
         state_view: Arc<S>,
         block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
         concurrency_level_per_shard: usize,
         maybe_block_gas_limit: Option<u64>,
+        stream_events: bool,
     ) {
         assert_eq!(block.len(), self.num_shards());
+        let mut results = self.results.lock();
         for (i, sub_blocks_for_shard) in block.into_iter().enumerate() {
-            self.command_txs[i]
-                .send(ExecutorShardCommand::ExecuteSubBlocks(
-                    state_view.clone(),
-                    sub_blocks_for_shard,
-                    concurrency_level_per_shard,
-                    maybe_block_gas_limit,
-                ))
-                .unwrap();
+            let command = ExecutorShardCommand::ExecuteSubBlocks(
+                state_view.clone(),
+                sub_blocks_for_shard,
+                concurrency_level_per_shard,
+                maybe_block_gas_limit,
+                stream_events,
+            );
+            if self.command_txs[i].send(command).is_err() {
+                self.set_result(&mut results, i, Err(shard_gone(i)));
+            }
         }
     }
 
     fn get_execution_result(&self) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus> {
         trace!("LocalExecutorClient Waiting for results");
-        let mut results = vec![];
-        for (i, rx) in self.result_rxs.iter().enumerate() {
-            results.push(
-                rx.recv()
-                    .unwrap_or_else(|_| panic!("Did not receive output from shard {}", i))?,
-            );
+        let mut results = self.results.lock();
+        // The results are received as they come, for a failure to abort the block right away.
+        while results.iter().any(Option::is_none) {
+            self.select(&mut results, None);
         }
-        Ok(results)
+        results
+            .iter_mut()
+            .map(|result| result.take().expect("Must be received"))
+            .collect()
+    }
+
+    fn receive_coordinator_msg(&self) -> Option<CrossShardMsg> {
+        let mut results = self.results.lock();
+        // Once a shard failed, the messages of the block are moot, and the failed shard may never
+        // send its stop message.
+        while !results.iter().any(|result| matches!(result, Some(Err(_)))) {
+            if let Some(msg) = self.select(&mut results, Some(&self.coordinator_msg_rx)) {
+                return msg.ok();
+            }
+        }
+        None
+    }
+
+    fn reset_shards(&mut self) {
+        // The abort messages of the failed block may still be queued for the shards.
+        *self = LocalExecutorService::setup_local_executor_shards_with_network(
+            self.num_shards(),
+            self.num_threads,
+            self.network,
+        );
     }
 }
 
@@ -202,30 +383,72 @@ impl<S: StateView + Sync + Send + 'static> CoordinatorClient<S> for LocalCoordin
 }
 
 pub struct LocalCrossShardClient {
+    shard_id: ShardId,
     // The senders of cross-shard messages to other shards per round.
     message_txs: Vec<Vec<Sender<CrossShardMsg>>>,
     // The receivers of cross shard messages from other shards per round.
     message_rxs: Vec<Receiver<CrossShardMsg>>,
+    // The sender of messages streamed to the coordinator.
+    coordinator_tx: Sender<CrossShardMsg>,
 }
 
 impl LocalCrossShardClient {
     pub fn new(
+        shard_id: ShardId,
         cross_shard_txs: Vec<Vec<Sender<CrossShardMsg>>>,
         cross_shard_rxs: Vec<Receiver<CrossShardMsg>>,
+        coordinator_tx: Sender<CrossShardMsg>,
     ) -> Self {
         Self {
+            shard_id,
             message_txs: cross_shard_txs,
             message_rxs: cross_shard_rxs,
+            coordinator_tx,
         }
     }
 }
 
 impl CrossShardClient for LocalCrossShardClient {
-    fn send_cross_shard_msg(&self, shard_id: ShardId, round: RoundId, msg: CrossShardMsg) {
-        self.message_txs[shard_id][round].send(msg).unwrap()
+    fn send_cross_shard_msg(
+        &self,
+        shard_id: ShardId,
+        round: RoundId,
+        msg: CrossShardMsg,
+    ) -> anyhow::Result<()> {
+        let message_tx = &self.message_txs[shard_id][round];
+        message_tx.send(msg).map_err(|_| {
+            anyhow::anyhow!(
+                "The channel to shard {} for round {} is closed",
+                shard_id,
+                round
+            )
+        })?;
+        set_queue_depth(shard_id, round, message_tx.len());
+        Ok(())
     }
 
-    fn receive_cross_shard_msg(&self, current_round: RoundId) -> CrossShardMsg {
-        self.message_rxs[current_round].recv().unwrap()
+    fn receive_cross_shard_msg(
+        &self,
+        current_round: RoundId,
+        timeout: Duration,
+    ) -> Result<CrossShardMsg, RecvTimeoutError> {
+        let message_rx = &self.message_rxs[current_round];
+        let msg = message_rx.recv_timeout(timeout)?;
+        set_queue_depth(self.shard_id, current_round, message_rx.len());
+        Ok(msg)
+    }
+
+    fn streams_to_coordinator(&self) -> bool {
+        true
     }
+
+    fn send_coordinator_msg(&self, msg: CrossShardMsg) {
+        self.coordinator_tx.send(msg).unwrap()
+    }
+}
+
+fn set_queue_depth(shard_id: ShardId, round: RoundId, queue_depth: usize) {
+    CROSS_SHARD_QUEUE_DEPTH
+        .with_label_values(&[&shard_id.to_string(), &round.to_string()])
+        .set(queue_depth as i64);
 }
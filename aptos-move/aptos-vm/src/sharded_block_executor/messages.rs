@@ -1,30 +1,189 @@
 // Copyright © Aptos Foundation
 
-use aptos_types::{state_store::state_key::StateKey, write_set::WriteOp};
+use aptos_aggregator::delta_change_set::DeltaOp;
+use aptos_compression::{metrics::CompressionClient, CompressedData};
+use aptos_mvhashmap::types::TxnIndex;
+use aptos_types::{
+    contract_event::ContractEvent,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    write_set::WriteOp,
+};
 use serde::{Deserialize, Serialize};
 
+/// Maximum size of a batch of writes, compressed or not.
+const MAX_TXN_WRITES_BYTES: usize = 256 * 1024 * 1024;
+
+/// The priority of a batch of writes, i.e. the length of the longest chain of cross shard
+/// dependencies they unblock (see `SubBlocksForShard::set_dependent_chain_lengths`). The batches
+/// with the highest priority are sent and processed first.
+pub type CrossShardMsgPriority = usize;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum CrossShardMsg {
-    RemoteTxnWriteMsg(RemoteTxnWrite),
+    // The writes of a committed transaction to the storage locations a shard depends on, batched
+    // into a single message per dependent shard and round, along with their priority.
+    RemoteTxnWriteMsg(Vec<RemoteTxnWrite>, CrossShardMsgPriority),
+    // A batch of writes compressed with LZ4, trading CPU for the bandwidth to the remote shards.
+    CompressedRemoteTxnWriteMsg(CompressedData, CrossShardMsgPriority),
+    // Sent by the shards to the coordinator, as their transactions are committed.
+    RemoteEventMsg(RemoteTxnEvents),
     StopMsg,
+    // Sent by the coordinator once a shard failed, for the other shards to give up on the values
+    // they wait on and abort the block rather than hang.
+    AbortMsg,
+}
+
+impl CrossShardMsg {
+    pub fn compressed_txn_writes(
+        txn_writes: &[RemoteTxnWrite],
+        priority: CrossShardMsgPriority,
+    ) -> Self {
+        let bytes = bcs::to_bytes(txn_writes).expect("Failed to serialize the writes");
+        Self::CompressedRemoteTxnWriteMsg(
+            aptos_compression::compress(
+                bytes,
+                CompressionClient::ShardedExecution,
+                MAX_TXN_WRITES_BYTES,
+            )
+            .expect("Failed to compress the writes"),
+            priority,
+        )
+    }
+
+    /// The priority of the message. The stop and event messages have the lowest priority, so
+    /// that the stop message of a round is only processed after the writes received before it,
+    /// while the abort message has the highest, as the writes are moot once the block is aborted.
+    pub fn priority(&self) -> CrossShardMsgPriority {
+        match self {
+            Self::RemoteTxnWriteMsg(_, priority)
+            | Self::CompressedRemoteTxnWriteMsg(_, priority) => *priority,
+            Self::RemoteEventMsg(_) | Self::StopMsg => 0,
+            Self::AbortMsg => CrossShardMsgPriority::MAX,
+        }
+    }
+
+    pub fn decompress_txn_writes(compressed_txn_writes: &CompressedData) -> Vec<RemoteTxnWrite> {
+        let bytes = aptos_compression::decompress(
+            compressed_txn_writes,
+            CompressionClient::ShardedExecution,
+            MAX_TXN_WRITES_BYTES,
+        )
+        .expect("Failed to decompress the writes");
+        bcs::from_bytes(&bytes).expect("Failed to deserialize the writes")
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RemoteTxnWrite {
+    // The sequence number of the write, i.e. the index in the block of the transaction it is from,
+    // for the receivers to drop the writes delivered more than once (e.g. as a send is retried).
+    seq_num: TxnIndex,
     state_key: StateKey,
-    // The write op is None if the transaction is aborted.
-    write_op: Option<WriteOp>,
+    // The write op is None if the value of the key could not be determined by the source shard,
+    // in which case the value before the block is used (and the source shard aborts the block).
+    write_op: Option<RemoteWriteOp>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum RemoteWriteOp {
+    Write(WriteOp),
+    // The aggregator delta of the transaction, for the dependent shard to apply it to the value
+    // before the block rather than receive the materialized value.
+    Delta(DeltaOp),
+    // The value of the key before the transaction, as seen by its shard, if the transaction did
+    // not write the key (e.g. as it was aborted, or its write hints over-approximate its writes).
+    Value(Option<StateValue>),
 }
 
 impl RemoteTxnWrite {
-    pub fn new(state_key: StateKey, write_op: Option<WriteOp>) -> Self {
+    pub fn new(seq_num: TxnIndex, state_key: StateKey, write_op: Option<WriteOp>) -> Self {
+        Self {
+            seq_num,
+            state_key,
+            write_op: write_op.map(RemoteWriteOp::Write),
+        }
+    }
+
+    pub fn new_delta(seq_num: TxnIndex, state_key: StateKey, delta_op: DeltaOp) -> Self {
+        Self {
+            seq_num,
+            state_key,
+            write_op: Some(RemoteWriteOp::Delta(delta_op)),
+        }
+    }
+
+    pub fn new_value(
+        seq_num: TxnIndex,
+        state_key: StateKey,
+        state_value: Option<StateValue>,
+    ) -> Self {
         Self {
+            seq_num,
             state_key,
-            write_op,
+            write_op: Some(RemoteWriteOp::Value(state_value)),
         }
     }
 
-    pub fn take(self) -> (StateKey, Option<WriteOp>) {
+    pub fn seq_num(&self) -> TxnIndex {
+        self.seq_num
+    }
+
+    pub fn take(self) -> (StateKey, Option<RemoteWriteOp>) {
         (self.state_key, self.write_op)
     }
 }
+
+/// The events emitted by a committed transaction, identified by its index in the block.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RemoteTxnEvents {
+    txn_idx: usize,
+    events: Vec<ContractEvent>,
+}
+
+impl RemoteTxnEvents {
+    pub fn new(txn_idx: usize, events: Vec<ContractEvent>) -> Self {
+        Self { txn_idx, events }
+    }
+
+    pub fn take(self) -> (usize, Vec<ContractEvent>) {
+        (self.txn_idx, self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_aggregator::delta_change_set::delta_add;
+
+    #[test]
+    fn test_compressed_txn_writes() {
+        let txn_writes: Vec<_> = (0..100)
+            .map(|i| {
+                let state_key = StateKey::raw(format!("key_{}", i).into_bytes());
+                match i % 3 {
+                    0 => RemoteTxnWrite::new(i, state_key, None),
+                    1 => RemoteTxnWrite::new(
+                        i,
+                        state_key,
+                        Some(WriteOp::Modification(vec![i as u8; 64])),
+                    ),
+                    _ => RemoteTxnWrite::new_delta(i, state_key, delta_add(i as u128, 1000)),
+                }
+            })
+            .collect();
+        let msg = CrossShardMsg::compressed_txn_writes(&txn_writes, 3);
+        assert_eq!(msg.priority(), 3);
+        let compressed_txn_writes = match &msg {
+            CrossShardMsg::CompressedRemoteTxnWriteMsg(compressed, _) => compressed,
+            _ => panic!("Expected a compressed message"),
+        };
+        assert!(compressed_txn_writes.len() < bcs::to_bytes(&txn_writes).unwrap().len());
+
+        let decompressed_txn_writes = CrossShardMsg::decompress_txn_writes(compressed_txn_writes);
+        assert_eq!(decompressed_txn_writes.len(), txn_writes.len());
+        for (decompressed, original) in decompressed_txn_writes.into_iter().zip(txn_writes) {
+            assert_eq!(decompressed.seq_num(), original.seq_num());
+            assert_eq!(decompressed.take(), original.take());
+        }
+    }
+}
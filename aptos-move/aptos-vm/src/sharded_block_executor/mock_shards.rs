@@ -0,0 +1,382 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-memory shards running the cross shard commit senders and receivers without the executor.
+//!
+//! The sub blocks of the shards are scripted: each transaction is either committed with the given
+//! writes or aborted, and the dependency edges between the transactions are given explicitly. Each
+//! shard runs its rounds in order, committing (or aborting) a transaction once the cross shard
+//! values it requires are resolved, in place of executing it. Once all the rounds are done, the
+//! contents of the cross shard state views of the shards are returned to be asserted on.
+
+use crate::{
+    block_executor::AptosTransactionOutput,
+    sharded_block_executor::{
+        cross_shard_client::{
+            send_cross_shard_msg_with_retry, CrossShardBatchConfig, CrossShardClient,
+            CrossShardCommitReceiver, CrossShardCommitSender, DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT,
+        },
+        cross_shard_state_view::CrossShardStateView,
+        local_executor_shard::LocalCrossShardClient,
+        messages::CrossShardMsg,
+    },
+};
+use aptos_block_executor::{
+    task::TransactionOutput as BlockExecutorTransactionOutput,
+    txn_commit_hook::TransactionCommitHook,
+};
+use aptos_crypto::HashValue;
+use aptos_mvhashmap::types::TxnIndex;
+use aptos_state_view::in_memory_state_view::InMemoryStateView;
+use aptos_types::{
+    block_executor::partitioner::{
+        CrossShardDependencies, RoundId, ShardId, ShardedTxnIndex, SubBlock, SubBlocksForShard,
+        TransactionWithDependencies,
+    },
+    fee_statement::FeeStatement,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{
+        analyzed_transaction::{AnalyzedTransaction, StorageLocation},
+        ExecutionStatus, Transaction, TransactionStatus,
+    },
+    write_set::WriteOp,
+};
+use aptos_vm_types::{change_set::VMChangeSet, check_change_set::CheckChangeSet, output::VMOutput};
+use crossbeam_channel::unbounded;
+use move_core_types::vm_status::VMStatus;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    thread,
+};
+
+/// A transaction of a scripted sub block.
+pub struct ScriptedTxn {
+    // The writes of the transaction, or None if it is aborted.
+    writes: Option<Vec<(StateKey, WriteOp)>>,
+}
+
+impl ScriptedTxn {
+    pub fn committed(writes: Vec<(StateKey, WriteOp)>) -> Self {
+        Self {
+            writes: Some(writes),
+        }
+    }
+
+    pub fn aborted() -> Self {
+        Self { writes: None }
+    }
+
+    fn analyzed_txn(&self) -> AnalyzedTransaction {
+        let write_hints = self
+            .writes
+            .iter()
+            .flatten()
+            .map(|(state_key, _)| StorageLocation::Specific(state_key.clone()))
+            .collect();
+        AnalyzedTransaction::new(
+            Transaction::StateCheckpoint(HashValue::zero()),
+            vec![],
+            write_hints,
+        )
+    }
+}
+
+/// A scripted transaction, by its shard, round and position in the sub block.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ScriptedTxnId {
+    pub shard_id: ShardId,
+    pub round: RoundId,
+    pub position: usize,
+}
+
+/// The contents of the cross shard state view of a shard for a round, once the round is done.
+#[derive(Debug)]
+pub struct CrossShardViewContents {
+    pub values: HashMap<StateKey, Option<StateValue>>,
+    pub published_modules: HashMap<StateKey, Option<StateValue>>,
+    pub has_failed_reads: bool,
+}
+
+pub struct MockShards {
+    num_shards: usize,
+    base_view: InMemoryStateView,
+    // The scripted transactions per shard and round.
+    scripted_txns: Vec<Vec<Vec<ScriptedTxn>>>,
+    // The dependency edges, from the source to the target transaction, by key.
+    edges: Vec<(ScriptedTxnId, ScriptedTxnId, StateKey)>,
+    batch_config: CrossShardBatchConfig,
+}
+
+impl MockShards {
+    pub fn new(num_shards: usize, base_values: HashMap<StateKey, StateValue>) -> Self {
+        Self {
+            num_shards,
+            base_view: InMemoryStateView::new(base_values),
+            scripted_txns: (0..num_shards).map(|_| vec![]).collect(),
+            edges: vec![],
+            batch_config: CrossShardBatchConfig::default(),
+        }
+    }
+
+    pub fn with_batch_config(mut self, batch_config: CrossShardBatchConfig) -> Self {
+        self.batch_config = batch_config;
+        self
+    }
+
+    /// Appends a transaction to the sub block of the shard for the round.
+    pub fn add_txn(
+        &mut self,
+        shard_id: ShardId,
+        round: RoundId,
+        txn: ScriptedTxn,
+    ) -> ScriptedTxnId {
+        let sub_blocks = &mut self.scripted_txns[shard_id];
+        if sub_blocks.len() <= round {
+            sub_blocks.resize_with(round + 1, Vec::new);
+        }
+        sub_blocks[round].push(txn);
+        ScriptedTxnId {
+            shard_id,
+            round,
+            position: sub_blocks[round].len() - 1,
+        }
+    }
+
+    /// Makes the target transaction wait for the value of the key after the source transaction.
+    /// As with the partitioner, the source transaction must be of another shard, and precede the
+    /// target one in the block, i.e. be of an earlier round or of an earlier shard in the round.
+    pub fn add_edge(&mut self, source: ScriptedTxnId, target: ScriptedTxnId, state_key: StateKey) {
+        assert_ne!(source.shard_id, target.shard_id, "Not a cross shard edge");
+        assert!(
+            (source.round, source.shard_id) < (target.round, target.shard_id),
+            "The source transaction must precede the target one"
+        );
+        self.edges.push((source, target, state_key));
+    }
+
+    /// Runs all the rounds of the shards, and returns the contents of their cross shard state
+    /// views, per shard and round.
+    pub fn run(self) -> Vec<Vec<CrossShardViewContents>> {
+        let num_shards = self.num_shards;
+        let num_rounds = self
+            .scripted_txns
+            .iter()
+            .map(|sub_blocks| sub_blocks.len())
+            .max()
+            .unwrap_or_default();
+        let mut scripted_txns = self.scripted_txns;
+        for sub_blocks in scripted_txns.iter_mut() {
+            sub_blocks.resize_with(num_rounds, Vec::new);
+        }
+
+        // The transactions are indexed in the block round by round, and shard by shard within a
+        // round, as the outputs of the executor are.
+        let mut start_indices = vec![vec![0; num_rounds]; num_shards];
+        let mut num_txns = 0;
+        for round in 0..num_rounds {
+            for (shard_id, txns_per_round) in scripted_txns.iter().enumerate() {
+                start_indices[shard_id][round] = num_txns;
+                num_txns += txns_per_round[round].len();
+            }
+        }
+        let mut block: Vec<_> = scripted_txns
+            .iter()
+            .enumerate()
+            .map(|(shard_id, txns_per_round)| {
+                let sub_blocks = txns_per_round
+                    .iter()
+                    .enumerate()
+                    .map(|(round, txns)| {
+                        let txns = txns
+                            .iter()
+                            .map(|txn| {
+                                TransactionWithDependencies::new(
+                                    txn.analyzed_txn(),
+                                    CrossShardDependencies::default(),
+                                )
+                            })
+                            .collect();
+                        SubBlock::new(start_indices[shard_id][round], txns)
+                    })
+                    .collect();
+                SubBlocksForShard::new(shard_id, sub_blocks)
+            })
+            .collect();
+        for (source, target, state_key) in self.edges {
+            let storage_location = StorageLocation::Specific(state_key);
+            let source_idx = ShardedTxnIndex::new(
+                start_indices[source.shard_id][source.round] + source.position,
+                source.shard_id,
+                source.round,
+            );
+            let target_idx = ShardedTxnIndex::new(
+                start_indices[target.shard_id][target.round] + target.position,
+                target.shard_id,
+                target.round,
+            );
+            block[target.shard_id].sub_blocks[target.round].transactions[target.position]
+                .cross_shard_dependencies
+                .add_required_edge(source_idx.clone(), storage_location.clone());
+            block[source.shard_id].sub_blocks[source.round].add_dependent_edge(
+                source_idx.txn_index,
+                target_idx,
+                vec![storage_location],
+            );
+        }
+        SubBlocksForShard::set_dependent_chain_lengths(&mut block);
+
+        let (message_txs, message_rxs): (Vec<Vec<_>>, Vec<Vec<_>>) = (0..num_shards)
+            .map(|_| (0..num_rounds).map(|_| unbounded()).unzip())
+            .unzip();
+        // The events streamed to the coordinator are dropped once the shards are done.
+        let (coordinator_tx, _coordinator_rx) = unbounded();
+        let base_view = &self.base_view;
+        let batch_config = self.batch_config;
+        thread::scope(|s| {
+            let shards: Vec<_> = block
+                .into_iter()
+                .zip(scripted_txns)
+                .zip(message_rxs)
+                .enumerate()
+                .map(|(shard_id, ((sub_blocks, scripted_txns), message_rxs))| {
+                    let cross_shard_client: Arc<dyn CrossShardClient> =
+                        Arc::new(LocalCrossShardClient::new(
+                            shard_id,
+                            message_txs.clone(),
+                            message_rxs,
+                            coordinator_tx.clone(),
+                        ));
+                    s.spawn(move || {
+                        run_shard(
+                            shard_id,
+                            base_view,
+                            sub_blocks.into_sub_blocks(),
+                            scripted_txns,
+                            cross_shard_client,
+                            batch_config,
+                        )
+                    })
+                })
+                .collect();
+            shards
+                .into_iter()
+                .map(|shard| shard.join().expect("Mock shard panicked"))
+                .collect()
+        })
+    }
+}
+
+/// Runs the rounds of a shard in order, as the executor shard does, committing the scripted
+/// transactions instead of executing them.
+fn run_shard(
+    shard_id: ShardId,
+    base_view: &InMemoryStateView,
+    sub_blocks: Vec<SubBlock<AnalyzedTransaction>>,
+    scripted_txns: Vec<Vec<ScriptedTxn>>,
+    cross_shard_client: Arc<dyn CrossShardClient>,
+    batch_config: CrossShardBatchConfig,
+) -> Vec<CrossShardViewContents> {
+    let cross_shard_state_views: Vec<_> = sub_blocks
+        .iter()
+        .map(|sub_block| {
+            let cross_shard_keys = sub_block.iter().flat_map(required_keys).collect();
+            Arc::new(CrossShardStateView::new(
+                shard_id,
+                cross_shard_keys,
+                base_view,
+            ))
+        })
+        .collect();
+    thread::scope(|s| {
+        for (round, cross_shard_state_view) in cross_shard_state_views.iter().enumerate() {
+            let cross_shard_state_view = cross_shard_state_view.clone();
+            let cross_shard_client = cross_shard_client.clone();
+            s.spawn(move || {
+                CrossShardCommitReceiver::start(
+                    shard_id,
+                    cross_shard_state_view,
+                    cross_shard_client,
+                    round,
+                    DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT,
+                )
+            });
+        }
+        for (round, (sub_block, txns)) in sub_blocks.iter().zip(scripted_txns).enumerate() {
+            let cross_shard_commit_sender = CrossShardCommitSender::new(
+                shard_id,
+                round,
+                cross_shard_client.clone(),
+                sub_block,
+                cross_shard_state_views[round].as_ref(),
+                batch_config,
+            );
+            for (position, (txn_with_deps, txn)) in sub_block.iter().zip(txns).enumerate() {
+                // The transaction is committed once the values it reads are resolved, and aborted
+                // if one of them is unavailable.
+                let values_available = cross_shard_state_views[round]
+                    .wait_for_values(required_keys(txn_with_deps).iter())
+                    .is_ok();
+                match txn.writes {
+                    Some(writes) if values_available => cross_shard_commit_sender
+                        .on_transaction_committed(position as TxnIndex, &committed_output(writes)),
+                    _ => cross_shard_commit_sender.on_execution_aborted(position as TxnIndex),
+                }
+            }
+            // Flushes the writes still held back.
+            drop(cross_shard_commit_sender);
+            send_cross_shard_msg_with_retry(
+                cross_shard_client.as_ref(),
+                shard_id,
+                (shard_id, round),
+                CrossShardMsg::StopMsg,
+            )
+            .expect("Failed to stop the cross shard commit receiver");
+        }
+    });
+    cross_shard_state_views
+        .iter()
+        .map(|cross_shard_state_view| CrossShardViewContents {
+            values: cross_shard_state_view.resolved_values(),
+            published_modules: cross_shard_state_view.published_modules(),
+            has_failed_reads: cross_shard_state_view.has_failed_reads(),
+        })
+        .collect()
+}
+
+fn required_keys(txn: &TransactionWithDependencies<AnalyzedTransaction>) -> Vec<StateKey> {
+    txn.cross_shard_dependencies
+        .required_edges_iter()
+        .flat_map(|(_, storage_locations)| storage_locations.iter())
+        .map(|storage_location| storage_location.clone().into_state_key())
+        .collect()
+}
+
+struct NoOpChangeSetChecker;
+
+impl CheckChangeSet for NoOpChangeSetChecker {
+    fn check_change_set(&self, _change_set: &VMChangeSet) -> anyhow::Result<(), VMStatus> {
+        Ok(())
+    }
+}
+
+/// The committed output of a transaction with the given writes. As the commit sender tells the
+/// modules apart by their keys, all the writes are put in the resource write set.
+fn committed_output(writes: Vec<(StateKey, WriteOp)>) -> AptosTransactionOutput {
+    let change_set = VMChangeSet::new(
+        writes.into_iter().collect(),
+        BTreeMap::new(),
+        BTreeMap::new(),
+        BTreeMap::new(),
+        vec![],
+        &NoOpChangeSetChecker,
+    )
+    .expect("Change set must be valid");
+    let txn_output = AptosTransactionOutput::new(VMOutput::new(
+        change_set,
+        FeeStatement::zero(),
+        TransactionStatus::Keep(ExecutionStatus::Success),
+    ));
+    txn_output.incorporate_delta_writes(vec![]);
+    txn_output
+}
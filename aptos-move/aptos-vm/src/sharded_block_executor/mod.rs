@@ -3,25 +3,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::sharded_block_executor::{
-    counters::NUM_EXECUTOR_SHARDS, executor_client::ExecutorClient,
+    counters::NUM_EXECUTOR_SHARDS, event_assembler::OrderedEventAssembler,
+    executor_client::ExecutorClient, messages::CrossShardMsg,
 };
 use aptos_logger::{info, trace};
 use aptos_state_view::StateView;
 use aptos_types::{
     block_executor::partitioner::SubBlocksForShard,
+    contract_event::ContractEvent,
     transaction::{analyzed_transaction::AnalyzedTransaction, TransactionOutput},
 };
 use move_core_types::vm_status::VMStatus;
 use std::{marker::PhantomData, sync::Arc};
 
 pub mod coordinator_client;
-mod counters;
+pub mod counters;
 pub mod cross_shard_client;
 mod cross_shard_state_view;
+pub mod cross_shard_stats;
+pub mod event_assembler;
 pub mod executor_client;
 pub mod local_executor_shard;
 pub mod messages;
+#[cfg(test)]
+mod mock_shards;
 pub mod sharded_executor_service;
+pub mod simulated_network;
 #[cfg(test)]
 mod test_utils;
 #[cfg(test)]
@@ -39,6 +46,8 @@ pub enum ExecutorShardCommand<S> {
         SubBlocksForShard<AnalyzedTransaction>,
         usize,
         Option<u64>,
+        // Whether to stream the events of the committed transactions to the coordinator.
+        bool,
     ),
     Stop,
 }
@@ -59,6 +68,28 @@ impl<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>> ShardedBlockExe
         self.executor_client.num_shards()
     }
 
+    /// Changes the number of shards the blocks are executed with, e.g. to match the number of
+    /// shards the partitioner picked for the next block given the load of the shards (see
+    /// `aptos_types::block_executor::shard_load`). The shards and the channels between them are
+    /// set up anew, so this is best done only when the number of shards changes.
+    pub fn set_num_shards(&mut self, num_shards: usize) {
+        if num_shards != self.num_shards() {
+            info!(
+                "Changing the number of executor shards from {} to {}",
+                self.num_shards(),
+                num_shards
+            );
+            self.executor_client.set_num_shards(num_shards);
+        }
+    }
+
+    /// Sets up the shards anew, which is required after a block failed (e.g. as a shard panicked)
+    /// before executing the next one.
+    pub fn reset_shards(&mut self) {
+        info!("Resetting the {} executor shards", self.num_shards());
+        self.executor_client.reset_shards();
+    }
+
     /// Execute a block of transactions in parallel by splitting the block into num_remote_executors partitions and
     /// dispatching each partition to a remote executor shard.
     pub fn execute_block(
@@ -67,6 +98,47 @@ impl<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>> ShardedBlockExe
         block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
         concurrency_level_per_shard: usize,
         maybe_block_gas_limit: Option<u64>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        self.execute_block_impl(
+            state_view,
+            block,
+            concurrency_level_per_shard,
+            maybe_block_gas_limit,
+            None::<fn(usize, &[ContractEvent])>,
+        )
+    }
+
+    /// Same as `execute_block`, but also passes the events of each transaction of the block to
+    /// `on_events`, in the order of the transactions in the block. If the executor client streams
+    /// the events, they are passed on as the shards commit the transactions, before the whole
+    /// block is executed. If a shard fails, the other shards abort the block and the error of the
+    /// failed shard is returned, after which `reset_shards` must be called.
+    pub fn execute_block_with_events(
+        &self,
+        state_view: Arc<S>,
+        block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+        concurrency_level_per_shard: usize,
+        maybe_block_gas_limit: Option<u64>,
+        on_events: impl FnMut(usize, &[ContractEvent]),
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        self.execute_block_impl(
+            state_view,
+            block,
+            concurrency_level_per_shard,
+            maybe_block_gas_limit,
+            Some(on_events),
+        )
+    }
+
+    /// The shards only stream (and thus copy) the events of their transactions if there is an
+    /// `on_events` to pass them on to.
+    fn execute_block_impl(
+        &self,
+        state_view: Arc<S>,
+        block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+        concurrency_level_per_shard: usize,
+        maybe_block_gas_limit: Option<u64>,
+        on_events: Option<impl FnMut(usize, &[ContractEvent])>,
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
         let num_executor_shards = self.executor_client.num_shards();
         NUM_EXECUTOR_SHARDS.set(num_executor_shards as i64);
@@ -76,12 +148,36 @@ impl<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>> ShardedBlockExe
             "Block must be partitioned into {} sub-blocks",
             num_executor_shards
         );
+        let mut block = block;
+        SubBlocksForShard::set_dependent_chain_lengths(&mut block);
         self.executor_client.execute_block(
             state_view,
             block,
             concurrency_level_per_shard,
             maybe_block_gas_limit,
+            on_events.is_some(),
         );
+        let mut event_assembler = on_events.map(OrderedEventAssembler::new);
+        if let Some(event_assembler) = event_assembler.as_mut() {
+            let mut num_done_shards = 0;
+            while num_done_shards < num_executor_shards {
+                match self.executor_client.receive_coordinator_msg() {
+                    Some(CrossShardMsg::RemoteEventMsg(msg)) => {
+                        let (txn_idx, events) = msg.take();
+                        event_assembler.add(txn_idx, events);
+                    },
+                    Some(CrossShardMsg::StopMsg) => num_done_shards += 1,
+                    Some(
+                        CrossShardMsg::RemoteTxnWriteMsg(..)
+                        | CrossShardMsg::CompressedRemoteTxnWriteMsg(..)
+                        | CrossShardMsg::AbortMsg,
+                    ) => {
+                        unreachable!("Writes and aborts are only sent to the shards")
+                    },
+                    None => break,
+                }
+            }
+        }
         // wait for all remote executors to send the result back and append them in order by shard id
         let results = self.executor_client.get_execution_result()?;
         trace!("ShardedBlockExecutor Received all results");
@@ -97,6 +193,9 @@ impl<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>> ShardedBlockExe
         for result in ordered_results.into_iter() {
             aggreate_results.extend(result);
         }
+        if let Some(event_assembler) = event_assembler {
+            event_assembler.finish(&aggreate_results);
+        }
 
         Ok(aggreate_results)
     }
@@ -4,22 +4,69 @@ use crate::{
     block_executor::BlockAptosVM,
     sharded_block_executor::{
         coordinator_client::CoordinatorClient,
-        counters::SHARDED_BLOCK_EXECUTION_SECONDS,
-        cross_shard_client::{CrossShardClient, CrossShardCommitReceiver, CrossShardCommitSender},
+        counters::{SHARDED_BLOCK_EXECUTION_SECONDS, SHARD_BLOCK_EXECUTION_SECONDS},
+        cross_shard_client::{
+            send_cross_shard_msg_with_retry, CrossShardClient, CrossShardCommitReceiver,
+            CrossShardCommitSender,
+        },
         cross_shard_state_view::CrossShardStateView,
         messages::CrossShardMsg,
         ExecutorShardCommand,
     },
+    AptosVM,
 };
-use aptos_logger::{info, trace};
+use aptos_logger::{error, info, trace, warn, LogContext};
 use aptos_state_view::StateView;
 use aptos_types::{
-    block_executor::partitioner::{ShardId, SubBlock, SubBlocksForShard},
-    transaction::{analyzed_transaction::AnalyzedTransaction, TransactionOutput},
+    block_executor::{
+        partitioner::{RoundId, ShardId, SubBlock, SubBlocksForShard},
+        shard_load::SHARD_LOAD,
+    },
+    state_store::state_key::StateKeyInner,
+    transaction::{
+        analyzed_transaction::{AnalyzedTransaction, StorageLocation},
+        TransactionOutput,
+    },
+};
+use move_core_types::vm_status::{StatusCode, VMStatus};
+use std::{
+    any::Any,
+    collections::HashSet,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+    time::Instant,
 };
-use futures::{channel::oneshot, executor::block_on};
-use move_core_types::vm_status::VMStatus;
-use std::{collections::HashSet, sync::Arc};
+
+/// Whether the storage location is the package registry of an account, which transactions
+/// publishing modules write.
+fn is_package_registry(storage_location: &StorageLocation) -> bool {
+    match storage_location {
+        StorageLocation::Specific(state_key) => match state_key.inner() {
+            StateKeyInner::AccessPath(access_path) => {
+                *storage_location
+                    == AnalyzedTransaction::package_registry_location(access_path.address)
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Turns the panic of a shard into an error, for the coordinator to be told that the block failed
+/// rather than wait for the shard forever.
+fn panic_to_vm_status(panic: Box<dyn Any + Send>) -> VMStatus {
+    let panic_msg = panic
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    error!("Shard panicked while executing the block: {}", panic_msg);
+    VMStatus::error(
+        StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+        Some(format!("Shard panicked: {}", panic_msg)),
+    )
+}
 
 pub struct ShardedExecutorService<S: StateView + Sync + Send + 'static> {
     shard_id: ShardId,
@@ -39,9 +86,8 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
     ) -> Self {
         let executor_thread_pool = Arc::new(
             rayon::ThreadPoolBuilder::new()
-                // We need two extra threads for the cross-shard commit receiver and the thread
-                // that is blocked on waiting for execute block to finish.
-                .num_threads(num_threads + 2)
+                .num_threads(num_threads)
+                .start_handler(move |_| LogContext::default().shard_id(shard_id).set())
                 .build()
                 .unwrap(),
         );
@@ -67,68 +113,123 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                 }
             }
         }
-        CrossShardStateView::new(self.shard_id, cross_shard_state_key, base_view)
+        let mut cross_shard_state_view =
+            CrossShardStateView::new(self.shard_id, cross_shard_state_key, base_view);
+        cross_shard_state_view.set_speculative_reads(AptosVM::get_speculative_cross_shard_reads());
+        // Batch the reads of the (hinted) keys that are local to the shard.
+        let local_state_keys = sub_block
+            .transactions
+            .iter()
+            .flat_map(|txn| txn.txn().read_hints().iter())
+            .filter_map(|storage_location| match storage_location {
+                StorageLocation::Specific(state_key) => Some(state_key.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if let Err(err) = cross_shard_state_view.prefetch_base_values(local_state_keys) {
+            warn!("Failed to prefetch state values: {:?}", err);
+        }
+        // The base values of the cross shard keys are read while the remote shards execute.
+        if let Err(err) = cross_shard_state_view.prefetch_cross_shard_base_values() {
+            warn!("Failed to prefetch cross shard base values: {:?}", err);
+        }
+        cross_shard_state_view
     }
 
     fn execute_sub_block(
         &self,
         sub_block: SubBlock<AnalyzedTransaction>,
-        round: usize,
-        state_view: &S,
+        round: RoundId,
+        cross_shard_state_view: &CrossShardStateView<S>,
         concurrency_level: usize,
         maybe_block_gas_limit: Option<u64>,
+        stream_events: bool,
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
-        trace!(
-            "executing sub block for shard {} and round {}",
+        trace!("executing sub block");
+        // The modules published before the sub block in the block are only broadcast to the
+        // shard along with the package registries of their publishers.
+        let package_registry_keys: HashSet<_> = sub_block
+            .iter()
+            .flat_map(|txn| txn.cross_shard_dependencies.required_edges_iter())
+            .flat_map(|(_, storage_locations)| storage_locations.iter())
+            .filter(|storage_location| is_package_registry(storage_location))
+            .map(|storage_location| storage_location.state_key())
+            .collect();
+        if let Err(err) = cross_shard_state_view.wait_for_values(package_registry_keys) {
+            error!(
+                "Published modules are unavailable, aborting the block: {:?}",
+                err
+            );
+            return Err(VMStatus::error(
+                StatusCode::STORAGE_ERROR,
+                Some("Cross shard dependencies are unavailable".to_string()),
+            ));
+        }
+        let speculative = cross_shard_state_view.speculative_reads_enabled();
+        let mut cross_shard_commit_sender = CrossShardCommitSender::new(
             self.shard_id,
-            round
+            round,
+            self.cross_shard_client.clone(),
+            &sub_block,
+            cross_shard_state_view,
+            AptosVM::get_cross_shard_batch_config(),
         );
-        let cross_shard_commit_sender =
-            CrossShardCommitSender::new(self.shard_id, self.cross_shard_client.clone(), &sub_block);
-
-        let (callback, callback_receiver) = oneshot::channel();
-
-        let cross_shard_state_view =
-            Arc::new(self.create_cross_shard_state_view(state_view, &sub_block));
-        let cross_shard_state_view_clone = cross_shard_state_view.clone();
-        let cross_shard_client = self.cross_shard_client.clone();
-        let cross_shard_client_clone = cross_shard_client.clone();
-        self.executor_thread_pool.scope(|s| {
-            s.spawn(move |_| {
-                CrossShardCommitReceiver::start(
-                    cross_shard_state_view_clone,
-                    cross_shard_client,
-                    round,
-                );
-            });
-            s.spawn(move |_| {
-                let ret = BlockAptosVM::execute_block(
-                    self.executor_thread_pool.clone(),
-                    sub_block
-                        .into_txns()
-                        .into_iter()
-                        .map(|txn| txn.into_txn())
-                        .collect(),
-                    cross_shard_state_view.as_ref(),
-                    concurrency_level,
-                    maybe_block_gas_limit,
-                    Some(cross_shard_commit_sender),
-                );
-                trace!(
-                    "executed sub block for shard {} and round {}",
-                    self.shard_id,
-                    round
-                );
-                // Send a self message to stop the cross-shard commit receiver.
-                cross_shard_client_clone.send_cross_shard_msg(
-                    self.shard_id,
-                    round,
-                    CrossShardMsg::StopMsg,
-                );
-                callback.send(ret).unwrap();
-            });
-        });
-        block_on(callback_receiver).unwrap()
+        if stream_events {
+            cross_shard_commit_sender = cross_shard_commit_sender.with_event_streaming();
+        }
+        if speculative {
+            // The writes and events are only sent once the speculative reads are validated.
+            cross_shard_commit_sender = cross_shard_commit_sender.with_hold_back();
+        }
+        let transactions: Vec<_> = sub_block
+            .into_txns()
+            .into_iter()
+            .map(|txn| txn.into_txn())
+            .collect();
+        let maybe_priority_hints = BlockAptosVM::priority_hints(&transactions);
+        let execute = |transactions| {
+            BlockAptosVM::execute_block_with_scheduler_config(
+                self.executor_thread_pool.clone(),
+                transactions,
+                cross_shard_state_view,
+                concurrency_level,
+                maybe_block_gas_limit,
+                // The block output limit applies to the whole block, so it is applied once the
+                // outputs of all the shards are collected.
+                None,
+                Some(&cross_shard_commit_sender),
+                AptosVM::get_scheduler_config(),
+                maybe_priority_hints.clone(),
+            )
+            .map(|(outputs, _)| outputs)
+        };
+        let ret = if speculative {
+            let ret = execute(transactions.clone());
+            cross_shard_state_view.set_speculative_reads(false);
+            if cross_shard_state_view.validate_speculative_reads() {
+                ret
+            } else {
+                info!("Speculative cross shard reads mismatched, re-executing the sub block");
+                cross_shard_commit_sender.discard_held_back();
+                execute(transactions)
+            }
+        } else {
+            execute(transactions)
+        };
+        // Sends the writes and events still held back.
+        drop(cross_shard_commit_sender);
+        trace!("executed sub block");
+        // A transaction read a cross shard value that never arrived, so its output is bogus.
+        if cross_shard_state_view.has_failed_reads() {
+            error!("Cross shard dependencies are unavailable, aborting the block");
+            return Err(VMStatus::error(
+                StatusCode::STORAGE_ERROR,
+                Some("Cross shard dependencies are unavailable".to_string()),
+            ));
+        }
+        ret
     }
 
     fn execute_block(
@@ -137,40 +238,107 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
         state_view: &S,
         concurrency_level: usize,
         maybe_block_gas_limit: Option<u64>,
+        stream_events: bool,
     ) -> Result<Vec<Vec<TransactionOutput>>, VMStatus> {
-        let mut result = vec![];
-        for (round, sub_block) in transactions.into_sub_blocks().into_iter().enumerate() {
-            let _timer = SHARDED_BLOCK_EXECUTION_SECONDS
-                .with_label_values(&[&self.shard_id.to_string(), &round.to_string()])
-                .start_timer();
-            info!(
-                "executing sub block for shard {} and round {}, number of txns {}",
-                self.shard_id,
-                round,
-                sub_block.transactions.len()
-            );
-            result.push(self.execute_sub_block(
-                sub_block,
-                round,
-                state_view,
-                concurrency_level,
-                maybe_block_gas_limit,
-            )?);
-            trace!(
-                "Finished executing sub block for shard {} and round {}",
-                self.shard_id,
-                round
-            );
-        }
-        Ok(result)
+        let _timer = SHARD_BLOCK_EXECUTION_SECONDS
+            .with_label_values(&[&self.shard_id.to_string()])
+            .start_timer();
+        let sub_blocks = transactions.into_sub_blocks();
+        // The cross shard state views of all the rounds are created upfront, and their commit
+        // receivers started along, so that the writes sent by other shards are applied as soon as
+        // they are received, even for the later rounds. The cross shard channels of the shard are
+        // thus always drained, and the shards blocked on sending to them (once full) can not
+        // deadlock with the shard.
+        let cross_shard_state_views: Vec<_> = sub_blocks
+            .iter()
+            .map(|sub_block| Arc::new(self.create_cross_shard_state_view(state_view, sub_block)))
+            .collect();
+        let receive_timeout = AptosVM::get_cross_shard_receive_timeout();
+        thread::scope(|scope| {
+            for (round, cross_shard_state_view) in cross_shard_state_views.iter().enumerate() {
+                let log_context = LogContext::current().round(round);
+                let cross_shard_state_view = cross_shard_state_view.clone();
+                let cross_shard_client = self.cross_shard_client.clone();
+                thread::Builder::new()
+                    .name(format!("cross-shard-receiver-{}-{}", self.shard_id, round))
+                    .spawn_scoped(scope, move || {
+                        let _log_context = log_context.enter();
+                        let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+                            CrossShardCommitReceiver::start(
+                                self.shard_id,
+                                cross_shard_state_view.clone(),
+                                cross_shard_client,
+                                round,
+                                receive_timeout,
+                            )
+                        }));
+                        if ret.is_err() {
+                            // The values the round waits on will never be received.
+                            let num_failed = cross_shard_state_view.fail_waiting_values();
+                            error!(
+                                "Cross shard commit receiver panicked, giving up on {} values",
+                                num_failed
+                            );
+                        }
+                    })
+                    .expect("Failed to spawn the cross shard commit receiver");
+            }
+
+            let mut result = Ok(vec![]);
+            for (round, (sub_block, cross_shard_state_view)) in sub_blocks
+                .into_iter()
+                .zip(cross_shard_state_views.iter())
+                .enumerate()
+            {
+                // Once a round fails, the later ones are skipped, but their receivers still need
+                // to be stopped.
+                if let Ok(outputs) = &mut result {
+                    let _timer = SHARDED_BLOCK_EXECUTION_SECONDS
+                        .with_label_values(&[&self.shard_id.to_string(), &round.to_string()])
+                        .start_timer();
+                    let _log_context = LogContext::current().round(round).enter();
+                    info!(
+                        "executing sub block, number of txns {}",
+                        sub_block.transactions.len()
+                    );
+                    // A panic fails the block, but the receivers are still stopped below.
+                    match panic::catch_unwind(AssertUnwindSafe(|| {
+                        self.execute_sub_block(
+                            sub_block,
+                            round,
+                            cross_shard_state_view,
+                            concurrency_level,
+                            maybe_block_gas_limit,
+                            stream_events,
+                        )
+                    }))
+                    .unwrap_or_else(|panic| Err(panic_to_vm_status(panic)))
+                    {
+                        Ok(sub_block_outputs) => outputs.push(sub_block_outputs),
+                        Err(err) => result = Err(err),
+                    }
+                    trace!("Finished executing sub block");
+                }
+                // Send a self message to stop the cross-shard commit receiver of the round.
+                if let Err(err) = send_cross_shard_msg_with_retry(
+                    self.cross_shard_client.as_ref(),
+                    self.shard_id,
+                    (self.shard_id, round),
+                    CrossShardMsg::StopMsg,
+                ) {
+                    error!(
+                        "Failed to stop the cross shard commit receiver of round {}: {:?}",
+                        round, err
+                    );
+                }
+            }
+            result
+        })
     }
 
     pub fn start(&self) {
-        trace!(
-            "Shard starting, shard_id={}, num_shards={}.",
-            self.shard_id,
-            self.num_shards
-        );
+        LogContext::current().shard_id(self.shard_id).set();
+        trace!("Shard starting, num_shards={}.", self.num_shards);
         loop {
             let command = self.coordinator_client.receive_execute_command();
             match command {
@@ -179,19 +347,38 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                     transactions,
                     concurrency_level_per_shard,
                     maybe_block_gas_limit,
+                    stream_events,
                 ) => {
+                    // Events are only streamed if the coordinator asked for them, and can
+                    // receive them.
+                    let stream_events =
+                        stream_events && self.cross_shard_client.streams_to_coordinator();
                     trace!(
-                        "Shard {} received ExecuteBlock command of block size {} ",
-                        self.shard_id,
+                        "Received ExecuteBlock command of block size {} ",
                         transactions.num_txns()
                     );
-                    let ret = self.execute_block(
-                        transactions,
-                        state_view.as_ref(),
-                        concurrency_level_per_shard,
-                        maybe_block_gas_limit,
+                    let execution_start_time = Instant::now();
+                    let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+                        self.execute_block(
+                            transactions,
+                            state_view.as_ref(),
+                            concurrency_level_per_shard,
+                            maybe_block_gas_limit,
+                            stream_events,
+                        )
+                    }))
+                    .unwrap_or_else(|panic| Err(panic_to_vm_status(panic)));
+                    SHARD_LOAD.record_execution_time(
+                        self.shard_id,
+                        self.num_shards,
+                        execution_start_time.elapsed(),
                     );
                     drop(state_view);
+                    if stream_events {
+                        // Tell the coordinator that there are no more events for this block.
+                        self.cross_shard_client
+                            .send_coordinator_msg(CrossShardMsg::StopMsg);
+                    }
                     self.coordinator_client.send_execution_result(ret);
                 },
                 ExecutorShardCommand::Stop => {
@@ -199,6 +386,6 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                 },
             }
         }
-        trace!("Shard {} is shutting down", self.shard_id);
+        trace!("Shard is shutting down");
     }
 }
@@ -0,0 +1,141 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Channels delaying their messages by a configurable latency and jitter, standing in for the
+//! network between the coordinator and remote shards. This allows benchmarking how sharded
+//! execution behaves with remote shards while running all the shards in-process, without
+//! provisioning a cluster.
+
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender};
+use rand::{rngs::ThreadRng, Rng};
+use std::{
+    collections::VecDeque,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SimulatedNetworkConfig {
+    /// One way delay of each message.
+    pub latency: Duration,
+    /// Maximum extra delay of each message, drawn uniformly. Messages are still delivered in
+    /// order, like over a TCP connection.
+    pub jitter: Duration,
+}
+
+impl SimulatedNetworkConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero()
+    }
+
+    fn delay(&self, rng: &mut ThreadRng) -> Duration {
+        let jitter_nanos = rng.gen_range(0, self.jitter.as_nanos() as u64 + 1);
+        self.latency + Duration::from_nanos(jitter_nanos)
+    }
+}
+
+/// Creates a channel (holding at most `capacity` messages if bounded) whose messages are
+/// delivered after the delay of the simulated network. Without any delay, this is a plain
+/// channel, otherwise the messages are relayed by a thread named after `name`, which exits once
+/// all the senders are dropped and the messages in flight are delivered. The relay holds at most
+/// `capacity` messages in flight as well, so that senders still get backpressure.
+pub fn simulated_channel<T: Send + 'static>(
+    config: SimulatedNetworkConfig,
+    name: &str,
+    capacity: Option<usize>,
+) -> (Sender<T>, Receiver<T>) {
+    let new_channel = || capacity.map_or_else(unbounded, bounded);
+    let (inbound_tx, inbound_rx) = new_channel();
+    if !config.is_enabled() {
+        return (inbound_tx, inbound_rx);
+    }
+    let (outbound_tx, outbound_rx) = new_channel();
+    let max_in_flight = capacity.unwrap_or(usize::MAX).max(1);
+    thread::Builder::new()
+        .name(format!("sim-net-{}", name))
+        .spawn(move || relay(config, inbound_rx, outbound_tx, max_in_flight))
+        .expect("Failed to spawn the thread of the simulated network");
+    (inbound_tx, outbound_rx)
+}
+
+fn relay<T>(
+    config: SimulatedNetworkConfig,
+    inbound: Receiver<T>,
+    outbound: Sender<T>,
+    max_in_flight: usize,
+) {
+    let mut rng = rand::thread_rng();
+    // Messages with their delivery time, which is non-decreasing to keep the messages in order.
+    let mut in_flight: VecDeque<(Instant, T)> = VecDeque::new();
+    let mut inbound_open = true;
+    loop {
+        let received = match in_flight.front() {
+            Some((deliver_at, _)) if *deliver_at <= Instant::now() => {
+                let (_, msg) = in_flight.pop_front().expect("Must exist");
+                if outbound.send(msg).is_err() {
+                    // Nobody is listening anymore.
+                    return;
+                }
+                continue;
+            },
+            Some((deliver_at, _)) if inbound_open && in_flight.len() < max_in_flight => {
+                match inbound.recv_deadline(*deliver_at) {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        inbound_open = false;
+                        continue;
+                    },
+                }
+            },
+            Some((deliver_at, _)) => {
+                thread::sleep(deliver_at.saturating_duration_since(Instant::now()));
+                continue;
+            },
+            None => match inbound.recv() {
+                Ok(msg) => msg,
+                Err(_) => return,
+            },
+        };
+        let mut deliver_at = Instant::now() + config.delay(&mut rng);
+        if let Some((last_deliver_at, _)) = in_flight.back() {
+            deliver_at = deliver_at.max(*last_deliver_at);
+        }
+        in_flight.push_back((deliver_at, received));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_channel() {
+        let config = SimulatedNetworkConfig {
+            latency: Duration::from_millis(50),
+            jitter: Duration::from_millis(20),
+        };
+        let (tx, rx) = simulated_channel(config, "test", None);
+        let start_time = Instant::now();
+        for i in 0..100 {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+
+        assert_eq!(rx.recv().unwrap(), 0);
+        assert!(start_time.elapsed() >= config.latency);
+        for i in 1..100 {
+            assert_eq!(rx.recv().unwrap(), i);
+        }
+        // The relay exits once the messages are delivered.
+        assert!(rx.recv().is_err());
+        assert!(start_time.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let (tx, rx) = simulated_channel(SimulatedNetworkConfig::default(), "test", Some(1));
+        tx.send(1).unwrap();
+        assert_eq!(rx.try_recv().unwrap(), 1);
+    }
+}
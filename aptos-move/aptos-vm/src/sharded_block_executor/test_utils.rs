@@ -1,25 +1,44 @@
 // Copyright © Aptos Foundation
 
 use crate::{
-    sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor},
+    block_executor::{AptosTransactionOutput, BlockAptosVM},
+    sharded_block_executor::{
+        cross_shard_client::DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT, executor_client::ExecutorClient,
+        ShardedBlockExecutor,
+    },
     AptosVM, VMExecutor,
 };
+use anyhow::Result;
+use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
 use aptos_block_partitioner::sharded_block_partitioner::ShardedBlockPartitioner;
 use aptos_crypto::hash::CryptoHash;
 use aptos_language_e2e_tests::{
-    account::AccountData, common_transactions::peer_to_peer_txn, data_store::FakeDataStore,
+    account::AccountData,
+    chaos_state_view::{ChaosConfig, ChaosStateView},
+    common_transactions::peer_to_peer_txn,
+    data_store::FakeDataStore,
     executor::FakeExecutor,
 };
+use aptos_state_view::{StateViewId, TStateView};
 use aptos_types::{
     block_executor::partitioner::SubBlocksForShard,
-    state_store::state_key::StateKeyInner,
-    transaction::{analyzed_transaction::AnalyzedTransaction, Transaction, TransactionOutput},
+    state_store::{
+        state_key::{StateKey, StateKeyInner},
+        state_storage_usage::StateStorageUsage,
+        state_value::StateValue,
+    },
+    transaction::{
+        analyzed_transaction::{AnalyzedTransaction, StorageLocation},
+        Transaction, TransactionOutput,
+    },
+    vm_status::VMStatus,
 };
 use move_core_types::account_address::AccountAddress;
 use rand::{rngs::OsRng, Rng};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 pub fn generate_account_at(executor: &mut FakeExecutor, address: AccountAddress) -> AccountData {
@@ -131,6 +150,77 @@ pub fn test_sharded_block_executor_no_conflict<E: ExecutorClient<FakeDataStore>>
     compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
 }
 
+/// Executes a block with each of the given numbers of shards in turn, with the same executor.
+pub fn sharded_block_executor_with_changing_num_shards<E: ExecutorClient<FakeDataStore>>(
+    mut sharded_block_executor: ShardedBlockExecutor<FakeDataStore, E>,
+    nums_shards: &[usize],
+) {
+    let num_txns = 200;
+    let mut executor = FakeExecutor::from_head_genesis();
+    for num_shards in nums_shards {
+        sharded_block_executor.set_num_shards(*num_shards);
+        assert_eq!(sharded_block_executor.num_shards(), *num_shards);
+        let mut transactions = Vec::new();
+        for _ in 0..num_txns {
+            transactions.push(generate_non_conflicting_p2p(&mut executor).0)
+        }
+        let partitioner = ShardedBlockPartitioner::new(*num_shards);
+        let partitioned_txns = partitioner.partition(transactions.clone(), 2, 0.9);
+        let sharded_txn_output = sharded_block_executor
+            .execute_block(
+                Arc::new(executor.data_store().clone()),
+                partitioned_txns,
+                2,
+                None,
+            )
+            .unwrap();
+        let unsharded_txn_output = AptosVM::execute_block(
+            transactions.into_iter().map(|t| t.into_txn()).collect(),
+            &executor.data_store(),
+            None,
+        )
+        .unwrap();
+        compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
+    }
+}
+
+pub fn sharded_block_executor_with_ordered_events<E: ExecutorClient<FakeDataStore>>(
+    sharded_block_executor: ShardedBlockExecutor<FakeDataStore, E>,
+) {
+    let num_txns = 200;
+    let num_shards = sharded_block_executor.num_shards();
+    let mut executor = FakeExecutor::from_head_genesis();
+    let mut transactions = Vec::new();
+    for _ in 0..num_txns {
+        transactions.push(generate_non_conflicting_p2p(&mut executor).0)
+    }
+    let partitioner = ShardedBlockPartitioner::new(num_shards);
+    let partitioned_txns = partitioner.partition(transactions.clone(), 2, 0.9);
+    let mut streamed_events = vec![];
+    let sharded_txn_output = sharded_block_executor
+        .execute_block_with_events(
+            Arc::new(executor.data_store().clone()),
+            partitioned_txns,
+            2,
+            None,
+            |txn_idx, events| streamed_events.push((txn_idx, events.to_vec())),
+        )
+        .unwrap();
+    let unsharded_txn_output = AptosVM::execute_block(
+        transactions.into_iter().map(|t| t.into_txn()).collect(),
+        &executor.data_store(),
+        None,
+    )
+    .unwrap();
+    let unsharded_events: Vec<_> = unsharded_txn_output
+        .iter()
+        .map(|output| output.events().to_vec())
+        .enumerate()
+        .collect();
+    assert_eq!(streamed_events, unsharded_events);
+    compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
+}
+
 pub fn sharded_block_executor_with_conflict<E: ExecutorClient<FakeDataStore>>(
     sharded_block_executor: ShardedBlockExecutor<FakeDataStore, E>,
     concurrency: usize,
@@ -230,3 +320,220 @@ pub fn sharded_block_executor_with_random_transfers<E: ExecutorClient<FakeDataSt
         AptosVM::execute_block(execution_ordered_txns, &executor.data_store(), None).unwrap();
     compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
 }
+
+pub fn sharded_block_executor_with_slow_storage<
+    E: ExecutorClient<ChaosStateView<FakeDataStore>>,
+>(
+    sharded_block_executor: ShardedBlockExecutor<ChaosStateView<FakeDataStore>, E>,
+    concurrency: usize,
+) {
+    let num_txns = 100;
+    let num_shards = sharded_block_executor.num_shards();
+    let mut executor = FakeExecutor::from_head_genesis();
+    let mut transactions = Vec::new();
+    for _ in 0..num_txns {
+        transactions.push(generate_non_conflicting_p2p(&mut executor).0)
+    }
+
+    // Every read is slow, and reads of the first senders' accounts even slower.
+    let key_latencies = transactions
+        .iter()
+        .take(10)
+        .flat_map(|txn| txn.read_hints().iter())
+        .filter_map(|hint| match hint {
+            StorageLocation::Specific(state_key) => Some(state_key.clone()),
+            _ => None,
+        })
+        .map(|key| (key, Duration::from_millis(1)))
+        .collect();
+    let state_view = ChaosStateView::new(
+        executor.data_store().clone(),
+        ChaosConfig {
+            default_latency: Duration::from_micros(10),
+            key_latencies,
+            ..ChaosConfig::default()
+        },
+    );
+
+    let partitioner = ShardedBlockPartitioner::new(num_shards);
+    let partitioned_txns = partitioner.partition(transactions.clone(), 2, 0.9);
+    let execution_ordered_txns = SubBlocksForShard::flatten(partitioned_txns.clone())
+        .into_iter()
+        .map(|t| t.into_txn())
+        .collect();
+    let sharded_txn_output = sharded_block_executor
+        .execute_block(Arc::new(state_view), partitioned_txns, concurrency, None)
+        .unwrap();
+
+    let unsharded_txn_output =
+        AptosVM::execute_block(execution_ordered_txns, &executor.data_store(), None).unwrap();
+    compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
+}
+
+/// Executes conflicting transfers with BlockSTM over a slow storage, where the reads of a few
+/// accounts are even slower, and compares the outputs with a sequential execution.
+pub fn block_executor_with_slow_storage(concurrency: usize) {
+    let num_txns = 200;
+    let num_accounts = 10;
+    let mut executor = FakeExecutor::from_head_genesis();
+    let mut accounts = Vec::new();
+    for _ in 0..num_accounts {
+        accounts.push(generate_account_at(&mut executor, AccountAddress::random()));
+    }
+    let mut transactions = Vec::new();
+    for i in 0..num_txns {
+        let receiver = accounts[(i + 3) % num_accounts].clone();
+        transactions.push(generate_p2p_txn(
+            &mut accounts[i % num_accounts],
+            &receiver,
+            1_000,
+        ));
+    }
+
+    let key_latencies = transactions
+        .iter()
+        .take(2)
+        .flat_map(|txn| txn.read_hints().iter())
+        .filter_map(|hint| match hint {
+            StorageLocation::Specific(state_key) => Some(state_key.clone()),
+            _ => None,
+        })
+        .map(|key| (key, Duration::from_millis(1)))
+        .collect();
+    let state_view = ChaosStateView::new(
+        executor.data_store().clone(),
+        ChaosConfig {
+            default_latency: Duration::from_micros(20),
+            key_latencies,
+            ..ChaosConfig::default()
+        },
+    );
+
+    let transactions: Vec<_> = transactions.into_iter().map(|t| t.into_txn()).collect();
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(concurrency)
+            .build()
+            .unwrap(),
+    );
+    let parallel_txn_output = BlockAptosVM::execute_block::<
+        _,
+        NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
+    >(
+        executor_thread_pool,
+        transactions.clone(),
+        &state_view,
+        concurrency,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let sequential_txn_output =
+        AptosVM::execute_block(transactions, &executor.data_store(), None).unwrap();
+    compare_txn_outputs(sequential_txn_output, parallel_txn_output);
+}
+
+/// A state view panicking on the reads of the given keys, as if the shard reading them crashed.
+pub struct PanickingStateView {
+    inner: FakeDataStore,
+    panicking_keys: HashSet<StateKey>,
+}
+
+impl PanickingStateView {
+    pub fn new(inner: FakeDataStore, panicking_keys: HashSet<StateKey>) -> Self {
+        Self {
+            inner,
+            panicking_keys,
+        }
+    }
+}
+
+impl TStateView for PanickingStateView {
+    type Key = StateKey;
+
+    fn id(&self) -> StateViewId {
+        self.inner.id()
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
+        if self.panicking_keys.contains(state_key) {
+            panic!("Injected panic on reading {:?}", state_key);
+        }
+        self.inner.get_state_value(state_key)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.inner.is_genesis()
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage> {
+        self.inner.get_usage()
+    }
+}
+
+pub fn sharded_block_executor_with_panicking_shard<E: ExecutorClient<PanickingStateView>>(
+    mut sharded_block_executor: ShardedBlockExecutor<PanickingStateView, E>,
+) {
+    let num_txns = 400;
+    let num_shards = sharded_block_executor.num_shards();
+    let num_accounts = 40;
+    let mut executor = FakeExecutor::from_head_genesis();
+    let mut accounts = Vec::new();
+    for _ in 0..num_accounts {
+        accounts.push(generate_account_at(&mut executor, AccountAddress::random()));
+    }
+    // Chains of transfers between the accounts, for the shards to depend on each other.
+    let mut transactions = Vec::new();
+    for i in 1..num_txns / num_accounts {
+        for j in 0..num_accounts {
+            let receiver = accounts[(j + i) % num_accounts].clone();
+            transactions.push(generate_p2p_txn(&mut accounts[j], &receiver, 1_000));
+        }
+    }
+    let panicking_keys: HashSet<_> = transactions[0]
+        .read_hints()
+        .iter()
+        .filter_map(|hint| match hint {
+            StorageLocation::Specific(state_key) => Some(state_key.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let partitioner = ShardedBlockPartitioner::new(num_shards);
+    let partitioned_txns = partitioner.partition(transactions, 8, 0.9);
+    let start_time = Instant::now();
+    let ret = sharded_block_executor.execute_block(
+        Arc::new(PanickingStateView::new(
+            executor.data_store().clone(),
+            panicking_keys,
+        )),
+        partitioned_txns.clone(),
+        2,
+        None,
+    );
+    assert!(ret.is_err());
+    // The shards waiting on the values of the panicked shard are aborted, rather than time out.
+    assert!(start_time.elapsed() < DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT);
+
+    // The block succeeds once the shards are reset, without the panics.
+    sharded_block_executor.reset_shards();
+    let execution_ordered_txns = SubBlocksForShard::flatten(partitioned_txns.clone())
+        .into_iter()
+        .map(|t| t.into_txn())
+        .collect();
+    let sharded_txn_output = sharded_block_executor
+        .execute_block(
+            Arc::new(PanickingStateView::new(
+                executor.data_store().clone(),
+                HashSet::new(),
+            )),
+            partitioned_txns,
+            2,
+            None,
+        )
+        .unwrap();
+    let unsharded_txn_output =
+        AptosVM::execute_block(execution_ordered_txns, &executor.data_store(), None).unwrap();
+    compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
+}
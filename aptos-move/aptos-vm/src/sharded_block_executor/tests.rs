@@ -1,10 +1,14 @@
 // Copyright © Aptos Foundation
 
 use crate::{
-    sharded_block_executor::{local_executor_shard::LocalExecutorService, test_utils},
+    sharded_block_executor::{
+        local_executor_shard::LocalExecutorService, simulated_network::SimulatedNetworkConfig,
+        test_utils,
+    },
     ShardedBlockExecutor,
 };
 use rand::{rngs::OsRng, Rng};
+use std::time::Duration;
 
 #[test]
 fn test_sharded_block_executor_no_conflict() {
@@ -14,6 +18,24 @@ fn test_sharded_block_executor_no_conflict() {
     test_utils::test_sharded_block_executor_no_conflict(sharded_block_executor);
 }
 
+#[test]
+fn test_sharded_block_executor_with_ordered_events() {
+    let num_shards = 4;
+    let client = LocalExecutorService::setup_local_executor_shards(num_shards, Some(2));
+    let sharded_block_executor = ShardedBlockExecutor::new(client);
+    test_utils::sharded_block_executor_with_ordered_events(sharded_block_executor);
+}
+
+#[test]
+fn test_sharded_block_executor_with_changing_num_shards() {
+    let client = LocalExecutorService::setup_local_executor_shards(2, Some(2));
+    let sharded_block_executor = ShardedBlockExecutor::new(client);
+    test_utils::sharded_block_executor_with_changing_num_shards(
+        sharded_block_executor,
+        &[2, 4, 3, 3, 2],
+    );
+}
+
 #[test]
 // Sharded execution with cross shard conflict doesn't work for now because we don't have
 // cross round dependency tracking yet.
@@ -51,3 +73,39 @@ fn test_sharded_block_executor_with_random_transfers_sequential() {
     let sharded_block_executor = ShardedBlockExecutor::new(client);
     test_utils::sharded_block_executor_with_random_transfers(sharded_block_executor, 1)
 }
+
+#[test]
+fn test_sharded_block_executor_with_slow_storage() {
+    let num_shards = 4;
+    let client = LocalExecutorService::setup_local_executor_shards(num_shards, Some(2));
+    let sharded_block_executor = ShardedBlockExecutor::new(client);
+    test_utils::sharded_block_executor_with_slow_storage(sharded_block_executor, 2)
+}
+
+#[test]
+fn test_block_executor_with_slow_storage() {
+    test_utils::block_executor_with_slow_storage(4)
+}
+
+#[test]
+fn test_sharded_block_executor_with_panicking_shard() {
+    let num_shards = 4;
+    let client = LocalExecutorService::setup_local_executor_shards(num_shards, Some(2));
+    let sharded_block_executor = ShardedBlockExecutor::new(client);
+    test_utils::sharded_block_executor_with_panicking_shard(sharded_block_executor)
+}
+
+#[test]
+fn test_sharded_block_executor_with_simulated_network() {
+    let num_shards = 4;
+    let client = LocalExecutorService::setup_local_executor_shards_with_network(
+        num_shards,
+        Some(2),
+        SimulatedNetworkConfig {
+            latency: Duration::from_millis(5),
+            jitter: Duration::from_millis(5),
+        },
+    );
+    let sharded_block_executor = ShardedBlockExecutor::new(client);
+    test_utils::sharded_block_executor_with_conflict(sharded_block_executor, 2)
+}
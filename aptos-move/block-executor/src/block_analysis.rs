@@ -0,0 +1,70 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::task::Accesses;
+use aptos_mvhashmap::types::TxnIndex;
+use std::{collections::HashMap, hash::Hash};
+
+/// Read and write sets of the transactions of a block, as observed by a (dry-run) parallel
+/// execution of the block, and the conflict graph they induce. Meant for predicting how well a
+/// block parallelizes, e.g. by the partitioner, without committing the block.
+#[derive(Clone, Debug)]
+pub struct BlockAnalysis<K> {
+    /// Keys read and written by each transaction, by index in the block. Covers the transactions
+    /// up to the end of the block, or up to where the block would be cut, e.g. by the block gas
+    /// limit.
+    pub txn_accesses: Vec<Accesses<K>>,
+    /// For each transaction, the earlier transactions whose writes it reads, i.e. that it must
+    /// be executed after. Only the last earlier writer of each key read is a dependency.
+    pub dependencies: Vec<Vec<TxnIndex>>,
+}
+
+impl<K: Hash + Eq + Clone> BlockAnalysis<K> {
+    pub fn new(txn_accesses: Vec<Accesses<K>>) -> Self {
+        let mut last_writers: HashMap<K, TxnIndex> = HashMap::new();
+        let mut dependencies = Vec::with_capacity(txn_accesses.len());
+        for (txn_idx, accesses) in txn_accesses.iter().enumerate() {
+            let mut txn_dependencies: Vec<TxnIndex> = accesses
+                .keys_read
+                .iter()
+                .filter_map(|key| last_writers.get(key).copied())
+                .collect();
+            txn_dependencies.sort_unstable();
+            txn_dependencies.dedup();
+            dependencies.push(txn_dependencies);
+
+            for key in &accesses.keys_written {
+                last_writers.insert(key.clone(), txn_idx as TxnIndex);
+            }
+        }
+
+        Self {
+            txn_accesses,
+            dependencies,
+        }
+    }
+
+    /// Number of transactions that depend on an earlier transaction of the block.
+    pub fn num_dependent_txns(&self) -> usize {
+        self.dependencies
+            .iter()
+            .filter(|txn_dependencies| !txn_dependencies.is_empty())
+            .count()
+    }
+
+    /// Length of the longest chain of dependencies in the block, i.e. the minimum number of
+    /// rounds in which the block can be executed, even with unlimited parallelism.
+    pub fn critical_path_length(&self) -> usize {
+        let mut path_lengths: Vec<usize> = Vec::with_capacity(self.dependencies.len());
+        for txn_dependencies in &self.dependencies {
+            let path_length = txn_dependencies
+                .iter()
+                .map(|dep_idx| path_lengths[*dep_idx as usize])
+                .max()
+                .unwrap_or(0)
+                + 1;
+            path_lengths.push(path_length);
+        }
+        path_lengths.into_iter().max().unwrap_or(0)
+    }
+}
@@ -10,6 +10,10 @@ pub enum Error<E> {
     /// TODO: (short-med term) relax the limitation, and (mid-long term) provide proper multi-versioning
     /// for code (like data) for the cache.
     ModulePathReadWrite,
+    /// A transaction was about to be re-executed more times than allowed by the
+    /// BoundedRetriesThenSequential conflict resolution policy. The parallel execution is aborted
+    /// and the block is executed sequentially instead.
+    MaxRetriesExceeded,
     /// Execution of a thread yields a non-recoverable error, such error will be propagated back to
     /// the caller.
     UserError(E),
@@ -0,0 +1,98 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_mvhashmap::types::TxnIndex;
+use std::{
+    cmp::Reverse,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Telemetry of the (speculative) executions of a single transaction of a block.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TxnExecutionStats {
+    /// Number of times the transaction was executed, including the re-executions after its
+    /// speculative executions were aborted. 0 if the transaction was not executed, e.g. as the
+    /// block gas limit was reached before it.
+    pub num_executions: u32,
+    /// Total time spent executing the transaction, over all its executions.
+    pub execution_time: Duration,
+}
+
+impl TxnExecutionStats {
+    /// Number of times the transaction was executed again, as it conflicted with the
+    /// transactions before it.
+    pub fn num_reexecutions(&self) -> u32 {
+        self.num_executions.saturating_sub(1)
+    }
+}
+
+/// Per-transaction execution telemetry of the last execution of a block, for identifying the
+/// conflict-heavy transactions, i.e. the ones executed many times or for long.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockExecutionSummary {
+    /// Statistics of each transaction of the block, by index in the block.
+    pub txn_stats: Vec<TxnExecutionStats>,
+}
+
+impl BlockExecutionSummary {
+    /// Total number of executions of the transactions of the block.
+    pub fn total_executions(&self) -> u64 {
+        self.txn_stats
+            .iter()
+            .map(|stats| stats.num_executions as u64)
+            .sum()
+    }
+
+    /// Total time spent executing the transactions of the block.
+    pub fn total_execution_time(&self) -> Duration {
+        self.txn_stats
+            .iter()
+            .map(|stats| stats.execution_time)
+            .sum()
+    }
+
+    /// The (at most) `limit` transactions re-executed the most, by decreasing number of
+    /// re-executions, then by decreasing execution time. Transactions executed only once are
+    /// left out.
+    pub fn most_reexecuted(&self, limit: usize) -> Vec<(TxnIndex, TxnExecutionStats)> {
+        let mut reexecuted: Vec<_> = self
+            .txn_stats
+            .iter()
+            .enumerate()
+            .filter(|(_, stats)| stats.num_reexecutions() > 0)
+            .map(|(txn_idx, stats)| (txn_idx as TxnIndex, *stats))
+            .collect();
+        reexecuted.sort_by_key(|(txn_idx, stats)| {
+            (
+                Reverse(stats.num_executions),
+                Reverse(stats.execution_time),
+                *txn_idx,
+            )
+        });
+        reexecuted.truncate(limit);
+        reexecuted
+    }
+}
+
+/// Execution telemetry of a transaction, recorded concurrently by the workers.
+#[derive(Debug, Default)]
+pub(crate) struct TxnExecutionCounters {
+    num_executions: AtomicU32,
+    execution_nanos: AtomicU64,
+}
+
+impl TxnExecutionCounters {
+    pub(crate) fn record_execution(&self, elapsed: Duration) {
+        self.num_executions.fetch_add(1, Ordering::Relaxed);
+        self.execution_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> TxnExecutionStats {
+        TxnExecutionStats {
+            num_executions: self.num_executions.load(Ordering::Relaxed),
+            execution_time: Duration::from_nanos(self.execution_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -3,27 +3,38 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    block_analysis::BlockAnalysis,
     counters,
     counters::{
-        PARALLEL_EXECUTION_SECONDS, RAYON_EXECUTION_SECONDS, TASK_EXECUTE_SECONDS,
-        TASK_VALIDATE_SECONDS, VM_INIT_SECONDS, WORK_WITH_TASK_SECONDS,
+        DELAYED_DELTA_MATERIALIZATION_SECONDS, PARALLEL_EXECUTION_SECONDS, RAYON_EXECUTION_SECONDS,
+        TASK_EXECUTE_SECONDS, TASK_VALIDATE_SECONDS, VM_INIT_SECONDS, WORK_WITH_TASK_SECONDS,
     },
     errors::*,
-    scheduler::{DependencyStatus, ExecutionTaskType, Scheduler, SchedulerTask, Wave},
-    task::{ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
+    execution_summary::{BlockExecutionSummary, TxnExecutionStats},
+    scheduler::{
+        resolve_dependency, DependencyStatus, ExecutionTaskType, Scheduler, SchedulerTask, Wave,
+    },
+    scheduler_config::SchedulerConfig,
+    scheduler_stats::{SchedulerReport, WorkerStats},
+    speculative_events::SpeculativeEventBuffer,
+    task::{Accesses, ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
     txn_commit_hook::TransactionCommitHook,
     txn_last_input_output::TxnLastInputOutput,
     view::{LatestView, MVHashMapView},
 };
 use aptos_aggregator::delta_change_set::{deserialize, serialize};
-use aptos_logger::{debug, info};
+use aptos_infallible::Mutex;
+use aptos_logger::{debug, info, warn};
 use aptos_mvhashmap::{
     types::{MVDataError, MVDataOutput, TxnIndex, Version},
     unsync_map::UnsyncMap,
     MVHashMap,
 };
 use aptos_state_view::TStateView;
-use aptos_types::{executable::Executable, fee_statement::FeeStatement, write_set::WriteOp};
+use aptos_types::{
+    block_executor::hot_state_keys::HotKeyRegistry, executable::Executable,
+    fee_statement::FeeStatement, write_set::WriteOp,
+};
 use aptos_vm_logging::{clear_speculative_txn_logs, init_speculative_logs};
 use num_cpus;
 use rayon::ThreadPool;
@@ -34,6 +45,7 @@ use std::{
         mpsc::{Receiver, Sender},
         Arc,
     },
+    time::Instant,
 };
 
 struct CommitGuard<'a> {
@@ -75,6 +87,29 @@ pub struct BlockExecutor<T, E, S, L, X> {
     executor_thread_pool: Arc<ThreadPool>,
     maybe_block_gas_limit: Option<u64>,
     transaction_commit_hook: Option<L>,
+    // If set, the block is cut (the remaining transactions are skipped) once the accumulated
+    // output size of the committed transactions reaches the limit, in bytes.
+    maybe_block_output_limit: Option<u64>,
+    // If set, parallel execution checks that no events emitted by aborted speculative
+    // executions leak into the committed outputs, and panics otherwise.
+    check_speculative_events: bool,
+    // Optional per-transaction priority hints (higher value means higher priority), based on
+    // which the scheduler prefers executing high-priority transactions early.
+    maybe_priority_hints: Option<Vec<u64>>,
+    // If set, aggregator deltas are not materialized (and the commit hook is not invoked)
+    // when transactions are committed during parallel execution, but only when the outputs
+    // of the block are assembled after the execution finishes.
+    delay_delta_materialization: bool,
+    // Tuning knobs of the parallel execution scheduler.
+    scheduler_config: SchedulerConfig,
+    // Report of the scheduler for the last block executed in parallel, if statistics
+    // collection is enabled in the scheduler config.
+    last_scheduler_report: Mutex<Option<SchedulerReport>>,
+    // Number of executions and execution time of each transaction of the last block executed.
+    last_execution_summary: Mutex<Option<BlockExecutionSummary>>,
+    // Registry to which the keys written by several committed transactions are reported after
+    // each block.
+    maybe_hot_key_registry: Option<Arc<HotKeyRegistry<T::Key>>>,
     phantom: PhantomData<(T, E, S, L, X)>,
 }
 
@@ -104,15 +139,91 @@ where
             executor_thread_pool,
             maybe_block_gas_limit,
             transaction_commit_hook,
+            maybe_block_output_limit: None,
+            check_speculative_events: false,
+            maybe_priority_hints: None,
+            delay_delta_materialization: false,
+            scheduler_config: SchedulerConfig::default(),
+            last_scheduler_report: Mutex::new(None),
+            last_execution_summary: Mutex::new(None),
+            maybe_hot_key_registry: None,
             phantom: PhantomData,
         }
     }
 
+    /// Sets the per-block output limit: once the accumulated output size (see
+    /// TransactionOutput::output_size) of the committed transactions reaches the limit, the
+    /// remaining transactions of the block are skipped, as with the per-block gas limit. The cut
+    /// is the same in parallel and sequential execution, so it is deterministic across validators
+    /// executing the block with the same limit.
+    pub fn with_block_output_limit(mut self, maybe_block_output_limit: Option<u64>) -> Self {
+        self.maybe_block_output_limit = maybe_block_output_limit;
+        self
+    }
+
+    /// Enables the invariant-check mode for events emitted by speculative executions,
+    /// which detects events that leaked past the rollback of an aborted incarnation. Events
+    /// are only buffered in this mode.
+    pub fn with_speculative_event_checks(mut self, enabled: bool) -> Self {
+        self.check_speculative_events = enabled;
+        self
+    }
+
+    /// Provides per-transaction priority hints (e.g. based on gas price) for the block, one
+    /// per transaction, where a higher value means a higher priority and 0 means no priority.
+    /// During parallel execution, the scheduler prefers to execute high-priority transactions
+    /// early, improving their latency in case the block gets cut off by the block gas limit.
+    /// The hints do not affect the preset serialization order or the output of the block.
+    pub fn with_priority_hints(mut self, priority_hints: Vec<u64>) -> Self {
+        self.maybe_priority_hints = Some(priority_hints);
+        self
+    }
+
+    /// Delays the materialization of aggregator deltas in parallel execution until the
+    /// outputs of the block are assembled, at which point the deltas of all committed
+    /// transactions are resolved to values in order. This keeps the commit path free of
+    /// contention on heavily updated aggregators, at the cost of notifying the transaction
+    /// commit hook only after the whole block has been executed.
+    pub fn with_delayed_delta_materialization(mut self, enabled: bool) -> Self {
+        self.delay_delta_materialization = enabled;
+        self
+    }
+
+    /// Sets the tuning knobs of the parallel execution scheduler. If statistics collection is
+    /// enabled, a SchedulerReport is available after each block executed in parallel.
+    pub fn with_scheduler_config(mut self, scheduler_config: SchedulerConfig) -> Self {
+        self.scheduler_config = scheduler_config;
+        self
+    }
+
+    /// Reports the keys written by more than one committed transaction of each executed block to
+    /// the registry, so that the placement of transactions in the next blocks can account for the
+    /// observed contention. Only the committed outputs are reported (and not e.g. the speculative
+    /// aborts of the parallel execution, which depend on timing), so that the registry evolves
+    /// the same way for the same sequence of blocks, regardless of the concurrency level.
+    pub fn with_hot_key_registry(mut self, hot_key_registry: Arc<HotKeyRegistry<T::Key>>) -> Self {
+        self.maybe_hot_key_registry = Some(hot_key_registry);
+        self
+    }
+
+    /// Takes the scheduler report of the last block executed in parallel, if any.
+    pub fn take_scheduler_report(&self) -> Option<SchedulerReport> {
+        self.last_scheduler_report.lock().take()
+    }
+
+    /// Takes the number of (speculative) executions and the execution time of each transaction
+    /// of the last block executed, if any. If the parallel execution of the block fell back to
+    /// sequential execution, the summary covers the sequential execution.
+    pub fn take_execution_summary(&self) -> Option<BlockExecutionSummary> {
+        self.last_execution_summary.lock().take()
+    }
+
     fn execute(
         &self,
         version: Version,
         signature_verified_block: &[T],
         last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        event_buffer: Option<&SpeculativeEventBuffer<T::Event>>,
         versioned_cache: &MVHashMap<T::Key, T::Value, X>,
         scheduler: &Scheduler,
         executor: &E,
@@ -122,15 +233,31 @@ where
         let (idx_to_execute, incarnation) = version;
         let txn = &signature_verified_block[idx_to_execute as usize];
 
+        if scheduler.halt_if_max_retries_exceeded(incarnation) {
+            // The transaction conflicts too much, the block is executed sequentially instead.
+            return SchedulerTask::NoTask;
+        }
+
         let speculative_view = MVHashMapView::new(versioned_cache, scheduler);
 
         // VM execution.
+        let execution_start = Instant::now();
         let execute_result = executor.execute_transaction(
             &LatestView::<T, S, X>::new_mv_view(base_view, &speculative_view, idx_to_execute),
             txn,
             idx_to_execute,
             false,
         );
+        let execution_time = execution_start.elapsed();
+
+        if let Some(dep_idx) = speculative_view.abandoned_on_dependency() {
+            // The execution read an estimate under the EagerAbort policy, so its output is
+            // discarded (along with the logs) and the transaction is executed again later.
+            clear_speculative_txn_logs(idx_to_execute as usize);
+            last_input_output.record_abandoned_execution(idx_to_execute, execution_time);
+            return scheduler.abandon_execution(idx_to_execute, incarnation, dep_idx);
+        }
+
         let mut prev_modified_keys = last_input_output.modified_keys(idx_to_execute);
 
         // For tracking whether the recent execution wrote outside of the previous write/delta set.
@@ -180,8 +307,24 @@ where
             versioned_cache.delete(&k, idx_to_execute);
         }
 
+        // Buffer the events of the incarnation, they are rolled back if it gets aborted.
+        if let Some(event_buffer) = event_buffer {
+            let events = match &result {
+                ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                    output.get_events()
+                },
+                ExecutionStatus::Abort(_) => vec![],
+            };
+            event_buffer.record(idx_to_execute, incarnation, events);
+        }
+
         if last_input_output
-            .record(idx_to_execute, speculative_view.take_reads(), result)
+            .record(
+                idx_to_execute,
+                speculative_view.take_reads(),
+                result,
+                execution_time,
+            )
             .is_err()
         {
             // When there is module publishing r/w intersection, can early halt BlockSTM to
@@ -197,6 +340,7 @@ where
         version_to_validate: Version,
         validation_wave: Wave,
         last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        event_buffer: Option<&SpeculativeEventBuffer<T::Event>>,
         versioned_cache: &MVHashMap<T::Key, T::Value, X>,
         scheduler: &Scheduler,
     ) -> SchedulerTask {
@@ -235,6 +379,10 @@ where
 
             // Any logs from the aborted execution should be cleared and not reported.
             clear_speculative_txn_logs(idx_to_validate as usize);
+            // Similarly, events emitted by the aborted incarnation must be rolled back.
+            if let Some(event_buffer) = event_buffer {
+                event_buffer.rollback(idx_to_validate, incarnation);
+            }
 
             // Not valid and successfully aborted, mark the latest write/delta sets as estimates.
             for k in last_input_output.modified_keys(idx_to_validate) {
@@ -258,6 +406,7 @@ where
         last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
         accumulated_fee_statement: &mut FeeStatement,
         txn_fee_statements: &mut Vec<FeeStatement>,
+        accumulated_output_size: &mut u64,
     ) {
         while let Some(txn_idx) = scheduler.try_commit() {
             // Create a CommitGuard to ensure Coordinator sends the committed txn index to Worker.
@@ -271,6 +420,7 @@ where
                 accumulated_fee_statement.add_fee_statement(&fee_statement);
                 txn_fee_statements.push(fee_statement);
 
+                let mut exceeds_per_block_limit = false;
                 if let Some(per_block_gas_limit) = maybe_block_gas_limit {
                     // When the accumulated execution and io gas of the committed txns exceeds
                     // PER_BLOCK_GAS_LIMIT, early halt BlockSTM. Storage gas does not count towards
@@ -287,11 +437,31 @@ where
                              accumulated_non_storage_gas {} >= PER_BLOCK_GAS_LIMIT {}",
                             accumulated_non_storage_gas, per_block_gas_limit,
                         );
+                        exceeds_per_block_limit = true;
+                    }
+                }
 
-                        // Set the execution output status to be SkipRest, to skip the rest of the txns.
-                        last_input_output.update_to_skip_rest(txn_idx);
+                if let Some(per_block_output_limit) = self.maybe_block_output_limit {
+                    // Similarly, early halt BlockSTM when the accumulated output size of the
+                    // committed txns exceeds the per block output limit.
+                    *accumulated_output_size += last_input_output.output_size(txn_idx);
+                    if *accumulated_output_size >= per_block_output_limit {
+                        counters::EXCEED_PER_BLOCK_OUTPUT_LIMIT_COUNT
+                            .with_label_values(&[counters::Mode::PARALLEL])
+                            .inc();
+                        info!(
+                            "[BlockSTM]: Parallel execution early halted due to \
+                             accumulated_output_size {} >= PER_BLOCK_OUTPUT_LIMIT {}",
+                            accumulated_output_size, per_block_output_limit,
+                        );
+                        exceeds_per_block_limit = true;
                     }
                 }
+
+                if exceeds_per_block_limit && !last_input_output.block_truncated_at_idx(txn_idx) {
+                    // Set the execution output status to be SkipRest, to skip the rest of the txns.
+                    last_input_output.update_to_skip_rest(txn_idx);
+                }
             }
 
             // Committed the last transaction, BlockSTM finishes execution.
@@ -346,6 +516,20 @@ where
         versioned_cache: &MVHashMap<T::Key, T::Value, X>,
         last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
         base_view: &S,
+    ) {
+        // With delayed materialization, both steps happen during the final output assembly.
+        if !self.delay_delta_materialization {
+            self.materialize_deltas(txn_idx, versioned_cache, last_input_output, base_view);
+            self.notify_commit_hook(txn_idx, last_input_output);
+        }
+    }
+
+    fn materialize_deltas(
+        &self,
+        txn_idx: TxnIndex,
+        versioned_cache: &MVHashMap<T::Key, T::Value, X>,
+        last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        base_view: &S,
     ) {
         let (num_deltas, delta_keys) = last_input_output.delta_keys(txn_idx);
         let mut delta_writes = Vec::with_capacity(num_deltas);
@@ -381,6 +565,13 @@ where
             ));
         }
         last_input_output.record_delta_writes(txn_idx, delta_writes);
+    }
+
+    fn notify_commit_hook(
+        &self,
+        txn_idx: TxnIndex,
+        last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+    ) {
         if let Some(txn_commit_listener) = &self.transaction_commit_hook {
             let txn_output = last_input_output.txn_output(txn_idx).unwrap();
             let execution_status = txn_output.output_status();
@@ -401,11 +592,12 @@ where
         executor_arguments: &E::Argument,
         block: &[T],
         last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        event_buffer: Option<&SpeculativeEventBuffer<T::Event>>,
         versioned_cache: &MVHashMap<T::Key, T::Value, X>,
         scheduler: &Scheduler,
         base_view: &S,
         role: CommitRole,
-    ) {
+    ) -> WorkerStats {
         // Make executor for each task. TODO: fast concurrent executor.
         let init_timer = VM_INIT_SECONDS.start_timer();
         let executor = E::init(*executor_arguments);
@@ -416,9 +608,12 @@ where
         let _timer = WORK_WITH_TASK_SECONDS.start_timer();
         let mut scheduler_task = SchedulerTask::NoTask;
         let mut worker_idx = 0;
+        let collect_stats = scheduler.stats().is_some();
+        let mut worker_stats = WorkerStats::default();
 
         let mut accumulated_fee_statement = FeeStatement::zero();
         let mut txn_fee_statements = Vec::with_capacity(block.len());
+        let mut accumulated_output_size = 0;
         loop {
             // Only one thread does try_commit to avoid contention.
             match &role {
@@ -432,6 +627,7 @@ where
                         last_input_output,
                         &mut accumulated_fee_statement,
                         &mut txn_fee_statements,
+                        &mut accumulated_output_size,
                     );
                 },
                 CommitRole::Worker(rx) => {
@@ -447,18 +643,24 @@ where
             }
 
             scheduler_task = match scheduler_task {
-                SchedulerTask::ValidationTask(version_to_validate, wave) => self.validate(
-                    version_to_validate,
-                    wave,
-                    last_input_output,
-                    versioned_cache,
-                    scheduler,
-                ),
+                SchedulerTask::ValidationTask(version_to_validate, wave) => {
+                    worker_stats.num_validations += 1;
+                    self.validate(
+                        version_to_validate,
+                        wave,
+                        last_input_output,
+                        event_buffer,
+                        versioned_cache,
+                        scheduler,
+                    )
+                },
                 SchedulerTask::ExecutionTask(version_to_execute, ExecutionTaskType::Execution) => {
+                    worker_stats.num_executions += 1;
                     self.execute(
                         version_to_execute,
                         block,
                         last_input_output,
+                        event_buffer,
                         versioned_cache,
                         scheduler,
                         &executor,
@@ -466,14 +668,22 @@ where
                     )
                 },
                 SchedulerTask::ExecutionTask(_, ExecutionTaskType::Wakeup(condvar)) => {
-                    let (lock, cvar) = &*condvar;
-                    // Mark dependency resolved.
-                    *lock.lock() = DependencyStatus::Resolved;
-                    // Wake up the process waiting for dependency.
-                    cvar.notify_one();
+                    worker_stats.num_wakeups += 1;
+                    // Mark dependency resolved, and wake up the process waiting for it.
+                    resolve_dependency(&condvar, DependencyStatus::Resolved);
 
                     SchedulerTask::NoTask
                 },
+                SchedulerTask::NoTask if collect_stats => {
+                    let poll_start = Instant::now();
+                    let task = scheduler.next_task(committing);
+                    worker_stats.record_poll(
+                        matches!(task, SchedulerTask::NoTask),
+                        poll_start.elapsed(),
+                        scheduler.queue_depths(),
+                    );
+                    task
+                },
                 SchedulerTask::NoTask => scheduler.next_task(committing),
                 SchedulerTask::Done => {
                     // Make sure to drain any remaining commit tasks assigned by the coordinator.
@@ -492,6 +702,7 @@ where
                 },
             }
         }
+        worker_stats
     }
 
     pub(crate) fn execute_transactions_parallel(
@@ -499,6 +710,45 @@ where
         executor_initial_arguments: E::Argument,
         signature_verified_block: &Vec<T>,
         base_view: &S,
+    ) -> Result<Vec<E::Output>, E::Error> {
+        self.execute_transactions_parallel_with_accesses(
+            executor_initial_arguments,
+            signature_verified_block,
+            base_view,
+            None,
+        )
+    }
+
+    // Accesses of a committed transaction, from its last recorded input and output.
+    fn txn_accesses(
+        txn_idx: TxnIndex,
+        last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+    ) -> Accesses<T::Key> {
+        let mut keys_read: Vec<T::Key> = last_input_output
+            .read_set(txn_idx)
+            .map(|read_set| read_set.iter().map(|r| r.path().clone()).collect())
+            .unwrap_or_default();
+        keys_read.sort();
+        keys_read.dedup();
+        let mut keys_written: Vec<T::Key> = last_input_output
+            .modified_keys(txn_idx)
+            .into_iter()
+            .collect();
+        keys_written.sort();
+
+        Accesses {
+            keys_read,
+            keys_written,
+        }
+    }
+
+    // If maybe_accesses is set, the accesses of each committed transaction are pushed to it.
+    fn execute_transactions_parallel_with_accesses(
+        &self,
+        executor_initial_arguments: E::Argument,
+        signature_verified_block: &Vec<T>,
+        base_view: &S,
+        mut maybe_accesses: Option<&mut Vec<Accesses<T::Key>>>,
     ) -> Result<Vec<E::Output>, E::Error> {
         let _timer = PARALLEL_EXECUTION_SECONDS.start_timer();
         // Using parallel execution with 1 thread currently will not work as it
@@ -515,7 +765,24 @@ where
 
         let num_txns = signature_verified_block.len() as u32;
         let last_input_output = TxnLastInputOutput::new(num_txns);
-        let scheduler = Scheduler::new(num_txns);
+        let event_buffer = self
+            .check_speculative_events
+            .then(|| SpeculativeEventBuffer::new(num_txns));
+        let scheduler = match &self.maybe_priority_hints {
+            Some(priority_hints) if priority_hints.len() == num_txns as usize => {
+                Scheduler::new_with_priorities(num_txns, Scheduler::priority_order(priority_hints))
+            },
+            Some(priority_hints) => {
+                warn!(
+                    "[BlockSTM]: Ignoring {} priority hints for a block of {} txns",
+                    priority_hints.len(),
+                    num_txns
+                );
+                Scheduler::new(num_txns)
+            },
+            None => Scheduler::new(num_txns),
+        }
+        .with_config(self.scheduler_config);
 
         let mut roles: Vec<CommitRole> = vec![];
         let mut senders: Vec<Sender<u32>> = Vec::with_capacity(self.concurrency_level - 1);
@@ -531,25 +798,34 @@ where
         // executors are running concurrently, they will all have active coordinator.
         roles.push(CommitRole::Coordinator(senders));
 
+        let worker_stats = Mutex::new(Vec::with_capacity(self.concurrency_level));
         let timer = RAYON_EXECUTION_SECONDS.start_timer();
         self.executor_thread_pool.scope(|s| {
             for _ in 0..self.concurrency_level {
                 let role = roles.pop().expect("Role must be set for all threads");
                 s.spawn(|_| {
-                    self.work_task_with_scope(
+                    let stats = self.work_task_with_scope(
                         &executor_initial_arguments,
                         signature_verified_block,
                         &last_input_output,
+                        event_buffer.as_ref(),
                         &versioned_cache,
                         &scheduler,
                         base_view,
                         role,
                     );
+                    worker_stats.lock().push(stats);
                 });
             }
         });
         drop(timer);
 
+        if let Some(stats) = scheduler.stats() {
+            let report = stats.report(num_txns, worker_stats.into_inner());
+            *self.last_scheduler_report.lock() = Some(report);
+        }
+        *self.last_execution_summary.lock() = Some(last_input_output.execution_summary());
+
         let num_txns = num_txns as usize;
         // TODO: for large block sizes and many cores, extract outputs in parallel.
         let mut final_results = Vec::with_capacity(num_txns);
@@ -557,12 +833,40 @@ where
         let maybe_err = if last_input_output.module_publishing_may_race() {
             counters::MODULE_PUBLISHING_FALLBACK_COUNT.inc();
             Some(Error::ModulePathReadWrite)
+        } else if scheduler.max_retries_exceeded() {
+            counters::MAX_RETRIES_FALLBACK_COUNT.inc();
+            Some(Error::MaxRetriesExceeded)
         } else {
             let mut ret = None;
+            let _timer = self
+                .delay_delta_materialization
+                .then(|| DELAYED_DELTA_MATERIALIZATION_SECONDS.start_timer());
             for idx in 0..num_txns {
+                if self.delay_delta_materialization {
+                    // Transactions are processed in order, so all deltas of the previous
+                    // transactions have been materialized at this point.
+                    self.materialize_deltas(
+                        idx as TxnIndex,
+                        &versioned_cache,
+                        &last_input_output,
+                        base_view,
+                    );
+                    self.notify_commit_hook(idx as TxnIndex, &last_input_output);
+                }
+                if let Some(accesses) = maybe_accesses.as_mut() {
+                    accesses.push(Self::txn_accesses(idx as TxnIndex, &last_input_output));
+                }
                 match last_input_output.take_output(idx as TxnIndex) {
-                    ExecutionStatus::Success(t) => final_results.push(t),
+                    ExecutionStatus::Success(t) => {
+                        if let Some(event_buffer) = &event_buffer {
+                            event_buffer.commit(idx as TxnIndex, &t.get_events());
+                        }
+                        final_results.push(t);
+                    },
                     ExecutionStatus::SkipRest(t) => {
+                        if let Some(event_buffer) = &event_buffer {
+                            event_buffer.commit(idx as TxnIndex, &t.get_events());
+                        }
                         final_results.push(t);
                         break;
                     },
@@ -575,6 +879,16 @@ where
             ret
         };
 
+        if let Some(event_buffer) = event_buffer {
+            // Events of the transactions that were not committed are rolled back.
+            event_buffer.discard_from(final_results.len() as TxnIndex);
+            assert_eq!(
+                event_buffer.num_leaked(),
+                0,
+                "[BlockSTM]: Events of aborted speculative executions leaked"
+            );
+        }
+
         self.executor_thread_pool.spawn(move || {
             // Explicit async drops.
             drop(last_input_output);
@@ -605,14 +919,22 @@ where
         let mut ret = Vec::with_capacity(num_txns);
 
         let mut accumulated_fee_statement = FeeStatement::zero();
+        let mut accumulated_output_size = 0;
+        // Transactions after the ones executed are not executed, e.g. due to the block gas limit.
+        let mut txn_stats = vec![TxnExecutionStats::default(); num_txns];
 
         for (idx, txn) in signature_verified_block.iter().enumerate() {
+            let execution_start = Instant::now();
             let res = executor.execute_transaction(
                 &LatestView::<T, S, X>::new_btree_view(base_view, &data_map, idx as TxnIndex),
                 txn,
                 idx as TxnIndex,
                 true,
             );
+            txn_stats[idx] = TxnExecutionStats {
+                num_executions: 1,
+                execution_time: execution_start.elapsed(),
+            };
 
             let must_skip = matches!(res, ExecutionStatus::SkipRest(_));
             match res {
@@ -630,6 +952,9 @@ where
                     let fee_statement = output.fee_statement();
                     accumulated_fee_statement.add_fee_statement(&fee_statement);
                     counters::update_sequential_txn_gas_counters(&fee_statement);
+                    if self.maybe_block_output_limit.is_some() {
+                        accumulated_output_size += output.output_size();
+                    }
 
                     // No delta writes are needed for sequential execution.
                     output.incorporate_delta_writes(vec![]);
@@ -643,6 +968,7 @@ where
                     if let Some(commit_hook) = &self.transaction_commit_hook {
                         commit_hook.on_execution_aborted(idx as TxnIndex);
                     }
+                    *self.last_execution_summary.lock() = Some(BlockExecutionSummary { txn_stats });
                     // Record the status indicating abort.
                     return Err(Error::UserError(err));
                 },
@@ -671,6 +997,22 @@ where
                     break;
                 }
             }
+
+            if let Some(per_block_output_limit) = self.maybe_block_output_limit {
+                if accumulated_output_size >= per_block_output_limit {
+                    counters::EXCEED_PER_BLOCK_OUTPUT_LIMIT_COUNT
+                        .with_label_values(&[counters::Mode::SEQUENTIAL])
+                        .inc();
+                    info!(
+                        "[Execution]: Sequential execution early halted due to \
+                        accumulated_output_size {} >= PER_BLOCK_OUTPUT_LIMIT {}, {} txns committed.",
+                        accumulated_output_size,
+                        per_block_output_limit,
+                        ret.len()
+                    );
+                    break;
+                }
+            }
         }
 
         if ret.len() == num_txns {
@@ -687,10 +1029,63 @@ where
         }
 
         counters::update_sequential_block_gas_counters(&accumulated_fee_statement, ret.len());
+        *self.last_execution_summary.lock() = Some(BlockExecutionSummary { txn_stats });
         ret.resize_with(num_txns, E::Output::skip_output);
         Ok(ret)
     }
 
+    /// Executes the block in parallel only to collect the read and write sets of its transactions
+    /// and their conflict graph. The outputs are discarded: nothing is committed, but the commit
+    /// hook, if any, is still notified (use the executor without a commit hook to analyze blocks).
+    /// Unlike execute_block, there is no fallback to sequential execution, which does not track
+    /// the reads: the errors that would trigger it (e.g. a module both read and published in the
+    /// block) are returned instead.
+    pub fn analyze_block(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: Vec<T>,
+        base_view: &S,
+    ) -> Result<BlockAnalysis<T::Key>, E::Error> {
+        assert!(
+            self.concurrency_level > 1,
+            "Analyzing a block requires parallel execution"
+        );
+
+        let mut accesses = Vec::with_capacity(signature_verified_block.len());
+        let ret = self.execute_transactions_parallel_with_accesses(
+            executor_arguments,
+            &signature_verified_block,
+            base_view,
+            Some(&mut accesses),
+        );
+        self.executor_thread_pool.spawn(move || {
+            // Explicit async drops.
+            drop(signature_verified_block);
+        });
+
+        // The accesses are only complete if the parallel execution succeeded.
+        ret.map(|outputs| {
+            self.executor_thread_pool.spawn(move || {
+                // Explicit async drops.
+                drop(outputs);
+            });
+            BlockAnalysis::new(accesses)
+        })
+    }
+
+    // Reports the keys written by several committed transactions to the hot key registry.
+    fn record_hot_keys(&self, outputs: &[E::Output]) {
+        if let Some(hot_key_registry) = &self.maybe_hot_key_registry {
+            hot_key_registry.record_block_writes(outputs.iter().map(|output| {
+                output
+                    .get_writes()
+                    .into_iter()
+                    .map(|(k, _)| k)
+                    .chain(output.get_deltas().into_iter().map(|(k, _)| k))
+            }));
+        }
+    }
+
     pub fn execute_block(
         &self,
         executor_arguments: E::Argument,
@@ -711,12 +1106,21 @@ where
             )
         };
 
-        if matches!(ret, Err(Error::ModulePathReadWrite)) {
-            debug!("[Execution]: Module read & written, sequential fallback");
+        let maybe_fallback_reason = match ret {
+            Err(Error::ModulePathReadWrite) => Some("Module read & written"),
+            Err(Error::MaxRetriesExceeded) => Some("Max re-executions exceeded"),
+            _ => None,
+        };
+        if let Some(fallback_reason) = maybe_fallback_reason {
+            debug!("[Execution]: {}, sequential fallback", fallback_reason);
 
             // All logs from the parallel execution should be cleared and not reported.
             // Clear by re-initializing the speculative logs.
             init_speculative_logs(signature_verified_block.len());
+            // The transactions committed by the parallel execution are committed again.
+            if let Some(commit_hook) = &self.transaction_commit_hook {
+                commit_hook.on_execution_restarted();
+            }
 
             ret = self.execute_transactions_sequential(
                 executor_arguments,
@@ -724,6 +1128,9 @@ where
                 base_view,
             )
         }
+        if let Ok(outputs) = &ret {
+            self.record_hot_keys(outputs);
+        }
         self.executor_thread_pool.spawn(move || {
             // Explicit async drops.
             drop(signature_verified_block);
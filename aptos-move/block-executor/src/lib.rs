@@ -136,12 +136,17 @@ and threads that perform these tasks can already detect validation failures
 due to the ESTIMATE markers on memory locations, instead of waiting for a
 subsequent incarnation to finish.
 **/
+pub mod block_analysis;
 pub mod counters;
 pub mod errors;
+pub mod execution_summary;
 pub mod executor;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod proptest_types;
 mod scheduler;
+pub mod scheduler_config;
+pub mod scheduler_stats;
+mod speculative_events;
 pub mod task;
 pub mod txn_commit_hook;
 pub mod txn_last_input_output;
@@ -13,7 +13,7 @@
 /// of each transaction of the tested block executor execution.
 use crate::{
     errors::{Error as BlockExecutorError, Result as BlockExecutorResult},
-    proptest_types::types::{MockOutput, MockTransaction, STORAGE_AGGREGATOR_VALUE},
+    proptest_types::types::{MockEvent, MockOutput, MockTransaction, STORAGE_AGGREGATOR_VALUE},
 };
 use aptos_aggregator::{delta_change_set::serialize, transaction::AggregatorValue};
 use aptos_types::write_set::TransactionWrite;
@@ -74,7 +74,8 @@ enum BaselineStatus {
 /// The size of the vector should be equal to the size of the block if the block execution
 /// was successful. Otherwise, it is the index of a transaction where the block execution
 /// stopped, e.g. due to gas limit, abort, or reconfiguration (skip rest status). It also
-/// contains resolved values for each of the deltas produced by the dummy transaction.
+/// contains resolved values for each of the deltas produced by the dummy transaction, and
+/// the events emitted by the latest incarnation of the dummy transaction.
 ///
 /// For both read_values and resolved_deltas the keys are not included because they are
 /// in the same order as the reads and deltas in the Transaction::Write.
@@ -82,6 +83,7 @@ pub(crate) struct BaselineOutput<V> {
     status: BaselineStatus,
     read_values: Vec<Result<Vec<BaselineValue<V>>, ()>>,
     resolved_deltas: Vec<Result<Vec<u128>, ()>>,
+    events: Vec<Vec<MockEvent>>,
 }
 
 impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
@@ -97,6 +99,7 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
         let mut status = BaselineStatus::Success;
         let mut read_values = vec![];
         let mut resolved_deltas = vec![];
+        let mut events = vec![];
         for txn in txns.iter() {
             match txn {
                 MockTransaction::Abort => {
@@ -108,6 +111,7 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
                     // transaction, so create a successful empty reads and deltas.
                     read_values.push(Ok(vec![]));
                     resolved_deltas.push(Ok(vec![]));
+                    events.push(vec![]);
 
                     status = BaselineStatus::SkipRest;
                     break;
@@ -121,6 +125,7 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
                     // the last mock execution, and is >= 1 because there is at least one execution.
                     let last_incarnation = (incarnation_counter.load(Ordering::SeqCst) - 1)
                         % incarnation_behaviors.len();
+                    events.push(incarnation_behaviors[last_incarnation].events.clone());
 
                     match incarnation_behaviors[last_incarnation]
                         .deltas
@@ -202,6 +207,7 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
             status,
             read_values,
             resolved_deltas,
+            events,
         }
     }
 
@@ -220,9 +226,13 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
                 izip!(
                     results.iter().take(committed),
                     self.read_values.iter(),
-                    self.resolved_deltas.iter()
+                    self.resolved_deltas.iter(),
+                    self.events.iter()
                 )
-                .for_each(|(output, reads, resolved_deltas)| {
+                .for_each(|(output, reads, resolved_deltas, events)| {
+                    // Committed outputs must contain exactly the events of the latest incarnation.
+                    assert_eq!(output.events, *events);
+
                     reads
                         .as_ref()
                         .expect("Aggregator failures not yet tested")
@@ -257,6 +267,7 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
                     // Ensure the transaction is skipped based on the output.
                     assert!(output.writes.is_empty());
                     assert!(output.deltas.is_empty());
+                    assert!(output.events.is_empty());
                     assert!(output.read_results.is_empty());
                     assert_eq!(output.total_gas, 0);
 
@@ -270,7 +281,8 @@ impl<V: Debug + Clone + PartialEq + Eq + TransactionWrite> BaselineOutput<V> {
                 assert_eq!(*idx, self.read_values.len());
                 assert_eq!(*idx, self.resolved_deltas.len());
             },
-            Err(BlockExecutorError::ModulePathReadWrite) => unimplemented!("not tested here"),
+            Err(BlockExecutorError::ModulePathReadWrite)
+            | Err(BlockExecutorError::MaxRetriesExceeded) => unimplemented!("not tested here"),
         }
     }
 }
@@ -167,7 +167,11 @@ fn dynamic_read_writes_with_block_gas_limit(num_txns: usize, maybe_block_gas_lim
     );
 }
 
-fn deltas_writes_mixed_with_block_gas_limit(num_txns: usize, maybe_block_gas_limit: Option<u64>) {
+fn deltas_writes_mixed_with_block_gas_limit(
+    num_txns: usize,
+    maybe_block_gas_limit: Option<u64>,
+    delay_delta_materialization: bool,
+) {
     let mut runner = TestRunner::default();
 
     let universe = vec(any::<[u8; 32]>(), 50)
@@ -212,13 +216,18 @@ fn deltas_writes_mixed_with_block_gas_limit(num_txns: usize, maybe_block_gas_lim
             maybe_block_gas_limit,
             None,
         )
+        .with_delayed_delta_materialization(delay_delta_materialization)
         .execute_transactions_parallel((), &transactions, &data_view);
 
         BaselineOutput::generate(&transactions, maybe_block_gas_limit).assert_output(&output);
     }
 }
 
-fn deltas_resolver_with_block_gas_limit(num_txns: usize, maybe_block_gas_limit: Option<u64>) {
+fn deltas_resolver_with_block_gas_limit(
+    num_txns: usize,
+    maybe_block_gas_limit: Option<u64>,
+    delay_delta_materialization: bool,
+) {
     let mut runner = TestRunner::default();
 
     let universe = vec(any::<[u8; 32]>(), 50)
@@ -263,6 +272,7 @@ fn deltas_resolver_with_block_gas_limit(num_txns: usize, maybe_block_gas_limit:
             maybe_block_gas_limit,
             None,
         )
+        .with_delayed_delta_materialization(delay_delta_materialization)
         .execute_transactions_parallel((), &transactions, &data_view);
 
         BaselineOutput::generate(&transactions, maybe_block_gas_limit).assert_output(&output);
@@ -475,12 +485,22 @@ fn dynamic_read_writes() {
 
 #[test]
 fn deltas_writes_mixed() {
-    deltas_writes_mixed_with_block_gas_limit(1000, None);
+    deltas_writes_mixed_with_block_gas_limit(1000, None, false);
 }
 
 #[test]
 fn deltas_resolver() {
-    deltas_resolver_with_block_gas_limit(1000, None);
+    deltas_resolver_with_block_gas_limit(1000, None, false);
+}
+
+#[test]
+fn deltas_writes_mixed_with_delayed_materialization() {
+    deltas_writes_mixed_with_block_gas_limit(1000, None, true);
+}
+
+#[test]
+fn deltas_resolver_with_delayed_materialization() {
+    deltas_resolver_with_block_gas_limit(1000, None, true);
 }
 
 #[test]
@@ -571,8 +591,9 @@ fn deltas_writes_mixed_with_block_gas_limit_test() {
     deltas_writes_mixed_with_block_gas_limit(
         1000,
         Some(rand::thread_rng().gen_range(0, 1000) as u64),
+        false,
     );
-    deltas_writes_mixed_with_block_gas_limit(1000, Some(0));
+    deltas_writes_mixed_with_block_gas_limit(1000, Some(0), false);
 }
 
 #[test]
@@ -580,8 +601,29 @@ fn deltas_resolver_with_block_gas_limit_test() {
     deltas_resolver_with_block_gas_limit(
         1000,
         Some(rand::thread_rng().gen_range(0, 1000 * MAX_GAS_PER_TXN / 2)),
+        false,
+    );
+    deltas_resolver_with_block_gas_limit(1000, Some(0), false);
+}
+
+#[test]
+fn deltas_writes_mixed_with_block_gas_limit_and_delayed_materialization_test() {
+    deltas_writes_mixed_with_block_gas_limit(
+        1000,
+        Some(rand::thread_rng().gen_range(0, 1000) as u64),
+        true,
+    );
+    deltas_writes_mixed_with_block_gas_limit(1000, Some(0), true);
+}
+
+#[test]
+fn deltas_resolver_with_block_gas_limit_and_delayed_materialization_test() {
+    deltas_resolver_with_block_gas_limit(
+        1000,
+        Some(rand::thread_rng().gen_range(0, 1000 * MAX_GAS_PER_TXN / 2)),
+        true,
     );
-    deltas_resolver_with_block_gas_limit(1000, Some(0));
+    deltas_resolver_with_block_gas_limit(1000, Some(0), true);
 }
 
 #[test]
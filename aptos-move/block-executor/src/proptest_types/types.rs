@@ -160,6 +160,20 @@ impl<V: Into<Vec<u8>> + Debug + Clone + Eq + Send + Sync + Arbitrary> Transactio
     }
 }
 
+/// A mock event, emitted to a given key with a given payload. For testing purposes, events
+/// of different incarnations of the same transaction may differ (e.g. in the payload).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MockEvent {
+    pub(crate) key: u64,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl MockEvent {
+    pub(crate) fn new(key: u64, payload: Vec<u8>) -> Self {
+        Self { key, payload }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub(crate) struct TransactionGenParams {
     /// Each transaction's read-set consists of between 1 and read_size-1 many reads.
@@ -210,6 +224,8 @@ pub(crate) struct MockIncarnation<K, V> {
     pub(crate) writes: Vec<(K, V)>,
     /// A vector of keys and corresponding deltas to be produced during mock incarnation execution.
     pub(crate) deltas: Vec<(K, DeltaOp)>,
+    /// A vector of events to be emitted during mock incarnation execution.
+    pub(crate) events: Vec<MockEvent>,
     /// total execution gas to be charged for mock incarnation execution.
     pub(crate) gas: u64,
 }
@@ -372,6 +388,7 @@ impl<V: Into<Vec<u8>> + Arbitrary + Clone + Debug + Eq + Sync + Send> Transactio
             reads,
             writes,
             deltas,
+            events: vec![],
             gas,
         })
         .collect();
@@ -467,6 +484,7 @@ where
     K: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug + 'static,
     V: Debug + Send + Sync + Debug + Clone + TransactionWrite + 'static,
 {
+    type Event = MockEvent;
     type Key = K;
     type Value = V;
 }
@@ -530,6 +548,7 @@ where
                 ExecutionStatus::Success(MockOutput {
                     writes: behavior.writes.clone(),
                     deltas: behavior.deltas.clone(),
+                    events: behavior.events.clone(),
                     read_results: reads_result,
                     materialized_delta_writes: OnceCell::new(),
                     total_gas: behavior.gas,
@@ -546,6 +565,7 @@ pub(crate) struct MockOutput<K, V> {
     // TODO: Split writes into resources & modules.
     pub(crate) writes: Vec<(K, V)>,
     pub(crate) deltas: Vec<(K, DeltaOp)>,
+    pub(crate) events: Vec<MockEvent>,
     pub(crate) read_results: Vec<Option<Vec<u8>>>,
     pub(crate) materialized_delta_writes: OnceCell<Vec<(K, WriteOp)>>,
     pub(crate) total_gas: u64,
@@ -566,10 +586,15 @@ where
         self.deltas.clone()
     }
 
+    fn get_events(&self) -> Vec<MockEvent> {
+        self.events.clone()
+    }
+
     fn skip_output() -> Self {
         Self {
             writes: vec![],
             deltas: vec![],
+            events: vec![],
             read_results: vec![],
             materialized_delta_writes: OnceCell::new(),
             total_gas: 0,
@@ -593,4 +618,15 @@ where
             0,
         )
     }
+
+    fn output_size(&self) -> u64 {
+        let writes_size: usize = self
+            .writes
+            .iter()
+            .filter_map(|(_, v)| v.extract_raw_bytes())
+            .map(|bytes| bytes.len())
+            .sum();
+        let events_size: usize = self.events.iter().map(|event| event.payload.len()).sum();
+        (writes_size + events_size) as u64
+    }
 }
@@ -2,17 +2,21 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::counters::GET_NEXT_TASK_SECONDS;
+use crate::{
+    counters::GET_NEXT_TASK_SECONDS,
+    scheduler_config::{ConflictResolutionPolicy, DependencyWaitStrategy, SchedulerConfig},
+    scheduler_stats::SchedulerCounters,
+};
 use aptos_infallible::Mutex;
 use aptos_mvhashmap::types::{Incarnation, TxnIndex, Version};
 use crossbeam::utils::CachePadded;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use std::{
-    cmp::{max, min},
+    cmp::{max, min, Reverse},
     hint,
     ops::DerefMut,
     sync::{
-        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
         Arc, Condvar,
     },
 };
@@ -30,7 +34,25 @@ pub enum DependencyStatus {
     // The parallel execution is halted.
     ExecutionHalted,
 }
-type DependencyCondvar = Arc<(Mutex<DependencyStatus>, Condvar)>;
+/// The status of a read dependency, the condition variable to wait on for it to be resolved, and
+/// a flag set once it is no longer Unresolved, which allows polling it without taking the lock.
+type DependencyCondvar = Arc<(Mutex<DependencyStatus>, Condvar, AtomicBool)>;
+
+fn new_dependency_condvar() -> DependencyCondvar {
+    Arc::new((
+        Mutex::new(DependencyStatus::Unresolved),
+        Condvar::new(),
+        AtomicBool::new(false),
+    ))
+}
+
+/// Sets the status of the read dependency, and wakes up the thread waiting for it.
+pub(crate) fn resolve_dependency(condvar: &DependencyCondvar, status: DependencyStatus) {
+    let (lock, cvar, resolved) = &**condvar;
+    *lock.lock() = status;
+    resolved.store(true, Ordering::Release);
+    cvar.notify_one();
+}
 
 // Return value of the function wait_for_dependency
 pub enum DependencyResult {
@@ -74,7 +96,9 @@ pub enum SchedulerTask {
 /// of a prior incarnation is waiting on it with a read dependency resolved (when dependency was
 /// encountered, the status changed to Suspended, and suspended changed to Ready when the dependency
 /// finished its execution). In this case the caller need not create a new execution task, but
-/// just notify the suspended execution via the dependency condition variable.
+/// just notify the suspended execution via the dependency condition variable. Under the EagerAbort
+/// conflict resolution policy, the execution that encountered the dependency is abandoned instead
+/// of waiting, and suspended changes to Ready(incarnation + 1) with the Execution task type.
 ///
 /// 'Executing' status of an incarnation turns into 'Executed' if the execution task finishes, or
 /// if a dependency is encountered, it becomes 'Ready(incarnation + 1)' once the
@@ -211,6 +235,10 @@ pub struct Scheduler {
     /// Next transaction to commit, and sweeping lower bound on the wave of a validation that must
     /// be successful in order to commit the next transaction.
     commit_state: CachePadded<Mutex<(TxnIndex, Wave)>>,
+    /// Number of committed transactions (i.e. the next transaction to commit), mirrored from
+    /// commit_state so that it can be read without locking when enforcing the
+    /// validation-ahead window.
+    num_committed: CachePadded<AtomicU32>,
 
     // Note: with each thread reading both counters when deciding the next task, and being able
     // to choose either execution or validation task, separately padding these indices may increase
@@ -236,15 +264,46 @@ pub struct Scheduler {
     /// monotonically increasing index stored in the first 32 bits.
     validation_idx: AtomicU64,
 
+    /// Indices of transactions ordered by decreasing priority (based on the priority hints
+    /// provided by the caller), whose initial incarnations are executed before following the
+    /// preset serialization order. Empty if no priority hints were provided.
+    priority_order: Vec<TxnIndex>,
+    /// A shared index into priority_order, of the next prioritized transaction to execute.
+    priority_idx: AtomicUsize,
+
     /// Shared marker that is set when a thread detects that all txns can be committed.
     done_marker: CachePadded<AtomicBool>,
+
+    /// If set, execution tasks are only created for transactions with index below
+    /// num_committed + validation_ahead_window.
+    validation_ahead_window: Option<TxnIndex>,
+    /// How executions wait for read dependencies to be resolved.
+    dependency_wait_strategy: DependencyWaitStrategy,
+    /// How the conflicts between speculative executions are resolved.
+    conflict_resolution_policy: ConflictResolutionPolicy,
+    /// Set when the parallel execution is halted as a transaction exceeded the bound on
+    /// re-executions of the BoundedRetriesThenSequential policy.
+    max_retries_exceeded: AtomicBool,
+    /// Statistics for the post-block report, collected only if enabled.
+    stats: Option<SchedulerCounters>,
 }
 
 /// Public Interfaces for the Scheduler
 impl Scheduler {
     pub fn new(num_txns: TxnIndex) -> Self {
+        Self::new_with_priorities(num_txns, vec![])
+    }
+
+    /// Creates a scheduler that prefers executing the initial incarnations of transactions
+    /// in the provided priority order (e.g. obtained by 'priority_order' from the hints).
+    /// Transactions not in the priority order are executed following the preset order.
+    pub fn new_with_priorities(num_txns: TxnIndex, priority_order: Vec<TxnIndex>) -> Self {
         // Empty block should early return and not create a scheduler.
         assert!(num_txns > 0, "No scheduler needed for 0 transactions");
+        assert!(
+            priority_order.iter().all(|idx| *idx < num_txns),
+            "Prioritized transaction index out of bounds"
+        );
 
         Self {
             num_txns,
@@ -260,12 +319,70 @@ impl Scheduler {
                 })
                 .collect(),
             commit_state: CachePadded::new(Mutex::new((0, 0))),
+            num_committed: CachePadded::new(AtomicU32::new(0)),
             execution_idx: AtomicU32::new(0),
             validation_idx: AtomicU64::new(0),
+            priority_order,
+            priority_idx: AtomicUsize::new(0),
             done_marker: CachePadded::new(AtomicBool::new(false)),
+            validation_ahead_window: None,
+            dependency_wait_strategy: DependencyWaitStrategy::Block,
+            conflict_resolution_policy: ConflictResolutionPolicy::Optimistic,
+            max_retries_exceeded: AtomicBool::new(false),
+            stats: None,
         }
     }
 
+    /// Applies the tuning knobs of the scheduler.
+    pub fn with_config(mut self, config: SchedulerConfig) -> Self {
+        assert!(
+            config.validation_ahead_window != Some(0),
+            "Validation-ahead window must be positive"
+        );
+        self.validation_ahead_window = config.validation_ahead_window;
+        self.dependency_wait_strategy = config.dependency_wait_strategy;
+        self.conflict_resolution_policy = config.conflict_resolution_policy;
+        self.stats = config.collect_stats.then(SchedulerCounters::default);
+        self
+    }
+
+    pub fn dependency_wait_strategy(&self) -> DependencyWaitStrategy {
+        self.dependency_wait_strategy
+    }
+
+    pub fn conflict_resolution_policy(&self) -> ConflictResolutionPolicy {
+        self.conflict_resolution_policy
+    }
+
+    /// Returns the statistics collected for the post-block report, if enabled.
+    pub(crate) fn stats(&self) -> Option<&SchedulerCounters> {
+        self.stats.as_ref()
+    }
+
+    /// Returns the current execution queue depth (the number of transactions claimed for
+    /// execution but not yet committed) and validation queue depth (the number of
+    /// transactions claimed for execution but not yet claimed for validation).
+    pub fn queue_depths(&self) -> (TxnIndex, TxnIndex) {
+        let idx_to_execute = min(self.execution_idx.load(Ordering::Relaxed), self.num_txns);
+        let (idx_to_validate, _) =
+            Self::unpack_validation_idx(self.validation_idx.load(Ordering::Relaxed));
+        (
+            idx_to_execute.saturating_sub(self.num_committed.load(Ordering::Relaxed)),
+            idx_to_execute.saturating_sub(idx_to_validate),
+        )
+    }
+
+    /// Converts per-transaction priority hints (a higher value means a higher priority) to
+    /// the order in which the transactions should be prioritized. Transactions with hint 0
+    /// are not prioritized, and ties are broken by the preset serialization order.
+    pub fn priority_order(priority_hints: &[u64]) -> Vec<TxnIndex> {
+        let mut prioritized: Vec<TxnIndex> = (0..priority_hints.len() as TxnIndex)
+            .filter(|idx| priority_hints[*idx as usize] > 0)
+            .collect();
+        prioritized.sort_by_key(|idx| (Reverse(priority_hints[*idx as usize]), *idx));
+        prioritized
+    }
+
     pub fn num_txns(&self) -> TxnIndex {
         self.num_txns
     }
@@ -301,6 +418,7 @@ impl Scheduler {
                             *status_write = ExecutionStatus::Committed(incarnation);
 
                             *commit_idx += 1;
+                            self.num_committed.store(*commit_idx, Ordering::Release);
                             if *commit_idx == self.num_txns {
                                 // All txns have been committed, the parallel execution can finish.
                                 self.done_marker.store(true, Ordering::SeqCst);
@@ -374,6 +492,18 @@ impl Scheduler {
                 };
             }
 
+            if !prefer_validate && self.exceeds_validation_ahead_window(idx_to_execute) {
+                // Executing further ahead would only increase the amount of speculative work
+                // that may be wasted. Wait for the commit index to advance instead.
+                if let Some(stats) = &self.stats {
+                    stats.record_throttled_poll();
+                }
+                if !committing {
+                    hint::spin_loop();
+                }
+                return SchedulerTask::NoTask;
+            }
+
             if prefer_validate {
                 if let Some((version_to_validate, wave)) =
                     self.try_validate_next_version(idx_to_validate, wave)
@@ -385,6 +515,10 @@ impl Scheduler {
             {
                 return SchedulerTask::ExecutionTask(version_to_execute, execution_task_type);
             }
+
+            if let Some(stats) = &self.stats {
+                stats.record_empty_claim();
+            }
         }
     }
 
@@ -403,7 +537,7 @@ impl Scheduler {
         // usually has just observed the read dependency.
 
         // Create a condition variable associated with the dependency.
-        let dep_condvar = Arc::new((Mutex::new(DependencyStatus::Unresolved), Condvar::new()));
+        let dep_condvar = new_dependency_condvar();
 
         let mut stored_deps = self.txn_dependency[dep_txn_idx as usize].lock();
 
@@ -441,6 +575,78 @@ impl Scheduler {
         DependencyResult::Dependency(dep_condvar)
     }
 
+    /// Under the EagerAbort policy, abandons the ongoing execution of version (txn_idx,
+    /// incarnation), which read an estimate written by dep_txn_idx. Similar to
+    /// wait_for_dependency, txn_idx is added to the dependency list of dep_txn_idx, but the
+    /// transaction is then executed again as a new incarnation, instead of resuming the abandoned
+    /// execution. If the dependency got resolved in the meantime, the transaction is ready to be
+    /// executed again right away, and the re-execution task may be returned to the caller.
+    pub fn abandon_execution(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        dep_txn_idx: TxnIndex,
+    ) -> SchedulerTask {
+        debug_assert!(self.conflict_resolution_policy == ConflictResolutionPolicy::EagerAbort);
+        {
+            let mut stored_deps = self.txn_dependency[dep_txn_idx as usize].lock();
+            if self.is_executed(dep_txn_idx, true).is_none() {
+                // Nobody waits on the condition variable, as resume makes the transaction ready
+                // for a new incarnation under the EagerAbort policy.
+                let dep_condvar = new_dependency_condvar();
+                if self.suspend(txn_idx, dep_condvar) {
+                    stored_deps.push(txn_idx);
+                }
+                return SchedulerTask::NoTask;
+            }
+        }
+
+        // The dependency is resolved, the transaction can be executed again right away.
+        {
+            let mut status = self.txn_status[txn_idx as usize].0.write();
+            match *status {
+                ExecutionStatus::Executing(incarnation_executing) => {
+                    debug_assert!(incarnation_executing == incarnation);
+                    *status = ExecutionStatus::Ready(incarnation + 1, ExecutionTaskType::Execution);
+                },
+                ExecutionStatus::ExecutionHalted => return SchedulerTask::NoTask,
+                _ => unreachable!(),
+            }
+        }
+        // As in finish_abort, the re-execution task is returned to the caller unless the
+        // execution index is lower, in which case the transaction will be executed anyway.
+        if self.execution_idx.load(Ordering::Acquire) > txn_idx {
+            if let Some((new_incarnation, execution_task_type)) = self.try_incarnate(txn_idx) {
+                return SchedulerTask::ExecutionTask(
+                    (txn_idx, new_incarnation),
+                    execution_task_type,
+                );
+            }
+        }
+        SchedulerTask::NoTask
+    }
+
+    /// Under the BoundedRetriesThenSequential policy, returns true if the incarnation is a
+    /// re-execution beyond the bound, in which case the parallel execution is halted, so that
+    /// the block is executed sequentially.
+    pub fn halt_if_max_retries_exceeded(&self, incarnation: Incarnation) -> bool {
+        match self.conflict_resolution_policy {
+            ConflictResolutionPolicy::BoundedRetriesThenSequential(max_retries)
+                if incarnation > max_retries =>
+            {
+                self.max_retries_exceeded.store(true, Ordering::Release);
+                self.halt();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns true if the parallel execution was halted by halt_if_max_retries_exceeded.
+    pub fn max_retries_exceeded(&self) -> bool {
+        self.max_retries_exceeded.load(Ordering::Acquire)
+    }
+
     pub fn finish_validation(&self, txn_idx: TxnIndex, wave: Wave) {
         let mut validation_status = self.txn_status[txn_idx as usize].1.write();
         validation_status.maybe_max_validated_wave = Some(
@@ -490,8 +696,14 @@ impl Scheduler {
         if let Some(execution_target_idx) = min_dep {
             // Decrease the execution index as necessary to ensure resolved dependencies
             // get a chance to be re-executed.
-            self.execution_idx
+            let prev_execution_idx = self
+                .execution_idx
                 .fetch_min(execution_target_idx, Ordering::SeqCst);
+            if let Some(stats) = &self.stats {
+                if prev_execution_idx > execution_target_idx {
+                    stats.record_execution_idx_decrease();
+                }
+            }
         }
 
         let (cur_val_idx, mut cur_wave) =
@@ -592,11 +804,9 @@ impl Scheduler {
             match &*status {
                 ExecutionStatus::Suspended(_, condvar)
                 | ExecutionStatus::Ready(_, ExecutionTaskType::Wakeup(condvar)) => {
-                    let (lock, cvar) = &*(condvar.clone());
-                    // Mark parallel execution halted due to reasons like module r/w intersection.
-                    *lock.lock() = DependencyStatus::ExecutionHalted;
-                    // Wake up the process waiting for dependency.
-                    cvar.notify_one();
+                    // Mark parallel execution halted due to reasons like module r/w intersection,
+                    // and wake up the process waiting for dependency.
+                    resolve_dependency(condvar, DependencyStatus::ExecutionHalted);
                 },
                 _ => (),
             }
@@ -616,6 +826,17 @@ impl Scheduler {
         )
     }
 
+    /// Returns true if the validation-ahead window is set and idx_to_execute is outside of it.
+    fn exceeds_validation_ahead_window(&self, idx_to_execute: TxnIndex) -> bool {
+        self.validation_ahead_window.map_or(false, |window| {
+            idx_to_execute
+                >= self
+                    .num_committed
+                    .load(Ordering::Acquire)
+                    .saturating_add(window)
+        })
+    }
+
     /// Decreases the validation index, adjusting the wave and validation status as needed.
     fn decrease_validation_idx(&self, target_idx: TxnIndex) -> Option<Wave> {
         // We only call with txn_idx + 1, so it can equal num_txns, but not be strictly larger.
@@ -645,6 +866,9 @@ impl Scheduler {
                     }
                 })
         {
+            if let Some(stats) = &self.stats {
+                stats.record_validation_wave();
+            }
             let (_, wave) = Self::unpack_validation_idx(prev_val_idx);
             // Note that 'wave' is the previous wave value, and we must update it to 'wave + 1'.
             Some(wave + 1)
@@ -764,6 +988,10 @@ impl Scheduler {
     /// return the version to the caller for the corresponding ExecutionTask.
     /// - Otherwise, return None.
     fn try_execute_next_version(&self) -> Option<(Version, ExecutionTaskType)> {
+        if let Some(version) = self.try_execute_next_prioritized_version() {
+            return Some((version, ExecutionTaskType::Execution));
+        }
+
         let idx_to_execute = self.execution_idx.fetch_add(1, Ordering::SeqCst);
 
         if idx_to_execute >= self.num_txns {
@@ -778,6 +1006,24 @@ impl Scheduler {
             })
     }
 
+    /// Grab the next prioritized transaction (by fetch-and-incrementing priority_idx) whose
+    /// initial incarnation has not yet been executed, and return its version to the caller.
+    /// Returns None once all prioritized transactions have been claimed. Re-executions
+    /// are always scheduled following the preset serialization order.
+    fn try_execute_next_prioritized_version(&self) -> Option<Version> {
+        while self.priority_idx.load(Ordering::Relaxed) < self.priority_order.len() {
+            let idx = self.priority_idx.fetch_add(1, Ordering::SeqCst);
+            if let Some(txn_idx) = self.priority_order.get(idx) {
+                let mut status = self.txn_status[*txn_idx as usize].0.write();
+                if let ExecutionStatus::Ready(0, ExecutionTaskType::Execution) = &*status {
+                    *status = ExecutionStatus::Executing(0);
+                    return Some((*txn_idx, 0));
+                }
+            }
+        }
+        None
+    }
+
     /// Put a transaction in a suspended state, with a condition variable that can be
     /// used to wake it up after the dependency is resolved.
     /// Return true when the txn is successfully suspended.
@@ -805,10 +1051,16 @@ impl Scheduler {
         }
 
         if let ExecutionStatus::Suspended(incarnation, dep_condvar) = &*status {
-            *status = ExecutionStatus::Ready(
-                *incarnation,
-                ExecutionTaskType::Wakeup(dep_condvar.clone()),
-            );
+            *status = match self.conflict_resolution_policy {
+                // The suspended execution was abandoned, the transaction is executed again.
+                ConflictResolutionPolicy::EagerAbort => {
+                    ExecutionStatus::Ready(*incarnation + 1, ExecutionTaskType::Execution)
+                },
+                _ => ExecutionStatus::Ready(
+                    *incarnation,
+                    ExecutionTaskType::Wakeup(dep_condvar.clone()),
+                ),
+            };
         } else {
             unreachable!();
         }
@@ -0,0 +1,54 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_mvhashmap::types::TxnIndex;
+
+/// How an execution that encountered a read dependency waits for the dependency to be resolved.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DependencyWaitStrategy {
+    /// Block on the condition variable associated with the dependency right away.
+    #[default]
+    Block,
+    /// Poll the dependency status up to the given number of times before blocking on the
+    /// condition variable, avoiding the cost of parking and waking up the thread when
+    /// dependencies are resolved quickly.
+    SpinThenBlock(u32),
+}
+
+/// How the scheduler resolves the conflicts between the speculative executions of transactions,
+/// trading the latency variance of the blocks against the throughput of the execution.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConflictResolutionPolicy {
+    /// An execution that reads an estimate written by a lower transaction waits for the lower
+    /// transaction to be re-executed (following the dependency wait strategy), and transactions
+    /// are re-executed as many times as their validations fail.
+    #[default]
+    Optimistic,
+    /// An execution that reads an estimate written by a lower transaction is abandoned right
+    /// away, and the transaction is executed again as a new incarnation once the lower
+    /// transaction is re-executed. Workers never block on dependencies, at the cost of restarting
+    /// the abandoned executions from scratch.
+    EagerAbort,
+    /// Like Optimistic, but once a transaction is about to be re-executed more than the given
+    /// number of times, the parallel execution is halted and the block is executed sequentially,
+    /// bounding the speculative work wasted on heavily conflicting blocks.
+    BoundedRetriesThenSequential(u32),
+}
+
+/// Tuning knobs of the BlockSTM scheduler. The default configuration preserves the standard
+/// scheduling behavior.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SchedulerConfig {
+    /// If set, bounds how far ahead of the next transaction to commit the scheduler may create
+    /// execution tasks: transactions with index >= (commit index + window) are not executed
+    /// until the commit index advances, keeping (re-)validations within the window. This
+    /// limits wasted speculative work for high-contention workloads. Must be positive.
+    pub validation_ahead_window: Option<TxnIndex>,
+    /// How executions wait for read dependencies to be resolved.
+    pub dependency_wait_strategy: DependencyWaitStrategy,
+    /// If set, the scheduler and the workers collect statistics, which are available as a
+    /// SchedulerReport after the block is executed.
+    pub collect_stats: bool,
+    /// How the conflicts between speculative executions are resolved.
+    pub conflict_resolution_policy: ConflictResolutionPolicy,
+}
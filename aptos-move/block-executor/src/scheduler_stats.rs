@@ -0,0 +1,137 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_mvhashmap::types::TxnIndex;
+use std::{
+    cmp::max,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Statistics collected by a single worker thread during parallel execution of a block.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WorkerStats {
+    /// Number of execution tasks (including re-executions) performed by the worker.
+    pub num_executions: u64,
+    /// Number of validation tasks performed by the worker.
+    pub num_validations: u64,
+    /// Number of suspended executions woken up by the worker.
+    pub num_wakeups: u64,
+    /// Number of times the worker polled the scheduler without obtaining a task.
+    pub num_idle_polls: u64,
+    /// Total time the worker spent polling the scheduler without obtaining a task.
+    pub idle_time: Duration,
+    /// Maximum observed number of transactions claimed for execution but not yet committed.
+    pub max_execution_queue_depth: TxnIndex,
+    /// Maximum observed number of transactions claimed for execution but not yet claimed
+    /// for validation (in the current validation wave).
+    pub max_validation_queue_depth: TxnIndex,
+}
+
+impl WorkerStats {
+    /// Records a poll of the scheduler, given whether it yielded a task, the time it took
+    /// and the (execution, validation) queue depths observed afterwards.
+    pub(crate) fn record_poll(
+        &mut self,
+        idle: bool,
+        elapsed: Duration,
+        (execution_queue_depth, validation_queue_depth): (TxnIndex, TxnIndex),
+    ) {
+        if idle {
+            self.num_idle_polls += 1;
+            self.idle_time += elapsed;
+        }
+        self.max_execution_queue_depth = max(self.max_execution_queue_depth, execution_queue_depth);
+        self.max_validation_queue_depth =
+            max(self.max_validation_queue_depth, validation_queue_depth);
+    }
+}
+
+/// Post-block report of the BlockSTM scheduler, available when statistics collection is
+/// enabled in the SchedulerConfig, for diagnosing scheduling regressions.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SchedulerReport {
+    /// Number of transactions in the block.
+    pub num_txns: TxnIndex,
+    /// Statistics of each worker thread that participated in the execution.
+    pub worker_stats: Vec<WorkerStats>,
+    /// Number of validation waves triggered, i.e. how many times the validation index was
+    /// decreased due to aborts or executions that wrote outside of the previous write-set.
+    pub num_validation_waves: u64,
+    /// Number of times the execution index was decreased to re-execute transactions whose
+    /// read dependencies got resolved.
+    pub num_execution_idx_decreases: u64,
+    /// Number of indices claimed from the shared execution and validation indices that did
+    /// not yield a task, because the transaction was concurrently taken by another worker or
+    /// was not in a suitable state (the equivalent of failed steals).
+    pub num_empty_claims: u64,
+    /// Number of polls for which the creation of execution tasks was throttled by the
+    /// validation-ahead window.
+    pub num_throttled_polls: u64,
+    /// Number of executions that waited for a read dependency to be resolved.
+    pub num_dependency_waits: u64,
+    /// Total time executions spent waiting for read dependencies to be resolved.
+    pub dependency_wait_time: Duration,
+}
+
+impl SchedulerReport {
+    /// Total time the workers spent polling the scheduler without obtaining a task.
+    pub fn total_idle_time(&self) -> Duration {
+        self.worker_stats.iter().map(|stats| stats.idle_time).sum()
+    }
+}
+
+/// Statistics shared by all workers, collected by the scheduler.
+#[derive(Debug, Default)]
+pub(crate) struct SchedulerCounters {
+    num_validation_waves: AtomicU64,
+    num_execution_idx_decreases: AtomicU64,
+    num_empty_claims: AtomicU64,
+    num_throttled_polls: AtomicU64,
+    num_dependency_waits: AtomicU64,
+    dependency_wait_nanos: AtomicU64,
+}
+
+impl SchedulerCounters {
+    pub(crate) fn record_validation_wave(&self) {
+        self.num_validation_waves.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_execution_idx_decrease(&self) {
+        self.num_execution_idx_decreases
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_empty_claim(&self) {
+        self.num_empty_claims.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_throttled_poll(&self) {
+        self.num_throttled_polls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dependency_wait(&self, elapsed: Duration) {
+        self.num_dependency_waits.fetch_add(1, Ordering::Relaxed);
+        self.dependency_wait_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report(
+        &self,
+        num_txns: TxnIndex,
+        worker_stats: Vec<WorkerStats>,
+    ) -> SchedulerReport {
+        SchedulerReport {
+            num_txns,
+            worker_stats,
+            num_validation_waves: self.num_validation_waves.load(Ordering::Relaxed),
+            num_execution_idx_decreases: self.num_execution_idx_decreases.load(Ordering::Relaxed),
+            num_empty_claims: self.num_empty_claims.load(Ordering::Relaxed),
+            num_throttled_polls: self.num_throttled_polls.load(Ordering::Relaxed),
+            num_dependency_waits: self.num_dependency_waits.load(Ordering::Relaxed),
+            dependency_wait_time: Duration::from_nanos(
+                self.dependency_wait_nanos.load(Ordering::Relaxed),
+            ),
+        }
+    }
+}
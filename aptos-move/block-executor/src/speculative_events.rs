@@ -0,0 +1,108 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters;
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use crossbeam::utils::CachePadded;
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Events emitted by a particular incarnation of a transaction.
+struct IncarnationEvents<Ev> {
+    incarnation: Incarnation,
+    events: Vec<Ev>,
+}
+
+/// Buffers the events emitted by speculative executions during parallel execution. Events
+/// of an incarnation are recorded when the incarnation finishes executing, and must be
+/// explicitly rolled back when the incarnation is aborted (i.e. fails validation). When a
+/// transaction is committed, its buffered events are taken out of the buffer.
+///
+/// The buffer keeps track of speculative events that have leaked, i.e. events of an
+/// incarnation that were not rolled back before a higher incarnation of the same transaction
+/// recorded its events, or committed events that do not match the events in the committed
+/// output of the transaction. Since every recorded event is a copy, the buffer is only used
+/// when the invariant checks are enabled.
+pub(crate) struct SpeculativeEventBuffer<Ev> {
+    txn_events: Vec<CachePadded<Mutex<Option<IncarnationEvents<Ev>>>>>,
+    num_leaked: AtomicUsize,
+}
+
+impl<Ev: Debug + Clone + PartialEq> SpeculativeEventBuffer<Ev> {
+    pub(crate) fn new(num_txns: TxnIndex) -> Self {
+        Self {
+            txn_events: (0..num_txns)
+                .map(|_| CachePadded::new(Mutex::new(None)))
+                .collect(),
+            num_leaked: AtomicUsize::new(0),
+        }
+    }
+
+    /// Records the events emitted by the given incarnation of a transaction. Any events
+    /// still buffered for a previous incarnation at this point have not been rolled back,
+    /// and are counted as leaked.
+    pub(crate) fn record(&self, txn_idx: TxnIndex, incarnation: Incarnation, events: Vec<Ev>) {
+        let mut slot = self.txn_events[txn_idx as usize].lock();
+        if let Some(prev) = slot.as_ref() {
+            if prev.incarnation < incarnation && !prev.events.is_empty() {
+                self.report_leak(prev.events.len());
+            }
+        }
+        *slot = Some(IncarnationEvents {
+            incarnation,
+            events,
+        });
+    }
+
+    /// Rolls back the events emitted by the aborted incarnation of a transaction. Returns
+    /// the number of events that were discarded. Events recorded by a different (newer)
+    /// incarnation are left intact.
+    pub(crate) fn rollback(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> usize {
+        let mut slot = self.txn_events[txn_idx as usize].lock();
+        let num_rolled_back = match slot.as_ref() {
+            Some(entry) if entry.incarnation == incarnation => entry.events.len(),
+            _ => return 0,
+        };
+        *slot = None;
+
+        counters::SPECULATIVE_EVENT_ROLLBACK_COUNT.inc_by(num_rolled_back as u64);
+        num_rolled_back
+    }
+
+    /// Removes the buffered events of a committed transaction from the buffer. Buffered
+    /// events must match the events of the committed output.
+    pub(crate) fn commit(&self, txn_idx: TxnIndex, committed_events: &[Ev]) {
+        let events = self.txn_events[txn_idx as usize]
+            .lock()
+            .take()
+            .map_or(vec![], |entry| entry.events);
+
+        if events.as_slice() != committed_events {
+            self.report_leak(events.len());
+        }
+    }
+
+    /// Discards the buffered events of all transactions starting from the given index,
+    /// which were (speculatively) executed but not committed, e.g. due to an early halt.
+    pub(crate) fn discard_from(&self, first_uncommitted_idx: TxnIndex) {
+        for slot in self.txn_events.iter().skip(first_uncommitted_idx as usize) {
+            if let Some(entry) = slot.lock().take() {
+                counters::SPECULATIVE_EVENT_ROLLBACK_COUNT.inc_by(entry.events.len() as u64);
+            }
+        }
+    }
+
+    /// Returns the number of speculative events that leaked so far.
+    pub(crate) fn num_leaked(&self) -> usize {
+        self.num_leaked.load(Ordering::Acquire)
+    }
+
+    fn report_leak(&self, num_events: usize) {
+        counters::SPECULATIVE_EVENT_LEAK_COUNT.inc();
+        self.num_leaked
+            .fetch_add(num_events.max(1), Ordering::AcqRel);
+    }
+}
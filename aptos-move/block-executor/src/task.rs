@@ -30,9 +30,11 @@ pub enum ExecutionStatus<T, E> {
 pub trait Transaction: Sync + Send + Clone + 'static {
     type Key: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug;
     type Value: Send + Sync + Clone + TransactionWrite;
+    type Event: Send + Sync + Debug + Clone + PartialEq;
 }
 
 /// Inference result of a transaction.
+#[derive(Clone, Debug)]
 pub struct Accesses<K> {
     pub keys_read: Vec<K>,
     pub keys_written: Vec<K>,
@@ -83,6 +85,9 @@ pub trait TransactionOutput: Send + Sync + Debug {
     /// Get the aggregator deltas of a transaction from its output.
     fn get_deltas(&self) -> Vec<(<Self::Txn as Transaction>::Key, DeltaOp)>;
 
+    /// Get the events emitted by a transaction from its output.
+    fn get_events(&self) -> Vec<<Self::Txn as Transaction>::Event>;
+
     /// Execution output for transactions that comes after SkipRest signal.
     fn skip_output() -> Self;
 
@@ -96,4 +101,9 @@ pub trait TransactionOutput: Send + Sync + Debug {
 
     /// Return the fee statement of the transaction.
     fn fee_statement(&self) -> FeeStatement;
+
+    /// Return the size of the output in bytes, i.e. of the writes, aggregator deltas and events,
+    /// which counts towards the per-block output limit. Like the fee statement, it is obtained
+    /// before the delta writes are incorporated.
+    fn output_size(&self) -> u64;
 }
@@ -13,6 +13,29 @@ pub trait TransactionCommitHook: Send + Sync {
     fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &Self::Output);
 
     fn on_execution_aborted(&self, txn_idx: TxnIndex);
+
+    /// Called when the block is executed again from the start (sequentially, as modules were
+    /// published concurrently), in which case the commits notified so far are void, and all the
+    /// transactions are notified again.
+    fn on_execution_restarted(&self) {}
+}
+
+/// Lets a hook outlive the block execution it listens to, e.g. to act on the committed
+/// transactions once the block is executed.
+impl<H: TransactionCommitHook> TransactionCommitHook for &H {
+    type Output = H::Output;
+
+    fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &Self::Output) {
+        (**self).on_transaction_committed(txn_idx, output)
+    }
+
+    fn on_execution_aborted(&self, txn_idx: TxnIndex) {
+        (**self).on_execution_aborted(txn_idx)
+    }
+
+    fn on_execution_restarted(&self) {
+        (**self).on_execution_restarted()
+    }
 }
 
 pub struct NoOpTransactionCommitHook<T, E> {
@@ -3,6 +3,7 @@
 
 use crate::{
     errors::Error,
+    execution_summary::{BlockExecutionSummary, TxnExecutionCounters},
     task::{ExecutionStatus, Transaction, TransactionOutput},
 };
 use anyhow::anyhow;
@@ -22,6 +23,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 type TxnInput<K> = Vec<ReadDescriptor<K>>;
@@ -129,6 +131,10 @@ pub struct TxnLastInputOutput<K, T: TransactionOutput, E: Debug> {
 
     outputs: Vec<CachePadded<ArcSwapOption<TxnOutput<T, E>>>>, // txn_idx -> output.
 
+    // Number of (speculative) executions and the time spent executing, over all the executions
+    // of each transaction, for telemetry.
+    execution_counters: Vec<CachePadded<TxnExecutionCounters>>, // txn_idx -> counters.
+
     // Record all writes and reads to access paths corresponding to modules (code) in any
     // (speculative) executions. Used to avoid a potential race with module publishing and
     // Move-VM loader cache - see 'record' function comment for more information.
@@ -147,6 +153,9 @@ impl<K: ModulePath, T: TransactionOutput, E: Debug + Send + Clone> TxnLastInputO
             outputs: (0..num_txns)
                 .map(|_| CachePadded::new(ArcSwapOption::empty()))
                 .collect(),
+            execution_counters: (0..num_txns)
+                .map(|_| CachePadded::new(TxnExecutionCounters::default()))
+                .collect(),
             module_writes: DashSet::new(),
             module_reads: DashSet::new(),
             module_read_write_intersection: AtomicBool::new(false),
@@ -181,12 +190,16 @@ impl<K: ModulePath, T: TransactionOutput, E: Debug + Send + Clone> TxnLastInputO
     /// error that ensures a fallback to a correct sequential execution.
     /// When the sets do not have an intersection, it is impossible for the race to occur as any
     /// module in the loader cache may not be published by a transaction in the ongoing block.
+    /// The execution and its `execution_time` are accounted in the execution summary either way.
     pub(crate) fn record(
         &self,
         txn_idx: TxnIndex,
         input: Vec<ReadDescriptor<K>>,
         output: ExecutionStatus<T, Error<E>>,
+        execution_time: Duration,
     ) -> anyhow::Result<()> {
+        self.execution_counters[txn_idx as usize].record_execution(execution_time);
+
         let read_modules: Vec<AccessPath> =
             input.iter().filter_map(|desc| desc.module_path()).collect();
         let written_modules: Vec<AccessPath> = match &output {
@@ -217,6 +230,22 @@ impl<K: ModulePath, T: TransactionOutput, E: Debug + Send + Clone> TxnLastInputO
         Ok(())
     }
 
+    /// Accounts an execution whose output was discarded in the execution summary.
+    pub(crate) fn record_abandoned_execution(&self, txn_idx: TxnIndex, execution_time: Duration) {
+        self.execution_counters[txn_idx as usize].record_execution(execution_time);
+    }
+
+    /// Returns the number of executions and the execution time of each transaction so far.
+    pub(crate) fn execution_summary(&self) -> BlockExecutionSummary {
+        BlockExecutionSummary {
+            txn_stats: self
+                .execution_counters
+                .iter()
+                .map(|counters| counters.stats())
+                .collect(),
+        }
+    }
+
     pub(crate) fn module_publishing_may_race(&self) -> bool {
         self.module_read_write_intersection.load(Ordering::Acquire)
     }
@@ -250,6 +279,20 @@ impl<K: ModulePath, T: TransactionOutput, E: Debug + Send + Clone> TxnLastInputO
         )
     }
 
+    /// Returns the output size of the transaction, 0 if the execution aborted.
+    pub(crate) fn output_size(&self, txn_idx: TxnIndex) -> u64 {
+        match &self.outputs[txn_idx as usize]
+            .load_full()
+            .expect("[BlockSTM]: Execution output must be recorded after execution")
+            .output_status
+        {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                output.output_size()
+            },
+            ExecutionStatus::Abort(_) => 0,
+        }
+    }
+
     pub(crate) fn update_to_skip_rest(&self, txn_idx: TxnIndex) {
         if let ExecutionStatus::Success(output) = self.take_output(txn_idx) {
             self.outputs[txn_idx as usize].store(Some(Arc::new(TxnOutput {
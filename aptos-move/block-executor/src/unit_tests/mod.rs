@@ -7,23 +7,30 @@ use crate::{
     proptest_types::{
         baseline::BaselineOutput,
         types::{
-            DeltaDataView, KeyType, MockIncarnation, MockOutput, MockTask, MockTransaction,
-            ValueType,
+            DeltaDataView, KeyType, MockEvent, MockIncarnation, MockOutput, MockTask,
+            MockTransaction, ValueType,
         },
     },
     scheduler::{DependencyResult, ExecutionTaskType, Scheduler, SchedulerTask},
+    scheduler_config::{ConflictResolutionPolicy, DependencyWaitStrategy, SchedulerConfig},
     txn_commit_hook::NoOpTransactionCommitHook,
 };
 use aptos_aggregator::delta_change_set::{delta_add, delta_sub, DeltaOp, DeltaUpdate};
 use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::{
+    block_executor::hot_state_keys::{HotKeyConfig, HotKeyRegistry},
     executable::{ExecutableTestType, ModulePath},
     write_set::TransactionWrite,
 };
 use claims::{assert_matches, assert_some_eq};
 use rand::{prelude::*, random};
 use std::{
-    cmp::min, collections::BTreeMap, fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc,
+    cmp::min,
+    collections::{BTreeMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    sync::Arc,
 };
 
 // TODO: add unit test for block gas limit!
@@ -51,6 +58,7 @@ where
         NoOpTransactionCommitHook<MockOutput<K, V>, usize>,
         ExecutableTestType,
     >::new(num_cpus::get(), executor_thread_pool, None, None)
+    .with_speculative_event_checks(true)
     .execute_transactions_parallel((), &transactions, &data_view);
 
     let baseline = BaselineOutput::generate(&transactions, None);
@@ -75,6 +83,7 @@ fn delta_counters() {
         reads: vec![],
         writes: vec![(key, random_value(false))],
         deltas: vec![],
+        events: vec![],
         gas: 1,
     })];
 
@@ -83,6 +92,7 @@ fn delta_counters() {
             reads: vec![key],
             writes: vec![],
             deltas: vec![(key, delta_add(5, u128::MAX))],
+            events: vec![],
             gas: 1,
         }));
     }
@@ -91,6 +101,7 @@ fn delta_counters() {
         reads: vec![],
         writes: vec![(key, random_value(false))],
         deltas: vec![],
+        events: vec![],
         gas: 1,
     }));
 
@@ -99,6 +110,7 @@ fn delta_counters() {
             reads: vec![key],
             writes: vec![],
             deltas: vec![(key, delta_sub(2, u128::MAX))],
+            events: vec![],
             gas: 1,
         }));
     }
@@ -143,6 +155,7 @@ fn delta_chains() {
                             false => None,
                         })
                         .collect(),
+                    events: vec![],
                     gas: 1,
                 },
             ),
@@ -167,6 +180,7 @@ fn cycle_transactions() {
                 reads: vec![KeyType(key, false)],
                 writes: vec![(KeyType(key, false), random_value(false))],
                 deltas: vec![],
+                events: vec![],
                 gas: 1,
             }));
         }
@@ -189,6 +203,7 @@ fn one_reads_all_barrier() {
                 reads: vec![*key],
                 writes: vec![(*key, random_value(false))],
                 deltas: vec![],
+                events: vec![],
                 gas: 1,
             }));
         }
@@ -197,6 +212,7 @@ fn one_reads_all_barrier() {
             reads: keys.clone(),
             writes: vec![],
             deltas: vec![],
+            events: vec![],
             gas: 1,
         }));
     }
@@ -215,6 +231,7 @@ fn one_writes_all_barrier() {
                 reads: vec![*key],
                 writes: vec![(*key, random_value(false))],
                 deltas: vec![],
+                events: vec![],
                 gas: 1,
             }));
         }
@@ -226,6 +243,7 @@ fn one_writes_all_barrier() {
                 .map(|key| (*key, random_value(false)))
                 .collect::<Vec<_>>(),
             deltas: vec![],
+            events: vec![],
             gas: 1,
         }));
     }
@@ -245,6 +263,7 @@ fn early_aborts() {
                 reads: vec![*key],
                 writes: vec![(*key, random_value(false))],
                 deltas: vec![],
+                events: vec![],
                 gas: 1,
             }));
         }
@@ -267,6 +286,7 @@ fn early_skips() {
                 reads: vec![*key],
                 writes: vec![(*key, random_value(false))],
                 deltas: vec![],
+                events: vec![],
                 gas: 1,
             }));
         }
@@ -276,6 +296,369 @@ fn early_skips() {
     run_and_assert(transactions)
 }
 
+#[test]
+fn module_events_with_reexecutions() {
+    let mut transactions = vec![];
+    let keys: Vec<_> = (0..10)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+
+    // Transactions contend on a small set of keys, leading to aborts and re-executions. Every
+    // incarnation of a transaction emits different events, so only the events of the latest
+    // incarnation may be observed in the committed output.
+    for i in 0..500 {
+        let key = keys[i % keys.len()];
+        let behaviors = (0..3)
+            .map(|incarnation| MockIncarnation {
+                reads: vec![key, keys[(i + incarnation) % keys.len()]],
+                writes: vec![(key, random_value(false))],
+                deltas: vec![],
+                events: (0..=incarnation)
+                    .map(|j| MockEvent::new(i as u64, vec![incarnation as u8, j as u8]))
+                    .collect(),
+                gas: 1,
+            })
+            .collect();
+        transactions.push(MockTransaction::from_behaviors(behaviors));
+    }
+    run_and_assert(transactions)
+}
+
+type TestKey = KeyType<[u8; 32]>;
+type TestValue = ValueType<Vec<u8>>;
+type TestBlockExecutor = BlockExecutor<
+    MockTransaction<TestKey, TestValue>,
+    MockTask<TestKey, TestValue>,
+    DeltaDataView<TestKey, TestValue>,
+    NoOpTransactionCommitHook<MockOutput<TestKey, TestValue>, usize>,
+    ExecutableTestType,
+>;
+
+fn test_block_executor(concurrency_level: usize) -> TestBlockExecutor {
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    TestBlockExecutor::new(concurrency_level, executor_thread_pool, None, None)
+}
+
+fn test_data_view() -> DeltaDataView<TestKey, TestValue> {
+    DeltaDataView {
+        phantom: PhantomData,
+    }
+}
+
+// Transactions contending on a small set of keys, each writing the key read by the next one,
+// leading to aborts and re-executions.
+fn contended_transactions(num_txns: usize) -> Vec<MockTransaction<TestKey, TestValue>> {
+    let keys: Vec<_> = (0..10)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    (0..num_txns)
+        .map(|i| {
+            MockTransaction::from_behavior(MockIncarnation {
+                reads: vec![keys[i % keys.len()]],
+                writes: vec![(keys[(i + 1) % keys.len()], random_value(false))],
+                deltas: vec![],
+                events: vec![],
+                gas: 1,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn priority_hints() {
+    let transactions = contended_transactions(500);
+    let priority_hints = (0..500).map(|_| random::<u64>() % 4).collect();
+
+    // Priority hints must not affect the output of the block.
+    let output = test_block_executor(num_cpus::get())
+        .with_priority_hints(priority_hints)
+        .execute_transactions_parallel((), &transactions, &test_data_view());
+
+    let baseline = BaselineOutput::generate(&transactions, None);
+    baseline.assert_output(&output);
+}
+
+#[test]
+fn scheduler_config_and_report() {
+    let transactions = contended_transactions(500);
+
+    // Tuning knobs must not affect the output of the block.
+    let executor = test_block_executor(num_cpus::get()).with_scheduler_config(SchedulerConfig {
+        validation_ahead_window: Some(8),
+        dependency_wait_strategy: DependencyWaitStrategy::SpinThenBlock(100),
+        collect_stats: true,
+        conflict_resolution_policy: ConflictResolutionPolicy::Optimistic,
+    });
+    let output = executor.execute_transactions_parallel((), &transactions, &test_data_view());
+
+    let baseline = BaselineOutput::generate(&transactions, None);
+    baseline.assert_output(&output);
+
+    let report = executor
+        .take_scheduler_report()
+        .expect("Report must be available when collecting stats");
+    assert_eq!(report.num_txns, 500);
+    assert_eq!(report.worker_stats.len(), num_cpus::get());
+    let num_executions: u64 = report
+        .worker_stats
+        .iter()
+        .map(|stats| stats.num_executions)
+        .sum();
+    assert!(num_executions >= 500);
+    assert!(executor.take_scheduler_report().is_none());
+}
+
+#[test]
+fn execution_summary() {
+    let transactions = contended_transactions(500);
+    let data_view = test_data_view();
+
+    let executor = test_block_executor(num_cpus::get()).with_scheduler_config(SchedulerConfig {
+        collect_stats: true,
+        ..SchedulerConfig::default()
+    });
+    let output = executor.execute_transactions_parallel((), &transactions, &data_view);
+    let baseline = BaselineOutput::generate(&transactions, None);
+    baseline.assert_output(&output);
+
+    // Every transaction is executed at least once, and the summary accounts for all the
+    // executions performed by the workers.
+    let summary = executor
+        .take_execution_summary()
+        .expect("Summary must be available after the execution");
+    assert_eq!(summary.txn_stats.len(), 500);
+    assert!(summary
+        .txn_stats
+        .iter()
+        .all(|stats| stats.num_executions >= 1));
+    let report = executor
+        .take_scheduler_report()
+        .expect("Report must be available when collecting stats");
+    let num_executions: u64 = report
+        .worker_stats
+        .iter()
+        .map(|stats| stats.num_executions)
+        .sum();
+    assert_eq!(summary.total_executions(), num_executions);
+    for (txn_idx, stats) in summary.most_reexecuted(10) {
+        assert!(stats.num_reexecutions() > 0);
+        assert_eq!(summary.txn_stats[txn_idx as usize], stats);
+    }
+    assert!(executor.take_execution_summary().is_none());
+
+    // Sequential execution executes every transaction exactly once.
+    let output = executor.execute_transactions_sequential((), &transactions, &data_view);
+    baseline.assert_output(&output);
+    let summary = executor
+        .take_execution_summary()
+        .expect("Summary must be available after the execution");
+    assert_eq!(summary.txn_stats.len(), 500);
+    assert!(summary
+        .txn_stats
+        .iter()
+        .all(|stats| stats.num_executions == 1));
+    assert!(summary.most_reexecuted(10).is_empty());
+}
+
+#[test]
+fn conflict_resolution_policies() {
+    let transactions = contended_transactions(500);
+    let baseline = BaselineOutput::generate(&transactions, None);
+
+    // The policy must not affect the output of the block, including when the block is executed
+    // sequentially as soon as any transaction is re-executed.
+    for conflict_resolution_policy in [
+        ConflictResolutionPolicy::Optimistic,
+        ConflictResolutionPolicy::EagerAbort,
+        ConflictResolutionPolicy::BoundedRetriesThenSequential(0),
+        ConflictResolutionPolicy::BoundedRetriesThenSequential(3),
+    ] {
+        let executor =
+            test_block_executor(num_cpus::get()).with_scheduler_config(SchedulerConfig {
+                conflict_resolution_policy,
+                ..SchedulerConfig::default()
+            });
+        let output = executor.execute_block((), transactions.clone(), &test_data_view());
+        baseline.assert_output(&output);
+    }
+}
+
+#[test]
+fn hot_key_registry() {
+    let shared_key = KeyType(random::<[u8; 32]>(), false);
+    // Every transaction writes the shared key, and a key that no other transaction writes.
+    let transactions: Vec<_> = (0..200)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation {
+                reads: vec![shared_key],
+                writes: vec![
+                    (shared_key, random_value(false)),
+                    (KeyType(random::<[u8; 32]>(), false), random_value(false)),
+                ],
+                deltas: vec![],
+                events: vec![],
+                gas: 1,
+            })
+        })
+        .collect();
+    let baseline = BaselineOutput::generate(&transactions, None);
+
+    // The registry is fed from the committed writes, so it ends up the same for the parallel
+    // and the sequential execution.
+    for concurrency_level in [num_cpus::get(), 1] {
+        let registry = Arc::new(HotKeyRegistry::new(HotKeyConfig::default()));
+        let output = test_block_executor(concurrency_level)
+            .with_hot_key_registry(registry.clone())
+            .execute_block((), transactions.clone(), &test_data_view());
+        baseline.assert_output(&output);
+
+        assert_eq!(registry.hot_keys(), HashSet::from([shared_key]));
+    }
+}
+
+#[test]
+fn block_output_limit() {
+    let transactions: Vec<_> = (0..100)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation {
+                reads: vec![],
+                writes: vec![(KeyType(random::<[u8; 32]>(), false), random_value(false))],
+                deltas: vec![],
+                events: vec![],
+                gas: 1,
+            })
+        })
+        .collect();
+
+    for concurrency_level in [1, num_cpus::get()] {
+        // Each transaction writes a 16 bytes value, so the limit is reached by the 11th one.
+        let executor =
+            test_block_executor(concurrency_level).with_block_output_limit(Some(16 * 10 + 1));
+        let output = executor
+            .execute_block((), transactions.clone(), &test_data_view())
+            .unwrap();
+
+        // The block is cut at the same transaction in parallel and sequential execution.
+        assert_eq!(output.len(), 100);
+        for (idx, txn_output) in output.iter().enumerate() {
+            assert_eq!(txn_output.writes.len(), usize::from(idx < 11));
+        }
+    }
+}
+
+#[test]
+fn scheduler_validation_ahead_window() {
+    let s = Scheduler::new(4).with_config(SchedulerConfig {
+        validation_ahead_window: Some(2),
+        dependency_wait_strategy: DependencyWaitStrategy::Block,
+        collect_stats: true,
+        conflict_resolution_policy: ConflictResolutionPolicy::Optimistic,
+    });
+
+    for i in 0..2 {
+        assert!(matches!(
+            s.next_task(false),
+            SchedulerTask::ExecutionTask((j, 0), ExecutionTaskType::Execution) if j == i
+        ));
+    }
+    // Transaction 2 is outside of the window until transaction 0 is committed.
+    assert!(matches!(s.next_task(false), SchedulerTask::NoTask));
+
+    assert!(matches!(
+        s.finish_execution(0, 0, false),
+        SchedulerTask::NoTask
+    ));
+    assert!(matches!(
+        s.next_task(false),
+        SchedulerTask::ValidationTask((0, 0), 0)
+    ));
+    s.finish_validation(0, 0);
+    assert_some_eq!(s.try_commit(), 0);
+
+    assert!(matches!(
+        s.next_task(false),
+        SchedulerTask::ExecutionTask((2, 0), ExecutionTaskType::Execution)
+    ));
+    assert!(matches!(s.next_task(false), SchedulerTask::NoTask));
+    assert_eq!(s.queue_depths(), (2, 2));
+
+    let report = s.stats().unwrap().report(s.num_txns(), vec![]);
+    assert_eq!(report.num_throttled_polls, 2);
+}
+
+#[test]
+fn scheduler_eager_abort() {
+    let s = Scheduler::new(10).with_config(SchedulerConfig {
+        conflict_resolution_policy: ConflictResolutionPolicy::EagerAbort,
+        ..SchedulerConfig::default()
+    });
+
+    for i in 0..5 {
+        assert!(matches!(
+            s.next_task(false),
+            SchedulerTask::ExecutionTask((j, 0), ExecutionTaskType::Execution) if j == i
+        ));
+    }
+
+    // Transaction 4 abandons its execution on a dependency on transaction 2.
+    assert!(matches!(
+        s.abandon_execution(4, 0, 2),
+        SchedulerTask::NoTask
+    ));
+    assert!(matches!(
+        s.finish_execution(2, 0, false),
+        SchedulerTask::NoTask
+    ));
+    // Once resumed, transaction 4 is executed again as a new incarnation.
+    assert!(matches!(
+        s.next_task(false),
+        SchedulerTask::ExecutionTask((4, 1), ExecutionTaskType::Execution)
+    ));
+
+    // The dependency of transaction 3 on transaction 2 is already resolved, so the re-execution
+    // task is returned right away.
+    assert!(matches!(
+        s.abandon_execution(3, 0, 2),
+        SchedulerTask::ExecutionTask((3, 1), ExecutionTaskType::Execution)
+    ));
+}
+
+#[test]
+fn scheduler_bounded_retries() {
+    let s = Scheduler::new(10).with_config(SchedulerConfig {
+        conflict_resolution_policy: ConflictResolutionPolicy::BoundedRetriesThenSequential(2),
+        ..SchedulerConfig::default()
+    });
+
+    assert!(!s.halt_if_max_retries_exceeded(2));
+    assert!(!s.max_retries_exceeded());
+    assert!(s.halt_if_max_retries_exceeded(3));
+    assert!(s.max_retries_exceeded());
+    assert!(matches!(s.next_task(false), SchedulerTask::Done));
+}
+
+#[test]
+fn scheduler_priority_order() {
+    assert_eq!(
+        Scheduler::priority_order(&[0, 3, 1, 3, 0, 7]),
+        vec![5, 1, 3, 2]
+    );
+
+    let s = Scheduler::new_with_priorities(5, vec![3, 1]);
+    // Prioritized transactions are executed first, followed by the rest in preset order.
+    for i in [3, 1, 0, 2, 4] {
+        assert!(matches!(
+            s.next_task(false),
+            SchedulerTask::ExecutionTask((j, 0), ExecutionTaskType::Execution) if i == j
+        ));
+    }
+}
+
 #[test]
 fn scheduler_tasks() {
     let s = Scheduler::new(5);
@@ -846,3 +1229,57 @@ fn no_conflict_task_count() {
         assert!(matches!(s.next_task(false), SchedulerTask::Done));
     }
 }
+
+#[test]
+fn analyze_block() {
+    let keys: Vec<_> = (0..6)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    // (keys read, keys written) by each transaction.
+    let accesses: Vec<(Vec<usize>, Vec<usize>)> = vec![
+        (vec![], vec![0]),
+        (vec![0], vec![1]),
+        (vec![1], vec![2]),
+        (vec![0], vec![3]),
+        (vec![5], vec![4]),
+        (vec![0, 1], vec![0]),
+    ];
+    let transactions: Vec<_> = accesses
+        .iter()
+        .map(|(reads, writes)| {
+            MockTransaction::from_behavior(MockIncarnation {
+                reads: reads.iter().map(|i| keys[*i]).collect(),
+                writes: writes
+                    .iter()
+                    .map(|i| (keys[*i], random_value(false)))
+                    .collect(),
+                deltas: vec![],
+                events: vec![],
+                gas: 1,
+            })
+        })
+        .collect();
+
+    let analysis = test_block_executor(num_cpus::get())
+        .analyze_block((), transactions, &test_data_view())
+        .unwrap();
+
+    assert_eq!(analysis.txn_accesses.len(), accesses.len());
+    for (txn_accesses, (reads, writes)) in analysis.txn_accesses.iter().zip(accesses.iter()) {
+        let mut keys_read: Vec<_> = reads.iter().map(|i| keys[*i]).collect();
+        keys_read.sort();
+        let mut keys_written: Vec<_> = writes.iter().map(|i| keys[*i]).collect();
+        keys_written.sort();
+        assert_eq!(txn_accesses.keys_read, keys_read);
+        assert_eq!(txn_accesses.keys_written, keys_written);
+    }
+
+    // Each transaction depends on the last earlier writers of the keys it reads.
+    assert_eq!(
+        analysis.dependencies,
+        vec![vec![], vec![0], vec![1], vec![0], vec![], vec![0, 1]]
+    );
+    assert_eq!(analysis.num_dependent_txns(), 4);
+    // 0 -> 1 -> 2 (or 0 -> 1 -> 5).
+    assert_eq!(analysis.critical_path_length(), 3);
+}
@@ -4,6 +4,7 @@
 use crate::{
     counters,
     scheduler::{DependencyResult, DependencyStatus, Scheduler},
+    scheduler_config::{ConflictResolutionPolicy, DependencyWaitStrategy},
     task::Transaction,
     txn_last_input_output::ReadDescriptor,
 };
@@ -23,7 +24,14 @@ use aptos_types::{
     write_set::TransactionWrite,
 };
 use aptos_vm_logging::{log_schema::AdapterLogSchema, prelude::*};
-use std::{cell::RefCell, fmt::Debug, hash::Hash, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    hash::Hash,
+    hint,
+    sync::{atomic::Ordering, Arc},
+    time::Instant,
+};
 
 /// A struct that is always used by a single thread performing an execution task. The struct is
 /// passed to the VM and acts as a proxy to resolve reads first in the shared multi-version
@@ -36,6 +44,9 @@ pub(crate) struct MVHashMapView<'a, K, V: TransactionWrite, X: Executable> {
     versioned_map: &'a MVHashMap<K, V, X>,
     scheduler: &'a Scheduler,
     captured_reads: RefCell<Vec<ReadDescriptor<K>>>,
+    // The transaction whose estimate was read, if the execution was abandoned on the read
+    // dependency under the EagerAbort conflict resolution policy.
+    abandoned_on_dependency: Cell<Option<TxnIndex>>,
 }
 
 /// A struct which describes the result of the read from the proxy. The client
@@ -50,6 +61,8 @@ pub(crate) enum ReadResult<V> {
     Unresolved,
     // Parallel execution halts.
     ExecutionHalted,
+    // The execution is abandoned on a read dependency.
+    ExecutionAbandoned,
     // Read did not return anything.
     None,
 }
@@ -66,9 +79,16 @@ impl<
             versioned_map,
             scheduler,
             captured_reads: RefCell::new(Vec::new()),
+            abandoned_on_dependency: Cell::new(None),
         }
     }
 
+    /// Returns the transaction whose estimate was read, if the execution was abandoned on the
+    /// read dependency, in which case the output of the execution must be discarded.
+    pub(crate) fn abandoned_on_dependency(&self) -> Option<TxnIndex> {
+        self.abandoned_on_dependency.get()
+    }
+
     /// Drains the captured reads.
     pub(crate) fn take_reads(&self) -> Vec<ReadDescriptor<K>> {
         self.captured_reads.take()
@@ -123,6 +143,16 @@ impl<
                 Err(Unresolved(_)) => return ReadResult::Unresolved,
                 Err(Dependency(dep_idx)) => {
                     // `self.txn_idx` estimated to depend on a write from `dep_idx`.
+                    if self.scheduler.conflict_resolution_policy()
+                        == ConflictResolutionPolicy::EagerAbort
+                    {
+                        // The caller registers the dependency once the VM returns, see
+                        // Scheduler::abandon_execution.
+                        if self.abandoned_on_dependency.get().is_none() {
+                            self.abandoned_on_dependency.set(Some(dep_idx));
+                        }
+                        return ReadResult::ExecutionAbandoned;
+                    }
                     match self.scheduler.wait_for_dependency(txn_idx, dep_idx) {
                         DependencyResult::Dependency(dep_condition) => {
                             let _timer = counters::DEPENDENCY_WAIT_SECONDS.start_timer();
@@ -140,11 +170,27 @@ impl<
                             // thread that aborted dep_idx was alive, and again, since lower txns
                             // than txn_idx are not blocked, so the execution of dep_idx will
                             // eventually finish and lead to unblocking txn_idx, contradiction.
-                            let (lock, cvar) = &*dep_condition;
+                            let wait_start = self.scheduler.stats().map(|_| Instant::now());
+                            let (lock, cvar, resolved) = &*dep_condition;
+                            if let DependencyWaitStrategy::SpinThenBlock(num_spins) =
+                                self.scheduler.dependency_wait_strategy()
+                            {
+                                for _ in 0..num_spins {
+                                    if resolved.load(Ordering::Acquire) {
+                                        break;
+                                    }
+                                    hint::spin_loop();
+                                }
+                            }
                             let mut dep_resolved = lock.lock();
                             while let DependencyStatus::Unresolved = *dep_resolved {
                                 dep_resolved = cvar.wait(dep_resolved).unwrap();
                             }
+                            if let (Some(stats), Some(wait_start)) =
+                                (self.scheduler.stats(), wait_start)
+                            {
+                                stats.record_dependency_wait(wait_start.elapsed());
+                            }
                             if let DependencyStatus::ExecutionHalted = *dep_resolved {
                                 return ReadResult::ExecutionHalted;
                             }
@@ -274,6 +320,11 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TStateView
                             StatusCode::STORAGE_ERROR,
                             Some("Speculative error to halt BlockSTM early.".to_string()),
                         ))),
+                        // Similarly, the output of an abandoned execution is discarded.
+                        ReadResult::ExecutionAbandoned => Err(anyhow::Error::new(VMStatus::error(
+                            StatusCode::STORAGE_ERROR,
+                            Some("Speculative error to abandon the execution.".to_string()),
+                        ))),
                         ReadResult::None => self.get_base_value(state_key),
                         ReadResult::Unresolved => unreachable!(
                             "Must be resolved as base value is recorded in the MV data structure"
@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{assert_success, assert_vm_status, MoveHarness};
+use crate::{assert_success, assert_vm_status, tests::common, MoveHarness};
 use aptos_cached_packages::aptos_stdlib;
 use aptos_framework::{BuildOptions, BuiltPackage};
 use aptos_package_builder::PackageBuilder;
@@ -204,6 +204,97 @@ fn verify_resource_groups_fail_when_not_enabled() {
     assert_vm_status!(result, StatusCode::CONSTRAINT_NOT_SATISFIED);
 }
 
+#[test]
+fn verify_module_events_fail_when_not_enabled() {
+    let mut h = MoveHarness::new_with_features(vec![], vec![FeatureFlag::MODULE_EVENT]);
+    let account = h.new_account_at(AccountAddress::from_hex_literal("0xf00d").unwrap());
+    let source = r#"
+        module 0xf00d::M {
+            struct Event has drop, store { }
+        }
+        "#;
+    let fake_attribute = FakeKnownAttribute {
+        kind: 4,
+        args: vec![],
+    };
+    let (code, metadata) =
+        build_package_and_insert_attribute(source, Some(("Event", fake_attribute)), None);
+    let result = h.run_transaction_payload(
+        &account,
+        aptos_stdlib::code_publish_package_txn(metadata, code),
+    );
+
+    assert_vm_status!(result, StatusCode::CONSTRAINT_NOT_SATISFIED);
+}
+
+#[test]
+fn test_emit_event() {
+    let mut h = MoveHarness::new();
+    let account = h.new_account_at(AccountAddress::from_hex_literal("0xf00d").unwrap());
+    let path = write_event_package(
+        r#"
+        module 0xf00d::M {
+            use aptos_framework::event;
+
+            #[event]
+            struct Event has drop, store { }
+
+            public entry fun emit() { event::emit(Event { }) }
+        }
+        "#,
+    );
+    assert_success!(h.publish_package(&account, path.path()));
+}
+
+#[test]
+#[should_panic]
+fn test_emit_event_without_event_attribute() {
+    let mut h = MoveHarness::new();
+    let account = h.new_account_at(AccountAddress::from_hex_literal("0xf00d").unwrap());
+    let path = write_event_package(
+        r#"
+        module 0xf00d::M {
+            use aptos_framework::event;
+
+            struct Event has drop, store { }
+
+            public entry fun emit() { event::emit(Event { }) }
+        }
+        "#,
+    );
+    assert_success!(h.publish_package(&account, path.path()));
+}
+
+#[test]
+#[should_panic]
+fn test_emit_event_of_another_module() {
+    let mut h = MoveHarness::new();
+    let account = h.new_account_at(AccountAddress::from_hex_literal("0xf00d").unwrap());
+    let path = write_event_package(
+        r#"
+        module 0xf00d::M {
+            use aptos_framework::event;
+            use aptos_framework::guid;
+
+            public entry fun emit() { event::emit(guid::create_id(@0xf00d, 0)) }
+        }
+        "#,
+    );
+    assert_success!(h.publish_package(&account, path.path()));
+}
+
+fn write_event_package(source: &str) -> tempfile::TempDir {
+    let mut builder = PackageBuilder::new("Package");
+    builder.add_source("m.move", source);
+    builder.add_local_dep(
+        "AptosFramework",
+        &common::framework_dir_path("aptos-framework")
+            .display()
+            .to_string(),
+    );
+    builder.write_to_temp().unwrap()
+}
+
 fn build_package_and_insert_attribute(
     source: &str,
     struct_attr: Option<(&str, FakeKnownAttribute)>,
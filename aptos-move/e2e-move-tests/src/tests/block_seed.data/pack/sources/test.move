@@ -0,0 +1,16 @@
+module 0xbeef::test {
+    use aptos_framework::block_seed;
+
+    struct Seed has key {
+        seed: vector<u8>,
+    }
+
+    public entry fun read_seed(account: &signer) acquires Seed {
+        let seed = block_seed::per_block_seed();
+        if (exists<Seed>(@0xbeef)) {
+            borrow_global_mut<Seed>(@0xbeef).seed = seed;
+        } else {
+            move_to(account, Seed { seed });
+        }
+    }
+}
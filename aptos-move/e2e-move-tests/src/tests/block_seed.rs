@@ -0,0 +1,57 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{assert_abort, assert_success, tests::common, MoveHarness};
+use aptos_crypto::HashValue;
+use aptos_framework::natives::block_seed::NativeBlockSeedContext;
+use aptos_types::{account_address::AccountAddress, on_chain_config::FeatureFlag};
+use move_core_types::parser::parse_struct_tag;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+struct Seed {
+    seed: Vec<u8>,
+}
+
+#[test]
+fn block_seed() {
+    let mut h = MoveHarness::new();
+
+    let acc = h.new_account_at(AccountAddress::from_hex_literal("0xbeef").unwrap());
+    assert_success!(h.publish_package(&acc, &common::test_dir_path("block_seed.data/pack")));
+
+    let run = |h: &mut MoveHarness| {
+        h.run_entry_function(
+            &acc,
+            str::parse("0xbeef::test::read_seed").unwrap(),
+            vec![],
+            vec![],
+        )
+    };
+
+    // EBLOCK_SEED_NOT_ENABLED, with the NOT_IMPLEMENTED category.
+    h.enable_features(vec![], vec![FeatureFlag::BLOCK_SEED]);
+    assert_abort!(run(&mut h), 0x0C_0001);
+    // The seed is stored by the block prologue, so until the next block user transactions get
+    // ESEED_NOT_AVAILABLE (with the UNAVAILABLE category) even once it is enabled.
+    h.enable_features(vec![FeatureFlag::BLOCK_SEED], vec![]);
+    assert_abort!(run(&mut h), 0x0D_0002);
+
+    // The blocks of the test executor all have the zero id.
+    h.executor.new_block();
+    assert_success!(run(&mut h));
+    let seed = h
+        .read_resource::<Seed>(
+            acc.address(),
+            parse_struct_tag("0xbeef::test::Seed").unwrap(),
+        )
+        .unwrap()
+        .seed;
+    assert_eq!(
+        seed,
+        NativeBlockSeedContext::for_block(HashValue::zero())
+            .seed()
+            .unwrap()
+            .to_vec()
+    );
+}
@@ -0,0 +1,37 @@
+module 0x1::counter_test {
+    use std::signer;
+
+    use aptos_framework::counter::{Self, Counter};
+
+    /// When the value of a snapshot is not the expected one.
+    const ENOT_EQUAL: u64 = 17;
+
+    struct CounterHolder has key {
+        counter: Counter,
+    }
+
+    public entry fun create(account: &signer, limit: u128) {
+        move_to(account, CounterHolder { counter: counter::create(limit) });
+    }
+
+    public entry fun increment(account: &signer, value: u128) acquires CounterHolder {
+        let addr = signer::address_of(account);
+        let counter = &mut borrow_global_mut<CounterHolder>(addr).counter;
+        counter::increment(counter, value);
+    }
+
+    /// Checks the value of the counter before and after the increment, through snapshots.
+    public entry fun increment_and_check(
+        account: &signer,
+        value: u128,
+        expected: u128,
+    ) acquires CounterHolder {
+        let addr = signer::address_of(account);
+        let counter = &mut borrow_global_mut<CounterHolder>(addr).counter;
+        let snapshot = counter::read_snapshot(counter);
+        counter::increment(counter, value);
+        assert!(counter::snapshot_value(snapshot) == expected, ENOT_EQUAL);
+        let snapshot = counter::read_snapshot(counter);
+        assert!(counter::snapshot_value(snapshot) == expected + value, ENOT_EQUAL);
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{assert_abort, assert_success, tests::common, MoveHarness};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{on_chain_config::FeatureFlag, transaction::SignedTransaction};
+
+fn setup(enabled: bool) -> (MoveHarness, Account) {
+    let mut h = MoveHarness::new();
+    if enabled {
+        h.enable_features(vec![FeatureFlag::CONCURRENT_COUNTERS], vec![]);
+    } else {
+        h.enable_features(vec![], vec![FeatureFlag::CONCURRENT_COUNTERS]);
+    }
+    let acc = h.aptos_framework_account();
+    assert_success!(h.publish_package(&acc, &common::test_dir_path("counter.data/pack")));
+    (h, acc)
+}
+
+fn create(h: &mut MoveHarness, acc: &Account, limit: u128) -> SignedTransaction {
+    h.create_entry_function(
+        acc,
+        str::parse("0x1::counter_test::create").unwrap(),
+        vec![],
+        vec![bcs::to_bytes(&limit).unwrap()],
+    )
+}
+
+fn increment(h: &mut MoveHarness, acc: &Account, value: u128) -> SignedTransaction {
+    h.create_entry_function(
+        acc,
+        str::parse("0x1::counter_test::increment").unwrap(),
+        vec![],
+        vec![bcs::to_bytes(&value).unwrap()],
+    )
+}
+
+fn increment_and_check(
+    h: &mut MoveHarness,
+    acc: &Account,
+    value: u128,
+    expected: u128,
+) -> SignedTransaction {
+    h.create_entry_function(
+        acc,
+        str::parse("0x1::counter_test::increment_and_check").unwrap(),
+        vec![],
+        vec![
+            bcs::to_bytes(&value).unwrap(),
+            bcs::to_bytes(&expected).unwrap(),
+        ],
+    )
+}
+
+#[test]
+fn test_counter_snapshots() {
+    let (mut h, acc) = setup(true);
+    let txn = create(&mut h, &acc, 1000);
+    assert_success!(h.run(txn));
+
+    // The counter is only incremented (as a delta) within each transaction, so the snapshots
+    // read the value of the counter in storage, as left by the previous transactions.
+    let txns: Vec<_> = (1..=10)
+        .map(|i| increment_and_check(&mut h, &acc, i, (1..i).sum()))
+        .collect();
+    for status in h.run_block(txns) {
+        assert_success!(status);
+    }
+
+    // The increment overflowing the limit aborts, and leaves the counter as is.
+    let txns = vec![
+        increment(&mut h, &acc, 1000),
+        increment_and_check(&mut h, &acc, 5, 55),
+    ];
+    let statuses = h.run_block(txns);
+    assert_abort!(statuses[0], 0x020001);
+    assert_success!(statuses[1].clone());
+}
+
+#[test]
+fn test_counter_not_enabled() {
+    let (mut h, acc) = setup(false);
+    let txn = create(&mut h, &acc, 1000);
+    assert_abort!(h.run(txn), 0x0C0002);
+}
@@ -0,0 +1,43 @@
+module 0xbeef::test {
+    use aptos_framework::account;
+    use aptos_framework::event;
+
+    // The layout of A5 has 95 nodes, the one of A6 has 191 nodes.
+    struct A0 has copy, drop, store { a: u64 }
+    struct A1 has copy, drop, store { a: A0, b: A0 }
+    struct A2 has copy, drop, store { a: A1, b: A1 }
+    struct A3 has copy, drop, store { a: A2, b: A2 }
+    struct A4 has copy, drop, store { a: A3, b: A3 }
+    #[event]
+    struct A5 has copy, drop, store { a: A4, b: A4 }
+    #[event]
+    struct A6 has copy, drop, store { a: A5, b: A5 }
+
+    fun a5(): A5 {
+        let a0 = A0 { a: 0 };
+        let a1 = A1 { a: copy a0, b: a0 };
+        let a2 = A2 { a: copy a1, b: a1 };
+        let a3 = A3 { a: copy a2, b: a2 };
+        let a4 = A4 { a: copy a3, b: a3 };
+        A5 { a: copy a4, b: a4 }
+    }
+
+    fun a6(): A6 {
+        let a5 = a5();
+        A6 { a: copy a5, b: a5 }
+    }
+
+    public entry fun emit_small_module_event() {
+        event::emit(a5());
+    }
+
+    public entry fun emit_large_module_event() {
+        event::emit(a6());
+    }
+
+    public entry fun emit_large_handle_event(account: &signer) {
+        let handle = account::new_event_handle<A6>(account);
+        event::emit_event(&mut handle, a6());
+        event::destroy_handle(handle);
+    }
+}
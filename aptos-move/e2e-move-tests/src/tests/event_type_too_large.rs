@@ -0,0 +1,31 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{assert_abort, assert_success, tests::common, MoveHarness};
+use aptos_types::account_address::AccountAddress;
+
+#[test]
+fn event_type_too_large() {
+    let mut h = MoveHarness::new();
+
+    let acc = h.new_account_at(AccountAddress::from_hex_literal("0xbeef").unwrap());
+    assert_success!(h.publish_package(
+        &acc,
+        &common::test_dir_path("event_type_too_large.data/pack"),
+    ));
+
+    let mut run = |name: &str| {
+        h.run_entry_function(
+            &acc,
+            str::parse(&format!("0xbeef::test::{}", name)).unwrap(),
+            vec![],
+            vec![],
+        )
+    };
+
+    assert_success!(run("emit_small_module_event"));
+    // EEVENT_TYPE_TOO_LARGE, with the RESOURCE_EXHAUSTED category.
+    assert_abort!(run("emit_large_module_event"), 0x09_0004);
+    // The limits on the layouts of event types only apply to module events.
+    assert_success!(run("emit_large_handle_event"));
+}
@@ -4,11 +4,14 @@
 mod access_path_test;
 mod aggregator;
 mod attributes;
+mod block_seed;
 mod chain_id;
 mod code_publishing;
 mod common;
 mod constructor_args;
+mod counter;
 mod error_map;
+mod event_type_too_large;
 mod fee_payer;
 mod fungible_asset;
 mod gas;
@@ -31,6 +34,7 @@ mod resource_groups;
 mod rotate_auth_key;
 mod scripts;
 mod simple_defi;
+mod simulation;
 mod smart_data_structures;
 mod stake;
 mod state_metadata;
@@ -0,0 +1,99 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{assert_success, MoveHarness};
+use aptos_cached_packages::aptos_stdlib;
+use aptos_crypto::ed25519::Ed25519Signature;
+use aptos_framework::natives::event::EventFilter;
+use aptos_types::{
+    account_address::AccountAddress, contract_event::ContractEvent, transaction::SignedTransaction,
+};
+use aptos_vm::AptosVM;
+use serde_json::json;
+use std::sync::Arc;
+
+#[test]
+fn simulation_returns_decoded_events() {
+    let mut h = MoveHarness::new();
+    let sender = h.new_account_at(AccountAddress::from_hex_literal("0xcafe").unwrap());
+    let receiver = h.new_account_at(AccountAddress::from_hex_literal("0xbeef").unwrap());
+
+    let raw_txn = sender
+        .transaction()
+        .sequence_number(h.sequence_number(sender.address()))
+        .max_gas_amount(2_000_000)
+        .gas_unit_price(100)
+        .payload(aptos_stdlib::aptos_coin_transfer(*receiver.address(), 1000))
+        .raw();
+    // Simulated transactions must not carry a valid signature.
+    let txn = SignedTransaction::new(
+        raw_txn,
+        sender.pubkey.clone(),
+        Ed25519Signature::dummy_signature(),
+    );
+
+    let (_, output, decoded_events) =
+        AptosVM::simulate_signed_transaction_with_decoded_events(&txn, h.executor.get_state_view());
+    assert_success!(output.status().clone());
+    assert_eq!(decoded_events.len(), output.events().len());
+
+    let decoded_events: Vec<_> = decoded_events
+        .into_iter()
+        .map(|event| event.expect("Events emitted by the framework must be decodable"))
+        .collect();
+    let withdraw = decoded_events
+        .iter()
+        .find(|event| event.struct_tag().unwrap().to_string() == "0x1::coin::WithdrawEvent")
+        .expect("Coin transfer must emit a withdraw event");
+    assert_eq!(withdraw.data, json!({ "amount": "1000" }));
+    let deposit = decoded_events
+        .iter()
+        .find(|event| event.struct_tag().unwrap().to_string() == "0x1::coin::DepositEvent")
+        .expect("Coin transfer must emit a deposit event");
+    assert_eq!(deposit.data, json!({ "amount": "1000" }));
+}
+
+#[test]
+fn simulation_with_event_filter() {
+    struct DropDepositEvents;
+
+    impl EventFilter for DropDepositEvents {
+        fn filter(&self, event: ContractEvent) -> Option<ContractEvent> {
+            (event.type_tag().to_string() != "0x1::coin::DepositEvent").then_some(event)
+        }
+    }
+
+    let mut h = MoveHarness::new();
+    let sender = h.new_account_at(AccountAddress::from_hex_literal("0xcafe").unwrap());
+    let receiver = h.new_account_at(AccountAddress::from_hex_literal("0xbeef").unwrap());
+
+    let raw_txn = sender
+        .transaction()
+        .sequence_number(h.sequence_number(sender.address()))
+        .max_gas_amount(2_000_000)
+        .gas_unit_price(100)
+        .payload(aptos_stdlib::aptos_coin_transfer(*receiver.address(), 1000))
+        .raw();
+    let txn = SignedTransaction::new(
+        raw_txn,
+        sender.pubkey.clone(),
+        Ed25519Signature::dummy_signature(),
+    );
+
+    let (_, output) = AptosVM::simulate_signed_transaction(&txn, h.executor.get_state_view());
+    let (_, filtered_output) = AptosVM::simulate_signed_transaction_with_event_filter(
+        &txn,
+        h.executor.get_state_view(),
+        Arc::new(DropDepositEvents),
+    );
+    assert_success!(filtered_output.status().clone());
+
+    let expected_events: Vec<_> = output
+        .events()
+        .iter()
+        .filter(|event| DropDepositEvents.filter((*event).clone()).is_some())
+        .cloned()
+        .collect();
+    assert!(expected_events.len() < output.events().len());
+    assert_eq!(filtered_output.events(), expected_events.as_slice());
+}
@@ -0,0 +1,167 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A state view wrapper that injects latency and faults into reads, for testing the robustness
+//! of the executors against slow or misbehaving storage.
+
+use anyhow::{bail, Result};
+use aptos_state_view::{in_memory_state_view::InMemoryStateView, StateViewId, TStateView};
+use aptos_types::state_store::{state_storage_usage::StateStorageUsage, state_value::StateValue};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Faults injected by a [`ChaosStateView`]. The default configuration injects nothing.
+#[derive(Clone, Debug)]
+pub struct ChaosConfig<K> {
+    /// Latency added to every read of a key without a specific latency.
+    pub default_latency: Duration,
+    /// Latency added to reads of specific keys.
+    pub key_latencies: HashMap<K, Duration>,
+    /// Probability (between 0 and 1) that a read fails with a transient error.
+    pub transient_error_rate: f64,
+    /// Maximum number of transient errors injected over the lifetime of the view, after which
+    /// all reads succeed. Bounds the number of retries the caller needs.
+    pub max_transient_errors: usize,
+    /// Stale values returned instead of the values of the wrapped view, for specific keys.
+    pub stale_values: HashMap<K, Option<StateValue>>,
+    /// Probability (between 0 and 1) that a read of a key with a stale value returns it.
+    pub stale_read_rate: f64,
+    /// Seed of the random number generator that decides which reads are faulty.
+    pub seed: u64,
+}
+
+impl<K> Default for ChaosConfig<K> {
+    fn default() -> Self {
+        Self {
+            default_latency: Duration::ZERO,
+            key_latencies: HashMap::new(),
+            transient_error_rate: 0.0,
+            max_transient_errors: usize::MAX,
+            stale_values: HashMap::new(),
+            stale_read_rate: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Wraps a state view, injecting configurable per-key latency, transient errors and stale
+/// reads (see [`ChaosConfig`]). Works with any state view, e.g. [`FakeDataStore`] or the mock
+/// views of the block executor.
+///
+/// [`FakeDataStore`]: crate::data_store::FakeDataStore
+pub struct ChaosStateView<S: TStateView> {
+    inner: S,
+    config: ChaosConfig<S::Key>,
+    rng: Mutex<StdRng>,
+    num_transient_errors: AtomicUsize,
+    num_stale_reads: AtomicUsize,
+}
+
+impl<S> ChaosStateView<S>
+where
+    S: TStateView,
+    S::Key: Hash + Eq,
+{
+    pub fn new(inner: S, config: ChaosConfig<S::Key>) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&config.transient_error_rate)
+                && (0.0..=1.0).contains(&config.stale_read_rate),
+            "Fault rates must be between 0 and 1"
+        );
+        let rng = Mutex::new(StdRng::seed_from_u64(config.seed));
+        Self {
+            inner,
+            config,
+            rng,
+            num_transient_errors: AtomicUsize::new(0),
+            num_stale_reads: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the wrapped state view.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Number of transient errors injected so far.
+    pub fn num_transient_errors(&self) -> usize {
+        self.num_transient_errors.load(Ordering::Relaxed)
+    }
+
+    /// Number of stale values returned so far.
+    pub fn num_stale_reads(&self) -> usize {
+        self.num_stale_reads.load(Ordering::Relaxed)
+    }
+
+    fn sample(&self, rate: f64) -> bool {
+        rate > 0.0 && self.rng.lock().unwrap().gen_bool(rate)
+    }
+
+    fn try_inject_transient_error(&self) -> bool {
+        self.sample(self.config.transient_error_rate)
+            && self
+                .num_transient_errors
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |num_errors| {
+                    (num_errors < self.config.max_transient_errors).then_some(num_errors + 1)
+                })
+                .is_ok()
+    }
+}
+
+impl<S> TStateView for ChaosStateView<S>
+where
+    S: TStateView,
+    S::Key: Hash + Eq,
+{
+    type Key = S::Key;
+
+    fn id(&self) -> StateViewId {
+        self.inner.id()
+    }
+
+    fn get_state_value(&self, state_key: &S::Key) -> Result<Option<StateValue>> {
+        let latency = self
+            .config
+            .key_latencies
+            .get(state_key)
+            .copied()
+            .unwrap_or(self.config.default_latency);
+        if !latency.is_zero() {
+            thread::sleep(latency);
+        }
+
+        if self.try_inject_transient_error() {
+            bail!("Injected transient error");
+        }
+
+        if let Some(stale_value) = self.config.stale_values.get(state_key) {
+            if self.sample(self.config.stale_read_rate) {
+                self.num_stale_reads.fetch_add(1, Ordering::Relaxed);
+                return Ok(stale_value.clone());
+            }
+        }
+
+        self.inner.get_state_value(state_key)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.inner.is_genesis()
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage> {
+        self.inner.get_usage()
+    }
+
+    fn as_in_memory_state_view(&self) -> InMemoryStateView {
+        self.inner.as_in_memory_state_view()
+    }
+}
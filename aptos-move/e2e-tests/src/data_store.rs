@@ -25,7 +25,13 @@ use aptos_vm_genesis::{
 use move_core_types::language_storage::ModuleId;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::Arc,
+};
 
 /// Dummy genesis ChangeSet for testing
 pub static GENESIS_CHANGE_SET_HEAD: Lazy<ChangeSet> =
@@ -41,22 +47,101 @@ pub static GENESIS_CHANGE_SET_MAINNET: Lazy<ChangeSet> =
 ///
 /// Tests use this to set up state, and pass in a reference to the cache whenever a `StateView` or
 /// `RemoteCache` is needed.
+///
+/// The data is shared copy-on-write, so that [`snapshot`](FakeDataStore::snapshot) and
+/// [`fork`](FakeDataStore::fork) are cheap, and the data is only copied when a fork is modified.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct FakeDataStore {
-    state_data: HashMap<StateKey, StateValue>,
+    state_data: Arc<HashMap<StateKey, StateValue>>,
+}
+
+/// The difference of the value of a single key between two data stores.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StateValueDiff {
+    pub state_key: StateKey,
+    /// The value in the data store `diff` was called on, None if the key does not exist.
+    pub before: Option<StateValue>,
+    /// The value in the other data store, None if the key does not exist.
+    pub after: Option<StateValue>,
 }
 
 impl FakeDataStore {
     /// Creates a new `FakeDataStore` with the provided initial data.
     pub fn new(data: HashMap<StateKey, Vec<u8>>) -> Self {
         FakeDataStore {
-            state_data: data
-                .into_iter()
-                .map(|(k, v)| (k, StateValue::new_legacy(v)))
-                .collect(),
+            state_data: Arc::new(
+                data.into_iter()
+                    .map(|(k, v)| (k, StateValue::new_legacy(v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Loads a data store previously persisted by [`save`](FakeDataStore::save).
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut bytes = vec![];
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+        Ok(FakeDataStore {
+            state_data: Arc::new(bcs::from_bytes(&bytes)?),
+        })
+    }
+
+    /// Persists the contents of this data store to a file (BCS-encoded key/value pairs), so
+    /// that large generated states can be reloaded quickly with [`load`](FakeDataStore::load).
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        bcs::serialize_into(&mut writer, self.state_data.as_ref())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns an immutable snapshot of the current state, which is not affected by later
+    /// modifications of this data store. Can be passed wherever an `Arc` of a state view is
+    /// needed, e.g. to the sharded block executor.
+    pub fn snapshot(&self) -> Arc<FakeDataStore> {
+        Arc::new(self.fork())
+    }
+
+    /// Returns a new data store that starts with the current state, and can be modified
+    /// independently from this data store (e.g. to execute an alternative block).
+    pub fn fork(&self) -> FakeDataStore {
+        FakeDataStore {
+            state_data: self.state_data.clone(),
         }
     }
 
+    /// Returns the keys whose values differ between this data store and the other one, with
+    /// the values in both, sorted by key.
+    pub fn diff(&self, other: &FakeDataStore) -> Vec<StateValueDiff> {
+        if Arc::ptr_eq(&self.state_data, &other.state_data) {
+            return vec![];
+        }
+
+        let mut diffs: Vec<_> = self
+            .state_data
+            .iter()
+            .filter(|(state_key, value)| other.state_data.get(state_key) != Some(value))
+            .map(|(state_key, value)| StateValueDiff {
+                state_key: state_key.clone(),
+                before: Some(value.clone()),
+                after: other.state_data.get(state_key).cloned(),
+            })
+            .chain(
+                other
+                    .state_data
+                    .iter()
+                    .filter(|(state_key, _)| !self.state_data.contains_key(state_key))
+                    .map(|(state_key, value)| StateValueDiff {
+                        state_key: state_key.clone(),
+                        before: None,
+                        after: Some(value.clone()),
+                    }),
+            )
+            .collect();
+        diffs.sort_by(|a, b| a.state_key.cmp(&b.state_key));
+        diffs
+    }
+
     /// Adds a [`WriteSet`] to this data store.
     pub fn add_write_set(&mut self, write_set: &WriteSet) {
         for (state_key, write_op) in write_set {
@@ -71,22 +156,21 @@ impl FakeDataStore {
     ///
     /// Returns the previous data if the key was occupied.
     pub fn set_legacy(&mut self, state_key: StateKey, bytes: Vec<u8>) -> Option<StateValue> {
-        self.state_data
-            .insert(state_key, StateValue::new_legacy(bytes))
+        self.set(state_key, StateValue::new_legacy(bytes))
     }
 
     /// Sets a (key, value) pair within this data store.
     ///
     /// Returns the previous data if the key was occupied.
     pub fn set(&mut self, state_key: StateKey, state_value: StateValue) -> Option<StateValue> {
-        self.state_data.insert(state_key, state_value)
+        Arc::make_mut(&mut self.state_data).insert(state_key, state_value)
     }
 
     /// Deletes a key from this data store.
     ///
     /// Returns the previous data if the key was occupied.
     pub fn remove(&mut self, state_key: &StateKey) -> Option<StateValue> {
-        self.state_data.remove(state_key)
+        Arc::make_mut(&mut self.state_data).remove(state_key)
     }
 
     /// Adds an [`AccountData`] to this data store.
@@ -135,7 +219,7 @@ impl TStateView for FakeDataStore {
     }
 
     fn as_in_memory_state_view(&self) -> InMemoryStateView {
-        InMemoryStateView::new(self.state_data.clone())
+        InMemoryStateView::new(self.state_data.as_ref().clone())
     }
 }
 
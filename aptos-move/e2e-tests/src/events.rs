@@ -0,0 +1,110 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to assert on the events emitted by a transaction, based on their payloads decoded
+//! into JSON (see [`FakeExecutor::decode_event`]) rather than on their raw BCS bytes.
+
+use crate::executor::FakeExecutor;
+use aptos_types::{contract_event::ContractEvent, transaction::TransactionOutput};
+use move_core_types::{language_storage::TypeTag, parser::parse_type_tag};
+use serde_json::Value;
+
+/// Returns the decoded payloads of the events of the given type (e.g. `0x1::coin::DepositEvent`)
+/// emitted by the transaction, in emission order.
+pub fn find_events(
+    executor: &FakeExecutor,
+    output: &TransactionOutput,
+    event_type: &str,
+) -> Vec<Value> {
+    let type_tag = parse_event_type(event_type);
+    output
+        .events()
+        .iter()
+        .filter(|event| event.type_tag() == &type_tag)
+        .map(|event| decode(executor, event))
+        .collect()
+}
+
+/// Asserts that the transaction emitted an event of the given type whose payload matches the
+/// expected fields, and returns the payload of the first such event.
+///
+/// Only the fields present in `expected` are compared, recursively. Numbers are also matched
+/// against integers that are encoded as strings, so `json!({"amount": 100})` matches a `u64`
+/// amount of 100.
+pub fn assert_event_emitted(
+    executor: &FakeExecutor,
+    output: &TransactionOutput,
+    event_type: &str,
+    expected: Value,
+) -> Value {
+    let events = find_events(executor, output, event_type);
+    match events.iter().find(|event| matches(event, &expected)) {
+        Some(event) => event.clone(),
+        None => panic!(
+            "No {} event matching {} was emitted, the emitted events are:\n{}",
+            event_type,
+            expected,
+            describe_events(executor, output),
+        ),
+    }
+}
+
+/// Asserts that the transaction did not emit any event of the given type.
+pub fn assert_event_not_emitted(
+    executor: &FakeExecutor,
+    output: &TransactionOutput,
+    event_type: &str,
+) {
+    let events = find_events(executor, output, event_type);
+    assert!(
+        events.is_empty(),
+        "Unexpected {} events were emitted: {:?}",
+        event_type,
+        events
+    );
+}
+
+/// Returns whether `actual` contains all the fields of `expected`.
+fn matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => {
+            expected.iter().all(|(field, expected)| {
+                actual
+                    .get(field)
+                    .map_or(false, |actual| matches(actual, expected))
+            })
+        },
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| matches(actual, expected))
+        },
+        (Value::String(actual), Value::Number(expected)) => actual == &expected.to_string(),
+        _ => actual == expected,
+    }
+}
+
+fn parse_event_type(event_type: &str) -> TypeTag {
+    parse_type_tag(event_type)
+        .unwrap_or_else(|err| panic!("Invalid event type {}: {:?}", event_type, err))
+}
+
+fn decode(executor: &FakeExecutor, event: &ContractEvent) -> Value {
+    executor
+        .decode_event(event)
+        .unwrap_or_else(|err| panic!("Failed to decode {} event: {:#}", event.type_tag(), err))
+}
+
+fn describe_events(executor: &FakeExecutor, output: &TransactionOutput) -> String {
+    output
+        .events()
+        .iter()
+        .map(|event| match executor.decode_event(event) {
+            Ok(data) => format!("  {}: {}", event.type_tag(), data),
+            Err(err) => format!("  {}: <{:#}>", event.type_tag(), err),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
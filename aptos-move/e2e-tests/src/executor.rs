@@ -12,7 +12,7 @@ use crate::{
     },
     golden_outputs::GoldenOutputs,
 };
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use aptos_bitvec::BitVec;
 use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
 use aptos_crypto::HashValue;
@@ -31,6 +31,7 @@ use aptos_types::{
     },
     block_metadata::BlockMetadata,
     chain_id::ChainId,
+    contract_event::ContractEvent,
     on_chain_config::{
         Features, OnChainConfig, TimedFeatureOverride, TimedFeatures, ValidatorSet, Version,
     },
@@ -45,6 +46,7 @@ use aptos_types::{
 use aptos_vm::{
     block_executor::{AptosTransactionOutput, BlockAptosVM},
     data_cache::{AsMoveResolver, StorageAdapter},
+    decoded_event::decode_event_as_json,
     move_vm_ext::{MoveVmExt, SessionId},
     AptosVM, VMExecutor, VMValidator,
 };
@@ -807,6 +809,27 @@ impl FakeExecutor {
         Ok(write_set)
     }
 
+    /// Decodes the payload of the event into JSON (see [`aptos_vm::decoded_event`]), using the
+    /// layout of its type as defined by the modules currently in the data store.
+    pub fn decode_event(&self, event: &ContractEvent) -> anyhow::Result<serde_json::Value> {
+        let vm = MoveVmExt::new(
+            NativeGasParameters::zeros(),
+            MiscGasParameters::zeros(),
+            LATEST_GAS_FEATURE_VERSION,
+            self.chain_id,
+            self.features.clone(),
+            TimedFeatures::enable_all(),
+        )
+        .map_err(|err| anyhow!("Failed to create the VM: {:?}", err))?;
+        let remote_view = StorageAdapter::new(&self.data_store);
+        let session = vm.new_session(&remote_view, SessionId::void());
+        let type_tag = event.type_tag();
+        let layout = session
+            .get_fully_annotated_type_layout(type_tag)
+            .map_err(|err| anyhow!("Failed to resolve layout of {}: {:?}", type_tag, err))?;
+        decode_event_as_json(event, &layout)
+    }
+
     pub fn execute_view_function(
         &mut self,
         module_id: ModuleId,
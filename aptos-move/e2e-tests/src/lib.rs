@@ -12,9 +12,11 @@ use aptos_types::{transaction::TransactionStatus, vm_status::KeptVMStatus};
 
 pub mod account;
 pub mod account_universe;
+pub mod chaos_state_view;
 pub mod common_transactions;
 pub mod compile;
 pub mod data_store;
+pub mod events;
 pub mod execution_strategies;
 pub mod executor;
 pub mod gas_costs;
@@ -22,6 +24,9 @@ mod golden_outputs;
 pub mod on_chain_configs;
 mod proptest_types;
 
+#[doc(hidden)]
+pub use serde_json;
+
 pub fn assert_status_eq(s1: &KeptVMStatus, s2: &KeptVMStatus) -> bool {
     assert_eq!(s1, s2);
     true
@@ -57,6 +62,40 @@ macro_rules! assert_prologue_disparity {
     };
 }
 
+/// Asserts that a transaction emitted an event of the given type whose payload contains the
+/// given fields, and returns the decoded payload. See [`events::assert_event_emitted`].
+///
+/// ```ignore
+/// assert_event_emitted!(executor, output, "0x1::coin::DepositEvent", { "amount": 1_000 });
+/// ```
+#[macro_export]
+macro_rules! assert_event_emitted {
+    ($executor:expr, $output:expr, $event_type:expr) => {
+        $crate::events::assert_event_emitted(
+            &$executor,
+            &$output,
+            $event_type,
+            $crate::serde_json::json!({}),
+        )
+    };
+    ($executor:expr, $output:expr, $event_type:expr, $($fields:tt)+) => {
+        $crate::events::assert_event_emitted(
+            &$executor,
+            &$output,
+            $event_type,
+            $crate::serde_json::json!($($fields)+),
+        )
+    };
+}
+
+/// Asserts that a transaction did not emit any event of the given type.
+#[macro_export]
+macro_rules! assert_event_not_emitted {
+    ($executor:expr, $output:expr, $event_type:expr) => {
+        $crate::events::assert_event_not_emitted(&$executor, &$output, $event_type)
+    };
+}
+
 /// Returns the name of the current function. This macro is used to derive the name for the golden
 /// file of each test case.
 #[macro_export]
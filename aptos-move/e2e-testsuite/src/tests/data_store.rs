@@ -3,15 +3,73 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_language_e2e_tests::{
-    account::AccountData, compile::compile_script, current_function_name, executor::FakeExecutor,
+    account::AccountData, common_transactions::peer_to_peer_txn, compile::compile_script,
+    current_function_name, data_store::FakeDataStore, executor::FakeExecutor,
 };
-use aptos_types::transaction::{
-    ExecutionStatus, Module, SignedTransaction, Transaction, TransactionStatus,
+use aptos_temppath::TempPath;
+use aptos_types::{
+    transaction::{ExecutionStatus, Module, SignedTransaction, Transaction, TransactionStatus},
+    write_set::TransactionWrite,
 };
 use move_binary_format::CompiledModule;
 use move_bytecode_verifier::verify_module;
 use move_ir_compiler::Compiler;
 
+#[test]
+fn snapshot_fork_and_diff() {
+    let mut executor = FakeExecutor::from_head_genesis();
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    let snapshot = executor.data_store().snapshot();
+    let mut fork = executor.data_store().fork();
+    assert!(snapshot.diff(&fork).is_empty());
+
+    let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000, 100);
+    let output = executor.execute_transaction(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(ExecutionStatus::Success)
+    );
+    executor.apply_write_set(output.write_set());
+
+    // The snapshot and the fork are not affected by the executed transaction.
+    assert!(snapshot.diff(&fork).is_empty());
+    let diffs = snapshot.diff(executor.data_store());
+    assert!(!diffs.is_empty());
+    for diff in &diffs {
+        assert_ne!(diff.before, diff.after);
+        assert_eq!(
+            diff.after.as_ref(),
+            output
+                .write_set()
+                .into_iter()
+                .find(|(state_key, _)| *state_key == &diff.state_key)
+                .and_then(|(_, write_op)| write_op.as_state_value())
+                .as_ref()
+        );
+    }
+
+    // Applying the same changes to the fork results in the same state.
+    fork.add_write_set(output.write_set());
+    assert!(fork.diff(executor.data_store()).is_empty());
+    assert_eq!(fork.diff(&snapshot).len(), diffs.len());
+}
+
+#[test]
+fn save_and_load() {
+    let mut executor = FakeExecutor::from_head_genesis();
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let path = TempPath::new();
+    executor.data_store().save(path.path()).unwrap();
+    let loaded = FakeDataStore::load(path.path()).unwrap();
+    assert!(loaded.diff(executor.data_store()).is_empty());
+}
+
 #[test]
 fn move_from_across_blocks() {
     let mut executor = FakeExecutor::from_head_genesis();
@@ -3,10 +3,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_language_e2e_tests::{
-    account::Account, compile::compile_module, current_function_name, executor::FakeExecutor,
+    account::Account,
+    compile::{compile_module, compile_script},
+    current_function_name,
+    executor::FakeExecutor,
     transaction_status_eq,
 };
 use aptos_types::transaction::{ExecutionStatus, TransactionStatus};
+use aptos_vm::AptosVM;
 use move_core_types::vm_status::StatusCode;
 
 // TODO: ignoring most tests for now as bundle publishing is no longer available. Want to resurrect
@@ -355,3 +359,101 @@ pub fn test_publishing_allow_modules() {
         &TransactionStatus::Keep(ExecutionStatus::Success)
     );
 }
+
+// The workers of the parallel execution of a block share a VM, which must not publish modules.
+// Executing a block publishing modules in parallel must give the same outputs as executing it
+// sequentially (which `execute_block` checks), the published modules being available to the later
+// transactions of the block, and the failed publishes leaving no trace.
+#[test]
+pub fn test_publishing_in_parallel_block_with_shared_vm() {
+    AptosVM::set_shared_module_cache_once(true);
+    let mut executor = FakeExecutor::from_head_genesis();
+
+    let mut txns = vec![];
+    for _ in 0..4 {
+        let sender = executor.create_raw_account_data(1_000_000, 10);
+        executor.add_account_data(&sender);
+
+        let (compiled_module, module) = compile_module(&format!(
+            "
+            module 0x{}.M {{
+                struct T {{ f: u64 }}
+                public f() {{ label b0: return; }}
+            }}
+            ",
+            sender.address(),
+        ));
+        // Changes the layout of the struct, so the publish fails.
+        let incompatible_module = compile_module(&format!(
+            "
+            module 0x{}.M {{
+                struct T {{ f: bool }}
+                public f() {{ label b0: return; }}
+            }}
+            ",
+            sender.address(),
+        ))
+        .1;
+        let script = compile_script(
+            &format!(
+                "
+                import 0x{}.M;
+
+                main(account: signer) {{
+                label b0:
+                    M.f();
+                    return;
+                }}
+                ",
+                sender.address(),
+            ),
+            vec![compiled_module],
+        );
+
+        let account = sender.account();
+        txns.push(
+            account
+                .transaction()
+                .module(module)
+                .sequence_number(10)
+                .sign(),
+        );
+        txns.push(
+            account
+                .transaction()
+                .script(script.clone())
+                .sequence_number(11)
+                .sign(),
+        );
+        txns.push(
+            account
+                .transaction()
+                .module(incompatible_module)
+                .sequence_number(12)
+                .sign(),
+        );
+        txns.push(
+            account
+                .transaction()
+                .script(script)
+                .sequence_number(13)
+                .sign(),
+        );
+    }
+
+    let outputs = executor.execute_block(txns).unwrap();
+    for outputs_of_sender in outputs.chunks(4) {
+        let statuses: Vec<_> = outputs_of_sender
+            .iter()
+            .map(|output| output.status())
+            .collect();
+        assert_eq!(statuses, vec![
+            &TransactionStatus::Keep(ExecutionStatus::Success),
+            &TransactionStatus::Keep(ExecutionStatus::Success),
+            &TransactionStatus::Keep(ExecutionStatus::MiscellaneousError(Some(
+                StatusCode::BACKWARD_INCOMPATIBLE_MODULE_UPDATE
+            ))),
+            &TransactionStatus::Keep(ExecutionStatus::Success),
+        ]);
+    }
+}
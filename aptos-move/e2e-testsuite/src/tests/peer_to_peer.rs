@@ -3,7 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_language_e2e_tests::{
-    account::Account, common_transactions::peer_to_peer_txn, executor::FakeExecutor,
+    account::Account, assert_event_emitted, common_transactions::peer_to_peer_txn,
+    executor::FakeExecutor,
 };
 use aptos_types::{
     account_config::{DepositEvent, WithdrawEvent},
@@ -56,6 +57,12 @@ fn single_peer_to_peer_with_event() {
     for event in output.events() {
         assert!(rec_ev_path == event.key() || sent_ev_path == event.key());
     }
+    assert_event_emitted!(executor, output, "0x1::coin::WithdrawEvent", {
+        "amount": transfer_amount,
+    });
+    assert_event_emitted!(executor, output, "0x1::coin::DepositEvent", {
+        "amount": transfer_amount,
+    });
 }
 
 #[test]
@@ -1332,4 +1332,25 @@ module aptos_framework::account {
         let event = CoinRegisterEvent { type_info: type_info::type_of<SadFakeCoin>() };
         assert!(!event::was_event_emitted(eventhandle, &event), 3);
     }
+
+    #[test_only]
+    #[event]
+    struct TestEvent has drop, store {
+        value: u64,
+    }
+
+    #[test(account = @0x1234)]
+    fun test_handle_and_module_events(account: &signer) acquires Account {
+        let addr = signer::address_of(account);
+        create_account_unchecked(addr);
+        let handle = new_event_handle<TestEvent>(account);
+        event::emit_event(&mut handle, TestEvent { value: 1 });
+        event::emit(TestEvent { value: 2 });
+
+        // Events emitted to a handle and module events of the same type are kept apart.
+        assert!(event::emitted_events(&handle) == vector[TestEvent { value: 1 }], 0);
+        assert!(event::emitted_module_events<TestEvent>() == vector[TestEvent { value: 2 }], 1);
+        assert!(event::pending_event_count<TestEvent>() == 2, 2);
+        event::destroy_handle(handle);
+    }
 }
@@ -0,0 +1,68 @@
+/// This module provides an interface for bounded concurrent counters. Counters
+/// are aggregators which can only be incremented: transactions incrementing the
+/// same counter can be executed in parallel, as long as none of them needs the
+/// value of the counter.
+/// Instead of reading the counter, which would create a read-modify-write
+/// dependency on it, a transaction can take a snapshot of the counter. If the
+/// value of the counter is not known yet, the snapshot only records the increments
+/// made by the transaction so far, and the value in storage is only read once
+/// the value of the snapshot is needed.
+module aptos_framework::counter {
+
+    /// The value of the counter overflows its limit. Raised by native code.
+    const ECOUNTER_OVERFLOW: u64 = 1;
+
+    /// Concurrent counters are not enabled. Raised by native code.
+    const ECONCURRENT_COUNTERS_NOT_ENABLED: u64 = 2;
+
+    /// Represents an integer which supports parallel increments across multiple
+    /// transactions, up to a limit. See the module description for more details.
+    struct Counter has store {
+        handle: address,
+        key: address,
+        limit: u128,
+    }
+
+    /// A snapshot of a counter. If `is_delta` is set, `value` is the amount the
+    /// counter was incremented by when the snapshot was taken, and its actual value
+    /// has yet to be computed from the value of the counter in storage.
+    /// Such a snapshot is only meaningful within the transaction which took it, so
+    /// it can neither be stored nor copied, and is consumed by `snapshot_value`.
+    struct CounterSnapshot has drop {
+        handle: address,
+        key: address,
+        limit: u128,
+        value: u128,
+        is_delta: bool,
+    }
+
+    /// Returns `limit` exceeding which counter overflows.
+    public fun limit(counter: &Counter): u128 {
+        counter.limit
+    }
+
+    /// Creates a new counter, starting at zero, which overflows on exceeding `limit`.
+    /// Aborts if concurrent counters are not enabled, as do the other natives.
+    public native fun create(limit: u128): Counter;
+
+    /// Adds `value` to counter. Aborts on overflowing the limit.
+    public native fun increment(counter: &mut Counter, value: u128);
+
+    /// Takes a snapshot of counter, without reading its value in storage.
+    public native fun read_snapshot(counter: &Counter): CounterSnapshot;
+
+    /// Returns the value of the counter at the time the snapshot was taken. Reads
+    /// the value of the counter in storage if the snapshot only has the increments.
+    public native fun snapshot_value(snapshot: CounterSnapshot): u128;
+
+    #[test_only]
+    struct CounterHolder has key {
+        counter: Counter,
+    }
+
+    #[test(account = @0x123)]
+    #[expected_failure(abort_code = 0x0C0002, location = Self)]
+    fun test_counter_not_enabled(account: &signer) {
+        move_to(account, CounterHolder { counter: create(10) });
+    }
+}
@@ -0,0 +1,26 @@
+spec aptos_framework::counter {
+    spec create(limit: u128): Counter {
+        pragma opaque;
+        ensures result.limit == limit;
+    }
+
+    spec increment(counter: &mut Counter, value: u128) {
+        pragma opaque;
+        ensures counter.limit == old(counter).limit;
+    }
+
+    spec read_snapshot(counter: &Counter): CounterSnapshot {
+        pragma opaque;
+        ensures result.limit == counter.limit;
+    }
+
+    spec snapshot_value(snapshot: CounterSnapshot): u128 {
+        pragma opaque;
+        ensures result <= snapshot.limit;
+    }
+
+    spec limit {
+        aborts_if false;
+        ensures result == counter.limit;
+    }
+}
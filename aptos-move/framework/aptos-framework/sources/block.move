@@ -6,6 +6,7 @@ module aptos_framework::block {
     use std::option;
 
     use aptos_framework::account;
+    use aptos_framework::block_seed;
     use aptos_framework::event::{Self, EventHandle};
     use aptos_framework::reconfiguration;
     use aptos_framework::stake;
@@ -150,6 +151,7 @@ module aptos_framework::block {
         // transition is the last block in the previous epoch.
         stake::update_performance_statistics(proposer_index, failed_proposer_indices);
         state_storage::on_new_block(reconfiguration::current_epoch());
+        block_seed::on_new_block(&vm);
 
         if (timestamp - reconfiguration::last_reconfiguration_time() >= block_metadata_ref.epoch_interval) {
             reconfiguration::reconfigure();
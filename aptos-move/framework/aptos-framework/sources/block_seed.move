@@ -0,0 +1,82 @@
+/// This module provides access to a per-block seed, which the VM derives by hashing the id of the
+/// block. The seed is NOT randomness: anyone can compute it from the block id, and the proposer of
+/// the block can grind the block id to pick it, so it must not be used where anyone could benefit
+/// from predicting or biasing it (e.g. lotteries). The block prologue stores the seed of each new
+/// block, so that it can be read by all the transactions of the block.
+module aptos_framework::block_seed {
+    use std::error;
+    use std::features;
+
+    use aptos_framework::create_signer::create_signer;
+    use aptos_framework::system_addresses;
+
+    friend aptos_framework::block;
+
+    /// The block seed is not enabled.
+    const EBLOCK_SEED_NOT_ENABLED: u64 = 1;
+
+    /// The seed of the current block has not been stored yet, which happens until the first block
+    /// prologue after the block seed got enabled.
+    const ESEED_NOT_AVAILABLE: u64 = 2;
+
+    /// The seed of the current block, stored under @aptos_framework by the block prologue.
+    struct PerBlockSeed has key {
+        seed: vector<u8>,
+    }
+
+    /// Stores the seed of the new block. Called by the block prologue, which is the only place where
+    /// the VM knows the id of the block.
+    public(friend) fun on_new_block(vm: &signer) acquires PerBlockSeed {
+        system_addresses::assert_vm(vm);
+
+        if (!features::block_seed_enabled()) {
+            return
+        };
+        let seed = fetch_block_seed();
+        if (exists<PerBlockSeed>(@aptos_framework)) {
+            borrow_global_mut<PerBlockSeed>(@aptos_framework).seed = seed;
+        } else {
+            move_to(&create_signer(@aptos_framework), PerBlockSeed { seed });
+        }
+    }
+
+    /// Returns the 32 bytes seed of the current block. Aborts if the block seed is not enabled, or
+    /// if no block prologue has stored a seed since it got enabled.
+    public fun per_block_seed(): vector<u8> acquires PerBlockSeed {
+        assert!(features::block_seed_enabled(), error::not_implemented(EBLOCK_SEED_NOT_ENABLED));
+        assert!(exists<PerBlockSeed>(@aptos_framework), error::unavailable(ESEED_NOT_AVAILABLE));
+        borrow_global<PerBlockSeed>(@aptos_framework).seed
+    }
+
+    /// Returns the seed of the block being executed. Only known to the block metadata transaction.
+    native fun fetch_block_seed(): vector<u8>;
+
+    #[test]
+    #[expected_failure(abort_code = 0x0C0001, location = Self)]
+    fun test_per_block_seed_not_enabled() acquires PerBlockSeed {
+        per_block_seed();
+    }
+
+    #[test(fx = @std)]
+    #[expected_failure(abort_code = 0x0D0002, location = Self)]
+    fun test_per_block_seed_not_stored(fx: signer) acquires PerBlockSeed {
+        features::change_feature_flags(&fx, vector[features::get_block_seed_feature()], vector[]);
+        per_block_seed();
+    }
+
+    #[test(fx = @std, vm = @vm_reserved)]
+    fun test_per_block_seed(fx: signer, vm: signer) acquires PerBlockSeed {
+        use std::vector;
+
+        // Blocks do not store a seed while the block seed is not enabled.
+        on_new_block(&vm);
+        assert!(!exists<PerBlockSeed>(@aptos_framework), 0);
+
+        features::change_feature_flags(&fx, vector[features::get_block_seed_feature()], vector[]);
+        on_new_block(&vm);
+        let seed = per_block_seed();
+        assert!(vector::length(&seed) == 32, 1);
+        // The seed is the same for every call within a block.
+        assert!(per_block_seed() == seed, 2);
+    }
+}
@@ -0,0 +1,20 @@
+spec aptos_framework::block_seed {
+    spec on_new_block(vm: &signer) {
+        use aptos_framework::system_addresses;
+
+        requires system_addresses::is_vm(vm);
+        aborts_if false;
+    }
+
+    spec per_block_seed(): vector<u8> {
+        pragma opaque;
+        aborts_if !features::spec_is_enabled(features::BLOCK_SEED);
+        aborts_if !exists<PerBlockSeed>(@aptos_framework);
+        ensures result == global<PerBlockSeed>(@aptos_framework).seed;
+    }
+
+    spec fetch_block_seed(): vector<u8> {
+        pragma opaque;
+        ensures len(result) == 32;
+    }
+}
@@ -2,9 +2,16 @@
 /// `EventHandle`s with unique GUIDs. It contains a counter for the number
 /// of `EventHandle`s it generates. An `EventHandle` is used to count the number of
 /// events emitted to a handle and emit events to the event store.
+///
+/// Events can also be emitted as module events with `emit`, which are not emitted to a handle and
+/// are identified by their type only. A module event must be a struct declared with `#[event]`, and
+/// can only be emitted by the module defining it.
 module aptos_framework::event {
     use std::bcs;
+    use std::features;
+    use aptos_std::type_info;
 
+    use aptos_framework::create_signer::create_signer;
     use aptos_framework::guid::GUID;
 
     friend aptos_framework::account;
@@ -20,6 +27,13 @@ module aptos_framework::event {
         guid: GUID,
     }
 
+    /// The number of legacy copies of the module events of type `T` emitted so far, stored under the
+    /// address of the module defining `T` while module events are also emitted as legacy events. Its
+    /// counter is the sequence number of the next copy in the event stream of `T`.
+    struct LegacyEventCounter<phantom T> has key {
+        counter: u64,
+    }
+
     /// Use EventHandleGenerator to generate a unique event handle for `sig`
     public(friend) fun new_event_handle<T: drop + store>(guid: GUID): EventHandle<T> {
         EventHandle<T> {
@@ -50,6 +64,55 @@ module aptos_framework::event {
     /// Log `msg` as the `count`th event associated with the event stream identified by `guid`
     native fun write_to_event_store<T: drop + store>(guid: vector<u8>, count: u64, msg: T);
 
+    /// Emit a module event with payload `msg`. Unlike events emitted to an `EventHandle`, module
+    /// events are not part of an event stream and are identified by their type, which must be a
+    /// struct declared with `#[event]` in the module calling this function.
+    ///
+    /// While the module event dual emission feature is enabled, the event is also emitted as a legacy
+    /// event to an event stream of type `T`, so that indexers which only know about event handles keep
+    /// receiving it.
+    public fun emit<T: store + drop>(msg: T) acquires LegacyEventCounter {
+        if (features::module_event_dual_emission_enabled()) {
+            let addr = type_info::account_address(&type_info::type_of<T>());
+            if (!exists<LegacyEventCounter<T>>(addr)) {
+                move_to(&create_signer(addr), LegacyEventCounter<T> { counter: 0 });
+            };
+            let counter = &mut borrow_global_mut<LegacyEventCounter<T>>(addr).counter;
+            write_to_module_event_store_with_legacy_copy<T>(msg, *counter);
+            *counter = *counter + 1;
+        } else {
+            write_to_module_event_store<T>(msg);
+        }
+    }
+
+    /// Log `msg` as a module event, emitted by the caller of `emit`.
+    native fun write_to_module_event_store<T: drop + store>(msg: T);
+
+    /// Log `msg` as a module event, emitted by the caller of `emit`, and as the `legacy_count`th event
+    /// of the legacy event stream of type `T`.
+    native fun write_to_module_event_store_with_legacy_copy<T: drop + store>(msg: T, legacy_count: u64);
+
+    /// Emit a module event with payload `msg`, tagged with the version of its schema. `version` must
+    /// be the version declared by the `#[event(version = ...)]` attribute of the struct `T`, so that
+    /// indexers can tell apart the successive schemas of an event type.
+    public fun emit_versioned_event<T: store + drop>(msg: T, version: u64) {
+        write_to_versioned_module_event_store<T>(msg, version);
+    }
+
+    /// Log `msg` as a module event with the given schema version, emitted by the caller of
+    /// `emit_versioned_event`.
+    native fun write_to_versioned_module_event_store<T: drop + store>(msg: T, version: u64);
+
+    /// Returns the BCS bytes of the events of type `T` emitted so far by the current transaction, both to an
+    /// `EventHandle` and as module events, in emission order. This allows checking invariants on the events of a
+    /// transaction, e.g. that a transfer emits exactly one withdraw event. The events are returned as bytes rather
+    /// than as values of type `T`, as the latter would let any module create values of any event type.
+    public native fun read_pending_event_bytes<T: store + drop>(): vector<vector<u8>>;
+
+    /// Returns the number of events of type `T` emitted so far by the current transaction. Cheaper than
+    /// `read_pending_event_bytes` when the contents of the events are not needed.
+    public native fun pending_event_count<T: store + drop>(): u64;
+
     /// Destroy a unique handle.
     public fun destroy_handle<T: drop + store>(handle: EventHandle<T>) {
         EventHandle<T> { counter: _, guid: _ } = handle;
@@ -66,6 +129,78 @@ module aptos_framework::event {
         vector::contains(&emitted_events(handle), msg)
     }
 
+    /// Returns the module events of type `T` emitted so far, in emission order.
+    #[test_only]
+    public fun emitted_module_events<T: drop + store>(): vector<T> {
+        emitted_module_events_internal<T>()
+    }
+
+    /// Returns whether the module event `msg` was emitted.
+    #[test_only]
+    public fun was_module_event_emitted<T: drop + store>(msg: &T): bool {
+        use std::vector;
+        vector::contains(&emitted_module_events<T>(), msg)
+    }
+
     #[test_only]
     native fun emitted_events_internal<T: drop + store>(id: vector<u8>): vector<T>;
+
+    #[test_only]
+    native fun emitted_module_events_internal<T: drop + store>(): vector<T>;
+
+    #[test_only]
+    #[event]
+    struct TestEvent has drop, store {
+        value: u64,
+    }
+
+    #[test]
+    fun test_module_events() acquires LegacyEventCounter {
+        use std::vector;
+
+        assert!(vector::is_empty(&emitted_module_events<TestEvent>()), 0);
+        emit(TestEvent { value: 1 });
+        emit(TestEvent { value: 2 });
+        let expected = vector[TestEvent { value: 1 }, TestEvent { value: 2 }];
+        assert!(emitted_module_events<TestEvent>() == expected, 1);
+        assert!(was_module_event_emitted(&TestEvent { value: 2 }), 2);
+        assert!(!was_module_event_emitted(&TestEvent { value: 3 }), 3);
+    }
+
+    #[test]
+    fun test_read_pending_events() acquires LegacyEventCounter {
+        assert!(pending_event_count<TestEvent>() == 0, 0);
+        emit(TestEvent { value: 1 });
+        emit(TestEvent { value: 2 });
+        assert!(pending_event_count<TestEvent>() == 2, 1);
+        let expected = vector[bcs::to_bytes(&TestEvent { value: 1 }), bcs::to_bytes(&TestEvent { value: 2 })];
+        assert!(read_pending_event_bytes<TestEvent>() == expected, 2);
+    }
+
+    #[test(fx = @std)]
+    fun test_module_event_dual_emission(fx: signer) acquires LegacyEventCounter {
+        features::change_feature_flags(&fx, vector[features::get_module_event_dual_emission_feature()], vector[]);
+
+        emit(TestEvent { value: 1 });
+        emit(TestEvent { value: 2 });
+        assert!(borrow_global<LegacyEventCounter<TestEvent>>(@aptos_framework).counter == 2, 0);
+        // The legacy copies are not returned as module events, nor counted twice.
+        let expected = vector[TestEvent { value: 1 }, TestEvent { value: 2 }];
+        assert!(emitted_module_events<TestEvent>() == expected, 1);
+        assert!(pending_event_count<TestEvent>() == 2, 2);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 0x50005, location = Self)]
+    fun test_module_event_requires_defining_module() acquires LegacyEventCounter {
+        use aptos_framework::guid;
+
+        emit(guid::create_id(@0x1, 0));
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 0x10002, location = Self)]
+    fun test_versioned_event_requires_declared_version() {
+        emit_versioned_event(TestEvent { value: 1 }, 1);
+    }
 }
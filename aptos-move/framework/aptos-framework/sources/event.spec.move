@@ -15,6 +15,40 @@ spec aptos_framework::event {
         pragma opaque;
     }
 
+    spec emit {
+        pragma opaque;
+        aborts_if [abstract] false;
+    }
+
+    /// Native function use opaque.
+    spec write_to_module_event_store<T: drop + store>(msg: T) {
+        pragma opaque;
+    }
+
+    /// Native function use opaque.
+    spec write_to_module_event_store_with_legacy_copy<T: drop + store>(msg: T, legacy_count: u64) {
+        pragma opaque;
+    }
+
+    spec emit_versioned_event {
+        pragma opaque;
+    }
+
+    /// Native function use opaque.
+    spec write_to_versioned_module_event_store<T: drop + store>(msg: T, version: u64) {
+        pragma opaque;
+    }
+
+    /// Native function use opaque.
+    spec read_pending_event_bytes<T: drop + store>(): vector<vector<u8>> {
+        pragma opaque;
+    }
+
+    /// Native function use opaque.
+    spec pending_event_count<T: drop + store>(): u64 {
+        pragma opaque;
+    }
+
     spec guid {
         aborts_if false;
     }
@@ -14,9 +14,19 @@ module aptos_std::debug {
         aptos_std::string_utils::debug_string(x)
     }
 
+    #[test_only]
+    /// Prints the value as JSON through the node logger, using the same encodings as the GraphQL
+    /// scalars (e.g. integers wider than 32 bits as strings, `vector<u8>` as hex strings).
+    public fun print_value<T>(x: &T) {
+        native_print_value(x);
+    }
+
     native fun native_print(x: String);
     native fun native_stack_trace(): String;
 
+    #[test_only]
+    native fun native_print_value<T>(x: &T);
+
     #[test_only]
     use std::vector;
 
@@ -269,4 +279,18 @@ module aptos_std::debug {
 
         assert_equal(&obj, b"0x1::debug::GenericStruct<0x1::debug::Foo> {\n  val: 60\n}");
     }
+
+    #[test]
+    fun test_print_value() {
+        let obj = TestStruct {
+            addr: @0x1,
+            number: 255u8,
+            bytes: x"c0ffee",
+            name: std::string::utf8(b"Hello"),
+            vec: vector[TestInner { val: 1, vec: vector[130u128], msgs: vector[b"msg"] }],
+        };
+
+        print_value(&obj);
+        print_value(&std::option::some(42u64));
+    }
 }
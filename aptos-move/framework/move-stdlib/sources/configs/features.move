@@ -220,6 +220,60 @@ module std::features {
         is_enabled(BULLETPROOFS_NATIVES)
     }
 
+    /// Whether module events can be emitted with a schema version, declared with the `#[event(version = ...)]`
+    /// attribute on the event struct. This is needed because of the introduction of a new native function.
+    /// Lifetime: transient
+    const MODULE_EVENT_VERSIONS: u64 = 25;
+
+    public fun get_module_event_versions_feature(): u64 { MODULE_EVENT_VERSIONS }
+
+    public fun module_event_versions_enabled(): bool acquires Features {
+        is_enabled(MODULE_EVENT_VERSIONS)
+    }
+
+    /// Whether module events can be emitted with `event::emit`. Only the module defining an event struct, which
+    /// must be annotated with `#[event]`, can emit it. This is needed because of the introduction of a new native
+    /// function.
+    /// Lifetime: transient
+    const MODULE_EVENT: u64 = 26;
+
+    public fun get_module_event_feature(): u64 { MODULE_EVENT }
+
+    public fun module_event_enabled(): bool acquires Features {
+        is_enabled(MODULE_EVENT)
+    }
+
+    /// Whether the per-block seed (which is not randomness) is available to the framework.
+    /// Lifetime: transient
+    const BLOCK_SEED: u64 = 27;
+
+    public fun get_block_seed_feature(): u64 { BLOCK_SEED }
+
+    public fun block_seed_enabled(): bool acquires Features {
+        is_enabled(BLOCK_SEED)
+    }
+
+    /// Whether the concurrent counter natives are enabled.
+    /// Lifetime: transient
+    const CONCURRENT_COUNTERS: u64 = 28;
+
+    public fun get_concurrent_counters_feature(): u64 { CONCURRENT_COUNTERS }
+
+    public fun concurrent_counters_enabled(): bool acquires Features {
+        is_enabled(CONCURRENT_COUNTERS)
+    }
+
+    /// Whether module events emitted with `event::emit` are also emitted as legacy events to an event stream of
+    /// their type, while indexers migrate to module events.
+    /// Lifetime: transient
+    const MODULE_EVENT_DUAL_EMISSION: u64 = 29;
+
+    public fun get_module_event_dual_emission_feature(): u64 { MODULE_EVENT_DUAL_EMISSION }
+
+    public fun module_event_dual_emission_enabled(): bool acquires Features {
+        is_enabled(MODULE_EVENT_DUAL_EMISSION)
+    }
+
     // ============================================================================================
     // Feature Flag Implementation
 
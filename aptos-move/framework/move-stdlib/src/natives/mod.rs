@@ -21,22 +21,23 @@ pub fn all_natives(
 ) -> NativeFunctionTable {
     let mut natives = vec![];
 
-    macro_rules! add_natives {
-        ($module_name:expr, $natives:expr) => {
-            natives.extend(
-                $natives.map(|(func_name, func)| ($module_name.to_string(), func_name, func)),
-            );
-        };
-    }
-
     builder.with_incremental_gas_charging(false, |builder| {
-        add_natives!("bcs", bcs::make_all(builder));
-        add_natives!("hash", hash::make_all(builder));
-        add_natives!("signer", signer::make_all(builder));
-        add_natives!("string", string::make_all(builder));
+        macro_rules! add_natives {
+            ($module_name:expr, $make_all:expr) => {
+                natives.extend(
+                    $make_all(&builder.for_module($module_name))
+                        .map(|(func_name, func)| ($module_name.to_string(), func_name, func)),
+                );
+            };
+        }
+
+        add_natives!("bcs", bcs::make_all);
+        add_natives!("hash", hash::make_all);
+        add_natives!("signer", signer::make_all);
+        add_natives!("string", string::make_all);
         #[cfg(feature = "testing")]
         {
-            add_natives!("unit_test", unit_test::make_all(builder));
+            add_natives!("unit_test", unit_test::make_all);
         }
     });
 
@@ -1,8 +1,8 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{KnownAttribute, RuntimeModuleMetadataV1};
-use move_binary_format::file_format::{Ability, AbilitySet, Visibility};
+use crate::{is_valid_event_group_name, KnownAttribute, RuntimeModuleMetadataV1};
+use move_binary_format::file_format::{Ability, AbilitySet, Bytecode, Visibility};
 use move_core_types::{
     account_address::AccountAddress,
     errmap::{ErrorDescription, ErrorMapping},
@@ -24,6 +24,10 @@ use thiserror::Error;
 const INIT_MODULE_FUN: &str = "init_module";
 const LEGAC_ENTRY_FUN_ATTRIBUTE: &str = "legacy_entry_fun";
 const ERROR_PREFIX: &str = "E";
+const EVENT_ATTRIBUTE: &str = "event";
+const EVENT_EMIT_FUNCTIONS: &[&str] = &["emit", "emit_versioned_event"];
+const EVENT_GROUP: &str = "group";
+const EVENT_VERSION: &str = "version";
 const RESOURCE_GROUP: &str = "resource_group";
 const RESOURCE_GROUP_MEMBER: &str = "resource_group_member";
 const RESOURCE_GROUP_NAME: &str = "group";
@@ -46,6 +50,8 @@ struct ExtendedChecker<'a> {
     output: BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
     /// The id of the module defining error categories
     error_category_module: ModuleId,
+    /// The id of the module defining the module event emission functions
+    event_module: ModuleId,
 }
 
 impl<'a> ExtendedChecker<'a> {
@@ -57,6 +63,7 @@ impl<'a> ExtendedChecker<'a> {
                 AccountAddress::ONE,
                 Identifier::new("error").unwrap(),
             ),
+            event_module: ModuleId::new(AccountAddress::ONE, Identifier::new("event").unwrap()),
         }
     }
 
@@ -66,6 +73,8 @@ impl<'a> ExtendedChecker<'a> {
                 self.check_and_record_resource_groups(module);
                 self.check_and_record_resource_group_members(module);
                 self.check_and_record_view_functions(module);
+                self.check_and_record_events(module);
+                self.check_emit_event_calls(module);
                 self.check_entry_functions(module);
                 self.check_init_module(module);
                 self.build_error_map(module)
@@ -428,6 +437,139 @@ impl<'a> ExtendedChecker<'a> {
     }
 }
 
+// ----------------------------------------------------------------------------------
+// Events
+
+impl<'a> ExtendedChecker<'a> {
+    // An event should be a struct with drop and store, declared with `#[event]`. It may declare
+    // the version of its schema with `#[event(version = N)]`.
+    fn check_and_record_events(&mut self, module: &ModuleEnv) {
+        let module_id = self.get_runtime_module_id(module);
+
+        for ref struct_ in module.get_structs() {
+            if let Some(Attribute::Apply(_, _, attributes)) = self.get_event_attribute(struct_) {
+                let abilities = struct_.get_abilities();
+                if !abilities.has_ability(Ability::Drop) || !abilities.has_ability(Ability::Store) {
+                    self.env.error(
+                        &struct_.get_loc(),
+                        "event should have drop and store abilities",
+                    );
+                    continue;
+                }
+
+                let mut version = None;
+                let mut group = None;
+                let mut valid = true;
+                for attribute in attributes {
+                    match attribute {
+                        Attribute::Assign(_, name, AttributeValue::Value(_, Value::Number(v)))
+                            if self.name_string(*name).as_str() == EVENT_VERSION
+                                && version.is_none() =>
+                        {
+                            version = u64::try_from(v).ok();
+                            valid &= version.is_some();
+                        },
+                        Attribute::Assign(
+                            _,
+                            name,
+                            AttributeValue::Value(_, Value::ByteArray(g)),
+                        ) if self.name_string(*name).as_str() == EVENT_GROUP && group.is_none() => {
+                            group = String::from_utf8(g.clone())
+                                .ok()
+                                .filter(|g| is_valid_event_group_name(g));
+                            valid &= group.is_some();
+                        },
+                        _ => valid = false,
+                    }
+                }
+                if !valid {
+                    self.env.error(
+                        &struct_.get_loc(),
+                        "event may only contain a 'version' parameter, a u64 number, and/or a \
+                         'group' parameter, a short name made of alphanumeric characters, '_' \
+                         and '-'",
+                    );
+                    continue;
+                }
+
+                let attributes = self
+                    .output
+                    .entry(module_id.clone())
+                    .or_default()
+                    .struct_attributes
+                    .entry(self.name_string(struct_.get_name()).to_string())
+                    .or_default();
+                attributes.push(
+                    version.map_or_else(KnownAttribute::event, KnownAttribute::versioned_event),
+                );
+                attributes.extend(group.map(KnownAttribute::event_group));
+            }
+        }
+    }
+
+    // Module events can only be emitted by the module defining their struct, which must be
+    // declared with `#[event]`, so that no module can emit events on behalf of another one.
+    fn check_emit_event_calls(&self, module: &ModuleEnv) {
+        let compiled_module = match module.get_verified_module() {
+            Some(compiled_module) => compiled_module,
+            None => return,
+        };
+        for ref fun in module.get_functions() {
+            // Native and inline functions have no bytecode of their own.
+            if fun.is_native() || fun.is_inline() {
+                continue;
+            }
+            for (offset, bc) in fun.get_bytecode().unwrap_or_default().iter().enumerate() {
+                let instantiation = match bc {
+                    Bytecode::CallGeneric(idx) => compiled_module.function_instantiation_at(*idx),
+                    _ => continue,
+                };
+                match module.get_used_function(instantiation.handle) {
+                    Some(ref callee) if self.is_emit_event_function(callee) => {},
+                    _ => continue,
+                }
+
+                let loc = fun
+                    .get_bytecode_loc(offset as u16)
+                    .unwrap_or_else(|| fun.get_loc());
+                match module
+                    .get_type_actuals(Some(instantiation.type_parameters))
+                    .as_deref()
+                {
+                    Some([Type::Struct(mid, sid, _)]) if *mid == module.get_id() => {
+                        if self.get_event_attribute(&module.get_struct(*sid)).is_none() {
+                            self.env.error(
+                                &loc,
+                                "module event must be a struct declared with the `#[event]` \
+                                 attribute",
+                            );
+                        }
+                    },
+                    _ => self.env.error(
+                        &loc,
+                        "module event must be a struct defined in the module emitting it",
+                    ),
+                }
+            }
+        }
+    }
+
+    fn is_emit_event_function(&self, fun: &FunctionEnv) -> bool {
+        self.get_runtime_module_id(&fun.module_env) == self.event_module
+            && EVENT_EMIT_FUNCTIONS.contains(&fun.get_simple_name_string().as_str())
+    }
+
+    fn get_event_attribute<'b>(&self, struct_: &'b StructEnv) -> Option<&'b Attribute> {
+        struct_.get_attributes().iter().find(|attr| {
+            if let Attribute::Apply(_, name, _) = attr {
+                self.name_string(*name).as_str() == EVENT_ATTRIBUTE
+            } else {
+                false
+            }
+        })
+    }
+}
+
 // ----------------------------------------------------------------------------------
 // Error Map
 
@@ -66,6 +66,8 @@ pub enum KnownAttributeKind {
     ViewFunction = 1,
     ResourceGroup = 2,
     ResourceGroupMember = 3,
+    Event = 4,
+    EventGroup = 5,
 }
 
 impl KnownAttribute {
@@ -118,6 +120,66 @@ impl KnownAttribute {
     pub fn is_resource_group_member(&self) -> bool {
         self.kind == KnownAttributeKind::ResourceGroupMember as u8
     }
+
+    pub fn event() -> Self {
+        Self {
+            kind: KnownAttributeKind::Event as u8,
+            args: vec![],
+        }
+    }
+
+    pub fn versioned_event(version: u64) -> Self {
+        Self {
+            kind: KnownAttributeKind::Event as u8,
+            args: vec![version.to_string()],
+        }
+    }
+
+    pub fn is_event(&self) -> bool {
+        self.kind == KnownAttributeKind::Event as u8
+    }
+
+    pub fn get_event_version(&self) -> Option<u64> {
+        if self.kind == KnownAttributeKind::Event as u8 {
+            self.args.get(0)?.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn event_group(group: String) -> Self {
+        Self {
+            kind: KnownAttributeKind::EventGroup as u8,
+            args: vec![group],
+        }
+    }
+
+    pub fn is_event_group(&self) -> bool {
+        self.kind == KnownAttributeKind::EventGroup as u8
+    }
+
+    pub fn get_event_group(&self) -> Option<&str> {
+        if self.kind == KnownAttributeKind::EventGroup as u8 {
+            self.args
+                .get(0)
+                .map(String::as_str)
+                .filter(|group| is_valid_event_group_name(group))
+        } else {
+            None
+        }
+    }
+}
+
+/// The maximum length of the name of an event group.
+const MAX_EVENT_GROUP_NAME_LENGTH: usize = 64;
+
+/// Event group names are short ASCII names, made of alphanumeric characters, `_` and `-`.
+pub fn is_valid_event_group_name(group: &str) -> bool {
+    !group.is_empty()
+        && group.len() <= MAX_EVENT_GROUP_NAME_LENGTH
+        && group
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
 }
 
 /// Extract metadata from the VM, upgrading V0 to V1 representation as needed
@@ -324,6 +386,26 @@ pub fn is_valid_resource_group_member(
     })
 }
 
+pub fn is_valid_event(
+    structs: &BTreeMap<Identifier, Struct>,
+    struct_: &str,
+) -> Result<(), AttributeValidationError> {
+    if let Ok(ident_struct) = Identifier::new(struct_) {
+        if let Some(mod_struct) = structs.get(&ident_struct) {
+            if mod_struct.abilities.has_ability(Ability::Drop)
+                && mod_struct.abilities.has_ability(Ability::Store)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(AttributeValidationError {
+        key: struct_.to_string(),
+        attribute: KnownAttributeKind::Event as u8,
+    })
+}
+
 pub fn verify_module_metadata(
     module: &CompiledModule,
     features: &Features,
@@ -376,6 +458,17 @@ pub fn verify_module_metadata(
                     continue;
                 }
             }
+            if features.is_module_event_enabled() && attr.is_event() && attr.args.is_empty() {
+                is_valid_event(&structs, struct_)?;
+                continue;
+            }
+            if features.are_module_event_versions_enabled()
+                && ((attr.is_event() && attr.get_event_version().is_some())
+                    || (attr.is_event_group() && attr.get_event_group().is_some()))
+            {
+                is_valid_event(&structs, struct_)?;
+                continue;
+            }
             return Err(AttributeValidationError {
                 key: struct_.clone(),
                 attribute: attr.kind,
@@ -418,6 +511,30 @@ impl RuntimeModuleMetadataV1 {
             && self.struct_attributes.is_empty()
     }
 
+    /// Whether the struct is declared with the `#[event]` attribute.
+    pub fn is_event(&self, struct_name: &str) -> bool {
+        self.struct_attributes
+            .get(struct_name)
+            .map_or(false, |attrs| attrs.iter().any(|attr| attr.is_event()))
+    }
+
+    /// The schema version declared by the `#[event(version = ...)]` attribute of the struct, if
+    /// any.
+    pub fn event_version(&self, struct_name: &str) -> Option<u64> {
+        self.struct_attributes
+            .get(struct_name)?
+            .iter()
+            .find_map(|attr| attr.get_event_version())
+    }
+
+    /// The group declared by the `#[event(group = ...)]` attribute of the struct, if any.
+    pub fn event_group(&self, struct_name: &str) -> Option<&str> {
+        self.struct_attributes
+            .get(struct_name)?
+            .iter()
+            .find_map(|attr| attr.get_event_group())
+    }
+
     pub fn extract_abort_info(&self, code: u64) -> Option<AbortInfo> {
         self.error_map
             .get(&(code & 0xFFF))
@@ -4,6 +4,6 @@
 pub mod aggregator;
 pub mod aggregator_factory;
 pub mod context;
-mod helpers;
+pub(crate) mod helpers;
 
 pub use context::{AggregatorChange, AggregatorChangeSet, NativeAggregatorContext};
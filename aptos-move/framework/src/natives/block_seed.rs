@@ -0,0 +1,109 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_crypto::{hash::DefaultHasher, HashValue};
+use aptos_gas_schedule::gas_params::natives::aptos_framework::*;
+use aptos_native_interface::{
+    RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeError, SafeNativeResult,
+};
+use better_any::{Tid, TidAble};
+use move_vm_runtime::native_functions::NativeFunction;
+use move_vm_types::{loaded_data::runtime_types::Type, values::Value};
+use smallvec::{smallvec, SmallVec};
+use std::collections::VecDeque;
+
+/// Abort code when the seed of the block is not known to the session (0x0D == UNAVAILABLE)
+const ESEED_NOT_AVAILABLE: u64 = 0x0D_0002;
+
+/// The native block seed context extension. This needs to be attached to the
+/// NativeContextExtensions value which is passed into session functions, so its accessible from
+/// natives of this extension.
+///
+/// The seed is derived from the metadata of the block, so it is only known to the sessions
+/// executing the block metadata transaction, whose prologue stores it for the rest of the block.
+#[derive(Tid)]
+pub struct NativeBlockSeedContext {
+    seed: Option<[u8; 32]>,
+}
+
+impl NativeBlockSeedContext {
+    /// Create a new instance of a native block seed context, without a seed. This must be passed
+    /// in via an extension into VM session functions.
+    pub fn new() -> Self {
+        Self { seed: None }
+    }
+
+    /// Create a new instance of a native block seed context, with the seed of the block with the
+    /// given id.
+    pub fn for_block(block_id: HashValue) -> Self {
+        let mut hasher = DefaultHasher::new(b"aptos_framework::block_seed");
+        hasher.update(block_id.as_slice());
+        Self {
+            seed: Some(*hasher.finish()),
+        }
+    }
+
+    pub fn seed(&self) -> Option<[u8; 32]> {
+        self.seed
+    }
+}
+
+impl Default for NativeBlockSeedContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/***************************************************************************************************
+ * native fun fetch_block_seed
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+fn native_fetch_block_seed(
+    context: &mut SafeNativeContext,
+    mut _ty_args: Vec<Type>,
+    _args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    context.charge(BLOCK_SEED_FETCH_BLOCK_SEED_BASE)?;
+
+    match context.extensions().get::<NativeBlockSeedContext>().seed() {
+        Some(seed) => Ok(smallvec![Value::vector_u8(seed)]),
+        None => Err(SafeNativeError::Abort {
+            abort_code: ESEED_NOT_AVAILABLE,
+        }),
+    }
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+pub fn make_all(
+    builder: &SafeNativeBuilder,
+) -> impl Iterator<Item = (String, NativeFunction)> + '_ {
+    let natives = [("fetch_block_seed", native_fetch_block_seed as RawSafeNative)];
+
+    builder.make_named_natives(natives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_of_block() {
+        assert_eq!(NativeBlockSeedContext::new().seed(), None);
+
+        let block_id = HashValue::random();
+        let seed = NativeBlockSeedContext::for_block(block_id).seed().unwrap();
+        assert_eq!(
+            NativeBlockSeedContext::for_block(block_id).seed(),
+            Some(seed)
+        );
+        assert_ne!(
+            NativeBlockSeedContext::for_block(HashValue::random()).seed(),
+            Some(seed)
+        );
+    }
+}
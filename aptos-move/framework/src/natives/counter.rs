@@ -0,0 +1,253 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::natives::aggregator_natives::{helpers::aggregator_info, NativeAggregatorContext};
+use aptos_aggregator::{
+    aggregator_extension::{extension_error, AggregatorHandle, AggregatorID, AggregatorState},
+    delta_change_set::{addition, deserialize},
+};
+use aptos_crypto::hash::DefaultHasher;
+use aptos_gas_schedule::gas_params::natives::aptos_framework::*;
+use aptos_native_interface::{
+    safely_pop_arg, RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeError,
+    SafeNativeResult,
+};
+use aptos_table_natives::TableHandle;
+use aptos_types::{account_address::AccountAddress, on_chain_config::FeatureFlag};
+use better_any::{Tid, TidAble};
+use move_binary_format::errors::PartialVMResult;
+use move_vm_runtime::native_functions::NativeFunction;
+use move_vm_types::{
+    loaded_data::runtime_types::Type,
+    values::{Struct, StructRef, Value},
+};
+use smallvec::{smallvec, SmallVec};
+use std::collections::VecDeque;
+
+/// Counters are not stored in a table of their own. They all share this handle, which only
+/// namespaces their keys in storage.
+const COUNTER_HANDLE: TableHandle = TableHandle(AccountAddress::ONE);
+
+/// Abort code when concurrent counters are not enabled (0x0C == NOT_IMPLEMENTED)
+const ECONCURRENT_COUNTERS_NOT_ENABLED: u64 = 0x0C_0002;
+
+fn ensure_enabled(context: &SafeNativeContext) -> SafeNativeResult<()> {
+    if !context
+        .get_feature_flags()
+        .is_enabled(FeatureFlag::CONCURRENT_COUNTERS)
+    {
+        return Err(SafeNativeError::Abort {
+            abort_code: ECONCURRENT_COUNTERS_NOT_ENABLED,
+        });
+    }
+    Ok(())
+}
+
+/// The native counter context extension.
+///
+/// Counters are aggregators under the hood: their increments are recorded as deltas in the
+/// `NativeAggregatorContext`, which the VM materializes once the transaction is executed, so that
+/// transactions incrementing the same counter do not conflict with each other. This context only
+/// keeps track of the counters created by the transaction, to derive their keys.
+#[derive(Tid)]
+pub struct NativeCounterContext {
+    txn_hash: [u8; 32],
+    num_counters: u32,
+}
+
+impl NativeCounterContext {
+    /// Creates a new instance of a native counter context. This must be passed into VM session.
+    pub fn new(txn_hash: [u8; 32]) -> Self {
+        Self {
+            txn_hash,
+            num_counters: 0,
+        }
+    }
+
+    /// Returns the key of the next counter created by the transaction.
+    fn next_key(&mut self) -> PartialVMResult<AggregatorHandle> {
+        let mut hasher = DefaultHasher::new(b"aptos_framework::counter");
+        hasher.update(&self.txn_hash);
+        hasher.update(&self.num_counters.to_be_bytes());
+        self.num_counters += 1;
+        Ok(AggregatorHandle(
+            AccountAddress::from_bytes(hasher.finish().to_vec())
+                .map_err(|_| extension_error("unable to create counter key"))?,
+        ))
+    }
+}
+
+/***************************************************************************************************
+ * native fun create(limit: u128): Counter;
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+fn native_create(
+    context: &mut SafeNativeContext,
+    _ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert_eq!(args.len(), 1);
+
+    context.charge(COUNTER_CREATE_BASE)?;
+    ensure_enabled(context)?;
+
+    let limit = safely_pop_arg!(args, u128);
+    let key = context
+        .extensions_mut()
+        .get_mut::<NativeCounterContext>()
+        .next_key()?;
+
+    // The value of a new counter is known, so it is created in a data state.
+    let id = AggregatorID::new(COUNTER_HANDLE, key);
+    let aggregator_context = context.extensions().get::<NativeAggregatorContext>();
+    aggregator_context
+        .aggregator_data
+        .borrow_mut()
+        .create_new_aggregator(id, limit);
+
+    Ok(smallvec![Value::struct_(Struct::pack(vec![
+        Value::address(COUNTER_HANDLE.0),
+        Value::address(key.0),
+        Value::u128(limit),
+    ]))])
+}
+
+/***************************************************************************************************
+ * native fun increment(counter: &mut Counter, value: u128);
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+fn native_increment(
+    context: &mut SafeNativeContext,
+    _ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert_eq!(args.len(), 2);
+
+    context.charge(COUNTER_INCREMENT_BASE)?;
+    ensure_enabled(context)?;
+
+    let value = safely_pop_arg!(args, u128);
+    let (id, limit) = aggregator_info(&safely_pop_arg!(args, StructRef))?;
+
+    let aggregator_context = context.extensions().get::<NativeAggregatorContext>();
+    let mut aggregator_data = aggregator_context.aggregator_data.borrow_mut();
+    aggregator_data.get_aggregator(id, limit)?.add(value)?;
+
+    Ok(smallvec![])
+}
+
+/***************************************************************************************************
+ * native fun read_snapshot(counter: &Counter): CounterSnapshot;
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+fn native_read_snapshot(
+    context: &mut SafeNativeContext,
+    _ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert_eq!(args.len(), 1);
+
+    context.charge(COUNTER_READ_SNAPSHOT_BASE)?;
+    ensure_enabled(context)?;
+
+    let (id, limit) = aggregator_info(&safely_pop_arg!(args, StructRef))?;
+
+    // Unlike reading an aggregator, taking a snapshot does not materialize the counter: if the
+    // value in storage is not known, the snapshot records the increments made by the transaction
+    // so far, and the value in storage is only read if the value of the snapshot is needed.
+    let aggregator_context = context.extensions().get::<NativeAggregatorContext>();
+    let mut aggregator_data = aggregator_context.aggregator_data.borrow_mut();
+    let (value, state) = aggregator_data.get_aggregator(id, limit)?.value_and_state();
+    let is_delta = match state {
+        AggregatorState::Data => false,
+        AggregatorState::PositiveDelta => true,
+        AggregatorState::NegativeDelta => {
+            return Err(extension_error("counters can only be incremented").into())
+        },
+    };
+
+    Ok(smallvec![Value::struct_(Struct::pack(vec![
+        Value::address(id.handle.0),
+        Value::address(id.key.0),
+        Value::u128(limit),
+        Value::u128(value),
+        Value::bool(is_delta),
+    ]))])
+}
+
+/***************************************************************************************************
+ * native fun snapshot_value(snapshot: CounterSnapshot): u128;
+ *
+ *   gas cost: base_cost + read_cost (if the value in storage is read)
+ *
+ **************************************************************************************************/
+fn native_snapshot_value(
+    context: &mut SafeNativeContext,
+    _ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert_eq!(args.len(), 1);
+
+    context.charge(COUNTER_SNAPSHOT_VALUE_BASE)?;
+    ensure_enabled(context)?;
+
+    let mut fields = safely_pop_arg!(args, Struct).unpack()?;
+    let (handle, key, limit, value, is_delta) = match (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) {
+        (Some(handle), Some(key), Some(limit), Some(value), Some(is_delta)) => (
+            handle.value_as::<AccountAddress>()?,
+            key.value_as::<AccountAddress>()?,
+            limit.value_as::<u128>()?,
+            value.value_as::<u128>()?,
+            is_delta.value_as::<bool>()?,
+        ),
+        _ => return Err(extension_error("malformed counter snapshot").into()),
+    };
+    if !is_delta {
+        return Ok(smallvec![Value::u128(value)]);
+    }
+
+    // Only read the value in storage, so that the counter itself stays a delta.
+    context.charge(COUNTER_SNAPSHOT_VALUE_READ)?;
+    let aggregator_context = context.extensions().get::<NativeAggregatorContext>();
+    let value_from_storage = aggregator_context
+        .resolver
+        .resolve_table_entry(&TableHandle(handle), &key.to_vec())
+        .map_err(|_| extension_error("could not find the value of the counter"))?
+        .map(|bytes| deserialize(&bytes))
+        .ok_or_else(|| extension_error("could not find the value of the counter"))?;
+
+    Ok(smallvec![Value::u128(addition(
+        value_from_storage,
+        value,
+        limit
+    )?)])
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+pub fn make_all(
+    builder: &SafeNativeBuilder,
+) -> impl Iterator<Item = (String, NativeFunction)> + '_ {
+    let natives = [
+        ("create", native_create as RawSafeNative),
+        ("increment", native_increment),
+        ("read_snapshot", native_read_snapshot),
+        ("snapshot_value", native_snapshot_value),
+    ];
+
+    builder.make_named_natives(natives)
+}
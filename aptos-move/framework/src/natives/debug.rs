@@ -9,6 +9,8 @@ use aptos_native_interface::{
     safely_pop_arg, RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeError,
     SafeNativeResult,
 };
+#[cfg(feature = "testing")]
+use aptos_types::move_value_json::move_value_to_json;
 use move_vm_runtime::native_functions::NativeFunction;
 #[allow(unused_imports)]
 use move_vm_types::{
@@ -104,13 +106,35 @@ fn native_old_print_stacktrace(
     Ok(smallvec![])
 }
 
+/***************************************************************************************************
+ * native fun native_print_value
+ *
+ **************************************************************************************************/
+#[cfg(feature = "testing")]
+fn native_print_value(
+    context: &mut SafeNativeContext,
+    ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(args.len() == 1);
+
+    let x = safely_pop_arg!(args, Reference);
+    let val = x.read_ref().map_err(SafeNativeError::InvariantViolation)?;
+    let layout = context.type_to_fully_annotated_layout(&ty_args[0])?;
+    let json = move_value_to_json(&layout, val.as_move_value(&layout));
+
+    aptos_logger::info!("[debug] {}", json);
+    Ok(smallvec![])
+}
+
 /***************************************************************************************************
  * module
  **************************************************************************************************/
 pub fn make_all(
     builder: &SafeNativeBuilder,
 ) -> impl Iterator<Item = (String, NativeFunction)> + '_ {
-    let natives = [
+    let mut natives = vec![
         ("native_print", native_print as RawSafeNative),
         ("native_stack_trace", native_stack_trace),
         // For re-playability on-chain we still implement the old versions of these functions
@@ -118,5 +142,8 @@ pub fn make_all(
         ("print_stack_trace", native_old_print_stacktrace),
     ];
 
+    #[cfg(feature = "testing")]
+    natives.push(("native_print_value", native_print_value));
+
     builder.make_named_natives(natives)
 }
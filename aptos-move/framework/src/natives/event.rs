@@ -1,15 +1,416 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::get_metadata;
+use aptos_crypto::HashValue;
 use aptos_gas_schedule::gas_params::natives::aptos_framework::*;
 use aptos_native_interface::{
-    safely_pop_arg, RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeError,
-    SafeNativeResult,
+    aptos_try_native, safely_pop_arg, ErrorCategory, RawSafeNative, SafeNativeBuilder,
+    SafeNativeContext, SafeNativeError, SafeNativeResult,
+};
+use aptos_types::{
+    contract_event::{ContractEvent, ContractEventV2},
+    event::EventKey,
+    on_chain_config::FeatureFlag,
+};
+use better_any::{Tid, TidAble};
+use move_core_types::{
+    gas_algebra::{NumArgs, NumBytes},
+    language_storage::{StructTag, TypeTag},
+    value::MoveTypeLayout,
+    vm_status::StatusCode,
 };
 use move_vm_runtime::native_functions::NativeFunction;
-use move_vm_types::{loaded_data::runtime_types::Type, values::Value};
+use move_vm_types::{
+    loaded_data::runtime_types::Type,
+    values::{Value, Vector},
+};
 use smallvec::{smallvec, SmallVec};
-use std::collections::VecDeque;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+/// Abort reason when emitting a module event whose type is not a struct (INVALID_ARGUMENT)
+const ENOT_A_STRUCT: u64 = 1;
+/// Abort reason when emitting a versioned module event with a version other than the one declared
+/// by the `#[event(version = ...)]` attribute of its struct (INVALID_ARGUMENT)
+const EEVENT_VERSION_MISMATCH: u64 = 2;
+/// Abort reason when emitting a module event would exceed the limits on the number or the size of
+/// the events of the session (RESOURCE_EXHAUSTED)
+const EEVENT_LIMIT_EXCEEDED: u64 = 3;
+/// Abort reason when the layout of an event type is deeper or has more nodes than allowed
+/// (RESOURCE_EXHAUSTED)
+const EEVENT_TYPE_TOO_LARGE: u64 = 4;
+/// Abort reason when emitting a module event from a module other than the one defining its struct
+/// (PERMISSION_DENIED)
+const ENOT_EMITTED_BY_DEFINING_MODULE: u64 = 5;
+/// Abort reason when module events, or their dual emission as legacy events, are not enabled
+/// (NOT_IMPLEMENTED)
+const EMODULE_EVENT_NOT_ENABLED: u64 = 6;
+/// Abort reason when emitting a module event whose struct is not declared with the `#[event]`
+/// attribute (INVALID_ARGUMENT)
+const ENOT_AN_EVENT: u64 = 7;
+
+/// Limits on the events emitted during a session, enforced when emitting module events. The size
+/// of the events is the size of their payloads.
+///
+/// The layouts of the module event types are limited too, as the layout of a deeply nested or
+/// generic type can be much larger than its type argument.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NativeEventLimits {
+    pub max_num_events: u64,
+    pub max_total_bytes: u64,
+    pub max_type_layout_depth: u64,
+    pub max_type_layout_nodes: u64,
+}
+
+impl NativeEventLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_num_events: u64::MAX,
+            max_total_bytes: u64::MAX,
+            max_type_layout_depth: u64::MAX,
+            max_type_layout_nodes: u64::MAX,
+        }
+    }
+}
+
+impl Default for NativeEventLimits {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// A filter applied to the events of a session when they are collected by
+/// [`NativeEventContext::into_events`], e.g. by simulations which redact or annotate large event
+/// payloads before returning them.
+pub trait EventFilter: Send + Sync {
+    /// Returns the event to return in place of the given one, or None to drop it.
+    fn filter(&self, event: ContractEvent) -> Option<ContractEvent>;
+}
+
+/// The attributes of a struct which matter when it is emitted as a module event, as declared in
+/// the metadata of its module.
+#[derive(Clone, Debug, Default)]
+struct EventAttributes {
+    is_event: bool,
+    // The version declared by the `#[event(version = ...)]` attribute, if any.
+    version: Option<u64>,
+    // The group declared by the `#[event(group = ...)]` attribute, if any.
+    group: Option<String>,
+}
+
+/// The native event context extension, which collects the events emitted during a session, both
+/// to an event handle and as module events, in emission order. This needs to be attached to the
+/// NativeContextExtensions value which is passed into session functions, so its accessible from
+/// natives of this extension.
+///
+/// It also caches the layouts of the event types and the event attributes of the structs emitted as
+/// module events, so that emitting many events of the same type only resolves them once.
+///
+/// While module events are also emitted as legacy events, the copies are collected with the other
+/// events, but are not visible to the natives reading the pending events of the session.
+#[derive(Tid, Default)]
+pub struct NativeEventContext {
+    events: Vec<ContractEvent>,
+    limits: NativeEventLimits,
+    total_bytes: u64,
+    legacy_copy_keys: HashSet<EventKey>,
+    layouts: HashMap<TypeTag, Arc<MoveTypeLayout>>,
+    event_attributes: HashMap<StructTag, EventAttributes>,
+    // Whether the structs emitted as module events need not be declared with `#[event]`.
+    skip_event_check: bool,
+    filter: Option<Arc<dyn EventFilter>>,
+}
+
+impl NativeEventContext {
+    pub fn new(limits: NativeEventLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Creates the context of Move unit tests. The modules of unit tests carry no metadata, so the
+    /// structs they emit as module events are not checked to be declared with `#[event]`.
+    pub fn for_unit_tests() -> Self {
+        Self {
+            skip_event_check: true,
+            ..Self::default()
+        }
+    }
+
+    /// Installs a filter on the events returned by [`Self::into_events`].
+    pub fn with_filter(mut self, filter: Arc<dyn EventFilter>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Returns the events emitted during the session, in emission order, as transformed by the
+    /// filter if one is installed. Module events carry the group declared by their struct, if any.
+    pub fn into_events(self) -> Vec<ContractEvent> {
+        match self.filter {
+            Some(filter) => self
+                .events
+                .into_iter()
+                .filter_map(|event| filter.filter(event))
+                .collect(),
+            None => self.events,
+        }
+    }
+
+    fn push(&mut self, event: ContractEvent) {
+        self.total_bytes += event.event_data().len() as u64;
+        self.events.push(event);
+    }
+
+    /// Records a module event, aborting if this exceeds the limits of the session.
+    fn push_module_event(&mut self, event: ContractEvent) -> SafeNativeResult<()> {
+        let num_events = self.events.len() as u64 + 1;
+        let total_bytes = self.total_bytes + event.event_data().len() as u64;
+        if num_events > self.limits.max_num_events || total_bytes > self.limits.max_total_bytes {
+            return Err(SafeNativeError::abort(
+                ErrorCategory::ResourceExhausted,
+                EEVENT_LIMIT_EXCEEDED,
+            ));
+        }
+        self.push(event);
+        Ok(())
+    }
+
+    /// Records the legacy copy of a module event, aborting if this exceeds the limits of the
+    /// session.
+    fn push_legacy_copy(&mut self, event: ContractEvent) -> SafeNativeResult<()> {
+        if let Some(key) = event.event_key() {
+            self.legacy_copy_keys.insert(*key);
+        }
+        self.push_module_event(event)
+    }
+
+    fn is_legacy_copy(&self, event: &ContractEvent) -> bool {
+        event
+            .event_key()
+            .map_or(false, |key| self.legacy_copy_keys.contains(key))
+    }
+
+    /// Returns the payloads of the events of the given type emitted so far, both to an event
+    /// handle and as module events, in emission order.
+    fn pending_events(&self, ty_tag: &TypeTag) -> Vec<&[u8]> {
+        self.events
+            .iter()
+            .filter(|event| event.type_tag() == ty_tag && !self.is_legacy_copy(event))
+            .map(|event| event.event_data())
+            .collect()
+    }
+
+    /// Returns the payloads of the events of the given type emitted to the given event handle.
+    #[cfg(feature = "testing")]
+    fn emitted_handle_events(&self, key: &EventKey, ty_tag: &TypeTag) -> Vec<&[u8]> {
+        self.events
+            .iter()
+            .filter(|event| event.event_key() == Some(key) && event.type_tag() == ty_tag)
+            .map(|event| event.event_data())
+            .collect()
+    }
+
+    /// Returns the payloads of the module events of the given type.
+    #[cfg(feature = "testing")]
+    fn emitted_module_events(&self, ty_tag: &TypeTag) -> Vec<&[u8]> {
+        self.events
+            .iter()
+            .filter(|event| event.is_v2() && event.type_tag() == ty_tag)
+            .map(|event| event.event_data())
+            .collect()
+    }
+}
+
+/// Serializes the payload of an event emitted to a handle with the layout of its type. These are
+/// not subject to the limits on the layouts of module event types, nor charged for them.
+fn serialize_handle_event(
+    context: &mut SafeNativeContext,
+    ty: &Type,
+    msg: &Value,
+) -> SafeNativeResult<(TypeTag, Vec<u8>)> {
+    let ty_tag = context.type_to_type_tag(ty)?;
+    let cached_layout = context
+        .extensions()
+        .get::<NativeEventContext>()
+        .layouts
+        .get(&ty_tag)
+        .cloned();
+    // Unbounded layouts are not cached, as module events of the same type must not skip the
+    // limits.
+    let blob = match cached_layout {
+        Some(ty_layout) => aptos_try_native!(msg.simple_serialize(&ty_layout)),
+        None => aptos_try_native!(msg.simple_serialize(&context.type_to_type_layout(ty)?)),
+    };
+    Ok((ty_tag, blob))
+}
+
+fn ensure_module_events_enabled(
+    context: &SafeNativeContext,
+    flag: FeatureFlag,
+) -> SafeNativeResult<()> {
+    if !context.get_feature_flags().is_enabled(flag) {
+        return Err(SafeNativeError::abort(
+            ErrorCategory::NotImplemented,
+            EMODULE_EVENT_NOT_ENABLED,
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the struct tag of a module event type, aborting if the type is not a struct or if the
+/// struct is not defined by the module emitting the event, so that no module can emit events on
+/// behalf of another one.
+///
+/// The module emitting the event is the caller of the `event` function calling the native, as the
+/// frame of the latter is not on the call stack while the native runs.
+fn module_event_struct_tag(context: &SafeNativeContext, ty: &Type) -> SafeNativeResult<StructTag> {
+    let struct_tag = match context.type_to_type_tag(ty)? {
+        TypeTag::Struct(struct_tag) => *struct_tag,
+        _ => {
+            return Err(SafeNativeError::abort(
+                ErrorCategory::InvalidArgument,
+                ENOT_A_STRUCT,
+            ))
+        },
+    };
+    let frames = context.stack_frames(1);
+    let emitter = frames
+        .stack_trace()
+        .first()
+        .and_then(|(module_id, _, _)| module_id.as_ref());
+    if emitter != Some(&struct_tag.module_id()) {
+        return Err(SafeNativeError::abort(
+            ErrorCategory::PermissionDenied,
+            ENOT_EMITTED_BY_DEFINING_MODULE,
+        ));
+    }
+    Ok(struct_tag)
+}
+
+/// Returns the key of the legacy event stream to which the module events of a struct are copied,
+/// under the address of the module defining it. Its creation number is derived from the struct
+/// tag, with the top bit set, as the creation numbers of the GUIDs of accounts and objects are
+/// allocated sequentially well below this range.
+fn legacy_event_key(struct_tag: &StructTag) -> SafeNativeResult<EventKey> {
+    let hash = HashValue::sha3_256_of(&aptos_try_native!(bcs::to_bytes(struct_tag)));
+    let mut creation_num_bytes = [0u8; 8];
+    creation_num_bytes.copy_from_slice(&hash.as_ref()[..8]);
+    let creation_num = u64::from_le_bytes(creation_num_bytes) | (1 << 63);
+    Ok(EventKey::new(creation_num, struct_tag.address))
+}
+
+/// Serializes the payload of a module event with the layout of its type.
+fn serialize_module_event(
+    context: &mut SafeNativeContext,
+    ty: &Type,
+    ty_tag: &TypeTag,
+    msg: &Value,
+) -> SafeNativeResult<Vec<u8>> {
+    let ty_layout = get_module_event_type_layout(context, ty, ty_tag)?;
+    Ok(aptos_try_native!(msg.simple_serialize(&ty_layout)))
+}
+
+/// Returns the layout of a module event type, aborting as soon as it exceeds the limits of the
+/// session while it is built. Layouts are charged for by their number of nodes when they are first
+/// computed, then cached.
+fn get_module_event_type_layout(
+    context: &mut SafeNativeContext,
+    ty: &Type,
+    ty_tag: &TypeTag,
+) -> SafeNativeResult<Arc<MoveTypeLayout>> {
+    let ctx = context.extensions().get::<NativeEventContext>();
+    if let Some(ty_layout) = ctx.layouts.get(ty_tag) {
+        return Ok(ty_layout.clone());
+    }
+    let limits = ctx.limits;
+
+    let (ty_layout, num_nodes) = context
+        .type_to_type_layout_with_limits(
+            ty,
+            limits.max_type_layout_nodes,
+            limits.max_type_layout_depth,
+        )
+        .map_err(|err| match err.major_status() {
+            StatusCode::TOO_MANY_TYPE_NODES | StatusCode::VM_MAX_VALUE_DEPTH_REACHED => {
+                SafeNativeError::abort(ErrorCategory::ResourceExhausted, EEVENT_TYPE_TOO_LARGE)
+            },
+            _ => SafeNativeError::InvariantViolation(err),
+        })?;
+    context.charge(EVENT_TYPE_LAYOUT_PER_NODE * NumArgs::new(num_nodes))?;
+
+    let ty_layout = Arc::new(ty_layout);
+    context
+        .extensions_mut()
+        .get_mut::<NativeEventContext>()
+        .layouts
+        .insert(ty_tag.clone(), ty_layout.clone());
+    Ok(ty_layout)
+}
+
+/// Returns the event attributes of a module event struct, aborting if it is not declared with the
+/// `#[event]` attribute. They are resolved from the metadata of its module the first time the
+/// struct is emitted in the session, and each of these lookups is charged for, as it reads the
+/// module from storage.
+fn module_event_attributes(
+    context: &mut SafeNativeContext,
+    struct_tag: &StructTag,
+) -> SafeNativeResult<EventAttributes> {
+    let ctx = context.extensions().get::<NativeEventContext>();
+    let skip_event_check = ctx.skip_event_check;
+    let attributes = match ctx.event_attributes.get(struct_tag).cloned() {
+        Some(attributes) => attributes,
+        None => {
+            context.charge(EVENT_METADATA_LOOKUP_BASE)?;
+            let attributes = context
+                .with_module_metadata(&struct_tag.module_id(), |metadata| {
+                    let metadata = get_metadata(metadata)?;
+                    let struct_name = struct_tag.name.as_str();
+                    Some(EventAttributes {
+                        is_event: metadata.is_event(struct_name),
+                        version: metadata.event_version(struct_name),
+                        group: metadata.event_group(struct_name).map(str::to_string),
+                    })
+                })
+                .unwrap_or_default();
+            context
+                .extensions_mut()
+                .get_mut::<NativeEventContext>()
+                .event_attributes
+                .insert(struct_tag.clone(), attributes.clone());
+            attributes
+        },
+    };
+    if !attributes.is_event && !skip_event_check {
+        return Err(SafeNativeError::abort(
+            ErrorCategory::InvalidArgument,
+            ENOT_AN_EVENT,
+        ));
+    }
+    Ok(attributes)
+}
+
+/// Deserializes event payloads emitted during the session, for inspection by Move tests.
+#[cfg(feature = "testing")]
+fn deserialize_events(
+    ty: &Type,
+    ty_layout: &MoveTypeLayout,
+    blobs: Vec<&[u8]>,
+) -> SafeNativeResult<Value> {
+    let events = blobs
+        .into_iter()
+        .map(|blob| {
+            Ok(aptos_try_native!(
+                Value::simple_deserialize(blob, ty_layout),
+                StatusCode::VALUE_DESERIALIZATION_ERROR
+            ))
+        })
+        .collect::<SafeNativeResult<Vec<_>>>()?;
+    Ok(Vector::pack(ty, events)?)
+}
 
 /***************************************************************************************************
  * native fun write_to_event_store
@@ -37,13 +438,210 @@ fn native_write_to_event_store(
             + EVENT_WRITE_TO_EVENT_STORE_PER_ABSTRACT_VALUE_UNIT * context.abs_val_size(&msg),
     )?;
 
-    if !context.save_event(guid, seq_num, ty, msg)? {
-        return Err(SafeNativeError::Abort { abort_code: 0 });
+    let key: EventKey = aptos_try_native!(bcs::from_bytes(&guid), StatusCode::EVENT_KEY_MISMATCH);
+    let (ty_tag, blob) = serialize_handle_event(context, &ty, &msg)?;
+    let ctx = context.extensions_mut().get_mut::<NativeEventContext>();
+    ctx.push(ContractEvent::new(key, seq_num, ty_tag, blob));
+
+    Ok(smallvec![])
+}
+
+/***************************************************************************************************
+ * native fun write_to_module_event_store
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+#[inline]
+fn native_write_module_event_to_store(
+    context: &mut SafeNativeContext,
+    mut ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.len() == 1);
+
+    let ty = ty_args.pop().unwrap();
+    let msg = arguments.pop_back().unwrap();
+
+    ensure_module_events_enabled(context, FeatureFlag::MODULE_EVENT)?;
+    context.charge(
+        EVENT_WRITE_TO_MODULE_EVENT_STORE_BASE
+            + EVENT_WRITE_TO_MODULE_EVENT_STORE_PER_ABSTRACT_VALUE_UNIT
+                * context.abs_val_size(&msg),
+    )?;
+
+    let struct_tag = module_event_struct_tag(context, &ty)?;
+    let attributes = module_event_attributes(context, &struct_tag)?;
+    let ty_tag = TypeTag::Struct(Box::new(struct_tag));
+    let blob = serialize_module_event(context, &ty, &ty_tag, &msg)?;
+    let event = ContractEventV2::new(ty_tag, blob).with_group(attributes.group);
+    context
+        .extensions_mut()
+        .get_mut::<NativeEventContext>()
+        .push_module_event(event.into())?;
+
+    Ok(smallvec![])
+}
+
+/***************************************************************************************************
+ * native fun write_to_module_event_store_with_legacy_copy
+ *
+ *   gas cost: base_cost + legacy copy base_cost + per_byte * size of the copy
+ *
+ **************************************************************************************************/
+#[inline]
+fn native_write_module_event_with_legacy_copy_to_store(
+    context: &mut SafeNativeContext,
+    mut ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.len() == 2);
+
+    let ty = ty_args.pop().unwrap();
+    let legacy_seq_num = safely_pop_arg!(arguments, u64);
+    let msg = arguments.pop_back().unwrap();
+
+    ensure_module_events_enabled(context, FeatureFlag::MODULE_EVENT)?;
+    ensure_module_events_enabled(context, FeatureFlag::MODULE_EVENT_DUAL_EMISSION)?;
+    context.charge(
+        EVENT_WRITE_TO_MODULE_EVENT_STORE_BASE
+            + EVENT_WRITE_TO_MODULE_EVENT_STORE_PER_ABSTRACT_VALUE_UNIT
+                * context.abs_val_size(&msg),
+    )?;
+
+    let struct_tag = module_event_struct_tag(context, &ty)?;
+    let attributes = module_event_attributes(context, &struct_tag)?;
+    let legacy_key = legacy_event_key(&struct_tag)?;
+    let ty_tag = TypeTag::Struct(Box::new(struct_tag));
+    let blob = serialize_module_event(context, &ty, &ty_tag, &msg)?;
+    context.charge(
+        EVENT_LEGACY_COPY_BASE + EVENT_LEGACY_COPY_PER_BYTE * NumBytes::new(blob.len() as u64),
+    )?;
+
+    let ctx = context.extensions_mut().get_mut::<NativeEventContext>();
+    let legacy_copy = ContractEvent::new(legacy_key, legacy_seq_num, ty_tag.clone(), blob.clone());
+    let event = ContractEventV2::new(ty_tag, blob).with_group(attributes.group);
+    ctx.push_module_event(event.into())?;
+    ctx.push_legacy_copy(legacy_copy)?;
+
+    Ok(smallvec![])
+}
+
+/***************************************************************************************************
+ * native fun write_to_versioned_module_event_store
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+#[inline]
+fn native_write_versioned_module_event_to_store(
+    context: &mut SafeNativeContext,
+    mut ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.len() == 2);
+
+    let ty = ty_args.pop().unwrap();
+    let version = safely_pop_arg!(arguments, u64);
+    let msg = arguments.pop_back().unwrap();
+
+    ensure_module_events_enabled(context, FeatureFlag::MODULE_EVENT)?;
+    // Charged the same as unversioned module events.
+    context.charge(
+        EVENT_WRITE_TO_MODULE_EVENT_STORE_BASE
+            + EVENT_WRITE_TO_MODULE_EVENT_STORE_PER_ABSTRACT_VALUE_UNIT
+                * context.abs_val_size(&msg),
+    )?;
+
+    let struct_tag = module_event_struct_tag(context, &ty)?;
+    let attributes = module_event_attributes(context, &struct_tag)?;
+    if attributes.version != Some(version) {
+        return Err(SafeNativeError::abort(
+            ErrorCategory::InvalidArgument,
+            EEVENT_VERSION_MISMATCH,
+        ));
     }
+    let ty_tag = TypeTag::Struct(Box::new(struct_tag));
+    let blob = serialize_module_event(context, &ty, &ty_tag, &msg)?;
+    let event = ContractEventV2::new_versioned(ty_tag, version, blob).with_group(attributes.group);
+    context
+        .extensions_mut()
+        .get_mut::<NativeEventContext>()
+        .push_module_event(event.into())?;
 
     Ok(smallvec![])
 }
 
+/***************************************************************************************************
+ * native fun read_pending_event_bytes
+ *
+ *   gas cost: base_cost + per_event * number of events read + per_byte * total size of the events
+ *             read
+ *
+ **************************************************************************************************/
+fn native_read_pending_event_bytes(
+    context: &mut SafeNativeContext,
+    mut ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.is_empty());
+
+    let ty = ty_args.pop().unwrap();
+
+    context.charge(EVENT_READ_PENDING_EVENTS_BASE)?;
+    let ty_tag = context.type_to_type_tag(&ty)?;
+    let events: Vec<Vec<u8>> = context
+        .extensions()
+        .get::<NativeEventContext>()
+        .pending_events(&ty_tag)
+        .into_iter()
+        .map(|blob| blob.to_vec())
+        .collect();
+    let num_bytes: usize = events.iter().map(|blob| blob.len()).sum();
+    context.charge(
+        EVENT_READ_PENDING_EVENTS_PER_EVENT * NumArgs::new(events.len() as u64)
+            + EVENT_READ_PENDING_EVENTS_PER_BYTE * NumBytes::new(num_bytes as u64),
+    )?;
+
+    let events = events.into_iter().map(Value::vector_u8).collect();
+    Ok(smallvec![Vector::pack(
+        &Type::Vector(Box::new(Type::U8)),
+        events
+    )?])
+}
+
+/***************************************************************************************************
+ * native fun pending_event_count
+ *
+ *   gas cost: base_cost + per_event * number of events counted
+ *
+ **************************************************************************************************/
+fn native_pending_event_count(
+    context: &mut SafeNativeContext,
+    mut ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.is_empty());
+
+    let ty = ty_args.pop().unwrap();
+
+    context.charge(EVENT_READ_PENDING_EVENTS_BASE)?;
+    let ty_tag = context.type_to_type_tag(&ty)?;
+    let count = context
+        .extensions()
+        .get::<NativeEventContext>()
+        .pending_events(&ty_tag)
+        .len() as u64;
+    context.charge(EVENT_READ_PENDING_EVENTS_PER_EVENT * NumArgs::new(count))?;
+
+    Ok(smallvec![Value::u64(count)])
+}
+
 #[cfg(feature = "testing")]
 fn native_emitted_events_internal(
     context: &mut SafeNativeContext,
@@ -56,8 +654,30 @@ fn native_emitted_events_internal(
     let ty = ty_args.pop().unwrap();
     let guid = safely_pop_arg!(arguments, Vec<u8>);
 
-    let events = context.emitted_events(guid, ty)?;
-    Ok(smallvec![Value::vector_for_testing_only(events)])
+    let key: EventKey = aptos_try_native!(bcs::from_bytes(&guid), StatusCode::EVENT_KEY_MISMATCH);
+    let ty_tag = context.type_to_type_tag(&ty)?;
+    let ty_layout = context.type_to_type_layout(&ty)?;
+    let ctx = context.extensions().get::<NativeEventContext>();
+    let events = deserialize_events(&ty, &ty_layout, ctx.emitted_handle_events(&key, &ty_tag))?;
+    Ok(smallvec![events])
+}
+
+#[cfg(feature = "testing")]
+fn native_emitted_module_events_internal(
+    context: &mut SafeNativeContext,
+    mut ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.is_empty());
+
+    let ty = ty_args.pop().unwrap();
+
+    let ty_tag = context.type_to_type_tag(&ty)?;
+    let ty_layout = context.type_to_type_layout(&ty)?;
+    let ctx = context.extensions().get::<NativeEventContext>();
+    let events = deserialize_events(&ty, &ty_layout, ctx.emitted_module_events(&ty_tag))?;
+    Ok(smallvec![events])
 }
 
 /***************************************************************************************************
@@ -70,15 +690,567 @@ pub fn make_all(
     let mut natives = vec![];
 
     #[cfg(feature = "testing")]
-    natives.extend([(
-        "emitted_events_internal",
-        native_emitted_events_internal as RawSafeNative,
-    )]);
+    natives.extend([
+        (
+            "emitted_events_internal",
+            native_emitted_events_internal as RawSafeNative,
+        ),
+        (
+            "emitted_module_events_internal",
+            native_emitted_module_events_internal,
+        ),
+    ]);
 
-    natives.extend([(
-        "write_to_event_store",
-        native_write_to_event_store as RawSafeNative,
-    )]);
+    natives.extend([
+        (
+            "write_to_event_store",
+            native_write_to_event_store as RawSafeNative,
+        ),
+        (
+            "write_to_module_event_store",
+            native_write_module_event_to_store,
+        ),
+        (
+            "write_to_module_event_store_with_legacy_copy",
+            native_write_module_event_with_legacy_copy_to_store,
+        ),
+        (
+            "write_to_versioned_module_event_store",
+            native_write_versioned_module_event_to_store,
+        ),
+        ("read_pending_event_bytes", native_read_pending_event_bytes),
+        ("pending_event_count", native_pending_event_count),
+    ]);
 
     builder.make_named_natives(natives)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KnownAttribute, RuntimeModuleMetadataV1, APTOS_METADATA_KEY_V1};
+    use aptos_gas_schedule::{MiscGasParameters, NativeGasParameters, LATEST_GAS_FEATURE_VERSION};
+    use aptos_types::on_chain_config::{Features, TimedFeatures};
+    use move_binary_format::{errors::VMResult, CompiledModule};
+    use move_core_types::{
+        account_address::AccountAddress,
+        gas_algebra::{InternalGas, InternalGasPerByte},
+        identifier::{IdentStr, Identifier},
+        language_storage::ModuleId,
+        metadata::Metadata,
+    };
+    use move_ir_compiler::Compiler;
+    use move_vm_runtime::{move_vm::MoveVM, native_extensions::NativeContextExtensions};
+    use move_vm_test_utils::InMemoryStorage;
+    use move_vm_types::{gas::UnmeteredGasMeter, natives::function::NativeResult};
+    use std::sync::Mutex;
+
+    const HANDLE_EVENT_BASE: u64 = 100;
+    const MODULE_EVENT_BASE: u64 = 300;
+    const METADATA_LOOKUP_BASE: u64 = 20;
+    const LEGACY_COPY_BASE: u64 = 50;
+
+    const EVENT_MODULE: &str = r#"
+        module 0x1.event {
+            native public write_to_event_store<T: drop + store>(guid: vector<u8>, count: u64, msg: T);
+            native write_to_module_event_store<T: drop + store>(msg: T);
+            native write_to_versioned_module_event_store<T: drop + store>(msg: T, version: u64);
+            native write_to_module_event_store_with_legacy_copy<T: drop + store>(msg: T, legacy_count: u64);
+
+            public emit<T: drop + store>(msg: T) {
+            label b0:
+                Self.write_to_module_event_store<T>(move(msg));
+                return;
+            }
+
+            public emit_with_legacy_copy<T: drop + store>(msg: T, legacy_count: u64) {
+            label b0:
+                Self.write_to_module_event_store_with_legacy_copy<T>(move(msg), move(legacy_count));
+                return;
+            }
+
+            public emit_versioned_event<T: drop + store>(msg: T, version: u64) {
+            label b0:
+                Self.write_to_versioned_module_event_store<T>(move(msg), move(version));
+                return;
+            }
+        }
+    "#;
+
+    const TEST_MODULE: &str = r#"
+        module 0xcafe.test {
+            import 0x1.event;
+
+            struct Event has drop, store { value: u64 }
+            struct VersionedEvent has drop, store { value: u64 }
+            struct NotAnEvent has drop, store { value: u64 }
+
+            public new_event(): Self.Event {
+            label b0:
+                return Event { value: 1 };
+            }
+
+            public emit_handle_event(guid: vector<u8>, seq_num: u64) {
+            label b0:
+                event.write_to_event_store<Self.NotAnEvent>(move(guid), move(seq_num), NotAnEvent { value: 1 });
+                return;
+            }
+
+            public emit_events() {
+            label b0:
+                event.emit<Self.Event>(Event { value: 1 });
+                event.emit<Self.Event>(Event { value: 1 });
+                return;
+            }
+
+            public emit_event_with_legacy_copy(legacy_count: u64) {
+            label b0:
+                event.emit_with_legacy_copy<Self.Event>(Event { value: 1 }, move(legacy_count));
+                return;
+            }
+
+            public emit_versioned_event(version: u64) {
+            label b0:
+                event.emit_versioned_event<Self.VersionedEvent>(VersionedEvent { value: 1 }, move(version));
+                return;
+            }
+
+            public emit_not_an_event() {
+            label b0:
+                event.emit<Self.NotAnEvent>(NotAnEvent { value: 1 });
+                return;
+            }
+
+            public emit_u64() {
+            label b0:
+                event.emit<u64>(1);
+                return;
+            }
+        }
+    "#;
+
+    const OTHER_MODULE: &str = r#"
+        module 0xcafe.other {
+            import 0x1.event;
+            import 0xcafe.test;
+
+            public emit_event_of_test_module() {
+            label b0:
+                event.emit<test.Event>(test.new_event());
+                return;
+            }
+        }
+    "#;
+
+    /// Runs Move functions calling the event natives, recording the gas charged by each call to
+    /// a native.
+    struct EventHarness {
+        vm: MoveVM,
+        storage: InMemoryStorage,
+        costs: Arc<Mutex<Vec<InternalGas>>>,
+    }
+
+    impl EventHarness {
+        fn new(features: Features) -> Self {
+            let mut native_gas_params = NativeGasParameters::zeros();
+            let gas_params = &mut native_gas_params.aptos_framework;
+            gas_params.event_write_to_event_store_base = InternalGas::new(HANDLE_EVENT_BASE);
+            gas_params.event_write_to_module_event_store_base = InternalGas::new(MODULE_EVENT_BASE);
+            gas_params.event_metadata_lookup_base = InternalGas::new(METADATA_LOOKUP_BASE);
+            gas_params.event_legacy_copy_base = InternalGas::new(LEGACY_COPY_BASE);
+            gas_params.event_legacy_copy_per_byte = InternalGasPerByte::new(1);
+            let builder = SafeNativeBuilder::new(
+                LATEST_GAS_FEATURE_VERSION,
+                native_gas_params,
+                MiscGasParameters::zeros(),
+                TimedFeatures::enable_all(),
+                features,
+            );
+
+            let costs = Arc::new(Mutex::new(vec![]));
+            let natives: Vec<_> = make_all(&builder)
+                .map(|(name, native)| {
+                    let costs = costs.clone();
+                    let native: NativeFunction = Arc::new(move |context, ty_args, args| {
+                        let result = native(context, ty_args, args)?;
+                        let cost = match &result {
+                            NativeResult::Success { cost, .. }
+                            | NativeResult::Abort { cost, .. } => *cost,
+                            NativeResult::OutOfGas { partial_cost } => *partial_cost,
+                        };
+                        costs.lock().unwrap().push(cost);
+                        Ok(result)
+                    });
+                    (
+                        AccountAddress::ONE,
+                        Identifier::new("event").unwrap(),
+                        Identifier::new(name).unwrap(),
+                        native,
+                    )
+                })
+                .collect();
+
+            let mut storage = InMemoryStorage::new();
+            for module in compile_test_modules() {
+                let mut blob = vec![];
+                module.serialize(&mut blob).unwrap();
+                storage.publish_or_overwrite_module(module.self_id(), blob);
+            }
+
+            Self {
+                vm: MoveVM::new(natives).unwrap(),
+                storage,
+                costs,
+            }
+        }
+
+        /// Runs a function of a test module at 0xcafe, returning its result, the gas charged by
+        /// the natives it called and the events emitted.
+        fn run(
+            &self,
+            limits: NativeEventLimits,
+            module: &str,
+            function: &str,
+            args: Vec<Vec<u8>>,
+        ) -> (VMResult<()>, Vec<InternalGas>, Vec<ContractEvent>) {
+            self.costs.lock().unwrap().clear();
+            let mut extensions = NativeContextExtensions::default();
+            extensions.add(NativeEventContext::new(limits));
+            let mut session = self
+                .vm
+                .new_session_with_extensions(&self.storage, extensions);
+            let result = session
+                .execute_function_bypass_visibility(
+                    &ModuleId::new(
+                        AccountAddress::from_hex_literal("0xcafe").unwrap(),
+                        Identifier::new(module).unwrap(),
+                    ),
+                    IdentStr::new(function).unwrap(),
+                    vec![],
+                    args,
+                    &mut UnmeteredGasMeter,
+                )
+                .map(|_| ());
+            let (_, _, mut extensions) = session.finish_with_extensions().unwrap();
+            let events = extensions.remove::<NativeEventContext>().into_events();
+            let costs = self.costs.lock().unwrap().clone();
+            (result, costs, events)
+        }
+    }
+
+    fn compile_test_modules() -> Vec<CompiledModule> {
+        let event_module = Compiler::new(vec![])
+            .into_compiled_module(EVENT_MODULE)
+            .unwrap();
+        let mut test_module = Compiler::new(vec![&event_module])
+            .into_compiled_module(TEST_MODULE)
+            .unwrap();
+        let other_module = Compiler::new(vec![&event_module, &test_module])
+            .into_compiled_module(OTHER_MODULE)
+            .unwrap();
+
+        // The metadata the extended checks would attach to the module for the `#[event]` and
+        // `#[event(version = 1, group = "defi")]` attributes.
+        let metadata = RuntimeModuleMetadataV1 {
+            struct_attributes: [
+                ("Event".to_string(), vec![KnownAttribute::event()]),
+                (
+                    "VersionedEvent".to_string(),
+                    vec![
+                        KnownAttribute::versioned_event(1),
+                        KnownAttribute::event_group("defi".to_string()),
+                    ],
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..RuntimeModuleMetadataV1::default()
+        };
+        test_module.metadata.push(Metadata {
+            key: APTOS_METADATA_KEY_V1.to_vec(),
+            value: bcs::to_bytes(&metadata).unwrap(),
+        });
+
+        vec![event_module, test_module, other_module]
+    }
+
+    fn struct_tag(name: &str) -> TypeTag {
+        TypeTag::Struct(Box::new(format!("0xcafe::test::{}", name).parse().unwrap()))
+    }
+
+    fn features_with(flag: FeatureFlag) -> Features {
+        let mut features = Features::default();
+        let idx = flag as usize;
+        features
+            .features
+            .resize(features.features.len().max(idx / 8 + 1), 0);
+        features.features[idx / 8] |= 1 << (idx % 8);
+        features
+    }
+
+    fn assert_abort(result: VMResult<()>, abort_code: u64) {
+        let err = result.unwrap_err();
+        assert_eq!(err.major_status(), StatusCode::ABORTED);
+        assert_eq!(err.sub_status(), Some(abort_code));
+    }
+
+    fn event(len: usize) -> ContractEvent {
+        ContractEvent::new_v2(TypeTag::U8, vec![0; len])
+    }
+
+    fn is_limit_exceeded(result: SafeNativeResult<()>) -> bool {
+        match result {
+            Err(SafeNativeError::Abort { abort_code }) => {
+                assert_eq!(abort_code, 0x09_0003);
+                assert_eq!(
+                    ErrorCategory::of_abort_code(abort_code),
+                    Some(ErrorCategory::ResourceExhausted)
+                );
+                true
+            },
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_module_event_limits() {
+        let mut ctx = NativeEventContext::new(NativeEventLimits {
+            max_num_events: 3,
+            max_total_bytes: 10,
+            ..NativeEventLimits::unlimited()
+        });
+        ctx.push(event(4));
+        assert!(ctx.push_module_event(event(4)).is_ok());
+        // Handle events count towards the limits as well.
+        assert!(is_limit_exceeded(ctx.push_module_event(event(3))));
+        assert!(ctx.push_module_event(event(2)).is_ok());
+        assert!(is_limit_exceeded(ctx.push_module_event(event(0))));
+        assert_eq!(ctx.into_events().len(), 3);
+
+        let mut ctx = NativeEventContext::default();
+        for _ in 0..100 {
+            assert!(ctx.push_module_event(event(1 << 10)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_event_filter() {
+        struct DropLargeEvents;
+
+        impl EventFilter for DropLargeEvents {
+            fn filter(&self, event: ContractEvent) -> Option<ContractEvent> {
+                (event.event_data().len() <= 2).then_some(event)
+            }
+        }
+
+        let mut ctx = NativeEventContext::default().with_filter(Arc::new(DropLargeEvents));
+        for len in 1..=4 {
+            assert!(ctx.push_module_event(event(len)).is_ok());
+        }
+        assert_eq!(ctx.into_events(), vec![event(1), event(2)]);
+    }
+
+    #[test]
+    fn test_write_to_event_store() {
+        let harness = EventHarness::new(Features::default());
+        let key = EventKey::new(0, AccountAddress::ONE);
+        let guid = bcs::to_bytes(&key).unwrap();
+
+        // Events emitted to a handle need not be declared with `#[event]`.
+        let (result, costs, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_handle_event",
+            vec![bcs::to_bytes(&guid).unwrap(), bcs::to_bytes(&7u64).unwrap()],
+        );
+        assert!(result.is_ok());
+        assert_eq!(costs, vec![InternalGas::new(HANDLE_EVENT_BASE)]);
+        assert_eq!(
+            events,
+            vec![ContractEvent::new(
+                key,
+                7,
+                struct_tag("NotAnEvent"),
+                bcs::to_bytes(&1u64).unwrap()
+            )]
+        );
+
+        let (result, _, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_handle_event",
+            vec![
+                bcs::to_bytes(&vec![0u8; 3]).unwrap(),
+                bcs::to_bytes(&0u64).unwrap(),
+            ],
+        );
+        assert_eq!(
+            result.unwrap_err().major_status(),
+            StatusCode::EVENT_KEY_MISMATCH
+        );
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_write_to_module_event_store() {
+        let harness = EventHarness::new(Features::default());
+
+        // The attributes of the struct are only looked up, and charged for, once per session.
+        let (result, costs, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_events",
+            vec![],
+        );
+        assert!(result.is_ok());
+        assert_eq!(
+            costs,
+            vec![
+                InternalGas::new(MODULE_EVENT_BASE + METADATA_LOOKUP_BASE),
+                InternalGas::new(MODULE_EVENT_BASE),
+            ]
+        );
+        let event = ContractEvent::new_v2(struct_tag("Event"), bcs::to_bytes(&1u64).unwrap());
+        assert_eq!(events, vec![event.clone(), event]);
+
+        let limits = NativeEventLimits {
+            max_num_events: 1,
+            ..NativeEventLimits::unlimited()
+        };
+        let (result, _, events) = harness.run(limits, "test", "emit_events", vec![]);
+        assert_abort(result, 0x09_0003);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_write_to_module_event_store_with_legacy_copy() {
+        let harness = EventHarness::new(features_with(FeatureFlag::MODULE_EVENT_DUAL_EMISSION));
+        let blob = bcs::to_bytes(&1u64).unwrap();
+
+        let (result, costs, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_event_with_legacy_copy",
+            vec![bcs::to_bytes(&5u64).unwrap()],
+        );
+        assert!(result.is_ok());
+        assert_eq!(
+            costs,
+            vec![InternalGas::new(
+                MODULE_EVENT_BASE + METADATA_LOOKUP_BASE + LEGACY_COPY_BASE + blob.len() as u64
+            )]
+        );
+        let legacy_key = legacy_event_key(&"0xcafe::test::Event".parse().unwrap()).unwrap();
+        assert_eq!(
+            legacy_key.get_creator_address(),
+            AccountAddress::from_hex_literal("0xcafe").unwrap()
+        );
+        assert!(legacy_key.get_creation_number() >= 1 << 63);
+        assert_eq!(
+            events,
+            vec![
+                ContractEvent::new_v2(struct_tag("Event"), blob.clone()),
+                ContractEvent::new(legacy_key, 5, struct_tag("Event"), blob),
+            ]
+        );
+
+        // The legacy copy counts towards the limits of the session.
+        let limits = NativeEventLimits {
+            max_num_events: 1,
+            ..NativeEventLimits::unlimited()
+        };
+        let (result, _, _) = harness.run(
+            limits,
+            "test",
+            "emit_event_with_legacy_copy",
+            vec![bcs::to_bytes(&0u64).unwrap()],
+        );
+        assert_abort(result, 0x09_0003);
+
+        let harness = EventHarness::new(Features::default());
+        let (result, costs, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_event_with_legacy_copy",
+            vec![bcs::to_bytes(&0u64).unwrap()],
+        );
+        assert_abort(result, 0x0C_0006);
+        assert_eq!(costs, vec![InternalGas::new(0)]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_write_to_versioned_module_event_store() {
+        let harness = EventHarness::new(Features::default());
+
+        let (result, costs, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_versioned_event",
+            vec![bcs::to_bytes(&1u64).unwrap()],
+        );
+        assert!(result.is_ok());
+        assert_eq!(
+            costs,
+            vec![InternalGas::new(MODULE_EVENT_BASE + METADATA_LOOKUP_BASE)]
+        );
+        // The event carries the group of its struct.
+        assert_eq!(
+            events,
+            vec![ContractEvent::from(
+                ContractEventV2::new_versioned(
+                    struct_tag("VersionedEvent"),
+                    1,
+                    bcs::to_bytes(&1u64).unwrap()
+                )
+                .with_group(Some("defi".to_string()))
+            )]
+        );
+
+        let (result, _, events) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_versioned_event",
+            vec![bcs::to_bytes(&2u64).unwrap()],
+        );
+        assert_abort(result, 0x01_0002);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_module_event_aborts() {
+        let harness = EventHarness::new(Features::default());
+        let run = |module, function| {
+            let (result, costs, events) =
+                harness.run(NativeEventLimits::unlimited(), module, function, vec![]);
+            assert!(events.is_empty());
+            (result, costs)
+        };
+
+        let (result, costs) = run("test", "emit_u64");
+        assert_abort(result, 0x01_0001);
+        assert_eq!(costs, vec![InternalGas::new(MODULE_EVENT_BASE)]);
+
+        let (result, costs) = run("other", "emit_event_of_test_module");
+        assert_abort(result, 0x05_0005);
+        assert_eq!(costs, vec![InternalGas::new(MODULE_EVENT_BASE)]);
+
+        let (result, costs) = run("test", "emit_not_an_event");
+        assert_abort(result, 0x01_0007);
+        assert_eq!(
+            costs,
+            vec![InternalGas::new(MODULE_EVENT_BASE + METADATA_LOOKUP_BASE)]
+        );
+
+        // Nothing is charged when module events are not enabled.
+        let harness = EventHarness::new(Features { features: vec![] });
+        let (result, costs, _) = harness.run(
+            NativeEventLimits::unlimited(),
+            "test",
+            "emit_events",
+            vec![],
+        );
+        assert_abort(result, 0x0C_0006);
+        assert_eq!(costs, vec![InternalGas::new(0)]);
+    }
+}
@@ -5,7 +5,9 @@
 pub mod account;
 pub mod aggregator_natives;
 pub mod any;
+pub mod block_seed;
 pub mod code;
+pub mod counter;
 pub mod create_signer;
 pub mod cryptography;
 pub mod debug;
@@ -40,45 +42,42 @@ pub fn all_natives(
     let mut natives = vec![];
 
     macro_rules! add_natives_from_module {
-        ($module_name:expr, $natives:expr) => {
+        ($module_name:expr, $make_all:expr) => {
             natives.extend(
-                $natives.map(|(func_name, func)| ($module_name.to_string(), func_name, func)),
+                $make_all(&builder.for_module($module_name))
+                    .map(|(func_name, func)| ($module_name.to_string(), func_name, func)),
             );
         };
     }
 
-    add_natives_from_module!("account", account::make_all(builder));
-    add_natives_from_module!("create_signer", create_signer::make_all(builder));
-    add_natives_from_module!("ed25519", ed25519::make_all(builder));
-    add_natives_from_module!("crypto_algebra", cryptography::algebra::make_all(builder));
-    add_natives_from_module!("genesis", create_signer::make_all(builder));
-    add_natives_from_module!("multi_ed25519", multi_ed25519::make_all(builder));
-    add_natives_from_module!("bls12381", cryptography::bls12381::make_all(builder));
-    add_natives_from_module!("secp256k1", cryptography::secp256k1::make_all(builder));
-    add_natives_from_module!("aptos_hash", hash::make_all(builder));
-    add_natives_from_module!(
-        "ristretto255",
-        cryptography::ristretto255::make_all(builder)
-    );
-    add_natives_from_module!("type_info", type_info::make_all(builder));
-    add_natives_from_module!("util", util::make_all(builder));
-    add_natives_from_module!("from_bcs", util::make_all(builder));
+    add_natives_from_module!("account", account::make_all);
+    add_natives_from_module!("create_signer", create_signer::make_all);
+    add_natives_from_module!("ed25519", ed25519::make_all);
+    add_natives_from_module!("crypto_algebra", cryptography::algebra::make_all);
+    add_natives_from_module!("genesis", create_signer::make_all);
+    add_natives_from_module!("multi_ed25519", multi_ed25519::make_all);
+    add_natives_from_module!("bls12381", cryptography::bls12381::make_all);
+    add_natives_from_module!("secp256k1", cryptography::secp256k1::make_all);
+    add_natives_from_module!("aptos_hash", hash::make_all);
+    add_natives_from_module!("ristretto255", cryptography::ristretto255::make_all);
+    add_natives_from_module!("type_info", type_info::make_all);
+    add_natives_from_module!("util", util::make_all);
+    add_natives_from_module!("from_bcs", util::make_all);
     add_natives_from_module!(
         "ristretto255_bulletproofs",
-        cryptography::bulletproofs::make_all(builder)
-    );
-    add_natives_from_module!(
-        "transaction_context",
-        transaction_context::make_all(builder)
+        cryptography::bulletproofs::make_all
     );
-    add_natives_from_module!("code", code::make_all(builder));
-    add_natives_from_module!("event", event::make_all(builder));
-    add_natives_from_module!("state_storage", state_storage::make_all(builder));
-    add_natives_from_module!("aggregator", aggregator::make_all(builder));
-    add_natives_from_module!("aggregator_factory", aggregator_factory::make_all(builder));
-    add_natives_from_module!("object", object::make_all(builder));
-    add_natives_from_module!("debug", debug::make_all(builder));
-    add_natives_from_module!("string_utils", string_utils::make_all(builder));
+    add_natives_from_module!("transaction_context", transaction_context::make_all);
+    add_natives_from_module!("code", code::make_all);
+    add_natives_from_module!("event", event::make_all);
+    add_natives_from_module!("state_storage", state_storage::make_all);
+    add_natives_from_module!("aggregator", aggregator::make_all);
+    add_natives_from_module!("aggregator_factory", aggregator_factory::make_all);
+    add_natives_from_module!("counter", counter::make_all);
+    add_natives_from_module!("block_seed", block_seed::make_all);
+    add_natives_from_module!("object", object::make_all);
+    add_natives_from_module!("debug", debug::make_all);
+    add_natives_from_module!("string_utils", string_utils::make_all);
 
     make_table_from_iter(framework_addr, natives)
 }
@@ -3,11 +3,10 @@
 
 use aptos_gas_schedule::gas_params::natives::aptos_framework::*;
 use aptos_native_interface::{
-    RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeResult,
+    aptos_try_native, RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeResult,
 };
 use aptos_types::{state_store::state_storage_usage::StateStorageUsage, vm_status::StatusCode};
 use better_any::{Tid, TidAble};
-use move_binary_format::errors::PartialVMError;
 use move_vm_runtime::native_functions::NativeFunction;
 use move_vm_types::{
     loaded_data::runtime_types::Type,
@@ -54,10 +53,10 @@ fn native_get_usage(
     context.charge(STATE_STORAGE_GET_USAGE_BASE_COST)?;
 
     let ctx = context.extensions().get::<NativeStateStorageContext>();
-    let usage = ctx.resolver.get_state_storage_usage().map_err(|err| {
-        PartialVMError::new(StatusCode::VM_EXTENSION_ERROR)
-            .with_message(format!("Failed to get state storage usage: {}", err))
-    })?;
+    let usage = aptos_try_native!(
+        ctx.resolver.get_state_storage_usage(),
+        StatusCode::VM_EXTENSION_ERROR
+    );
 
     Ok(smallvec![Value::struct_(Struct::pack(vec![
         Value::u64(usage.items() as u64),
@@ -209,6 +209,7 @@ pub fn table_natives(
 ) -> NativeFunctionTable {
     builder.with_incremental_gas_charging(false, |builder| {
         builder
+            .for_module("table")
             .make_named_natives([
                 ("new_table_handle", native_new_table_handle as RawSafeNative),
                 ("add_box", native_add_box),
@@ -377,7 +377,7 @@ fn create_single_node_test_config(
     node_config.consensus.quorum_store_poll_time_ms = 1000;
 
     node_config.execution.concurrency_level = 1;
-    node_config.execution.num_proof_reading_threads = 1;
+    node_config.execution.proof_reading_thread_pool.num_threads = Some(1);
     node_config.execution.paranoid_hot_potato_verification = false;
     node_config.execution.paranoid_type_verification = false;
     node_config
@@ -468,6 +468,9 @@ pub fn setup_environment_and_start_node(
     let peers_and_metadata = network::create_peers_and_metadata(&node_config);
     services::start_node_inspection_service(&node_config, peers_and_metadata.clone());
 
+    // Configure the dedicated thread pools (before any of them are used)
+    utils::set_thread_pool_configurations(&node_config);
+
     // Set up the storage database and any RocksDB checkpoints
     let (aptos_db, db_rw, backup_service, genesis_waypoint) =
         storage::initialize_database_and_checkpoints(&mut node_config)?;
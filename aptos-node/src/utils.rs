@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::anyhow;
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{NodeConfig, ThreadPoolConfig};
+use aptos_runtimes::thread_pools::{set_thread_pool_spec_once, ThreadPoolKind, ThreadPoolSpec};
 use aptos_state_view::account_with_state_view::AsAccountWithStateView;
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
 use aptos_types::{
@@ -50,9 +51,11 @@ pub fn fetch_chain_id(db: &DbReaderWriter) -> anyhow::Result<ChainId> {
 pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
     AptosVM::set_paranoid_type_checks(node_config.execution.paranoid_type_verification);
     AptosVM::set_concurrency_level_once(node_config.execution.concurrency_level as usize);
-    AptosVM::set_num_proof_reading_threads_once(
-        node_config.execution.num_proof_reading_threads as usize,
+    AptosVM::set_delayed_delta_materialization_once(
+        node_config.execution.delayed_delta_materialization,
     );
+    AptosVM::set_gas_price_priority_hints_once(node_config.execution.gas_price_priority_hints);
+    AptosVM::set_shared_module_cache_once(node_config.execution.shared_module_cache);
 
     if node_config
         .execution
@@ -61,3 +64,28 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
         AptosVM::set_processed_transactions_detailed_counters();
     }
 }
+
+/// Configures the dedicated execution, proof reading and commit thread pools based on the
+/// node configurations. Must be called before the pools are used for the first time.
+pub fn set_thread_pool_configurations(node_config: &NodeConfig) {
+    let to_spec = |config: &ThreadPoolConfig| {
+        ThreadPoolSpec::new(
+            config.num_threads.map(|num_threads| num_threads as usize),
+            config.pinned_cores.clone(),
+        )
+    };
+
+    let execution_config = &node_config.execution;
+    set_thread_pool_spec_once(
+        ThreadPoolKind::Execution,
+        to_spec(&execution_config.execution_thread_pool),
+    );
+    set_thread_pool_spec_once(
+        ThreadPoolKind::ProofReading,
+        to_spec(&execution_config.proof_reading_thread_pool),
+    );
+    set_thread_pool_spec_once(
+        ThreadPoolKind::Commit,
+        to_spec(&execution_config.commit_thread_pool),
+    );
+}
@@ -58,6 +58,16 @@ pub struct ApiConfig {
     pub max_account_resources_page_size: u16,
     /// Maximum page size for module paginated APIs
     pub max_account_modules_page_size: u16,
+    /// Enables the event stream API, which pushes newly committed events to clients
+    #[serde(default = "default_disabled")]
+    pub event_stream_enabled: bool,
+    /// Maximum number of event streams open at the same time
+    pub max_event_streams: usize,
+    /// Interval in milliseconds at which event streams poll the DB for new transactions
+    pub event_stream_poll_interval_ms: u64,
+    /// Interval in milliseconds between the reads of an event stream catching up with the
+    /// committed transactions, so that streams starting far behind don't hog the DB
+    pub event_stream_catch_up_interval_ms: u64,
     /// Maximum gas unit limit for view functions
     ///
     /// This limits the execution length of a view function to the given gas used.
@@ -81,6 +91,9 @@ pub const DEFAULT_MAX_SUBMIT_TRANSACTION_BATCH_SIZE: usize = 10;
 pub const DEFAULT_MAX_PAGE_SIZE: u16 = 100;
 const DEFAULT_MAX_ACCOUNT_RESOURCES_PAGE_SIZE: u16 = 9999;
 const DEFAULT_MAX_ACCOUNT_MODULES_PAGE_SIZE: u16 = 9999;
+const DEFAULT_MAX_EVENT_STREAMS: usize = 64;
+const DEFAULT_EVENT_STREAM_POLL_INTERVAL_MS: u64 = 500;
+const DEFAULT_EVENT_STREAM_CATCH_UP_INTERVAL_MS: u64 = 10;
 const DEFAULT_MAX_VIEW_GAS: u64 = 2_000_000; // We keep this value the same as the max number of gas allowed for one single transaction defined in aptos-gas.
 
 fn default_enabled() -> bool {
@@ -112,6 +125,10 @@ impl Default for ApiConfig {
             max_events_page_size: DEFAULT_MAX_PAGE_SIZE,
             max_account_resources_page_size: DEFAULT_MAX_ACCOUNT_RESOURCES_PAGE_SIZE,
             max_account_modules_page_size: DEFAULT_MAX_ACCOUNT_MODULES_PAGE_SIZE,
+            event_stream_enabled: default_disabled(),
+            max_event_streams: DEFAULT_MAX_EVENT_STREAMS,
+            event_stream_poll_interval_ms: DEFAULT_EVENT_STREAM_POLL_INTERVAL_MS,
+            event_stream_catch_up_interval_ms: DEFAULT_EVENT_STREAM_CATCH_UP_INTERVAL_MS,
             max_gas_view_function: DEFAULT_MAX_VIEW_GAS,
             max_runtime_workers: None,
             runtime_worker_multiplier: 2,
@@ -26,14 +26,33 @@ pub struct ExecutionConfig {
     pub genesis_file_location: PathBuf,
     /// Number of threads to run execution
     pub concurrency_level: u16,
-    /// Number of threads to read proofs
-    pub num_proof_reading_threads: u16,
     /// Enables paranoid mode for types, which adds extra runtime VM checks
     pub paranoid_type_verification: bool,
     /// Enables paranoid mode for hot potatoes, which adds extra runtime VM checks
     pub paranoid_hot_potato_verification: bool,
     /// Enables enhanced metrics around processed transactions
     pub processed_transactions_detailed_counters: bool,
+    /// Materializes aggregator deltas only when the outputs of a block are assembled
+    pub delayed_delta_materialization: bool,
+    /// Prioritizes the transactions of a block by their gas unit price in parallel execution
+    pub gas_price_priority_hints: bool,
+    /// Shares the module cache between the workers of the parallel execution of a block
+    pub shared_module_cache: bool,
+    /// Configuration of the thread pool used for parallel execution
+    pub execution_thread_pool: ThreadPoolConfig,
+    /// Configuration of the thread pool used for reading proofs
+    pub proof_reading_thread_pool: ThreadPoolConfig,
+    /// Configuration of the thread pool used for committing to storage
+    pub commit_thread_pool: ThreadPoolConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThreadPoolConfig {
+    /// Number of threads in the pool, a pool specific default is used if not set
+    pub num_threads: Option<u16>,
+    /// CPU cores to pin the threads of the pool to (round-robin), no pinning if empty
+    pub pinned_cores: Vec<usize>,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -59,10 +78,15 @@ impl Default for ExecutionConfig {
             genesis_file_location: PathBuf::new(),
             // Parallel execution by default.
             concurrency_level: 8,
-            num_proof_reading_threads: 32,
             paranoid_type_verification: true,
             paranoid_hot_potato_verification: true,
             processed_transactions_detailed_counters: false,
+            delayed_delta_materialization: false,
+            gas_price_priority_hints: false,
+            shared_module_cache: false,
+            execution_thread_pool: ThreadPoolConfig::default(),
+            proof_reading_thread_pool: ThreadPoolConfig::default(),
+            commit_thread_pool: ThreadPoolConfig::default(),
         }
     }
 }
@@ -66,6 +66,7 @@ use aptos_network::{application::interface::NetworkClient, protocols::network::E
 use aptos_safety_rules::SafetyRulesManager;
 use aptos_types::{
     account_address::AccountAddress,
+    block_executor::hot_state_keys::{HotKeyConfig, HotStateKeyRegistry},
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
     on_chain_config::{
@@ -86,7 +87,7 @@ use futures::{
 use itertools::Itertools;
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     hash::Hash,
     mem::{discriminant, Discriminant},
     sync::Arc,
@@ -99,6 +100,9 @@ const PROPSER_ELECTION_CACHING_WINDOW_ADDITION: usize = 3;
 /// Number of rounds we expect storage to be ahead of the proposer round,
 /// used for fetching data from DB.
 const PROPSER_ROUND_BEHIND_STORAGE_BUFFER: usize = 10;
+/// Number of committed transactions before the start of an epoch whose writes determine the hot
+/// addresses used by the transaction shuffler during the epoch.
+const HOT_ADDRESSES_HISTORY_NUM_TXNS: u64 = 1_000;
 
 #[allow(clippy::large_enum_variant)]
 pub enum LivenessStorageData {
@@ -599,6 +603,40 @@ impl EpochManager {
         tokio::spawn(recovery_manager.start(recovery_manager_rx, close_rx));
     }
 
+    /// Returns the accounts with resources written by several of the last committed transactions
+    /// of the previous epoch, which the transaction shuffler spreads across the blocks of the
+    /// epoch. They are derived from the committed ledger rather than from the blocks this node
+    /// happened to execute, so that all nodes shuffle the blocks of the epoch the same way. This
+    /// relies on the node storing the transactions before the epoch, as for leader reputation.
+    fn hot_addresses_at_epoch_start(&self, epoch: u64) -> HashSet<AccountAddress> {
+        let aptos_db = self.storage.aptos_db();
+        let hot_addresses = aptos_db
+            .get_epoch_ending_ledger_infos(epoch.saturating_sub(1), epoch)
+            .and_then(|proof| {
+                let ledger_info = proof
+                    .ledger_info_with_sigs
+                    .first()
+                    .context("Missing epoch ending ledger info")?;
+                let end_version = ledger_info.ledger_info().version() + 1;
+                let start_version = end_version.saturating_sub(HOT_ADDRESSES_HISTORY_NUM_TXNS);
+                let hot_key_registry = HotStateKeyRegistry::new(HotKeyConfig::default());
+                hot_key_registry.record_block_writes(
+                    aptos_db
+                        .get_write_set_iterator(start_version, end_version - start_version)?
+                        .map_ok(|write_set| write_set.into_iter().map(|(key, _)| key))
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                );
+                Ok(hot_key_registry.hot_addresses())
+            });
+        hot_addresses.unwrap_or_else(|err| {
+            error!(
+                "Couldn't compute the hot addresses at the start of epoch {}, {:?}",
+                epoch, err
+            );
+            HashSet::new()
+        })
+    }
+
     async fn start_round_manager(
         &mut self,
         recovery_data: RecoveryData,
@@ -679,8 +717,10 @@ impl EpochManager {
         };
 
         let (payload_manager, quorum_store_msg_tx) = quorum_store_builder.init_payload_manager();
-        let transaction_shuffler =
-            create_transaction_shuffler(onchain_execution_config.transaction_shuffler_type());
+        let transaction_shuffler = create_transaction_shuffler(
+            onchain_execution_config.transaction_shuffler_type(),
+            self.hot_addresses_at_epoch_start(epoch),
+        );
         let block_gas_limit = onchain_execution_config.block_gas_limit();
         let transaction_deduper =
             create_transaction_deduper(onchain_execution_config.transaction_deduper_type());
@@ -4,9 +4,9 @@ use crate::{
     counters::{NUM_SENDERS_IN_BLOCK, TXN_SHUFFLE_SECONDS},
     transaction_shuffler::TransactionShuffler,
 };
-use aptos_types::transaction::SignedTransaction;
+use aptos_types::transaction::{SignedTransaction, TransactionPayload};
 use move_core_types::account_address::AccountAddress;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// An implementation of transaction shuffler, which tries to spread transactions from same senders
 /// in a block in order to reduce conflict. On a high level, it works as follows - It defines a
@@ -31,9 +31,16 @@ use std::collections::{HashMap, VecDeque};
 ///         else we add it to the block
 ///   else
 ///       take the first transaction from the pending transactions and add it to the block
-
+///
+/// If hot addresses are set, the transactions of the senders that touch them (either their own
+/// account, or the account of the module they call) are then spread evenly across the block, as
+/// they are likely to conflict with each other. This keeps the relative ordering of the
+/// transactions of each sender (invariant 1), but may reorder the transactions of unique senders
+/// (invariant 2). The hot addresses are fixed for the lifetime of the shuffler, so that the same
+/// block is always shuffled the same way.
 pub struct SenderAwareShuffler {
     conflict_window_size: usize,
+    hot_addresses: HashSet<AccountAddress>,
 }
 
 impl TransactionShuffler for SenderAwareShuffler {
@@ -80,7 +87,7 @@ impl TransactionShuffler for SenderAwareShuffler {
             let txn = next_to_add(&mut sliding_window);
             sliding_window.add_transaction(txn)
         }
-        sliding_window.finalize()
+        spread_hot_transactions(sliding_window.finalize(), &self.hot_addresses)
     }
 }
 
@@ -88,8 +95,61 @@ impl SenderAwareShuffler {
     pub fn new(conflict_window_size: usize) -> Self {
         Self {
             conflict_window_size,
+            hot_addresses: HashSet::new(),
         }
     }
+
+    pub fn with_hot_addresses(mut self, hot_addresses: HashSet<AccountAddress>) -> Self {
+        self.hot_addresses = hot_addresses;
+        self
+    }
+}
+
+/// Spreads the transactions of the senders that touch hot addresses evenly across the block,
+/// keeping the relative order of the hot transactions and of the other transactions.
+fn spread_hot_transactions(
+    txns: Vec<SignedTransaction>,
+    hot_addresses: &HashSet<AccountAddress>,
+) -> Vec<SignedTransaction> {
+    if hot_addresses.is_empty() {
+        return txns;
+    }
+    let touches_hot_address = |txn: &SignedTransaction| {
+        hot_addresses.contains(&txn.sender())
+            || matches!(txn.payload(), TransactionPayload::EntryFunction(entry_function)
+                if hot_addresses.contains(entry_function.module().address()))
+    };
+    // Senders are classified as a whole, so that their transactions are not reordered.
+    let hot_senders: HashSet<_> = txns
+        .iter()
+        .filter(|txn| touches_hot_address(txn))
+        .map(|txn| txn.sender())
+        .collect();
+    let (hot_txns, other_txns): (Vec<_>, Vec<_>) = txns
+        .into_iter()
+        .partition(|txn| hot_senders.contains(&txn.sender()));
+    if hot_txns.is_empty() || other_txns.is_empty() {
+        return hot_txns.into_iter().chain(other_txns).collect();
+    }
+
+    let num_txns = hot_txns.len() + other_txns.len();
+    let num_hot_txns = hot_txns.len();
+    let mut hot_txns = hot_txns.into_iter().peekable();
+    let mut other_txns = other_txns.into_iter().peekable();
+    let mut result = Vec::with_capacity(num_txns);
+    let mut num_hot_added = 0;
+    for idx in 0..num_txns {
+        // Adds the k-th hot transaction once k / num_hot_txns of the block is filled.
+        let hot_turn = (num_hot_added + 1) * num_txns <= (idx + 1) * num_hot_txns;
+        let txn = if (hot_turn || other_txns.peek().is_none()) && hot_txns.peek().is_some() {
+            num_hot_added += 1;
+            hot_txns.next()
+        } else {
+            other_txns.next()
+        };
+        result.push(txn.expect("Transaction expected"));
+    }
+    result
 }
 
 /// A structure to maintain a set of transactions that are pending to be added to the block indexed by
@@ -363,6 +423,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hot_senders_are_spread() {
+        let mut txns = Vec::new();
+        for _ in 0..10 {
+            txns.extend(create_signed_transaction(1));
+        }
+        // The accounts of the first 2 senders are hot.
+        let hot_addresses = txns.iter().take(2).map(|txn| txn.sender()).collect();
+
+        let txn_shuffler = SenderAwareShuffler::new(3).with_hot_addresses(hot_addresses);
+        let optimized_txns = txn_shuffler.shuffle(txns.clone());
+        let mut expected_txns = txns[2..].to_vec();
+        expected_txns.insert(4, txns[0].clone());
+        expected_txns.insert(9, txns[1].clone());
+        assert_eq!(optimized_txns, expected_txns);
+    }
+
     #[test]
     // S1_1, S2_1, S3_1, S3_2
     // with conflict_window_size=3, should return (keep the order, fairness to early transactions):
@@ -331,6 +331,7 @@ async fn test_commit_sync_race() {
         transaction_shuffler::create_transaction_shuffler,
     };
     use aptos_consensus_notifications::Error;
+    use aptos_executor_types::StateCheckpointPolicy;
     use aptos_types::{
         aggregate_signature::AggregateSignature,
         block_executor::partitioner::ExecutableBlock,
@@ -339,6 +340,7 @@ async fn test_commit_sync_race() {
         on_chain_config::{TransactionDeduperType, TransactionShufflerType},
         transaction::SignedTransaction,
     };
+    use std::collections::HashSet;
 
     struct RecordedCommit {
         time: Mutex<LogicalTime>,
@@ -353,11 +355,12 @@ async fn test_commit_sync_race() {
             Ok(())
         }
 
-        fn execute_block(
+        fn execute_block_ext(
             &self,
             _block: ExecutableBlock,
             _parent_block_id: HashValue,
             _maybe_block_gas_limit: Option<u64>,
+            _state_checkpoint_policy: StateCheckpointPolicy,
         ) -> Result<StateComputeResult, ExecutionError> {
             Ok(StateComputeResult::new_dummy())
         }
@@ -437,7 +440,7 @@ async fn test_commit_sync_race() {
     executor.new_epoch(
         &EpochState::empty(),
         Arc::new(PayloadManager::DirectMempool),
-        create_transaction_shuffler(TransactionShufflerType::NoShuffling),
+        create_transaction_shuffler(TransactionShufflerType::NoShuffling, HashSet::new()),
         None,
         create_transaction_deduper(TransactionDeduperType::NoDedup),
     );
@@ -10,7 +10,8 @@ use aptos_types::{
     },
     transaction::SignedTransaction,
 };
-use std::sync::Arc;
+use move_core_types::account_address::AccountAddress;
+use std::{collections::HashSet, sync::Arc};
 
 /// Interface to shuffle transactions
 pub trait TransactionShuffler: Send + Sync {
@@ -28,6 +29,7 @@ impl TransactionShuffler for NoOpShuffler {
 
 pub fn create_transaction_shuffler(
     shuffler_type: TransactionShufflerType,
+    hot_addresses: HashSet<AccountAddress>,
 ) -> Arc<dyn TransactionShuffler> {
     match shuffler_type {
         NoShuffling => {
@@ -40,10 +42,14 @@ pub fn create_transaction_shuffler(
         },
         SenderAwareV2(confict_window_size) => {
             info!(
-                "Using sender aware transaction shuffling with conflict window size {}",
-                confict_window_size
+                "Using sender aware transaction shuffling with conflict window size {} and {} hot addresses",
+                confict_window_size,
+                hot_addresses.len()
             );
-            Arc::new(SenderAwareShuffler::new(confict_window_size as usize))
+            Arc::new(
+                SenderAwareShuffler::new(confict_window_size as usize)
+                    .with_hot_addresses(hot_addresses),
+            )
         },
     }
 }
@@ -19,6 +19,7 @@ pub const RAW_BYTES: &str = "raw_bytes";
 pub enum CompressionClient {
     Consensus,
     Mempool,
+    ShardedExecution,
     StateSync,
 }
 
@@ -28,6 +29,7 @@ impl CompressionClient {
         match self {
             Self::Consensus => "consensus",
             Self::Mempool => "mempool",
+            Self::ShardedExecution => "sharded_execution",
             Self::StateSync => "state_sync",
         }
     }
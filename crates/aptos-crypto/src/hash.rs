@@ -547,6 +547,75 @@ impl DefaultHasher {
     }
 }
 
+/// Size of the buffer of [`StreamingHasher`].
+const STREAMING_HASHER_BUFFER_SIZE: usize = 4096;
+
+/// Feeds a [`CryptoHasher`] incrementally, through a fixed-size buffer.
+///
+/// Large values (e.g. multi-megabyte write sets) can be hashed by serializing them directly into
+/// the hasher, chunk by chunk, without materializing their serialization in memory. The buffer
+/// batches the many small writes of the serializer into few updates of the underlying hash
+/// state. The result does not depend on how the input is split into chunks.
+pub struct StreamingHasher<H> {
+    hasher: H,
+    buffer: [u8; STREAMING_HASHER_BUFFER_SIZE],
+    len: usize,
+}
+
+impl<H: CryptoHasher> Default for StreamingHasher<H> {
+    fn default() -> Self {
+        Self {
+            hasher: H::default(),
+            buffer: [0; STREAMING_HASHER_BUFFER_SIZE],
+            len: 0,
+        }
+    }
+}
+
+impl<H: CryptoHasher> StreamingHasher<H> {
+    /// Writes a chunk of bytes into the hasher.
+    pub fn update(&mut self, bytes: &[u8]) {
+        if self.len + bytes.len() > STREAMING_HASHER_BUFFER_SIZE {
+            self.flush_buffer();
+        }
+        if bytes.len() >= STREAMING_HASHER_BUFFER_SIZE {
+            self.hasher.update(bytes);
+        } else {
+            self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        }
+    }
+
+    /// Writes the BCS serialization of `value` into the hasher, without materializing it.
+    pub fn update_bcs<T: Serialize + ?Sized>(&mut self, value: &T) -> bcs::Result<()> {
+        bcs::serialize_into(self, value)
+    }
+
+    /// Finish constructing the [`HashValue`].
+    pub fn finish(mut self) -> HashValue {
+        self.flush_buffer();
+        self.hasher.finish()
+    }
+
+    fn flush_buffer(&mut self) {
+        if self.len > 0 {
+            self.hasher.update(&self.buffer[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+impl<H: CryptoHasher> std::io::Write for StreamingHasher<H> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.update(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 impl fmt::Debug for DefaultHasher {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "DefaultHasher: state = Sha3")
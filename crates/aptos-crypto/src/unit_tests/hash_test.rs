@@ -27,6 +27,14 @@ fn test_default_hasher() {
     );
 }
 
+#[test]
+fn test_streaming_hasher_bcs() {
+    let values: Vec<_> = (0..10_000).map(Foo).collect();
+    let mut hasher = StreamingHasher::<TestOnlyHasher>::default();
+    hasher.update_bcs(&values).unwrap();
+    assert_eq!(hasher.finish(), values.test_only_hash());
+}
+
 #[test]
 fn test_primitive_type() {
     let x = 0xF312_u16;
@@ -245,4 +253,22 @@ proptest! {
         let bits2 = vec![false; HashValue::LENGTH_IN_BITS + 10];
         prop_assert!(HashValue::from_bit_iter(bits2.into_iter()).is_err());
     }
+
+    #[test]
+    fn test_streaming_hasher_chunks(
+        bytes in vec(any::<u8>(), 0..20_000),
+        chunk_sizes in vec(1usize..6_000, 1..10),
+    ) {
+        let mut hasher = StreamingHasher::<TestOnlyHasher>::default();
+        let mut remaining = &bytes[..];
+        for chunk_size in chunk_sizes.iter().cycle() {
+            if remaining.is_empty() {
+                break;
+            }
+            let (chunk, rest) = remaining.split_at((*chunk_size).min(remaining.len()));
+            hasher.update(chunk);
+            remaining = rest;
+        }
+        prop_assert_eq!(hasher.finish(), TestOnlyHasher::hash_all(&bytes));
+    }
 }
@@ -159,6 +159,7 @@ impl LogEntry {
         };
 
         let mut data = BTreeMap::new();
+        crate::context::visit_current(&mut JsonVisitor(&mut data));
         for schema in event.keys_and_values() {
             schema.visit(&mut JsonVisitor(&mut data));
         }
@@ -0,0 +1,155 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A logging context set by a thread and attached to all the log lines it emits, so that e.g. the
+//! logs of the different shards of the sharded block executor can be told apart and filtered.
+//!
+//! ```
+//! use aptos_logger::{info, LogContext};
+//!
+//! // Attached to all the log lines of this thread from now on.
+//! LogContext::current().shard_id(1).set();
+//!
+//! {
+//!     // Attached until the guard is dropped.
+//!     let _guard = LogContext::current().round(0).enter();
+//!     info!("executing sub block");
+//!     // => {"data": {"round": 0, "shard_id": 1}, "message": "executing sub block", ...}
+//! }
+//! ```
+
+use crate::{Key, Schema, Value, Visitor};
+use std::{cell::RefCell, marker::PhantomData};
+
+thread_local! {
+    static CURRENT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LogContext {
+    shard_id: Option<usize>,
+    block_id: Option<String>,
+    round: Option<usize>,
+}
+
+impl LogContext {
+    /// Returns the context of the current thread, e.g. to extend it, or to set it on the threads
+    /// the current thread hands work to.
+    pub fn current() -> Self {
+        CURRENT
+            .try_with(|current| current.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    pub fn shard_id(mut self, shard_id: usize) -> Self {
+        self.shard_id = Some(shard_id);
+        self
+    }
+
+    pub fn block_id(mut self, block_id: impl ToString) -> Self {
+        self.block_id = Some(block_id.to_string());
+        self
+    }
+
+    pub fn round(mut self, round: usize) -> Self {
+        self.round = Some(round);
+        self
+    }
+
+    /// Sets the context of the current thread, for the rest of its lifetime.
+    pub fn set(self) {
+        CURRENT.with(|current| *current.borrow_mut() = self);
+    }
+
+    /// Sets the context of the current thread until the returned guard is dropped, at which point
+    /// the previous context is restored.
+    #[must_use]
+    pub fn enter(self) -> LogContextGuard {
+        LogContextGuard {
+            previous: CURRENT.with(|current| current.replace(self)),
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Schema for LogContext {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        if let Some(shard_id) = &self.shard_id {
+            visitor.visit_pair(Key::new("shard_id"), Value::from_serde(shard_id));
+        }
+        if let Some(block_id) = &self.block_id {
+            visitor.visit_pair(Key::new("block_id"), Value::from_serde(block_id));
+        }
+        if let Some(round) = &self.round {
+            visitor.visit_pair(Key::new("round"), Value::from_serde(round));
+        }
+    }
+}
+
+/// Visits the context of the current thread (if any), without cloning it.
+pub(crate) fn visit_current(visitor: &mut dyn Visitor) {
+    // The context is not available while the thread local storage is being torn down.
+    let _ = CURRENT.try_with(|current| current.borrow().visit(visitor));
+}
+
+/// Restores the previous context of the thread when dropped. It must be dropped on the thread it
+/// was created on.
+pub struct LogContextGuard {
+    previous: LogContext,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for LogContextGuard {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        let _ = CURRENT.try_with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    struct CollectingVisitor(BTreeMap<Key, String>);
+
+    impl Visitor for CollectingVisitor {
+        fn visit_pair(&mut self, key: Key, value: Value<'_>) {
+            let value = match value {
+                Value::Serde(value) => serde_json::to_string(value).unwrap(),
+                _ => unreachable!("The context only holds serde values"),
+            };
+            self.0.insert(key, value);
+        }
+    }
+
+    fn current_fields() -> BTreeMap<Key, String> {
+        let mut visitor = CollectingVisitor(BTreeMap::new());
+        visit_current(&mut visitor);
+        visitor.0
+    }
+
+    #[test]
+    fn test_scoped_context() {
+        assert!(current_fields().is_empty());
+
+        LogContext::current().shard_id(3).set();
+        {
+            let _guard = LogContext::current().round(1).block_id("abc").enter();
+            assert_eq!(
+                current_fields(),
+                BTreeMap::from([
+                    (Key::new("block_id"), "\"abc\"".to_string()),
+                    (Key::new("round"), "1".to_string()),
+                    (Key::new("shard_id"), "3".to_string()),
+                ])
+            );
+        }
+        assert_eq!(LogContext::current(), LogContext::default().shard_id(3));
+
+        // The context is per thread.
+        std::thread::spawn(|| assert!(current_fields().is_empty()))
+            .join()
+            .unwrap();
+    }
+}
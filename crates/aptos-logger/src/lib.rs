@@ -145,6 +145,7 @@ pub mod prelude {
 }
 
 pub mod aptos_logger;
+mod context;
 mod event;
 mod filter;
 mod kv;
@@ -161,6 +162,7 @@ pub use crate::aptos_logger::{
     AptosData as Logger, AptosDataBuilder, LoggerFilterUpdater, Writer, CHANNEL_SIZE,
 };
 pub use aptos_log_derive::Schema;
+pub use context::{LogContext, LogContextGuard};
 pub use event::Event;
 pub use filter::{Filter, LevelFilter};
 pub use kv::{Key, KeyValue, Schema, Value, Visitor};
@@ -10,6 +10,13 @@ pub mod aptos {
         }
     }
     pub mod internal {
+        pub mod executor {
+            // @@protoc_insertion_point(attribute:aptos.internal.executor.v1)
+            pub mod v1 {
+                include!("aptos.internal.executor.v1.rs");
+                // @@protoc_insertion_point(aptos.internal.executor.v1)
+            }
+        }
         pub mod fullnode {
             // @@protoc_insertion_point(attribute:aptos.internal.fullnode.v1)
             pub mod v1 {
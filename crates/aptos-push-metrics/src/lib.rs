@@ -34,7 +34,8 @@ pub struct MetricsPusher {
 }
 
 impl MetricsPusher {
-    fn push(
+    /// Pushes the current values of all the registered metrics to the endpoint, once.
+    pub fn push(
         push_metrics_endpoint: &str,
         api_token: Option<&str>,
         push_metrics_extra_labels: &[String],
@@ -1722,9 +1722,8 @@ async fn parse_delegation_pool_resource_changes(
                         event
                     } else {
                         warn!(
-                            "Failed to parse withdraw undelegated event! Skipping for {}:{}",
-                            e.key().get_creator_address(),
-                            e.key().get_creation_number()
+                            "Failed to parse withdraw undelegated event! Skipping for {:?}",
+                            e.event_key()
                         );
                         continue;
                     };
@@ -1817,7 +1816,7 @@ fn filter_events<F: Fn(&EventKey, &ContractEvent) -> Option<T>, T>(
 ) -> Vec<T> {
     events
         .iter()
-        .filter(|event| event.key() == event_key)
+        .filter(|event| event.event_key() == Some(event_key))
         .sorted_by(|a, b| a.sequence_number().cmp(&b.sequence_number()))
         .filter_map(|event| parser(event_key, event))
         .collect()
@@ -0,0 +1,221 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A registry of dedicated rayon thread pools, one per kind of workload, so that co-located
+//! workloads (e.g. VM execution and proof reading) do not steal each other's cores
+//! unpredictably. Each pool is configured (at most once) before its first use, e.g. based on
+//! the node or the benchmark configuration, and is lazily created on first use.
+
+use once_cell::sync::{Lazy, OnceCell};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::{fs, io, sync::Arc};
+
+/// The kinds of workloads that are executed on dedicated thread pools.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ThreadPoolKind {
+    /// Parallel execution of blocks in the VM.
+    Execution,
+    /// Asynchronous reading of state proofs.
+    ProofReading,
+    /// Committing to storage.
+    Commit,
+}
+
+impl ThreadPoolKind {
+    pub const ALL: [ThreadPoolKind; 3] = [Self::Execution, Self::ProofReading, Self::Commit];
+
+    fn index(&self) -> usize {
+        match self {
+            Self::Execution => 0,
+            Self::ProofReading => 1,
+            Self::Commit => 2,
+        }
+    }
+
+    fn thread_name_prefix(&self) -> &'static str {
+        match self {
+            Self::Execution => "par_exec",
+            Self::ProofReading => "proof_reader",
+            Self::Commit => "commit",
+        }
+    }
+
+    fn default_num_threads(&self) -> usize {
+        match self {
+            Self::Execution => num_cpus::get(),
+            Self::ProofReading | Self::Commit => 32,
+        }
+    }
+}
+
+/// The configuration of a thread pool.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ThreadPoolSpec {
+    /// The number of threads in the pool, a pool specific default is used if not set.
+    pub num_threads: Option<usize>,
+    /// The CPU cores that the threads of the pool are pinned to (the i-th thread is pinned
+    /// to core pinned_cores[i % pinned_cores.len()]). The threads are not pinned if empty.
+    pub pinned_cores: Vec<usize>,
+}
+
+impl ThreadPoolSpec {
+    pub fn new(num_threads: Option<usize>, pinned_cores: Vec<usize>) -> Self {
+        Self {
+            num_threads,
+            pinned_cores,
+        }
+    }
+}
+
+struct ThreadPoolEntry {
+    spec: OnceCell<ThreadPoolSpec>,
+    pool: OnceCell<Arc<ThreadPool>>,
+}
+
+static THREAD_POOLS: Lazy<Vec<ThreadPoolEntry>> = Lazy::new(|| {
+    ThreadPoolKind::ALL
+        .iter()
+        .map(|_| ThreadPoolEntry {
+            spec: OnceCell::new(),
+            pool: OnceCell::new(),
+        })
+        .collect()
+});
+
+/// Sets the configuration of the thread pool of the given kind when invoked the first time.
+/// Returns false if the pool was already configured, or already created (in which case the
+/// configuration can no longer take effect).
+pub fn set_thread_pool_spec_once(kind: ThreadPoolKind, spec: ThreadPoolSpec) -> bool {
+    // Only the first call succeeds, due to OnceCell semantics. Creating the pool sets the
+    // (default) configuration if it was not set before.
+    THREAD_POOLS[kind.index()].spec.set(spec).is_ok()
+}
+
+/// Returns the configuration of the thread pool of the given kind, with the pool specific
+/// default number of threads filled in if not configured. Does not prevent configuring the pool
+/// later on.
+pub fn get_thread_pool_spec(kind: ThreadPoolKind) -> ThreadPoolSpec {
+    let mut spec = THREAD_POOLS[kind.index()]
+        .spec
+        .get()
+        .cloned()
+        .unwrap_or_default();
+    spec.num_threads = Some(
+        spec.num_threads
+            .unwrap_or_else(|| kind.default_num_threads()),
+    );
+    spec
+}
+
+/// Returns the CPU cores of the given NUMA node, e.g. for the threads of a pool to be pinned to
+/// the cores close to the memory they use. Only supported on Linux.
+pub fn numa_node_cores(node: usize) -> io::Result<Vec<usize>> {
+    let cpu_list = fs::read_to_string(format!("/sys/devices/system/node/node{}/cpulist", node))?;
+    parse_cpu_list(cpu_list.trim())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, cpu_list))
+}
+
+/// Parses a list of CPU cores in the format of the kernel, e.g. "0-3,8,10-11".
+fn parse_cpu_list(cpu_list: &str) -> Option<Vec<usize>> {
+    let mut cores = vec![];
+    for range in cpu_list.split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((first, last)) => cores.extend(first.parse::<usize>().ok()?..=last.parse().ok()?),
+            None => cores.push(range.parse().ok()?),
+        }
+    }
+    Some(cores)
+}
+
+/// Returns the thread pool of the given kind, creating it based on its configuration (or
+/// the default configuration if not configured) on the first call.
+pub fn get_thread_pool(kind: ThreadPoolKind) -> Arc<ThreadPool> {
+    let entry = &THREAD_POOLS[kind.index()];
+    entry
+        .pool
+        .get_or_init(|| {
+            let spec = entry.spec.get_or_init(ThreadPoolSpec::default).clone();
+            Arc::new(build_thread_pool(kind, spec))
+        })
+        .clone()
+}
+
+fn build_thread_pool(kind: ThreadPoolKind, spec: ThreadPoolSpec) -> ThreadPool {
+    let ThreadPoolSpec {
+        num_threads,
+        pinned_cores,
+    } = spec;
+
+    ThreadPoolBuilder::new()
+        .num_threads(num_threads.unwrap_or_else(|| kind.default_num_threads()))
+        .thread_name(move |index| format!("{}_{}", kind.thread_name_prefix(), index))
+        .start_handler(move |index| {
+            if !pinned_cores.is_empty() {
+                pin_current_thread(pinned_cores[index % pinned_cores.len()]);
+            }
+        })
+        .build()
+        .unwrap_or_else(|error| panic!("Failed to build {:?} thread pool: {:?}", kind, error))
+}
+
+/// Pins the current thread to the given CPU core. Pinning is best-effort (e.g. pinning to a core
+/// outside of the affinity mask of the process fails, and the thread then keeps running on any
+/// core), and is only supported on Linux (a no-op on other platforms).
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+fn pin_current_thread(core_id: usize) {
+    // SAFETY: cpu_set_t is a plain bitmask for which all-zeroes is a valid (empty) value,
+    // CPU_SET only sets a bit within its bounds, and sched_setaffinity only reads the set.
+    unsafe {
+        let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core_id, &mut cpu_set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_core_id: usize) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_pool_spec() {
+        assert!(set_thread_pool_spec_once(
+            ThreadPoolKind::Commit,
+            ThreadPoolSpec::new(Some(3), vec![0])
+        ));
+        // Only the first configuration takes effect.
+        assert!(!set_thread_pool_spec_once(
+            ThreadPoolKind::Commit,
+            ThreadPoolSpec::new(Some(5), vec![])
+        ));
+
+        assert_eq!(
+            get_thread_pool_spec(ThreadPoolKind::Commit),
+            ThreadPoolSpec::new(Some(3), vec![0])
+        );
+        let pool = get_thread_pool(ThreadPoolKind::Commit);
+        assert_eq!(pool.current_num_threads(), 3);
+        assert!(Arc::ptr_eq(&pool, &get_thread_pool(ThreadPoolKind::Commit)));
+
+        // Pools can not be configured after they are created.
+        let _ = get_thread_pool(ThreadPoolKind::ProofReading);
+        assert!(!set_thread_pool_spec_once(
+            ThreadPoolKind::ProofReading,
+            ThreadPoolSpec::default()
+        ));
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("0-x"), None);
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        types::{
+            CliCommand, CliError, CliTypedResult, MovePackageDir, ProfileOptions, RestOptions,
+        },
+        utils::read_from_file,
+    },
+    move_tool::IncludedArtifacts,
+};
+use aptos_api_types::MoveType;
+use aptos_framework::BuiltPackage;
+use aptos_vm::decoded_event::move_value_to_json;
+use async_trait::async_trait;
+use clap::Parser;
+use move_binary_format::CompiledModule;
+use move_bytecode_utils::{layout::TypeLayoutBuilder, module_cache::GetModule};
+use move_core_types::{
+    language_storage::{ModuleId, TypeTag},
+    value::{MoveTypeLayout, MoveValue},
+};
+use std::{cell::RefCell, collections::BTreeMap, path::PathBuf};
+
+/// Decode a BCS encoded Move value into JSON
+///
+/// The layout of the type is built from the modules of the local package given by
+/// `--package-dir` (and its dependencies) if any, and otherwise from the modules on chain. This
+/// is handy for debugging raw events, write sets or cross-shard message dumps.
+///
+/// The JSON uses the same encodings as the GraphQL scalars: integers wider than 32 bits and
+/// addresses are strings, `vector<u8>` is hex, and strings and options are flattened.
+///
+/// Example: aptos move decode --type 0x1::coin::DepositEvent --hex 0x0a00000000000000
+#[derive(Parser)]
+pub struct Decode {
+    /// Type of the value e.g. `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`
+    #[clap(long = "type")]
+    pub(crate) type_: MoveType,
+
+    /// Hex encoded BCS bytes of the value, with or without a `0x` prefix
+    #[clap(long, group = "value")]
+    pub(crate) hex: Option<String>,
+
+    /// Path to a file holding the BCS bytes of the value
+    #[clap(long, group = "value", value_parser)]
+    pub(crate) bcs_path: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<serde_json::Value> for Decode {
+    fn command_name(&self) -> &'static str {
+        "Decode"
+    }
+
+    async fn execute(self) -> CliTypedResult<serde_json::Value> {
+        let type_tag = TypeTag::try_from(self.type_.clone())
+            .map_err(|err| CliError::UnableToParse("type", err.to_string()))?;
+        let bytes = match (&self.hex, &self.bcs_path) {
+            (Some(hex), None) => hex::decode(hex.strip_prefix("0x").unwrap_or(hex))?,
+            (None, Some(path)) => read_from_file(path)?,
+            _ => {
+                return Err(CliError::CommandArgumentError(
+                    "Exactly one of --hex and --bcs-path must be given".to_string(),
+                ))
+            },
+        };
+
+        let layout = self.build_layout(&type_tag).await?;
+        let value = MoveValue::simple_deserialize(&bytes, &layout).map_err(|err| {
+            CliError::UnableToParse("value", format!("Not a valid {}: {}", type_tag, err))
+        })?;
+        Ok(move_value_to_json(&layout, value))
+    }
+}
+
+impl Decode {
+    /// Builds the layout of the type, fetching the modules it depends on from chain on demand.
+    async fn build_layout(&self, type_tag: &TypeTag) -> CliTypedResult<MoveTypeLayout> {
+        let mut modules = BTreeMap::new();
+        if self.move_options.package_dir.is_some() {
+            let build_options = IncludedArtifacts::None.build_options(
+                self.move_options.dev,
+                self.move_options.skip_fetch_latest_git_deps,
+                self.move_options.named_addresses(),
+                self.move_options.bytecode_version,
+            );
+            let package = BuiltPackage::build(self.move_options.get_package_path()?, build_options)
+                .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+            for module in package.all_modules() {
+                modules.insert(module.self_id(), module.clone());
+            }
+        }
+
+        let client = self.rest_options.client(&self.profile_options)?;
+        loop {
+            let module_id = {
+                let resolver = ModuleResolver::new(&modules);
+                let result = TypeLayoutBuilder::build_with_types(type_tag, &resolver);
+                match resolver.missing.into_inner() {
+                    Some(module_id) => module_id,
+                    None => {
+                        return result.map_err(|err| {
+                            CliError::UnexpectedError(format!(
+                                "Failed to build the layout of {}: {:#}",
+                                type_tag, err
+                            ))
+                        })
+                    },
+                }
+            };
+            let bytes = client
+                .get_account_module_bcs(*module_id.address(), module_id.name().as_str())
+                .await?
+                .into_inner();
+            let module = CompiledModule::deserialize(&bytes).map_err(|err| {
+                CliError::UnexpectedError(format!(
+                    "Failed to deserialize module {}: {}",
+                    module_id, err
+                ))
+            })?;
+            modules.insert(module_id, module);
+        }
+    }
+}
+
+/// Resolves modules from the modules known so far, and records the first module that was
+/// looked up but is missing, so that it can be fetched before building the layout again.
+struct ModuleResolver<'a> {
+    modules: &'a BTreeMap<ModuleId, CompiledModule>,
+    missing: RefCell<Option<ModuleId>>,
+}
+
+impl<'a> ModuleResolver<'a> {
+    fn new(modules: &'a BTreeMap<ModuleId, CompiledModule>) -> Self {
+        Self {
+            modules,
+            missing: RefCell::new(None),
+        }
+    }
+}
+
+impl<'a> GetModule for ModuleResolver<'a> {
+    type Error = anyhow::Error;
+    type Item = &'a CompiledModule;
+
+    fn get_module_by_id(&self, id: &ModuleId) -> anyhow::Result<Option<&'a CompiledModule>> {
+        let module = self.modules.get(id);
+        if module.is_none() {
+            self.missing.borrow_mut().get_or_insert_with(|| id.clone());
+        }
+        Ok(module)
+    }
+}
@@ -3,6 +3,7 @@
 
 mod aptos_debug_natives;
 pub mod coverage;
+mod decode;
 mod disassembler;
 mod manifest;
 pub mod package_hooks;
@@ -85,6 +86,7 @@ pub enum MoveTool {
     #[clap(subcommand)]
     Coverage(coverage::CoveragePackage),
     CreateResourceAccountAndPublishPackage(CreateResourceAccountAndPublishPackage),
+    Decode(decode::Decode),
     Disassemble(Disassemble),
     Document(DocumentPackage),
     Download(DownloadPackage),
@@ -113,6 +115,7 @@ impl MoveTool {
             MoveTool::CreateResourceAccountAndPublishPackage(tool) => {
                 tool.execute_serialized_success().await
             },
+            MoveTool::Decode(tool) => tool.execute_serialized().await,
             MoveTool::Disassemble(tool) => tool.execute_serialized().await,
             MoveTool::Document(tool) => tool.execute_serialized().await,
             MoveTool::Download(tool) => tool.execute_serialized().await,
@@ -8,7 +8,7 @@ use aptos_sdk::{
     transaction_builder::TransactionFactory,
     types::{transaction::SignedTransaction, LocalAccount},
 };
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, Rng};
 use std::{sync::Arc, time::Duration};
 
 pub struct AccountGenerator {
@@ -149,7 +149,7 @@ impl AccountGeneratorCreator {
 impl TransactionGeneratorCreator for AccountGeneratorCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(AccountGenerator::new(
-            StdRng::from_entropy(),
+            crate::new_rng(),
             self.txn_factory.clone(),
             self.addresses_pool.clone(),
             self.accounts_pool.clone(),
@@ -1,9 +1,14 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{EntryPoints, TransactionType};
+use crate::{
+    entry_functions::{EntryFunctionCall, EntryFunctionWorkload},
+    EntryPoints, TransactionType,
+};
+use anyhow::{bail, format_err, Result};
 use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
 
 /// Utility class for specifying transaction type with predefined configurations through CLI
 #[derive(Debug, Copy, Clone, ValueEnum, Default, Deserialize, Parser, Serialize)]
@@ -30,6 +35,9 @@ pub enum TransactionTypeArg {
     TokenV1FTMintAndStore,
     TokenV1FTMintAndTransfer,
     TokenV2AmbassadorMint,
+    EventEmittingEntryFunctions,
+    CoinRegister,
+    EmitEvents100,
 }
 
 impl TransactionTypeArg {
@@ -157,6 +165,26 @@ impl TransactionTypeArg {
                 num_modules: module_working_set_size,
                 use_account_pool: sender_use_account_pool,
             },
+            TransactionTypeArg::EventEmittingEntryFunctions => TransactionType::EntryFunctions {
+                workload: Arc::new(EntryFunctionWorkload::event_emitting()),
+                use_account_pool: sender_use_account_pool,
+            },
+            TransactionTypeArg::CoinRegister => TransactionType::EntryFunctions {
+                workload: Arc::new(EntryFunctionWorkload {
+                    calls: vec![EntryFunctionCall {
+                        function: "0x1::managed_coin::register".to_string(),
+                        type_args: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+                        args: vec![],
+                        weight: 1,
+                    }],
+                }),
+                use_account_pool: sender_use_account_pool,
+            },
+            TransactionTypeArg::EmitEvents100 => TransactionType::CallCustomModules {
+                entry_point: EntryPoints::EmitEvents { count: 100 },
+                num_modules: module_working_set_size,
+                use_account_pool: sender_use_account_pool,
+            },
         }
     }
 
@@ -216,3 +244,58 @@ impl TransactionTypeArg {
         transaction_mix_per_phase
     }
 }
+
+/// A transaction type and the percentage of the transactions of a workload it makes up,
+/// specified on the command line as `<transaction-type>=<percentage>`, e.g. `coin-transfer=80`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkloadShareArg {
+    pub transaction_type: TransactionTypeArg,
+    pub percentage: usize,
+}
+
+impl FromStr for WorkloadShareArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (transaction_type, percentage) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("Expected <transaction-type>=<percentage>, got {}", s))?;
+        let transaction_type = TransactionTypeArg::from_str(transaction_type.trim(), true)
+            .map_err(|err| format_err!("Invalid transaction type: {}", err))?;
+        let percentage = percentage
+            .trim()
+            .parse()
+            .map_err(|err| format_err!("Invalid percentage {}: {}", percentage, err))?;
+        Ok(Self {
+            transaction_type,
+            percentage,
+        })
+    }
+}
+
+impl WorkloadShareArg {
+    /// Converts the shares of a workload into a transaction mix, checking that their percentages
+    /// add up to 100.
+    pub fn shares_to_transaction_mix(
+        shares: &[WorkloadShareArg],
+        module_working_set_size: usize,
+        sender_use_account_pool: bool,
+    ) -> Result<Vec<(TransactionType, usize)>> {
+        let total: usize = shares.iter().map(|share| share.percentage).sum();
+        if total != 100 {
+            bail!("Workload percentages add up to {}, instead of 100", total);
+        }
+        Ok(shares
+            .iter()
+            .filter(|share| share.percentage > 0)
+            .map(|share| {
+                (
+                    share
+                        .transaction_type
+                        .materialize(module_working_set_size, sender_use_account_pool),
+                    share.percentage,
+                )
+            })
+            .collect())
+    }
+}
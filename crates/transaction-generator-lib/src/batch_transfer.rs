@@ -7,7 +7,7 @@ use aptos_sdk::{
     transaction_builder::{aptos_stdlib, TransactionFactory},
     types::{transaction::SignedTransaction, LocalAccount},
 };
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom};
 use std::sync::Arc;
 
 pub struct BatchTransferTransactionGenerator {
@@ -90,7 +90,7 @@ impl BatchTransferTransactionGeneratorCreator {
 impl TransactionGeneratorCreator for BatchTransferTransactionGeneratorCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(BatchTransferTransactionGenerator::new(
-            StdRng::from_entropy(),
+            crate::new_rng(),
             self.batch_size,
             self.amount,
             self.txn_factory.clone(),
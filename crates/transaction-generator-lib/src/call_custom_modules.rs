@@ -12,7 +12,7 @@ use aptos_sdk::{
     types::{transaction::SignedTransaction, LocalAccount},
 };
 use async_trait::async_trait;
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom};
 use std::sync::Arc;
 
 // Fn + Send + Sync, as it will be called from multiple threads simultaneously
@@ -118,7 +118,7 @@ impl CustomModulesDelegationGeneratorCreator {
         package_name: &str,
         workload: &mut dyn UserModuleTransactionGenerator,
     ) -> Self {
-        let mut rng = StdRng::from_entropy();
+        let mut rng = crate::new_rng();
         assert!(accounts.len() >= num_modules);
         let mut requests_create = Vec::with_capacity(accounts.len());
         let mut requests_publish = Vec::with_capacity(accounts.len());
@@ -187,7 +187,7 @@ impl CustomModulesDelegationGeneratorCreator {
 impl TransactionGeneratorCreator for CustomModulesDelegationGeneratorCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(CustomModulesDelegationGenerator::new(
-            StdRng::from_entropy(),
+            crate::new_rng(),
             self.txn_factory.clone(),
             self.packages.clone(),
             self.txn_generator.clone(),
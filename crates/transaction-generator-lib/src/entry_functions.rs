@@ -0,0 +1,274 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Workloads of calls to arbitrary entry functions, defined in code or loaded from a config file,
+//! so that executor-benchmark and forge load tests can share the same workload definitions.
+//!
+//! A workload config is a YAML file listing the calls to make, e.g.:
+//! ```yaml
+//! calls:
+//!   - function: "0x1::aptos_account::transfer"
+//!     args: [random_account, {u64: 1}]
+//!     weight: 2
+//!   - function: "0x1::coin::transfer"
+//!     type_args: ["0x1::aptos_coin::AptosCoin"]
+//!     args: [sender, {bcs: "0x0100000000000000"}]
+//! ```
+
+use crate::{TransactionGenerator, TransactionGeneratorCreator};
+use anyhow::{format_err, Context, Result};
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    bcs,
+    move_types::{
+        account_address::AccountAddress,
+        identifier::Identifier,
+        language_storage::{ModuleId, TypeTag},
+        parser::parse_type_tag,
+    },
+    transaction_builder::TransactionFactory,
+    types::{
+        transaction::{EntryFunction, SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, str::FromStr, sync::Arc};
+
+/// A set of entry function calls, one of which is picked (by weight) for each transaction.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct EntryFunctionWorkload {
+    pub calls: Vec<EntryFunctionCall>,
+}
+
+/// A call to an entry function, as defined in a workload config.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EntryFunctionCall {
+    /// Fully qualified name of the function, e.g. `0x1::aptos_account::transfer`.
+    pub function: String,
+    #[serde(default)]
+    pub type_args: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<EntryFunctionArg>,
+    /// Relative weight of the call within the workload.
+    #[serde(default = "default_weight")]
+    pub weight: usize,
+}
+
+fn default_weight() -> usize {
+    1
+}
+
+/// An argument of an entry function call.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryFunctionArg {
+    /// Hex encoded BCS bytes of the argument.
+    Bcs(String),
+    Bool(bool),
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Address(AccountAddress),
+    String(String),
+    /// Address of the sender of the transaction.
+    Sender,
+    /// Address of an account picked at random among the existing accounts.
+    RandomAccount,
+}
+
+impl EntryFunctionWorkload {
+    /// Loads a workload from a YAML config file.
+    pub fn load_config(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read workload config {}", path.display()))?;
+        let workload: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse workload config {}", path.display()))?;
+        // Fail early rather than when the first transactions are generated.
+        workload.resolve()?;
+        Ok(workload)
+    }
+
+    /// Calls to framework entry functions that emit events: coin transfers, which emit withdraw
+    /// and deposit events.
+    pub fn event_emitting() -> Self {
+        Self {
+            calls: vec![
+                EntryFunctionCall {
+                    function: "0x1::aptos_account::transfer".to_string(),
+                    type_args: vec![],
+                    args: vec![EntryFunctionArg::RandomAccount, EntryFunctionArg::U64(1)],
+                    weight: 1,
+                },
+                EntryFunctionCall {
+                    function: "0x1::coin::transfer".to_string(),
+                    type_args: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+                    args: vec![EntryFunctionArg::RandomAccount, EntryFunctionArg::U64(1)],
+                    weight: 1,
+                },
+            ],
+        }
+    }
+
+    fn resolve(&self) -> Result<Vec<ResolvedCall>> {
+        if self.calls.iter().all(|call| call.weight == 0) {
+            return Err(format_err!("Workload has no call with a positive weight"));
+        }
+        self.calls.iter().map(ResolvedCall::new).collect()
+    }
+}
+
+/// An entry function call with its function id, type arguments and static arguments parsed.
+struct ResolvedCall {
+    module: ModuleId,
+    function: Identifier,
+    type_args: Vec<TypeTag>,
+    args: Vec<ResolvedArg>,
+    weight: usize,
+}
+
+enum ResolvedArg {
+    Bytes(Vec<u8>),
+    Sender,
+    RandomAccount,
+}
+
+impl ResolvedCall {
+    fn new(call: &EntryFunctionCall) -> Result<Self> {
+        let invalid_function = || format_err!("Invalid function id {}", call.function);
+        let (module, function) = call
+            .function
+            .rsplit_once("::")
+            .ok_or_else(invalid_function)?;
+        let (address, module) = module.split_once("::").ok_or_else(invalid_function)?;
+        let module = ModuleId::new(AccountAddress::from_str(address)?, Identifier::new(module)?);
+        let type_args = call
+            .type_args
+            .iter()
+            .map(|type_arg| parse_type_tag(type_arg))
+            .collect::<Result<_>>()?;
+        let args = call
+            .args
+            .iter()
+            .map(|arg| {
+                Ok(match arg {
+                    EntryFunctionArg::Bcs(bytes) => {
+                        ResolvedArg::Bytes(hex::decode(bytes.trim_start_matches("0x"))?)
+                    },
+                    EntryFunctionArg::Bool(value) => ResolvedArg::Bytes(bcs::to_bytes(value)?),
+                    EntryFunctionArg::U8(value) => ResolvedArg::Bytes(bcs::to_bytes(value)?),
+                    EntryFunctionArg::U64(value) => ResolvedArg::Bytes(bcs::to_bytes(value)?),
+                    EntryFunctionArg::U128(value) => ResolvedArg::Bytes(bcs::to_bytes(value)?),
+                    EntryFunctionArg::Address(value) => ResolvedArg::Bytes(bcs::to_bytes(value)?),
+                    EntryFunctionArg::String(value) => ResolvedArg::Bytes(bcs::to_bytes(value)?),
+                    EntryFunctionArg::Sender => ResolvedArg::Sender,
+                    EntryFunctionArg::RandomAccount => ResolvedArg::RandomAccount,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            module,
+            function: Identifier::new(function)?,
+            type_args,
+            args,
+            weight: call.weight,
+        })
+    }
+}
+
+pub struct EntryFunctionTransactionGenerator {
+    rng: StdRng,
+    calls: Arc<Vec<ResolvedCall>>,
+    total_weight: usize,
+    txn_factory: TransactionFactory,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+}
+
+impl EntryFunctionTransactionGenerator {
+    /// Picks the index of a call at random, by weight.
+    fn pick_call(&mut self) -> usize {
+        let mut picked = self.rng.gen_range(0, self.total_weight);
+        for (index, call) in self.calls.iter().enumerate() {
+            if picked < call.weight {
+                return index;
+            }
+            picked -= call.weight;
+        }
+        unreachable!("Picked {} out of {}", picked, self.total_weight);
+    }
+
+    fn create_payload(&mut self, sender: AccountAddress) -> TransactionPayload {
+        let calls = self.calls.clone();
+        let call = &calls[self.pick_call()];
+        let args = call
+            .args
+            .iter()
+            .map(|arg| match arg {
+                ResolvedArg::Bytes(bytes) => bytes.clone(),
+                ResolvedArg::Sender => bcs::to_bytes(&sender).unwrap(),
+                ResolvedArg::RandomAccount => {
+                    let address = *self
+                        .all_addresses
+                        .read()
+                        .choose(&mut self.rng)
+                        .expect("Addresses pool must not be empty");
+                    bcs::to_bytes(&address).unwrap()
+                },
+            })
+            .collect();
+        TransactionPayload::EntryFunction(EntryFunction::new(
+            call.module.clone(),
+            call.function.clone(),
+            call.type_args.clone(),
+            args,
+        ))
+    }
+}
+
+impl TransactionGenerator for EntryFunctionTransactionGenerator {
+    fn generate_transactions(
+        &mut self,
+        account: &mut LocalAccount,
+        num_to_create: usize,
+    ) -> Vec<SignedTransaction> {
+        let mut requests = Vec::with_capacity(num_to_create);
+        for _ in 0..num_to_create {
+            let payload = self.create_payload(account.address());
+            requests.push(account.sign_with_transaction_builder(self.txn_factory.payload(payload)));
+        }
+        requests
+    }
+}
+
+pub struct EntryFunctionTransactionGeneratorCreator {
+    calls: Arc<Vec<ResolvedCall>>,
+    txn_factory: TransactionFactory,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+}
+
+impl EntryFunctionTransactionGeneratorCreator {
+    pub fn new(
+        workload: &EntryFunctionWorkload,
+        txn_factory: TransactionFactory,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    ) -> Result<Self> {
+        Ok(Self {
+            calls: Arc::new(workload.resolve()?),
+            txn_factory,
+            all_addresses,
+        })
+    }
+}
+
+impl TransactionGeneratorCreator for EntryFunctionTransactionGeneratorCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(EntryFunctionTransactionGenerator {
+            rng: crate::new_rng(),
+            calls: self.calls.clone(),
+            total_weight: self.calls.iter().map(|call| call.weight).sum(),
+            txn_factory: self.txn_factory.clone(),
+            all_addresses: self.all_addresses.clone(),
+        })
+    }
+}
@@ -13,10 +13,12 @@ use aptos_sdk::{
 };
 use args::TransactionTypeArg;
 use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use rand::{rngs::StdRng, SeedableRng};
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
@@ -27,6 +29,7 @@ mod accounts_pool_wrapper;
 pub mod args;
 mod batch_transfer;
 mod call_custom_modules;
+pub mod entry_functions;
 mod entry_points;
 mod p2p_transaction_generator;
 pub mod publish_modules;
@@ -42,13 +45,34 @@ use self::{
 use crate::{
     accounts_pool_wrapper::AccountsPoolWrapperCreator,
     batch_transfer::BatchTransferTransactionGeneratorCreator,
+    entry_functions::{EntryFunctionTransactionGeneratorCreator, EntryFunctionWorkload},
     entry_points::EntryPointTransactionGenerator, p2p_transaction_generator::SamplingMode,
 };
 pub use publishing::module_simple::EntryPoints;
 
 pub const SEND_AMOUNT: u64 = 1;
 
-#[derive(Debug, Copy, Clone)]
+static RNG_SEED: OnceCell<u64> = OnceCell::new();
+static NUM_SEEDED_RNGS: AtomicU64 = AtomicU64::new(0);
+
+/// Seeds the RNGs of the generators created afterwards, so that a run creating its generators
+/// in the same order generates the same transactions. Only the first call has an effect.
+pub fn set_rng_seed_once(seed: u64) {
+    let _ = RNG_SEED.set(seed);
+}
+
+/// Returns a new RNG, derived from the seed set with [`set_rng_seed_once`] and the number of
+/// RNGs created before it, or seeded from entropy if no seed was set.
+pub fn new_rng() -> StdRng {
+    match RNG_SEED.get() {
+        Some(seed) => StdRng::seed_from_u64(
+            seed.wrapping_add(NUM_SEEDED_RNGS.fetch_add(1, Ordering::Relaxed)),
+        ),
+        None => StdRng::from_entropy(),
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum TransactionType {
     NonConflictingCoinTransfer {
         invalid_transaction_ratio: usize,
@@ -74,6 +98,10 @@ pub enum TransactionType {
     BatchTransfer {
         batch_size: usize,
     },
+    EntryFunctions {
+        workload: Arc<EntryFunctionWorkload>,
+        use_account_pool: bool,
+    },
 }
 
 impl Default for TransactionType {
@@ -290,6 +318,21 @@ pub async fn create_txn_generator_creator(
                         *batch_size,
                     ))
                 },
+                TransactionType::EntryFunctions {
+                    workload,
+                    use_account_pool,
+                } => wrap_accounts_pool(
+                    Box::new(
+                        EntryFunctionTransactionGeneratorCreator::new(
+                            workload,
+                            txn_factory.clone(),
+                            addresses_pool.clone(),
+                        )
+                        .expect("Invalid entry function workload"),
+                    ),
+                    *use_account_pool,
+                    accounts_pool.clone(),
+                ),
             };
             txn_generator_creator_mix.push((txn_generator_creator, *weight));
         }
@@ -11,7 +11,7 @@ use rand::{
     distributions::{Distribution, Standard},
     prelude::SliceRandom,
     rngs::StdRng,
-    Rng, RngCore, SeedableRng,
+    Rng, RngCore,
 };
 use std::{
     cmp::{max, min},
@@ -120,7 +120,7 @@ impl<T: Clone + Send + Sync> Sampler<T> for BurnAndRecycleSampler<T> {
 #[test]
 fn test_burn_and_recycle_sampler() {
     use std::collections::HashSet;
-    let mut rng = StdRng::from_entropy();
+    let mut rng = crate::new_rng();
     let mut sampler = BurnAndRecycleSampler::new(3);
     let mut pool: Vec<u8> = (0..8).collect();
     let samples = (0..16)
@@ -322,7 +322,7 @@ impl P2PTransactionGeneratorCreator {
 
 impl TransactionGeneratorCreator for P2PTransactionGeneratorCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
-        let rng = StdRng::from_entropy();
+        let rng = crate::new_rng();
         let sampler: Box<dyn Sampler<AccountAddress>> = match self.sampling_mode {
             SamplingMode::Basic => Box::new(BasicSampler::new()),
             SamplingMode::BurnAndRecycle(recycle_batch_size) => {
@@ -8,7 +8,7 @@ use aptos_sdk::{
     transaction_builder::TransactionFactory,
     types::{transaction::SignedTransaction, LocalAccount},
 };
-use rand::{rngs::StdRng, SeedableRng};
+use rand::rngs::StdRng;
 use std::sync::Arc;
 
 pub struct PublishPackageGenerator {
@@ -80,7 +80,7 @@ impl PublishPackageCreator {
 impl TransactionGeneratorCreator for PublishPackageCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(PublishPackageGenerator::new(
-            StdRng::from_entropy(),
+            crate::new_rng(),
             self.package_handler.clone(),
             self.txn_factory.clone(),
         ))
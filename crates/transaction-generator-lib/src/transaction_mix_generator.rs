@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{TransactionGenerator, TransactionGeneratorCreator};
 use aptos_sdk::types::{transaction::SignedTransaction, LocalAccount};
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, Rng};
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
@@ -91,7 +91,7 @@ impl TransactionGeneratorCreator for PhasedTxnMixGeneratorCreator {
         }
 
         Box::new(PhasedTxnMixGenerator::new(
-            StdRng::from_entropy(),
+            crate::new_rng(),
             txn_mix_per_phase,
             self.phase.clone(),
         ))
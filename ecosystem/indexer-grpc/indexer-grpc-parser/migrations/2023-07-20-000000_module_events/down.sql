@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS me_struct_tag_index;
+DROP INDEX IF EXISTS me_type_index;
+DROP INDEX IF EXISTS me_insat_index;
+DROP TABLE IF EXISTS module_events;
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+-- Module events are not emitted to an event handle, so unlike events they are
+-- keyed by their position in the transaction and looked up by their struct tag.
+CREATE TABLE IF NOT EXISTS module_events (
+  transaction_version BIGINT NOT NULL,
+  event_index BIGINT NOT NULL,
+  transaction_block_height BIGINT NOT NULL,
+  type_address VARCHAR(66) NOT NULL,
+  type_module VARCHAR(255) NOT NULL,
+  type_name VARCHAR(255) NOT NULL,
+  type TEXT NOT NULL,
+  data jsonb NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (transaction_version, event_index)
+);
+CREATE INDEX IF NOT EXISTS me_struct_tag_index ON module_events (
+  type_address,
+  type_module,
+  type_name,
+  transaction_version
+);
+CREATE INDEX IF NOT EXISTS me_type_index ON module_events (type);
+CREATE INDEX IF NOT EXISTS me_insat_index ON module_events (inserted_at);
@@ -3,7 +3,10 @@
 
 #![allow(clippy::extra_unused_lifetimes)]
 use super::transactions::{Transaction, TransactionQuery};
-use crate::{schema::events, utils::util::standardize_address};
+use crate::{
+    models::module_event_models::module_events::is_module_event, schema::events,
+    utils::util::standardize_address,
+};
 use aptos_protos::transaction::v1::Event as EventPB;
 use field_count::FieldCount;
 use serde::{Deserialize, Serialize};
@@ -66,9 +69,12 @@ impl Event {
         transaction_version: i64,
         transaction_block_height: i64,
     ) -> Vec<Self> {
+        // Module events all share the same (zero) event key and sequence number, so they are
+        // indexed separately by the module event processor.
         events
             .iter()
             .enumerate()
+            .filter(|(_, event)| !is_module_event(event))
             .map(|(index, event)| {
                 Self::from_event(
                     event,
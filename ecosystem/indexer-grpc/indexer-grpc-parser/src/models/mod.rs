@@ -4,6 +4,7 @@
 pub mod coin_models;
 pub mod default_models;
 pub mod ledger_info;
+pub mod module_event_models;
 pub mod processor_status;
 pub mod property_map;
 pub mod stake_models;
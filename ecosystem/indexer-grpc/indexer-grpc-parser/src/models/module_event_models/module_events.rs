@@ -0,0 +1,94 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+use crate::{schema::module_events, utils::util::standardize_address};
+use aptos_protos::transaction::v1::{
+    move_type::Content, transaction::TxnData, Event as EventPB, Transaction as TransactionPB,
+};
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(transaction_version, event_index))]
+#[diesel(table_name = module_events)]
+pub struct ModuleEvent {
+    pub transaction_version: i64,
+    pub event_index: i64,
+    pub transaction_block_height: i64,
+    pub type_address: String,
+    pub type_module: String,
+    pub type_name: String,
+    pub type_: String,
+    pub data: serde_json::Value,
+}
+
+impl ModuleEvent {
+    pub fn from_event(
+        event: &EventPB,
+        transaction_version: i64,
+        transaction_block_height: i64,
+        event_index: i64,
+    ) -> Option<Self> {
+        if !is_module_event(event) {
+            return None;
+        }
+        let struct_tag = match event.r#type.as_ref().and_then(|t| t.content.as_ref()) {
+            Some(Content::Struct(struct_tag)) => struct_tag,
+            _ => {
+                error!(
+                    transaction_version = transaction_version,
+                    event_index = event_index,
+                    "Module event type is not a struct"
+                );
+                return None;
+            },
+        };
+        Some(Self {
+            transaction_version,
+            event_index,
+            transaction_block_height,
+            type_address: standardize_address(&struct_tag.address),
+            type_module: struct_tag.module.clone(),
+            type_name: struct_tag.name.clone(),
+            type_: event.type_str.clone(),
+            data: serde_json::from_str(event.data.as_str()).unwrap(),
+        })
+    }
+
+    /// Extracts the module events of the transaction, with the payloads as decoded (based on
+    /// the type layouts) by the fullnode.
+    pub fn from_transaction(transaction: &TransactionPB) -> Vec<Self> {
+        let txn_version = transaction.version as i64;
+        let block_height = transaction.block_height as i64;
+        let events = match transaction
+            .txn_data
+            .as_ref()
+            .unwrap_or_else(|| panic!("Txn Data doesn't exit for version {}", txn_version))
+        {
+            TxnData::User(inner) => &inner.events,
+            TxnData::Genesis(inner) => &inner.events,
+            TxnData::BlockMetadata(inner) => &inner.events,
+            _ => return vec![],
+        };
+        events
+            .iter()
+            .enumerate()
+            .filter_map(|(index, event)| {
+                Self::from_event(event, txn_version, block_height, index as i64)
+            })
+            .collect()
+    }
+}
+
+/// Module events are not emitted to an event handle, so the fullnode exposes them with a zero
+/// event key and sequence number.
+pub fn is_module_event(event: &EventPB) -> bool {
+    event.sequence_number == 0
+        && event.key.as_ref().map_or(false, |key| {
+            key.creation_number == 0 && standardize_address(&key.account_address) == ZERO_ADDRESS
+        })
+}
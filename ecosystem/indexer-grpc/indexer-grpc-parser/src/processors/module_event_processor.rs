@@ -0,0 +1,133 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::processor_trait::{ProcessingResult, ProcessorTrait};
+use crate::{
+    models::module_event_models::module_events::ModuleEvent,
+    schema,
+    utils::database::{
+        clean_data_for_db, execute_with_better_error, get_chunks, PgDbPool, PgPoolConnection,
+    },
+};
+use anyhow::bail;
+use aptos_protos::transaction::v1::Transaction;
+use async_trait::async_trait;
+use diesel::{result::Error, PgConnection};
+use field_count::FieldCount;
+use std::fmt::Debug;
+use tracing::error;
+
+pub const NAME: &str = "module_event_processor";
+pub struct ModuleEventProcessor {
+    connection_pool: PgDbPool,
+}
+
+impl ModuleEventProcessor {
+    pub fn new(connection_pool: PgDbPool) -> Self {
+        Self { connection_pool }
+    }
+}
+
+impl Debug for ModuleEventProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "ModuleEventProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+fn insert_to_db(
+    conn: &mut PgPoolConnection,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    module_events: Vec<ModuleEvent>,
+) -> Result<(), diesel::result::Error> {
+    tracing::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+    match conn
+        .build_transaction()
+        .read_write()
+        .run::<_, Error, _>(|pg_conn| insert_module_events(pg_conn, &module_events))
+    {
+        Ok(_) => Ok(()),
+        Err(_) => conn
+            .build_transaction()
+            .read_write()
+            .run::<_, Error, _>(|pg_conn| {
+                let module_events = clean_data_for_db(module_events, true);
+                insert_module_events(pg_conn, &module_events)
+            }),
+    }
+}
+
+fn insert_module_events(
+    conn: &mut PgConnection,
+    items_to_insert: &[ModuleEvent],
+) -> Result<(), diesel::result::Error> {
+    use schema::module_events::dsl::*;
+    let chunks = get_chunks(items_to_insert.len(), ModuleEvent::field_count());
+    for (start_ind, end_ind) in chunks {
+        execute_with_better_error(
+            conn,
+            diesel::insert_into(schema::module_events::table)
+                .values(&items_to_insert[start_ind..end_ind])
+                .on_conflict((transaction_version, event_index))
+                .do_nothing(),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ProcessorTrait for ModuleEventProcessor {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+    ) -> anyhow::Result<ProcessingResult> {
+        let mut conn = self.get_conn();
+        let module_events = transactions
+            .iter()
+            .flat_map(ModuleEvent::from_transaction)
+            .collect();
+
+        let tx_result = insert_to_db(
+            &mut conn,
+            self.name(),
+            start_version,
+            end_version,
+            module_events,
+        );
+        match tx_result {
+            Ok(_) => Ok((start_version, end_version)),
+            Err(e) => {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            },
+        }
+    }
+
+    fn connection_pool(&self) -> &PgDbPool {
+        &self.connection_pool
+    }
+}
@@ -535,6 +535,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    module_events (transaction_version, event_index) {
+        transaction_version -> Int8,
+        event_index -> Int8,
+        transaction_block_height -> Int8,
+        #[max_length = 66]
+        type_address -> Varchar,
+        #[max_length = 255]
+        type_module -> Varchar,
+        #[max_length = 255]
+        type_name -> Varchar,
+        #[sql_name = "type"]
+        type_ -> Text,
+        data -> Jsonb,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     move_modules (transaction_version, write_set_change_index) {
         transaction_version -> Int8,
@@ -954,6 +972,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     events,
     indexer_status,
     ledger_infos,
+    module_events,
     move_modules,
     move_resources,
     nft_points,
@@ -0,0 +1,282 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A partitioner placing the transactions by the connected components of their conflicts.
+//!
+//! Two transactions conflict if one of them writes a storage location the other reads or writes
+//! (or if they have the same sender). The connected components of the conflicts are found with a
+//! union-find over the read/write sets, and assigned as a whole to the shards, the largest ones
+//! first to the least loaded shard. As the components never conflict with each other, the block is
+//! executed in a single round without cross shard dependencies (besides the ones of the published
+//! modules), at the cost of the balance of the shards when a component is large.
+
+use crate::{
+    sharded_block_partitioner::{
+        counters::{LARGEST_CONNECTED_COMPONENT_TXNS, NUM_CONNECTED_COMPONENTS},
+        ShardedBlockPartitioner,
+    },
+    BlockPartitioner,
+};
+use aptos_types::{
+    block_executor::partitioner::{
+        CrossShardDependencies, ExecutableTransactions, SubBlock, SubBlocksForShard,
+        TransactionWithDependencies,
+    },
+    transaction::{
+        analyzed_transaction::{AnalyzedTransaction, StorageLocation},
+        Transaction,
+    },
+};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, HashMap},
+};
+
+pub struct ConnectedComponentsPartitioner {
+    num_shards: usize,
+}
+
+impl ConnectedComponentsPartitioner {
+    pub fn new(num_shards: usize) -> Self {
+        assert!(num_shards > 0, "num_shards must be > 0");
+        Self { num_shards }
+    }
+
+    /// Partitions the transactions into a single round of sub blocks, one per shard.
+    pub fn partition(
+        &self,
+        transactions: Vec<AnalyzedTransaction>,
+    ) -> Vec<SubBlocksForShard<AnalyzedTransaction>> {
+        let components = connected_components(&transactions);
+        NUM_CONNECTED_COMPONENTS.set(components.len() as i64);
+        LARGEST_CONNECTED_COMPONENT_TXNS.set(
+            components
+                .iter()
+                .map(|component| component.len())
+                .max()
+                .unwrap_or_default() as i64,
+        );
+
+        // The largest components first, ties broken by their first transaction for the
+        // partitioning to be deterministic.
+        let mut components = components;
+        components.sort_by_key(|component| (Reverse(component.len()), component[0]));
+        let mut txn_indices_per_shard = vec![vec![]; self.num_shards];
+        for component in components {
+            let least_loaded_shard = txn_indices_per_shard
+                .iter_mut()
+                .min_by_key(|txn_indices| txn_indices.len())
+                .expect("Must have a shard");
+            least_loaded_shard.extend(component);
+        }
+
+        let mut transactions: Vec<_> = transactions.into_iter().map(Some).collect();
+        let mut start_index = 0;
+        let mut sub_blocks: Vec<_> = txn_indices_per_shard
+            .into_iter()
+            .enumerate()
+            .map(|(shard_id, mut txn_indices)| {
+                // The transactions of each shard keep their relative order in the block.
+                txn_indices.sort_unstable();
+                let txns: Vec<_> = txn_indices
+                    .into_iter()
+                    .map(|txn_idx| {
+                        TransactionWithDependencies::new(
+                            transactions[txn_idx].take().expect("Placed once"),
+                            CrossShardDependencies::default(),
+                        )
+                    })
+                    .collect();
+                let sub_block = SubBlock::new(start_index, txns);
+                start_index += sub_block.num_txns();
+                SubBlocksForShard::new(shard_id, vec![sub_block])
+            })
+            .collect();
+        ShardedBlockPartitioner::add_module_publishing_dependencies(&mut sub_blocks);
+        sub_blocks
+    }
+}
+
+impl BlockPartitioner for ConnectedComponentsPartitioner {
+    fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
+    /// The transactions without a sender (e.g. the block metadata transaction) are executed
+    /// unsharded, ahead of the others.
+    fn partition_block(&self, transactions: Vec<AnalyzedTransaction>) -> ExecutableTransactions {
+        let (unsharded_prefix, txns_to_partition): (Vec<_>, Vec<_>) = transactions
+            .into_iter()
+            .partition(|txn| txn.sender().is_none());
+        let unsharded_prefix: Vec<Transaction> =
+            unsharded_prefix.into_iter().map(|txn| txn.into()).collect();
+        if txns_to_partition.is_empty() {
+            return ExecutableTransactions::Unsharded(unsharded_prefix);
+        }
+        let sharded = self.partition(txns_to_partition);
+        if unsharded_prefix.is_empty() {
+            ExecutableTransactions::Sharded(sharded)
+        } else {
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            }
+        }
+    }
+}
+
+/// Returns the indices of the transactions of each connected component of the conflicts, in
+/// the order of their first transaction, and each in the order of the block.
+fn connected_components(transactions: &[AnalyzedTransaction]) -> Vec<Vec<usize>> {
+    let mut union_find = UnionFind::new(transactions.len());
+    let mut first_writers: HashMap<&StorageLocation, usize> = HashMap::new();
+    let mut first_txn_of_senders = HashMap::new();
+    for (txn_idx, txn) in transactions.iter().enumerate() {
+        for storage_location in txn.write_hints() {
+            let first_writer = *first_writers.entry(storage_location).or_insert(txn_idx);
+            union_find.union(first_writer, txn_idx);
+        }
+        if let Some(sender) = txn.sender() {
+            let first_txn_of_sender = *first_txn_of_senders.entry(sender).or_insert(txn_idx);
+            union_find.union(first_txn_of_sender, txn_idx);
+        }
+    }
+    // Reading a location only conflicts with writing it.
+    for (txn_idx, txn) in transactions.iter().enumerate() {
+        for storage_location in txn.read_hints() {
+            if let Some(first_writer) = first_writers.get(storage_location) {
+                union_find.union(*first_writer, txn_idx);
+            }
+        }
+    }
+
+    let mut components: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut component_of_roots = HashMap::new();
+    for txn_idx in 0..transactions.len() {
+        let root = union_find.find(txn_idx);
+        // Components are keyed by their first transaction.
+        let first_txn_idx = *component_of_roots.entry(root).or_insert(txn_idx);
+        components.entry(first_txn_idx).or_default().push(txn_idx);
+    }
+    components.into_values().collect()
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        Self {
+            parents: (0..size).collect(),
+            ranks: vec![0; size],
+        }
+    }
+
+    fn find(&mut self, mut element: usize) -> usize {
+        let mut root = element;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        // Path compression.
+        while self.parents[element] != root {
+            let parent = self.parents[element];
+            self.parents[element] = root;
+            element = parent;
+        }
+        root
+    }
+
+    fn union(&mut self, element1: usize, element2: usize) {
+        let (root1, root2) = (self.find(element1), self.find(element2));
+        if root1 == root2 {
+            return;
+        }
+        match self.ranks[root1].cmp(&self.ranks[root2]) {
+            Ordering::Less => self.parents[root1] = root2,
+            Ordering::Greater => self.parents[root2] = root1,
+            Ordering::Equal => {
+                self.parents[root2] = root1;
+                self.ranks[root1] += 1;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        create_signed_p2p_transaction, create_signed_publish_package_transaction,
+        generate_test_account, generate_test_account_for_address,
+    };
+
+    #[test]
+    fn test_connected_components() {
+        let mut accounts: Vec<_> = (0..6).map(|_| generate_test_account()).collect();
+        let mut transfer = |sender: usize, receiver: usize| {
+            let receiver = generate_test_account_for_address(accounts[receiver].account_address);
+            create_signed_p2p_transaction(&mut accounts[sender], vec![&receiver]).remove(0)
+        };
+        // The transactions 0, 1 and 3 are chained through the coin stores of the accounts 1 and
+        // 2, and the transactions 2 and 4 have the same sender.
+        let transactions = vec![
+            transfer(0, 1),
+            transfer(2, 3),
+            transfer(4, 5),
+            transfer(1, 2),
+            transfer(4, 5),
+        ];
+        assert_eq!(
+            connected_components(&transactions),
+            vec![vec![0, 1, 3], vec![2, 4]]
+        );
+
+        let partitioner = ConnectedComponentsPartitioner::new(3);
+        let sub_blocks = partitioner.partition(transactions.clone());
+        assert_eq!(sub_blocks.len(), 3);
+        let txns_of_shards: Vec<Vec<_>> = sub_blocks
+            .iter()
+            .map(|sub_blocks_for_shard| {
+                sub_blocks_for_shard
+                    .iter()
+                    .map(|txn| {
+                        assert_eq!(txn.cross_shard_dependencies().num_required_edges(), 0);
+                        txn.txn().clone()
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(txns_of_shards, vec![
+            vec![
+                transactions[0].clone(),
+                transactions[1].clone(),
+                transactions[3].clone(),
+            ],
+            vec![transactions[2].clone(), transactions[4].clone()],
+            vec![],
+        ]);
+        assert_eq!(sub_blocks[1].sub_blocks[0].start_index, 3);
+        assert_eq!(sub_blocks[2].sub_blocks[0].start_index, 5);
+    }
+
+    #[test]
+    fn test_module_publishing_dependencies() {
+        let mut publisher = generate_test_account();
+        let mut sender = generate_test_account();
+        let receiver = generate_test_account();
+        let transactions = vec![
+            create_signed_publish_package_transaction(&mut publisher),
+            create_signed_p2p_transaction(&mut sender, vec![&receiver]).remove(0),
+        ];
+        let sub_blocks = ConnectedComponentsPartitioner::new(2).partition(transactions);
+        // The transfer, in the later shard, waits for the modules to be published.
+        assert_eq!(
+            sub_blocks[1].sub_blocks[0].transactions[0]
+                .cross_shard_dependencies()
+                .num_required_edges(),
+            1
+        );
+    }
+}
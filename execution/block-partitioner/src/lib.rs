@@ -2,35 +2,50 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod connected_components;
 pub mod sharded_block_partitioner;
 pub mod test_utils;
 
-use aptos_types::transaction::Transaction;
+use crate::{
+    connected_components::ConnectedComponentsPartitioner,
+    sharded_block_partitioner::ShardedBlockPartitioner,
+};
+use aptos_types::{
+    block_executor::partitioner::ExecutableTransactions,
+    transaction::analyzed_transaction::AnalyzedTransaction,
+};
+use clap::ValueEnum;
 
+/// A strategy to partition the transactions of a block across the executor shards. All the
+/// strategies produce the sub blocks (with their cross shard dependencies) executed by the sharded
+/// block executor, so that they can be benchmarked against the same executor and cross shard
+/// client.
 pub trait BlockPartitioner: Send + Sync {
-    fn partition(&self, transactions: Vec<Transaction>, num_shards: usize)
-        -> Vec<Vec<Transaction>>;
-}
+    fn num_shards(&self) -> usize;
 
-/// An implementation of partitioner that splits the transactions into equal-sized chunks.
-pub struct UniformPartitioner {}
+    /// Partitions the transactions into sub blocks for each shard, possibly leaving some of them
+    /// to be executed unsharded, ahead of the sub blocks.
+    fn partition_block(&self, transactions: Vec<AnalyzedTransaction>) -> ExecutableTransactions;
+}
 
-impl BlockPartitioner for UniformPartitioner {
-    fn partition(
-        &self,
-        transactions: Vec<Transaction>,
-        num_shards: usize,
-    ) -> Vec<Vec<Transaction>> {
-        let total_txns = transactions.len();
-        if total_txns == 0 {
-            return vec![];
-        }
-        let txns_per_shard = (total_txns as f64 / num_shards as f64).ceil() as usize;
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum PartitionerType {
+    /// Discards the transactions with cross shard conflicts into the next rounds, see
+    /// [ShardedBlockPartitioner].
+    #[default]
+    Sharded,
+    /// Places the connected components of the conflicts in the shards, see
+    /// [ConnectedComponentsPartitioner].
+    ConnectedComponents,
+}
 
-        let mut result = Vec::new();
-        for chunk in transactions.chunks(txns_per_shard) {
-            result.push(chunk.to_vec());
+impl PartitionerType {
+    pub fn create_partitioner(&self, num_shards: usize) -> Box<dyn BlockPartitioner> {
+        match self {
+            PartitionerType::Sharded => Box::new(ShardedBlockPartitioner::new(num_shards)),
+            PartitionerType::ConnectedComponents => {
+                Box::new(ConnectedComponentsPartitioner::new(num_shards))
+            },
         }
-        result
     }
 }
@@ -3,6 +3,7 @@
 use aptos_block_partitioner::{
     sharded_block_partitioner::ShardedBlockPartitioner,
     test_utils::{create_signed_p2p_transaction, generate_test_account, TestAccount},
+    BlockPartitioner, PartitionerType,
 };
 use aptos_types::transaction::analyzed_transaction::AnalyzedTransaction;
 use clap::Parser;
@@ -23,6 +24,17 @@ struct Args {
 
     #[clap(long, default_value_t = 12)]
     pub num_shards: usize,
+
+    #[clap(long, value_enum, default_value_t = PartitionerType::default())]
+    pub partitioner_type: PartitionerType,
+
+    /// Only applies to the sharded partitioner.
+    #[clap(long, default_value_t = 2)]
+    pub max_partitioning_rounds: usize,
+
+    /// Only applies to the sharded partitioner.
+    #[clap(long, default_value_t = 0.9)]
+    pub cross_shard_dep_avoid_threshold: f32,
 }
 
 fn main() {
@@ -48,12 +60,20 @@ fn main() {
         })
         .collect();
 
-    let partitioner = ShardedBlockPartitioner::new(args.num_shards);
+    let partitioner: Box<dyn BlockPartitioner> = match args.partitioner_type {
+        PartitionerType::Sharded => Box::new(
+            ShardedBlockPartitioner::new(args.num_shards).with_partitioning_rounds(
+                args.max_partitioning_rounds,
+                args.cross_shard_dep_avoid_threshold,
+            ),
+        ),
+        partitioner_type => partitioner_type.create_partitioner(args.num_shards),
+    };
     for _ in 0..args.num_blocks {
         let transactions = transactions.clone();
         println!("Starting to partition");
         let now = Instant::now();
-        partitioner.partition(transactions, 2, 0.9);
+        partitioner.partition_block(transactions);
         let elapsed = now.elapsed();
         println!("Time taken to partition: {:?}", elapsed);
     }
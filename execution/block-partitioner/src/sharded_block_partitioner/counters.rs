@@ -3,8 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_metrics_core::{
-    exponential_buckets, register_histogram, register_histogram_vec, register_int_gauge_vec,
-    Histogram, HistogramVec, IntGaugeVec,
+    exponential_buckets, register_histogram, register_histogram_vec, register_int_gauge,
+    register_int_gauge_vec, Histogram, HistogramVec, IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -17,6 +17,30 @@ pub static NUM_PARTITIONED_TXNS: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static NUM_CONFLICT_TAIL_TXNS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_num_conflict_tail_txns",
+        "Number of transactions of the latest block placed in the conflict tail, i.e. the final round executed by a single shard"
+    )
+    .unwrap()
+});
+
+pub static NUM_CONNECTED_COMPONENTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_num_connected_components",
+        "Number of connected components of the conflicts of the latest block partitioned by connected components"
+    )
+    .unwrap()
+});
+
+pub static LARGEST_CONNECTED_COMPONENT_TXNS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_largest_connected_component_txns",
+        "Number of transactions of the largest connected component of the conflicts of the latest block partitioned by connected components"
+    )
+    .unwrap()
+});
+
 pub static BLOCK_PARTITIONING_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -1,26 +1,38 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::sharded_block_partitioner::{
-    counters::BLOCK_PARTITIONING_MISC_TIMERS_SECONDS,
-    cross_shard_messages::CrossShardMsg,
-    dependency_analysis::WriteSetWithTxnIndex,
-    messages::{
-        AddWithCrossShardDep, ControlMsg,
-        ControlMsg::{AddCrossShardDepReq, DiscardCrossShardDepReq},
-        DiscardCrossShardDep, PartitioningResp,
+use crate::{
+    sharded_block_partitioner::{
+        counters::{BLOCK_PARTITIONING_MISC_TIMERS_SECONDS, NUM_CONFLICT_TAIL_TXNS},
+        cross_shard_messages::CrossShardMsg,
+        dependency_analysis::WriteSetWithTxnIndex,
+        messages::{
+            AddWithCrossShardDep, ControlMsg,
+            ControlMsg::{AddCrossShardDepReq, DiscardCrossShardDepReq},
+            DiscardCrossShardDep, PartitioningResp,
+        },
+        partitioning_shard::PartitioningShard,
     },
-    partitioning_shard::PartitioningShard,
+    BlockPartitioner,
 };
 use aptos_logger::{error, info};
 use aptos_types::{
-    block_executor::partitioner::{RoundId, ShardId, SubBlocksForShard, TxnIndex},
-    transaction::analyzed_transaction::AnalyzedTransaction,
+    block_executor::{
+        hot_state_keys::HotStateKeyRegistry,
+        partitioner::{
+            ExecutableTransactions, RoundId, ShardId, ShardedTxnIndex, SubBlocksForShard, TxnIndex,
+        },
+    },
+    state_store::state_key::StateKeyInterner,
+    transaction::{
+        analyzed_transaction::{AnalyzedTransaction, StorageLocation},
+        Transaction,
+    },
 };
 use counters::BLOCK_PARTITIONING_SECONDS;
 use itertools::Itertools;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{
         mpsc::{Receiver, Sender},
         Arc,
@@ -29,7 +41,7 @@ use std::{
 };
 
 mod conflict_detector;
-mod counters;
+pub(crate) mod counters;
 mod cross_shard_messages;
 mod dependency_analysis;
 mod dependent_edges;
@@ -108,11 +120,22 @@ mod partitioning_shard;
 ///
 ///
 pub static MAX_ALLOWED_PARTITIONING_ROUNDS: usize = 8;
+/// The partitioning parameters used through the [BlockPartitioner] trait.
+pub const DEFAULT_MAX_PARTITIONING_ROUNDS: RoundId = 4;
+pub const DEFAULT_CROSS_SHARD_DEP_AVOID_THRESHOLD: f32 = 0.95;
+/// The number of senders writing a key in a block from which the key is hot, see
+/// [ShardedBlockPartitioner::partition_hybrid].
+pub const DEFAULT_HOT_KEY_MIN_SENDERS: usize = 4;
 pub struct ShardedBlockPartitioner {
     num_shards: usize,
     control_txs: Vec<Sender<ControlMsg>>,
     result_rxs: Vec<Receiver<PartitioningResp>>,
     shard_threads: Vec<thread::JoinHandle<()>>,
+    maybe_hot_key_registry: Option<Arc<HotStateKeyRegistry>>,
+    conflict_tail: bool,
+    hot_key_min_senders: usize,
+    max_partitioning_rounds: RoundId,
+    cross_shard_dep_avoid_threshold: f32,
 }
 
 impl ShardedBlockPartitioner {
@@ -157,11 +180,59 @@ impl ShardedBlockPartitioner {
             control_txs,
             result_rxs,
             shard_threads: shard_join_handles,
+            maybe_hot_key_registry: None,
+            conflict_tail: false,
+            hot_key_min_senders: DEFAULT_HOT_KEY_MIN_SENDERS,
+            max_partitioning_rounds: DEFAULT_MAX_PARTITIONING_ROUNDS,
+            cross_shard_dep_avoid_threshold: DEFAULT_CROSS_SHARD_DEP_AVOID_THRESHOLD,
         }
     }
 
+    /// Places the senders whose transactions write the same hot key (i.e. a key that several
+    /// transactions of the recent blocks wrote) in the same shard, as they are bound to conflict.
+    /// The partitioning then depends on the blocks executed by this process, so this is only meant
+    /// for benchmarks, not for blocks whose execution other nodes must reproduce.
+    pub fn with_hot_key_registry(mut self, hot_key_registry: Arc<HotStateKeyRegistry>) -> Self {
+        self.maybe_hot_key_registry = Some(hot_key_registry);
+        self
+    }
+
+    /// Places the transactions remaining after the rounds discarding the cross shard conflicts in
+    /// a conflict tail, i.e. a final round in which all of them are in the first shard, instead of
+    /// spreading them across the shards. The conflict tail only depends on the previous rounds, so
+    /// that the shards never depend on each other within a round, at the cost of the parallelism
+    /// across shards for the tail.
+    pub fn with_conflict_tail(mut self) -> Self {
+        self.conflict_tail = true;
+        self
+    }
+
+    /// Sets the number of senders writing a key in a block from which the key is hot, see
+    /// [Self::partition_hybrid].
+    pub fn with_hot_key_min_senders(mut self, hot_key_min_senders: usize) -> Self {
+        self.hot_key_min_senders = hot_key_min_senders;
+        self
+    }
+
+    /// Sets the partitioning rounds and the cross shard dependency avoid threshold used by
+    /// [BlockPartitioner::partition_block], see [Self::partition].
+    pub fn with_partitioning_rounds(
+        mut self,
+        max_partitioning_rounds: RoundId,
+        cross_shard_dep_avoid_threshold: f32,
+    ) -> Self {
+        self.max_partitioning_rounds = max_partitioning_rounds;
+        self.cross_shard_dep_avoid_threshold = cross_shard_dep_avoid_threshold;
+        self
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
     // reorders the transactions so that transactions from the same sender always go to the same shard.
     // This places transactions from the same sender next to each other, which is not optimal for parallelism.
+    // Senders writing the same hot key are also placed next to each other, in the same shard.
     // TODO(skedia): Improve this logic to shuffle senders
     fn partition_by_senders(
         &self,
@@ -180,11 +251,41 @@ impl ShardedBlockPartitioner {
             }
         }
 
+        // Groups of senders to place in the same shard, in the order of their first sender.
+        let mut sender_groups: Vec<Vec<_>> = Vec::new();
+        let hot_keys = self
+            .maybe_hot_key_registry
+            .as_ref()
+            .map(|registry| registry.hot_keys())
+            .unwrap_or_default();
+        let mut hot_key_to_group = HashMap::new();
+        for sender in sender_order {
+            let hot_key = sender_to_txns[&sender]
+                .iter()
+                .flat_map(|txn| txn.write_hints())
+                .find_map(|location| match location {
+                    StorageLocation::Specific(key) if hot_keys.contains(key) => Some(key),
+                    _ => None,
+                });
+            match hot_key.and_then(|key| hot_key_to_group.get(key)) {
+                Some(group_idx) => sender_groups[*group_idx].push(sender),
+                None => {
+                    if let Some(key) = hot_key {
+                        hot_key_to_group.insert(key.clone(), sender_groups.len());
+                    }
+                    sender_groups.push(vec![sender]);
+                },
+            }
+        }
+
         let mut result = Vec::new();
         result.push(Vec::new());
 
-        for sender in sender_order {
-            let txns = sender_to_txns.remove(&sender).unwrap();
+        for senders in sender_groups {
+            let txns: Vec<_> = senders
+                .iter()
+                .flat_map(|sender| sender_to_txns.remove(sender).unwrap())
+                .collect();
             let txns_in_shard = result.last().unwrap().len();
 
             if txns_in_shard < approx_txns_per_shard {
@@ -303,9 +404,11 @@ impl ShardedBlockPartitioner {
     /// `max_partitioning_rounds` is the maximum number of partitioning rounds we allow.
     /// `cross_shard_dep_avoid_threshold` is the maximum fraction of transactions we try to avoid cross shard dependencies. Once we reach
     /// this fraction, we terminate early and add cross-shard dependencies to the remaining transactions.
+    /// With a conflict tail (see `with_conflict_tail`), the remaining transactions are instead all
+    /// placed in the first shard, so that they only depend on the previous rounds.
     pub fn partition(
         &self,
-        transactions: Vec<AnalyzedTransaction>,
+        mut transactions: Vec<AnalyzedTransaction>,
         max_partitioning_rounds: RoundId,
         cross_shard_dep_avoid_threshold: f32,
     ) -> Vec<SubBlocksForShard<AnalyzedTransaction>> {
@@ -324,6 +427,17 @@ impl ShardedBlockPartitioner {
             return vec![];
         }
 
+        // Intern the hints, so that the storage locations shared across transactions are cheap
+        // to clone into the read/write sets and the dependent edges.
+        let timer = BLOCK_PARTITIONING_MISC_TIMERS_SECONDS
+            .with_label_values(&["intern_hints"])
+            .start_timer();
+        let mut interner = StateKeyInterner::new();
+        for txn in transactions.iter_mut() {
+            txn.intern_hints(&mut interner);
+        }
+        let _duration = timer.stop_and_record();
+
         // First round, we filter all transactions with cross-shard dependencies
         let timer = BLOCK_PARTITIONING_MISC_TIMERS_SECONDS
             .with_label_values(&["partition_by_senders"])
@@ -388,8 +502,12 @@ impl ShardedBlockPartitioner {
         let timer = BLOCK_PARTITIONING_MISC_TIMERS_SECONDS
             .with_label_values(&["last_round"])
             .start_timer();
+        // Without any previous round, all the transactions would end up in the conflict tail.
+        if self.conflict_tail && current_round > 0 {
+            txns_to_partition = self.into_conflict_tail(txns_to_partition);
+        }
         // We just add cross shard dependencies for remaining transactions.
-        let (frozen_sub_blocks, _, rejected_txns) = self.add_cross_shard_dependencies(
+        let (mut frozen_sub_blocks, _, rejected_txns) = self.add_cross_shard_dependencies(
             current_round_start_index,
             txns_to_partition,
             frozen_sub_blocks,
@@ -399,10 +517,154 @@ impl ShardedBlockPartitioner {
 
         // Assert rejected transactions are empty
         assert!(rejected_txns.iter().all(|txns| txns.is_empty()));
+        Self::add_module_publishing_dependencies(&mut frozen_sub_blocks);
         let _duration = timer.stop_and_record();
 
         frozen_sub_blocks
     }
+
+    /// Makes the sub blocks following each transaction publishing modules in the block (i.e. those
+    /// of the later rounds, and of the later shards in its round) depend on it, for the executor
+    /// to broadcast the published modules to them before they are executed. As the modules the
+    /// transactions read are not part of their hints, the first transaction of each sub block
+    /// depends on the package registry of the publisher instead.
+    pub(crate) fn add_module_publishing_dependencies(
+        sub_blocks: &mut [SubBlocksForShard<AnalyzedTransaction>],
+    ) {
+        let mut publishing_txns = vec![];
+        for (shard_id, sub_blocks_for_shard) in sub_blocks.iter().enumerate() {
+            for (round, sub_block) in sub_blocks_for_shard.sub_block_iter().enumerate() {
+                for (txn_idx, txn) in sub_block.txn_with_index_iter() {
+                    if !txn.txn().publishes_modules() {
+                        continue;
+                    }
+                    let sender = txn
+                        .txn()
+                        .sender()
+                        .expect("Only user transactions publish modules");
+                    publishing_txns.push((
+                        ShardedTxnIndex::new(txn_idx, shard_id, round),
+                        AnalyzedTransaction::package_registry_location(sender),
+                    ));
+                }
+            }
+        }
+
+        for (publishing_txn_idx, storage_location) in publishing_txns {
+            let mut dependent_txn_indices = vec![];
+            for (shard_id, sub_blocks_for_shard) in sub_blocks.iter_mut().enumerate() {
+                for (round, sub_block) in sub_blocks_for_shard.sub_blocks.iter_mut().enumerate() {
+                    let follows = (round, shard_id)
+                        > (publishing_txn_idx.round_id, publishing_txn_idx.shard_id);
+                    if !follows || sub_block.is_empty() {
+                        continue;
+                    }
+                    sub_block.transactions[0]
+                        .cross_shard_dependencies
+                        .add_required_edge(publishing_txn_idx.clone(), storage_location.clone());
+                    dependent_txn_indices.push(ShardedTxnIndex::new(
+                        sub_block.start_index,
+                        shard_id,
+                        round,
+                    ));
+                }
+            }
+            let publishing_sub_block = sub_blocks[publishing_txn_idx.shard_id]
+                .get_sub_block_mut(publishing_txn_idx.round_id)
+                .expect("Must exist");
+            for dependent_txn_idx in dependent_txn_indices {
+                publishing_sub_block.add_dependent_edge(
+                    publishing_txn_idx.txn_index,
+                    dependent_txn_idx,
+                    vec![storage_location.clone()],
+                );
+            }
+        }
+    }
+
+    /// Moves the remaining transactions to the first shard. They keep their order, and thus their
+    /// indices, as the transactions of a round are indexed in the order of their shard.
+    fn into_conflict_tail(
+        &self,
+        txns_to_partition: Vec<Vec<AnalyzedTransaction>>,
+    ) -> Vec<Vec<AnalyzedTransaction>> {
+        let conflict_tail: Vec<_> = txns_to_partition.into_iter().flatten().collect();
+        NUM_CONFLICT_TAIL_TXNS.set(conflict_tail.len() as i64);
+        let mut txns_to_partition = vec![vec![]; self.num_shards];
+        txns_to_partition[0] = conflict_tail;
+        txns_to_partition
+    }
+
+    /// Splits the block into a prefix that is executed unsharded and the rest of the block, which
+    /// is partitioned. The prefix holds the transactions that cannot be partitioned (i.e. the ones
+    /// without a sender, such as the block metadata transaction), and the transactions of the
+    /// senders writing hot keys, as they are bound to conflict. The hot keys are the ones that the
+    /// write hints of the transactions of at least `hot_key_min_senders` senders of the block
+    /// contain, so that the split only depends on the block. The relative order of the
+    /// transactions of each sender is preserved.
+    pub fn partition_hybrid(
+        &self,
+        transactions: Vec<AnalyzedTransaction>,
+        max_partitioning_rounds: RoundId,
+        cross_shard_dep_avoid_threshold: f32,
+    ) -> ExecutableTransactions {
+        let mut senders_by_key = HashMap::new();
+        for txn in &transactions {
+            if let Some(sender) = txn.sender() {
+                for location in txn.write_hints() {
+                    if let StorageLocation::Specific(key) = location {
+                        senders_by_key
+                            .entry(key)
+                            .or_insert_with(HashSet::new)
+                            .insert(sender);
+                    }
+                }
+            }
+        }
+        let hot_senders: HashSet<_> = senders_by_key
+            .into_values()
+            .filter(|senders| senders.len() >= self.hot_key_min_senders)
+            .flatten()
+            .collect();
+        let (unsharded_prefix, txns_to_partition): (Vec<_>, Vec<_>) =
+            transactions.into_iter().partition(|txn| {
+                txn.sender()
+                    .map_or(true, |sender| hot_senders.contains(&sender))
+            });
+        let unsharded_prefix: Vec<Transaction> =
+            unsharded_prefix.into_iter().map(|txn| txn.into()).collect();
+
+        if txns_to_partition.is_empty() {
+            return ExecutableTransactions::Unsharded(unsharded_prefix);
+        }
+        let sharded = self.partition(
+            txns_to_partition,
+            max_partitioning_rounds,
+            cross_shard_dep_avoid_threshold,
+        );
+        if unsharded_prefix.is_empty() {
+            ExecutableTransactions::Sharded(sharded)
+        } else {
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            }
+        }
+    }
+}
+
+impl BlockPartitioner for ShardedBlockPartitioner {
+    fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
+    fn partition_block(&self, transactions: Vec<AnalyzedTransaction>) -> ExecutableTransactions {
+        self.partition_hybrid(
+            transactions,
+            self.max_partitioning_rounds,
+            self.cross_shard_dep_avoid_threshold,
+        )
+    }
 }
 
 impl Drop for ShardedBlockPartitioner {
@@ -448,17 +710,28 @@ mod tests {
         sharded_block_partitioner::ShardedBlockPartitioner,
         test_utils::{
             create_non_conflicting_p2p_transaction, create_signed_p2p_transaction,
-            generate_test_account, generate_test_account_for_address, TestAccount,
+            create_signed_publish_package_transaction, generate_test_account,
+            generate_test_account_for_address, TestAccount,
         },
     };
-    use aptos_crypto::hash::CryptoHash;
+    use aptos_crypto::{hash::CryptoHash, HashValue};
     use aptos_types::{
-        block_executor::partitioner::{ShardedTxnIndex, SubBlock, SubBlocksForShard},
+        block_executor::{
+            hot_state_keys::{HotKeyConfig, HotStateKeyRegistry},
+            partitioner::{
+                ExecutableBlock, ExecutableTransactions, ShardedTxnIndex, SubBlock,
+                SubBlocksForShard,
+            },
+        },
+        block_metadata::BlockMetadata,
         transaction::{analyzed_transaction::AnalyzedTransaction, Transaction},
     };
     use move_core_types::account_address::AccountAddress;
     use rand::{rngs::OsRng, Rng};
-    use std::{collections::HashMap, sync::Mutex};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
 
     fn verify_no_cross_shard_dependency(sub_blocks_for_shards: Vec<SubBlock<AnalyzedTransaction>>) {
         for sub_blocks in sub_blocks_for_shards {
@@ -586,6 +859,112 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_senders_writing_hot_key_in_one_shard() {
+        let hot_receiver = generate_test_account();
+        let mut senders: Vec<_> = (0..4).map(|_| generate_test_account()).collect();
+        let receivers = [
+            &hot_receiver,
+            &generate_test_account(),
+            &generate_test_account(),
+            &hot_receiver,
+        ];
+        let transactions: Vec<_> = senders
+            .iter_mut()
+            .zip(receivers)
+            .map(|(sender, receiver)| {
+                create_signed_p2p_transaction(sender, vec![receiver]).remove(0)
+            })
+            .collect();
+
+        let hot_key =
+            AnalyzedTransaction::coin_store_location(hot_receiver.account_address).into_state_key();
+        let hot_key_registry = Arc::new(HotStateKeyRegistry::new(HotKeyConfig::default()));
+        hot_key_registry.record_block(vec![hot_key; 4]);
+        let partitioner = ShardedBlockPartitioner::new(2).with_hot_key_registry(hot_key_registry);
+        let sub_blocks = partitioner.partition(transactions.clone(), 2, 0.9);
+
+        // The first and last transactions both write the coin store of the hot receiver.
+        let first_shard_txns: Vec<_> = sub_blocks[0]
+            .iter()
+            .map(|txn| txn.txn().transaction().clone())
+            .collect();
+        assert_eq!(first_shard_txns, vec![
+            transactions[0].transaction().clone(),
+            transactions[3].transaction().clone(),
+        ]);
+        assert_eq!(sub_blocks[1].num_txns(), 2);
+        verify_no_cross_shard_dependency(
+            sub_blocks
+                .iter()
+                .flat_map(|sub_blocks| sub_blocks.sub_block_iter())
+                .cloned()
+                .collect(),
+        );
+    }
+
+    #[test]
+    // Test that the transactions without a sender and the transactions of the senders writing hot
+    // keys are kept in the unsharded prefix, in order, and that the rest is partitioned.
+    fn test_partition_hybrid() {
+        let hot_receiver = generate_test_account();
+        let block_metadata = Transaction::BlockMetadata(BlockMetadata::new(
+            HashValue::random(),
+            0,
+            1,
+            AccountAddress::random(),
+            vec![],
+            vec![],
+            1,
+        ));
+        let mut transactions = vec![block_metadata.clone().into()];
+        transactions.push(create_non_conflicting_p2p_transaction());
+        // Two senders write the coin store of the hot receiver, the first one twice.
+        let mut hot_senders = vec![generate_test_account(), generate_test_account()];
+        transactions.extend(create_signed_p2p_transaction(
+            &mut hot_senders[0],
+            vec![&generate_test_account(), &hot_receiver],
+        ));
+        transactions.extend(create_signed_p2p_transaction(
+            &mut hot_senders[1],
+            vec![&hot_receiver],
+        ));
+        transactions.push(create_non_conflicting_p2p_transaction());
+
+        let partitioner = ShardedBlockPartitioner::new(2).with_hot_key_min_senders(2);
+        match partitioner.partition_hybrid(transactions.clone(), 2, 0.9) {
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            } => {
+                assert_eq!(unsharded_prefix, vec![
+                    block_metadata,
+                    transactions[2].transaction().clone(),
+                    transactions[3].transaction().clone(),
+                    transactions[4].transaction().clone(),
+                ]);
+                assert_eq!(sharded.len(), 2);
+                assert_eq!(SubBlocksForShard::flatten(sharded).len(), 2);
+            },
+            _ => panic!("Expected a hybrid block"),
+        }
+
+        // Without enough senders writing the same key, only the block metadata transaction is
+        // left unsharded.
+        let partitioner = ShardedBlockPartitioner::new(2).with_hot_key_min_senders(3);
+        let num_txns = transactions.len();
+        match partitioner.partition_hybrid(transactions, 2, 0.9) {
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            } => {
+                assert_eq!(unsharded_prefix.len(), 1);
+                assert_eq!(SubBlocksForShard::flatten(sharded).len(), num_txns - 1);
+            },
+            _ => panic!("Expected a hybrid block"),
+        }
+    }
+
     fn get_account_seq_number(txn: &Transaction) -> (AccountAddress, u64) {
         match txn {
             Transaction::UserTransaction(txn) => (txn.sender(), txn.sequence_number()),
@@ -865,4 +1244,144 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    // Ensures that with a conflict tail, the remaining transactions are in the first shard of the
+    // last round, and that no transaction depends on another shard of the same round.
+    fn test_conflict_tail() {
+        let mut rng = OsRng;
+        let num_shards = 8;
+        let num_accounts = 50;
+        let num_txns = 500;
+        let mut accounts = Vec::new();
+        for _ in 0..num_accounts {
+            accounts.push(Mutex::new(generate_test_account()));
+        }
+        let mut transactions = Vec::new();
+
+        for _ in 0..num_txns {
+            let indices = rand::seq::index::sample(&mut rng, num_accounts, 2);
+            let sender = &mut accounts[indices.index(0)].lock().unwrap();
+            let receiver = &accounts[indices.index(1)].lock().unwrap();
+            transactions.push(create_signed_p2p_transaction(sender, vec![receiver]).remove(0));
+        }
+
+        let partitioner = ShardedBlockPartitioner::new(num_shards).with_conflict_tail();
+        let partitioned_txns = partitioner.partition(transactions, 4, 0.9);
+        assert_eq!(
+            partitioned_txns
+                .iter()
+                .map(|sub_blocks| sub_blocks.num_txns())
+                .sum::<usize>(),
+            num_txns
+        );
+        let last_round = partitioned_txns[0].num_sub_blocks() - 1;
+        for (shard_id, sub_blocks_for_shard) in partitioned_txns.iter().enumerate() {
+            for round in 0..=last_round {
+                let sub_block = sub_blocks_for_shard.get_sub_block(round).unwrap();
+                if round == last_round && round > 0 && shard_id != 0 {
+                    assert!(sub_block.is_empty());
+                }
+                for txn in sub_block.iter() {
+                    let dependencies = txn.cross_shard_dependencies();
+                    for (required_txn_idx, _) in dependencies.required_edges_iter() {
+                        assert!(required_txn_idx.round_id < round);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    // Ensures that the sub blocks following a transaction publishing modules depend on it.
+    fn test_module_publishing_dependencies() {
+        let num_shards = 3;
+        let mut publisher = generate_test_account();
+        let mut transactions = vec![];
+        for i in 0..12 {
+            if i == 4 {
+                transactions.push(create_signed_publish_package_transaction(&mut publisher));
+            } else {
+                transactions.push(create_non_conflicting_p2p_transaction());
+            }
+        }
+        let partitioner = ShardedBlockPartitioner::new(num_shards);
+        let partitioned_txns = partitioner.partition(transactions, 2, 0.9);
+
+        let mut publishing_txn_idx = None;
+        for (shard_id, sub_blocks_for_shard) in partitioned_txns.iter().enumerate() {
+            for (round, sub_block) in sub_blocks_for_shard.sub_block_iter().enumerate() {
+                for (txn_idx, txn) in sub_block.txn_with_index_iter() {
+                    if txn.txn().publishes_modules() {
+                        publishing_txn_idx = Some(ShardedTxnIndex::new(txn_idx, shard_id, round));
+                    }
+                }
+            }
+        }
+        let publishing_txn_idx = publishing_txn_idx.unwrap();
+        let package_registry =
+            AnalyzedTransaction::package_registry_location(publisher.account_address);
+
+        let mut num_dependent_sub_blocks = 0;
+        for (shard_id, sub_blocks_for_shard) in partitioned_txns.iter().enumerate() {
+            for (round, sub_block) in sub_blocks_for_shard.sub_block_iter().enumerate() {
+                let follows =
+                    (round, shard_id) > (publishing_txn_idx.round_id, publishing_txn_idx.shard_id);
+                let first_txn = match sub_block.iter().next() {
+                    Some(first_txn) => first_txn,
+                    None => continue,
+                };
+                let required_edge = first_txn
+                    .cross_shard_dependencies()
+                    .get_required_edge_for(publishing_txn_idx.clone());
+                if follows {
+                    assert_eq!(required_edge, Some(&vec![package_registry.clone()]));
+                    num_dependent_sub_blocks += 1;
+                } else {
+                    assert_eq!(required_edge, None);
+                }
+            }
+        }
+        assert!(num_dependent_sub_blocks > 0);
+        let publishing_txn = partitioned_txns[publishing_txn_idx.shard_id]
+            .get_sub_block(publishing_txn_idx.round_id)
+            .unwrap()
+            .iter()
+            .find(|txn| txn.txn().publishes_modules())
+            .unwrap();
+        assert_eq!(
+            publishing_txn
+                .cross_shard_dependencies()
+                .dependent_edges()
+                .len(),
+            num_dependent_sub_blocks
+        );
+    }
+
+    #[test]
+    // Test that the partitioner output (including the cross shard dependencies) survives a BCS
+    // round trip, so that it can be shipped to remote shards or persisted.
+    fn test_partitioned_txns_bcs_round_trip() {
+        let mut sender = generate_test_account();
+        let receivers: Vec<_> = (0..4).map(|_| generate_test_account()).collect();
+        let mut transactions = create_signed_p2p_transaction(
+            &mut sender,
+            receivers.iter().collect::<Vec<&TestAccount>>(),
+        );
+        for _ in 0..4 {
+            transactions.push(create_non_conflicting_p2p_transaction());
+        }
+        let partitioner = ShardedBlockPartitioner::new(2);
+        let partitioned_txns = partitioner.partition(transactions, 2, 0.9);
+
+        let block = ExecutableBlock::new(
+            HashValue::random(),
+            ExecutableTransactions::Sharded(partitioned_txns),
+        );
+        let bytes = bcs::to_bytes(&block).unwrap();
+        let deserialized: ExecutableBlock = bcs::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized.block_id, block.block_id);
+        assert_eq!(deserialized.num_transactions(), block.num_transactions());
+        assert_eq!(bcs::to_bytes(&deserialized).unwrap(), bytes);
+    }
 }
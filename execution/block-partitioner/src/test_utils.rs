@@ -79,3 +79,32 @@ pub fn create_signed_p2p_transaction(
     }
     transactions
 }
+
+pub fn create_signed_publish_package_transaction(sender: &mut TestAccount) -> AnalyzedTransaction {
+    let transaction_payload = TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(AccountAddress::ONE, Identifier::new("code").unwrap()),
+        Identifier::new("publish_package_txn").unwrap(),
+        vec![],
+        vec![
+            bcs::to_bytes(&Vec::<u8>::new()).unwrap(),
+            bcs::to_bytes(&Vec::<Vec<u8>>::new()).unwrap(),
+        ],
+    ));
+
+    let raw_transaction = RawTransaction::new(
+        sender.account_address,
+        sender.sequence_number,
+        transaction_payload,
+        0,
+        0,
+        0,
+        ChainId::new(10),
+    );
+    sender.sequence_number += 1;
+    Transaction::UserTransaction(SignedTransaction::new(
+        raw_transaction.clone(),
+        sender.private_key.public_key().clone(),
+        sender.private_key.sign(&raw_transaction).unwrap(),
+    ))
+    .into()
+}
@@ -0,0 +1,73 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_bitvec::BitVec;
+use aptos_crypto::HashValue;
+use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader};
+use aptos_types::{
+    account_address::AccountAddress,
+    block_metadata::BlockMetadata,
+    on_chain_config::{
+        ConfigurationResource, CurrentTimeMicroseconds, OnChainConfig, ValidatorSet,
+    },
+};
+use aptos_vm::data_cache::AsMoveResolver;
+use std::sync::Arc;
+
+/// Interval between the timestamps of two consecutive generated blocks.
+const BLOCK_INTERVAL_USECS: u64 = 250_000;
+
+/// Generates realistic block metadata for the benchmark blocks: the timestamp progresses from
+/// block to block, and the proposer rotates over the validators of the current epoch.
+pub(crate) struct BlockMetadataGenerator {
+    epoch: u64,
+    round: u64,
+    timestamp_usecs: u64,
+    proposers: Vec<AccountAddress>,
+}
+
+impl BlockMetadataGenerator {
+    /// Initializes the generator from the latest state checkpoint in the DB.
+    pub fn from_db(db: &Arc<dyn DbReader>) -> Self {
+        let state_view = db
+            .latest_state_checkpoint_view()
+            .expect("Failed to get the latest state checkpoint view.");
+        let resolver = state_view.as_move_resolver();
+        let epoch = ConfigurationResource::fetch_config(&resolver)
+            .expect("Unable to retrieve the configuration from storage")
+            .epoch();
+        let timestamp_usecs = CurrentTimeMicroseconds::fetch_config(&resolver)
+            .expect("Unable to retrieve the current time from storage")
+            .microseconds;
+        let proposers: Vec<_> = ValidatorSet::fetch_config(&resolver)
+            .expect("Unable to retrieve the validator set from storage")
+            .payload()
+            .map(|validator| *validator.account_address())
+            .collect();
+        assert!(!proposers.is_empty(), "Validator set is empty.");
+
+        Self {
+            epoch,
+            round: 0,
+            timestamp_usecs,
+            proposers,
+        }
+    }
+
+    pub fn next(&mut self, block_id: HashValue) -> BlockMetadata {
+        self.round += 1;
+        // The timestamp has to strictly increase for blocks with a (non VM reserved) proposer.
+        self.timestamp_usecs += BLOCK_INTERVAL_USECS;
+        let proposer = self.proposers[self.round as usize % self.proposers.len()];
+
+        BlockMetadata::new(
+            block_id,
+            self.epoch,
+            self.round,
+            proposer,
+            BitVec::with_num_bits(self.proposers.len() as u16).into(),
+            vec![],
+            self.timestamp_usecs,
+        )
+    }
+}
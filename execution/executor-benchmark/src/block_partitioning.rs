@@ -1,13 +1,22 @@
 // Copyright © Aptos Foundation
 
-use crate::pipeline::ExecuteBlockMessage;
-use aptos_block_partitioner::sharded_block_partitioner::ShardedBlockPartitioner;
+use crate::{
+    block_metadata_generator::BlockMetadataGenerator, pipeline::ExecuteBlockMessage,
+    profiler::profile_stage,
+};
+use aptos_block_partitioner::{
+    sharded_block_partitioner::ShardedBlockPartitioner, BlockPartitioner, PartitionerType,
+};
 use aptos_crypto::HashValue;
 use aptos_logger::info;
 use aptos_types::{
-    block_executor::partitioner::{
-        CrossShardDependencies, ExecutableBlock, ExecutableTransactions,
-        TransactionWithDependencies,
+    block_executor::{
+        hot_state_keys::HOT_STATE_KEYS,
+        partitioner::{
+            CrossShardDependencies, ExecutableBlock, ExecutableTransactions,
+            TransactionWithDependencies,
+        },
+        shard_load::{DynamicShardingConfig, SHARD_LOAD},
     },
     transaction::Transaction,
 };
@@ -15,53 +24,121 @@ use std::time::Instant;
 
 pub(crate) struct BlockPartitioningStage {
     num_blocks_processed: usize,
-    maybe_partitioner: Option<ShardedBlockPartitioner>,
+    maybe_partitioner: Option<Box<dyn BlockPartitioner>>,
+    partitioner_type: PartitionerType,
+    conflict_tail: bool,
+    // If set, the number of shards of each block is picked given the load of the executor shards.
+    maybe_dynamic_sharding: Option<DynamicShardingConfig>,
+    maybe_block_metadata_generator: Option<BlockMetadataGenerator>,
 }
 
 impl BlockPartitioningStage {
-    pub fn new(num_shards: usize) -> Self {
-        let maybe_partitioner = if num_shards <= 1 {
-            None
-        } else {
-            let partitioner = ShardedBlockPartitioner::new(num_shards);
-            Some(partitioner)
-        };
+    pub(crate) fn new(
+        num_shards: usize,
+        partitioner_type: PartitionerType,
+        conflict_tail: bool,
+        maybe_dynamic_sharding: Option<DynamicShardingConfig>,
+        maybe_block_metadata_generator: Option<BlockMetadataGenerator>,
+    ) -> Self {
+        let maybe_partitioner = (num_shards > 1)
+            .then(|| Self::create_partitioner(partitioner_type, num_shards, conflict_tail));
 
         Self {
             num_blocks_processed: 0,
             maybe_partitioner,
+            partitioner_type,
+            conflict_tail,
+            maybe_dynamic_sharding,
+            maybe_block_metadata_generator,
+        }
+    }
+
+    fn create_partitioner(
+        partitioner_type: PartitionerType,
+        num_shards: usize,
+        conflict_tail: bool,
+    ) -> Box<dyn BlockPartitioner> {
+        match partitioner_type {
+            PartitionerType::Sharded => {
+                let partitioner = ShardedBlockPartitioner::new(num_shards)
+                    .with_hot_key_registry(HOT_STATE_KEYS.clone());
+                if conflict_tail {
+                    Box::new(partitioner.with_conflict_tail())
+                } else {
+                    Box::new(partitioner)
+                }
+            },
+            PartitionerType::ConnectedComponents => partitioner_type.create_partitioner(num_shards),
+        }
+    }
+
+    /// Recreates the partitioner if the load of the executor shards calls for another number of
+    /// shards. The sharded block executor follows the number of shards of the blocks.
+    fn rebalance_shards(&mut self) {
+        let (partitioner, config) = match (&self.maybe_partitioner, &self.maybe_dynamic_sharding) {
+            (Some(partitioner), Some(config)) => (partitioner, config),
+            _ => return,
+        };
+        let num_shards = partitioner.num_shards();
+        let recommended_num_shards = SHARD_LOAD.recommended_num_shards(num_shards, config);
+        if recommended_num_shards != num_shards {
+            info!(
+                "Partitioning block {} into {} shards instead of {}",
+                self.num_blocks_processed, recommended_num_shards, num_shards
+            );
+            self.maybe_partitioner = Some(Self::create_partitioner(
+                self.partitioner_type,
+                recommended_num_shards,
+                self.conflict_tail,
+            ));
         }
     }
 
     pub fn process(&mut self, mut txns: Vec<Transaction>) -> ExecuteBlockMessage {
+        let _profile = profile_stage("partition", self.num_blocks_processed);
         let current_block_start_time = Instant::now();
         info!(
             "In iteration {}, received {:?} transactions.",
             self.num_blocks_processed,
             txns.len()
         );
+        self.rebalance_shards();
         let block_id = HashValue::random();
-        let block: ExecutableBlock = match &self.maybe_partitioner {
-            None => (block_id, txns).into(),
+        let transactions = match &self.maybe_partitioner {
+            None => ExecutableTransactions::Unsharded(txns),
             Some(partitioner) => {
                 let last_txn = txns.pop().unwrap();
                 assert!(matches!(last_txn, Transaction::StateCheckpoint(_)));
                 let analyzed_transactions = txns.into_iter().map(|t| t.into()).collect();
-                let mut sub_blocks = partitioner.partition(analyzed_transactions, 4, 0.95);
-                sub_blocks
-                    .last_mut()
-                    .unwrap()
-                    .sub_blocks
-                    .last_mut()
-                    .unwrap()
-                    .transactions
-                    .push(TransactionWithDependencies::new(
-                        last_txn.into(),
-                        CrossShardDependencies::default(),
-                    ));
-                ExecutableBlock::new(block_id, ExecutableTransactions::Sharded(sub_blocks))
+                let mut transactions = partitioner.partition_block(analyzed_transactions);
+                match &mut transactions {
+                    ExecutableTransactions::Unsharded(txns) => txns.push(last_txn),
+                    ExecutableTransactions::Sharded(sub_blocks)
+                    | ExecutableTransactions::Hybrid {
+                        sharded: sub_blocks,
+                        ..
+                    } => sub_blocks
+                        .last_mut()
+                        .unwrap()
+                        .sub_blocks
+                        .last_mut()
+                        .unwrap()
+                        .transactions
+                        .push(TransactionWithDependencies::new(
+                            last_txn.into(),
+                            CrossShardDependencies::default(),
+                        )),
+                }
+                transactions
             },
         };
+        // For sharded blocks, the block metadata transaction is executed unsharded, ahead of the
+        // sharded transactions.
+        let block = ExecutableBlock::new(block_id, transactions);
+        let block = match &mut self.maybe_block_metadata_generator {
+            Some(generator) => block.with_block_metadata(generator.next(block_id)),
+            None => block,
+        };
         self.num_blocks_processed += 1;
         ExecuteBlockMessage {
             current_block_start_time,
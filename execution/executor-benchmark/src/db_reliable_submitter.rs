@@ -2,7 +2,10 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::db_access::{CoinStore, DbAccessUtil};
+use crate::{
+    db_access::{CoinStore, DbAccessUtil},
+    metered_channel::MeteredSender,
+};
 use anyhow::{Context, Result};
 use aptos_crypto::HashValue;
 use aptos_state_view::account_with_state_view::AsAccountWithStateView;
@@ -14,16 +17,11 @@ use aptos_types::{
     transaction::{SignedTransaction, Transaction},
 };
 use async_trait::async_trait;
-use std::{
-    collections::HashMap,
-    iter::once,
-    sync::{atomic::AtomicUsize, mpsc},
-    time::Duration,
-};
+use std::{collections::HashMap, iter::once, sync::atomic::AtomicUsize, time::Duration};
 
 pub struct DbReliableTransactionSubmitter {
     pub db: DbReaderWriter,
-    pub block_sender: mpsc::SyncSender<Vec<Transaction>>,
+    pub block_sender: MeteredSender<Vec<Transaction>>,
 }
 
 #[async_trait]
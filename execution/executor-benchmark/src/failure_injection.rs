@@ -0,0 +1,161 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Injection of failing transactions into the generated blocks, so that the handling of discarded
+//! and aborted transactions is exercised under load, and not only the happy path.
+
+use aptos_sdk::{
+    transaction_builder::TransactionFactory,
+    types::{transaction::SignedTransaction, LocalAccount},
+};
+use rand::{rngs::StdRng, Rng};
+
+/// Gap added to the sequence number of the sender, for the transaction to be discarded with
+/// SEQUENCE_NUMBER_TOO_NEW.
+const SEQUENCE_NUMBER_GAP: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InjectedFailure {
+    /// Discarded by the prologue, as its sequence number is ahead of the one of the sender.
+    BadSequenceNumber,
+    /// Aborts on transferring more coins than the sender has. Still committed, charging gas and
+    /// bumping the sequence number of the sender.
+    InsufficientBalance,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FailureInjector {
+    /// Fraction of the transactions replaced with failing ones.
+    ratio: f64,
+}
+
+impl FailureInjector {
+    pub fn new(ratio: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&ratio),
+            "Ratio of injected failures must be within [0, 1], got {}",
+            ratio
+        );
+        Self { ratio }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ratio > 0.0
+    }
+
+    /// Replaces each of the transactions with a failing one of a random kind, with probability
+    /// `ratio`. Only the trailing transactions signed by `sender` (with its latest sequence
+    /// numbers) can be replaced, as replacing an earlier one would leave a gap in the sequence
+    /// numbers of the sender, so the replaced transactions are the last ones of the sender.
+    pub fn inject(
+        &self,
+        rng: &mut StdRng,
+        transaction_factory: &TransactionFactory,
+        sender: &mut LocalAccount,
+        txns: &mut Vec<SignedTransaction>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let num_to_replace = (0..txns.len()).filter(|_| rng.gen_bool(self.ratio)).count();
+        let mut num_replaced = 0;
+        while num_replaced < num_to_replace {
+            match txns.last() {
+                Some(txn)
+                    if txn.sender() == sender.address()
+                        && txn.sequence_number() + 1 == sender.sequence_number() =>
+                {
+                    txns.pop();
+                    *sender.sequence_number_mut() -= 1;
+                    num_replaced += 1;
+                },
+                _ => break,
+            }
+        }
+        txns.extend((0..num_replaced).map(|_| {
+            let failure = if rng.gen_bool(0.5) {
+                InjectedFailure::BadSequenceNumber
+            } else {
+                InjectedFailure::InsufficientBalance
+            };
+            Self::failing_transaction(failure, transaction_factory, sender)
+        }));
+    }
+
+    fn failing_transaction(
+        failure: InjectedFailure,
+        transaction_factory: &TransactionFactory,
+        sender: &mut LocalAccount,
+    ) -> SignedTransaction {
+        match failure {
+            InjectedFailure::BadSequenceNumber => sender.sign_transaction(
+                transaction_factory
+                    .transfer(sender.address(), 1)
+                    .sender(sender.address())
+                    .sequence_number(sender.sequence_number() + SEQUENCE_NUMBER_GAP)
+                    .build(),
+            ),
+            InjectedFailure::InsufficientBalance => sender.sign_with_transaction_builder(
+                transaction_factory.transfer(sender.address(), u64::MAX),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::chain_id::ChainId;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_inject() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let transaction_factory = TransactionFactory::new(ChainId::test());
+        let mut sender = LocalAccount::generate(&mut rng);
+        let mut other = LocalAccount::generate(&mut rng);
+        let mut txns =
+            vec![other
+                .sign_with_transaction_builder(transaction_factory.transfer(sender.address(), 1))];
+        txns.extend((0..10).map(|_| {
+            sender.sign_with_transaction_builder(transaction_factory.transfer(other.address(), 1))
+        }));
+
+        FailureInjector::new(1.0).inject(&mut rng, &transaction_factory, &mut sender, &mut txns);
+
+        // All the transactions of the sender were replaced, the one of the other account is kept.
+        assert_eq!(txns.len(), 11);
+        assert_eq!(txns[0].sender(), other.address());
+        let mut next_sequence_number = 0;
+        for txn in &txns[1..] {
+            assert_eq!(txn.sender(), sender.address());
+            if txn.sequence_number() >= SEQUENCE_NUMBER_GAP {
+                assert_eq!(
+                    txn.sequence_number(),
+                    next_sequence_number + SEQUENCE_NUMBER_GAP
+                );
+            } else {
+                assert_eq!(txn.sequence_number(), next_sequence_number);
+                next_sequence_number += 1;
+            }
+        }
+        // Only the aborting transactions consume sequence numbers.
+        assert_eq!(sender.sequence_number(), next_sequence_number);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let transaction_factory = TransactionFactory::new(ChainId::test());
+        let mut sender = LocalAccount::generate(&mut rng);
+        let mut txns =
+            vec![sender
+                .sign_with_transaction_builder(transaction_factory.transfer(sender.address(), 1))];
+        let expected = txns.clone();
+
+        FailureInjector::default().inject(&mut rng, &transaction_factory, &mut sender, &mut txns);
+
+        assert_eq!(txns, expected);
+        assert_eq!(sender.sequence_number(), 1);
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gas used by the committed transactions, reported as a throughput (gas/s) and a histogram of the
+//! gas per transaction. Unlike TPS, gas throughput is comparable across workloads whose
+//! transactions do different amounts of work.
+
+use aptos_logger::info;
+use serde::Serialize;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Transactions with the gas used in `[min_gas, max_gas)`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct GasBucket {
+    pub min_gas: u64,
+    pub max_gas: u64,
+    pub num_txns: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GasReport {
+    /// Number of transactions which were charged gas (i.e. excluding the block metadata and
+    /// state checkpoint ones).
+    pub num_txns: u64,
+    pub total_gas: u64,
+    pub gas_per_sec: f64,
+    pub avg_gas_per_txn: f64,
+    pub histogram: Vec<GasBucket>,
+}
+
+impl GasReport {
+    pub fn log(&self) {
+        info!(
+            "Gas throughput: {:.0} gas/s ({} gas over {} txns, {:.1} gas/txn)",
+            self.gas_per_sec, self.total_gas, self.num_txns, self.avg_gas_per_txn
+        );
+        for bucket in &self.histogram {
+            info!(
+                "Gas per txn in [{}, {}): {} txns",
+                bucket.min_gas, bucket.max_gas, bucket.num_txns
+            );
+        }
+    }
+}
+
+/// Counts the transactions by power of two buckets of the gas they used.
+#[derive(Debug, Default)]
+pub struct GasCollector {
+    /// Bucket `i` holds the transactions with gas in `[2^(i-1), 2^i)`.
+    num_txns_by_bucket: BTreeMap<u32, u64>,
+    num_txns: u64,
+    total_gas: u64,
+}
+
+impl GasCollector {
+    pub fn record_txn(&mut self, gas_used: u64) {
+        if gas_used == 0 {
+            return;
+        }
+        *self
+            .num_txns_by_bucket
+            .entry(u64::BITS - gas_used.leading_zeros())
+            .or_default() += 1;
+        self.num_txns += 1;
+        self.total_gas += gas_used;
+    }
+
+    /// Reports the gas recorded over the given time.
+    pub fn report(&self, elapsed: Duration) -> GasReport {
+        GasReport {
+            num_txns: self.num_txns,
+            total_gas: self.total_gas,
+            gas_per_sec: if elapsed.is_zero() {
+                0.0
+            } else {
+                self.total_gas as f64 / elapsed.as_secs_f64()
+            },
+            avg_gas_per_txn: self.total_gas as f64 / self.num_txns.max(1) as f64,
+            histogram: self
+                .num_txns_by_bucket
+                .iter()
+                .map(|(bucket, num_txns)| GasBucket {
+                    min_gas: 1u64 << (bucket - 1),
+                    max_gas: 1u64.checked_shl(*bucket).unwrap_or(u64::MAX),
+                    num_txns: *num_txns,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut collector = GasCollector::default();
+        for gas_used in [0, 1, 5, 6, 7, 8, 1000] {
+            collector.record_txn(gas_used);
+        }
+        let report = collector.report(Duration::from_secs(2));
+        assert_eq!(report.num_txns, 6);
+        assert_eq!(report.total_gas, 1027);
+        assert_eq!(report.gas_per_sec, 513.5);
+        assert_eq!(
+            report.histogram,
+            vec![
+                GasBucket {
+                    min_gas: 1,
+                    max_gas: 2,
+                    num_txns: 1,
+                },
+                GasBucket {
+                    min_gas: 4,
+                    max_gas: 8,
+                    num_txns: 3,
+                },
+                GasBucket {
+                    min_gas: 8,
+                    max_gas: 16,
+                    num_txns: 1,
+                },
+                GasBucket {
+                    min_gas: 512,
+                    max_gas: 1024,
+                    num_txns: 1,
+                },
+            ]
+        );
+    }
+}
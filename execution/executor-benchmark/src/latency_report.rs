@@ -0,0 +1,176 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Latencies of the stages of the pipeline, collected by the committer for each block and
+//! reported as percentiles, both per block and per transaction (i.e. the latency of a block
+//! divided by its number of transactions).
+
+use aptos_logger::info;
+use serde::Serialize;
+use std::{fs, path::Path, time::Duration};
+
+/// Percentiles of a latency, in microseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    pub p50_us: f64,
+    pub p90_us: f64,
+    pub p99_us: f64,
+}
+
+impl LatencyPercentiles {
+    fn new(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(f64::total_cmp);
+        Self {
+            p50_us: percentile(&samples, 50),
+            p90_us: percentile(&samples, 90),
+            p99_us: percentile(&samples, 99),
+        }
+    }
+}
+
+/// Returns the given percentile of the sorted samples (using the nearest rank method), or 0 if
+/// there are none.
+fn percentile(sorted_samples: &[f64], percentile: usize) -> f64 {
+    if sorted_samples.is_empty() {
+        return 0.0;
+    }
+    let rank = (sorted_samples.len() * percentile + 99) / 100;
+    sorted_samples[rank.max(1) - 1]
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct StageLatencyReport {
+    pub per_block: LatencyPercentiles,
+    pub per_txn: LatencyPercentiles,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct LatencyReport {
+    pub num_blocks: usize,
+    pub num_txns: usize,
+    pub partition: StageLatencyReport,
+    pub execution: StageLatencyReport,
+    pub commit: StageLatencyReport,
+    /// From the start of the processing of a block to the end of its commit, including the time
+    /// it spent queued between the stages.
+    pub end_to_end: StageLatencyReport,
+}
+
+impl LatencyReport {
+    pub fn log(&self) {
+        for (stage, report) in [
+            ("partition", &self.partition),
+            ("execution", &self.execution),
+            ("commit", &self.commit),
+            ("end to end", &self.end_to_end),
+        ] {
+            info!(
+                "Latency of {} over {} blocks: per block p50: {:.0} us, p90: {:.0} us, p99: {:.0} us. Per transaction p50: {:.2} us, p90: {:.2} us, p99: {:.2} us",
+                stage,
+                self.num_blocks,
+                report.per_block.p50_us,
+                report.per_block.p90_us,
+                report.per_block.p99_us,
+                report.per_txn.p50_us,
+                report.per_txn.p90_us,
+                report.per_txn.p99_us,
+            );
+        }
+    }
+
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// The latencies of the stages of a block.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockLatencies {
+    pub partition_time: Duration,
+    pub execution_time: Duration,
+    pub commit_time: Duration,
+    pub end_to_end_time: Duration,
+    pub num_txns: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct LatencyCollector {
+    blocks: Vec<BlockLatencies>,
+}
+
+impl LatencyCollector {
+    pub fn record_block(&mut self, latencies: BlockLatencies) {
+        self.blocks.push(latencies);
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        LatencyReport {
+            num_blocks: self.blocks.len(),
+            num_txns: self.blocks.iter().map(|block| block.num_txns).sum(),
+            partition: self.stage_report(|block| block.partition_time),
+            execution: self.stage_report(|block| block.execution_time),
+            commit: self.stage_report(|block| block.commit_time),
+            end_to_end: self.stage_report(|block| block.end_to_end_time),
+        }
+    }
+
+    fn stage_report(&self, stage_time: impl Fn(&BlockLatencies) -> Duration) -> StageLatencyReport {
+        let micros = |block: &BlockLatencies| stage_time(block).as_nanos() as f64 / 1000.0;
+        StageLatencyReport {
+            per_block: LatencyPercentiles::new(self.blocks.iter().map(micros).collect()),
+            // Empty blocks have no per transaction latency.
+            per_txn: LatencyPercentiles::new(
+                self.blocks
+                    .iter()
+                    .filter(|block| block.num_txns > 0)
+                    .map(|block| micros(block) / block.num_txns as f64)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let percentiles = LatencyPercentiles::new((1..=100).rev().map(f64::from).collect());
+        assert_eq!(
+            percentiles,
+            LatencyPercentiles {
+                p50_us: 50.0,
+                p90_us: 90.0,
+                p99_us: 99.0,
+            }
+        );
+        assert_eq!(
+            LatencyPercentiles::new(vec![]),
+            LatencyPercentiles::default()
+        );
+        assert_eq!(LatencyPercentiles::new(vec![7.0]).p99_us, 7.0);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut collector = LatencyCollector::default();
+        for (millis, num_txns) in [(10, 10), (20, 10), (30, 0)] {
+            collector.record_block(BlockLatencies {
+                partition_time: Duration::from_millis(millis),
+                execution_time: Duration::from_millis(2 * millis),
+                commit_time: Duration::from_millis(millis),
+                end_to_end_time: Duration::from_millis(4 * millis),
+                num_txns,
+            });
+        }
+        let report = collector.report();
+        assert_eq!(report.num_blocks, 3);
+        assert_eq!(report.num_txns, 20);
+        assert_eq!(report.execution.per_block.p50_us, 40_000.0);
+        assert_eq!(report.execution.per_block.p99_us, 60_000.0);
+        assert_eq!(report.execution.per_txn.p50_us, 2_000.0);
+        assert_eq!(report.execution.per_txn.p99_us, 4_000.0);
+    }
+}
@@ -3,22 +3,34 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod account_generator;
+mod block_metadata_generator;
 pub mod block_partitioning;
 pub mod db_access;
 pub mod db_generator;
 mod db_reliable_submitter;
+mod failure_injection;
+pub mod gas_report;
+pub mod latency_report;
+pub mod metered_channel;
 mod metrics;
 pub mod native_executor;
+pub mod output_sink;
 pub mod pipeline;
+pub mod prepopulate;
+pub mod profiler;
+pub mod replay;
+pub mod run_manifest;
 pub mod transaction_committer;
 pub mod transaction_executor;
 pub mod transaction_generator;
 
 use crate::{
-    pipeline::Pipeline, transaction_committer::TransactionCommitter,
-    transaction_executor::TransactionExecutor, transaction_generator::TransactionGenerator,
+    failure_injection::FailureInjector, output_sink::OutputSinkConfig, pipeline::Pipeline,
+    transaction_committer::TransactionCommitter, transaction_executor::TransactionExecutor,
+    transaction_generator::TransactionGenerator,
 };
 use aptos_block_executor::counters as block_executor_counters;
+use aptos_block_partitioner::PartitionerType;
 use aptos_config::config::{NodeConfig, PrunerConfig};
 use aptos_db::AptosDB;
 use aptos_executor::{
@@ -39,8 +51,9 @@ use aptos_transaction_generator_lib::{
     create_txn_generator_creator, TransactionGeneratorCreator, TransactionType,
     TransactionType::NonConflictingCoinTransfer,
 };
+use aptos_types::{contract_event::count_events_by_type, transaction::Version};
 use db_reliable_submitter::DbReliableTransactionSubmitter;
-use pipeline::PipelineConfig;
+use pipeline::{PipelineChannelSizes, PipelineConfig};
 use std::{
     collections::HashMap,
     fs,
@@ -168,6 +181,16 @@ pub fn run_benchmark<V>(
                 allow_aborts: false,
                 num_executor_shards: 1,
                 async_partitioning: false,
+                generate_block_metadata: false,
+                partition_conflict_tail: false,
+                partitioner_type: PartitionerType::default(),
+                dynamic_sharding: None,
+                channel_sizes: PipelineChannelSizes::default(),
+                latency_report_path: None,
+                warmup_blocks: 0,
+                injected_failure_ratio: 0.0,
+                streaming: false,
+                output_sinks: OutputSinkConfig::default(),
             },
         )
     });
@@ -201,7 +224,8 @@ pub fn run_benchmark<V>(
         source_dir,
         version,
         Some(num_accounts_to_load),
-    );
+    )
+    .with_failure_injector(FailureInjector::new(pipeline_config.injected_failure_ratio));
 
     let mut start_time = Instant::now();
     let start_gas_measurement = GasMesurement::start();
@@ -313,11 +337,44 @@ pub fn run_benchmark<V>(
         delta_v / time_in_commit
     );
 
+    log_event_counts_of_last_block(&db, version, block_size);
+
     if verify_sequence_numbers {
         generator.verify_sequence_numbers(db.reader);
     }
 }
 
+/// Logs the number of events emitted per event type by the last block of the workload, which
+/// gives an idea of the event mix of (event heavy) workloads.
+fn log_event_counts_of_last_block(db: &DbReaderWriter, start_version: Version, block_size: usize) {
+    let latest_version = db.reader.get_latest_version().unwrap();
+    if latest_version <= start_version {
+        return;
+    }
+    // Each block ends with a state checkpoint.
+    let num_txns = (latest_version - start_version).min(block_size as u64 + 1);
+    let outputs = match db.reader.get_transaction_outputs(
+        latest_version + 1 - num_txns,
+        num_txns,
+        latest_version,
+    ) {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            warn!("Failed to read the outputs of the last block: {:?}", err);
+            return;
+        },
+    };
+    let event_counts = count_events_by_type(
+        outputs
+            .transactions_and_outputs
+            .iter()
+            .flat_map(|(_, output)| output.events()),
+    );
+    for (type_tag, count) in event_counts {
+        info!("Events in last block: {} x {}", count, type_tag);
+    }
+}
+
 fn init_workload<V>(
     transaction_mix: Vec<(TransactionType, usize)>,
     mut main_signer_accounts: Vec<LocalAccount>,
@@ -532,7 +589,11 @@ impl GasMesurement {
 
 #[cfg(test)]
 mod tests {
-    use crate::{native_executor::NativeExecutor, pipeline::PipelineConfig};
+    use crate::{
+        native_executor::NativeExecutor,
+        output_sink::OutputSinkConfig,
+        pipeline::{PipelineChannelSizes, PipelineConfig},
+    };
     use aptos_config::config::NO_OP_STORAGE_PRUNER_CONFIG;
     use aptos_executor::block_executor::TransactionBlockExecutor;
     use aptos_temppath::TempPath;
@@ -542,6 +603,7 @@ mod tests {
     fn test_generic_benchmark<E>(
         transaction_type: Option<TransactionTypeArg>,
         verify_sequence_numbers: bool,
+        generate_block_metadata: bool,
     ) where
         E: TransactionBlockExecutor + 'static,
     {
@@ -571,6 +633,16 @@ mod tests {
                 allow_aborts: false,
                 num_executor_shards: 1,
                 async_partitioning: false,
+                generate_block_metadata: false,
+                partition_conflict_tail: false,
+                partitioner_type: PartitionerType::default(),
+                dynamic_sharding: None,
+                channel_sizes: PipelineChannelSizes::default(),
+                latency_report_path: None,
+                warmup_blocks: 0,
+                injected_failure_ratio: 0.0,
+                streaming: false,
+                output_sinks: OutputSinkConfig::default(),
             },
         );
 
@@ -598,23 +670,42 @@ mod tests {
                 allow_aborts: false,
                 num_executor_shards: 1,
                 async_partitioning: false,
+                generate_block_metadata,
+                partition_conflict_tail: false,
+                partitioner_type: PartitionerType::default(),
+                dynamic_sharding: None,
+                channel_sizes: PipelineChannelSizes::default(),
+                latency_report_path: None,
+                warmup_blocks: 0,
+                injected_failure_ratio: 0.0,
+                streaming: false,
+                output_sinks: OutputSinkConfig::default(),
             },
         );
     }
 
     #[test]
     fn test_benchmark() {
-        test_generic_benchmark::<AptosVM>(None, true);
+        test_generic_benchmark::<AptosVM>(None, true, false);
+    }
+
+    #[test]
+    fn test_benchmark_with_block_metadata() {
+        test_generic_benchmark::<AptosVM>(None, true, true);
     }
 
     #[test]
     fn test_benchmark_transaction() {
-        test_generic_benchmark::<AptosVM>(Some(TransactionTypeArg::TokenV2AmbassadorMint), true);
+        test_generic_benchmark::<AptosVM>(
+            Some(TransactionTypeArg::TokenV2AmbassadorMint),
+            true,
+            false,
+        );
     }
 
     #[test]
     fn test_native_benchmark() {
         // correct execution not yet implemented, so cannot be checked for validity
-        test_generic_benchmark::<NativeExecutor>(None, false);
+        test_generic_benchmark::<NativeExecutor>(None, false, false);
     }
 }
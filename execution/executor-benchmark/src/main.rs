@@ -2,20 +2,46 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use aptos_block_executor::scheduler_config::{
+    ConflictResolutionPolicy, DependencyWaitStrategy, SchedulerConfig,
+};
+use aptos_block_partitioner::PartitionerType;
 use aptos_config::config::{
     EpochSnapshotPrunerConfig, LedgerPrunerConfig, PrunerConfig, StateMerklePrunerConfig,
 };
 use aptos_executor::block_executor::TransactionBlockExecutor;
-use aptos_executor_benchmark::{native_executor::NativeExecutor, pipeline::PipelineConfig};
+use aptos_executor_benchmark::{
+    native_executor::NativeExecutor,
+    output_sink::OutputSinkConfig,
+    pipeline::{PipelineChannelSizes, PipelineConfig},
+    profiler::{enable_profiling, write_chrome_trace},
+    run_manifest::{RunManifest, ThreadPoolConfig},
+};
 use aptos_metrics_core::{register_int_gauge, IntGauge};
 use aptos_push_metrics::MetricsPusher;
-use aptos_transaction_generator_lib::args::TransactionTypeArg;
-use aptos_vm::AptosVM;
+use aptos_runtimes::thread_pools::{
+    get_thread_pool_spec, numa_node_cores, set_thread_pool_spec_once, ThreadPoolKind,
+    ThreadPoolSpec,
+};
+use aptos_transaction_generator_lib::{
+    args::{TransactionTypeArg, WorkloadShareArg},
+    entry_functions::EntryFunctionWorkload,
+    set_rng_seed_once, TransactionType,
+};
+use aptos_types::block_executor::shard_load::DynamicShardingConfig;
+use aptos_vm::{
+    sharded_block_executor::{
+        cross_shard_client::{CrossShardBatchConfig, DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT},
+        simulated_network::SimulatedNetworkConfig,
+    },
+    AptosVM,
+};
 use clap::{Parser, Subcommand};
 use once_cell::sync::Lazy;
 use std::{
     path::PathBuf,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 #[cfg(unix)]
@@ -96,18 +122,253 @@ pub struct PipelineOpt {
     num_executor_shards: usize,
     #[clap(long)]
     async_partitioning: bool,
+    /// Prepend a block metadata transaction (with progressing timestamps and rotating proposers)
+    /// to each block. With sharding, it is executed ahead of the sharded transactions.
+    #[clap(long)]
+    generate_block_metadata: bool,
+    /// Place the transactions the partitioner cannot spread across the shards without cross shard
+    /// dependencies in a final round executed by a single shard.
+    #[clap(long)]
+    partition_conflict_tail: bool,
+    /// The strategy partitioning the blocks across the executor shards
+    #[clap(long, value_enum, default_value_t = PartitionerType::default())]
+    partitioner_type: PartitionerType,
+    /// Maximum number of executor shards. If above --num-executor-shards, the number of shards
+    /// changes between blocks (from --min-executor-shards up to this one), adding shards while
+    /// the slowest shard takes long, and removing them when the shards are fast or unbalanced.
+    #[clap(long)]
+    max_executor_shards: Option<usize>,
+    #[clap(long, default_value_t = DynamicShardingConfig::default().min_shards)]
+    min_executor_shards: usize,
+    /// Capacity (in blocks) of the channel from the transaction generator to the partitioner.
+    #[clap(long, default_value_t = PipelineChannelSizes::default().generated_blocks)]
+    generated_blocks_channel_size: usize,
+    /// Capacity (in blocks) of the channel from the partitioner to the executor, with
+    /// --async-partitioning.
+    #[clap(long, default_value_t = PipelineChannelSizes::default().partitioned_blocks)]
+    partitioned_blocks_channel_size: usize,
+    /// Capacity (in blocks) of the channel from the executor to the committer.
+    #[clap(long, default_value_t = PipelineChannelSizes::default().executed_blocks)]
+    executed_blocks_channel_size: usize,
+    /// Path to write the p50/p90/p99 latencies of the partition, execution and commit stages
+    /// to, as JSON, both per block and per transaction.
+    #[clap(long, value_parser)]
+    output_json: Option<PathBuf>,
+    /// Fraction of the generated transactions to replace with ones which are discarded (with bad
+    /// sequence numbers) or abort (transferring more than the balance of the sender). Implies
+    /// --allow-discards and --allow-aborts
+    #[clap(long, default_value_t = 0.0)]
+    inject_failures: f64,
+    /// Run the generation, partitioning, execution and commit of the blocks concurrently, and
+    /// report the queue depths of the channels between the stages and the time the stages stall
+    /// on them
+    #[clap(
+        long,
+        conflicts_with_all = &["generate_then_execute", "split_stages", "skip_commit"]
+    )]
+    streaming: bool,
+    /// Path to write the metrics of each committed block to, as CSV
+    #[clap(long, value_parser)]
+    block_metrics_csv: Option<PathBuf>,
+    /// Prometheus pushgateway endpoint to push the metrics of the committed blocks to, e.g.
+    /// http://pushgateway.server.com:9091/metrics/job/executor_benchmark
+    #[clap(long)]
+    block_metrics_pushgateway: Option<String>,
+    /// One way latency (in ms) of the messages between the executor shards and with the
+    /// coordinator, to benchmark sharded execution as if the shards were remote (only applies with
+    /// --num-executor-shards > 1)
+    #[clap(long, default_value_t = 0)]
+    shard_network_latency_ms: u64,
+    /// Maximum extra random delay (in ms) of the messages between the executor shards and with the
+    /// coordinator
+    #[clap(long, default_value_t = 0)]
+    shard_network_jitter_ms: u64,
+    /// How long (in us) the writes sent to another executor shard are held back, for the writes
+    /// of later transactions to be batched with them into a single message
+    #[clap(long, default_value_t = 0)]
+    cross_shard_batch_window_us: u64,
+    /// Number of writes beyond which a batch of writes is sent to another executor shard before
+    /// the end of its window
+    #[clap(long, default_value_t = CrossShardBatchConfig::default().max_batch_size)]
+    cross_shard_max_batch_size: usize,
+    /// Compress the batches of writes sent between the executor shards with LZ4
+    #[clap(long)]
+    compress_cross_shard_writes: bool,
+    /// Send the aggregator deltas to the other executor shards rather than their materialized
+    /// values, when the deltas apply to the values before the block
+    #[clap(long)]
+    send_cross_shard_deltas: bool,
+    /// How long (in secs) an executor shard waits for the cross shard values it depends on
+    /// without receiving any message, before aborting the block
+    #[clap(long, default_value_t = DEFAULT_CROSS_SHARD_RECEIVE_TIMEOUT.as_secs())]
+    cross_shard_receive_timeout_secs: u64,
+    /// Read the cross shard values that did not arrive yet from the state before the block rather
+    /// than wait for them, re-executing the sub block of a shard if the remote writes differ
+    #[clap(long)]
+    speculative_cross_shard_reads: bool,
 }
 
 impl PipelineOpt {
+    fn simulated_shard_network(&self) -> SimulatedNetworkConfig {
+        SimulatedNetworkConfig {
+            latency: Duration::from_millis(self.shard_network_latency_ms),
+            jitter: Duration::from_millis(self.shard_network_jitter_ms),
+        }
+    }
+
+    fn cross_shard_batch_config(&self) -> CrossShardBatchConfig {
+        CrossShardBatchConfig {
+            window: Duration::from_micros(self.cross_shard_batch_window_us),
+            max_batch_size: self.cross_shard_max_batch_size,
+            compress: self.compress_cross_shard_writes,
+            send_deltas: self.send_cross_shard_deltas,
+        }
+    }
+
+    fn dynamic_sharding(&self) -> Option<DynamicShardingConfig> {
+        let max_shards = self.max_executor_shards?;
+        (self.num_executor_shards > 1 && max_shards > self.num_executor_shards).then(|| {
+            DynamicShardingConfig {
+                min_shards: self.min_executor_shards.clamp(2, self.num_executor_shards),
+                max_shards,
+                ..DynamicShardingConfig::default()
+            }
+        })
+    }
+
     fn pipeline_config(&self) -> PipelineConfig {
         PipelineConfig {
             delay_execution_start: self.generate_then_execute,
             split_stages: self.split_stages,
             skip_commit: self.skip_commit,
-            allow_discards: self.allow_discards,
-            allow_aborts: self.allow_aborts,
+            allow_discards: self.allow_discards || self.inject_failures > 0.0,
+            allow_aborts: self.allow_aborts || self.inject_failures > 0.0,
             num_executor_shards: self.num_executor_shards,
             async_partitioning: self.async_partitioning,
+            generate_block_metadata: self.generate_block_metadata,
+            partition_conflict_tail: self.partition_conflict_tail,
+            partitioner_type: self.partitioner_type,
+            dynamic_sharding: self.dynamic_sharding(),
+            channel_sizes: PipelineChannelSizes {
+                generated_blocks: self.generated_blocks_channel_size,
+                partitioned_blocks: self.partitioned_blocks_channel_size,
+                executed_blocks: self.executed_blocks_channel_size,
+            },
+            latency_report_path: self.output_json.clone(),
+            warmup_blocks: 0,
+            injected_failure_ratio: self.inject_failures,
+            streaming: self.streaming,
+            output_sinks: OutputSinkConfig {
+                csv_path: self.block_metrics_csv.clone(),
+                pushgateway_endpoint: self.block_metrics_pushgateway.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct ThreadPoolOpt {
+    /// Number of threads in the VM execution pool, defaults to the number of CPUs
+    #[clap(long)]
+    num_execution_threads: Option<usize>,
+    /// CPU cores to pin the VM execution threads to
+    #[clap(long, value_delimiter = ',')]
+    execution_pinned_cores: Vec<usize>,
+    /// NUMA node to pin the VM execution threads to the cores of
+    #[clap(long, conflicts_with = "execution_pinned_cores")]
+    execution_numa_node: Option<usize>,
+    /// Number of threads in the proof reading pool
+    #[clap(long)]
+    num_proof_reading_threads: Option<usize>,
+    /// CPU cores to pin the proof reading threads to
+    #[clap(long, value_delimiter = ',')]
+    proof_reading_pinned_cores: Vec<usize>,
+    /// NUMA node to pin the proof reading threads to the cores of
+    #[clap(long, conflicts_with = "proof_reading_pinned_cores")]
+    proof_reading_numa_node: Option<usize>,
+    /// Number of threads in the storage commit pool
+    #[clap(long)]
+    num_commit_threads: Option<usize>,
+    /// CPU cores to pin the storage commit threads to
+    #[clap(long, value_delimiter = ',')]
+    commit_pinned_cores: Vec<usize>,
+    /// NUMA node to pin the storage commit threads to the cores of
+    #[clap(long, conflicts_with = "commit_pinned_cores")]
+    commit_numa_node: Option<usize>,
+}
+
+impl ThreadPoolOpt {
+    fn set_thread_pool_specs(&self) {
+        for (kind, num_threads, pinned_cores, numa_node) in [
+            (
+                ThreadPoolKind::Execution,
+                self.num_execution_threads,
+                &self.execution_pinned_cores,
+                self.execution_numa_node,
+            ),
+            (
+                ThreadPoolKind::ProofReading,
+                self.num_proof_reading_threads,
+                &self.proof_reading_pinned_cores,
+                self.proof_reading_numa_node,
+            ),
+            (
+                ThreadPoolKind::Commit,
+                self.num_commit_threads,
+                &self.commit_pinned_cores,
+                self.commit_numa_node,
+            ),
+        ] {
+            let pinned_cores = match numa_node {
+                Some(node) => numa_node_cores(node).unwrap_or_else(|err| {
+                    panic!("Failed to read the cores of NUMA node {}: {}", node, err)
+                }),
+                None => pinned_cores.clone(),
+            };
+            assert!(
+                set_thread_pool_spec_once(kind, ThreadPoolSpec::new(num_threads, pinned_cores)),
+                "{:?} thread pool already configured.",
+                kind
+            );
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct SchedulerOpt {
+    /// Bound on how far ahead of the next transaction to commit BlockSTM executes transactions
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    validation_ahead_window: Option<u32>,
+    /// Number of times to poll a read dependency before blocking on it
+    #[clap(long)]
+    dependency_wait_spins: Option<u32>,
+    /// Log the BlockSTM scheduler report after each block
+    #[clap(long)]
+    scheduler_report: bool,
+    /// Abandon the executions reading an estimate instead of waiting on the dependency
+    #[clap(long, conflicts_with = "max_retries")]
+    eager_abort: bool,
+    /// Execute the block sequentially once a transaction is re-executed more than this many times
+    #[clap(long)]
+    max_retries: Option<u32>,
+}
+
+impl SchedulerOpt {
+    fn scheduler_config(&self) -> SchedulerConfig {
+        SchedulerConfig {
+            validation_ahead_window: self.validation_ahead_window,
+            dependency_wait_strategy: match self.dependency_wait_spins {
+                Some(num_spins) => DependencyWaitStrategy::SpinThenBlock(num_spins),
+                None => DependencyWaitStrategy::Block,
+            },
+            collect_stats: self.scheduler_report,
+            conflict_resolution_policy: match (self.eager_abort, self.max_retries) {
+                (true, _) => ConflictResolutionPolicy::EagerAbort,
+                (false, Some(max_retries)) => {
+                    ConflictResolutionPolicy::BoundedRetriesThenSequential(max_retries)
+                },
+                (false, None) => ConflictResolutionPolicy::Optimistic,
+            },
         }
     }
 }
@@ -138,6 +399,12 @@ struct Opt {
     #[clap(flatten)]
     pipeline_opt: PipelineOpt,
 
+    #[clap(flatten)]
+    thread_pool_opt: ThreadPoolOpt,
+
+    #[clap(flatten)]
+    scheduler_opt: SchedulerOpt,
+
     #[clap(subcommand)]
     cmd: Command,
 
@@ -147,6 +414,37 @@ struct Opt {
 
     #[clap(long)]
     use_native_executor: bool,
+
+    /// Materialize aggregator deltas only when the outputs of a block are assembled
+    #[clap(long)]
+    delayed_delta_materialization: bool,
+
+    /// Prioritize the transactions of a block by their gas unit price in parallel execution
+    #[clap(long)]
+    gas_price_priority_hints: bool,
+
+    /// Share the module cache between the workers of the parallel execution of a block
+    #[clap(long)]
+    shared_module_cache: bool,
+
+    /// Seed of the random generation of the transactions, for runs to be reproducible. A random
+    /// seed is picked (and recorded in the run manifest) if not set
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Run the partition, execution and commit stages of each block within tracing spans, and
+    /// record them for --profile-chrome-trace
+    #[clap(long)]
+    profile: bool,
+
+    /// Path to write the timeline of the profiled stages to, as a Chrome trace
+    #[clap(long, value_parser, requires = "profile")]
+    profile_chrome_trace: Option<PathBuf>,
+
+    /// Port to serve tokio-console on, to inspect the tracing spans live (requires building
+    /// aptos-logger with the tokio-console feature)
+    #[clap(long)]
+    tokio_console_port: Option<u16>,
 }
 
 impl Opt {
@@ -183,6 +481,16 @@ enum Command {
         #[clap(long, default_value_t = 1000)]
         blocks: usize,
 
+        /// Number of blocks to run before measuring, excluded from the reported steady state
+        /// throughput and latencies
+        #[clap(long, default_value_t = 0)]
+        warmup_blocks: usize,
+
+        /// Number of blocks to measure after the warm-up blocks. If set, the benchmark runs
+        /// --warmup-blocks + --measure-blocks blocks instead of --blocks
+        #[clap(long)]
+        measure_blocks: Option<usize>,
+
         #[clap(long, default_value_t = 1000000)]
         main_signer_accounts: usize,
 
@@ -202,6 +510,21 @@ enum Command {
         #[clap(long, num_args = 0..)]
         transaction_weights: Vec<usize>,
 
+        /// Path to a YAML config of entry function calls to run as the workload,
+        /// instead of the --transaction-type ones
+        #[clap(long, value_parser, conflicts_with = "transaction_type")]
+        entry_function_workload: Option<PathBuf>,
+
+        /// Mix of transaction types to run as the workload, each given as
+        /// <transaction-type>=<percentage>, e.g. --workload coin-transfer=80 publish-package=20.
+        /// Percentages need to add up to 100.
+        #[clap(
+            long,
+            num_args = 1..,
+            conflicts_with_all = &["transaction_type", "entry_function_workload"]
+        )]
+        workload: Vec<WorkloadShareArg>,
+
         #[clap(long, default_value_t = 1)]
         module_working_set_size: usize,
 
@@ -210,6 +533,44 @@ enum Command {
 
         #[clap(long, value_parser)]
         checkpoint_dir: PathBuf,
+
+        /// Path to write the manifest of the run (seed, block size, workload, concurrency and
+        /// git revision) to, as JSON
+        #[clap(long, value_parser)]
+        run_manifest: Option<PathBuf>,
+
+        /// Number of accounts the DB at --data-dir needs to hold before running, the missing
+        /// ones are created (or the DB is restored from --prepopulate-snapshot) first
+        #[clap(long)]
+        prepopulate: Option<usize>,
+
+        /// Directory to restore the prepopulated DB from if it holds enough accounts, and to save
+        /// it to otherwise, so that later runs don't need to create the accounts again
+        #[clap(long, value_parser, requires = "prepopulate")]
+        prepopulate_snapshot: Option<PathBuf>,
+
+        #[clap(long, default_value_t = 10000000000)]
+        prepopulate_account_balance: u64,
+    },
+    /// Replays the blocks of a DB (e.g. restored from a mainnet or testnet backup) on top of a
+    /// DB at an earlier version, checking that execution reproduces the recorded transaction
+    /// infos
+    ReplayBlocks {
+        /// DB to read the blocks to replay from
+        #[clap(long, value_parser)]
+        source_db_dir: PathBuf,
+
+        /// DB holding the state the replay starts from, blocks are replayed from its latest
+        /// version on
+        #[clap(long, value_parser)]
+        data_dir: PathBuf,
+
+        #[clap(long, value_parser)]
+        checkpoint_dir: PathBuf,
+
+        /// Maximum number of blocks to replay, all the blocks of the source DB if not set
+        #[clap(long)]
+        blocks: Option<usize>,
     },
     AddAccounts {
         #[clap(long, value_parser)]
@@ -226,7 +587,7 @@ enum Command {
     },
 }
 
-fn run<E>(opt: Opt)
+fn run<E>(opt: Opt, seed: u64)
 where
     E: TransactionBlockExecutor + 'static,
 {
@@ -251,15 +612,57 @@ where
         },
         Command::RunExecutor {
             blocks,
+            warmup_blocks,
+            measure_blocks,
             main_signer_accounts,
             additional_dst_pool_accounts,
             transaction_type,
             transaction_weights,
+            entry_function_workload,
+            workload,
             module_working_set_size,
             data_dir,
             checkpoint_dir,
+            run_manifest,
+            prepopulate,
+            prepopulate_snapshot,
+            prepopulate_account_balance,
         } => {
-            let transaction_mix = if transaction_type.is_empty() {
+            if let Some(num_accounts) = prepopulate {
+                aptos_executor_benchmark::prepopulate::prepopulate_db::<E>(
+                    num_accounts,
+                    prepopulate_account_balance,
+                    opt.block_size,
+                    &data_dir,
+                    prepopulate_snapshot,
+                    opt.pruner_opt.pruner_config(),
+                    opt.split_ledger_db,
+                    opt.use_sharded_state_merkle_db,
+                    opt.skip_index_and_usage,
+                    opt.pipeline_opt.num_executor_shards,
+                );
+            }
+
+            let transaction_mix = if let Some(path) = entry_function_workload {
+                let workload = EntryFunctionWorkload::load_config(&path)
+                    .expect("Failed to load entry function workload");
+                Some(vec![(
+                    TransactionType::EntryFunctions {
+                        workload: Arc::new(workload),
+                        use_account_pool: false,
+                    },
+                    1,
+                )])
+            } else if !workload.is_empty() {
+                Some(
+                    WorkloadShareArg::shares_to_transaction_mix(
+                        &workload,
+                        module_working_set_size,
+                        false,
+                    )
+                    .expect("Invalid workload"),
+                )
+            } else if transaction_type.is_empty() {
                 None
             } else {
                 let mix_per_phase = TransactionTypeArg::args_to_transaction_mix_per_phase(
@@ -273,6 +676,42 @@ where
                 Some(mix_per_phase[0].clone())
             };
 
+            let blocks =
+                measure_blocks.map_or(blocks, |measure_blocks| warmup_blocks + measure_blocks);
+            let mut pipeline_config = opt.pipeline_opt.pipeline_config();
+            pipeline_config.warmup_blocks = warmup_blocks;
+
+            let manifest = RunManifest {
+                seed,
+                block_size: opt.block_size,
+                num_blocks: blocks,
+                warmup_blocks,
+                workload: transaction_mix.as_ref().map_or_else(
+                    || "raw coin transfer".to_string(),
+                    |transaction_mix| format!("{:?}", transaction_mix),
+                ),
+                concurrency_level: AptosVM::get_concurrency_level(),
+                num_executor_shards: opt.pipeline_opt.num_executor_shards,
+                thread_pools: ThreadPoolKind::ALL
+                    .iter()
+                    .map(|kind| {
+                        let spec = get_thread_pool_spec(*kind);
+                        ThreadPoolConfig {
+                            kind: format!("{:?}", kind),
+                            num_threads: spec.num_threads.expect("Filled in if not configured"),
+                            pinned_cores: spec.pinned_cores,
+                        }
+                    })
+                    .collect(),
+                git_rev: aptos_build_info::get_git_hash(),
+            };
+            manifest.log();
+            if let Some(path) = run_manifest {
+                manifest
+                    .write_json(&path)
+                    .expect("Failed to write the run manifest");
+            }
+
             aptos_executor_benchmark::run_benchmark::<E>(
                 opt.block_size,
                 blocks,
@@ -287,9 +726,27 @@ where
                 opt.split_ledger_db,
                 opt.use_sharded_state_merkle_db,
                 opt.skip_index_and_usage,
-                opt.pipeline_opt.pipeline_config(),
+                pipeline_config,
             );
         },
+        Command::ReplayBlocks {
+            source_db_dir,
+            data_dir,
+            checkpoint_dir,
+            blocks,
+        } => {
+            aptos_executor_benchmark::replay::replay_blocks::<E>(
+                source_db_dir,
+                data_dir,
+                checkpoint_dir,
+                blocks,
+                opt.pruner_opt.pruner_config(),
+                opt.split_ledger_db,
+                opt.use_sharded_state_merkle_db,
+                opt.skip_index_and_usage,
+            )
+            .expect("Replay failed");
+        },
         Command::AddAccounts {
             data_dir,
             checkpoint_dir,
@@ -315,7 +772,9 @@ where
 
 fn main() {
     let opt = Opt::parse();
-    aptos_logger::Logger::new().init();
+    aptos_logger::Logger::new()
+        .tokio_console_port(opt.tokio_console_port)
+        .init();
     START_TIME.set(
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -328,14 +787,35 @@ fn main() {
         .thread_name(|index| format!("rayon-global-{}", index))
         .build_global()
         .expect("Failed to build rayon global thread pool.");
+    opt.thread_pool_opt.set_thread_pool_specs();
     AptosVM::set_concurrency_level_once(opt.concurrency_level());
     AptosVM::set_num_shards_once(opt.pipeline_opt.num_executor_shards);
+    AptosVM::set_simulated_shard_network_once(opt.pipeline_opt.simulated_shard_network());
+    AptosVM::set_cross_shard_batch_config_once(opt.pipeline_opt.cross_shard_batch_config());
+    AptosVM::set_cross_shard_receive_timeout_once(Duration::from_secs(
+        opt.pipeline_opt.cross_shard_receive_timeout_secs,
+    ));
+    AptosVM::set_speculative_cross_shard_reads_once(opt.pipeline_opt.speculative_cross_shard_reads);
+    AptosVM::set_delayed_delta_materialization_once(opt.delayed_delta_materialization);
+    AptosVM::set_gas_price_priority_hints_once(opt.gas_price_priority_hints);
+    AptosVM::set_shared_module_cache_once(opt.shared_module_cache);
+    AptosVM::set_scheduler_config_once(opt.scheduler_opt.scheduler_config());
     NativeExecutor::set_concurrency_level_once(opt.concurrency_level());
+    let seed = opt.seed.unwrap_or_else(rand::random);
+    set_rng_seed_once(seed);
+    if opt.profile {
+        enable_profiling();
+    }
+    let profile_chrome_trace = opt.profile_chrome_trace.clone();
 
     if opt.use_native_executor {
-        run::<NativeExecutor>(opt);
+        run::<NativeExecutor>(opt, seed);
     } else {
-        run::<AptosVM>(opt);
+        run::<AptosVM>(opt, seed);
+    }
+
+    if let Some(path) = profile_chrome_trace {
+        write_chrome_trace(&path).expect("Failed to write the Chrome trace");
     }
 }
 
@@ -0,0 +1,189 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bounded channels between the stages of the pipeline, exposing how many messages are queued in
+//! them, and how long the producers wait for room in them (i.e. the backpressure they get) and the
+//! consumers wait for messages (i.e. how starved they are).
+
+use crate::metrics::{
+    PIPELINE_QUEUE_DEPTH, PIPELINE_RECV_BLOCKED_SECONDS, PIPELINE_SEND_BLOCKED_SECONDS,
+};
+use aptos_metrics_core::{Histogram, IntGauge};
+use serde::Serialize;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvError, SendError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+pub struct MeteredSender<T> {
+    inner: mpsc::SyncSender<T>,
+    queue_depth: IntGauge,
+    send_blocked_seconds: Histogram,
+    stats: ChannelStats,
+}
+
+impl<T> Clone for MeteredSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            queue_depth: self.queue_depth.clone(),
+            send_blocked_seconds: self.send_blocked_seconds.clone(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl<T> MeteredSender<T> {
+    /// Sends the message, blocking while the channel is full.
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        let start_time = Instant::now();
+        // Incremented before sending, so that the gauge never goes negative when the message is
+        // received before the send returns.
+        self.queue_depth.inc();
+        self.stats.on_send(self.queue_depth.get() as u64);
+        let result = self.inner.send(msg).map_err(|err| {
+            self.queue_depth.dec();
+            err
+        });
+        let blocked_time = start_time.elapsed();
+        self.send_blocked_seconds
+            .observe(blocked_time.as_secs_f64());
+        add_nanos(&self.stats.inner.send_blocked_nanos, blocked_time);
+        result
+    }
+
+    pub fn stats(&self) -> ChannelStats {
+        self.stats.clone()
+    }
+}
+
+pub struct MeteredReceiver<T> {
+    inner: mpsc::Receiver<T>,
+    queue_depth: IntGauge,
+    recv_blocked_seconds: Histogram,
+    stats: ChannelStats,
+}
+
+impl<T> MeteredReceiver<T> {
+    /// Receives the next message, blocking while the channel is empty.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let start_time = Instant::now();
+        let result = self.inner.recv();
+        let blocked_time = start_time.elapsed();
+        self.recv_blocked_seconds
+            .observe(blocked_time.as_secs_f64());
+        add_nanos(&self.stats.inner.recv_blocked_nanos, blocked_time);
+        let msg = result?;
+        self.queue_depth.dec();
+        Ok(msg)
+    }
+}
+
+fn add_nanos(counter: &AtomicU64, duration: Duration) {
+    counter.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// Statistics of a channel over its lifetime, shared by its sender and receiver.
+#[derive(Clone, Default)]
+pub struct ChannelStats {
+    inner: Arc<ChannelStatsInner>,
+}
+
+#[derive(Default)]
+struct ChannelStatsInner {
+    num_sent: AtomicU64,
+    /// Sum of the queue depths seen by the sends (including the message sent), for the average.
+    queue_depth_sum: AtomicU64,
+    max_queue_depth: AtomicU64,
+    send_blocked_nanos: AtomicU64,
+    recv_blocked_nanos: AtomicU64,
+}
+
+impl ChannelStats {
+    fn on_send(&self, queue_depth: u64) {
+        let inner = &self.inner;
+        inner.num_sent.fetch_add(1, Ordering::Relaxed);
+        inner
+            .queue_depth_sum
+            .fetch_add(queue_depth, Ordering::Relaxed);
+        inner
+            .max_queue_depth
+            .fetch_max(queue_depth, Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> ChannelReport {
+        let inner = &self.inner;
+        let num_sent = inner.num_sent.load(Ordering::Relaxed);
+        ChannelReport {
+            num_sent,
+            avg_queue_depth: inner.queue_depth_sum.load(Ordering::Relaxed) as f64
+                / num_sent.max(1) as f64,
+            max_queue_depth: inner.max_queue_depth.load(Ordering::Relaxed),
+            send_stall_secs: inner.send_blocked_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+            recv_stall_secs: inner.recv_blocked_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct ChannelReport {
+    pub num_sent: u64,
+    /// Queue depths are sampled when sending, counting the message sent.
+    pub avg_queue_depth: f64,
+    pub max_queue_depth: u64,
+    /// Total time the producing stage was blocked on a full channel, i.e. held back by the
+    /// consuming stage.
+    pub send_stall_secs: f64,
+    /// Total time the consuming stage was blocked on an empty channel, i.e. starved by the
+    /// producing stage.
+    pub recv_stall_secs: f64,
+}
+
+/// Creates a channel holding at most `bound` messages, with its metrics labeled by `name`.
+pub fn bounded<T>(name: &str, bound: usize) -> (MeteredSender<T>, MeteredReceiver<T>) {
+    let queue_depth = PIPELINE_QUEUE_DEPTH.with_label_values(&[name]);
+    queue_depth.set(0);
+    let stats = ChannelStats::default();
+    let (sender, receiver) = mpsc::sync_channel(bound);
+    (
+        MeteredSender {
+            inner: sender,
+            queue_depth: queue_depth.clone(),
+            send_blocked_seconds: PIPELINE_SEND_BLOCKED_SECONDS.with_label_values(&[name]),
+            stats: stats.clone(),
+        },
+        MeteredReceiver {
+            inner: receiver,
+            queue_depth,
+            recv_blocked_seconds: PIPELINE_RECV_BLOCKED_SECONDS.with_label_values(&[name]),
+            stats,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_report() {
+        let (sender, receiver) = bounded::<u64>("test_channel_report", 10);
+        for i in 0..4 {
+            sender.send(i).unwrap();
+        }
+        for i in 0..4 {
+            assert_eq!(receiver.recv().unwrap(), i);
+        }
+        sender.send(4).unwrap();
+
+        let report = sender.stats().report();
+        assert_eq!(report.num_sent, 5);
+        assert_eq!(report.max_queue_depth, 4);
+        // Depths 1, 2, 3, 4, then 1 after draining the channel.
+        assert_eq!(report.avg_queue_depth, 11.0 / 5.0);
+    }
+}
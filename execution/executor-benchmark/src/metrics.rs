@@ -3,7 +3,10 @@
 
 #![forbid(unsafe_code)]
 
-use aptos_metrics_core::{exponential_buckets, register_histogram_vec, HistogramVec};
+use aptos_metrics_core::{
+    exponential_buckets, register_gauge_vec, register_histogram_vec, register_int_gauge_vec,
+    GaugeVec, HistogramVec, IntGaugeVec,
+};
 use once_cell::sync::Lazy;
 
 pub static TIMER: Lazy<HistogramVec> = Lazy::new(|| {
@@ -15,3 +18,41 @@ pub static TIMER: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static PIPELINE_QUEUE_DEPTH: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_executor_benchmark_pipeline_queue_depth",
+        "Number of messages queued in the channels between the stages of the pipeline.",
+        &["channel"],
+    )
+    .unwrap()
+});
+
+pub static PIPELINE_SEND_BLOCKED_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_executor_benchmark_pipeline_send_blocked_seconds",
+        "Time the stages of the pipeline spend waiting for room in the next channel.",
+        &["channel"],
+        exponential_buckets(/*start=*/ 1e-6, /*factor=*/ 2.0, /*count=*/ 28).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static PIPELINE_RECV_BLOCKED_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_executor_benchmark_pipeline_recv_blocked_seconds",
+        "Time the stages of the pipeline spend waiting for messages from the previous channel.",
+        &["channel"],
+        exponential_buckets(/*start=*/ 1e-6, /*factor=*/ 2.0, /*count=*/ 28).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static BLOCK_METRICS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "aptos_executor_benchmark_block_metrics",
+        "Metrics of the latest block committed by the pipeline.",
+        &["name"],
+    )
+    .unwrap()
+});
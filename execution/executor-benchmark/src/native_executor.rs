@@ -414,6 +414,7 @@ impl TransactionBlockExecutor for NativeExecutor {
         Ok(ChunkOutput {
             transactions,
             transaction_outputs,
+            committed_output_hashes: vec![],
             state_cache: state_view.into_state_cache(),
         })
     }
@@ -0,0 +1,179 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sinks the per block metrics of the pipeline are written to as the blocks are committed, for
+//! runs to be consumed by tools (e.g. the dashboards of nightly runs) rather than read from the
+//! logs.
+
+use crate::metrics::BLOCK_METRICS;
+use anyhow::Result;
+use aptos_logger::info;
+use aptos_push_metrics::MetricsPusher;
+use serde::Serialize;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Minimum interval between two pushes to the pushgateway, the gateway only keeping the latest
+/// values anyway.
+const PUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Metrics of a committed block, reported by the execution and commit stages.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct BlockMetrics {
+    /// Index of the block in the run.
+    pub block_index: usize,
+    /// Version of the last transaction of the block.
+    pub version: u64,
+    pub num_txns: usize,
+    pub partition_secs: f64,
+    pub execution_secs: f64,
+    pub commit_secs: f64,
+    pub end_to_end_secs: f64,
+    /// Gas used by the transactions of the block.
+    pub gas_used: u64,
+    /// Throughput since the start of the first block.
+    pub cumulative_tps: f64,
+}
+
+pub trait OutputSink: Send {
+    fn record_block(&mut self, metrics: &BlockMetrics) -> Result<()>;
+
+    /// Called once all the blocks are recorded.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes a row per block to a CSV file.
+pub struct CsvSink {
+    writer: csv::Writer<File>,
+}
+
+impl CsvSink {
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: csv::Writer::from_path(path)?,
+        })
+    }
+}
+
+impl OutputSink for CsvSink {
+    fn record_block(&mut self, metrics: &BlockMetrics) -> Result<()> {
+        self.writer.serialize(metrics)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Sets the metrics of the latest block as gauges, and pushes all the metrics to a Prometheus
+/// pushgateway.
+pub struct PushgatewaySink {
+    endpoint: String,
+    last_push_time: Option<Instant>,
+}
+
+impl PushgatewaySink {
+    pub fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            last_push_time: None,
+        }
+    }
+
+    fn push(&mut self) {
+        MetricsPusher::push(&self.endpoint, None, &[]);
+        self.last_push_time = Some(Instant::now());
+    }
+}
+
+impl OutputSink for PushgatewaySink {
+    fn record_block(&mut self, metrics: &BlockMetrics) -> Result<()> {
+        for (name, value) in [
+            ("version", metrics.version as f64),
+            ("num_txns", metrics.num_txns as f64),
+            ("partition_secs", metrics.partition_secs),
+            ("execution_secs", metrics.execution_secs),
+            ("commit_secs", metrics.commit_secs),
+            ("end_to_end_secs", metrics.end_to_end_secs),
+            ("gas_used", metrics.gas_used as f64),
+            ("cumulative_tps", metrics.cumulative_tps),
+        ] {
+            BLOCK_METRICS.with_label_values(&[name]).set(value);
+        }
+        if self.last_push_time.map_or(true, |last_push_time| {
+            last_push_time.elapsed() >= PUSH_INTERVAL
+        }) {
+            self.push();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.push();
+        Ok(())
+    }
+}
+
+/// The sinks to write the per block metrics to.
+#[derive(Clone, Debug, Default)]
+pub struct OutputSinkConfig {
+    pub csv_path: Option<PathBuf>,
+    /// e.g. "http://pushgateway.server.com:9091/metrics/job/executor_benchmark"
+    pub pushgateway_endpoint: Option<String>,
+}
+
+impl OutputSinkConfig {
+    pub fn build(&self) -> Result<Vec<Box<dyn OutputSink>>> {
+        let mut sinks: Vec<Box<dyn OutputSink>> = vec![];
+        if let Some(path) = &self.csv_path {
+            info!("Writing the metrics of the blocks to {}", path.display());
+            sinks.push(Box::new(CsvSink::create(path)?));
+        }
+        if let Some(endpoint) = &self.pushgateway_endpoint {
+            info!("Pushing the metrics of the blocks to {}", endpoint);
+            sinks.push(Box::new(PushgatewaySink::new(endpoint.clone())));
+        }
+        Ok(sinks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+
+    #[test]
+    fn test_csv_sink() {
+        let path = TempPath::new();
+        let mut sink = CsvSink::create(path.path()).unwrap();
+        for block_index in 0..2 {
+            sink.record_block(&BlockMetrics {
+                block_index,
+                version: 10 * (block_index as u64 + 1),
+                num_txns: 10,
+                partition_secs: 0.5,
+                execution_secs: 1.0,
+                commit_secs: 0.25,
+                end_to_end_secs: 2.0,
+                gas_used: 100,
+                cumulative_tps: 5.0,
+            })
+            .unwrap();
+        }
+        sink.finish().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(path.path()).unwrap(),
+            "block_index,version,num_txns,partition_secs,execution_secs,commit_secs,end_to_end_secs,gas_used,cumulative_tps\n\
+             0,10,10,0.5,1.0,0.25,2.0,100,5.0\n\
+             1,20,10,0.5,1.0,0.25,2.0,100,5.0\n"
+        );
+    }
+}
@@ -2,19 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_partitioning::BlockPartitioningStage, GasMesurement, TransactionCommitter,
-    TransactionExecutor,
+    block_metadata_generator::BlockMetadataGenerator,
+    block_partitioning::BlockPartitioningStage,
+    metered_channel::{self, ChannelStats, MeteredSender},
+    output_sink::OutputSinkConfig,
+    transaction_executor::MeasurementSummary,
+    GasMesurement, TransactionCommitter, TransactionExecutor,
 };
+use aptos_block_partitioner::PartitionerType;
 use aptos_crypto::HashValue;
 use aptos_executor::block_executor::{BlockExecutor, TransactionBlockExecutor};
 use aptos_executor_types::BlockExecutorTrait;
 use aptos_logger::info;
 use aptos_types::{
-    block_executor::partitioner::ExecutableBlock,
+    block_executor::{partitioner::ExecutableBlock, shard_load::DynamicShardingConfig},
     transaction::{Transaction, Version},
 };
 use std::{
     marker::PhantomData,
+    path::PathBuf,
     sync::{
         mpsc::{self, SyncSender},
         Arc,
@@ -32,12 +38,66 @@ pub struct PipelineConfig {
     pub allow_aborts: bool,
     pub num_executor_shards: usize,
     pub async_partitioning: bool,
+    pub generate_block_metadata: bool,
+    /// Collects the transactions the partitioner cannot place without cross shard dependencies in
+    /// a final round executed by a single shard.
+    pub partition_conflict_tail: bool,
+    /// The strategy partitioning the blocks across the executor shards.
+    pub partitioner_type: PartitionerType,
+    /// If set, the number of shards of each block changes within the bounds of the config, given
+    /// the execution time of the shards for the previous blocks.
+    pub dynamic_sharding: Option<DynamicShardingConfig>,
+    pub channel_sizes: PipelineChannelSizes,
+    /// If set, the latency percentiles of the stages of the pipeline are written there as JSON.
+    pub latency_report_path: Option<PathBuf>,
+    /// Number of blocks excluded from the reported throughput and latencies, while caches warm
+    /// up.
+    pub warmup_blocks: usize,
+    /// Fraction of the generated transactions replaced with ones which are discarded or abort.
+    pub injected_failure_ratio: f64,
+    /// Runs all the stages concurrently from the start (i.e. excludes the options holding stages
+    /// back until the previous ones are done), and reports the backpressure between the stages.
+    pub streaming: bool,
+    /// Where the committer writes the metrics of each block to.
+    pub output_sinks: OutputSinkConfig,
+}
+
+/// Capacities (in blocks) of the channels between the stages of the pipeline. A stage blocks when
+/// the channel to the next stage is full, so that slow stages apply backpressure to the previous
+/// ones instead of letting the queued blocks grow.
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineChannelSizes {
+    /// From the transaction generator to the partitioning stage.
+    pub generated_blocks: usize,
+    /// From the partitioning stage to the execution stage, with async partitioning.
+    pub partitioned_blocks: usize,
+    /// From the execution stage to the commit stage.
+    pub executed_blocks: usize,
+}
+
+impl Default for PipelineChannelSizes {
+    fn default() -> Self {
+        Self {
+            generated_blocks: 50,
+            partitioned_blocks: 3,
+            executed_blocks: 3,
+        }
+    }
 }
 
 pub struct Pipeline<V> {
     join_handles: Vec<JoinHandle<()>>,
     phantom: PhantomData<V>,
     start_execution_tx: Option<SyncSender<()>>,
+    /// The channels to report the backpressure of when streaming, with the stages they connect.
+    streaming_channels: Vec<StreamingChannel>,
+}
+
+struct StreamingChannel {
+    name: &'static str,
+    producer: &'static str,
+    consumer: &'static str,
+    stats: ChannelStats,
 }
 
 impl<V> Pipeline<V>
@@ -50,27 +110,35 @@ where
         config: PipelineConfig,
         // Need to specify num blocks, to size queues correctly, when delay_execution_start, split_stages or skip_commit are used
         num_blocks: Option<usize>,
-    ) -> (Self, mpsc::SyncSender<Vec<Transaction>>) {
+    ) -> (Self, MeteredSender<Vec<Transaction>>) {
+        assert!(
+            !config.streaming
+                || !(config.delay_execution_start || config.split_stages || config.skip_commit),
+            "Streaming runs all the stages concurrently, stages can't be delayed or skipped",
+        );
         let parent_block_id = executor.committed_block_id();
         let executor_1 = Arc::new(executor);
         let executor_2 = executor_1.clone();
 
-        let (raw_block_sender, raw_block_receiver) = mpsc::sync_channel::<Vec<Transaction>>(
+        let channel_sizes = config.channel_sizes;
+        let (raw_block_sender, raw_block_receiver) = metered_channel::bounded::<Vec<Transaction>>(
+            "generated_blocks",
             if config.delay_execution_start {
-                (num_blocks.unwrap() + 1).max(50)
+                (num_blocks.unwrap() + 1).max(channel_sizes.generated_blocks)
             } else {
-                50
+                channel_sizes.generated_blocks
             }, /* bound */
         );
 
         // Assume the distributed executor and the distributed partitioner share the same worker set.
         let num_partitioner_shards = config.num_executor_shards;
 
-        let (commit_sender, commit_receiver) = mpsc::sync_channel::<CommitBlockMessage>(
+        let (commit_sender, commit_receiver) = metered_channel::bounded::<CommitBlockMessage>(
+            "executed_blocks",
             if config.split_stages || config.skip_commit {
-                (num_blocks.unwrap() + 1).max(3)
+                (num_blocks.unwrap() + 1).max(channel_sizes.executed_blocks)
             } else {
-                3
+                channel_sizes.executed_blocks
             }, /* bound */
         );
 
@@ -89,8 +157,27 @@ where
         };
 
         let mut join_handles = vec![];
+        let mut streaming_channels = vec![];
+        if config.streaming {
+            streaming_channels.push(StreamingChannel {
+                name: "generated_blocks",
+                producer: "generation",
+                consumer: "partitioning",
+                stats: raw_block_sender.stats(),
+            });
+        }
+        let executed_blocks_stats = commit_sender.stats();
 
-        let mut partitioning_stage = BlockPartitioningStage::new(num_partitioner_shards);
+        let maybe_block_metadata_generator = config
+            .generate_block_metadata
+            .then(|| BlockMetadataGenerator::from_db(&executor_1.db.reader));
+        let mut partitioning_stage = BlockPartitioningStage::new(
+            num_partitioner_shards,
+            config.partitioner_type,
+            config.partition_conflict_tail,
+            config.dynamic_sharding,
+            maybe_block_metadata_generator,
+        );
 
         let mut exe = TransactionExecutor::new(
             executor_1,
@@ -99,11 +186,23 @@ where
             Some(commit_sender),
             config.allow_discards,
             config.allow_aborts,
+            config.warmup_blocks,
         );
 
         if config.async_partitioning {
             let (executable_block_sender, executable_block_receiver) =
-                mpsc::sync_channel::<ExecuteBlockMessage>(3);
+                metered_channel::bounded::<ExecuteBlockMessage>(
+                    "partitioned_blocks",
+                    channel_sizes.partitioned_blocks,
+                );
+            if config.streaming {
+                streaming_channels.push(StreamingChannel {
+                    name: "partitioned_blocks",
+                    producer: "partitioning",
+                    consumer: "execution",
+                    stats: executable_block_sender.stats(),
+                });
+            }
 
             let partitioning_thread = std::thread::Builder::new()
                 .name("block_partitioning".to_string())
@@ -129,7 +228,7 @@ where
                             partition_time,
                             block,
                         } = msg;
-                        let block_size = block.transactions.num_transactions();
+                        let block_size = block.num_transactions();
                         info!("Received block of size {:?} to execute", block_size);
                         executed += block_size;
                         exe.execute_block(current_block_start_time, partition_time, block);
@@ -154,6 +253,7 @@ where
                         delta_gas / (delta_gas_count as f64).max(1.0),
                         executed
                     );
+                    log_measurement_summary(&exe.measurement_summary());
 
                     start_commit_tx.map(|tx| tx.send(()));
                 })
@@ -177,7 +277,7 @@ where
                             partition_time,
                             block,
                         } = partitioning_stage.process(raw_block);
-                        let block_size = block.transactions.num_transactions();
+                        let block_size = block.num_transactions();
                         executed += block_size;
                         exe.execute_block(current_block_start_time, partition_time, block);
                         info!("Finished executing block");
@@ -201,6 +301,7 @@ where
                         delta_gas / (delta_gas_count as f64).max(1.0),
                         executed
                     );
+                    log_measurement_summary(&exe.measurement_summary());
 
                     start_commit_tx.map(|tx| tx.send(()));
                 })
@@ -208,7 +309,22 @@ where
             join_handles.push(par_exe_thread);
         }
 
+        if config.streaming {
+            streaming_channels.push(StreamingChannel {
+                name: "executed_blocks",
+                producer: "execution",
+                consumer: "commit",
+                stats: executed_blocks_stats,
+            });
+        }
+
         let skip_commit = config.skip_commit;
+        let latency_report_path = config.latency_report_path.clone();
+        let warmup_blocks = config.warmup_blocks;
+        let output_sinks = config
+            .output_sinks
+            .build()
+            .expect("Failed to create the output sinks");
 
         let commit_thread = std::thread::Builder::new()
             .name("txn_committer".to_string())
@@ -216,8 +332,14 @@ where
                 start_commit_rx.map(|rx| rx.recv());
                 info!("Starting commit thread");
                 if !skip_commit {
-                    let mut committer =
-                        TransactionCommitter::new(executor_2, version, commit_receiver);
+                    let mut committer = TransactionCommitter::new(
+                        executor_2,
+                        version,
+                        commit_receiver,
+                        latency_report_path,
+                        warmup_blocks,
+                        output_sinks,
+                    );
                     committer.run();
                 }
             })
@@ -229,6 +351,7 @@ where
                 join_handles,
                 phantom: PhantomData,
                 start_execution_tx,
+                streaming_channels,
             },
             raw_block_sender,
         )
@@ -242,9 +365,36 @@ where
         for handle in self.join_handles {
             handle.join().unwrap()
         }
+        for channel in &self.streaming_channels {
+            let report = channel.stats.report();
+            info!(
+                "Backpressure on {} ({} -> {}): queue depth avg {:.2}, max {} (over {} blocks). {} stalled {:.3}s waiting for room, {} stalled {:.3}s waiting for blocks",
+                channel.name,
+                channel.producer,
+                channel.consumer,
+                report.avg_queue_depth,
+                report.max_queue_depth,
+                report.num_sent,
+                channel.producer,
+                report.send_stall_secs,
+                channel.consumer,
+                report.recv_stall_secs,
+            );
+        }
     }
 }
 
+fn log_measurement_summary(summary: &MeasurementSummary) {
+    info!(
+        "Steady state execution TPS: {} txn/s (over {} txns, after {} warm-up blocks)",
+        summary.tps, summary.measured_txns, summary.warmup_blocks
+    );
+    info!(
+        "Measurement summary: {}",
+        serde_json::to_string(summary).expect("Summary must serialize")
+    );
+}
+
 /// Message from partitioning stage to execution stage.
 pub struct ExecuteBlockMessage {
     pub current_block_start_time: Instant,
@@ -0,0 +1,163 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pre-population of the DB a benchmark runs on with a large number of accounts, so that execution
+//! and commit are measured against a state (and a JMT) of a realistic size rather than a nearly
+//! empty one.
+//!
+//! Creating millions of accounts takes a while, so the populated DB can be saved to a snapshot
+//! directory, and later runs restore it from there (as a RocksDB checkpoint, i.e. mostly hard
+//! links) instead of creating the accounts again.
+
+use crate::{
+    add_accounts_impl, create_checkpoint,
+    db_generator::create_db_with_accounts,
+    output_sink::OutputSinkConfig,
+    pipeline::{PipelineChannelSizes, PipelineConfig},
+    transaction_generator::{TransactionGenerator, META_FILENAME},
+};
+use aptos_block_partitioner::PartitionerType;
+use aptos_config::config::PrunerConfig;
+use aptos_executor::block_executor::TransactionBlockExecutor;
+use aptos_logger::info;
+use std::{fs, path::Path};
+
+/// Makes sure the DB at `data_dir` holds at least `num_accounts` accounts, restoring it from
+/// `snapshot_dir` if the snapshot holds enough of them, and creating the missing ones (and then
+/// saving the DB to `snapshot_dir`) otherwise.
+#[allow(clippy::too_many_arguments)]
+pub fn prepopulate_db<V>(
+    num_accounts: usize,
+    init_account_balance: u64,
+    block_size: usize,
+    data_dir: impl AsRef<Path>,
+    snapshot_dir: Option<impl AsRef<Path>>,
+    pruner_config: PrunerConfig,
+    split_ledger_db: bool,
+    use_sharded_state_merkle_db: bool,
+    skip_index_and_usage: bool,
+    num_executor_shards: usize,
+) where
+    V: TransactionBlockExecutor + 'static,
+{
+    let data_dir = data_dir.as_ref();
+    let num_existing_accounts = TransactionGenerator::read_meta(&data_dir);
+    if num_existing_accounts >= num_accounts {
+        info!(
+            "{} holds {} accounts already, no need to prepopulate it",
+            data_dir.display(),
+            num_existing_accounts
+        );
+        return;
+    }
+
+    if let Some(snapshot_dir) = &snapshot_dir {
+        let snapshot_dir = snapshot_dir.as_ref();
+        let num_snapshot_accounts = TransactionGenerator::read_meta(&snapshot_dir);
+        if num_snapshot_accounts >= num_accounts {
+            info!(
+                "Restoring {} from the snapshot at {} ({} accounts)",
+                data_dir.display(),
+                snapshot_dir.display(),
+                num_snapshot_accounts
+            );
+            copy_db(
+                snapshot_dir,
+                data_dir,
+                split_ledger_db,
+                use_sharded_state_merkle_db,
+            );
+            return;
+        }
+    }
+
+    // Prepopulation always commits all the accounts, regardless of how the measured blocks are
+    // processed.
+    let pipeline_config = PipelineConfig {
+        delay_execution_start: false,
+        split_stages: false,
+        skip_commit: false,
+        allow_discards: false,
+        allow_aborts: false,
+        num_executor_shards,
+        async_partitioning: false,
+        generate_block_metadata: false,
+        partition_conflict_tail: false,
+        partitioner_type: PartitionerType::default(),
+        dynamic_sharding: None,
+        channel_sizes: PipelineChannelSizes::default(),
+        latency_report_path: None,
+        warmup_blocks: 0,
+        injected_failure_ratio: 0.0,
+        streaming: false,
+        output_sinks: OutputSinkConfig::default(),
+    };
+    if data_dir.exists() {
+        info!(
+            "Adding {} accounts to the {} accounts of {}",
+            num_accounts - num_existing_accounts,
+            num_existing_accounts,
+            data_dir.display()
+        );
+        add_accounts_impl::<V>(
+            num_accounts - num_existing_accounts,
+            init_account_balance,
+            block_size,
+            data_dir,
+            data_dir,
+            pruner_config,
+            false, /* verify_sequence_numbers */
+            split_ledger_db,
+            use_sharded_state_merkle_db,
+            skip_index_and_usage,
+            pipeline_config,
+        );
+    } else {
+        create_db_with_accounts::<V>(
+            num_accounts,
+            init_account_balance,
+            block_size,
+            data_dir,
+            pruner_config,
+            false, /* verify_sequence_numbers */
+            split_ledger_db,
+            use_sharded_state_merkle_db,
+            skip_index_and_usage,
+            pipeline_config,
+        );
+    }
+
+    if let Some(snapshot_dir) = snapshot_dir {
+        info!(
+            "Saving the prepopulated DB to {}",
+            snapshot_dir.as_ref().display()
+        );
+        copy_db(
+            data_dir,
+            snapshot_dir.as_ref(),
+            split_ledger_db,
+            use_sharded_state_merkle_db,
+        );
+    }
+}
+
+/// Replaces the DB at `target_dir` with a checkpoint of the one at `source_dir`, along with the
+/// metadata of its accounts.
+fn copy_db(
+    source_dir: &Path,
+    target_dir: &Path,
+    split_ledger_db: bool,
+    use_sharded_state_merkle_db: bool,
+) {
+    create_checkpoint(
+        source_dir,
+        target_dir,
+        split_ledger_db,
+        use_sharded_state_merkle_db,
+    );
+    fs::copy(
+        source_dir.join(META_FILENAME),
+        target_dir.join(META_FILENAME),
+    )
+    .expect("Failed to copy the metadata of the DB");
+}
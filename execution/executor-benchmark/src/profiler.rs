@@ -0,0 +1,174 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Profiling of the partition, execution and commit stages of the pipeline. Once enabled, each
+//! stage of each block runs within a tracing span (visible to tracing subscribers such as
+//! tokio-console), and is recorded so that a timeline of the run can be written as a Chrome trace
+//! (to be opened in chrome://tracing or https://ui.perfetto.dev).
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::{
+    cell::Cell,
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+static PROFILER: OnceCell<Profiler> = OnceCell::new();
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: Cell<u64> = Cell::new(0);
+}
+
+struct Profiler {
+    start_time: Instant,
+    events: Mutex<Vec<TraceEvent>>,
+}
+
+/// An event of the Chrome trace event format, either a complete event ("X") of a stage, or a
+/// metadata event ("M") naming a thread.
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    ph: &'static str,
+    pid: u64,
+    tid: u64,
+    /// Start time since the profiler was enabled, in microseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<u64>,
+    /// Duration, in microseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<u64>,
+    args: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct ChromeTrace<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: &'a [TraceEvent],
+}
+
+/// Enables the profiling of the stages, for the rest of the process.
+pub fn enable_profiling() {
+    PROFILER
+        .set(Profiler {
+            start_time: Instant::now(),
+            events: Mutex::new(vec![]),
+        })
+        .ok();
+}
+
+/// Starts profiling a stage of the given block, until the returned guard is dropped. A no-op if
+/// profiling is not enabled.
+pub fn profile_stage(stage: &'static str, block_index: usize) -> Option<StageGuard> {
+    let profiler = PROFILER.get()?;
+    Some(StageGuard {
+        profiler,
+        stage,
+        block_index,
+        start_time: Instant::now(),
+        _span: tracing::info_span!("pipeline_stage", stage, block_index).entered(),
+    })
+}
+
+pub struct StageGuard {
+    profiler: &'static Profiler,
+    stage: &'static str,
+    block_index: usize,
+    start_time: Instant,
+    _span: tracing::span::EnteredSpan,
+}
+
+impl Drop for StageGuard {
+    fn drop(&mut self) {
+        let event = TraceEvent {
+            name: self.stage.to_string(),
+            ph: "X",
+            pid: 1,
+            tid: current_thread_id(self.profiler),
+            ts: Some(
+                self.start_time
+                    .duration_since(self.profiler.start_time)
+                    .as_micros() as u64,
+            ),
+            dur: Some(self.start_time.elapsed().as_micros() as u64),
+            args: serde_json::json!({ "block_index": self.block_index }),
+        };
+        self.profiler.events.lock().unwrap().push(event);
+    }
+}
+
+/// Returns a small id of the current thread, naming the thread in the trace the first time.
+fn current_thread_id(profiler: &Profiler) -> u64 {
+    THREAD_ID.with(|thread_id| {
+        if thread_id.get() == 0 {
+            thread_id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+            profiler.events.lock().unwrap().push(TraceEvent {
+                name: "thread_name".to_string(),
+                ph: "M",
+                pid: 1,
+                tid: thread_id.get(),
+                ts: None,
+                dur: None,
+                args: serde_json::json!({
+                    "name": thread::current().name().unwrap_or("unnamed"),
+                }),
+            });
+        }
+        thread_id.get()
+    })
+}
+
+/// Writes the stages recorded so far as a Chrome trace. Fails if profiling is not enabled.
+pub fn write_chrome_trace(path: &Path) -> anyhow::Result<()> {
+    let profiler = PROFILER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("Profiling is not enabled"))?;
+    let events = profiler.events.lock().unwrap();
+    serde_json::to_writer(
+        BufWriter::new(File::create(path)?),
+        &ChromeTrace {
+            trace_events: &events,
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+
+    #[test]
+    fn test_chrome_trace() {
+        enable_profiling();
+        thread::Builder::new()
+            .name("test_stage".to_string())
+            .spawn(|| {
+                let _guard = profile_stage("execute", 3);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let path = TempPath::new();
+        write_chrome_trace(path.path()).unwrap();
+        let trace: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.path()).unwrap()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert!(events
+            .iter()
+            .any(|event| event["ph"] == "M" && event["args"]["name"] == "test_stage"));
+        assert!(events.iter().any(|event| event["ph"] == "X"
+            && event["name"] == "execute"
+            && event["args"]["block_index"] == 3));
+    }
+}
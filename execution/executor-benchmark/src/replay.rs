@@ -0,0 +1,276 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay of historical blocks (e.g. from mainnet or testnet) through the block executor, to
+//! measure the performance of execution on real workloads and to check that it still produces
+//! the recorded results.
+//!
+//! The blocks are read from a source AptosDB (e.g. restored from a backup with the db-tool), and
+//! executed on top of a checkpoint of a second AptosDB holding the state at an earlier version
+//! (e.g. restored from a state snapshot at that version). Each block is replayed from the
+//! version following the latest one of that DB, and the transaction infos it produces are
+//! compared against the recorded ones.
+
+use crate::{create_checkpoint, init_db_and_executor};
+use anyhow::{bail, ensure, Result};
+use aptos_config::config::{NodeConfig, PrunerConfig, NO_OP_STORAGE_PRUNER_CONFIG};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_db::AptosDB;
+use aptos_executor::block_executor::TransactionBlockExecutor;
+use aptos_executor_types::{BlockExecutorTrait, StateCheckpointPolicy};
+use aptos_logger::info;
+use aptos_storage_interface::DbReader;
+use aptos_types::{
+    aggregate_signature::AggregateSignature,
+    block_executor::partitioner::{ExecutableBlock, ExecutableTransactions},
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use serde::Serialize;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Number of transactions read from the source DB at once.
+const READ_BATCH_SIZE: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct ReplaySummary {
+    pub first_version: Version,
+    pub last_version: Version,
+    pub num_blocks: usize,
+    pub num_txns: usize,
+    pub execution_secs: f64,
+    pub commit_secs: f64,
+    /// Throughput of execution alone, excluding the commits.
+    pub execution_tps: f64,
+}
+
+/// A block read from the source DB, with the recorded infos of its transactions.
+struct RecordedBlock {
+    first_version: Version,
+    txns: Vec<Transaction>,
+    txn_infos: Vec<TransactionInfo>,
+}
+
+impl RecordedBlock {
+    fn last_version(&self) -> Version {
+        self.first_version + self.txns.len() as Version - 1
+    }
+
+    fn block_id(&self) -> HashValue {
+        match self.txns.first() {
+            Some(Transaction::BlockMetadata(block_metadata)) => block_metadata.id(),
+            _ => self
+                .txn_infos
+                .last()
+                .map_or_else(HashValue::zero, |info| info.hash()),
+        }
+    }
+}
+
+/// Reads the transactions of the source DB, split into blocks. A block ends with a state
+/// checkpoint, i.e. with the checkpoint appended to the blocks proposed by consensus, or with a
+/// genesis or write set transaction.
+struct BlockReader<'a> {
+    db: &'a dyn DbReader,
+    next_version: Version,
+    last_version: Version,
+}
+
+impl<'a> BlockReader<'a> {
+    fn next_block(&mut self) -> Result<Option<RecordedBlock>> {
+        let first_version = self.next_version;
+        let mut txns = vec![];
+        let mut txn_infos = vec![];
+        while self.next_version <= self.last_version {
+            let limit = READ_BATCH_SIZE.min(self.last_version - self.next_version + 1);
+            let batch_txns = self.db.get_transaction_iterator(self.next_version, limit)?;
+            let batch_txn_infos = self
+                .db
+                .get_transaction_info_iterator(self.next_version, limit)?;
+            for (txn, txn_info) in batch_txns.zip(batch_txn_infos) {
+                let txn_info = txn_info?;
+                let is_state_checkpoint = txn_info.is_state_checkpoint();
+                txns.push(txn?);
+                txn_infos.push(txn_info);
+                self.next_version += 1;
+                if is_state_checkpoint {
+                    return Ok(Some(RecordedBlock {
+                        first_version,
+                        txns,
+                        txn_infos,
+                    }));
+                }
+            }
+        }
+        // Transactions following the last state checkpoint do not form a complete block.
+        Ok(None)
+    }
+}
+
+/// Replays up to `max_num_blocks` blocks of the DB at `source_dir`, on top of a checkpoint (at
+/// `checkpoint_dir`) of the DB at `data_dir`. Fails at the first transaction whose info differs
+/// from the recorded one.
+#[allow(clippy::too_many_arguments)]
+pub fn replay_blocks<V>(
+    source_dir: impl AsRef<Path>,
+    data_dir: impl AsRef<Path>,
+    checkpoint_dir: impl AsRef<Path>,
+    max_num_blocks: Option<usize>,
+    pruner_config: PrunerConfig,
+    split_ledger_db: bool,
+    use_sharded_state_merkle_db: bool,
+    skip_index_and_usage: bool,
+) -> Result<ReplaySummary>
+where
+    V: TransactionBlockExecutor + 'static,
+{
+    create_checkpoint(
+        data_dir.as_ref(),
+        checkpoint_dir.as_ref(),
+        split_ledger_db,
+        use_sharded_state_merkle_db,
+    );
+
+    let mut config = NodeConfig::default();
+    config.storage.dir = checkpoint_dir.as_ref().to_path_buf();
+    config.storage.storage_pruner_config = pruner_config;
+    config.storage.rocksdb_configs.split_ledger_db = split_ledger_db;
+    config.storage.rocksdb_configs.use_sharded_state_merkle_db = use_sharded_state_merkle_db;
+    config.storage.rocksdb_configs.skip_index_and_usage = skip_index_and_usage;
+    let (db, executor) = init_db_and_executor::<V>(&config);
+
+    let source_db = AptosDB::open(
+        source_dir.as_ref(),
+        true, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        config.storage.rocksdb_configs,
+        false, /* enable_indexer */
+        config.storage.buffered_state_target_items,
+        config.storage.max_num_nodes_per_lru_cache_shard,
+    )?;
+
+    let first_version = db.reader.get_latest_version()? + 1;
+    let source_last_version = source_db.get_latest_version()?;
+    ensure!(
+        first_version <= source_last_version,
+        "Source DB ends at version {}, nothing to replay after version {}",
+        source_last_version,
+        first_version - 1,
+    );
+    info!(
+        "Replaying blocks from version {} (source DB ends at version {})",
+        first_version, source_last_version
+    );
+
+    let mut reader = BlockReader {
+        db: &source_db,
+        next_version: first_version,
+        last_version: source_last_version,
+    };
+    let mut summary = ReplaySummary {
+        first_version,
+        last_version: first_version - 1,
+        ..ReplaySummary::default()
+    };
+    let mut execution_time = Duration::ZERO;
+    let mut commit_time = Duration::ZERO;
+    let mut parent_block_id = executor.committed_block_id();
+    let mut epoch = db
+        .reader
+        .get_latest_ledger_info()?
+        .ledger_info()
+        .next_block_epoch();
+    while max_num_blocks.map_or(true, |max_num_blocks| summary.num_blocks < max_num_blocks) {
+        let block = match reader.next_block()? {
+            Some(block) => block,
+            None => break,
+        };
+        let block_id = block.block_id();
+        let last_version = block.last_version();
+        let num_txns = block.txns.len();
+
+        let execution_start_time = Instant::now();
+        let output = executor.execute_block_ext(
+            ExecutableBlock::new(block_id, ExecutableTransactions::Unsharded(block.txns)),
+            parent_block_id,
+            None, /* maybe_block_gas_limit */
+            StateCheckpointPolicy::Explicit,
+        )?;
+        execution_time += execution_start_time.elapsed();
+
+        for (index, (txn_info_hash, recorded_txn_info)) in output
+            .transaction_info_hashes()
+            .iter()
+            .zip(&block.txn_infos)
+            .enumerate()
+        {
+            if *txn_info_hash != recorded_txn_info.hash() {
+                bail!(
+                    "Transaction info mismatch at version {}, recorded: {:?}",
+                    block.first_version + index as Version,
+                    recorded_txn_info,
+                );
+            }
+        }
+        ensure!(
+            output.transaction_info_hashes().len() == num_txns,
+            "Block at versions {}..={} committed {} transactions, {} recorded",
+            block.first_version,
+            last_version,
+            output.transaction_info_hashes().len(),
+            num_txns,
+        );
+        let expected_root_hash = source_db.get_accumulator_root_hash(last_version)?;
+        ensure!(
+            output.root_hash() == expected_root_hash,
+            "Accumulator root hash mismatch at version {}: {} instead of {}",
+            last_version,
+            output.root_hash(),
+            expected_root_hash,
+        );
+
+        let commit_start_time = Instant::now();
+        let block_info = BlockInfo::new(
+            epoch,
+            0, /* round, doesn't matter */
+            block_id,
+            output.root_hash(),
+            last_version,
+            0, /* timestamp_usecs, doesn't matter */
+            output.epoch_state().clone(),
+        );
+        let ledger_info_with_sigs = LedgerInfoWithSignatures::new(
+            LedgerInfo::new(block_info, HashValue::zero()),
+            AggregateSignature::empty(),
+        );
+        executor.commit_blocks(vec![block_id], ledger_info_with_sigs)?;
+        commit_time += commit_start_time.elapsed();
+
+        if let Some(epoch_state) = output.epoch_state() {
+            epoch = epoch_state.epoch;
+        }
+        parent_block_id = block_id;
+        summary.last_version = last_version;
+        summary.num_blocks += 1;
+        summary.num_txns += num_txns;
+        info!(
+            "Replayed block {} at versions {}..={} ({} txns)",
+            block_id, block.first_version, last_version, num_txns
+        );
+    }
+
+    summary.execution_secs = execution_time.as_secs_f64();
+    summary.commit_secs = commit_time.as_secs_f64();
+    if summary.execution_secs > 0.0 {
+        summary.execution_tps = summary.num_txns as f64 / summary.execution_secs;
+    }
+    info!(
+        "Replay summary: {}",
+        serde_json::to_string(&summary).expect("Summary must serialize")
+    );
+    Ok(summary)
+}
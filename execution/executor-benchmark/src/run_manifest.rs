@@ -0,0 +1,48 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! The parameters a benchmark run was started with, recorded next to its results so that the
+//! run can be reproduced (with the same seed) and runs can be compared.
+
+use aptos_logger::info;
+use serde::Serialize;
+use std::{fs, path::Path};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RunManifest {
+    /// Seed of the RNGs of the transaction generators.
+    pub seed: u64,
+    pub block_size: usize,
+    pub num_blocks: usize,
+    pub warmup_blocks: usize,
+    /// The transaction mix of the workload, or the default workload if none was given.
+    pub workload: String,
+    pub concurrency_level: usize,
+    pub num_executor_shards: usize,
+    pub thread_pools: Vec<ThreadPoolConfig>,
+    /// The git revision the benchmark was built from.
+    pub git_rev: String,
+}
+
+/// The configuration a dedicated thread pool (e.g. for execution or commit) was created with.
+#[derive(Clone, Debug, Serialize)]
+pub struct ThreadPoolConfig {
+    pub kind: String,
+    pub num_threads: usize,
+    /// Empty if the threads are not pinned.
+    pub pinned_cores: Vec<usize>,
+}
+
+impl RunManifest {
+    pub fn log(&self) {
+        info!(
+            "Run manifest: {}",
+            serde_json::to_string(self).expect("Manifest must serialize")
+        );
+    }
+
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -2,7 +2,14 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::pipeline::CommitBlockMessage;
+use crate::{
+    gas_report::GasCollector,
+    latency_report::{BlockLatencies, LatencyCollector},
+    metered_channel::MeteredReceiver,
+    output_sink::{BlockMetrics, OutputSink},
+    pipeline::CommitBlockMessage,
+    profiler::profile_stage,
+};
 use aptos_crypto::hash::HashValue;
 use aptos_db::metrics::API_LATENCY_SECONDS;
 use aptos_executor::{
@@ -21,7 +28,8 @@ use aptos_types::{
     transaction::Version,
 };
 use std::{
-    sync::{mpsc, Arc},
+    path::PathBuf,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -50,7 +58,12 @@ pub(crate) fn gen_li_with_sigs(
 pub struct TransactionCommitter<V> {
     executor: Arc<BlockExecutor<V>>,
     version: Version,
-    block_receiver: mpsc::Receiver<CommitBlockMessage>,
+    block_receiver: MeteredReceiver<CommitBlockMessage>,
+    latencies: LatencyCollector,
+    gas: GasCollector,
+    latency_report_path: Option<PathBuf>,
+    warmup_blocks: usize,
+    output_sinks: Vec<Box<dyn OutputSink>>,
 }
 
 impl<V> TransactionCommitter<V>
@@ -60,12 +73,20 @@ where
     pub fn new(
         executor: Arc<BlockExecutor<V>>,
         version: Version,
-        block_receiver: mpsc::Receiver<CommitBlockMessage>,
+        block_receiver: MeteredReceiver<CommitBlockMessage>,
+        latency_report_path: Option<PathBuf>,
+        warmup_blocks: usize,
+        output_sinks: Vec<Box<dyn OutputSink>>,
     ) -> Self {
         Self {
             version,
             executor,
             block_receiver,
+            latencies: LatencyCollector::default(),
+            gas: GasCollector::default(),
+            latency_report_path,
+            warmup_blocks,
+            output_sinks,
         }
     }
 
@@ -73,6 +94,8 @@ where
         let start_version = self.version;
         info!("Start with version: {}", start_version);
 
+        let mut num_blocks = 0;
+        let mut measurement_start_time = None;
         while let Ok(msg) = self.block_receiver.recv() {
             let CommitBlockMessage {
                 block_id,
@@ -83,12 +106,16 @@ where
                 execution_time,
                 num_txns,
             } = msg;
+            let first_version = self.version + 1;
             self.version += num_txns as u64;
+            let profile = profile_stage("commit", num_blocks);
             let commit_start = std::time::Instant::now();
             let ledger_info_with_sigs = gen_li_with_sigs(block_id, root_hash, self.version);
             self.executor
                 .commit_blocks_ext(vec![block_id], ledger_info_with_sigs, false)
                 .unwrap();
+            let commit_time = Instant::now().duration_since(commit_start);
+            drop(profile);
 
             report_block(
                 start_version,
@@ -97,10 +124,72 @@ where
                 current_block_start_time,
                 partition_time,
                 execution_time,
-                Instant::now().duration_since(commit_start),
+                commit_time,
                 num_txns,
             );
+            let end_to_end_time = Instant::now().duration_since(current_block_start_time);
+            let gas_used = self.block_gas_used(first_version, num_txns);
+            if num_blocks >= self.warmup_blocks {
+                measurement_start_time.get_or_insert(current_block_start_time);
+                for txn_gas_used in &gas_used {
+                    self.gas.record_txn(*txn_gas_used);
+                }
+                self.latencies.record_block(BlockLatencies {
+                    partition_time,
+                    execution_time,
+                    commit_time,
+                    end_to_end_time,
+                    num_txns,
+                });
+            }
+            let block_metrics = BlockMetrics {
+                block_index: num_blocks,
+                version: self.version,
+                num_txns,
+                partition_secs: partition_time.as_secs_f64(),
+                execution_secs: execution_time.as_secs_f64(),
+                commit_secs: commit_time.as_secs_f64(),
+                end_to_end_secs: end_to_end_time.as_secs_f64(),
+                gas_used: gas_used.iter().sum(),
+                cumulative_tps: (self.version - start_version) as f64
+                    / first_block_start_time.elapsed().as_secs_f64(),
+            };
+            for sink in &mut self.output_sinks {
+                sink.record_block(&block_metrics)
+                    .expect("Failed to record the metrics of the block");
+            }
+            num_blocks += 1;
         }
+        for sink in &mut self.output_sinks {
+            sink.finish().expect("Failed to finish the output sink");
+        }
+
+        self.gas
+            .report(
+                measurement_start_time.map_or(Duration::ZERO, |start_time| start_time.elapsed()),
+            )
+            .log();
+        let report = self.latencies.report();
+        report.log();
+        if let Some(path) = &self.latency_report_path {
+            report
+                .write_json(path)
+                .unwrap_or_else(|err| panic!("Failed to write {}: {}", path.display(), err));
+        }
+    }
+
+    /// Reads the gas used by the transactions of the committed block back from the DB.
+    fn block_gas_used(&self, first_version: Version, num_txns: usize) -> Vec<u64> {
+        self.executor
+            .db
+            .reader
+            .get_transaction_info_iterator(first_version, num_txns as u64)
+            .and_then(|txn_infos| {
+                txn_infos
+                    .map(|txn_info| txn_info.map(|txn_info| txn_info.gas_used()))
+                    .collect()
+            })
+            .expect("Failed to read the infos of the committed transactions")
     }
 }
 
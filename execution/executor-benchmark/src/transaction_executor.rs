@@ -2,14 +2,21 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::pipeline::CommitBlockMessage;
+use crate::{
+    metered_channel::MeteredSender, pipeline::CommitBlockMessage, profiler::profile_stage,
+};
 use aptos_crypto::hash::HashValue;
 use aptos_executor::block_executor::{BlockExecutor, TransactionBlockExecutor};
 use aptos_executor_types::BlockExecutorTrait;
 use aptos_logger::info;
 use aptos_types::{block_executor::partitioner::ExecutableBlock, transaction::Version};
+use aptos_vm::{
+    sharded_block_executor::counters::{CROSS_SHARD_MESSAGES, SHARD_BLOCK_EXECUTION_SECONDS},
+    AptosVM,
+};
+use serde::Serialize;
 use std::{
-    sync::{mpsc, Arc},
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -20,9 +27,81 @@ pub struct TransactionExecutor<V> {
     maybe_first_block_start_time: Option<Instant>,
     version: Version,
     // If commit_sender is `None`, we will commit all the execution result immediately in this struct.
-    commit_sender: Option<mpsc::SyncSender<CommitBlockMessage>>,
+    commit_sender: Option<MeteredSender<CommitBlockMessage>>,
     allow_discards: bool,
     allow_aborts: bool,
+    // Number of blocks executed before the measurement window starts.
+    warmup_blocks: usize,
+    measurement: MeasurementWindow,
+}
+
+/// Throughput of the execution stage, over the blocks executed after the warm-up blocks.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct MeasurementSummary {
+    pub warmup_blocks: usize,
+    pub measured_blocks: usize,
+    pub measured_txns: usize,
+    pub elapsed_secs: f64,
+    pub tps: f64,
+}
+
+/// Counters of a shard of the sharded executor, compared before and after a block to show how
+/// balanced the shards are.
+#[derive(Clone, Copy, Debug, Default)]
+struct ShardCounters {
+    execution_secs: f64,
+    messages_sent: u64,
+    messages_received: u64,
+}
+
+impl ShardCounters {
+    fn read(shard_id: usize) -> Self {
+        let shard_id = shard_id.to_string();
+        Self {
+            execution_secs: SHARD_BLOCK_EXECUTION_SECONDS
+                .with_label_values(&[&shard_id])
+                .get_sample_sum(),
+            messages_sent: CROSS_SHARD_MESSAGES
+                .with_label_values(&[&shard_id, "sent"])
+                .get(),
+            messages_received: CROSS_SHARD_MESSAGES
+                .with_label_values(&[&shard_id, "received"])
+                .get(),
+        }
+    }
+
+    fn read_all() -> Vec<Self> {
+        (0..AptosVM::get_num_shards()).map(Self::read).collect()
+    }
+}
+
+/// Logs the execution time, the idle time (i.e. the part of the execution of the block the shard
+/// did not spend executing its sub blocks, e.g. waiting for the other shards) and the cross shard
+/// messages of each shard, for a block.
+fn report_shards(start_counters: &[ShardCounters], block_execution_time: Duration) {
+    for (shard_id, (start, end)) in start_counters
+        .iter()
+        .zip(ShardCounters::read_all())
+        .enumerate()
+    {
+        let execution_secs = end.execution_secs - start.execution_secs;
+        info!(
+            "Shard {}: execution time: {:.0} ms, idle time: {:.0} ms, cross shard messages sent: {}, received: {}",
+            shard_id,
+            execution_secs * 1000.0,
+            (block_execution_time.as_secs_f64() - execution_secs).max(0.0) * 1000.0,
+            end.messages_sent - start.messages_sent,
+            end.messages_received - start.messages_received,
+        );
+    }
+}
+
+#[derive(Debug, Default)]
+struct MeasurementWindow {
+    start_time: Option<Instant>,
+    end_time: Option<Instant>,
+    num_blocks: usize,
+    num_txns: usize,
 }
 
 impl<V> TransactionExecutor<V>
@@ -33,9 +112,10 @@ where
         executor: Arc<BlockExecutor<V>>,
         parent_block_id: HashValue,
         version: Version,
-        commit_sender: Option<mpsc::SyncSender<CommitBlockMessage>>,
+        commit_sender: Option<MeteredSender<CommitBlockMessage>>,
         allow_discards: bool,
         allow_aborts: bool,
+        warmup_blocks: usize,
     ) -> Self {
         Self {
             num_blocks_processed: 0,
@@ -46,6 +126,28 @@ where
             commit_sender,
             allow_discards,
             allow_aborts,
+            warmup_blocks,
+            measurement: MeasurementWindow::default(),
+        }
+    }
+
+    /// Returns the throughput over the blocks executed after the warm-up blocks so far, so that
+    /// cold caches do not skew the throughput of short runs.
+    pub fn measurement_summary(&self) -> MeasurementSummary {
+        let elapsed_secs = match (self.measurement.start_time, self.measurement.end_time) {
+            (Some(start_time), Some(end_time)) => end_time.duration_since(start_time).as_secs_f64(),
+            _ => 0.0,
+        };
+        MeasurementSummary {
+            warmup_blocks: self.warmup_blocks.min(self.num_blocks_processed),
+            measured_blocks: self.measurement.num_blocks,
+            measured_txns: self.measurement.num_txns,
+            elapsed_secs,
+            tps: if elapsed_secs > 0.0 {
+                self.measurement.num_txns as f64 / elapsed_secs
+            } else {
+                0.0
+            },
         }
     }
 
@@ -55,6 +157,7 @@ where
         partition_time: Duration,
         executable_block: ExecutableBlock,
     ) {
+        let _profile = profile_stage("execute", self.num_blocks_processed);
         let execution_start_time = Instant::now();
         if self.maybe_first_block_start_time.is_none() {
             self.maybe_first_block_start_time = Some(current_block_start_time);
@@ -64,12 +167,24 @@ where
             "In iteration {}, received block {}.",
             self.num_blocks_processed, block_id
         );
-        let num_txns = executable_block.transactions.num_transactions();
+        let num_txns = executable_block.num_transactions();
+        let is_measured = self.num_blocks_processed >= self.warmup_blocks;
+        if is_measured && self.measurement.start_time.is_none() {
+            info!(
+                "Warm-up done after {} blocks, starting the measurement window.",
+                self.num_blocks_processed
+            );
+            self.measurement.start_time = Some(execution_start_time);
+        }
         self.version += num_txns as Version;
+        let shard_counters = (AptosVM::get_num_shards() > 1).then(ShardCounters::read_all);
         let output = self
             .executor
             .execute_block(executable_block, self.parent_block_id, None)
             .unwrap();
+        if let Some(shard_counters) = &shard_counters {
+            report_shards(shard_counters, execution_start_time.elapsed());
+        }
 
         assert_eq!(output.compute_status().len(), num_txns);
         let discards = output
@@ -142,5 +257,10 @@ where
         }
         self.parent_block_id = block_id;
         self.num_blocks_processed += 1;
+        if is_measured {
+            self.measurement.end_time = Some(Instant::now());
+            self.measurement.num_blocks += 1;
+            self.measurement.num_txns += num_txns - discards.len();
+        }
     }
 }
@@ -2,12 +2,16 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::account_generator::{AccountCache, AccountGenerator};
+use crate::{
+    account_generator::{AccountCache, AccountGenerator},
+    failure_injection::FailureInjector,
+    metered_channel::MeteredSender,
+};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue};
 use aptos_sdk::{transaction_builder::TransactionFactory, types::LocalAccount};
 use aptos_state_view::account_with_state_view::AsAccountWithStateView;
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader, DbReaderWriter};
-use aptos_transaction_generator_lib::TransactionGeneratorCreator;
+use aptos_transaction_generator_lib::{new_rng, TransactionGeneratorCreator};
 use aptos_types::{
     account_address::AccountAddress,
     account_config::aptos_test_root_address,
@@ -18,7 +22,7 @@ use aptos_types::{
 use chrono::Local;
 use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
-use rand::thread_rng;
+use rand::rngs::StdRng;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -26,10 +30,10 @@ use std::{
     io::{Read, Write},
     iter::once,
     path::Path,
-    sync::{mpsc, Arc},
+    sync::Arc,
 };
 
-const META_FILENAME: &str = "metadata.toml";
+pub(crate) const META_FILENAME: &str = "metadata.toml";
 pub const MAX_ACCOUNTS_INVOLVED_IN_P2P: usize = 1_000_000;
 
 fn get_progress_bar(num_accounts: usize) -> ProgressBar {
@@ -85,15 +89,22 @@ pub struct TransactionGenerator {
     /// Record the number of txns generated.
     version: Version,
 
-    /// Each generated block of transactions are sent to this channel. Using `SyncSender` to make
-    /// sure if execution is slow to consume the transactions, we do not run out of memory.
-    block_sender: Option<mpsc::SyncSender<Vec<Transaction>>>,
+    /// Each generated block of transactions are sent to this channel. Using a bounded channel to
+    /// make sure if execution is slow to consume the transactions, we do not run out of memory.
+    block_sender: Option<MeteredSender<Vec<Transaction>>>,
 
     /// Transaction Factory
     transaction_factory: TransactionFactory,
 
     /// root account is used across creating and minting.
     root_account: LocalAccount,
+
+    /// Samples the senders of the workload and the ids of the state checkpoints. Seeded from the
+    /// seed of the run, if any, for the blocks to be reproducible.
+    rng: StdRng,
+
+    /// Replaces some of the generated transactions with failing ones.
+    failure_injector: FailureInjector,
 }
 
 impl TransactionGenerator {
@@ -173,7 +184,7 @@ impl TransactionGenerator {
     pub fn new_with_existing_db<P: AsRef<Path>>(
         db: DbReaderWriter,
         genesis_key: Ed25519PrivateKey,
-        block_sender: mpsc::SyncSender<Vec<Transaction>>,
+        block_sender: MeteredSender<Vec<Transaction>>,
         db_dir: P,
         version: Version,
         num_main_signer_accounts: Option<usize>,
@@ -196,9 +207,16 @@ impl TransactionGenerator {
             version,
             block_sender: Some(block_sender),
             transaction_factory: Self::create_transaction_factory(),
+            rng: new_rng(),
+            failure_injector: FailureInjector::default(),
         }
     }
 
+    pub fn with_failure_injector(mut self, failure_injector: FailureInjector) -> Self {
+        self.failure_injector = failure_injector;
+        self
+    }
+
     pub fn create_transaction_factory() -> TransactionFactory {
         TransactionFactory::new(ChainId::test())
             .with_transaction_expiration_time(300)
@@ -288,19 +306,27 @@ impl TransactionGenerator {
         let mut transaction_generator =
             transaction_generator_creator.create_transaction_generator();
         for _ in 0..num_blocks {
-            let transactions: Vec<_> = rand::seq::index::sample(
-                &mut thread_rng(),
-                account_pool_size,
-                num_senders_per_block,
-            )
-            .into_iter()
-            .flat_map(|idx| {
-                let sender = &mut self.main_signer_accounts.as_mut().unwrap().accounts[idx];
-                transaction_generator.generate_transactions(sender, transactions_per_sender)
-            })
-            .map(Transaction::UserTransaction)
-            .chain(once(Transaction::StateCheckpoint(HashValue::random())))
-            .collect();
+            let state_checkpoint =
+                Transaction::StateCheckpoint(HashValue::random_with_rng(&mut self.rng));
+            let sender_indices =
+                rand::seq::index::sample(&mut self.rng, account_pool_size, num_senders_per_block);
+            let transactions: Vec<_> = sender_indices
+                .into_iter()
+                .flat_map(|idx| {
+                    let sender = &mut self.main_signer_accounts.as_mut().unwrap().accounts[idx];
+                    let mut txns = transaction_generator
+                        .generate_transactions(sender, transactions_per_sender);
+                    self.failure_injector.inject(
+                        &mut self.rng,
+                        &self.transaction_factory,
+                        sender,
+                        &mut txns,
+                    );
+                    txns
+                })
+                .map(Transaction::UserTransaction)
+                .chain(once(state_checkpoint))
+                .collect();
             self.version += transactions.len() as Version;
 
             if let Some(sender) = &self.block_sender {
@@ -335,6 +361,8 @@ impl TransactionGenerator {
             .collect::<Vec<_>>()
             .chunks(block_size)
         {
+            let state_checkpoint =
+                Transaction::StateCheckpoint(HashValue::random_with_rng(&mut self.rng));
             let transactions: Vec<_> = chunk
                 .iter()
                 .map(|new_account| {
@@ -347,7 +375,7 @@ impl TransactionGenerator {
                     );
                     Transaction::UserTransaction(txn)
                 })
-                .chain(once(Transaction::StateCheckpoint(HashValue::random())))
+                .chain(once(state_checkpoint))
                 .collect();
             self.version += transactions.len() as Version;
             bar.inc(transactions.len() as u64 - 1);
@@ -379,6 +407,8 @@ impl TransactionGenerator {
         let bar = get_progress_bar(num_new_accounts);
 
         for chunk in &(0..num_new_accounts).chunks(block_size) {
+            let state_checkpoint =
+                Transaction::StateCheckpoint(HashValue::random_with_rng(&mut self.rng));
             let transactions: Vec<_> = chunk
                 .map(|_| {
                     let sender = self.seed_accounts_cache.as_mut().unwrap().get_random();
@@ -392,7 +422,7 @@ impl TransactionGenerator {
                     );
                     Transaction::UserTransaction(txn)
                 })
-                .chain(once(Transaction::StateCheckpoint(HashValue::random())))
+                .chain(once(state_checkpoint))
                 .collect();
             self.version += transactions.len() as Version;
             if let Some(sender) = &self.block_sender {
@@ -413,6 +443,8 @@ impl TransactionGenerator {
     ) {
         for _ in 0..num_blocks {
             // TODO: handle when block_size isn't divisible by transactions_per_sender
+            let state_checkpoint =
+                Transaction::StateCheckpoint(HashValue::random_with_rng(&mut self.rng));
             let transactions: Vec<_> = (0..(block_size / transactions_per_sender))
                 .flat_map(|_| {
                     let (sender, receivers) = self
@@ -420,18 +452,25 @@ impl TransactionGenerator {
                         .as_mut()
                         .unwrap()
                         .get_random_transfer_batch(transactions_per_sender);
-                    receivers
+                    let mut txns = receivers
                         .into_iter()
                         .map(|receiver| {
                             let amount = 1;
-                            let txn = sender.sign_with_transaction_builder(
+                            sender.sign_with_transaction_builder(
                                 self.transaction_factory.transfer(receiver, amount),
-                            );
-                            Transaction::UserTransaction(txn)
+                            )
                         })
-                        .collect::<Vec<_>>()
+                        .collect::<Vec<_>>();
+                    self.failure_injector.inject(
+                        &mut self.rng,
+                        &self.transaction_factory,
+                        sender,
+                        &mut txns,
+                    );
+                    txns
                 })
-                .chain(once(Transaction::StateCheckpoint(HashValue::random())))
+                .map(Transaction::UserTransaction)
+                .chain(once(state_checkpoint))
                 .collect();
             self.version += transactions.len() as Version;
 
@@ -0,0 +1,483 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cross shard client sending the messages over gRPC, each shard serving the messages to it on
+//! its own endpoint, for the shards to run on separate hosts.
+//!
+//! The messages to a remote shard are queued and sent in batches by a dedicated thread, waiting
+//! for each batch to be acknowledged before sending the next one. A batch failing to be sent (e.g.
+//! as the remote shard restarted its server) is sent again after reconnecting, and the sequence
+//! numbers of the messages let the remote shard drop the ones it already received, so that the
+//! messages are delivered in order and exactly once.
+//!
+//! The sequence numbers are scoped to a stream, identified by an increasing id sent along each
+//! request. A remote shard seeing a new stream (e.g. as it or the sender restarted) starts
+//! expecting the sequence numbers from the first message of the stream it receives. A batch that
+//! still fails to be sent after `MAX_SEND_ATTEMPTS` is dropped, and the sender starts a new
+//! stream for the remote shard to accept the following messages despite the gap.
+
+use anyhow::anyhow;
+use aptos_block_partitioner::sharded_block_partitioner::MAX_ALLOWED_PARTITIONING_ROUNDS;
+use aptos_infallible::Mutex;
+use aptos_logger::{error, info, warn};
+use aptos_protos::internal::executor::v1::{
+    cross_shard_messaging_client::CrossShardMessagingClient,
+    cross_shard_messaging_server::{CrossShardMessaging, CrossShardMessagingServer},
+    CrossShardMessage, SendCrossShardMessagesRequest, SendCrossShardMessagesResponse,
+};
+use aptos_types::block_executor::partitioner::{RoundId, ShardId};
+use aptos_vm::sharded_block_executor::{
+    cross_shard_client::CrossShardClient, messages::CrossShardMsg,
+};
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::runtime::Runtime;
+use tonic::{transport::Channel, Request, Response, Status};
+
+/// Maximum number of messages sent to a shard in a single request.
+const MAX_MESSAGES_PER_REQUEST: usize = 1024;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(10);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Number of times a request is sent before its messages are dropped.
+const MAX_SEND_ATTEMPTS: usize = 10;
+/// Metadata of the requests holding the id of the stream the messages belong to.
+const STREAM_ID_METADATA_KEY: &str = "x-cross-shard-stream-id";
+
+pub struct GrpcCrossShardClient {
+    shard_id: ShardId,
+    // The queues of the messages to send to each shard, along with their round. The queue to the
+    // shard itself is None, its messages being delivered directly.
+    outbound_txs: Vec<Option<Sender<(RoundId, CrossShardMsg)>>>,
+    // The senders and receivers of the messages to the shard per round.
+    inbound_txs: Arc<Vec<Sender<CrossShardMsg>>>,
+    inbound_rxs: Vec<Receiver<CrossShardMsg>>,
+    _runtime: Runtime,
+}
+
+impl GrpcCrossShardClient {
+    /// Serves the messages to the shard on its address, and connects to the other shards on
+    /// theirs.
+    pub fn new(shard_id: ShardId, shard_addresses: Vec<SocketAddr>) -> Self {
+        let runtime = aptos_runtimes::spawn_named_runtime(format!("xshard-{}", shard_id), Some(2));
+        let (inbound_txs, inbound_rxs): (Vec<_>, Vec<_>) = (0..MAX_ALLOWED_PARTITIONING_ROUNDS)
+            .map(|_| unbounded())
+            .unzip();
+        let inbound_txs = Arc::new(inbound_txs);
+
+        let service = CrossShardMessagingService {
+            inbound_txs: inbound_txs.clone(),
+            next_sequence_numbers: Mutex::new(HashMap::new()),
+        };
+        let listen_address = shard_addresses[shard_id];
+        info!(
+            "Serving the cross shard messages to shard {} on {}",
+            shard_id, listen_address
+        );
+        runtime.spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(CrossShardMessagingServer::new(service))
+                .serve(listen_address)
+                .await
+                .expect("Failed to serve the cross shard messages");
+        });
+
+        let outbound_txs = shard_addresses
+            .into_iter()
+            .enumerate()
+            .map(|(remote_shard_id, remote_address)| {
+                if remote_shard_id == shard_id {
+                    return None;
+                }
+                let (outbound_tx, outbound_rx) = unbounded();
+                let outbound_stream = OutboundStream {
+                    shard_id,
+                    remote_shard_id,
+                    remote_address,
+                    runtime: runtime.handle().clone(),
+                    client: None,
+                    stream_id: new_stream_id(),
+                    next_sequence_number: 0,
+                };
+                thread::Builder::new()
+                    .name(format!("xshard-{}-to-{}", shard_id, remote_shard_id))
+                    .spawn(move || outbound_stream.run(outbound_rx))
+                    .expect("Failed to spawn the cross shard outbound thread");
+                Some(outbound_tx)
+            })
+            .collect();
+
+        Self {
+            shard_id,
+            outbound_txs,
+            inbound_txs,
+            inbound_rxs,
+            _runtime: runtime,
+        }
+    }
+}
+
+impl CrossShardClient for GrpcCrossShardClient {
+    fn send_cross_shard_msg(
+        &self,
+        shard_id: ShardId,
+        round: RoundId,
+        msg: CrossShardMsg,
+    ) -> anyhow::Result<()> {
+        match &self.outbound_txs[shard_id] {
+            Some(outbound_tx) => outbound_tx
+                .send((round, msg))
+                .map_err(|_| anyhow!("The outbound stream to shard {} is gone", shard_id)),
+            None => {
+                assert_eq!(shard_id, self.shard_id);
+                self.inbound_txs[round]
+                    .send(msg)
+                    .map_err(|_| anyhow!("The inbound channel of round {} is closed", round))
+            },
+        }
+    }
+
+    fn receive_cross_shard_msg(
+        &self,
+        current_round: RoundId,
+        timeout: Duration,
+    ) -> Result<CrossShardMsg, RecvTimeoutError> {
+        self.inbound_rxs[current_round].recv_timeout(timeout)
+    }
+}
+
+/// Sends the messages queued for a remote shard, in order.
+struct OutboundStream {
+    shard_id: ShardId,
+    remote_shard_id: ShardId,
+    remote_address: SocketAddr,
+    runtime: tokio::runtime::Handle,
+    client: Option<CrossShardMessagingClient<Channel>>,
+    stream_id: u64,
+    next_sequence_number: u64,
+}
+
+/// Returns an id greater than the ones of the streams started before, including by a previous
+/// run of the process, which the remote shards may still know about.
+fn new_stream_id() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time must be after the epoch")
+        .as_micros() as u64
+}
+
+impl OutboundStream {
+    /// Sends the messages until the client is dropped.
+    fn run(mut self, outbound_rx: Receiver<(RoundId, CrossShardMsg)>) {
+        while let Ok(first_message) = outbound_rx.recv() {
+            let messages: Vec<_> = std::iter::once(first_message)
+                .chain(outbound_rx.try_iter().take(MAX_MESSAGES_PER_REQUEST - 1))
+                .map(|(round, msg)| {
+                    let sequence_number = self.next_sequence_number;
+                    self.next_sequence_number += 1;
+                    CrossShardMessage {
+                        round: round as u32,
+                        sequence_number,
+                        payload: bcs::to_bytes(&msg).unwrap(),
+                    }
+                })
+                .collect();
+            let request = SendCrossShardMessagesRequest {
+                sender_shard_id: self.shard_id as u32,
+                messages,
+            };
+            let runtime = self.runtime.clone();
+            runtime.block_on(self.send(request));
+        }
+    }
+
+    /// Sends the request until it succeeds, reconnecting to the remote shard as needed. Gives up
+    /// after `MAX_SEND_ATTEMPTS`, starting a new stream for the following messages.
+    async fn send(&mut self, request: SendCrossShardMessagesRequest) {
+        let mut retry_delay = INITIAL_RETRY_DELAY;
+        for attempt in 1..=MAX_SEND_ATTEMPTS {
+            let mut grpc_request = Request::new(request.clone());
+            grpc_request.metadata_mut().insert(
+                STREAM_ID_METADATA_KEY,
+                self.stream_id
+                    .to_string()
+                    .parse()
+                    .expect("Stream id must be valid metadata"),
+            );
+            let result = match self.connect().await {
+                Ok(client) => client
+                    .send_cross_shard_messages(grpc_request)
+                    .await
+                    .map(|_| ())
+                    .map_err(|status| status.to_string()),
+                Err(err) => Err(err.to_string()),
+            };
+            match result {
+                Ok(()) => return,
+                Err(err) => {
+                    warn!(
+                        "Failed to send {} cross shard messages to shard {} at {} (attempt {}): {}",
+                        request.messages.len(),
+                        self.remote_shard_id,
+                        self.remote_address,
+                        attempt,
+                        err
+                    );
+                    // Reconnect from scratch, in case the connection is broken.
+                    self.client = None;
+                    if attempt < MAX_SEND_ATTEMPTS {
+                        tokio::time::sleep(retry_delay).await;
+                        retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                    }
+                },
+            }
+        }
+
+        error!(
+            "Dropping {} cross shard messages to shard {} at {} after {} attempts",
+            request.messages.len(),
+            self.remote_shard_id,
+            self.remote_address,
+            MAX_SEND_ATTEMPTS
+        );
+        self.stream_id = new_stream_id().max(self.stream_id + 1);
+    }
+
+    async fn connect(
+        &mut self,
+    ) -> Result<&mut CrossShardMessagingClient<Channel>, tonic::transport::Error> {
+        if self.client.is_none() {
+            let client =
+                CrossShardMessagingClient::connect(format!("http://{}", self.remote_address))
+                    .await?;
+            self.client = Some(client);
+        }
+        Ok(self.client.as_mut().expect("Must be connected"))
+    }
+}
+
+struct CrossShardMessagingService {
+    inbound_txs: Arc<Vec<Sender<CrossShardMsg>>>,
+    // The current stream of each shard, and the sequence number of the next message expected in
+    // it.
+    next_sequence_numbers: Mutex<HashMap<ShardId, (u64, u64)>>,
+}
+
+#[tonic::async_trait]
+impl CrossShardMessaging for CrossShardMessagingService {
+    async fn send_cross_shard_messages(
+        &self,
+        request: Request<SendCrossShardMessagesRequest>,
+    ) -> Result<Response<SendCrossShardMessagesResponse>, Status> {
+        let stream_id: u64 = request
+            .metadata()
+            .get(STREAM_ID_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| Status::invalid_argument("Missing or invalid stream id"))?;
+        let request = request.into_inner();
+        let first_sequence_number = match request.messages.first() {
+            Some(message) => message.sequence_number,
+            None => return Ok(Response::new(SendCrossShardMessagesResponse {})),
+        };
+
+        let mut next_sequence_numbers = self.next_sequence_numbers.lock();
+        let (current_stream_id, next_sequence_number) = next_sequence_numbers
+            .entry(request.sender_shard_id as ShardId)
+            .or_insert((stream_id, first_sequence_number));
+        if stream_id < *current_stream_id {
+            return Err(Status::failed_precondition(format!(
+                "Stream {} of shard {} was replaced by stream {}",
+                stream_id, request.sender_shard_id, current_stream_id
+            )));
+        }
+        if stream_id > *current_stream_id {
+            info!(
+                "Shard {} started stream {} from message {}",
+                request.sender_shard_id, stream_id, first_sequence_number
+            );
+            *current_stream_id = stream_id;
+            *next_sequence_number = first_sequence_number;
+        }
+        for message in request.messages {
+            // Messages sent again after a failure to acknowledge them are dropped.
+            if message.sequence_number < *next_sequence_number {
+                continue;
+            }
+            if message.sequence_number > *next_sequence_number {
+                return Err(Status::invalid_argument(format!(
+                    "Expected message {} from shard {}, got {}",
+                    next_sequence_number, request.sender_shard_id, message.sequence_number
+                )));
+            }
+            let msg: CrossShardMsg = bcs::from_bytes(&message.payload)
+                .map_err(|err| Status::invalid_argument(err.to_string()))?;
+            let inbound_tx = self
+                .inbound_txs
+                .get(message.round as RoundId)
+                .ok_or_else(|| {
+                    Status::invalid_argument(format!("Invalid round {}", message.round))
+                })?;
+            inbound_tx
+                .send(msg)
+                .map_err(|_| Status::unavailable("The shard is shutting down"))?;
+            *next_sequence_number += 1;
+        }
+        Ok(Response::new(SendCrossShardMessagesResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::utils;
+    use aptos_vm::sharded_block_executor::messages::RemoteTxnEvents;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_send_receive_in_order() {
+        let num_shards = 2;
+        let shard_addresses: Vec<_> = (0..num_shards)
+            .map(|_| SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), utils::get_available_port()))
+            .collect();
+        let clients: Vec<_> = (0..num_shards)
+            .map(|shard_id| GrpcCrossShardClient::new(shard_id, shard_addresses.clone()))
+            .collect();
+
+        // The messages of each round are received in the order they were sent.
+        for round in 0..2 {
+            for txn_idx in 0..100 {
+                clients[0]
+                    .send_cross_shard_msg(
+                        1,
+                        round,
+                        CrossShardMsg::RemoteEventMsg(RemoteTxnEvents::new(txn_idx, vec![])),
+                    )
+                    .unwrap();
+            }
+            clients[0]
+                .send_cross_shard_msg(1, round, CrossShardMsg::StopMsg)
+                .unwrap();
+        }
+        for round in 0..2 {
+            for expected_txn_idx in 0..100 {
+                match clients[1].receive_cross_shard_msg(round, TIMEOUT).unwrap() {
+                    CrossShardMsg::RemoteEventMsg(events) => {
+                        assert_eq!(events.take().0, expected_txn_idx)
+                    },
+                    msg => panic!("Unexpected message {:?}", msg),
+                }
+            }
+            assert!(matches!(
+                clients[1].receive_cross_shard_msg(round, TIMEOUT).unwrap(),
+                CrossShardMsg::StopMsg
+            ));
+        }
+
+        // The messages to the shard itself are delivered directly.
+        clients[1]
+            .send_cross_shard_msg(1, 2, CrossShardMsg::StopMsg)
+            .unwrap();
+        assert!(matches!(
+            clients[1].receive_cross_shard_msg(2, TIMEOUT).unwrap(),
+            CrossShardMsg::StopMsg
+        ));
+    }
+
+    fn request(
+        stream_id: u64,
+        sequence_numbers: std::ops::Range<u64>,
+    ) -> Request<SendCrossShardMessagesRequest> {
+        let mut request = Request::new(SendCrossShardMessagesRequest {
+            sender_shard_id: 1,
+            messages: sequence_numbers
+                .map(|sequence_number| CrossShardMessage {
+                    round: 0,
+                    sequence_number,
+                    payload: bcs::to_bytes(&CrossShardMsg::StopMsg).unwrap(),
+                })
+                .collect(),
+        });
+        request.metadata_mut().insert(
+            STREAM_ID_METADATA_KEY,
+            stream_id.to_string().parse().unwrap(),
+        );
+        request
+    }
+
+    #[test]
+    fn test_duplicate_messages_dropped() {
+        let (inbound_tx, inbound_rx) = unbounded();
+        let service = CrossShardMessagingService {
+            inbound_txs: Arc::new(vec![inbound_tx]),
+            next_sequence_numbers: Mutex::new(HashMap::new()),
+        };
+        let runtime = aptos_runtimes::spawn_named_runtime("test".to_string(), Some(1));
+        runtime.block_on(async {
+            service
+                .send_cross_shard_messages(request(1, 0..3))
+                .await
+                .unwrap();
+            // Sent again, e.g. as the response was lost.
+            service
+                .send_cross_shard_messages(request(1, 1..5))
+                .await
+                .unwrap();
+            // Messages can not be skipped.
+            assert!(service
+                .send_cross_shard_messages(request(1, 6..7))
+                .await
+                .is_err());
+        });
+        assert_eq!(inbound_rx.try_iter().count(), 5);
+    }
+
+    #[test]
+    fn test_new_streams_accepted() {
+        let (inbound_tx, inbound_rx) = unbounded();
+        let service = CrossShardMessagingService {
+            inbound_txs: Arc::new(vec![inbound_tx]),
+            next_sequence_numbers: Mutex::new(HashMap::new()),
+        };
+        let runtime = aptos_runtimes::spawn_named_runtime("test".to_string(), Some(1));
+        runtime.block_on(async {
+            // The shard restarted, and receives the messages of a stream from the middle.
+            service
+                .send_cross_shard_messages(request(1, 10..12))
+                .await
+                .unwrap();
+            // The sender gave up on some messages, and started a new stream after them.
+            service
+                .send_cross_shard_messages(request(2, 15..17))
+                .await
+                .unwrap();
+            // The sender restarted.
+            service
+                .send_cross_shard_messages(request(3, 0..2))
+                .await
+                .unwrap();
+            // Requests of a replaced stream arriving late are rejected.
+            assert!(service
+                .send_cross_shard_messages(request(2, 17..18))
+                .await
+                .is_err());
+            // Requests without a stream are rejected.
+            let mut no_stream_request = request(3, 2..3);
+            no_stream_request
+                .metadata_mut()
+                .remove(STREAM_ID_METADATA_KEY);
+            assert!(service
+                .send_cross_shard_messages(no_stream_request)
+                .await
+                .is_err());
+        });
+        assert_eq!(inbound_rx.try_iter().count(), 6);
+    }
+}
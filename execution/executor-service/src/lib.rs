@@ -9,6 +9,7 @@ use aptos_types::{
 use serde::{Deserialize, Serialize};
 
 mod error;
+mod grpc_cross_shard_client;
 pub mod process_executor_service;
 mod remote_cordinator_client;
 mod remote_cross_shard_client;
@@ -1,6 +1,6 @@
 // Copyright © Aptos Foundation
 
-use crate::remote_executor_service::ExecutorService;
+use crate::remote_executor_service::{CrossShardTransport, ExecutorService};
 use aptos_logger::info;
 use aptos_types::block_executor::partitioner::ShardId;
 use std::net::SocketAddr;
@@ -17,6 +17,7 @@ impl ProcessExecutorService {
         num_threads: usize,
         coordinator_address: SocketAddr,
         remote_shard_addresses: Vec<SocketAddr>,
+        cross_shard_transport: CrossShardTransport,
     ) -> Self {
         let self_address = remote_shard_addresses[shard_id];
         info!(
@@ -30,6 +31,7 @@ impl ProcessExecutorService {
             self_address,
             coordinator_address,
             remote_shard_addresses,
+            cross_shard_transport,
         );
         executor_service.start();
         Self {
@@ -48,6 +48,7 @@ impl CoordinatorClient<InMemoryStateView> for RemoteCoordinatorClient {
                     sub_blocks,
                     concurrency,
                     gas_limit,
+                    false,
                 )
             },
         }
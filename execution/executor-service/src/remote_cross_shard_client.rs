@@ -6,17 +6,14 @@ use aptos_types::block_executor::partitioner::{RoundId, ShardId};
 use aptos_vm::sharded_block_executor::{
     cross_shard_client::CrossShardClient, messages::CrossShardMsg,
 };
-use crossbeam_channel::{Receiver, Sender};
-use std::{
-    net::SocketAddr,
-    sync::{Arc, Mutex},
-};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 pub struct RemoteCrossShardClient {
     // The senders of cross-shard messages to other shards per round.
-    message_txs: Arc<Vec<Vec<Mutex<Sender<Message>>>>>,
+    message_txs: Arc<Vec<Vec<Sender<Message>>>>,
     // The receivers of cross shard messages from other shards per round.
-    message_rxs: Arc<Vec<Mutex<Receiver<Message>>>>,
+    message_rxs: Arc<Vec<Receiver<Message>>>,
 }
 
 impl RemoteCrossShardClient {
@@ -29,7 +26,7 @@ impl RemoteCrossShardClient {
             for round in 0..MAX_ALLOWED_PARTITIONING_ROUNDS {
                 let message_type = format!("cross_shard_{}", round);
                 let tx = controller.create_outbound_channel(*remote_address, message_type);
-                txs.push(Mutex::new(tx));
+                txs.push(tx);
             }
             message_txs.push(txs);
         }
@@ -38,7 +35,7 @@ impl RemoteCrossShardClient {
         for round in 0..MAX_ALLOWED_PARTITIONING_ROUNDS {
             let message_type = format!("cross_shard_{}", round);
             let rx = controller.create_inbound_channel(message_type);
-            message_rxs.push(Mutex::new(rx));
+            message_rxs.push(rx);
         }
 
         Self {
@@ -49,16 +46,31 @@ impl RemoteCrossShardClient {
 }
 
 impl CrossShardClient for RemoteCrossShardClient {
-    fn send_cross_shard_msg(&self, shard_id: ShardId, round: RoundId, msg: CrossShardMsg) {
-        let input_message = bcs::to_bytes(&msg).unwrap();
-        let tx = self.message_txs[shard_id][round].lock().unwrap();
-        tx.send(Message::new(input_message)).unwrap();
+    fn send_cross_shard_msg(
+        &self,
+        shard_id: ShardId,
+        round: RoundId,
+        msg: CrossShardMsg,
+    ) -> anyhow::Result<()> {
+        let input_message = bcs::to_bytes(&msg)?;
+        self.message_txs[shard_id][round]
+            .send(Message::new(input_message))
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "The channel to shard {} for round {} is closed",
+                    shard_id,
+                    round
+                )
+            })
     }
 
-    fn receive_cross_shard_msg(&self, current_round: RoundId) -> CrossShardMsg {
-        let rx = self.message_rxs[current_round].lock().unwrap();
-        let message = rx.recv().unwrap();
+    fn receive_cross_shard_msg(
+        &self,
+        current_round: RoundId,
+        timeout: Duration,
+    ) -> Result<CrossShardMsg, RecvTimeoutError> {
+        let message = self.message_rxs[current_round].recv_timeout(timeout)?;
         let msg: CrossShardMsg = bcs::from_bytes(&message.to_bytes()).unwrap();
-        msg
+        Ok(msg)
     }
 }
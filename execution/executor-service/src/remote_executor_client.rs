@@ -75,6 +75,8 @@ impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for RemoteExecutorC
         block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
         concurrency_level_per_shard: usize,
         maybe_block_gas_limit: Option<u64>,
+        // The remote executor shards don't stream events.
+        _stream_events: bool,
     ) {
         self.thread_pool.scope(|s| {
             for (shard_id, sub_blocks) in block.into_iter().enumerate() {
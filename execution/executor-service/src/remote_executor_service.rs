@@ -2,15 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    grpc_cross_shard_client::GrpcCrossShardClient,
     remote_cordinator_client::RemoteCoordinatorClient,
     remote_cross_shard_client::RemoteCrossShardClient,
 };
 use aptos_secure_net::network_controller::NetworkController;
 use aptos_state_view::in_memory_state_view::InMemoryStateView;
 use aptos_types::block_executor::partitioner::ShardId;
-use aptos_vm::sharded_block_executor::sharded_executor_service::ShardedExecutorService;
+use aptos_vm::sharded_block_executor::{
+    cross_shard_client::CrossShardClient, sharded_executor_service::ShardedExecutorService,
+};
 use std::{net::SocketAddr, sync::Arc};
 
+/// How the executor shards exchange the cross shard messages.
+#[derive(Clone, Debug)]
+pub enum CrossShardTransport {
+    /// Through the network controller the shards also talk to the coordinator with.
+    NetworkController,
+    /// Over gRPC, each shard serving the messages to it on its address in the list.
+    Grpc(Vec<SocketAddr>),
+}
+
 /// A service that provides support for remote execution. Essentially, it reads a request from
 /// the remote executor client and executes the block locally and returns the result.
 pub struct ExecutorService {
@@ -26,6 +38,7 @@ impl ExecutorService {
         self_address: SocketAddr,
         coordinator_address: SocketAddr,
         remote_shard_addresses: Vec<SocketAddr>,
+        cross_shard_transport: CrossShardTransport,
     ) -> Self {
         let service_name = format!("executor_service-{}", shard_id);
         let mut controller = NetworkController::new(service_name, self_address, 5000);
@@ -34,10 +47,15 @@ impl ExecutorService {
             &mut controller,
             coordinator_address,
         ));
-        let cross_shard_client = Arc::new(RemoteCrossShardClient::new(
-            &mut controller,
-            remote_shard_addresses,
-        ));
+        let cross_shard_client: Arc<dyn CrossShardClient> = match cross_shard_transport {
+            CrossShardTransport::NetworkController => Arc::new(RemoteCrossShardClient::new(
+                &mut controller,
+                remote_shard_addresses,
+            )),
+            CrossShardTransport::Grpc(cross_shard_addresses) => {
+                Arc::new(GrpcCrossShardClient::new(shard_id, cross_shard_addresses))
+            },
+        };
 
         let executor_service = Arc::new(ShardedExecutorService::new(
             shard_id,
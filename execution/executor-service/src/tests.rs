@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    remote_executor_client::RemoteExecutorClient, test_utils,
-    thread_executor_service::ThreadExecutorService,
+    remote_executor_client::RemoteExecutorClient, remote_executor_service::CrossShardTransport,
+    test_utils, thread_executor_service::ThreadExecutorService,
 };
 use aptos_config::utils;
 use aptos_language_e2e_tests::data_store::FakeDataStore;
@@ -14,6 +14,7 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 pub fn create_thread_remote_executor_shards(
     num_shards: usize,
     num_threads: Option<usize>,
+    grpc_cross_shard: bool,
 ) -> (
     NetworkController,
     RemoteExecutorClient<FakeDataStore>,
@@ -27,12 +28,20 @@ pub fn create_thread_remote_executor_shards(
         coordinator_address,
         5000,
     );
-    let remote_shard_addresses = (0..num_shards)
-        .map(|_| {
-            let listen_port = utils::get_available_port();
-            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen_port)
-        })
-        .collect::<Vec<_>>();
+    let new_shard_addresses = || {
+        (0..num_shards)
+            .map(|_| {
+                let listen_port = utils::get_available_port();
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen_port)
+            })
+            .collect::<Vec<_>>()
+    };
+    let remote_shard_addresses = new_shard_addresses();
+    let cross_shard_transport = if grpc_cross_shard {
+        CrossShardTransport::Grpc(new_shard_addresses())
+    } else {
+        CrossShardTransport::NetworkController
+    };
 
     let num_threads =
         num_threads.unwrap_or_else(|| (num_cpus::get() as f64 / num_shards as f64).ceil() as usize);
@@ -45,6 +54,7 @@ pub fn create_thread_remote_executor_shards(
                 num_threads,
                 coordinator_address,
                 remote_shard_addresses.clone(),
+                cross_shard_transport.clone(),
             )
         })
         .collect::<Vec<_>>();
@@ -58,7 +68,17 @@ pub fn create_thread_remote_executor_shards(
 fn test_sharded_block_executor_no_conflict() {
     let num_shards = 8;
     let (mut controller, executor_client, _executor_services) =
-        create_thread_remote_executor_shards(num_shards, Some(2));
+        create_thread_remote_executor_shards(num_shards, Some(2), false);
+    controller.start();
+    let sharded_block_executor = ShardedBlockExecutor::new(executor_client);
+    test_utils::test_sharded_block_executor_no_conflict(sharded_block_executor);
+}
+
+#[test]
+fn test_sharded_block_executor_no_conflict_with_grpc_cross_shard() {
+    let num_shards = 8;
+    let (mut controller, executor_client, _executor_services) =
+        create_thread_remote_executor_shards(num_shards, Some(2), true);
     controller.start();
     let sharded_block_executor = ShardedBlockExecutor::new(executor_client);
     test_utils::test_sharded_block_executor_no_conflict(sharded_block_executor);
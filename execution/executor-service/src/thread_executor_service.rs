@@ -1,6 +1,6 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
-use crate::remote_executor_service::ExecutorService;
+use crate::remote_executor_service::{CrossShardTransport, ExecutorService};
 use aptos_types::block_executor::partitioner::ShardId;
 use std::{net::SocketAddr, thread, thread::JoinHandle};
 
@@ -18,6 +18,7 @@ impl ThreadExecutorService {
         num_threads: usize,
         coordinator_address: SocketAddr,
         remote_shard_addresses: Vec<SocketAddr>,
+        cross_shard_transport: CrossShardTransport,
     ) -> Self {
         let self_address = remote_shard_addresses[shard_id];
         let mut executor_service = ExecutorService::new(
@@ -27,6 +28,7 @@ impl ThreadExecutorService {
             self_address,
             coordinator_address,
             remote_shard_addresses,
+            cross_shard_transport,
         );
 
         let thread_name = format!("ThreadExecutorService-{}", shard_id);
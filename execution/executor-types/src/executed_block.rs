@@ -27,6 +27,8 @@ pub struct ExecutedBlock {
     pub transaction_info_hashes: Vec<HashValue>,
     pub block_state_updates: ShardedStateUpdates,
     pub sharded_state_cache: ShardedStateCache,
+    /// The number of blocks on top of the last state checkpoint, including this one.
+    pub num_blocks_since_state_checkpoint: u64,
 }
 
 impl ExecutedBlock {
@@ -54,6 +56,13 @@ impl ExecutedBlock {
         self.next_epoch_state.is_some()
     }
 
+    /// Whether the state after this block is at a state checkpoint, i.e. there are no state updates
+    /// left to be checkpointed.
+    pub fn is_at_state_checkpoint(&self) -> bool {
+        let state = self.result_view.state();
+        state.base_version == state.current_version
+    }
+
     /// Ensure that every block committed by consensus ends with a state checkpoint. That can be
     /// one of the two cases: 1. a reconfiguration (txns in the proposed block after the txn caused
     /// the reconfiguration will be retried) 2. a Transaction::StateCheckpoint at the end of the
@@ -31,6 +31,7 @@ use std::{
     cmp::max,
     collections::{BTreeSet, HashMap},
     fmt::Debug,
+    num::NonZeroU64,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -80,6 +81,53 @@ pub struct StateSnapshotDelta {
     pub jmt_updates: Vec<(HashValue, (HashValue, StateKey))>,
 }
 
+/// Controls whether the executor appends a state checkpoint to the end of an executed block.
+/// Unless the policy is `EveryNBlocks`, every block has to end with a state checkpoint (or a
+/// reconfiguration), which is either included in the block by its producer, or appended by the
+/// executor after execution.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StateCheckpointPolicy {
+    /// A state checkpoint is appended iff a block gas limit is set, since blocks may then be
+    /// cut off during execution. Otherwise the block must include its own checkpoint. This
+    /// is the behaviour consensus relies on.
+    #[default]
+    Default,
+    /// A state checkpoint is always appended, the block must not include one.
+    Always,
+    /// A state checkpoint is never appended, the block must include its own checkpoint (as
+    /// the last transaction), even if a block gas limit is set.
+    Explicit,
+    /// A state checkpoint is appended to every n-th block since the last one, the blocks in
+    /// between don't get one, and their state updates are carried over to the next checkpoint.
+    /// The count restarts on a reconfiguration or a checkpoint included in the block.
+    EveryNBlocks(NonZeroU64),
+}
+
+impl StateCheckpointPolicy {
+    /// Returns the id of the block if a state checkpoint is to be appended to it, given the
+    /// number of blocks executed on top of the last state checkpoint.
+    pub fn state_checkpoint_to_append(
+        &self,
+        block_id: HashValue,
+        maybe_block_gas_limit: Option<u64>,
+        num_blocks_since_state_checkpoint: u64,
+    ) -> Option<HashValue> {
+        match self {
+            Self::Default => maybe_block_gas_limit.map(|_| block_id),
+            Self::Always => Some(block_id),
+            Self::Explicit => None,
+            Self::EveryNBlocks(n) => {
+                (num_blocks_since_state_checkpoint + 1 >= n.get()).then_some(block_id)
+            },
+        }
+    }
+
+    /// Whether every block executed under this policy must end with a state checkpoint.
+    pub fn requires_state_checkpoint(&self) -> bool {
+        !matches!(self, Self::EveryNBlocks(_))
+    }
+}
+
 pub trait BlockExecutorTrait: Send + Sync {
     /// Get the latest committed block id
     fn committed_block_id(&self) -> HashValue;
@@ -87,13 +135,29 @@ pub trait BlockExecutorTrait: Send + Sync {
     /// Reset the internal state including cache with newly fetched latest committed block from storage.
     fn reset(&self) -> Result<()>;
 
+    /// Executes a block, with state checkpoints appended based on the given policy.
+    fn execute_block_ext(
+        &self,
+        block: ExecutableBlock,
+        parent_block_id: HashValue,
+        maybe_block_gas_limit: Option<u64>,
+        state_checkpoint_policy: StateCheckpointPolicy,
+    ) -> Result<StateComputeResult, Error>;
+
     /// Executes a block.
     fn execute_block(
         &self,
         block: ExecutableBlock,
         parent_block_id: HashValue,
         maybe_block_gas_limit: Option<u64>,
-    ) -> Result<StateComputeResult, Error>;
+    ) -> Result<StateComputeResult, Error> {
+        self.execute_block_ext(
+            block,
+            parent_block_id,
+            maybe_block_gas_limit,
+            StateCheckpointPolicy::default(),
+        )
+    }
 
     /// Saves eligible blocks to persistent storage.
     /// If we have multiple blocks and not all of them have signatures, we may send them to storage
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::in_memory_state_calculator::NEW_EPOCH_EVENT_KEY;
+use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_types::{
     contract_event::ContractEvent,
     transaction::{TransactionOutput, TransactionStatus},
@@ -12,11 +13,31 @@ use std::ops::Deref;
 pub struct ParsedTransactionOutput {
     output: TransactionOutput,
     reconfig_events: Vec<ContractEvent>,
+    // The hashes of the events and of the write set of the output, if they were calculated ahead
+    // of the ledger update, e.g. while the rest of the block was executing.
+    maybe_hashes: Option<(Vec<HashValue>, HashValue)>,
 }
 
 impl ParsedTransactionOutput {
     pub fn parse_reconfig_events(events: &[ContractEvent]) -> impl Iterator<Item = &ContractEvent> {
-        events.iter().filter(|e| *e.key() == *NEW_EPOCH_EVENT_KEY)
+        events
+            .iter()
+            .filter(|e| e.event_key() == Some(&*NEW_EPOCH_EVENT_KEY))
+    }
+
+    /// Calculates the hashes of the events and of the write set of the output, which go into its
+    /// TransactionInfo.
+    pub fn calculate_events_and_write_set_hashes(
+        output: &TransactionOutput,
+    ) -> (Vec<HashValue>, HashValue) {
+        (
+            output
+                .events()
+                .iter()
+                .map(CryptoHash::hash)
+                .collect::<Vec<_>>(),
+            output.write_set().streaming_hash(),
+        )
     }
 }
 
@@ -28,6 +49,7 @@ impl From<TransactionOutput> for ParsedTransactionOutput {
         Self {
             output,
             reconfig_events,
+            maybe_hashes: None,
         }
     }
 }
@@ -45,6 +67,22 @@ impl ParsedTransactionOutput {
         !self.reconfig_events.is_empty()
     }
 
+    /// Sets the hashes of the events and of the write set of the output, as calculated by
+    /// `calculate_events_and_write_set_hashes` ahead of the ledger update.
+    pub fn with_hashes(mut self, hashes: (Vec<HashValue>, HashValue)) -> Self {
+        self.maybe_hashes = Some(hashes);
+        self
+    }
+
+    /// Returns the hashes of the events and of the write set of the output, calculating them
+    /// unless they were calculated ahead.
+    pub fn events_and_write_set_hashes(&self) -> (Vec<HashValue>, HashValue) {
+        match &self.maybe_hashes {
+            Some(hashes) => hashes.clone(),
+            None => Self::calculate_events_and_write_set_hashes(&self.output),
+        }
+    }
+
     pub fn unpack(
         self,
     ) -> (
@@ -57,6 +95,7 @@ impl ParsedTransactionOutput {
         let Self {
             output,
             reconfig_events,
+            maybe_hashes: _,
         } = self;
         let (write_set, events, gas_used, status) = output.unpack();
 
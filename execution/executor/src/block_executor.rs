@@ -15,7 +15,7 @@ use crate::{
 };
 use anyhow::Result;
 use aptos_crypto::HashValue;
-use aptos_executor_types::{BlockExecutorTrait, Error, StateComputeResult};
+use aptos_executor_types::{BlockExecutorTrait, Error, StateCheckpointPolicy, StateComputeResult};
 use aptos_infallible::RwLock;
 use aptos_logger::prelude::*;
 use aptos_scratchpad::SparseMerkleTree;
@@ -28,7 +28,7 @@ use aptos_types::{
     ledger_info::LedgerInfoWithSignatures,
     state_store::state_value::StateValue,
 };
-use aptos_vm::AptosVM;
+use aptos_vm::{event_metrics::record_committed_events, AptosVM};
 use fail::fail_point;
 use std::{marker::PhantomData, sync::Arc};
 
@@ -104,18 +104,24 @@ where
         Ok(())
     }
 
-    fn execute_block(
+    fn execute_block_ext(
         &self,
         block: ExecutableBlock,
         parent_block_id: HashValue,
         maybe_block_gas_limit: Option<u64>,
+        state_checkpoint_policy: StateCheckpointPolicy,
     ) -> Result<StateComputeResult, Error> {
         self.maybe_initialize()?;
         self.inner
             .read()
             .as_ref()
             .expect("BlockExecutor is not reset")
-            .execute_block(block, parent_block_id, maybe_block_gas_limit)
+            .execute_block(
+                block,
+                parent_block_id,
+                maybe_block_gas_limit,
+                state_checkpoint_policy,
+            )
     }
 
     fn commit_blocks_ext(
@@ -179,12 +185,10 @@ where
         block: ExecutableBlock,
         parent_block_id: HashValue,
         maybe_block_gas_limit: Option<u64>,
+        state_checkpoint_policy: StateCheckpointPolicy,
     ) -> Result<StateComputeResult, Error> {
         let _timer = APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS.start_timer();
-        let ExecutableBlock {
-            block_id,
-            transactions,
-        } = block;
+        let block_id = block.block_id;
         let committed_block = self.block_tree.root_block();
         let mut block_vec = self
             .block_tree
@@ -233,7 +237,11 @@ where
                         "Injected error in vm_execute_block"
                     )))
                 });
-                V::execute_transaction_block(transactions, state_view, maybe_block_gas_limit)?
+                V::execute_transaction_block(
+                    block.into_transactions()?,
+                    state_view,
+                    maybe_block_gas_limit,
+                )?
             };
             chunk_output.trace_log_transaction_status();
 
@@ -241,12 +249,24 @@ where
                 .with_label_values(&["apply_to_ledger"])
                 .start_timer();
 
-            let (output, _, _) = chunk_output
-                .apply_to_ledger_for_block(parent_view, maybe_block_gas_limit.map(|_| block_id))?;
+            let (mut output, _, _) = chunk_output.apply_to_ledger_for_block(
+                parent_view,
+                state_checkpoint_policy.state_checkpoint_to_append(
+                    block_id,
+                    maybe_block_gas_limit,
+                    parent_output.num_blocks_since_state_checkpoint,
+                ),
+            )?;
+            if !output.is_at_state_checkpoint() {
+                output.num_blocks_since_state_checkpoint =
+                    parent_output.num_blocks_since_state_checkpoint + 1;
+            }
 
             output
         };
-        output.ensure_ends_with_state_checkpoint()?;
+        if state_checkpoint_policy.requires_state_checkpoint() {
+            output.ensure_ends_with_state_checkpoint()?;
+        }
 
         let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
             .with_label_values(&["as_state_compute_result"])
@@ -344,6 +364,7 @@ where
                 block.output.block_state_updates.clone(),
                 &block.output.sharded_state_cache,
             )?;
+            record_committed_events(txns_to_commit.iter().flat_map(|txn| txn.events()));
             first_version += txns_to_commit.len() as u64;
             committed_block = block.clone();
         }
@@ -37,7 +37,7 @@ use aptos_types::{
     },
     write_set::WriteSet,
 };
-use aptos_vm::VMExecutor;
+use aptos_vm::{event_metrics::record_committed_events, VMExecutor};
 use fail::fail_point;
 use itertools::multizip;
 use std::{iter::once, marker::PhantomData, sync::Arc};
@@ -169,6 +169,7 @@ impl<V: VMExecutor> ChunkExecutorInner<V> {
                 false, /* sync_commit */
                 to_commit.result_view.state().clone(),
             )?;
+            record_committed_events(txns_to_commit.iter().flat_map(|txn| txn.events()));
         }
 
         self.commit_queue
@@ -11,10 +11,7 @@ use crate::{
     metrics::{APTOS_EXECUTOR_ERRORS, APTOS_EXECUTOR_OTHER_TIMERS_SECONDS},
 };
 use anyhow::{ensure, Result};
-use aptos_crypto::{
-    hash::{CryptoHash, EventAccumulatorHasher},
-    HashValue,
-};
+use aptos_crypto::{hash::EventAccumulatorHasher, HashValue};
 use aptos_executor_types::{
     in_memory_state_calculator::InMemoryStateCalculator, ExecutedBlock, ExecutedChunk,
     ParsedTransactionOutput, TransactionData,
@@ -50,6 +47,7 @@ impl ApplyChunkOutput {
             state_cache,
             transactions,
             transaction_outputs,
+            committed_output_hashes,
         } = chunk_output;
         let (new_epoch, status, to_keep, to_discard, to_retry) = {
             let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
@@ -62,6 +60,7 @@ impl ApplyChunkOutput {
             Self::sort_transactions_with_state_checkpoint(
                 transactions,
                 transaction_outputs,
+                committed_output_hashes,
                 append_state_checkpoint_to_block,
             )?
         };
@@ -126,6 +125,7 @@ impl ApplyChunkOutput {
             state_cache,
             transactions,
             transaction_outputs,
+            committed_output_hashes,
         } = chunk_output;
         let (new_epoch, status, to_keep, to_discard, to_retry) = {
             let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
@@ -138,6 +138,7 @@ impl ApplyChunkOutput {
             Self::sort_transactions_with_state_checkpoint(
                 transactions,
                 transaction_outputs,
+                committed_output_hashes,
                 append_state_checkpoint_to_block,
             )?
         };
@@ -181,6 +182,7 @@ impl ApplyChunkOutput {
     fn sort_transactions_with_state_checkpoint(
         mut transactions: Vec<Transaction>,
         transaction_outputs: Vec<TransactionOutput>,
+        committed_output_hashes: Vec<(Vec<HashValue>, HashValue)>,
         append_state_checkpoint_to_block: Option<HashValue>,
     ) -> Result<(
         bool,
@@ -189,8 +191,18 @@ impl ApplyChunkOutput {
         Vec<Transaction>,
         Vec<Transaction>,
     )> {
-        let mut transaction_outputs: Vec<ParsedTransactionOutput> =
-            transaction_outputs.into_iter().map(Into::into).collect();
+        // The hashes calculated while the block was executing are those of the first outputs.
+        let mut committed_output_hashes = committed_output_hashes.into_iter();
+        let mut transaction_outputs: Vec<ParsedTransactionOutput> = transaction_outputs
+            .into_iter()
+            .map(|output| {
+                let output = ParsedTransactionOutput::from(output);
+                match committed_output_hashes.next() {
+                    Some(hashes) => output.with_hashes(hashes),
+                    None => output,
+                }
+            })
+            .collect();
         // N.B. off-by-1 intentionally, for exclusive index
         let new_epoch_marker = transaction_outputs
             .iter()
@@ -418,16 +430,7 @@ impl ApplyChunkOutput {
         to_keep
             .par_iter()
             .with_min_len(16)
-            .map(|(_, txn_output)| {
-                (
-                    txn_output
-                        .events()
-                        .iter()
-                        .map(CryptoHash::hash)
-                        .collect::<Vec<_>>(),
-                    CryptoHash::hash(txn_output.write_set()),
-                )
-            })
+            .map(|(_, txn_output)| txn_output.events_and_write_set_hashes())
             .collect::<Vec<_>>()
     }
 }
@@ -4,12 +4,18 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{components::apply_chunk_output::ApplyChunkOutput, metrics};
+use crate::{
+    components::{
+        apply_chunk_output::ApplyChunkOutput,
+        sharded_execution_state_view::ShardedExecutionStateView,
+    },
+    metrics,
+};
 use anyhow::Result;
 use aptos_crypto::HashValue;
-use aptos_executor_types::{ExecutedBlock, ExecutedChunk};
+use aptos_executor_types::{ExecutedBlock, ExecutedChunk, ParsedTransactionOutput};
 use aptos_infallible::Mutex;
-use aptos_logger::{sample, sample::SampleRate, trace, warn};
+use aptos_logger::{error, sample, sample::SampleRate, trace, warn};
 use aptos_storage_interface::{
     cached_state_view::{CachedStateView, StateCache},
     ExecutedTrees,
@@ -21,23 +27,37 @@ use aptos_types::{
         analyzed_transaction::AnalyzedTransaction, ExecutionStatus, Transaction, TransactionOutput,
         TransactionStatus,
     },
+    write_set::WriteSet,
 };
 use aptos_vm::{
+    block_executor::{streaming_commit_hook::CommittedOutputMsg, BlockAptosVM},
     sharded_block_executor::{
         local_executor_shard::{LocalExecutorClient, LocalExecutorService},
         ShardedBlockExecutor,
     },
     AptosVM, VMExecutor,
 };
+use crossbeam_channel::Receiver;
 use fail::fail_point;
 use move_core_types::vm_status::StatusCode;
 use once_cell::sync::Lazy;
-use std::{ops::Deref, sync::Arc, time::Duration};
+use std::{ops::Deref, sync::Arc, thread, time::Duration};
 
 pub static SHARDED_BLOCK_EXECUTOR: Lazy<
-    Arc<Mutex<ShardedBlockExecutor<CachedStateView, LocalExecutorClient<CachedStateView>>>>,
+    Arc<
+        Mutex<
+            ShardedBlockExecutor<
+                ShardedExecutionStateView,
+                LocalExecutorClient<ShardedExecutionStateView>,
+            >,
+        >,
+    >,
 > = Lazy::new(|| {
-    let client = LocalExecutorService::setup_local_executor_shards(AptosVM::get_num_shards(), None);
+    let client = LocalExecutorService::setup_local_executor_shards_with_network(
+        AptosVM::get_num_shards(),
+        None,
+        AptosVM::get_simulated_shard_network(),
+    );
     Arc::new(Mutex::new(ShardedBlockExecutor::new(client)))
 });
 
@@ -46,6 +66,9 @@ pub struct ChunkOutput {
     pub transactions: Vec<Transaction>,
     /// Raw VM output.
     pub transaction_outputs: Vec<TransactionOutput>,
+    /// The hashes of the events and of the write sets of the first outputs, calculated while the
+    /// rest of the block was executing. Empty if the outputs were not streamed by the VM.
+    pub committed_output_hashes: Vec<(Vec<HashValue>, HashValue)>,
     /// Carries the frozen base state view, so all in-mem nodes involved won't drop before the
     /// execution result is processed; as well as all the accounts touched during execution, together
     /// with their proofs.
@@ -71,6 +94,15 @@ impl ChunkOutput {
                 state_view,
                 maybe_block_gas_limit,
             ),
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            } => Self::by_transaction_execution_hybrid::<V>(
+                unsharded_prefix,
+                sharded,
+                state_view,
+                maybe_block_gas_limit,
+            ),
         }
     }
 
@@ -79,8 +111,11 @@ impl ChunkOutput {
         state_view: CachedStateView,
         maybe_block_gas_limit: Option<u64>,
     ) -> Result<Self> {
-        let transaction_outputs =
-            Self::execute_block::<V>(transactions.clone(), &state_view, maybe_block_gas_limit)?;
+        let (transaction_outputs, committed_output_hashes) = Self::execute_block_streaming::<V>(
+            transactions.clone(),
+            &state_view,
+            maybe_block_gas_limit,
+        )?;
 
         // to print txn output for debugging, uncomment:
         // println!("{:?}", transaction_outputs.iter().map(|t| t.status() ).collect::<Vec<_>>());
@@ -90,6 +125,7 @@ impl ChunkOutput {
         Ok(Self {
             transactions,
             transaction_outputs,
+            committed_output_hashes,
             state_cache: state_view.into_state_cache(),
         })
     }
@@ -99,29 +135,115 @@ impl ChunkOutput {
         state_view: CachedStateView,
         maybe_block_gas_limit: Option<u64>,
     ) -> Result<Self> {
-        let state_view_arc = Arc::new(state_view);
-        let transaction_outputs = Self::execute_block_sharded::<V>(
-            block.clone(),
-            state_view_arc.clone(),
+        let transactions = Self::flatten_sharded_txns(block.clone());
+        let (mut transaction_outputs, state_view) = Self::execute_block_sharded::<V>(
+            block,
+            &transactions,
+            ShardedExecutionStateView::new(state_view),
             maybe_block_gas_limit,
         )?;
+        Self::apply_block_output_limit(
+            &mut transaction_outputs,
+            &state_view,
+            maybe_block_gas_limit,
+        );
 
         // TODO(skedia) add logic to emit counters per shard instead of doing it globally.
 
-        // Unwrapping here is safe because the execution has finished and it is guaranteed that
-        // the state view is not used anymore.
-        let state_view = Arc::try_unwrap(state_view_arc).unwrap();
+        Ok(Self {
+            transactions,
+            transaction_outputs,
+            committed_output_hashes: vec![],
+            state_cache: state_view.into_state_cache(),
+        })
+    }
+
+    /// Executes the unsharded prefix of the block, then the sharded rest of the block on top of
+    /// the writes of the prefix. The block gas limit applies to the whole block, i.e. the gas used
+    /// by the prefix is deducted from the limit of the sharded rest of the block.
+    pub fn by_transaction_execution_hybrid<V: VMExecutor>(
+        unsharded_prefix: Vec<Transaction>,
+        sharded_block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+        state_view: CachedStateView,
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Result<Self> {
+        let mut transaction_outputs =
+            Self::execute_block::<V>(unsharded_prefix.clone(), &state_view, maybe_block_gas_limit)?;
+        update_counters_for_processed_chunk(&unsharded_prefix, &transaction_outputs, "executed");
+
+        let sharded_transactions = Self::flatten_sharded_txns(sharded_block.clone());
+        // The outputs only carry the total gas used by the transactions, i.e. including their
+        // storage gas, which the block executor does not count towards the limit. The sharded rest
+        // of the block may then be cut slightly earlier than it would be in an unsharded block.
+        let prefix_gas_used: u64 = transaction_outputs
+            .iter()
+            .map(|output| output.gas_used())
+            .sum();
+        let state_view = if transaction_outputs
+            .iter()
+            .any(|output| output.status().is_retry())
+            || maybe_block_gas_limit.map_or(false, |limit| prefix_gas_used >= limit)
+        {
+            // The block gas limit was reached within the prefix, so the rest of the block is
+            // retried, as it would be for an unsharded block.
+            transaction_outputs.extend(sharded_transactions.iter().map(|_| {
+                TransactionOutput::new(WriteSet::default(), vec![], 0, TransactionStatus::Retry)
+            }));
+            state_view
+        } else {
+            let state_view =
+                ShardedExecutionStateView::with_prefix_outputs(state_view, &transaction_outputs);
+            let (sharded_outputs, state_view) = Self::execute_block_sharded::<V>(
+                sharded_block,
+                &sharded_transactions,
+                state_view,
+                maybe_block_gas_limit.map(|limit| limit - prefix_gas_used),
+            )?;
+            transaction_outputs.extend(sharded_outputs);
+            // The prefix did not reach the block output limit, or the rest of the block would
+            // have been retried, but the whole block may.
+            Self::apply_block_output_limit(
+                &mut transaction_outputs,
+                &state_view,
+                maybe_block_gas_limit,
+            );
+            state_view
+        };
 
         Ok(Self {
-            transactions: SubBlocksForShard::flatten(block)
+            transactions: unsharded_prefix
                 .into_iter()
-                .map(|t| t.into_txn())
+                .chain(sharded_transactions)
                 .collect(),
             transaction_outputs,
+            committed_output_hashes: vec![],
             state_cache: state_view.into_state_cache(),
         })
     }
 
+    /// Cuts the block, executed in parts by the shards, once the outputs of its transactions reach
+    /// the block output limit, as the block executor does for an unsharded block.
+    fn apply_block_output_limit(
+        transaction_outputs: &mut [TransactionOutput],
+        state_view: &CachedStateView,
+        maybe_block_gas_limit: Option<u64>,
+    ) {
+        if let Some(block_output_limit) =
+            BlockAptosVM::block_output_limit(state_view, maybe_block_gas_limit)
+        {
+            BlockAptosVM::apply_block_output_limit(transaction_outputs, block_output_limit);
+        }
+    }
+
+    fn flatten_sharded_txns(
+        block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+    ) -> Vec<Transaction> {
+        SubBlocksForShard::flatten(block)
+            .into_iter()
+            .map(|t| t.into_txn())
+            .collect()
+    }
+
     pub fn by_transaction_output(
         transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
         state_view: CachedStateView,
@@ -143,6 +265,7 @@ impl ChunkOutput {
         Ok(Self {
             transactions,
             transaction_outputs,
+            committed_output_hashes: vec![],
             state_cache: state_view.into_state_cache(),
         })
     }
@@ -184,17 +307,86 @@ impl ChunkOutput {
         }
     }
 
+    /// Executes the sharded block, and returns the base view of `state_view` once done. If the
+    /// sharded execution fails (e.g. as a shard panicked), the block, i.e. `transactions`, is
+    /// executed again unsharded.
     fn execute_block_sharded<V: VMExecutor>(
         block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
-        state_view: Arc<CachedStateView>,
+        transactions: &[Transaction],
+        state_view: ShardedExecutionStateView,
         maybe_block_gas_limit: Option<u64>,
-    ) -> Result<Vec<TransactionOutput>> {
-        Ok(V::execute_block_sharded(
-            SHARDED_BLOCK_EXECUTOR.lock().deref(),
+    ) -> Result<(Vec<TransactionOutput>, CachedStateView)> {
+        let state_view_arc = Arc::new(state_view);
+        let mut sharded_block_executor = SHARDED_BLOCK_EXECUTOR.lock();
+        // The number of shards may change between blocks, per the load of the shards.
+        sharded_block_executor.set_num_shards(block.len());
+        let transaction_outputs = match V::execute_block_sharded(
+            sharded_block_executor.deref(),
             block,
-            state_view,
+            state_view_arc.clone(),
             maybe_block_gas_limit,
-        )?)
+        ) {
+            Ok(transaction_outputs) => transaction_outputs,
+            Err(err) => {
+                error!(
+                    "Sharded execution failed, executing the block unsharded: {:?}",
+                    err
+                );
+                metrics::APTOS_EXECUTOR_SHARDED_EXECUTION_FALLBACKS.inc();
+                sharded_block_executor.reset_shards();
+                V::execute_block(
+                    transactions.to_vec(),
+                    state_view_arc.as_ref(),
+                    maybe_block_gas_limit,
+                )?
+            },
+        };
+
+        // Unwrapping here is safe because the execution has finished (the failed shards included)
+        // and it is guaranteed that the state view is not used anymore.
+        let state_view = Arc::try_unwrap(state_view_arc).unwrap().into_base();
+        Ok((transaction_outputs, state_view))
+    }
+
+    /// Same as `execute_block`, but the outputs of the transactions are streamed as the VM commits
+    /// them, so that the hashes of their events and write sets are calculated while the rest of the
+    /// block executes, rather than by the ledger update once the whole block is executed. Returns
+    /// the outputs and the hashes of the first of them.
+    ///
+    /// When the VM delays the materialization of aggregator deltas, it only commits the
+    /// transactions once the whole block is executed, as it assembles the outputs, so the hashing
+    /// then only overlaps with the assembly of the outputs.
+    #[cfg(not(feature = "consensus-only-perf-test"))]
+    fn execute_block_streaming<V: VMExecutor>(
+        transactions: Vec<Transaction>,
+        state_view: &CachedStateView,
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Result<(Vec<TransactionOutput>, Vec<(Vec<HashValue>, HashValue)>)> {
+        let (output_tx, output_rx) = crossbeam_channel::unbounded();
+        thread::scope(|scope| {
+            let hasher = scope.spawn(move || hash_committed_outputs(output_rx));
+            // The sender is dropped once the block is executed, which ends the hashing.
+            let transaction_outputs = V::execute_block_streaming(
+                transactions,
+                state_view,
+                maybe_block_gas_limit,
+                output_tx,
+            );
+            let committed_output_hashes = hasher.join().expect("Output hashing must not panic");
+            Ok((transaction_outputs?, committed_output_hashes))
+        })
+    }
+
+    /// In consensus-only mode, the outputs are not streamed, see `execute_block`.
+    #[cfg(feature = "consensus-only-perf-test")]
+    fn execute_block_streaming<V: VMExecutor>(
+        transactions: Vec<Transaction>,
+        state_view: &CachedStateView,
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Result<(Vec<TransactionOutput>, Vec<(Vec<HashValue>, HashValue)>)> {
+        let transaction_outputs =
+            Self::execute_block::<V>(transactions, state_view, maybe_block_gas_limit)?;
+        Ok((transaction_outputs, vec![]))
     }
 
     /// Executes the block of [Transaction]s using the [VMExecutor] and returns
@@ -246,6 +438,26 @@ impl ChunkOutput {
     }
 }
 
+/// Calculates the hashes of the events and of the write sets of the outputs streamed by the VM,
+/// until the block is executed. The outputs are streamed in the order of the block, and streamed
+/// again from the start if the execution restarts.
+fn hash_committed_outputs(
+    output_rx: Receiver<CommittedOutputMsg>,
+) -> Vec<(Vec<HashValue>, HashValue)> {
+    let mut committed_output_hashes = vec![];
+    for msg in output_rx {
+        match msg {
+            CommittedOutputMsg::Output(txn_idx, output) => {
+                debug_assert_eq!(txn_idx as usize, committed_output_hashes.len());
+                committed_output_hashes
+                    .push(ParsedTransactionOutput::calculate_events_and_write_set_hashes(&output));
+            },
+            CommittedOutputMsg::Restart => committed_output_hashes.clear(),
+        }
+    }
+    committed_output_hashes
+}
+
 pub fn update_counters_for_processed_chunk(
     transactions: &[Transaction],
     transaction_outputs: &[TransactionOutput],
@@ -395,11 +607,17 @@ pub fn update_counters_for_processed_chunk(
         }
 
         for event in output.events() {
-            let is_core = event.key().get_creator_address() == CORE_CODE_ADDRESS;
-            let creation_number = if is_core && detailed_counters {
-                event.key().get_creation_number().to_string()
-            } else {
-                "event".to_string()
+            let (is_core, creation_number) = match event.event_key() {
+                Some(event_key) => {
+                    let is_core = event_key.get_creator_address() == CORE_CODE_ADDRESS;
+                    let creation_number = if is_core && detailed_counters {
+                        event_key.get_creation_number().to_string()
+                    } else {
+                        "event".to_string()
+                    };
+                    (is_core, creation_number)
+                },
+                None => (false, "module_event".to_string()),
             };
             metrics::APTOS_PROCESSED_USER_TRANSACTIONS_CORE_EVENTS
                 .with_label_values(&[
@@ -23,6 +23,7 @@ use aptos_types::{
     write_set::TransactionWrite,
 };
 use arr_macro::arr;
+use itertools::zip_eq;
 use rayon::prelude::*;
 use std::collections::HashMap;
 
@@ -75,29 +76,18 @@ impl InMemoryStateCalculatorV2 {
         ShardedStateCache,
     )> {
         ensure!(!to_keep.is_empty(), "Empty block is not allowed.");
-        ensure!(
-            base.base_version == base.current_version,
-            "Base version {:?} is different from current_version {:?}, cannot calculate state.",
-            base.base_version,
-            base.current_version,
-        );
-        base.updates_since_base.iter().try_for_each(|shard| {
-            ensure!(
-                shard.is_empty(),
-                "Updates is not empty, cannot calculate state."
-            );
-            Ok(())
-        })?;
 
         let num_txns = to_keep.len();
         for (i, (txn, txn_output)) in to_keep.iter().enumerate() {
             ensure!(
-                Self::need_checkpoint(txn, txn_output) ^ (i != num_txns - 1),
-                "Checkpoint is allowed iff it's the last txn in the block. index: {i}, is_last: {}, txn: {txn:?}, is_reconfig: {}",
+                !Self::need_checkpoint(txn, txn_output) || i == num_txns - 1,
+                "Checkpoint is only allowed as the last txn in the block. index: {i}, is_last: {}, txn: {txn:?}, is_reconfig: {}",
                 i == num_txns - 1,
                 txn_output.is_reconfig()
             );
         }
+        let (last_txn, last_txn_output) = to_keep.last().expect("Checked to be non-empty.");
+        let ends_with_checkpoint = Self::need_checkpoint(last_txn, last_txn_output);
 
         let StateCache {
             // This makes sure all in-mem nodes seen while proofs were fetched stays in mem during the
@@ -109,9 +99,10 @@ impl InMemoryStateCalculatorV2 {
 
         let state_updates_vec = Self::get_sharded_state_updates(to_keep);
         let updates: ShardedStateUpdates = Self::calculate_block_state_updates(&state_updates_vec);
-        let latest_checkpoint = base.current.clone();
-        let usage =
-            Self::calculate_usage(latest_checkpoint.usage(), &sharded_state_cache, &updates);
+        // The base might not be at a checkpoint if the previous block didn't end with one, in
+        // which case its updates since the last checkpoint are already applied to `current`.
+        let latest = base.current.clone();
+        let usage = Self::calculate_usage(latest.usage(), &sharded_state_cache, &updates);
 
         let next_epoch_state = if new_epoch {
             Some(Self::get_epoch_state(&sharded_state_cache, &updates)?)
@@ -119,34 +110,44 @@ impl InMemoryStateCalculatorV2 {
             None
         };
 
-        let (new_checkpoint, new_checkpoint_version) = {
+        let (new_latest, new_latest_version) = {
             let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
                 .with_label_values(&["make_checkpoint"])
                 .start_timer();
-            let latest_checkpoint_version = base.current_version;
-            let new_checkpoint_version =
-                Some(latest_checkpoint_version.map_or(0, |v| v + 1) + num_txns as u64 - 1);
-            let new_checkpoint = Self::make_checkpoint(
-                latest_checkpoint,
-                &updates,
-                usage,
-                ProofReader::new(proofs),
-            )?;
-            (new_checkpoint, new_checkpoint_version)
+            let new_latest_version =
+                Some(base.current_version.map_or(0, |v| v + 1) + num_txns as u64 - 1);
+            let new_latest =
+                Self::make_checkpoint(latest, &updates, usage, ProofReader::new(proofs))?;
+            (new_latest, new_latest_version)
         };
 
-        let state_checkpoint_hashes = std::iter::repeat(None)
-            .take(num_txns - 1)
-            .chain([Some(new_checkpoint.root_hash())])
-            .collect();
-
-        let result_state = StateDelta::new(
-            new_checkpoint.clone(),
-            new_checkpoint_version,
-            new_checkpoint,
-            new_checkpoint_version,
-            create_empty_sharded_state_updates(),
-        );
+        let (state_checkpoint_hashes, result_state) = if ends_with_checkpoint {
+            let state_checkpoint_hashes = std::iter::repeat(None)
+                .take(num_txns - 1)
+                .chain([Some(new_latest.root_hash())])
+                .collect();
+            let result_state = StateDelta::new(
+                new_latest.clone(),
+                new_latest_version,
+                new_latest,
+                new_latest_version,
+                create_empty_sharded_state_updates(),
+            );
+            (state_checkpoint_hashes, result_state)
+        } else {
+            let mut updates_since_base = base.updates_since_base.clone();
+            zip_eq(updates_since_base.iter_mut(), updates.iter()).for_each(|(shard, delta)| {
+                shard.extend(delta.iter().map(|(k, v)| (k.clone(), v.clone())));
+            });
+            let result_state = StateDelta::new(
+                base.base.clone(),
+                base.base_version,
+                new_latest,
+                new_latest_version,
+                updates_since_base,
+            );
+            (vec![None; num_txns], result_state)
+        };
 
         Ok((
             state_updates_vec,
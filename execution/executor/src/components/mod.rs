@@ -9,3 +9,4 @@ pub mod block_tree;
 pub mod chunk_commit_queue;
 pub mod chunk_output;
 pub mod in_memory_state_calculator_v2;
+pub mod sharded_execution_state_view;
@@ -0,0 +1,80 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::Result;
+use aptos_state_view::{StateViewId, TStateView};
+use aptos_storage_interface::cached_state_view::CachedStateView;
+use aptos_types::{
+    state_store::{
+        state_key::StateKey, state_storage_usage::StateStorageUsage, state_value::StateValue,
+    },
+    transaction::TransactionOutput,
+    write_set::TransactionWrite,
+};
+use std::collections::HashMap;
+
+/// The state view the sharded transactions of a block are executed against: the base state of
+/// the block, with the writes of the unsharded prefix of the block (if any) applied on top.
+///
+/// The writes are kept out of the base view, so that its cache keeps holding the values as of
+/// the beginning of the block once execution is done.
+#[derive(Debug)]
+pub struct ShardedExecutionStateView {
+    base: CachedStateView,
+    prefix_writes: HashMap<StateKey, Option<StateValue>>,
+}
+
+impl ShardedExecutionStateView {
+    pub fn new(base: CachedStateView) -> Self {
+        Self {
+            base,
+            prefix_writes: HashMap::new(),
+        }
+    }
+
+    /// Applies the outputs of the unsharded prefix, in order, on top of the base view.
+    pub fn with_prefix_outputs(
+        base: CachedStateView,
+        prefix_outputs: &[TransactionOutput],
+    ) -> Self {
+        let prefix_writes = prefix_outputs
+            .iter()
+            .flat_map(|output| output.write_set().iter())
+            .map(|(key, write_op)| (key.clone(), write_op.as_state_value()))
+            .collect();
+        Self {
+            base,
+            prefix_writes,
+        }
+    }
+
+    pub fn into_base(self) -> CachedStateView {
+        self.base
+    }
+}
+
+impl TStateView for ShardedExecutionStateView {
+    type Key = StateKey;
+
+    fn id(&self) -> StateViewId {
+        self.base.id()
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
+        match self.prefix_writes.get(state_key) {
+            Some(value) => Ok(value.clone()),
+            None => self.base.get_state_value(state_key),
+        }
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.base.is_genesis()
+    }
+
+    /// The usage as of the beginning of the block, i.e. not accounting for the prefix.
+    fn get_usage(&self) -> Result<StateStorageUsage> {
+        self.base.get_usage()
+    }
+}
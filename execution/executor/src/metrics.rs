@@ -68,6 +68,14 @@ pub static APTOS_EXECUTOR_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("aptos_executor_error_total", "Cumulative number of errors").unwrap()
 });
 
+pub static APTOS_EXECUTOR_SHARDED_EXECUTION_FALLBACKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_executor_sharded_execution_fallback_total",
+        "Cumulative number of blocks re-executed unsharded as their sharded execution failed"
+    )
+    .unwrap()
+});
+
 pub static APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
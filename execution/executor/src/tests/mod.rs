@@ -16,15 +16,20 @@ use anyhow::Result;
 use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
 use aptos_db::AptosDB;
 use aptos_executor_types::{
-    BlockExecutorTrait, ChunkExecutorTrait, TransactionReplayer, VerifyExecutionMode,
+    BlockExecutorTrait, ChunkExecutorTrait, StateCheckpointPolicy, TransactionReplayer,
+    VerifyExecutionMode,
 };
-use aptos_state_view::StateViewId;
+use aptos_state_view::{StateView, StateViewId};
 use aptos_storage_interface::{
     async_proof_fetcher::AsyncProofFetcher, DbReaderWriter, ExecutedTrees,
 };
 use aptos_types::{
     account_address::AccountAddress,
     aggregate_signature::AggregateSignature,
+    block_executor::partitioner::{
+        CrossShardDependencies, ExecutableTransactions, SubBlock, SubBlocksForShard,
+        TransactionWithDependencies,
+    },
     block_info::BlockInfo,
     chain_id::ChainId,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -32,14 +37,19 @@ use aptos_types::{
     state_store::{state_key::StateKey, state_value::StateValue},
     test_helpers::transaction_test_helpers::{block, BLOCK_GAS_LIMIT},
     transaction::{
-        ExecutionStatus, RawTransaction, Script, SignedTransaction, Transaction,
-        TransactionListWithProof, TransactionOutput, TransactionPayload, TransactionStatus,
-        Version,
+        analyzed_transaction::AnalyzedTransaction, ExecutionStatus, RawTransaction, Script,
+        SignedTransaction, Transaction, TransactionListWithProof, TransactionOutput,
+        TransactionPayload, TransactionStatus, Version,
     },
+    vm_status::VMStatus,
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
+use aptos_vm::{
+    sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor},
+    VMExecutor,
+};
 use proptest::prelude::*;
-use std::{iter::once, sync::Arc};
+use std::{iter::once, num::NonZeroU64, sync::Arc};
 
 mod chunk_executor_tests;
 
@@ -224,6 +234,131 @@ fn test_executor_one_block() {
     executor.commit_blocks(vec![block_id], ledger_info).unwrap();
 }
 
+#[test]
+fn test_executor_state_checkpoint_policy() {
+    let executor = TestExecutor::new();
+    let mut parent_block_id = executor.committed_block_id();
+    let mut version = 0;
+
+    for (i, policy) in [
+        StateCheckpointPolicy::Always,
+        StateCheckpointPolicy::Explicit,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let block_id = gen_block_id(i as u64 + 1);
+        let mut txns = (0..10)
+            .map(|j| encode_mint_transaction(gen_address(i as u64 * 10 + j), 100))
+            .collect::<Vec<_>>();
+        if policy == StateCheckpointPolicy::Explicit {
+            txns.push(Transaction::StateCheckpoint(block_id));
+        }
+        let output = executor
+            .execute_block_ext(
+                (block_id, txns).into(),
+                parent_block_id,
+                BLOCK_GAS_LIMIT,
+                policy,
+            )
+            .unwrap();
+        // Either way, the block ends with exactly one state checkpoint.
+        version += 11;
+        assert_eq!(output.version(), version);
+        assert_eq!(output.compute_status().len(), 11);
+
+        let ledger_info = gen_ledger_info(version, output.root_hash(), block_id, i as u64 + 1);
+        executor.commit_blocks(vec![block_id], ledger_info).unwrap();
+        parent_block_id = block_id;
+    }
+
+    // A block that includes a state checkpoint can not get another one appended.
+    let block_id = gen_block_id(3);
+    let txns = vec![
+        encode_mint_transaction(gen_address(100), 100),
+        Transaction::StateCheckpoint(block_id),
+    ];
+    assert!(executor
+        .execute_block_ext(
+            (block_id, txns).into(),
+            parent_block_id,
+            BLOCK_GAS_LIMIT,
+            StateCheckpointPolicy::Always,
+        )
+        .is_err());
+}
+
+#[test]
+fn test_executor_state_checkpoint_every_n_blocks() {
+    let executor = TestExecutor::new();
+    let mut parent_block_id = executor.committed_block_id();
+    let mut version = 0;
+    let mut checkpoint_version = 0;
+    let policy = StateCheckpointPolicy::EveryNBlocks(NonZeroU64::new(3).unwrap());
+
+    for i in 0..7 {
+        let block_id = gen_block_id(i + 1);
+        let txns = (0..10)
+            .map(|j| encode_mint_transaction(gen_address(i * 10 + j), 100))
+            .collect::<Vec<_>>();
+        let output = executor
+            .execute_block_ext(
+                (block_id, txns).into(),
+                parent_block_id,
+                BLOCK_GAS_LIMIT,
+                policy,
+            )
+            .unwrap();
+        // Only every third block gets a state checkpoint appended.
+        if i % 3 == 2 {
+            version += 11;
+            checkpoint_version = version;
+        } else {
+            version += 10;
+        }
+        assert_eq!(output.version(), version);
+
+        let ledger_info = gen_ledger_info(version, output.root_hash(), block_id, i + 1);
+        executor.commit_blocks(vec![block_id], ledger_info).unwrap();
+        assert_eq!(
+            executor
+                .db
+                .reader
+                .get_latest_state_checkpoint_version()
+                .unwrap(),
+            Some(checkpoint_version)
+        );
+        parent_block_id = block_id;
+    }
+
+    // A block on top of one without a state checkpoint can still be checkpointed explicitly.
+    let block_id = gen_block_id(8);
+    let txns = vec![
+        encode_mint_transaction(gen_address(100), 100),
+        Transaction::StateCheckpoint(block_id),
+    ];
+    let output = executor
+        .execute_block_ext(
+            (block_id, txns).into(),
+            parent_block_id,
+            BLOCK_GAS_LIMIT,
+            StateCheckpointPolicy::Explicit,
+        )
+        .unwrap();
+    version += 2;
+    assert_eq!(output.version(), version);
+    let ledger_info = gen_ledger_info(version, output.root_hash(), block_id, 8);
+    executor.commit_blocks(vec![block_id], ledger_info).unwrap();
+    assert_eq!(
+        executor
+            .db
+            .reader
+            .get_latest_state_checkpoint_version()
+            .unwrap(),
+        Some(version)
+    );
+}
+
 #[test]
 fn test_executor_multiple_blocks() {
     let executor = TestExecutor::new();
@@ -659,6 +794,100 @@ impl TestBlock {
     }
 }
 
+/// A VM charging the same gas for every transaction, which cuts the blocks (and the parts of the
+/// blocks executed by the shards) once they reach the block gas limit, as the block executor does.
+struct FixedGasVM;
+
+impl FixedGasVM {
+    const GAS_PER_TXN: u64 = 10;
+
+    fn outputs(num_txns: usize, maybe_block_gas_limit: Option<u64>) -> Vec<TransactionOutput> {
+        let num_committed = maybe_block_gas_limit.map_or(num_txns, |limit| {
+            num_txns.min(((limit + Self::GAS_PER_TXN - 1) / Self::GAS_PER_TXN).max(1) as usize)
+        });
+        (0..num_txns)
+            .map(|idx| {
+                let (gas_used, status) = if idx < num_committed {
+                    (Self::GAS_PER_TXN, KEEP_STATUS.clone())
+                } else {
+                    (0, TransactionStatus::Retry)
+                };
+                TransactionOutput::new(WriteSet::default(), vec![], gas_used, status)
+            })
+            .collect()
+    }
+}
+
+impl VMExecutor for FixedGasVM {
+    fn execute_block(
+        transactions: Vec<Transaction>,
+        _state_view: &(impl StateView + Sync),
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Ok(Self::outputs(transactions.len(), maybe_block_gas_limit))
+    }
+
+    fn execute_block_sharded<S: StateView + Sync + Send + 'static, E: ExecutorClient<S>>(
+        _sharded_block_executor: &ShardedBlockExecutor<S, E>,
+        block: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+        _state_view: Arc<S>,
+        maybe_block_gas_limit: Option<u64>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let num_txns = SubBlocksForShard::flatten(block).len();
+        Ok(Self::outputs(num_txns, maybe_block_gas_limit))
+    }
+}
+
+#[test]
+fn test_hybrid_block_gas_limit() {
+    let executor = TestExecutor::new();
+    let db = &executor.db;
+    let ledger_view: ExecutedTrees = db.reader.get_latest_executed_trees().unwrap();
+    let execute = |num_prefix_txns: u64, num_sharded_txns: u64, block_gas_limit: u64| {
+        let unsharded_prefix = (0..num_prefix_txns)
+            .map(|i| encode_mint_transaction(gen_address(i), 100))
+            .collect();
+        let sharded_txns = (num_prefix_txns..num_prefix_txns + num_sharded_txns)
+            .map(|i| {
+                let txn = encode_mint_transaction(gen_address(i), 100);
+                TransactionWithDependencies::new(txn.into(), CrossShardDependencies::default())
+            })
+            .collect();
+        let sharded = vec![SubBlocksForShard::new(
+            0,
+            vec![SubBlock::new(0, sharded_txns)],
+        )];
+        let state_view = ledger_view
+            .verified_state_view(
+                StateViewId::Miscellaneous,
+                Arc::clone(&db.reader),
+                Arc::new(AsyncProofFetcher::new(db.reader.clone())),
+            )
+            .unwrap();
+        ChunkOutput::by_transaction_execution::<FixedGasVM>(
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            },
+            state_view,
+            Some(block_gas_limit),
+        )
+        .unwrap()
+        .transaction_outputs
+        .iter()
+        .map(|output| output.status().is_retry())
+        .collect::<Vec<_>>()
+    };
+
+    // The gas used by the prefix counts towards the limit of the sharded rest of the block, which
+    // is then cut as the whole block would be if it were executed unsharded.
+    assert_eq!(execute(2, 3, 35), vec![false, false, false, false, true]);
+    assert_eq!(execute(2, 3, 100), vec![false; 5]);
+    // The prefix reaching the limit, the rest of the block is retried.
+    assert_eq!(execute(2, 3, 20), vec![false, false, true, true, true]);
+    assert_eq!(execute(3, 2, 15), vec![false, false, true, true, true]);
+}
+
 // Executes a list of transactions by executing and immediately committing one at a time. Returns
 // the root hash after all transactions are committed.
 fn run_transactions_naive(
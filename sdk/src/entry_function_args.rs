@@ -0,0 +1,298 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encoding of entry function arguments given as JSON, for frontends (e.g. GraphQL ones) that
+//! collect the arguments of a transaction as JSON values rather than as Rust values.
+//!
+//! Values are expected in the same encodings as the GraphQL scalars:
+//! * integers are JSON numbers or decimal strings (integers wider than 32 bits are usually
+//!   strings, so that JavaScript clients do not lose precision),
+//! * addresses and `0x1::object::Object<T>` are 0x-prefixed hex strings, in their canonical
+//!   (64 hex digits) or short form,
+//! * `vector<u8>` is a 0x-prefixed hex string (or an array of integers),
+//! * `0x1::string::String` is a string,
+//! * `0x1::option::Option<T>` is either null or a `T`.
+
+use crate::{
+    move_types::{
+        account_address::AccountAddress,
+        identifier::Identifier,
+        language_storage::{ModuleId, StructTag, TypeTag},
+        value::{MoveStruct, MoveValue},
+    },
+    types::transaction::EntryFunction,
+};
+use serde_json::Value;
+use std::{fmt, str::FromStr};
+
+/// Why an argument could not be encoded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArgumentError {
+    /// Index of the argument, not counting the signer parameters.
+    pub index: usize,
+    /// Path to the faulty value within the argument, e.g. `[2]` for the third element of a
+    /// vector. Empty if the argument itself is faulty.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid argument {}{}: {}",
+            self.index, self.path, self.message
+        )
+    }
+}
+
+impl std::error::Error for ArgumentError {}
+
+/// Builds an entry function call, encoding `args` based on the parameter types of the function.
+pub fn entry_function_from_json(
+    module: ModuleId,
+    function: Identifier,
+    ty_args: Vec<TypeTag>,
+    param_types: &[TypeTag],
+    args: &[Value],
+) -> Result<EntryFunction, ArgumentError> {
+    let args = encode_entry_function_args(param_types, args)?;
+    Ok(EntryFunction::new(module, function, ty_args, args))
+}
+
+/// BCS encodes the JSON arguments of an entry function, given its parameter types. Leading
+/// signer parameters are skipped, as they are not passed by the caller. The parameter types
+/// must be instantiated, i.e. not refer to the type parameters of the function.
+pub fn encode_entry_function_args(
+    param_types: &[TypeTag],
+    args: &[Value],
+) -> Result<Vec<Vec<u8>>, ArgumentError> {
+    let param_types: Vec<_> = param_types
+        .iter()
+        .skip_while(|param_type| matches!(param_type, TypeTag::Signer))
+        .collect();
+    if param_types.len() != args.len() {
+        return Err(ArgumentError {
+            index: param_types.len().min(args.len()),
+            path: String::new(),
+            message: format!(
+                "expected {} arguments, got {}",
+                param_types.len(),
+                args.len()
+            ),
+        });
+    }
+
+    param_types
+        .into_iter()
+        .zip(args)
+        .enumerate()
+        .map(|(index, (param_type, arg))| {
+            let mut path = String::new();
+            let value = json_to_move_value(param_type, arg, &mut path).map_err(|message| {
+                ArgumentError {
+                    index,
+                    path,
+                    message,
+                }
+            })?;
+            value.simple_serialize().ok_or_else(|| ArgumentError {
+                index,
+                path: String::new(),
+                message: format!("failed to serialize value of type {}", param_type),
+            })
+        })
+        .collect()
+}
+
+/// Converts a JSON value into a Move value of the given type. On error, `path` points to the
+/// faulty value.
+fn json_to_move_value(
+    type_tag: &TypeTag,
+    value: &Value,
+    path: &mut String,
+) -> Result<MoveValue, String> {
+    Ok(match type_tag {
+        TypeTag::Bool => match value {
+            Value::Bool(b) => MoveValue::Bool(*b),
+            _ => return Err(unexpected(type_tag, value)),
+        },
+        TypeTag::U8 => MoveValue::U8(parse_int(type_tag, value)?),
+        TypeTag::U16 => MoveValue::U16(parse_int(type_tag, value)?),
+        TypeTag::U32 => MoveValue::U32(parse_int(type_tag, value)?),
+        TypeTag::U64 => MoveValue::U64(parse_int(type_tag, value)?),
+        TypeTag::U128 => MoveValue::U128(parse_int(type_tag, value)?),
+        TypeTag::U256 => MoveValue::U256(parse_int(type_tag, value)?),
+        TypeTag::Address => MoveValue::Address(parse_address(type_tag, value)?),
+        TypeTag::Signer => {
+            return Err("signers can only be the first parameters of entry functions".to_string())
+        },
+        TypeTag::Vector(elem_type) => match (elem_type.as_ref(), value) {
+            (TypeTag::U8, Value::String(hex)) => MoveValue::vector_u8(parse_hex(hex)?),
+            (_, Value::Array(values)) => {
+                let mut elems = Vec::with_capacity(values.len());
+                for (i, value) in values.iter().enumerate() {
+                    let len = path.len();
+                    path.push_str(&format!("[{}]", i));
+                    elems.push(json_to_move_value(elem_type, value, path)?);
+                    path.truncate(len);
+                }
+                MoveValue::Vector(elems)
+            },
+            _ => return Err(unexpected(type_tag, value)),
+        },
+        TypeTag::Struct(struct_tag) => {
+            if is_std_struct(struct_tag, "string", "String") {
+                match value {
+                    Value::String(s) => {
+                        MoveValue::Struct(MoveStruct::new(vec![MoveValue::vector_u8(
+                            s.as_bytes().to_vec(),
+                        )]))
+                    },
+                    _ => return Err(unexpected(type_tag, value)),
+                }
+            } else if is_std_struct(struct_tag, "option", "Option") {
+                let elem_type = struct_tag
+                    .type_params
+                    .first()
+                    .ok_or_else(|| format!("{} is missing its type argument", struct_tag))?;
+                let elems = match value {
+                    Value::Null => vec![],
+                    value => vec![json_to_move_value(elem_type, value, path)?],
+                };
+                MoveValue::Struct(MoveStruct::new(vec![MoveValue::Vector(elems)]))
+            } else if is_std_struct(struct_tag, "object", "Object") {
+                MoveValue::Struct(MoveStruct::new(vec![MoveValue::Address(parse_address(
+                    type_tag, value,
+                )?)]))
+            } else {
+                return Err(format!(
+                    "{} can not be passed to entry functions, the only structs allowed are \
+                     0x1::string::String, 0x1::option::Option and 0x1::object::Object",
+                    struct_tag
+                ));
+            }
+        },
+    })
+}
+
+fn parse_int<T: FromStr>(type_tag: &TypeTag, value: &Value) -> Result<T, String> {
+    // With arbitrary precision, numbers keep all their digits when printed.
+    let digits = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => return Err(unexpected(type_tag, value)),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!(
+            "expected a {} as a decimal integer, got {}",
+            type_tag, value
+        ));
+    }
+    digits
+        .parse()
+        .map_err(|_| format!("{} is out of range for a {}", digits, type_tag))
+}
+
+fn parse_address(type_tag: &TypeTag, value: &Value) -> Result<AccountAddress, String> {
+    match value {
+        Value::String(s) => AccountAddress::from_hex_literal(s).map_err(|_| {
+            format!(
+                "expected a {} as a 0x-prefixed hex string of at most 64 digits, got {}",
+                type_tag, value
+            )
+        }),
+        _ => Err(unexpected(type_tag, value)),
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits = hex
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("expected 0x-prefixed hex bytes, got {:?}", hex))?;
+    hex::decode(digits).map_err(|err| format!("invalid hex bytes {:?}: {}", hex, err))
+}
+
+fn unexpected(type_tag: &TypeTag, value: &Value) -> String {
+    let kind = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    };
+    format!("expected a {}, got {} ({})", type_tag, kind, value)
+}
+
+fn is_std_struct(tag: &StructTag, module: &str, name: &str) -> bool {
+    tag.address == AccountAddress::ONE && tag.module.as_str() == module && tag.name.as_str() == name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn type_tag(s: &str) -> TypeTag {
+        crate::move_types::parser::parse_type_tag(s).unwrap()
+    }
+
+    fn encode(param_type: &str, arg: Value) -> Result<Vec<u8>, ArgumentError> {
+        encode_entry_function_args(&[TypeTag::Signer, type_tag(param_type)], &[arg])
+            .map(|mut args| args.remove(0))
+    }
+
+    #[test]
+    fn test_encode_scalars() {
+        assert_eq!(
+            encode("u8", json!(7)).unwrap(),
+            bcs::to_bytes(&7u8).unwrap()
+        );
+        assert_eq!(
+            encode("u64", json!("18446744073709551615")).unwrap(),
+            bcs::to_bytes(&u64::MAX).unwrap()
+        );
+        assert_eq!(
+            encode("u128", json!(42)).unwrap(),
+            bcs::to_bytes(&42u128).unwrap()
+        );
+        assert_eq!(
+            encode("address", json!("0x1")).unwrap(),
+            bcs::to_bytes(&AccountAddress::ONE).unwrap()
+        );
+        assert_eq!(
+            encode("vector<u8>", json!("0xcafe")).unwrap(),
+            bcs::to_bytes(&vec![0xcau8, 0xfe]).unwrap()
+        );
+        assert_eq!(
+            encode("0x1::string::String", json!("hi")).unwrap(),
+            bcs::to_bytes("hi").unwrap()
+        );
+        assert_eq!(
+            encode("0x1::option::Option<u64>", json!(null)).unwrap(),
+            bcs::to_bytes(&Option::<u64>::None).unwrap()
+        );
+        assert_eq!(
+            encode("vector<0x1::option::Option<u64>>", json!(["1", null])).unwrap(),
+            bcs::to_bytes(&vec![Some(1u64), None]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = encode("vector<u8>", json!([1, 256])).unwrap_err();
+        assert_eq!(err.index, 0);
+        assert_eq!(err.path, "[1]");
+        assert_eq!(err.message, "256 is out of range for a u8");
+
+        let err = encode("u64", json!("-1")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument 0: expected a u64 as a decimal integer, got \"-1\""
+        );
+
+        assert!(encode("0x1::coin::Coin<u8>", json!("1")).is_err());
+        assert!(encode_entry_function_args(&[type_tag("u8")], &[]).is_err());
+    }
+}
@@ -9,6 +9,7 @@
 //! This SDK provides all the necessary components for building on top of the Aptos Blockchain. Some of the important modules are:
 //!
 //! * `crypto` - Types used for signing and verifying
+//! * `entry_function_args` - Encoding of entry function arguments given as JSON
 //! * `move_types` - Includes types used when interacting with the Move VM
 //! * `rest_client` - The Aptos API Client, used for sending requests to the Aptos Blockchain.
 //! * `transaction_builder` - Includes helpers for constructing transactions
@@ -24,6 +25,8 @@ pub use bcs;
 
 pub mod coin_client;
 
+pub mod entry_function_args;
+
 pub mod crypto {
     pub use aptos_crypto::*;
 }
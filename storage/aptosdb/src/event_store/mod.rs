@@ -10,8 +10,11 @@ use super::AptosDB;
 use crate::{
     errors::AptosDbError,
     schema::{
-        event::EventSchema, event_accumulator::EventAccumulatorSchema,
-        event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
+        event::EventSchema,
+        event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema,
+        event_by_type::{event_type_hash, EventByTypeSchema},
+        event_by_version::EventByVersionSchema,
     },
     utils::iterators::EventsByVersionIter,
 };
@@ -30,6 +33,7 @@ use aptos_types::{
     proof::position::Position,
     transaction::Version,
 };
+use move_core_types::language_storage::TypeTag;
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     convert::{TryFrom, TryInto},
@@ -190,6 +194,40 @@ impl EventStore {
         Ok(result)
     }
 
+    /// Given `type_tag` and `start_version`, returns the events of that type emitted by
+    /// transactions at or after `start_version`, identified by transaction version and index
+    /// among all events emitted by the same transaction. Result won't contain records with a
+    /// transaction version > `ledger_version` and is in ascending order.
+    pub fn lookup_events_by_type(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<
+        Vec<(
+            Version, // transaction version it belongs to
+            u64,     // index among events for the same transaction
+        )>,
+    > {
+        let type_tag_hash = event_type_hash(type_tag)?;
+        let mut iter = self
+            .event_db
+            .iter::<EventByTypeSchema>(ReadOptions::default())?;
+        iter.seek(&(type_tag_hash, start_version, 0))?;
+
+        let mut result = Vec::new();
+        for res in iter.take(limit as usize) {
+            let ((hash, ver, idx), ()) = res?;
+            if hash != type_tag_hash || ver > ledger_version {
+                break;
+            }
+            result.push((ver, idx));
+        }
+
+        Ok(result)
+    }
+
     fn lookup_event_by_key(
         &self,
         event_key: &EventKey,
@@ -317,7 +355,8 @@ impl EventStore {
             .iter()
             .enumerate()
             .try_for_each::<_, Result<_>>(|(idx, event)| {
-                if !skip_index {
+                // Module events have no key, hence are not indexed.
+                if let (false, ContractEvent::V0(event)) = (skip_index, event) {
                     batch.put::<EventByKeySchema>(
                         &(*event.key(), event.sequence_number()),
                         &(version, idx as u64),
@@ -327,6 +366,12 @@ impl EventStore {
                         &(idx as u64),
                     )?;
                 }
+                if !skip_index {
+                    batch.put::<EventByTypeSchema>(
+                        &(event_type_hash(event.type_tag())?, version, idx as u64),
+                        &(),
+                    )?;
+                }
                 batch.put::<EventSchema>(&(version, idx as u64), event)
             })?;
 
@@ -470,12 +515,20 @@ impl EventStore {
         let mut current_version = start;
         for events in self.get_events_by_version_iter(start, (end - start) as usize)? {
             for (current_index, event) in (events?).into_iter().enumerate() {
-                db_batch.delete::<EventByVersionSchema>(&(
-                    *event.key(),
+                if let ContractEvent::V0(event) = &event {
+                    db_batch.delete::<EventByVersionSchema>(&(
+                        *event.key(),
+                        current_version,
+                        event.sequence_number(),
+                    ))?;
+                    db_batch
+                        .delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+                }
+                db_batch.delete::<EventByTypeSchema>(&(
+                    event_type_hash(event.type_tag())?,
                     current_version,
-                    event.sequence_number(),
+                    current_index as u64,
                 ))?;
-                db_batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
                 db_batch.delete::<EventSchema>(&(current_version, current_index as u64))?;
             }
             current_version += 1;
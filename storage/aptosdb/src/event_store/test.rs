@@ -99,6 +99,57 @@ proptest! {
     }
 }
 
+#[test]
+fn test_lookup_events_by_type() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let store = &db.event_store;
+
+    let module_event = ContractEvent::new_v2(TypeTag::U64, vec![1]);
+    let other_event = ContractEvent::new_v2(TypeTag::Bool, vec![0]);
+    let handle_event = ContractEvent::new(EventKey::random(), 0, TypeTag::U64, vec![2]);
+    for (version, events) in [
+        (10, vec![module_event.clone(), other_event.clone()]),
+        (11, vec![other_event.clone()]),
+        (12, vec![other_event, handle_event, module_event]),
+    ] {
+        let batch = SchemaBatch::new();
+        store
+            .put_events(version, &events, /*skip_index=*/ false, &batch)
+            .unwrap();
+        store.event_db.write_schemas(batch).unwrap();
+    }
+
+    assert_eq!(
+        store
+            .lookup_events_by_type(&TypeTag::U64, 0, 10, 12)
+            .unwrap(),
+        vec![(10, 0), (12, 1), (12, 2)]
+    );
+    assert_eq!(
+        store
+            .lookup_events_by_type(&TypeTag::U64, 11, 10, 12)
+            .unwrap(),
+        vec![(12, 1), (12, 2)]
+    );
+    assert_eq!(
+        store
+            .lookup_events_by_type(&TypeTag::U64, 0, 1, 12)
+            .unwrap(),
+        vec![(10, 0)]
+    );
+    assert_eq!(
+        store
+            .lookup_events_by_type(&TypeTag::U64, 0, 10, 11)
+            .unwrap(),
+        vec![(10, 0)]
+    );
+    assert!(store
+        .lookup_events_by_type(&TypeTag::Address, 0, 10, 12)
+        .unwrap()
+        .is_empty());
+}
+
 fn traverse_events_by_key(
     store: &EventStore,
     event_key: &EventKey,
@@ -74,6 +74,7 @@ use aptos_crypto::HashValue;
 use aptos_db_indexer::Indexer;
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
+use aptos_runtimes::thread_pools::{get_thread_pool, ThreadPoolKind};
 use aptos_schemadb::{SchemaBatch, DB};
 use aptos_storage_interface::{
     cached_state_view::ShardedStateCache, state_delta::StateDelta, state_view::DbStateView,
@@ -111,6 +112,7 @@ use aptos_types::{
 };
 use aptos_vm::data_cache::AsMoveResolver;
 use arr_macro::arr;
+use move_core_types::language_storage::TypeTag;
 use move_resource_viewer::MoveValueAnnotator;
 use once_cell::sync::Lazy;
 use rayon::prelude::*;
@@ -132,13 +134,8 @@ pub const STATE_KV_DB_NAME: &str = "state_kv_db";
 
 pub(crate) const NUM_STATE_SHARDS: usize = 16;
 
-static COMMIT_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(32)
-        .thread_name(|index| format!("commit_{}", index))
-        .build()
-        .unwrap()
-});
+static COMMIT_POOL: Lazy<Arc<rayon::ThreadPool>> =
+    Lazy::new(|| get_thread_pool(ThreadPoolKind::Commit));
 
 // TODO: Either implement an iteration API to allow a very old client to loop through a long history
 // or guarantee that there is always a recent enough waypoint and client knows to boot from there.
@@ -1570,6 +1567,36 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_events_by_type(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<EventWithVersion>> {
+        gauged_api("get_events_by_type", || {
+            error_if_too_many_requested(limit, MAX_REQUEST_LIMIT)?;
+            self.error_if_ledger_pruned("Event", start_version)?;
+
+            self.event_store
+                .lookup_events_by_type(type_tag, start_version, limit, ledger_version)?
+                .into_iter()
+                .map(|(version, index)| {
+                    let event = self
+                        .event_store
+                        .get_event_by_version_and_index(version, index)?;
+                    ensure!(
+                        event.type_tag() == type_tag,
+                        "Index broken, expected type: {}, actual: {}",
+                        type_tag,
+                        event.type_tag()
+                    );
+                    Ok(EventWithVersion::new(version, event))
+                })
+                .collect()
+        })
+    }
+
     fn get_transaction_iterator(
         &self,
         start_version: Version,
@@ -2106,8 +2133,12 @@ impl DbWriter for AptosDB {
                     let _timer = OTHER_TIMERS_SECONDS
                         .with_label_values(&["buffered_state___update"])
                         .start_timer();
+                    // A block not ending with a state checkpoint leaves its updates in the
+                    // in-memory state after the last checkpoint.
+                    let ends_with_checkpoint = latest_in_memory_state.base_version
+                        == latest_in_memory_state.current_version;
                     buffered_state.update(
-                        Some(block_state_updates),
+                        ends_with_checkpoint.then_some(block_state_updates),
                         latest_in_memory_state,
                         sync_commit || txns_to_commit.last().unwrap().is_reconfig(),
                     )?;
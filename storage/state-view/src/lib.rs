@@ -45,6 +45,16 @@ pub trait TStateView {
     /// Gets the state value for a given state key.
     fn get_state_value(&self, state_key: &Self::Key) -> Result<Option<StateValue>>;
 
+    /// Gets the state values for the given state keys, in the same order. The default
+    /// implementation reads the keys one by one, backends can override it to amortize the
+    /// per-read overhead (e.g. locking) across the keys.
+    fn get_state_values(&self, state_keys: &[Self::Key]) -> Result<Vec<Option<StateValue>>> {
+        state_keys
+            .iter()
+            .map(|state_key| self.get_state_value(state_key))
+            .collect()
+    }
+
     /// VM needs this method to know whether the current state view is for genesis state creation.
     /// Currently TransactionPayload::WriteSet is only valid for genesis state creation.
     fn is_genesis(&self) -> bool;
@@ -88,6 +98,10 @@ where
         self.deref().get_state_value(state_key)
     }
 
+    fn get_state_values(&self, state_keys: &[K]) -> Result<Vec<Option<StateValue>>> {
+        self.deref().get_state_values(state_keys)
+    }
+
     fn is_genesis(&self) -> bool {
         self.deref().is_genesis()
     }
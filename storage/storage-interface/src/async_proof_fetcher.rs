@@ -5,12 +5,12 @@ use crate::{metrics::TIMER, DbReader};
 use anyhow::{anyhow, Result};
 use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_logger::{error, sample, sample::SampleRate};
+use aptos_runtimes::thread_pools::{get_thread_pool, ThreadPoolKind};
 use aptos_types::{
     proof::SparseMerkleProofExt,
     state_store::{state_key::StateKey, state_value::StateValue},
     transaction::Version,
 };
-use aptos_vm::AptosVM;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use once_cell::sync::Lazy;
 use std::{
@@ -22,13 +22,8 @@ use std::{
     time::Duration,
 };
 
-static IO_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(AptosVM::get_num_proof_reading_threads())
-        .thread_name(|index| format!("proof_reader_{}", index))
-        .build()
-        .unwrap()
-});
+static IO_POOL: Lazy<Arc<rayon::ThreadPool>> =
+    Lazy::new(|| get_thread_pool(ThreadPoolKind::ProofReading));
 
 struct Proof {
     state_key_hash: HashValue,
@@ -267,6 +267,32 @@ impl TStateView for CachedDbStateView {
         Ok(new_value.clone())
     }
 
+    fn get_state_values(&self, state_keys: &[StateKey]) -> Result<Vec<Option<StateValue>>> {
+        // Read the uncached keys from the DB (one by one, as the DB has no batched point
+        // lookup), then insert them into the cache under a single write lock.
+        let missing_keys: Vec<StateKey> = {
+            let cache = self.state_cache.read();
+            state_keys
+                .iter()
+                .filter(|state_key| !cache.contains_key(state_key))
+                .cloned()
+                .collect()
+        };
+        if !missing_keys.is_empty() {
+            let missing_values = self.db_state_view.get_state_values(&missing_keys)?;
+            let mut cache = self.state_cache.write();
+            for (state_key, state_value_option) in missing_keys.into_iter().zip(missing_values) {
+                cache.entry(state_key).or_insert(state_value_option);
+            }
+        }
+
+        let cache = self.state_cache.read();
+        Ok(state_keys
+            .iter()
+            .map(|state_key| cache.get(state_key).cloned().flatten())
+            .collect())
+    }
+
     fn is_genesis(&self) -> bool {
         self.db_state_view.is_genesis()
     }
@@ -35,6 +35,7 @@ use aptos_types::{
     },
     write_set::WriteSet,
 };
+use move_core_types::language_storage::TypeTag;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 use thiserror::Error;
@@ -198,6 +199,18 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the events of the given type emitted at or after `start_version`, in ascending
+    /// order of version. Unlike `get_events`, this also covers module events.
+    fn get_events_by_type(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<EventWithVersion>> {
+        unimplemented!()
+    }
+
     fn get_transaction_iterator(
         &self,
         start_version: Version,
@@ -1932,6 +1932,16 @@ impl<'a> Resolver<'a> {
         self.loader.type_to_type_layout(ty)
     }
 
+    pub(crate) fn type_to_type_layout_with_limits(
+        &self,
+        ty: &Type,
+        max_nodes: u64,
+        max_depth: u64,
+    ) -> PartialVMResult<(MoveTypeLayout, u64)> {
+        self.loader
+            .type_to_type_layout_with_limits(ty, max_nodes, max_depth)
+    }
+
     pub(crate) fn type_to_fully_annotated_layout(
         &self,
         ty: &Type,
@@ -2731,6 +2741,22 @@ pub const VALUE_DEPTH_MAX: u64 = 128;
 /// fields for struct types.
 const MAX_TYPE_TO_LAYOUT_NODES: u64 = 256;
 
+/// Limits on the layouts built by `type_to_type_layout_impl`.
+#[derive(Clone, Copy)]
+struct LayoutLimits {
+    max_nodes: u64,
+    max_depth: u64,
+}
+
+impl Default for LayoutLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: MAX_TYPE_TO_LAYOUT_NODES,
+            max_depth: VALUE_DEPTH_MAX,
+        }
+    }
+}
+
 /// Maximal nodes which are all allowed when instantiating a generic type. This does not include
 /// field types of structs.
 const MAX_TYPE_INSTANTIATION_NODES: u64 = 128;
@@ -2862,6 +2888,7 @@ impl Loader {
         ty_args: &[Type],
         count: &mut u64,
         depth: u64,
+        limits: LayoutLimits,
     ) -> PartialVMResult<MoveStructLayout> {
         if let Some(struct_map) = self.type_cache.read().structs.get(&gidx) {
             if let Some(struct_info) = struct_map.get(ty_args) {
@@ -2883,7 +2910,7 @@ impl Loader {
             .collect::<PartialVMResult<Vec<_>>>()?;
         let field_layouts = field_tys
             .iter()
-            .map(|ty| self.type_to_type_layout_impl(ty, count, depth + 1))
+            .map(|ty| self.type_to_type_layout_impl(ty, count, depth + 1, limits))
             .collect::<PartialVMResult<Vec<_>>>()?;
         let field_node_count = *count - count_before;
 
@@ -2907,11 +2934,12 @@ impl Loader {
         ty: &Type,
         count: &mut u64,
         depth: u64,
+        limits: LayoutLimits,
     ) -> PartialVMResult<MoveTypeLayout> {
-        if *count > MAX_TYPE_TO_LAYOUT_NODES {
+        if *count > limits.max_nodes {
             return Err(PartialVMError::new(StatusCode::TOO_MANY_TYPE_NODES));
         }
-        if depth > VALUE_DEPTH_MAX {
+        if depth > limits.max_depth {
             return Err(PartialVMError::new(StatusCode::VM_MAX_VALUE_DEPTH_REACHED));
         }
         Ok(match ty {
@@ -2957,16 +2985,23 @@ impl Loader {
                     ty,
                     count,
                     depth + 1,
+                    limits,
                 )?))
             },
             Type::Struct(gidx) => {
                 *count += 1;
-                MoveTypeLayout::Struct(self.struct_gidx_to_type_layout(*gidx, &[], count, depth)?)
+                MoveTypeLayout::Struct(self.struct_gidx_to_type_layout(
+                    *gidx,
+                    &[],
+                    count,
+                    depth,
+                    limits,
+                )?)
             },
             Type::StructInstantiation(gidx, ty_args) => {
                 *count += 1;
                 MoveTypeLayout::Struct(
-                    self.struct_gidx_to_type_layout(*gidx, ty_args, count, depth)?,
+                    self.struct_gidx_to_type_layout(*gidx, ty_args, count, depth, limits)?,
                 )
             },
             Type::Reference(_) | Type::MutableReference(_) | Type::TyParam(_) => {
@@ -3092,7 +3127,29 @@ impl Loader {
 
     pub(crate) fn type_to_type_layout(&self, ty: &Type) -> PartialVMResult<MoveTypeLayout> {
         let mut count = 0;
-        self.type_to_type_layout_impl(ty, &mut count, 1)
+        self.type_to_type_layout_impl(ty, &mut count, 1, LayoutLimits::default())
+    }
+
+    /// Same as `type_to_type_layout`, but fails as soon as the layout exceeds the given limits
+    /// (if they are below the default ones), and also returns its number of nodes.
+    pub(crate) fn type_to_type_layout_with_limits(
+        &self,
+        ty: &Type,
+        max_nodes: u64,
+        max_depth: u64,
+    ) -> PartialVMResult<(MoveTypeLayout, u64)> {
+        let limits = LayoutLimits {
+            max_nodes: max_nodes.min(MAX_TYPE_TO_LAYOUT_NODES),
+            max_depth: max_depth.min(VALUE_DEPTH_MAX),
+        };
+        let mut count = 0;
+        let layout = self.type_to_type_layout_impl(ty, &mut count, 1, limits)?;
+        // The node count of a cached struct layout is only checked against the limit when the
+        // next node is visited.
+        if count > limits.max_nodes {
+            return Err(PartialVMError::new(StatusCode::TOO_MANY_TYPE_NODES));
+        }
+        Ok((layout, count))
     }
 
     pub(crate) fn type_to_fully_annotated_layout(
@@ -13,7 +13,8 @@ use move_core_types::{
     account_address::AccountAddress,
     gas_algebra::{InternalGas, NumBytes},
     identifier::Identifier,
-    language_storage::TypeTag,
+    language_storage::{ModuleId, TypeTag},
+    metadata::Metadata,
     value::MoveTypeLayout,
     vm_status::{StatusCode, StatusType},
 };
@@ -168,10 +169,30 @@ impl<'a, 'b, 'c> NativeContext<'a, 'b, 'c> {
         self.resolver.type_to_type_layout(ty)
     }
 
+    /// Same as `type_to_type_layout`, but fails as soon as the layout has more than `max_nodes`
+    /// nodes or is deeper than `max_depth`. Also returns the number of nodes of the layout.
+    pub fn type_to_type_layout_with_limits(
+        &self,
+        ty: &Type,
+        max_nodes: u64,
+        max_depth: u64,
+    ) -> PartialVMResult<(MoveTypeLayout, u64)> {
+        self.resolver
+            .type_to_type_layout_with_limits(ty, max_nodes, max_depth)
+    }
+
     pub fn type_to_fully_annotated_layout(&self, ty: &Type) -> PartialVMResult<MoveTypeLayout> {
         self.resolver.type_to_fully_annotated_layout(ty)
     }
 
+    /// Applies `f` to the metadata of the given module, if it is loaded.
+    pub fn with_module_metadata<T, F>(&self, module: &ModuleId, f: F) -> Option<T>
+    where
+        F: FnOnce(&[Metadata]) -> Option<T>,
+    {
+        f(&self.resolver.loader().get_module(module)?.module().metadata)
+    }
+
     pub fn extensions(&self) -> &NativeContextExtensions<'b> {
         self.extensions
     }
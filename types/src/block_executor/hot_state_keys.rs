@@ -0,0 +1,206 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A registry of the keys that recently caused contention during execution.
+//!
+//! The block executor records the keys written by more than one committed transaction at the end
+//! of each block. The partitioner consults the registry, so that the placement of transactions
+//! reacts to the contention observed in the previous blocks rather than relying only on static
+//! heuristics.
+//!
+//! The contents of the global registry depend on the blocks executed since the process started,
+//! so it must not influence anything the nodes have to agree on. The transaction shuffler of
+//! consensus instead uses a registry built at the start of each epoch from the writes of the last
+//! committed transactions, which are the same on every node.
+
+use crate::{
+    account_address::AccountAddress,
+    state_store::state_key::{StateKey, StateKeyInner},
+};
+use aptos_infallible::RwLock;
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::Arc,
+};
+
+/// The registry of hot state keys shared by the block executor and the partitioner of this
+/// process.
+pub static HOT_STATE_KEYS: Lazy<Arc<HotStateKeyRegistry>> =
+    Lazy::new(|| Arc::new(HotStateKeyRegistry::new(HotKeyConfig::default())));
+
+pub type HotStateKeyRegistry = HotKeyRegistry<StateKey>;
+
+/// Scores below which keys are forgotten.
+const MIN_SCORE: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HotKeyConfig {
+    /// Factor by which the scores of the keys are multiplied at the end of each block, so that
+    /// keys that stop causing conflicts cool down over the next few blocks.
+    pub decay: f64,
+    /// Score from which a key is considered hot. With a decay of 0.5, a key that causes 2
+    /// conflicts per block converges to a score of 4.
+    pub hot_threshold: f64,
+    /// Maximum number of keys tracked, the keys with the lowest scores are forgotten first.
+    pub max_tracked_keys: usize,
+}
+
+impl Default for HotKeyConfig {
+    fn default() -> Self {
+        Self {
+            decay: 0.5,
+            hot_threshold: 4.0,
+            max_tracked_keys: 1024,
+        }
+    }
+}
+
+/// Tracks an exponentially decaying count of the conflicts caused by each key.
+pub struct HotKeyRegistry<K> {
+    config: HotKeyConfig,
+    scores: RwLock<HashMap<K, f64>>,
+}
+
+impl<K: Clone + Eq + Hash + Ord> HotKeyRegistry<K> {
+    pub fn new(config: HotKeyConfig) -> Self {
+        Self {
+            config,
+            scores: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Records the keys that caused conflicts in a block (once per conflict), and
+    /// decays the scores of the keys recorded for the previous blocks.
+    pub fn record_block(&self, conflicting_keys: impl IntoIterator<Item = K>) {
+        let mut counts: HashMap<K, usize> = HashMap::new();
+        for key in conflicting_keys {
+            *counts.entry(key).or_default() += 1;
+        }
+
+        let mut scores = self.scores.write();
+        for score in scores.values_mut() {
+            *score *= self.config.decay;
+        }
+        for (key, count) in counts {
+            *scores.entry(key).or_default() += count as f64;
+        }
+        scores.retain(|_, score| *score >= MIN_SCORE);
+
+        if scores.len() > self.config.max_tracked_keys {
+            // Ties are broken by key, so that the same keys are forgotten for the same blocks.
+            let mut by_score: Vec<_> = scores.drain().collect();
+            by_score.sort_unstable_by(|(key1, score1), (key2, score2)| {
+                score2.total_cmp(score1).then_with(|| key1.cmp(key2))
+            });
+            by_score.truncate(self.config.max_tracked_keys);
+            scores.extend(by_score);
+        }
+    }
+
+    /// Records the keys written by several transactions of a block, given the keys written by
+    /// each transaction, once per writer after the first one.
+    pub fn record_block_writes<I>(&self, txn_writes: impl IntoIterator<Item = I>)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let mut num_writers: HashMap<K, usize> = HashMap::new();
+        for writes in txn_writes {
+            let written_keys: HashSet<K> = writes.into_iter().collect();
+            for key in written_keys {
+                *num_writers.entry(key).or_default() += 1;
+            }
+        }
+        self.record_block(
+            num_writers
+                .into_iter()
+                .flat_map(|(key, n)| std::iter::repeat(key).take(n - 1)),
+        );
+    }
+
+    pub fn is_hot(&self, key: &K) -> bool {
+        self.scores
+            .read()
+            .get(key)
+            .map_or(false, |score| *score >= self.config.hot_threshold)
+    }
+
+    /// Returns a snapshot of the keys that are currently hot.
+    pub fn hot_keys(&self) -> HashSet<K> {
+        self.scores
+            .read()
+            .iter()
+            .filter(|(_, score)| **score >= self.config.hot_threshold)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.scores.write().clear();
+    }
+}
+
+impl HotKeyRegistry<StateKey> {
+    /// Returns the accounts under which resources are currently hot.
+    pub fn hot_addresses(&self) -> HashSet<AccountAddress> {
+        self.hot_keys()
+            .iter()
+            .filter_map(|key| match key.inner() {
+                StateKeyInner::AccessPath(access_path) => Some(access_path.address),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> HotKeyRegistry<u64> {
+        HotKeyRegistry::new(HotKeyConfig {
+            decay: 0.5,
+            hot_threshold: 2.0,
+            max_tracked_keys: 2,
+        })
+    }
+
+    #[test]
+    fn test_keys_heat_up_and_cool_down() {
+        let registry = registry();
+        registry.record_block(vec![1, 1, 2]);
+        assert!(registry.is_hot(&1));
+        assert!(!registry.is_hot(&2));
+
+        // Repeated conflicts on key 2 heat it up, while key 1 cools down.
+        registry.record_block(vec![2]);
+        assert!(!registry.is_hot(&1));
+        assert!(!registry.is_hot(&2));
+        registry.record_block(vec![2, 2]);
+        assert_eq!(registry.hot_keys(), HashSet::from([2]));
+
+        for _ in 0..5 {
+            registry.record_block(vec![]);
+        }
+        assert!(registry.scores.read().is_empty());
+    }
+
+    #[test]
+    fn test_record_block_writes() {
+        let registry = registry();
+        // Keys are counted once per transaction writing them, after the first one.
+        registry.record_block_writes(vec![vec![1, 1, 2], vec![1, 3], vec![1]]);
+        assert_eq!(registry.hot_keys(), HashSet::from([1]));
+        assert!(!registry.scores.read().contains_key(&2));
+    }
+
+    #[test]
+    fn test_max_tracked_keys() {
+        let registry = registry();
+        registry.record_block(vec![1, 1, 1, 2, 2, 3]);
+        let scores = registry.scores.read();
+        assert_eq!(scores.len(), 2);
+        assert!(!scores.contains_key(&3));
+    }
+}
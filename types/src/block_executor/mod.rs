@@ -2,4 +2,6 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod hot_state_keys;
 pub mod partitioner;
+pub mod shard_load;
@@ -1,9 +1,13 @@
 // Copyright © Aptos Foundation
 
-use crate::transaction::{
-    analyzed_transaction::{AnalyzedTransaction, StorageLocation},
-    Transaction,
+use crate::{
+    block_metadata::BlockMetadata,
+    transaction::{
+        analyzed_transaction::{AnalyzedTransaction, StorageLocation},
+        Transaction,
+    },
 };
+use anyhow::{ensure, Result};
 use aptos_crypto::HashValue;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -88,6 +92,9 @@ impl IntoIterator for CrossShardEdges {
 pub struct CrossShardDependencies {
     required_edges: CrossShardEdges,
     dependent_edges: CrossShardEdges,
+    // The length of the longest chain of transactions (transitively) depending on the transaction
+    // across shards, see `SubBlocksForShard::set_dependent_chain_lengths`.
+    dependent_chain_length: usize,
 }
 
 impl CrossShardDependencies {
@@ -99,6 +106,10 @@ impl CrossShardDependencies {
         &self.dependent_edges
     }
 
+    pub fn dependent_chain_length(&self) -> usize {
+        self.dependent_chain_length
+    }
+
     pub fn num_required_edges(&self) -> usize {
         self.required_edges.len()
     }
@@ -318,6 +329,47 @@ impl<T: Clone> SubBlocksForShard<T> {
         self.sub_blocks.get_mut(round)
     }
 
+    // Sets the length of the longest chain of cross shard dependencies starting at each transaction
+    // of the block, i.e. of transactions of other shards waiting on the transaction, then on each
+    // other one after the other. The writes of the transactions with the longest chains are on the
+    // critical path of the block, so they are sent and processed ahead of the others.
+    pub fn set_dependent_chain_lengths(block: &mut [SubBlocksForShard<T>]) {
+        let num_rounds = block
+            .iter()
+            .map(|sub_blocks| sub_blocks.num_sub_blocks())
+            .max()
+            .unwrap_or_default();
+        let mut chain_lengths: HashMap<TxnIndex, usize> = HashMap::new();
+        // The dependent transactions come later in the block, i.e. in a later round or in a later
+        // shard of the same round, so they are visited first in the reverse order of the block.
+        for round in (0..num_rounds).rev() {
+            for sub_blocks in block.iter_mut().rev() {
+                let sub_block = match sub_blocks.get_sub_block_mut(round) {
+                    Some(sub_block) => sub_block,
+                    None => continue,
+                };
+                let start_index = sub_block.start_index;
+                for (offset, txn) in sub_block.transactions.iter_mut().enumerate().rev() {
+                    let chain_length = txn
+                        .cross_shard_dependencies
+                        .dependent_edges
+                        .iter()
+                        .map(|(dependent_txn_idx, _)| {
+                            chain_lengths
+                                .get(&dependent_txn_idx.txn_index)
+                                .map_or(1, |chain_length| chain_length + 1)
+                        })
+                        .max()
+                        .unwrap_or_default();
+                    txn.cross_shard_dependencies.dependent_chain_length = chain_length;
+                    if chain_length > 0 {
+                        chain_lengths.insert(start_index + offset, chain_length);
+                    }
+                }
+            }
+        }
+    }
+
     // Flattens a vector of `SubBlocksForShard` into a vector of transactions in the order they
     // appear in the block.
     pub fn flatten(block: Vec<SubBlocksForShard<T>>) -> Vec<T> {
@@ -375,9 +427,13 @@ impl<T: Clone> TransactionWithDependencies<T> {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExecutableBlock {
     pub block_id: HashValue,
     pub transactions: ExecutableTransactions,
+    /// If set, the corresponding block metadata transaction is prepended to the transactions of
+    /// the block when it is executed.
+    pub block_metadata: Option<BlockMetadata>,
 }
 
 impl ExecutableBlock {
@@ -385,8 +441,39 @@ impl ExecutableBlock {
         Self {
             block_id,
             transactions,
+            block_metadata: None,
         }
     }
+
+    pub fn with_block_metadata(mut self, block_metadata: BlockMetadata) -> Self {
+        self.block_metadata = Some(block_metadata);
+        self
+    }
+
+    /// The number of transactions to execute, including the block metadata transaction (if any).
+    pub fn num_transactions(&self) -> usize {
+        self.transactions.num_transactions() + usize::from(self.block_metadata.is_some())
+    }
+
+    /// Returns the transactions to execute, with the block metadata transaction (if any)
+    /// prepended.
+    pub fn into_transactions(self) -> Result<ExecutableTransactions> {
+        let Self {
+            block_id,
+            mut transactions,
+            block_metadata,
+        } = self;
+        if let Some(block_metadata) = block_metadata {
+            ensure!(
+                block_metadata.id() == block_id,
+                "Block metadata id {} does not match block id {}",
+                block_metadata.id(),
+                block_id,
+            );
+            transactions.prepend_block_metadata(block_metadata);
+        }
+        Ok(transactions)
+    }
 }
 
 impl From<(HashValue, Vec<Transaction>)> for ExecutableBlock {
@@ -396,19 +483,55 @@ impl From<(HashValue, Vec<Transaction>)> for ExecutableBlock {
 }
 
 // Represents the transactions in a block that are ready to be executed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ExecutableTransactions {
     Unsharded(Vec<Transaction>),
     Sharded(Vec<SubBlocksForShard<AnalyzedTransaction>>),
+    /// A prefix of the block that is executed unsharded (e.g. the block metadata transaction, or
+    /// transactions writing hot keys), followed by the rest of the block, which is executed sharded
+    /// on top of the state resulting from the prefix.
+    Hybrid {
+        unsharded_prefix: Vec<Transaction>,
+        sharded: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+    },
 }
 
 impl ExecutableTransactions {
     pub fn num_transactions(&self) -> usize {
         match self {
             ExecutableTransactions::Unsharded(transactions) => transactions.len(),
-            ExecutableTransactions::Sharded(sub_blocks) => sub_blocks
-                .iter()
-                .map(|sub_block| sub_block.num_txns())
-                .sum(),
+            ExecutableTransactions::Sharded(sub_blocks) => Self::num_sharded_txns(sub_blocks),
+            ExecutableTransactions::Hybrid {
+                unsharded_prefix,
+                sharded,
+            } => unsharded_prefix.len() + Self::num_sharded_txns(sharded),
+        }
+    }
+
+    fn num_sharded_txns(sub_blocks: &[SubBlocksForShard<AnalyzedTransaction>]) -> usize {
+        sub_blocks
+            .iter()
+            .map(|sub_block| sub_block.num_txns())
+            .sum()
+    }
+
+    /// Prepends the block metadata transaction. The indices (and the cross shard dependencies) of
+    /// sharded transactions are fixed by the partitioner, so the block metadata transaction is
+    /// executed as (part of) the unsharded prefix of the block instead.
+    pub fn prepend_block_metadata(&mut self, block_metadata: BlockMetadata) {
+        let block_metadata = Transaction::BlockMetadata(block_metadata);
+        match self {
+            ExecutableTransactions::Unsharded(transactions)
+            | ExecutableTransactions::Hybrid {
+                unsharded_prefix: transactions,
+                ..
+            } => transactions.insert(0, block_metadata),
+            ExecutableTransactions::Sharded(sub_blocks) => {
+                *self = ExecutableTransactions::Hybrid {
+                    unsharded_prefix: vec![block_metadata],
+                    sharded: std::mem::take(sub_blocks),
+                };
+            },
         }
     }
 }
@@ -418,3 +541,52 @@ impl From<Vec<Transaction>> for ExecutableTransactions {
         Self::Unsharded(txns)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sub_block(start_index: TxnIndex, num_txns: usize) -> SubBlock<usize> {
+        SubBlock::new(
+            start_index,
+            (start_index..start_index + num_txns)
+                .map(|txn_idx| {
+                    TransactionWithDependencies::new(txn_idx, CrossShardDependencies::default())
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_set_dependent_chain_lengths() {
+        // The transactions 0 and 1 are in shard 0 and 2 in shard 1 for round 0, and 3 in shard 0
+        // and 4 in shard 1 for round 1.
+        let mut block = vec![
+            SubBlocksForShard::new(0, vec![sub_block(0, 2), sub_block(3, 1)]),
+            SubBlocksForShard::new(1, vec![sub_block(2, 1), sub_block(4, 1)]),
+        ];
+        for (source, target) in [
+            ((0, 0, 0), (2, 1, 0)),
+            ((2, 1, 0), (3, 0, 1)),
+            ((3, 0, 1), (4, 1, 1)),
+            ((1, 0, 0), (4, 1, 1)),
+        ] {
+            let (source_idx, source_shard_id, source_round) = source;
+            let (target_idx, target_shard_id, target_round) = target;
+            block[source_shard_id].sub_blocks[source_round].add_dependent_edge(
+                source_idx,
+                ShardedTxnIndex::new(target_idx, target_shard_id, target_round),
+                vec![],
+            );
+        }
+        SubBlocksForShard::set_dependent_chain_lengths(&mut block);
+
+        let mut chain_lengths = vec![0; 5];
+        for sub_blocks in &block {
+            for txn in sub_blocks.iter() {
+                chain_lengths[*txn.txn()] = txn.cross_shard_dependencies().dependent_chain_length();
+            }
+        }
+        assert_eq!(chain_lengths, vec![3, 1, 2, 1, 0]);
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! The load of the executor shards, for the number of shards to adapt between blocks.
+//!
+//! The executor shards record how long they took to execute their part of each block. Before
+//! partitioning the next block, the partitioner asks for the number of shards to use given that
+//! load, and the sharded block executor resizes itself to the number of shards the block is
+//! partitioned into.
+
+use crate::block_executor::partitioner::ShardId;
+use aptos_infallible::RwLock;
+use once_cell::sync::Lazy;
+use std::{sync::Arc, time::Duration};
+
+/// The load of the executor shards of this process.
+pub static SHARD_LOAD: Lazy<Arc<ShardLoadMonitor>> =
+    Lazy::new(|| Arc::new(ShardLoadMonitor::new()));
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DynamicShardingConfig {
+    pub min_shards: usize,
+    pub max_shards: usize,
+    /// Execution time of the slowest shard above which a shard is added.
+    pub scale_up_threshold: Duration,
+    /// Execution time of the slowest shard below which a shard is removed, as the cross shard
+    /// messages outweigh the parallelism.
+    pub scale_down_threshold: Duration,
+    /// Ratio of the execution time of the slowest shard to the average one above which a shard is
+    /// removed, as the other shards mostly wait on the slowest one (e.g. for cross shard values).
+    pub max_imbalance: f64,
+}
+
+impl Default for DynamicShardingConfig {
+    fn default() -> Self {
+        Self {
+            min_shards: 2,
+            max_shards: 8,
+            scale_up_threshold: Duration::from_millis(200),
+            scale_down_threshold: Duration::from_millis(50),
+            max_imbalance: 2.0,
+        }
+    }
+}
+
+/// Tracks the execution time of each shard for the latest block it executed.
+pub struct ShardLoadMonitor {
+    execution_times: RwLock<Vec<Option<Duration>>>,
+}
+
+impl ShardLoadMonitor {
+    pub fn new() -> Self {
+        Self {
+            execution_times: RwLock::new(vec![]),
+        }
+    }
+
+    /// Records how long a shard, out of `num_shards`, took to execute its part of a block. The
+    /// times recorded with another number of shards are forgotten.
+    pub fn record_execution_time(
+        &self,
+        shard_id: ShardId,
+        num_shards: usize,
+        execution_time: Duration,
+    ) {
+        let mut execution_times = self.execution_times.write();
+        if execution_times.len() != num_shards {
+            *execution_times = vec![None; num_shards];
+        }
+        execution_times[shard_id] = Some(execution_time);
+    }
+
+    /// Returns the number of shards to partition the next block into, given the load of the
+    /// `num_shards` shards the previous blocks were executed with. The number of shards changes by
+    /// at most one per block, and only once all the shards recorded their execution time.
+    pub fn recommended_num_shards(
+        &self,
+        num_shards: usize,
+        config: &DynamicShardingConfig,
+    ) -> usize {
+        let execution_times: Option<Vec<Duration>> = {
+            let execution_times = self.execution_times.read();
+            if execution_times.len() == num_shards {
+                execution_times.iter().cloned().collect()
+            } else {
+                None
+            }
+        };
+        let recommended = match execution_times {
+            Some(execution_times) if !execution_times.is_empty() => {
+                let slowest = execution_times.iter().max().cloned().unwrap_or_default();
+                let average = execution_times.iter().sum::<Duration>() / num_shards as u32;
+                let imbalance = slowest.as_secs_f64() / average.as_secs_f64().max(f64::EPSILON);
+                if imbalance > config.max_imbalance || slowest < config.scale_down_threshold {
+                    num_shards.saturating_sub(1)
+                } else if slowest > config.scale_up_threshold {
+                    num_shards + 1
+                } else {
+                    num_shards
+                }
+            },
+            _ => num_shards,
+        };
+        recommended.clamp(config.min_shards, config.max_shards.max(config.min_shards))
+    }
+
+    pub fn clear(&self) {
+        self.execution_times.write().clear();
+    }
+}
+
+impl Default for ShardLoadMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_block(monitor: &ShardLoadMonitor, execution_times_ms: &[u64]) {
+        for (shard_id, execution_time_ms) in execution_times_ms.iter().enumerate() {
+            monitor.record_execution_time(
+                shard_id,
+                execution_times_ms.len(),
+                Duration::from_millis(*execution_time_ms),
+            );
+        }
+    }
+
+    #[test]
+    fn test_recommended_num_shards() {
+        let config = DynamicShardingConfig::default();
+        let monitor = ShardLoadMonitor::new();
+        // Without any load recorded for the current number of shards, it is kept.
+        assert_eq!(monitor.recommended_num_shards(4, &config), 4);
+        record_block(&monitor, &[300, 250]);
+        assert_eq!(monitor.recommended_num_shards(4, &config), 4);
+
+        // Slow but balanced shards.
+        assert_eq!(monitor.recommended_num_shards(2, &config), 3);
+        // Shards waiting on the slowest one.
+        record_block(&monitor, &[300, 20, 40]);
+        assert_eq!(monitor.recommended_num_shards(3, &config), 2);
+        // Fast shards.
+        record_block(&monitor, &[30, 40, 30]);
+        assert_eq!(monitor.recommended_num_shards(3, &config), 2);
+        record_block(&monitor, &[100, 120, 110]);
+        assert_eq!(monitor.recommended_num_shards(3, &config), 3);
+
+        // The number of shards stays within the bounds.
+        record_block(&monitor, &[10, 10]);
+        assert_eq!(monitor.recommended_num_shards(2, &config), 2);
+        record_block(&monitor, &[500; 8]);
+        assert_eq!(monitor.recommended_num_shards(8, &config), 8);
+    }
+}
@@ -9,16 +9,21 @@ use crate::{
 };
 use anyhow::{Error, Result};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
-use move_core_types::{language_storage::TypeTag, move_resource::MoveStructType};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    move_resource::MoveStructType,
+};
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
-use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, ops::Deref};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::TryFrom, ops::Deref};
 
 /// Support versioning of the data structure.
 #[derive(Hash, Clone, Eq, PartialEq, Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
 pub enum ContractEvent {
     V0(ContractEventV0),
+    V2(ContractEventV2),
 }
 
 impl ContractEvent {
@@ -35,18 +40,171 @@ impl ContractEvent {
             event_data,
         ))
     }
+
+    pub fn new_v2(type_tag: TypeTag, event_data: Vec<u8>) -> Self {
+        ContractEvent::V2(ContractEventV2::new(type_tag, event_data))
+    }
+
+    pub fn new_v2_versioned(type_tag: TypeTag, schema_version: u64, event_data: Vec<u8>) -> Self {
+        ContractEvent::V2(ContractEventV2::new_versioned(
+            type_tag,
+            schema_version,
+            event_data,
+        ))
+    }
+
+    pub fn is_v0(&self) -> bool {
+        matches!(self, ContractEvent::V0(_))
+    }
+
+    pub fn is_v2(&self) -> bool {
+        matches!(self, ContractEvent::V2(_))
+    }
+
+    pub fn v0(&self) -> Result<&ContractEventV0> {
+        match self {
+            ContractEvent::V0(event) => Ok(event),
+            ContractEvent::V2(_) => anyhow::bail!("This is a module event"),
+        }
+    }
+
+    pub fn v2(&self) -> Result<&ContractEventV2> {
+        match self {
+            ContractEvent::V0(_) => anyhow::bail!("This is an event handle event"),
+            ContractEvent::V2(event) => Ok(event),
+        }
+    }
+
+    /// The key of the event stream, only available for events emitted to an event handle.
+    pub fn event_key(&self) -> Option<&EventKey> {
+        match self {
+            ContractEvent::V0(event) => Some(event.key()),
+            ContractEvent::V2(_) => None,
+        }
+    }
+
+    pub fn type_tag(&self) -> &TypeTag {
+        match self {
+            ContractEvent::V0(event) => event.type_tag(),
+            ContractEvent::V2(event) => event.type_tag(),
+        }
+    }
+
+    pub fn event_data(&self) -> &[u8] {
+        match self {
+            ContractEvent::V0(event) => event.event_data(),
+            ContractEvent::V2(event) => event.event_data(),
+        }
+    }
+
+    /// The schema version of the event, only available for module events.
+    pub fn schema_version(&self) -> Option<u64> {
+        match self {
+            ContractEvent::V0(_) => None,
+            ContractEvent::V2(event) => Some(event.schema_version()),
+        }
+    }
+
+    /// The group of the event, only available for module events whose struct declares one.
+    pub fn group(&self) -> Option<&str> {
+        match self {
+            ContractEvent::V0(_) => None,
+            ContractEvent::V2(event) => event.group(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            ContractEvent::V0(event) => event.size(),
+            ContractEvent::V2(event) => event.size(),
+        }
+    }
+
+    /// The struct tag of the event, if the event is a struct (which is always the case for
+    /// events emitted by Move code).
+    pub fn struct_tag(&self) -> Option<&StructTag> {
+        match self.type_tag() {
+            TypeTag::Struct(struct_tag) => Some(struct_tag),
+            _ => None,
+        }
+    }
+
+    pub fn is_of_type(&self, struct_tag: &StructTag) -> bool {
+        self.struct_tag() == Some(struct_tag)
+    }
+
+    /// Whether the type of the event is defined by the given module.
+    pub fn is_from_module(&self, module_id: &ModuleId) -> bool {
+        self.struct_tag().map_or(false, |struct_tag| {
+            struct_tag.address == *module_id.address()
+                && struct_tag.module.as_ident_str() == module_id.name()
+        })
+    }
+
+    /// Decodes the payload of the event as `T`, checking first that the event is of type `T`.
+    pub fn decode_as<T: MoveStructType + DeserializeOwned>(&self) -> Result<T> {
+        let expected_type_tag = TypeTag::Struct(Box::new(T::struct_tag()));
+        if *self.type_tag() != expected_type_tag {
+            anyhow::bail!(
+                "Expected event of type {}, got {}",
+                expected_type_tag,
+                self.type_tag()
+            )
+        }
+        bcs::from_bytes(self.event_data()).map_err(Into::into)
+    }
+}
+
+impl From<ContractEventV0> for ContractEvent {
+    fn from(event: ContractEventV0) -> Self {
+        ContractEvent::V0(event)
+    }
+}
+
+impl From<ContractEventV2> for ContractEvent {
+    fn from(event: ContractEventV2) -> Self {
+        ContractEvent::V2(event)
+    }
 }
 
-// Temporary hack to avoid massive changes, it won't work when new variant comes and needs proper
-// dispatch at that time.
+// Temporary hack to avoid massive changes. Only events emitted to an event handle have a key and
+// a sequence number, so callers that may observe module events need to use `event_key()` or
+// `v0()` instead.
 impl Deref for ContractEvent {
     type Target = ContractEventV0;
 
     fn deref(&self) -> &Self::Target {
-        match self {
-            ContractEvent::V0(event) => event,
+        self.v0()
+            .expect("Module events have no key or sequence number")
+    }
+}
+
+/// Groups the events emitted to an event handle by the account that created the handle. Module
+/// events are not associated with an account, hence are skipped.
+pub fn group_events_by_account<'a>(
+    events: impl IntoIterator<Item = &'a ContractEvent>,
+) -> BTreeMap<AccountAddress, Vec<&'a ContractEvent>> {
+    let mut events_by_account: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for event in events {
+        if let Some(event_key) = event.event_key() {
+            events_by_account
+                .entry(event_key.get_creator_address())
+                .or_default()
+                .push(event);
         }
     }
+    events_by_account
+}
+
+/// Counts the events per type.
+pub fn count_events_by_type<'a>(
+    events: impl IntoIterator<Item = &'a ContractEvent>,
+) -> BTreeMap<&'a TypeTag, usize> {
+    let mut counts = BTreeMap::new();
+    for event in events {
+        *counts.entry(event.type_tag()).or_default() += 1;
+    }
+    counts
 }
 
 /// Entry produced via a call to the `emit_event` builtin.
@@ -99,14 +257,71 @@ impl ContractEventV0 {
     }
 }
 
+/// Entry produced via a call to the `emit` builtin, keyed by the type of the event rather than by
+/// an event handle, hence without a GUID or a sequence number.
+#[derive(Hash, Clone, Eq, PartialEq, Serialize, Deserialize, CryptoHasher)]
+pub struct ContractEventV2 {
+    /// The type of the data
+    type_tag: TypeTag,
+    /// The version of the schema of the data, as declared by the `#[event(version = ...)]`
+    /// attribute of the event struct. 0 for unversioned events.
+    schema_version: u64,
+    /// The group declared by the `#[event(group = ...)]` attribute of the event struct, if any, so
+    /// that indexers can subscribe to groups of events instead of enumerating their structs.
+    group: Option<String>,
+    /// The data payload of the event
+    #[serde(with = "serde_bytes")]
+    event_data: Vec<u8>,
+}
+
+impl ContractEventV2 {
+    pub fn new(type_tag: TypeTag, event_data: Vec<u8>) -> Self {
+        Self::new_versioned(type_tag, 0, event_data)
+    }
+
+    pub fn new_versioned(type_tag: TypeTag, schema_version: u64, event_data: Vec<u8>) -> Self {
+        Self {
+            type_tag,
+            schema_version,
+            group: None,
+            event_data,
+        }
+    }
+
+    pub fn with_group(mut self, group: Option<String>) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn event_data(&self) -> &[u8] {
+        &self.event_data
+    }
+
+    pub fn type_tag(&self) -> &TypeTag {
+        &self.type_tag
+    }
+
+    pub fn schema_version(&self) -> u64 {
+        self.schema_version
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    pub fn size(&self) -> usize {
+        bcs::to_bytes(&self.type_tag).unwrap().len()
+            + 8 /* u64 */
+            + self.group.as_ref().map_or(0, String::len)
+            + self.event_data.len()
+    }
+}
+
 impl TryFrom<&ContractEvent> for NewBlockEvent {
     type Error = Error;
 
     fn try_from(event: &ContractEvent) -> Result<Self> {
-        if event.type_tag != TypeTag::Struct(Box::new(Self::struct_tag())) {
-            anyhow::bail!("Expected NewBlockEvent")
-        }
-        Self::try_from_bytes(&event.event_data)
+        event.decode_as()
     }
 }
 
@@ -114,10 +329,7 @@ impl TryFrom<&ContractEvent> for NewEpochEvent {
     type Error = Error;
 
     fn try_from(event: &ContractEvent) -> Result<Self> {
-        if event.type_tag != TypeTag::Struct(Box::new(Self::struct_tag())) {
-            anyhow::bail!("Expected NewEpochEvent")
-        }
-        Self::try_from_bytes(&event.event_data)
+        event.decode_as()
     }
 }
 
@@ -125,10 +337,7 @@ impl TryFrom<&ContractEvent> for WithdrawEvent {
     type Error = Error;
 
     fn try_from(event: &ContractEvent) -> Result<Self> {
-        if event.type_tag != TypeTag::Struct(Box::new(WithdrawEvent::struct_tag())) {
-            anyhow::bail!("Expected Sent Payment")
-        }
-        Self::try_from_bytes(&event.event_data)
+        event.decode_as()
     }
 }
 
@@ -136,39 +345,49 @@ impl TryFrom<&ContractEvent> for DepositEvent {
     type Error = Error;
 
     fn try_from(event: &ContractEvent) -> Result<Self> {
-        if event.type_tag != TypeTag::Struct(Box::new(DepositEvent::struct_tag())) {
-            anyhow::bail!("Expected Received Payment")
-        }
-        Self::try_from_bytes(&event.event_data)
+        event.decode_as()
     }
 }
 
 impl std::fmt::Debug for ContractEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "ContractEvent {{ key: {:?}, index: {:?}, type: {:?}, event_data: {:?} }}",
-            self.key,
-            self.sequence_number,
-            self.type_tag,
-            hex::encode(&self.event_data)
-        )
+        match self {
+            ContractEvent::V0(event) => write!(
+                f,
+                "ContractEvent {{ key: {:?}, index: {:?}, type: {:?}, event_data: {:?} }}",
+                event.key,
+                event.sequence_number,
+                event.type_tag,
+                hex::encode(&event.event_data)
+            ),
+            ContractEvent::V2(event) => write!(
+                f,
+                "ModuleEvent {{ type: {:?}, schema_version: {:?}, event_data: {:?} }}",
+                event.type_tag,
+                event.schema_version,
+                hex::encode(&event.event_data)
+            ),
+        }
     }
 }
 
 impl std::fmt::Display for ContractEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let event = match self {
+            ContractEvent::V0(event) => event,
+            ContractEvent::V2(_) => return write!(f, "{:?}", self),
+        };
         if let Ok(payload) = WithdrawEvent::try_from(self) {
             write!(
                 f,
                 "ContractEvent {{ key: {}, index: {:?}, type: {:?}, event_data: {:?} }}",
-                self.key, self.sequence_number, self.type_tag, payload,
+                event.key, event.sequence_number, event.type_tag, payload,
             )
         } else if let Ok(payload) = DepositEvent::try_from(self) {
             write!(
                 f,
                 "ContractEvent {{ key: {}, index: {:?}, type: {:?}, event_data: {:?} }}",
-                self.key, self.sequence_number, self.type_tag, payload,
+                event.key, event.sequence_number, event.type_tag, payload,
             )
         } else {
             write!(f, "{:?}", self)
@@ -202,3 +421,119 @@ impl EventWithVersion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{count_events_by_type, group_events_by_account, ContractEvent, ContractEventV2};
+    use crate::{account_config::NewEpochEvent, event::EventKey};
+    use aptos_crypto::hash::CryptoHash;
+    use move_core_types::{
+        account_address::AccountAddress,
+        language_storage::{ModuleId, TypeTag},
+        move_resource::MoveStructType,
+    };
+
+    #[test]
+    fn test_module_event() {
+        let event = ContractEvent::new_v2(TypeTag::U64, bcs::to_bytes(&42u64).unwrap());
+        assert!(event.is_v2());
+        assert!(event.event_key().is_none());
+        assert!(event.v0().is_err());
+        assert_eq!(event.type_tag(), &TypeTag::U64);
+        assert_eq!(event.event_data(), bcs::to_bytes(&42u64).unwrap());
+
+        let bytes = bcs::to_bytes(&event).unwrap();
+        assert_eq!(bcs::from_bytes::<ContractEvent>(&bytes).unwrap(), event);
+
+        // Module events must not collide with handle events carrying the same payload.
+        let handle_event = ContractEvent::new(
+            EventKey::new(0, AccountAddress::ZERO),
+            0,
+            TypeTag::U64,
+            bcs::to_bytes(&42u64).unwrap(),
+        );
+        assert_ne!(CryptoHash::hash(&event), CryptoHash::hash(&handle_event));
+        assert_eq!(
+            ContractEvent::from(ContractEventV2::new(TypeTag::U64, vec![])),
+            ContractEvent::new_v2(TypeTag::U64, vec![])
+        );
+
+        // The schema version is part of the event.
+        let versioned_event =
+            ContractEvent::new_v2_versioned(TypeTag::U64, 2, bcs::to_bytes(&42u64).unwrap());
+        assert_eq!(event.schema_version(), Some(0));
+        assert_eq!(versioned_event.schema_version(), Some(2));
+        assert_ne!(CryptoHash::hash(&event), CryptoHash::hash(&versioned_event));
+        assert_eq!(handle_event.schema_version(), None);
+
+        // So is the group.
+        let grouped_event = ContractEvent::from(
+            ContractEventV2::new(TypeTag::U64, bcs::to_bytes(&42u64).unwrap())
+                .with_group(Some("defi".to_string())),
+        );
+        assert_eq!(event.group(), None);
+        assert_eq!(grouped_event.group(), Some("defi"));
+        assert_ne!(CryptoHash::hash(&event), CryptoHash::hash(&grouped_event));
+        assert_eq!(handle_event.group(), None);
+    }
+
+    #[test]
+    fn test_decode_as() {
+        let event = ContractEvent::new(
+            NewEpochEvent::event_key(),
+            0,
+            TypeTag::Struct(Box::new(NewEpochEvent::struct_tag())),
+            bcs::to_bytes(&7u64).unwrap(),
+        );
+        assert_eq!(event.decode_as::<NewEpochEvent>().unwrap().epoch(), 7);
+
+        let mismatched_event = ContractEvent::new_v2(TypeTag::U64, bcs::to_bytes(&7u64).unwrap());
+        assert!(mismatched_event.decode_as::<NewEpochEvent>().is_err());
+    }
+
+    #[test]
+    fn test_group_and_count_events() {
+        let new_epoch_type = TypeTag::Struct(Box::new(NewEpochEvent::struct_tag()));
+        let events = vec![
+            ContractEvent::new(
+                EventKey::new(0, AccountAddress::ONE),
+                0,
+                new_epoch_type.clone(),
+                vec![],
+            ),
+            ContractEvent::new(
+                EventKey::new(1, AccountAddress::TWO),
+                0,
+                TypeTag::U64,
+                vec![],
+            ),
+            ContractEvent::new(
+                EventKey::new(0, AccountAddress::ONE),
+                1,
+                new_epoch_type.clone(),
+                vec![],
+            ),
+            ContractEvent::new_v2(new_epoch_type.clone(), vec![]),
+        ];
+
+        let events_by_account = group_events_by_account(&events);
+        assert_eq!(events_by_account.len(), 2);
+        assert_eq!(events_by_account[&AccountAddress::ONE].len(), 2);
+        assert_eq!(events_by_account[&AccountAddress::TWO].len(), 1);
+
+        let counts = count_events_by_type(&events);
+        assert_eq!(counts[&new_epoch_type], 3);
+        assert_eq!(counts[&TypeTag::U64], 1);
+
+        let reconfiguration_module =
+            ModuleId::new(AccountAddress::ONE, NewEpochEvent::MODULE_NAME.to_owned());
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| event.is_from_module(&reconfiguration_module))
+                .count(),
+            3
+        );
+        assert!(!events[1].is_of_type(&NewEpochEvent::struct_tag()));
+    }
+}
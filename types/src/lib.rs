@@ -21,6 +21,7 @@ pub mod governance;
 pub mod ledger_info;
 pub mod mempool_status;
 pub mod move_resource;
+pub mod move_value_json;
 pub mod network_address;
 pub mod nibble;
 pub mod on_chain_config;
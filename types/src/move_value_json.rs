@@ -0,0 +1,126 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion of Move values into JSON, based on their (fully annotated) layouts.
+//!
+//! Values are encoded the same way as GraphQL scalars are: integers wider than 32 bits are
+//! strings (so that JavaScript clients do not lose precision), addresses and `vector<u8>` are
+//! 0x-prefixed hex strings, `0x1::string::String` is a UTF-8 string and `0x1::option::Option`
+//! is flattened to either its value or null.
+
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::StructTag,
+    value::{MoveStruct, MoveStructLayout, MoveTypeLayout, MoveValue},
+};
+use serde_json::{Map, Value};
+
+/// Converts a Move value into JSON, based on its (fully annotated) layout.
+pub fn move_value_to_json(layout: &MoveTypeLayout, value: MoveValue) -> Value {
+    match (layout, value) {
+        (MoveTypeLayout::Vector(elem_layout), MoveValue::Vector(values)) => {
+            if matches!(elem_layout.as_ref(), MoveTypeLayout::U8) {
+                Value::String(format!("0x{}", hex::encode(bytes(values))))
+            } else {
+                Value::Array(
+                    values
+                        .into_iter()
+                        .map(|value| move_value_to_json(elem_layout, value))
+                        .collect(),
+                )
+            }
+        },
+        (MoveTypeLayout::Struct(struct_layout), MoveValue::Struct(move_struct)) => {
+            struct_to_json(struct_layout, move_struct)
+        },
+        (_, value) => primitive_to_json(value),
+    }
+}
+
+fn primitive_to_json(value: MoveValue) -> Value {
+    match value {
+        MoveValue::Bool(b) => Value::Bool(b),
+        MoveValue::U8(v) => Value::from(v),
+        MoveValue::U16(v) => Value::from(v),
+        MoveValue::U32(v) => Value::from(v),
+        MoveValue::U64(v) => Value::String(v.to_string()),
+        MoveValue::U128(v) => Value::String(v.to_string()),
+        MoveValue::U256(v) => Value::String(v.to_string()),
+        MoveValue::Address(addr) | MoveValue::Signer(addr) => Value::String(addr.to_hex_literal()),
+        // Vectors and structs are only reached if the layout does not match the value.
+        MoveValue::Vector(values) => {
+            Value::Array(values.into_iter().map(primitive_to_json).collect())
+        },
+        MoveValue::Struct(move_struct) => Value::Array(
+            move_struct
+                .into_fields()
+                .into_iter()
+                .map(primitive_to_json)
+                .collect(),
+        ),
+    }
+}
+
+fn struct_to_json(layout: &MoveStructLayout, move_struct: MoveStruct) -> Value {
+    let field_layouts = match layout {
+        MoveStructLayout::WithTypes { type_, fields } => {
+            if is_std_struct(type_, "string", "String") {
+                if let Some(MoveValue::Vector(values)) = move_struct.into_fields().pop() {
+                    return Value::String(String::from_utf8_lossy(&bytes(values)).into_owned());
+                }
+                return Value::Null;
+            }
+            if is_std_struct(type_, "option", "Option") {
+                let elem_layout = match fields.first().map(|field| &field.layout) {
+                    Some(MoveTypeLayout::Vector(elem_layout)) => elem_layout,
+                    _ => return Value::Null,
+                };
+                return match move_struct.into_fields().pop() {
+                    Some(MoveValue::Vector(values)) => values
+                        .into_iter()
+                        .next()
+                        .map_or(Value::Null, |value| move_value_to_json(elem_layout, value)),
+                    _ => Value::Null,
+                };
+            }
+            fields
+        },
+        MoveStructLayout::WithFields(fields) => fields,
+        MoveStructLayout::Runtime(layouts) => {
+            return Value::Array(
+                layouts
+                    .iter()
+                    .zip(move_struct.into_fields())
+                    .map(|(layout, value)| move_value_to_json(layout, value))
+                    .collect(),
+            )
+        },
+    };
+
+    Value::Object(
+        field_layouts
+            .iter()
+            .zip(move_struct.into_fields())
+            .map(|(field, value)| {
+                (
+                    field.name.to_string(),
+                    move_value_to_json(&field.layout, value),
+                )
+            })
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn is_std_struct(tag: &StructTag, module: &str, name: &str) -> bool {
+    tag.address == AccountAddress::ONE && tag.module.as_str() == module && tag.name.as_str() == name
+}
+
+fn bytes(values: Vec<MoveValue>) -> Vec<u8> {
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            MoveValue::U8(byte) => Some(byte),
+            _ => None,
+        })
+        .collect()
+}
@@ -32,6 +32,11 @@ pub enum FeatureFlag {
     GAS_PAYER_ENABLED = 22,
     APTOS_UNIQUE_IDENTIFIERS = 23,
     BULLETPROOFS_NATIVES = 24,
+    MODULE_EVENT_VERSIONS = 25,
+    MODULE_EVENT = 26,
+    BLOCK_SEED = 27,
+    CONCURRENT_COUNTERS = 28,
+    MODULE_EVENT_DUAL_EMISSION = 29,
 }
 
 /// Representation of features on chain as a bitset.
@@ -44,7 +49,7 @@ pub struct Features {
 impl Default for Features {
     fn default() -> Self {
         Features {
-            features: vec![0b00100000, 0b00100000, 0b00000100],
+            features: vec![0b00100000, 0b00100000, 0b00000100, 0b00000100],
         }
     }
 }
@@ -69,6 +74,26 @@ impl Features {
     pub fn is_storage_slot_metadata_enabled(&self) -> bool {
         self.is_enabled(FeatureFlag::STORAGE_SLOT_METADATA)
     }
+
+    pub fn is_module_event_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::MODULE_EVENT)
+    }
+
+    pub fn are_module_event_versions_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::MODULE_EVENT_VERSIONS)
+    }
+
+    pub fn is_block_seed_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::BLOCK_SEED)
+    }
+
+    pub fn are_concurrent_counters_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::CONCURRENT_COUNTERS)
+    }
+
+    pub fn is_module_event_dual_emission_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::MODULE_EVENT_DUAL_EMISSION)
+    }
 }
 
 // --------------------------------------------------------------------------------------------
@@ -11,6 +11,7 @@ pub enum OnChainExecutionConfig {
     V1(ExecutionConfigV1),
     V2(ExecutionConfigV2),
     V3(ExecutionConfigV3),
+    V4(ExecutionConfigV4),
 }
 
 /// The public interface that exposes all values with safe fallback.
@@ -21,6 +22,7 @@ impl OnChainExecutionConfig {
             OnChainExecutionConfig::V1(config) => config.transaction_shuffler_type.clone(),
             OnChainExecutionConfig::V2(config) => config.transaction_shuffler_type.clone(),
             OnChainExecutionConfig::V3(config) => config.transaction_shuffler_type.clone(),
+            OnChainExecutionConfig::V4(config) => config.transaction_shuffler_type.clone(),
         }
     }
 
@@ -30,6 +32,18 @@ impl OnChainExecutionConfig {
             OnChainExecutionConfig::V1(_config) => None,
             OnChainExecutionConfig::V2(config) => config.block_gas_limit,
             OnChainExecutionConfig::V3(config) => config.block_gas_limit,
+            OnChainExecutionConfig::V4(config) => config.block_gas_limit,
+        }
+    }
+
+    /// The limit on the output size (in bytes) of the transactions committed in a block. Like the
+    /// block gas limit, the block is cut short once the limit is reached.
+    pub fn block_output_limit(&self) -> Option<u64> {
+        match &self {
+            OnChainExecutionConfig::V1(_config) => None,
+            OnChainExecutionConfig::V2(_config) => None,
+            OnChainExecutionConfig::V3(_config) => None,
+            OnChainExecutionConfig::V4(config) => config.block_output_limit,
         }
     }
 
@@ -39,6 +53,7 @@ impl OnChainExecutionConfig {
             OnChainExecutionConfig::V1(_config) => TransactionDeduperType::NoDedup,
             OnChainExecutionConfig::V2(_config) => TransactionDeduperType::NoDedup,
             OnChainExecutionConfig::V3(config) => config.transaction_deduper_type.clone(),
+            OnChainExecutionConfig::V4(config) => config.transaction_deduper_type.clone(),
         }
     }
 }
@@ -113,6 +128,25 @@ impl Default for ExecutionConfigV3 {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ExecutionConfigV4 {
+    pub transaction_shuffler_type: TransactionShufflerType,
+    pub block_gas_limit: Option<u64>,
+    pub transaction_deduper_type: TransactionDeduperType,
+    pub block_output_limit: Option<u64>,
+}
+
+impl Default for ExecutionConfigV4 {
+    fn default() -> Self {
+        Self {
+            transaction_shuffler_type: TransactionShufflerType::NoShuffling,
+            block_gas_limit: None,
+            transaction_deduper_type: TransactionDeduperType::TxnHashAndAuthenticatorV1,
+            block_output_limit: None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")] // cannot use tag = "type" as nested enums cannot work, and bcs doesn't support it
 pub enum TransactionShufflerType {
@@ -192,6 +226,18 @@ mod test {
             TransactionShufflerType::SenderAwareV2(32)
         ));
         assert!(matches!(result.block_gas_limit(), None));
+
+        // V4 test with a per-block output limit
+        let config = OnChainExecutionConfig::V4(ExecutionConfigV4 {
+            block_gas_limit: Some(rand_gas_limit),
+            block_output_limit: Some(1 << 20),
+            ..ExecutionConfigV4::default()
+        });
+
+        let s = serde_yaml::to_string(&config).unwrap();
+        let result = serde_yaml::from_str::<OnChainExecutionConfig>(&s).unwrap();
+        assert!(result.block_gas_limit() == Some(rand_gas_limit));
+        assert!(result.block_output_limit() == Some(1 << 20));
     }
 
     #[test]
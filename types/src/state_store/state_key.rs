@@ -8,26 +8,33 @@ use aptos_crypto::{
     HashValue,
 };
 use aptos_crypto_derive::CryptoHasher;
-use derivative::Derivative;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use once_cell::sync::OnceCell;
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest::{arbitrary::Arbitrary, prelude::*};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::{convert::TryInto, hash::Hash, ops::Deref};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    convert::TryInto,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
 use thiserror::Error;
 
-#[derive(Clone, Debug, Derivative)]
-#[derivative(PartialEq, PartialOrd, Hash, Ord)]
-#[cfg_attr(any(test, feature = "fuzzing"), derive(proptest_derive::Arbitrary))]
-pub struct StateKey {
+/// A key into the global state. The key is reference counted, so that cloning it (which happens
+/// a lot in the block executor and the partitioner) does not copy the underlying access path, and
+/// the cached hash is shared across all the clones. Use [`StateKeyInterner`] to make equal keys
+/// share a single allocation.
+#[derive(Clone)]
+pub struct StateKey(Arc<InternedStateKey>);
+
+#[derive(Debug)]
+struct InternedStateKey {
     inner: StateKeyInner,
-    #[derivative(
-        Hash = "ignore",
-        Ord = "ignore",
-        PartialEq = "ignore",
-        PartialOrd = "ignore"
-    )]
-    #[cfg_attr(any(test, feature = "fuzzing"), proptest(value = "OnceCell::new()"))]
     hash: OnceCell<HashValue>,
 }
 
@@ -58,10 +65,10 @@ pub enum StateKeyTag {
 
 impl StateKey {
     pub fn new(inner: StateKeyInner) -> Self {
-        Self {
+        Self(Arc::new(InternedStateKey {
             inner,
             hash: OnceCell::new(),
-        }
+        }))
     }
 
     /// Recovers from serialized bytes in physical storage.
@@ -97,7 +104,7 @@ impl StateKey {
     }
 
     pub fn size(&self) -> usize {
-        match &self.inner {
+        match self.inner() {
             StateKeyInner::AccessPath(access_path) => access_path.size(),
             StateKeyInner::TableItem { handle, key } => handle.size() + key.len(),
             StateKeyInner::Raw(bytes) => bytes.len(),
@@ -117,11 +124,20 @@ impl StateKey {
     }
 
     pub fn inner(&self) -> &StateKeyInner {
-        &self.inner
+        &self.0.inner
     }
 
     pub fn into_inner(self) -> StateKeyInner {
-        self.inner
+        match Arc::try_unwrap(self.0) {
+            Ok(interned) => interned.inner,
+            Err(shared) => shared.inner.clone(),
+        }
+    }
+
+    /// Returns true if both keys share the same allocation, e.g. because they were interned by
+    /// the same [`StateKeyInterner`].
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
     }
 
     pub fn get_shard_id(&self) -> u8 {
@@ -155,7 +171,7 @@ impl CryptoHash for StateKey {
     type Hasher = DummyHasher;
 
     fn hash(&self) -> HashValue {
-        *self.hash.get_or_init(|| CryptoHash::hash(&self.inner))
+        *self.0.hash.get_or_init(|| CryptoHash::hash(self.inner()))
     }
 }
 
@@ -164,7 +180,7 @@ impl Serialize for StateKey {
     where
         S: Serializer,
     {
-        self.inner.serialize(serializer)
+        self.inner().serialize(serializer)
     }
 }
 
@@ -182,12 +198,58 @@ impl Deref for StateKey {
     type Target = StateKeyInner;
 
     fn deref(&self) -> &Self::Target {
-        &self.inner
+        self.inner()
+    }
+}
+
+impl fmt::Debug for StateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateKey")
+            .field("inner", self.inner())
+            .field("hash", &self.0.hash)
+            .finish()
+    }
+}
+
+impl PartialEq for StateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || self.inner() == other.inner()
     }
 }
 
 impl Eq for StateKey {}
 
+impl PartialOrd for StateKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StateKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.ptr_eq(other) {
+            return Ordering::Equal;
+        }
+        self.inner().cmp(other.inner())
+    }
+}
+
+impl Hash for StateKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner().hash(state)
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+impl Arbitrary for StateKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        any::<StateKeyInner>().prop_map(StateKey::new).boxed()
+    }
+}
+
 impl From<StateKeyInner> for StateKey {
     fn from(inner: StateKeyInner) -> Self {
         StateKey::new(inner)
@@ -208,6 +270,37 @@ impl CryptoHash for StateKeyInner {
     }
 }
 
+/// Deduplicates equal state keys, so that all the copies of a key handed out by the interner share
+/// a single allocation. This makes the repeated clones and comparisons of the same keys (e.g. the
+/// hints of the transactions in a block) cheap.
+#[derive(Default)]
+pub struct StateKeyInterner {
+    keys: HashSet<StateKey>,
+}
+
+impl StateKeyInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the interned copy of the given key, interning it if it has not been seen before.
+    pub fn intern(&mut self, state_key: StateKey) -> StateKey {
+        if let Some(interned) = self.keys.get(&state_key) {
+            return interned.clone();
+        }
+        self.keys.insert(state_key.clone());
+        state_key
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
 /// Error thrown when a [`StateKey`] fails to be deserialized out of a byte sequence stored in physical
 /// storage, via [`StateKey::decode`].
 #[derive(Debug, Error)]
@@ -229,7 +322,7 @@ pub enum StateKeyDecodeErr {
 
 #[cfg(test)]
 mod tests {
-    use crate::state_store::state_key::{AccessPath, StateKey};
+    use crate::state_store::state_key::{AccessPath, StateKey, StateKeyInterner};
     use aptos_crypto::hash::CryptoHash;
 
     #[test]
@@ -258,4 +351,19 @@ mod tests {
             .unwrap();
         assert_eq!(CryptoHash::hash(&key), expected_hash);
     }
+
+    #[test]
+    fn test_state_key_interner() {
+        let mut interner = StateKeyInterner::new();
+        let key = interner.intern(StateKey::raw(vec![1, 2, 3]));
+        let same_key = interner.intern(StateKey::raw(vec![1, 2, 3]));
+        let other_key = interner.intern(StateKey::raw(vec![4, 5, 6]));
+
+        assert!(key.ptr_eq(&same_key));
+        assert!(!key.ptr_eq(&other_key));
+        assert_eq!(key, same_key);
+        assert_ne!(key, other_key);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(same_key.into_inner(), *StateKey::raw(vec![1, 2, 3]));
+    }
 }
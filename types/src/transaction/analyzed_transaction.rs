@@ -4,7 +4,10 @@
 use crate::{
     access_path::AccessPath,
     account_config::{AccountResource, CoinStoreResource},
-    state_store::{state_key::StateKey, table::TableHandle},
+    state_store::{
+        state_key::{StateKey, StateKeyInterner},
+        table::TableHandle,
+    },
     transaction::{SignedTransaction, Transaction, TransactionPayload},
 };
 use aptos_crypto::{hash::CryptoHash, HashValue};
@@ -12,7 +15,8 @@ pub use move_core_types::abi::{
     ArgumentABI, ScriptFunctionABI as EntryFunctionABI, TransactionScriptABI, TypeArgumentABI,
 };
 use move_core_types::{
-    account_address::AccountAddress, language_storage::StructTag, move_resource::MoveStructType,
+    account_address::AccountAddress, ident_str, language_storage::StructTag,
+    move_resource::MoveStructType,
 };
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
@@ -101,6 +105,20 @@ impl AnalyzedTransaction {
         &self.write_hints
     }
 
+    /// Replaces the specific storage locations in the hints with their interned copies, so that
+    /// the locations shared by multiple transactions are cheap to clone and compare.
+    pub fn intern_hints(&mut self, interner: &mut StateKeyInterner) {
+        for hint in self
+            .read_hints
+            .iter_mut()
+            .chain(self.write_hints.iter_mut())
+        {
+            if let StorageLocation::Specific(state_key) = hint {
+                *state_key = interner.intern(state_key.clone());
+            }
+        }
+    }
+
     pub fn predictable_transaction(&self) -> bool {
         self.predictable_transaction
     }
@@ -112,6 +130,23 @@ impl AnalyzedTransaction {
         }
     }
 
+    /// Whether the transaction publishes modules. The modules it writes are not part of its write
+    /// hints, as they are only known once it is executed.
+    pub fn publishes_modules(&self) -> bool {
+        match &self.transaction {
+            Transaction::UserTransaction(signed_txn) => match signed_txn.payload() {
+                TransactionPayload::ModuleBundle(_) => true,
+                TransactionPayload::EntryFunction(func) => {
+                    *func.module().address() == AccountAddress::ONE
+                        && func.module().name().as_str() == "code"
+                        && func.function().as_str() == "publish_package_txn"
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     pub fn analyzed_transaction_for_coin_transfer(
         signed_txn: SignedTransaction,
         sender_address: AccountAddress,
@@ -151,6 +186,37 @@ impl AnalyzedTransaction {
         )))
     }
 
+    /// The registry of the packages published by the account, written along with the modules.
+    pub fn package_registry_location(address: AccountAddress) -> StorageLocation {
+        let struct_tag = StructTag {
+            address: AccountAddress::ONE,
+            module: ident_str!("code").to_owned(),
+            name: ident_str!("PackageRegistry").to_owned(),
+            type_params: vec![],
+        };
+        StorageLocation::Specific(StateKey::access_path(AccessPath::new(
+            address,
+            struct_tag.access_vector(),
+        )))
+    }
+
+    pub fn analyzed_transaction_for_module_publishing(
+        signed_txn: SignedTransaction,
+        sender_address: AccountAddress,
+    ) -> Self {
+        let write_hints = vec![
+            Self::account_resource_location(sender_address),
+            Self::coin_store_location(sender_address),
+            Self::package_registry_location(sender_address),
+        ];
+        AnalyzedTransaction::new(
+            Transaction::UserTransaction(signed_txn),
+            vec![],
+            // The published modules are broadcast to the later transactions by the executor.
+            write_hints,
+        )
+    }
+
     pub fn analyzed_transaction_for_create_account(
         signed_txn: SignedTransaction,
         sender_address: AccountAddress,
@@ -224,10 +290,26 @@ impl From<Transaction> for AnalyzedTransaction {
                                 receiver_address,
                             )
                         },
-                        _ => todo!("Only coin transfer and create account transactions are supported for now")
+                        (AccountAddress::ONE, "code", "publish_package_txn") => {
+                            let sender_address = signed_txn.sender();
+                            AnalyzedTransaction::analyzed_transaction_for_module_publishing(
+                                signed_txn,
+                                sender_address,
+                            )
+                        },
+                        _ => todo!("Only coin transfer, create account and module publishing transactions are supported for now")
                     }
                 },
-                _ => todo!("Only entry function transactions are supported for now"),
+                TransactionPayload::ModuleBundle(_) => {
+                    let sender_address = signed_txn.sender();
+                    AnalyzedTransaction::analyzed_transaction_for_module_publishing(
+                        signed_txn,
+                        sender_address,
+                    )
+                },
+                _ => todo!(
+                    "Only entry function and module bundle transactions are supported for now"
+                ),
             },
             _ => AnalyzedTransaction::new_with_no_hints(txn),
         }
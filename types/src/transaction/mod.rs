@@ -6,7 +6,7 @@ use crate::{
     account_address::AccountAddress,
     block_metadata::BlockMetadata,
     chain_id::ChainId,
-    contract_event::ContractEvent,
+    contract_event::{count_events_by_type, group_events_by_account, ContractEvent},
     ledger_info::LedgerInfo,
     proof::{
         accumulator::InMemoryAccumulator, TransactionInfoListWithProof, TransactionInfoWithProof,
@@ -25,7 +25,10 @@ use aptos_crypto::{
     CryptoMaterialError, HashValue,
 };
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
-use move_core_types::transaction_argument::convert_txn_args;
+use move_core_types::{
+    language_storage::{ModuleId, StructTag, TypeTag},
+    transaction_argument::convert_txn_args,
+};
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
@@ -52,7 +55,12 @@ pub use script::{
     ArgumentABI, EntryABI, EntryFunction, EntryFunctionABI, Script, TransactionScriptABI,
     TypeArgumentABI,
 };
-use std::{collections::BTreeSet, hash::Hash, ops::Deref, sync::atomic::AtomicU64};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::Hash,
+    ops::Deref,
+    sync::atomic::AtomicU64,
+};
 pub use transaction_argument::{parse_transaction_argument, TransactionArgument};
 
 pub type Version = u64; // Height - also used for MVCC in StateDB
@@ -1068,6 +1076,36 @@ impl TransactionOutput {
         &self.status
     }
 
+    pub fn events_of_type<'a>(
+        &'a self,
+        struct_tag: &'a StructTag,
+    ) -> impl Iterator<Item = &'a ContractEvent> + 'a {
+        self.events
+            .iter()
+            .filter(move |event| event.is_of_type(struct_tag))
+    }
+
+    pub fn events_of_module<'a>(
+        &'a self,
+        module_id: &'a ModuleId,
+    ) -> impl Iterator<Item = &'a ContractEvent> + 'a {
+        self.events
+            .iter()
+            .filter(move |event| event.is_from_module(module_id))
+    }
+
+    /// See [`group_events_by_account`]; use it directly on the events of all the outputs of a
+    /// block to group them across transactions.
+    pub fn events_by_account(&self) -> BTreeMap<AccountAddress, Vec<&ContractEvent>> {
+        group_events_by_account(&self.events)
+    }
+
+    /// See [`count_events_by_type`]; use it directly on the events of all the outputs of a block
+    /// to count them across transactions.
+    pub fn event_counts_by_type(&self) -> BTreeMap<&TypeTag, usize> {
+        count_events_by_type(&self.events)
+    }
+
     pub fn unpack(self) -> (WriteSet, Vec<ContractEvent>, u64, TransactionStatus) {
         let Self {
             write_set,
@@ -1106,7 +1144,7 @@ impl TransactionOutput {
             txn_info.gas_used(),
         );
 
-        let write_set_hash = CryptoHash::hash(self.write_set());
+        let write_set_hash = self.write_set().streaming_hash();
         ensure!(
             write_set_hash == txn_info.state_change_hash(),
             "{}: version:{}, write_set_hash:{:?}, expected:{:?}, write_set: {:?}, expected(if known): {:?}",
@@ -1533,7 +1571,7 @@ impl TransactionOutputListWithProof {
             verify_events_against_root_hash(&txn_output.events, txn_info)?;
 
             // Verify the write set matches for both the transaction info and output
-            let write_set_hash = CryptoHash::hash(&txn_output.write_set);
+            let write_set_hash = txn_output.write_set.streaming_hash();
             ensure!(
                 txn_info.state_change_hash == write_set_hash,
                 "The write set in transaction output does not match the transaction info \
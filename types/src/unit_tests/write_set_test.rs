@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::write_set::WriteSet;
+use aptos_crypto::hash::CryptoHash;
 use bcs::test_helpers::assert_canonical_encode_decode;
 use proptest::prelude::*;
 
@@ -11,4 +12,9 @@ proptest! {
     fn write_set_roundtrip_canonical_serialization(write_set in any::<WriteSet>()) {
         assert_canonical_encode_decode(write_set);
     }
+
+    #[test]
+    fn write_set_streaming_hash(write_set in any::<WriteSet>()) {
+        prop_assert_eq!(write_set.streaming_hash(), CryptoHash::hash(&write_set));
+    }
 }
@@ -10,6 +10,10 @@ use crate::state_store::{
     state_value::{StateValue, StateValueMetadata},
 };
 use anyhow::{bail, Result};
+use aptos_crypto::{
+    hash::{CryptoHash, StreamingHasher},
+    HashValue,
+};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -246,6 +250,16 @@ impl WriteSet {
             Self::V0(write_set) => write_set.0,
         }
     }
+
+    /// Same as `CryptoHash::hash`, but feeds the serialization to the hasher through a buffer,
+    /// which is cheaper for large write sets.
+    pub fn streaming_hash(&self) -> HashValue {
+        let mut hasher = StreamingHasher::<<Self as CryptoHash>::Hasher>::default();
+        hasher
+            .update_bcs(self)
+            .expect("Failed to serialize the write set");
+        hasher.finish()
+    }
 }
 
 impl Deref for WriteSet {